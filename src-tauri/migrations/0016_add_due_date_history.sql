@@ -0,0 +1,13 @@
+-- Migration 0016: Track due date changes per task
+
+CREATE TABLE IF NOT EXISTS due_date_history (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL,
+    old_due_at INTEGER, -- NULL when a due date is set for the first time
+    new_due_at INTEGER,
+    source TEXT NOT NULL DEFAULT 'update', -- 'update', 'snooze', 'reschedule', ...
+    changed_at INTEGER NOT NULL,
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_due_date_history_task_id ON due_date_history(task_id, changed_at);
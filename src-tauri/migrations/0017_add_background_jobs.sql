@@ -0,0 +1,11 @@
+-- Migration 0017: Track background job runs for the status view
+
+CREATE TABLE IF NOT EXISTS background_jobs (
+    name TEXT PRIMARY KEY,
+    last_run_at INTEGER,
+    last_success_at INTEGER,
+    last_error TEXT,
+    last_error_at INTEGER,
+    run_count INTEGER NOT NULL DEFAULT 0,
+    failure_count INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,14 @@
+-- Migration 0018: Per-task activity log
+
+CREATE TABLE IF NOT EXISTS activity_log (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL,
+    action TEXT NOT NULL, -- e.g. 'priority_changed'
+    old_value TEXT,
+    new_value TEXT,
+    source TEXT NOT NULL DEFAULT 'user', -- 'user', 'aging', ...
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_activity_log_task_id ON activity_log(task_id, created_at);
@@ -0,0 +1,3 @@
+-- Migration 0019: Flag attachment rows whose file is gone from disk
+
+ALTER TABLE attachments ADD COLUMN missing_since INTEGER; -- NULL while the file exists
@@ -0,0 +1,11 @@
+-- Migration 0020: Vacation periods that neither extend nor break streaks
+
+CREATE TABLE IF NOT EXISTS vacation_periods (
+    id TEXT PRIMARY KEY,
+    start_date INTEGER NOT NULL, -- start of the first vacation day (UTC day boundary, like streaks)
+    end_date INTEGER NOT NULL, -- start of the last vacation day
+    created_at INTEGER NOT NULL,
+    CHECK(end_date >= start_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vacation_periods_dates ON vacation_periods(start_date, end_date);
@@ -0,0 +1,5 @@
+-- Migration 0021: Keep subtasks in the order they were added
+
+ALTER TABLE subtasks ADD COLUMN order_index INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_subtasks_task_order ON subtasks(task_id, order_index);
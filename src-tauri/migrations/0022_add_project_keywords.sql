@@ -0,0 +1,13 @@
+-- Migration 0022: Keyword weights per project, used to suggest a project for new tasks
+-- Rebuilt daily by the background job runner.
+
+CREATE TABLE IF NOT EXISTS project_keywords (
+    project_id TEXT NOT NULL,
+    term TEXT NOT NULL,
+    weight REAL NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (project_id, term),
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_keywords_term ON project_keywords(term);
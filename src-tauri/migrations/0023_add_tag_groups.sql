@@ -0,0 +1,14 @@
+-- Migration 0023: Tag groups ("context", "client", ...) for organising long tag lists
+-- Deleting a group ungroups its tags rather than deleting them.
+
+CREATE TABLE IF NOT EXISTS tag_groups (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    color TEXT,
+    order_index INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+ALTER TABLE tags ADD COLUMN group_id TEXT REFERENCES tag_groups(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_tags_group_id ON tags(group_id);
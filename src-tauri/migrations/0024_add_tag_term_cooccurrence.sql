@@ -0,0 +1,16 @@
+-- Migration 0024: How often each tag is applied to tasks whose text contains a term,
+-- used to suggest tags for new tasks. Rebuilt daily by the background job runner.
+
+CREATE TABLE IF NOT EXISTS tag_term_cooccurrence (
+    tag_id TEXT NOT NULL,
+    term TEXT NOT NULL,
+    -- Tasks containing the term that carry the tag
+    tag_count INTEGER NOT NULL,
+    -- Tasks containing the term at all
+    term_count INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (tag_id, term),
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_term_cooccurrence_term ON tag_term_cooccurrence(term);
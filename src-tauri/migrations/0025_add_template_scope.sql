@@ -0,0 +1,7 @@
+-- Migration 0025: Templates scoped to a project only show up inside that project.
+-- Separate from project_id, which is the project new tasks are filed into.
+-- Deleting the scope project makes its templates global again.
+
+ALTER TABLE task_templates ADD COLUMN scope_project_id TEXT REFERENCES projects(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_templates_scope_project ON task_templates(scope_project_id);
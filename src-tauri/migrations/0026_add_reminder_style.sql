@@ -0,0 +1,5 @@
+-- Migration 0026: Per-task reminder sound and urgency ("low" / "normal" / "critical")
+-- A NULL sound means the platform default for the urgency level.
+
+ALTER TABLE tasks ADD COLUMN reminder_sound TEXT;
+ALTER TABLE tasks ADD COLUMN reminder_urgency TEXT NOT NULL DEFAULT 'normal';
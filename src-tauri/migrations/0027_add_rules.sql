@@ -0,0 +1,17 @@
+-- Migration 0027: Automatic rules ("if title contains 'invoice' then tag #finance")
+-- One condition per rule; actions are stored as JSON (add_tags, set_project_id, set_priority, set_reminder_minutes).
+
+CREATE TABLE IF NOT EXISTS rules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    condition_field TEXT NOT NULL,    -- 'title', 'description', 'project', 'priority'
+    condition_operator TEXT NOT NULL, -- 'contains' (text fields) or 'equals'
+    condition_value TEXT NOT NULL,
+    actions TEXT NOT NULL DEFAULT '{}',
+    order_index INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rules_order ON rules(order_index);
@@ -0,0 +1,6 @@
+-- Migration 0028: Reminders that were already hours overdue when the app got to them (e.g. after
+-- the machine slept) are marked missed instead of toasted one by one, and listed in the app.
+
+ALTER TABLE notification_schedule ADD COLUMN missed_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_notification_schedule_missed_at ON notification_schedule(missed_at);
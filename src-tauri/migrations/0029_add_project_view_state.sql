@@ -0,0 +1,4 @@
+-- Migration 0029: Per-project view preferences (list/board, collapsed board columns, sort)
+-- Stored as JSON so they travel with exports instead of living in one machine's localStorage.
+
+ALTER TABLE projects ADD COLUMN view_state TEXT;
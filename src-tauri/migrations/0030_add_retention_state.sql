@@ -0,0 +1,8 @@
+-- Migration 0030: Retention bookkeeping for append-only tables (xp_history, activity_log)
+-- Retention itself is configured per table in settings (retention_days_<table>, 0 = keep forever).
+
+CREATE TABLE IF NOT EXISTS retention_state (
+    table_name TEXT PRIMARY KEY,
+    last_pruned_at INTEGER NOT NULL,
+    last_removed INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,50 @@
+-- Migration 0031: Best-effort sync between devices through a shared folder
+-- Deletions leave tombstones so they can be replayed on other devices.
+
+CREATE TABLE IF NOT EXISTS sync_tombstones (
+    entity TEXT NOT NULL, -- 'task', 'project' or 'tag'
+    entity_id TEXT NOT NULL,
+    deleted_at INTEGER NOT NULL,
+    PRIMARY KEY (entity, entity_id)
+);
+
+CREATE TRIGGER IF NOT EXISTS trg_tasks_sync_tombstone AFTER DELETE ON tasks
+BEGIN
+    INSERT OR REPLACE INTO sync_tombstones (entity, entity_id, deleted_at)
+    VALUES ('task', OLD.id, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_projects_sync_tombstone AFTER DELETE ON projects
+BEGIN
+    INSERT OR REPLACE INTO sync_tombstones (entity, entity_id, deleted_at)
+    VALUES ('project', OLD.id, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_tags_sync_tombstone AFTER DELETE ON tags
+BEGIN
+    INSERT OR REPLACE INTO sync_tombstones (entity, entity_id, deleted_at)
+    VALUES ('tag', OLD.id, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+-- Newest change file ingested from each other device
+CREATE TABLE IF NOT EXISTS sync_devices (
+    device_id TEXT PRIMARY KEY,
+    last_file_at INTEGER NOT NULL,
+    last_seen_at INTEGER NOT NULL
+);
+
+-- Changes that couldn't be resolved by "latest updated_at wins"
+CREATE TABLE IF NOT EXISTS sync_conflicts (
+    id TEXT PRIMARY KEY,
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    local_json TEXT, -- NULL when deleted here
+    remote_json TEXT, -- NULL when deleted on the other device
+    detected_at INTEGER NOT NULL,
+    resolved_at INTEGER,
+    resolution TEXT -- 'local' or 'remote'
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_conflicts_open ON sync_conflicts(resolved_at);
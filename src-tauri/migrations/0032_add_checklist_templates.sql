@@ -0,0 +1,18 @@
+-- Migration 0032: Reusable checklists that can be appended to any task as subtasks
+
+CREATE TABLE IF NOT EXISTS checklist_templates (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS checklist_template_items (
+    id TEXT PRIMARY KEY NOT NULL,
+    checklist_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    order_index INTEGER NOT NULL,
+    FOREIGN KEY (checklist_id) REFERENCES checklist_templates(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_checklist_items_order ON checklist_template_items(checklist_id, order_index);
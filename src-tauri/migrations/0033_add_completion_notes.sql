@@ -0,0 +1,6 @@
+-- Migration 0033: Optionally require an outcome note when completing a task
+-- Projects carry the default for tasks created in them; the latest note is kept on the task.
+
+ALTER TABLE tasks ADD COLUMN completion_note_required INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN completion_note TEXT;
+ALTER TABLE projects ADD COLUMN completion_note_required INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,19 @@
+-- Migration 0034: Identify the recurrence chain each instance belongs to
+-- recurrence_parent_id only links an instance to the one it was spawned from; recurrence_root_id
+-- points at the first task of the chain so a whole chain can be grouped in one query.
+-- The root task itself keeps NULL; group on COALESCE(recurrence_root_id, id).
+
+ALTER TABLE tasks ADD COLUMN recurrence_root_id TEXT;
+
+WITH RECURSIVE chain(id, root_id) AS (
+    -- Instances whose parent was deleted start a chain of their own
+    SELECT id, id FROM tasks
+    WHERE recurrence_parent_id IS NULL OR recurrence_parent_id NOT IN (SELECT id FROM tasks)
+    UNION ALL
+    SELECT t.id, chain.root_id FROM tasks t JOIN chain ON t.recurrence_parent_id = chain.id
+)
+UPDATE tasks
+SET recurrence_root_id = (SELECT root_id FROM chain WHERE chain.id = tasks.id)
+WHERE recurrence_parent_id IN (SELECT id FROM tasks);
+
+CREATE INDEX IF NOT EXISTS idx_tasks_recurrence_root ON tasks(recurrence_root_id);
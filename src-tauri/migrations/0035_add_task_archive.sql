@@ -0,0 +1,5 @@
+-- Migration 0035: Archive tasks to hide them from the task list without deleting them
+
+ALTER TABLE tasks ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_archived ON tasks(archived);
@@ -0,0 +1,9 @@
+-- Migration 0037: "Waiting on" status for tasks blocked on someone else, with follow-up reminders
+
+ALTER TABLE tasks ADD COLUMN waiting_on TEXT;
+ALTER TABLE tasks ADD COLUMN waiting_since INTEGER;
+
+-- Follow-ups share the reminder schedule but survive due date edits
+ALTER TABLE notification_schedule ADD COLUMN kind TEXT NOT NULL DEFAULT 'reminder';
+
+CREATE INDEX IF NOT EXISTS idx_tasks_waiting_since ON tasks(waiting_since);
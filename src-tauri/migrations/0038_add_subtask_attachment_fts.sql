@@ -0,0 +1,45 @@
+-- Migration 0038: Full-text indexes over subtask titles and attachment filenames for search_tasks
+-- Task titles and descriptions are searched through tasks_fts (0036). These are external-content
+-- tables like it: one index row per subtask or attachment, kept in step by triggers.
+
+CREATE VIRTUAL TABLE IF NOT EXISTS subtasks_fts USING fts5(
+    title,
+    content = 'subtasks',
+    content_rowid = 'rowid'
+);
+
+CREATE TRIGGER IF NOT EXISTS subtasks_fts_after_insert AFTER INSERT ON subtasks BEGIN
+    INSERT INTO subtasks_fts (rowid, title) VALUES (new.rowid, new.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_fts_after_delete AFTER DELETE ON subtasks BEGIN
+    INSERT INTO subtasks_fts (subtasks_fts, rowid, title) VALUES ('delete', old.rowid, old.title);
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_fts_after_update AFTER UPDATE OF title ON subtasks BEGIN
+    INSERT INTO subtasks_fts (subtasks_fts, rowid, title) VALUES ('delete', old.rowid, old.title);
+    INSERT INTO subtasks_fts (rowid, title) VALUES (new.rowid, new.title);
+END;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS attachments_fts USING fts5(
+    filename,
+    content = 'attachments',
+    content_rowid = 'rowid'
+);
+
+CREATE TRIGGER IF NOT EXISTS attachments_fts_after_insert AFTER INSERT ON attachments BEGIN
+    INSERT INTO attachments_fts (rowid, filename) VALUES (new.rowid, new.filename);
+END;
+
+CREATE TRIGGER IF NOT EXISTS attachments_fts_after_delete AFTER DELETE ON attachments BEGIN
+    INSERT INTO attachments_fts (attachments_fts, rowid, filename) VALUES ('delete', old.rowid, old.filename);
+END;
+
+CREATE TRIGGER IF NOT EXISTS attachments_fts_after_update AFTER UPDATE OF filename ON attachments BEGIN
+    INSERT INTO attachments_fts (attachments_fts, rowid, filename) VALUES ('delete', old.rowid, old.filename);
+    INSERT INTO attachments_fts (rowid, filename) VALUES (new.rowid, new.filename);
+END;
+
+-- Index the subtasks and attachments that already exist
+INSERT INTO subtasks_fts (subtasks_fts) VALUES ('rebuild');
+INSERT INTO attachments_fts (attachments_fts) VALUES ('rebuild');
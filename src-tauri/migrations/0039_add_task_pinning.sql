@@ -0,0 +1,5 @@
+-- Migration 0039: Pinned tasks stay at the top of the task list
+
+ALTER TABLE tasks ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_pinned ON tasks(pinned);
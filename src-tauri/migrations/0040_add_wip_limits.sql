@@ -0,0 +1,16 @@
+-- Migration 0040: Board status per task and work-in-progress limits per board column
+-- A NULL status follows completion: "done" once completed, "todo" otherwise.
+
+ALTER TABLE tasks ADD COLUMN status TEXT;
+
+CREATE TABLE IF NOT EXISTS wip_limits (
+    id TEXT PRIMARY KEY,
+    scope_kind TEXT NOT NULL, -- 'global', 'project' or 'tag'
+    scope_id TEXT,            -- project or tag id; NULL for global
+    status TEXT NOT NULL,
+    wip_limit INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wip_limits_scope ON wip_limits(scope_kind, COALESCE(scope_id, ''), status);
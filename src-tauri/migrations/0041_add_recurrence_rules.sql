@@ -0,0 +1,8 @@
+-- Migration 0041: Weekday sets and end conditions for recurring tasks
+-- recurrence_days_of_week lists ISO weekdays ("1,3,5" = Mon/Wed/Fri) and only applies to weekly
+-- recurrence. A chain stops once an instance would fall after recurrence_end_date, or once it
+-- has recurrence_count tasks in it.
+
+ALTER TABLE tasks ADD COLUMN recurrence_days_of_week TEXT;
+ALTER TABLE tasks ADD COLUMN recurrence_end_date INTEGER;
+ALTER TABLE tasks ADD COLUMN recurrence_count INTEGER;
@@ -0,0 +1,6 @@
+-- Migration 0042: Time-box estimates for tasks
+-- estimated_minutes is the time planned for a task, actual_minutes the time it really took.
+-- Both stay NULL until set; stats compare them once a task has both.
+
+ALTER TABLE tasks ADD COLUMN estimated_minutes INTEGER;
+ALTER TABLE tasks ADD COLUMN actual_minutes INTEGER;
@@ -1,25 +1,33 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+/// Copy a file into the attachments folder and return its path relative to app_data_dir.
+/// A partially written copy is removed, so a failure never leaves a stray file behind.
 pub fn copy_attachment_to_storage(
     app_handle: &tauri::AppHandle,
     source_path: &str,
     task_id: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<String, String> {
     // Get app data directory
     let app_data_dir = app_handle
         .path_resolver()
         .app_data_dir()
         .ok_or("Failed to get app data directory")?;
     
-    // Create attachments directory
-    let attachments_dir = app_data_dir.join("attachments");
-    fs::create_dir_all(&attachments_dir)?;
+    copy_into_storage(&app_data_dir, source_path, task_id)
+}
+
+// A fresh path under attachments/<task_id>/ with the same extension as `original_name`
+fn new_storage_path(app_data_dir: &Path, original_name: &str, task_id: &str) -> Result<PathBuf, String> {
+    // Create task-specific subdirectory under attachments/
+    let task_dir = app_data_dir.join("attachments").join(task_id);
+    fs::create_dir_all(&task_dir).map_err(|e| crate::storage::from_io("save the attachment", &e))?;
     
     // Get file extension from source
-    let source_path_buf = PathBuf::from(source_path);
-    let extension = source_path_buf
+    let extension = Path::new(original_name)
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("");
@@ -32,24 +40,45 @@ pub fn copy_attachment_to_storage(
         format!("{}.{}", unique_id, extension)
     };
     
-    // Create task-specific subdirectory
-    let task_dir = attachments_dir.join(task_id);
-    fs::create_dir_all(&task_dir)?;
-    
-    // Full destination path
-    let dest_path = task_dir.join(&filename);
-    
-    // Copy file
-    fs::copy(source_path, &dest_path)?;
-    
-    // Return relative path from app_data_dir
-    let relative_path = dest_path
-        .strip_prefix(&app_data_dir)
+    Ok(task_dir.join(&filename))
+}
+
+// Path of a stored file relative to app_data_dir, as kept in the attachments table
+fn relative_storage_path(app_data_dir: &Path, dest_path: &Path) -> Result<String, String> {
+    Ok(dest_path
+        .strip_prefix(app_data_dir)
         .map_err(|_| "Failed to compute relative path")?
         .to_string_lossy()
-        .to_string();
+        .to_string())
+}
+
+pub(crate) fn copy_into_storage(app_data_dir: &Path, source_path: &str, task_id: &str) -> Result<String, String> {
+    let action = "save the attachment";
+    let source_path_buf = PathBuf::from(source_path);
+    let dest_path = new_storage_path(app_data_dir, source_path, task_id)?;
+    
+    // Copy file
+    if let Err(e) = fs::copy(source_path, &dest_path) {
+        let _ = fs::remove_file(&dest_path);
+        return Err(if e.kind() == std::io::ErrorKind::NotFound && !source_path_buf.exists() {
+            format!("Attachment source file not found: {}", source_path)
+        } else {
+            crate::storage::from_io(action, &e).to_string()
+        });
+    }
     
-    Ok(relative_path)
+    relative_storage_path(app_data_dir, &dest_path)
+}
+
+/// Write attachment content received in memory (e.g. pasted into the quick-add dialog) into
+/// storage under a name with `filename`'s extension. Returns the path relative to app_data_dir.
+pub(crate) fn write_into_storage(app_data_dir: &Path, filename: &str, bytes: &[u8], task_id: &str) -> Result<String, String> {
+    let dest_path = new_storage_path(app_data_dir, filename, task_id)?;
+    if let Err(e) = fs::write(&dest_path, bytes) {
+        let _ = fs::remove_file(&dest_path);
+        return Err(crate::storage::from_io("save the attachment", &e).to_string());
+    }
+    relative_storage_path(app_data_dir, &dest_path)
 }
 
 pub fn validate_file_type(file_path: &str) -> Result<(), String> {
@@ -84,6 +113,81 @@ pub fn validate_file_type(file_path: &str) -> Result<(), String> {
     }
 }
 
+// Leading bytes of native executables and scripts, which are never opened whatever their name says
+const EXECUTABLE_SIGNATURES: &[&[u8]] = &[
+    b"MZ",                 // Windows PE
+    b"\x7fELF",            // Linux ELF
+    b"\xfe\xed\xfa\xce",   // Mach-O 32-bit
+    b"\xfe\xed\xfa\xcf",   // Mach-O 64-bit
+    b"\xce\xfa\xed\xfe",
+    b"\xcf\xfa\xed\xfe",
+    b"\xca\xfe\xba\xbe",   // Mach-O universal
+    b"#!",                 // Shebang script
+];
+
+// Whether `header` plausibly starts a file of type `mime`. Text types have no signature to check.
+fn content_matches_mime(mime: &str, header: &[u8]) -> bool {
+    let at = |offset: usize, magic: &[u8]| header.get(offset..offset + magic.len()) == Some(magic);
+    match mime {
+        "image/png" => at(0, b"\x89PNG"),
+        "image/jpeg" => at(0, b"\xff\xd8\xff"),
+        "image/gif" => at(0, b"GIF8"),
+        "image/webp" => at(0, b"RIFF") && at(8, b"WEBP"),
+        "image/bmp" => at(0, b"BM"),
+        "image/x-icon" => at(0, b"\x00\x00\x01\x00"),
+        "application/pdf" => at(0, b"%PDF"),
+        "video/mp4" | "video/quicktime" => [b"ftyp", b"moov", b"mdat", b"wide", b"free", b"skip"].iter().any(|atom| at(4, *atom)),
+        "video/webm" => at(0, b"\x1a\x45\xdf\xa3"),
+        // ID3 tag or a bare MPEG frame sync
+        "audio/mpeg" => at(0, b"ID3") || (header.len() >= 2 && header[0] == 0xff && header[1] & 0xe0 == 0xe0),
+        "audio/wav" => at(0, b"RIFF") && at(8, b"WAVE"),
+        "audio/ogg" => at(0, b"OggS"),
+        _ => true,
+    }
+}
+
+/// Check a stored attachment before handing it to the OS to open: both its stored path and
+/// original filename must have an allowed extension, and its content must not be an executable
+/// or contradict the type the extension claims.
+pub fn ensure_safe_to_open(full_path: &Path, filename: &str) -> Result<(), String> {
+    let refuse = |why: &str| format!("Refusing to open \"{}\": {}", filename, why);
+    
+    validate_file_type(&full_path.to_string_lossy()).map_err(|_| refuse("its stored file type is not allowed"))?;
+    validate_file_type(filename).map_err(|_| refuse("its file type is not allowed"))?;
+    
+    let mut header = [0u8; 16];
+    let read = {
+        use std::io::Read;
+        let mut file = fs::File::open(full_path).map_err(|e| format!("Failed to open attachment: {}", e))?;
+        let mut read = 0;
+        // A short read is possible; keep reading until the buffer is full or the file ends
+        loop {
+            match file.read(&mut header[read..]) {
+                Ok(0) => break,
+                Ok(n) => {
+                    read += n;
+                    if read == header.len() {
+                        break;
+                    }
+                }
+                Err(e) => return Err(format!("Failed to read attachment: {}", e)),
+            }
+        }
+        read
+    };
+    let header = &header[..read];
+    
+    if EXECUTABLE_SIGNATURES.iter().any(|magic| header.starts_with(magic)) {
+        return Err(refuse("its content is an executable program"));
+    }
+    if let Some(mime) = get_mime_type(&full_path.to_string_lossy()) {
+        if !header.is_empty() && !content_matches_mime(&mime, header) {
+            return Err(refuse(&format!("its content doesn't match its type ({})", mime)));
+        }
+    }
+    Ok(())
+}
+
 pub fn get_mime_type(file_path: &str) -> Option<String> {
     let path = PathBuf::from(file_path);
     let extension = path
@@ -117,6 +221,361 @@ pub fn get_mime_type(file_path: &str) -> Option<String> {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentBackfillResult {
+    pub scanned: usize,
+    pub updated: usize,
+    pub missing: usize,
+}
+
+// id, filename, path, mime, size
+type LegacyAttachmentRow = (String, String, String, Option<String>, Option<i64>);
+
+/// Fill in size and mime for attachment rows created before those columns were populated.
+/// Rows whose file no longer exists are flagged with `missing_since` instead.
+pub fn backfill_attachment_metadata(
+    conn: &rusqlite::Connection,
+    app_data_dir: &Path,
+) -> Result<AttachmentBackfillResult, String> {
+    let rows: Vec<LegacyAttachmentRow> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, path, mime, size FROM attachments WHERE size IS NULL OR mime IS NULL"
+        ).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        }).map_err(|e| format!("Query execution error: {}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("Row parsing error: {}", e))?
+    };
+    
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let mut result = AttachmentBackfillResult { scanned: rows.len(), updated: 0, missing: 0 };
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for (id, filename, path, mime, size) in rows {
+        // Stored filename keeps the original extension; the on-disk name does too
+        let mime = mime.or_else(|| get_mime_type(&filename)).or_else(|| get_mime_type(&path));
+        
+        match fs::metadata(app_data_dir.join(&path)) {
+            Ok(metadata) => {
+                let size = size.or_else(|| metadata.len().try_into().ok());
+                tx.execute(
+                    "UPDATE attachments SET size = ?1, mime = ?2, missing_since = NULL WHERE id = ?3",
+                    params![size, mime, id],
+                ).map_err(|e| format!("Failed to update attachment: {}", e))?;
+                result.updated += 1;
+            }
+            Err(_) => {
+                tx.execute(
+                    "UPDATE attachments SET mime = ?1, missing_since = COALESCE(missing_since, ?2) WHERE id = ?3",
+                    params![mime, now, id],
+                ).map_err(|e| format!("Failed to flag missing attachment: {}", e))?;
+                result.missing += 1;
+            }
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(result)
+}
+
+// "report.pdf" -> "report (1).pdf", "report (2).pdf", ...
+fn numbered_filename(filename: &str, n: usize) -> String {
+    let path = Path::new(filename);
+    match (path.file_stem().and_then(|s| s.to_str()), path.extension().and_then(|e| e.to_str())) {
+        (Some(stem), Some(ext)) => format!("{} ({}).{}", stem, n, ext),
+        _ => format!("{} ({})", filename, n),
+    }
+}
+
+/// Copy a stored attachment into `target_dir` under its original filename, adding a
+/// " (1)", " (2)", ... suffix if that name is taken. Returns the path written.
+pub fn save_attachment_copy(
+    app_data_dir: &Path,
+    stored_path: &str,
+    filename: &str,
+    target_dir: &Path,
+) -> Result<PathBuf, String> {
+    let source = app_data_dir.join(stored_path);
+    if !source.is_file() {
+        return Err(format!(
+            "Attachment file is missing from storage ({}). Run the database health check to list missing attachments.",
+            stored_path
+        ));
+    }
+    
+    let metadata = fs::metadata(target_dir)
+        .map_err(|e| format!("Target folder not found: {}: {}", target_dir.display(), e))?;
+    if !metadata.is_dir() {
+        return Err(format!("Target is not a folder: {}", target_dir.display()));
+    }
+    if metadata.permissions().readonly() {
+        return Err(format!("Target folder is not writable: {}", target_dir.display()));
+    }
+    
+    // Only the final component of the stored display name, so it can't point outside the target
+    let filename = Path::new(filename)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or("attachment")
+        .to_string();
+    
+    let mut candidate = target_dir.join(&filename);
+    let mut n = 1;
+    loop {
+        // create_new so a file appearing between the check and the copy is never overwritten
+        match fs::OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(mut dest) => {
+                let mut src = fs::File::open(&source).map_err(|e| format!("Failed to open attachment: {}", e))?;
+                if let Err(e) = std::io::copy(&mut src, &mut dest).and_then(|_| dest.sync_all()) {
+                    let _ = fs::remove_file(&candidate);
+                    return Err(crate::storage::from_io("save a copy of the attachment", &e).to_string());
+                }
+                return Ok(candidate);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                candidate = target_dir.join(numbered_filename(&filename, n));
+                n += 1;
+            }
+            Err(e) => {
+                return Err(crate::storage::from_io(&format!("write to {}", target_dir.display()), &e).to_string());
+            }
+        }
+    }
+}
+
+/// Size limits for attachments added in bulk
+#[derive(Debug, Clone, Copy)]
+pub struct AttachmentLimits {
+    pub max_file_bytes: u64,
+    /// Across all attachments already stored plus the new ones
+    pub max_total_bytes: u64,
+}
+
+impl Default for AttachmentLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 100 * 1024 * 1024,
+            max_total_bytes: 5 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+// Stop enumerating a folder (e.g. a home directory picked by mistake) past this many files
+const MAX_FOLDER_FILES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderAttachmentAdded {
+    pub id: String,
+    pub source_path: String,
+    pub filename: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderAttachmentRejected {
+    pub source_path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderAttachmentResult {
+    pub added: Vec<FolderAttachmentAdded>,
+    /// Not allowed: file type, size or storage limit
+    pub skipped: Vec<FolderAttachmentRejected>,
+    /// Allowed but could not be read or copied
+    pub failed: Vec<FolderAttachmentRejected>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderAttachmentProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub current: String,
+}
+
+// Files under `folder`, sorted by path; the bool is false when the walk stopped at MAX_FOLDER_FILES
+fn folder_files(folder: &Path, recursive: bool) -> Result<(Vec<PathBuf>, bool), String> {
+    let mut files = Vec::new();
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| crate::storage::from_io(&format!("read {}", dir.display()), &e).to_string())?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    if recursive {
+                        pending.push(path);
+                    }
+                }
+                Ok(_) => {
+                    if files.len() >= MAX_FOLDER_FILES {
+                        files.sort();
+                        return Ok((files, false));
+                    }
+                    files.push(path);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    files.sort();
+    Ok((files, true))
+}
+
+/// Attach every allowed file in `folder` to a task. Rejected files are reported rather than
+/// failing the whole import; the rows for the copied files are inserted in one transaction.
+pub fn add_attachments_from_folder<F: FnMut(FolderAttachmentProgress)>(
+    conn: &rusqlite::Connection,
+    app_data_dir: &Path,
+    task_id: &str,
+    folder: &Path,
+    recursive: bool,
+    limits: AttachmentLimits,
+    mut on_progress: F,
+) -> Result<FolderAttachmentResult, String> {
+    let task_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+        params![task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))?;
+    if !task_exists {
+        return Err(format!("Task not found: {}", task_id));
+    }
+    if !folder.is_dir() {
+        return Err(format!("Folder not found: {}", folder.display()));
+    }
+    
+    let (files, complete) = folder_files(folder, recursive)?;
+    let mut stored_bytes: u64 = conn.query_row(
+        "SELECT COALESCE(SUM(size), 0) FROM attachments",
+        [],
+        |row| row.get::<_, i64>(0),
+    ).map_err(|e| format!("Query error: {}", e))?.max(0) as u64;
+    
+    let mut result = FolderAttachmentResult { added: Vec::new(), skipped: Vec::new(), failed: Vec::new() };
+    if !complete {
+        result.skipped.push(FolderAttachmentRejected {
+            source_path: folder.to_string_lossy().to_string(),
+            reason: format!("Only the first {} files were considered", MAX_FOLDER_FILES),
+        });
+    }
+    // (stored path, mime) per added file, for the insert below
+    let mut stored = Vec::new();
+    
+    let total = files.len();
+    for (index, file) in files.iter().enumerate() {
+        let source_path = file.to_string_lossy().to_string();
+        let filename = file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string();
+        on_progress(FolderAttachmentProgress { processed: index, total, current: filename.clone() });
+        
+        let reject = |reason: String| FolderAttachmentRejected { source_path: source_path.clone(), reason };
+        if let Err(e) = validate_file_type(&source_path) {
+            result.skipped.push(reject(e));
+            continue;
+        }
+        let size = match fs::metadata(file) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                result.failed.push(reject(crate::storage::from_io("read the file", &e).to_string()));
+                continue;
+            }
+        };
+        if size > limits.max_file_bytes {
+            result.skipped.push(reject(format!(
+                "File is larger than the {} MB limit",
+                limits.max_file_bytes / (1024 * 1024)
+            )));
+            continue;
+        }
+        if stored_bytes + size > limits.max_total_bytes {
+            result.skipped.push(reject("Attachment storage limit reached".to_string()));
+            continue;
+        }
+        
+        match copy_into_storage(app_data_dir, &source_path, task_id) {
+            Ok(path) => {
+                stored_bytes += size;
+                stored.push((path, get_mime_type(&source_path)));
+                result.added.push(FolderAttachmentAdded {
+                    id: Uuid::new_v4().to_string(),
+                    source_path,
+                    filename,
+                    size: size as i64,
+                });
+            }
+            Err(e) => result.failed.push(reject(e)),
+        }
+    }
+    on_progress(FolderAttachmentProgress { processed: total, total, current: String::new() });
+    
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let inserted = (|| -> Result<(), String> {
+        let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+        for (added, (path, mime)) in result.added.iter().zip(&stored) {
+            tx.execute(
+                "INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![added.id, task_id, added.filename, path, mime, added.size, now],
+            ).map_err(|e| crate::storage::from_sqlite("create the attachment records", &e).to_string())?;
+        }
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))
+    })();
+    if let Err(e) = inserted {
+        // Don't leave orphaned files behind
+        for (path, _) in &stored {
+            let _ = fs::remove_file(app_data_dir.join(path));
+        }
+        return Err(e);
+    }
+    
+    Ok(result)
+}
+
+/// Cached previews live in app_data_dir/thumbnails, one PNG per attachment id
+pub const THUMBNAILS_DIR: &str = "thumbnails";
+/// Longest side of a task card preview, in pixels
+pub const THUMBNAIL_SIZE: u32 = 64;
+
+fn thumbnail_path(app_data_dir: &Path, attachment_id: &str) -> PathBuf {
+    app_data_dir.join(THUMBNAILS_DIR).join(format!("{}.png", attachment_id))
+}
+
+/// PNG bytes of an image attachment's thumbnail, generated and cached on first use
+pub fn ensure_thumbnail(app_data_dir: &Path, attachment_id: &str, attachment_path: &str) -> Result<Vec<u8>, String> {
+    let cached = thumbnail_path(app_data_dir, attachment_id);
+    if let Ok(bytes) = fs::read(&cached) {
+        return Ok(bytes);
+    }
+    
+    let image = image::open(app_data_dir.join(attachment_path))
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    let mut bytes = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+    
+    // A cache that can't be written only costs a regeneration next time
+    if fs::create_dir_all(app_data_dir.join(THUMBNAILS_DIR)).is_ok() {
+        if let Err(e) = fs::write(&cached, &bytes) {
+            eprintln!("Failed to cache thumbnail for {}: {}", attachment_id, e);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Drop an attachment's cached thumbnail, if it has one
+pub fn remove_thumbnail(app_data_dir: &Path, attachment_id: &str) {
+    let _ = fs::remove_file(thumbnail_path(app_data_dir, attachment_id));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,5 +611,175 @@ mod tests {
         assert_eq!(get_mime_type("test.unknown"), None);
         assert_eq!(get_mime_type("test"), None);
     }
-}
 
+    #[test]
+    fn test_backfill_attachment_metadata() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("attachments/t1")).unwrap();
+        fs::write(temp_dir.path().join("attachments/t1/a.png"), [0u8; 42]).unwrap();
+        
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE attachments (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                path TEXT NOT NULL,
+                mime TEXT,
+                size INTEGER,
+                created_at INTEGER NOT NULL
+            );"
+        ).unwrap();
+        conn.execute_batch(include_str!("../migrations/0019_add_attachment_missing_flag.sql")).unwrap();
+        conn.execute_batch(
+            "INSERT INTO attachments (id, task_id, filename, path, created_at) VALUES
+                ('present', 't1', 'Screenshot.PNG', 'attachments/t1/a.png', 0),
+                ('gone', 't1', 'notes.md', 'attachments/t1/b.md', 0);
+             INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES
+                ('complete', 't1', 'c.pdf', 'attachments/t1/c.pdf', 'application/pdf', 10, 0);"
+        ).unwrap();
+        
+        let result = backfill_attachment_metadata(&conn, temp_dir.path()).unwrap();
+        assert_eq!((result.scanned, result.updated, result.missing), (2, 1, 1));
+        
+        let (mime, size): (Option<String>, Option<i64>) = conn.query_row(
+            "SELECT mime, size FROM attachments WHERE id = 'present'", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(mime.as_deref(), Some("image/png"));
+        assert_eq!(size, Some(42));
+        
+        let (mime, missing_since): (Option<String>, Option<i64>) = conn.query_row(
+            "SELECT mime, missing_since FROM attachments WHERE id = 'gone'", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(mime.as_deref(), Some("text/markdown"));
+        assert!(missing_since.is_some());
+    }
+    
+    #[test]
+    fn test_thumbnail_cache() {
+        let storage = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(storage.path().join("attachments/t1")).unwrap();
+        image::RgbImage::new(200, 100).save(storage.path().join("attachments/t1/photo.png")).unwrap();
+        fs::write(storage.path().join("attachments/t1/notes.png"), b"not an image").unwrap();
+        
+        let bytes = ensure_thumbnail(storage.path(), "a1", "attachments/t1/photo.png").unwrap();
+        let thumb = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2));
+        assert!(thumbnail_path(storage.path(), "a1").exists());
+        
+        // Served from the cache once generated, even if the original goes away
+        fs::remove_file(storage.path().join("attachments/t1/photo.png")).unwrap();
+        assert_eq!(ensure_thumbnail(storage.path(), "a1", "attachments/t1/photo.png").unwrap(), bytes);
+        remove_thumbnail(storage.path(), "a1");
+        assert!(ensure_thumbnail(storage.path(), "a1", "attachments/t1/photo.png").is_err());
+        
+        assert!(ensure_thumbnail(storage.path(), "a2", "attachments/t1/notes.png").is_err());
+    }
+    
+    #[test]
+    fn test_save_attachment_copy() {
+        let storage = tempfile::TempDir::new().unwrap();
+        let target = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(storage.path().join("attachments/t1")).unwrap();
+        fs::write(storage.path().join("attachments/t1/a.pdf"), b"pdf").unwrap();
+        
+        let first = save_attachment_copy(storage.path(), "attachments/t1/a.pdf", "Report.pdf", target.path()).unwrap();
+        assert_eq!(first, target.path().join("Report.pdf"));
+        let second = save_attachment_copy(storage.path(), "attachments/t1/a.pdf", "Report.pdf", target.path()).unwrap();
+        assert_eq!(second, target.path().join("Report (1).pdf"));
+        let third = save_attachment_copy(storage.path(), "attachments/t1/a.pdf", "../Report.pdf", target.path()).unwrap();
+        assert_eq!(third, target.path().join("Report (2).pdf"));
+        assert_eq!(fs::read(&third).unwrap(), b"pdf");
+        
+        let missing = save_attachment_copy(storage.path(), "attachments/t1/gone.pdf", "Gone.pdf", target.path());
+        assert!(missing.unwrap_err().contains("health check"));
+        assert!(save_attachment_copy(storage.path(), "attachments/t1/a.pdf", "a.pdf", &target.path().join("nope")).is_err());
+    }
+    
+    #[test]
+    fn test_add_attachments_from_folder() {
+        let storage = tempfile::TempDir::new().unwrap();
+        let source = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(source.path().join("nested")).unwrap();
+        fs::write(source.path().join("a.pdf"), [0u8; 10]).unwrap();
+        fs::write(source.path().join("b.pdf"), [0u8; 30]).unwrap();
+        fs::write(source.path().join("big.pdf"), [0u8; 80]).unwrap();
+        fs::write(source.path().join("setup.exe"), [0u8; 5]).unwrap();
+        fs::write(source.path().join("nested/c.md"), b"# notes").unwrap();
+        
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (id TEXT PRIMARY KEY);
+            CREATE TABLE attachments (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                path TEXT NOT NULL,
+                mime TEXT,
+                size INTEGER,
+                created_at INTEGER NOT NULL
+            );
+            INSERT INTO tasks (id) VALUES ('t1');
+            INSERT INTO attachments (id, task_id, filename, path, size, created_at) VALUES ('old', 't1', 'old.png', 'attachments/t1/old.png', 50, 0);"
+        ).unwrap();
+        let limits = AttachmentLimits { max_file_bytes: 64, max_total_bytes: 80 };
+        
+        let mut progress = Vec::new();
+        let result = add_attachments_from_folder(&conn, storage.path(), "t1", source.path(), false, limits, |p| progress.push(p.processed)).unwrap();
+        let added: Vec<&str> = result.added.iter().map(|a| a.filename.as_str()).collect();
+        // b.pdf would push storage past 80 bytes once a.pdf is in
+        assert_eq!(added, vec!["a.pdf"]);
+        let mut reasons: Vec<&str> = result.skipped.iter().map(|s| s.reason.as_str()).collect();
+        reasons.sort();
+        assert_eq!(reasons.len(), 3);
+        assert!(reasons[0].starts_with("Attachment storage limit"));
+        assert!(reasons[1].starts_with("File is larger"));
+        assert!(reasons[2].starts_with("File type not allowed"));
+        assert!(result.failed.is_empty());
+        assert_eq!(progress, vec![0, 1, 2, 3, 4]);
+        
+        let (filename, path): (String, String) = conn.query_row(
+            "SELECT filename, path FROM attachments WHERE id = ?1", params![result.added[0].id], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(filename, "a.pdf");
+        assert_eq!(fs::read(storage.path().join(path)).unwrap().len(), 10);
+        
+        let roomy = AttachmentLimits { max_file_bytes: 64, max_total_bytes: 1024 };
+        let result = add_attachments_from_folder(&conn, storage.path(), "t1", &source.path().join("nested"), true, roomy, |_| {}).unwrap();
+        assert_eq!(result.added[0].filename, "c.md");
+        assert!(add_attachments_from_folder(&conn, storage.path(), "missing", source.path(), true, roomy, |_| {}).is_err());
+    }
+    
+    #[test]
+    fn test_ensure_safe_to_open() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let write = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path
+        };
+        
+        // Ampersands and spaces are passed through as-is, never to a shell
+        let path = write("Q&A notes & more.pdf", b"%PDF-1.7\n");
+        assert!(ensure_safe_to_open(&path, "Q&A notes & more.pdf").is_ok());
+        let path = write("a.png", b"\x89PNG\r\n\x1a\n");
+        assert!(ensure_safe_to_open(&path, "Screenshot.png").is_ok());
+        let path = write("readme.md", b"# Notes");
+        assert!(ensure_safe_to_open(&path, "readme.md").is_ok());
+        
+        // An executable renamed to look like a document
+        let path = write("invoice.pdf", b"MZ\x90\x00\x03");
+        assert!(ensure_safe_to_open(&path, "invoice.pdf").unwrap_err().contains("executable"));
+        let path = write("run.md", b"#!/bin/sh\nrm -rf ~");
+        assert!(ensure_safe_to_open(&path, "run.md").is_err());
+        let path = write("photo.jpg", b"%PDF-1.4");
+        assert!(ensure_safe_to_open(&path, "photo.jpg").unwrap_err().contains("doesn't match"));
+        
+        // Names that only look allowed
+        let path = write("b.pdf", b"%PDF-1.4");
+        assert!(ensure_safe_to_open(&path, "invoice.pdf ").is_err());
+        assert!(ensure_safe_to_open(&path, "report.pdf.exe").is_err());
+        let path = write("c.exe", b"%PDF-1.4");
+        assert!(ensure_safe_to_open(&path, "report.pdf").is_err());
+    }
+}
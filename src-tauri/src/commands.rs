@@ -1,8 +1,26 @@
 use crate::db::DbConnection;
+use crate::services::calendar_sync;
+use crate::services::completion_time_service;
+use crate::services::export_service;
+use crate::services::external_import_service;
+use crate::services::import_service;
+use crate::services::journal_service;
+use crate::services::palette_service;
 use crate::services::pomodoro_service;
+use crate::services::priority_aging_service;
+use crate::services::project_suggestion_service;
+use crate::services::retention_service;
+use crate::services::rollover_service;
+use crate::services::rules_service;
 use crate::services::stats_service;
+use crate::services::status_service;
+use crate::services::tag_suggestion_service;
 use crate::services::translation_service;
-use rusqlite::params;
+use crate::services::undo_service::{UndoEntry, UndoJournal};
+use crate::services::vacation_service;
+use crate::services::week_service;
+use crate::services::weekly_review_service;
+use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -22,13 +40,53 @@ pub struct Task {
     pub updated_at: i64,
     pub project_id: Option<String>,
     pub order_index: i32,
-    pub recurrence_type: String, // none, daily, weekly, monthly, custom
+    pub recurrence_type: String, // none, daily, weekly, monthly, yearly, custom
     pub recurrence_interval: i32,
     pub recurrence_parent_id: Option<String>,
     pub reminder_minutes_before: Option<i32>,
     pub notification_repeat: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<Tag>>,
+    /// Whether an incomplete task blocks this one; only filled in by get_tasks and get_task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_blocked: Option<bool>,
+    /// Number of incomplete tasks blocking this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocker_count: Option<i64>,
+    /// Set by update_task when the edit rescheduled the task's reminder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder_state: Option<crate::notifications::ReminderState>,
+    /// Whether completing needs a note (see COMPLETION_NOTE_REQUIRED_ERROR); only filled in by fetch_task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_note_required: Option<bool>,
+    /// Instances in this task's recurrence chain; only filled in by get_tasks with collapse_recurring
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_count: Option<i64>,
+    /// Who the task is waiting on (see set_waiting); only filled in by get_tasks and fetch_task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_on: Option<String>,
+    /// When the task was marked as waiting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_since: Option<i64>,
+    /// Pinned tasks are listed before the rest
+    #[serde(default)]
+    pub pinned: bool,
+    /// Board column: todo, in_progress or done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Recurrence rule details, only set on single-task responses: ISO weekdays for weekly
+    /// recurrence and when the chain ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_days_of_week: Option<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_end_date: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_count: Option<i32>,
+    /// Planned and tracked time for the task, in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_minutes: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_minutes: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +96,9 @@ pub struct Project {
     pub color: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
+    /// Default completion_note_required for new tasks; filled in by the project commands
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_note_required: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +127,30 @@ pub struct Tag {
     pub color: Option<String>,
     pub created_at: i64,
     pub usage_count: i32,
+    #[serde(default)]
+    pub group_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagGroup {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub order_index: i32,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagGroupWithTags {
+    #[serde(flatten)]
+    pub group: TagGroup,
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupedTags {
+    pub groups: Vec<TagGroupWithTags>,
+    pub ungrouped: Vec<Tag>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +168,27 @@ pub struct CreateTagInput {
     pub color: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTagInput {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    /// Group to move the tag into; an empty string ungroups it
+    pub group_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTagGroupInput {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTagGroupInput {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub order_index: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateRelationshipInput {
     pub task_id_1: String,
@@ -90,7 +196,7 @@ pub struct CreateRelationshipInput {
     pub relationship_type: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TaskFilter {
     pub project_id: Option<String>,
     pub completed: Option<bool>,
@@ -98,6 +204,70 @@ pub struct TaskFilter {
     pub due_after: Option<i64>,
     pub search: Option<String>,
     pub tag_id: Option<String>,
+    /// Fill in is_blocked/blocker_count; on unless explicitly false
+    pub with_block_status: Option<bool>,
+    /// Return only the newest matching instance of each recurrence chain, with instance_count set
+    pub collapse_recurring: Option<bool>,
+    /// Full-text search over title and description, ordered by relevance. Every word must match,
+    /// as a prefix. Behaves like `search` if the FTS index is unavailable.
+    #[serde(default)]
+    pub fts_search: Option<String>,
+    /// Include tasks marked as waiting on someone; they are left out unless explicitly true
+    #[serde(default)]
+    pub include_waiting: Option<bool>,
+    /// Page size; all matching tasks when absent
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+    /// One of TASK_SORT_FIELDS; defaults to the manual order (or relevance for fts_search)
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    /// "asc" (default) or "desc"
+    #[serde(default)]
+    pub sort_dir: Option<String>,
+    /// Only open tasks whose due date has passed, most overdue first unless sort_by is set
+    #[serde(default)]
+    pub overdue_only: Option<bool>,
+    /// Only tasks with (true) or without (false) a description that isn't just whitespace
+    #[serde(default)]
+    pub has_description: Option<bool>,
+    /// Only tasks with (true) or without (false) attachments
+    #[serde(default)]
+    pub has_attachments: Option<bool>,
+    /// Only tasks with (true) or without (false) subtasks
+    #[serde(default)]
+    pub has_subtasks: Option<bool>,
+    /// Cut descriptions to their first DESCRIPTION_PREVIEW_CHARS characters, for list views
+    #[serde(default)]
+    pub description_preview_only: Option<bool>,
+}
+
+/// One page of get_tasks_page results
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    /// Matching tasks across all pages
+    pub total_count: i64,
+}
+
+pub const TASK_SORT_FIELDS: &[&str] = &["order", "due_date", "created_at", "updated_at", "priority", "title"];
+
+// ORDER BY expression for a TaskFilter sort_by; tasks without a due date sort last either way
+fn task_sort_expression(sort_by: &str, descending: bool) -> Result<String, String> {
+    let dir = if descending { "DESC" } else { "ASC" };
+    Ok(match sort_by {
+        "order" => format!("order_index {}", dir),
+        "due_date" => format!("due_at IS NULL, due_at {}", dir),
+        "created_at" => format!("created_at {}", dir),
+        "updated_at" => format!("updated_at {}", dir),
+        "priority" => format!(
+            "CASE priority WHEN 'high' THEN 3 WHEN 'medium' THEN 2 WHEN 'low' THEN 1 ELSE 0 END {}",
+            dir
+        ),
+        "title" => format!("title COLLATE NOCASE {}", dir),
+        other => return Err(format!("Invalid sort_by: {} (expected one of {})", other, TASK_SORT_FIELDS.join(", "))),
+    })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -111,9 +281,33 @@ pub struct CreateTaskInput {
     pub recurrence_interval: Option<i32>,
     pub reminder_minutes_before: Option<i32>,
     pub notification_repeat: Option<bool>,
+    /// One of the ids from get_available_sounds; None uses the default for the urgency
+    #[serde(default)]
+    pub reminder_sound: Option<String>,
+    /// "low", "normal" (default) or "critical"
+    #[serde(default)]
+    pub reminder_urgency: Option<String>,
+    /// None takes the project's default
+    #[serde(default)]
+    pub completion_note_required: Option<bool>,
+    /// ISO weekdays (1 = Monday) a weekly recurrence falls on
+    #[serde(default)]
+    pub recurrence_days_of_week: Option<Vec<u32>>,
+    /// No instances are created after this
+    #[serde(default)]
+    pub recurrence_end_date: Option<i64>,
+    /// Total number of tasks in the chain, this one included
+    #[serde(default)]
+    pub recurrence_count: Option<i32>,
+    /// Planned time in minutes
+    #[serde(default)]
+    pub estimated_minutes: Option<i32>,
+    /// Time actually spent in minutes
+    #[serde(default)]
+    pub actual_minutes: Option<i32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct UpdateTaskInput {
     pub title: Option<String>,
     pub description: Option<String>,
@@ -125,18 +319,53 @@ pub struct UpdateTaskInput {
     pub recurrence_interval: Option<i32>,
     pub reminder_minutes_before: Option<i32>,
     pub notification_repeat: Option<bool>,
+    /// Empty string goes back to the default sound
+    #[serde(default)]
+    pub reminder_sound: Option<String>,
+    #[serde(default)]
+    pub reminder_urgency: Option<String>,
+    #[serde(default)]
+    pub completion_note_required: Option<bool>,
+    /// The updated_at the edit was based on; a different current value is an EditConflict
+    #[serde(default)]
+    pub expected_updated_at: Option<i64>,
+    /// Board column; moving into a column at its WIP limit fails unless `force` is set
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub force: Option<bool>,
+    /// An empty list clears the weekdays
+    #[serde(default)]
+    pub recurrence_days_of_week: Option<Vec<u32>>,
+    /// 0 clears the end date or count
+    #[serde(default)]
+    pub recurrence_end_date: Option<i64>,
+    #[serde(default)]
+    pub recurrence_count: Option<i32>,
+    /// 0 clears the estimate or the tracked time
+    #[serde(default)]
+    pub estimated_minutes: Option<i32>,
+    #[serde(default)]
+    pub actual_minutes: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateProjectInput {
     pub name: String,
     pub color: Option<String>,
+    #[serde(default)]
+    pub completion_note_required: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateProjectInput {
     pub name: Option<String>,
     pub color: Option<String>,
+    #[serde(default)]
+    pub completion_note_required: Option<bool>,
+    /// See UpdateTaskInput::expected_updated_at
+    #[serde(default)]
+    pub expected_updated_at: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -145,6 +374,26 @@ pub struct ImportSummary {
     pub tasks_updated: usize,
     pub projects_added: usize,
     pub projects_updated: usize,
+    pub badges_added: usize,
+    pub xp_history_added: usize,
+    pub pomodoro_sessions_added: usize,
+    pub user_progress_updated: usize,
+    pub pomodoro_streaks_updated: usize,
+    pub tag_groups_added: usize,
+    pub tags_added: usize,
+    pub checklists_added: usize,
+    pub rules_applied: usize,
+    #[serde(default)]
+    pub task_tags_added: usize,
+    #[serde(default)]
+    pub task_relationships_added: usize,
+    #[serde(default)]
+    pub templates_added: usize,
+    #[serde(default)]
+    pub templates_updated: usize,
+    /// Hand-corrected translations added or updated from "translation_overrides"
+    #[serde(default)]
+    pub translation_overrides_imported: usize,
 }
 
 // Helper function to get current timestamp
@@ -174,7 +423,7 @@ fn fetch_task_tags(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Tag
     
     // Try to fetch tags, but don't fail if there's an error
     match conn.prepare(
-        "SELECT t.id, t.name, t.color, t.created_at, t.usage_count 
+        "SELECT t.id, t.name, t.color, t.created_at, t.usage_count, t.group_id 
          FROM tags t 
          INNER JOIN task_tags tt ON t.id = tt.tag_id 
          WHERE tt.task_id = ?1 
@@ -188,6 +437,7 @@ fn fetch_task_tags(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Tag
                     color: row.get(2)?,
                     created_at: row.get(3)?,
                     usage_count: row.get(4)?,
+                    group_id: row.get(5)?,
                 })
             }) {
                 Ok(rows) => {
@@ -210,7 +460,7 @@ fn fetch_task_tags(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Tag
 // Helper function to fetch a task by ID (assumes lock is already held)
 fn fetch_task(conn: &rusqlite::Connection, id: &str) -> Result<Task, String> {
     let mut task = conn.query_row(
-        "SELECT id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, reminder_minutes_before, notification_repeat FROM tasks WHERE id = ?1",
+        "SELECT id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, reminder_minutes_before, notification_repeat, completion_note_required, waiting_on, waiting_since, pinned, status, recurrence_days_of_week, recurrence_end_date, recurrence_count, estimated_minutes, actual_minutes FROM tasks WHERE id = ?1",
         params![id],
         |row| {
             Ok(Task {
@@ -230,6 +480,22 @@ fn fetch_task(conn: &rusqlite::Connection, id: &str) -> Result<Task, String> {
                 reminder_minutes_before: row.get(14).ok().flatten(),
                 notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
                 tags: None,
+                is_blocked: None,
+                blocker_count: None,
+                reminder_state: None,
+                completion_note_required: Some(row.get::<_, i64>(16)? != 0),
+                instance_count: None,
+                waiting_on: row.get(17)?,
+                waiting_since: row.get(18)?,
+                pinned: row.get::<_, i64>(19)? != 0,
+                status: Some(board_status(row.get(20)?, row.get::<_, Option<i64>>(7)?.is_some())),
+                recurrence_days_of_week: row
+                    .get::<_, Option<String>>(21)?
+                    .map(|days| crate::services::task_service::parse_days_of_week(Some(&days))),
+                recurrence_end_date: row.get(22)?,
+                recurrence_count: row.get(23)?,
+                estimated_minutes: row.get(24)?,
+                actual_minutes: row.get(25)?,
             })
         },
     ).map_err(|e| format!("Task not found: {}", e))?;
@@ -241,95 +507,196 @@ fn fetch_task(conn: &rusqlite::Connection, id: &str) -> Result<Task, String> {
 }
 
 // Task commands
-#[tauri::command]
-pub fn get_tasks(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    filter: Option<TaskFilter>,
-) -> Result<Vec<Task>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut query = "SELECT id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, reminder_minutes_before, notification_repeat FROM tasks WHERE 1=1".to_string();
+// Turn free text into an FTS5 query: each word quoted (so punctuation can't break the syntax)
+// and matched as a prefix, all words required. None if there is nothing to search for.
+fn fts_match_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+// `archived` picks between the regular task list and the archive
+fn get_tasks_internal(conn: &rusqlite::Connection, filter: Option<&TaskFilter>, archived: bool) -> Result<Vec<Task>, String> {
+    Ok(get_tasks_page_internal(conn, filter, archived, false)?.tasks)
+}
+
+// The tasks matching `filter`, paged by its limit/offset. Counting every match takes an extra
+// query, so unless `count_all` is set total_count is just the number of tasks returned.
+fn get_tasks_page_internal(
+    conn: &rusqlite::Connection,
+    filter: Option<&TaskFilter>,
+    archived: bool,
+    count_all: bool,
+) -> Result<TaskPage, String> {
+    let mut conditions = if archived { " AND archived = 1" } else { " AND archived = 0" }.to_string();
     let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    // Set when results should be ranked by full-text relevance
+    let mut relevance_query: Option<String> = None;
+    
+    // Waiting tasks are out of the way until their follow-up
+    if filter.and_then(|f| f.include_waiting) != Some(true) {
+        conditions.push_str(" AND waiting_since IS NULL");
+    }
     
-    if let Some(f) = &filter {
+    if let Some(f) = filter {
         if let Some(project_id) = &f.project_id {
-            query.push_str(" AND project_id = ?");
+            conditions.push_str(" AND project_id = ?");
             query_params.push(Box::new(project_id.clone()));
         }
+        if f.overdue_only == Some(true) {
+            conditions.push_str(" AND due_at < ? AND completed_at IS NULL");
+            query_params.push(Box::new(now()));
+        }
         if let Some(completed) = f.completed {
             if completed {
-                query.push_str(" AND completed_at IS NOT NULL");
+                conditions.push_str(" AND completed_at IS NOT NULL");
             } else {
-                query.push_str(" AND completed_at IS NULL");
+                conditions.push_str(" AND completed_at IS NULL");
             }
         }
         if let Some(due_before) = f.due_before {
-            query.push_str(" AND due_at <= ?");
+            conditions.push_str(" AND due_at <= ?");
             query_params.push(Box::new(due_before));
         }
         if let Some(due_after) = f.due_after {
-            query.push_str(" AND due_at >= ?");
+            conditions.push_str(" AND due_at >= ?");
             query_params.push(Box::new(due_after));
         }
         if let Some(search) = &f.search {
-            query.push_str(" AND (title LIKE ? OR description LIKE ?)");
+            conditions.push_str(" AND (title LIKE ? OR description LIKE ?)");
             let search_pattern = format!("%{}%", search);
             query_params.push(Box::new(search_pattern.clone()));
             query_params.push(Box::new(search_pattern));
         }
+        if let Some(fts_search) = f.fts_search.as_deref().map(str::trim).filter(|text| !text.is_empty()) {
+            let fts_exists: bool = conn.query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='tasks_fts'",
+                [],
+                |row| Ok(row.get::<_, i64>(0)? > 0),
+            ).unwrap_or(false);
+            
+            if !fts_exists {
+                // SQLite built without FTS5 (or the migration failed): fall back to LIKE
+                conditions.push_str(" AND (title LIKE ? OR description LIKE ?)");
+                let search_pattern = format!("%{}%", fts_search);
+                query_params.push(Box::new(search_pattern.clone()));
+                query_params.push(Box::new(search_pattern));
+            } else if let Some(match_query) = fts_match_query(fts_search) {
+                conditions.push_str(" AND rowid IN (SELECT rowid FROM tasks_fts WHERE tasks_fts MATCH ?)");
+                query_params.push(Box::new(match_query.clone()));
+                relevance_query = Some(match_query);
+            }
+        }
+        let content_filters = [
+            (f.has_description, "SELECT 1 WHERE TRIM(COALESCE(tasks.description, '')) != ''"),
+            (f.has_attachments, "SELECT 1 FROM attachments WHERE attachments.task_id = tasks.id"),
+            (f.has_subtasks, "SELECT 1 FROM subtasks WHERE subtasks.task_id = tasks.id"),
+        ];
+        for (wanted, subquery) in content_filters {
+            if let Some(wanted) = wanted {
+                conditions.push_str(&format!(" AND {}EXISTS ({})", if wanted { "" } else { "NOT " }, subquery));
+            }
+        }
         if let Some(tag_id) = &f.tag_id {
             // Only apply tag filter if task_tags table exists
-            let task_tags_exists: bool = db.conn.query_row(
+            let task_tags_exists: bool = conn.query_row(
                 "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='task_tags'",
                 [],
                 |row| Ok(row.get::<_, i64>(0)? > 0),
             ).unwrap_or(false);
             
             if task_tags_exists {
-                query.push_str(" AND id IN (SELECT task_id FROM task_tags WHERE tag_id = ?)");
+                conditions.push_str(" AND id IN (SELECT task_id FROM task_tags WHERE tag_id = ?)");
                 query_params.push(Box::new(tag_id.clone()));
             } else {
                 // If table doesn't exist, no tasks will match tag filter, so return empty
-                return Ok(Vec::new());
+                return Ok(TaskPage { tasks: Vec::new(), total_count: 0 });
             }
         }
     }
     
-    query.push_str(" ORDER BY order_index, created_at");
+    let collapse_recurring = filter.and_then(|f| f.collapse_recurring).unwrap_or(false);
+    let mut query = if collapse_recurring {
+        // Rank the matching instances within each chain and keep the newest
+        format!(
+            "SELECT {columns}, instance_count, waiting_on, waiting_since FROM (
+                SELECT *, rowid AS task_rowid,
+                    ROW_NUMBER() OVER (PARTITION BY COALESCE(recurrence_root_id, id) ORDER BY created_at DESC, rowid DESC) AS chain_rank,
+                    COUNT(*) OVER (PARTITION BY COALESCE(recurrence_root_id, id)) AS instance_count
+                FROM tasks WHERE 1=1{conditions}
+            ) WHERE chain_rank = 1",
+            columns = TASK_COLUMNS,
+            conditions = conditions
+        )
+    } else {
+        format!("SELECT {}, NULL, waiting_on, waiting_since FROM tasks WHERE 1=1{}", TASK_COLUMNS, conditions)
+    };
+    
+    let limit = filter.and_then(|f| f.limit).filter(|limit| *limit >= 0);
+    let offset = filter.and_then(|f| f.offset).unwrap_or(0).max(0);
+    let counted_total = if count_all && (limit.is_some() || offset > 0) {
+        Some(conn.query_row(
+            &format!("SELECT COUNT(*) FROM ({})", query),
+            rusqlite::params_from_iter(query_params.iter()),
+            |row| row.get::<_, i64>(0),
+        ).map_err(|e| format!("Query error: {}", e))?)
+    } else {
+        None
+    };
+    
+    let descending = match filter.and_then(|f| f.sort_dir.as_deref()) {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => return Err(format!("Invalid sort_dir: {} (expected asc or desc)", other)),
+    };
+    // Pinned tasks lead the list; a relevance search is ordered purely by match
+    match (filter.and_then(|f| f.sort_by.as_deref()), relevance_query) {
+        (Some(sort_by), _) => {
+            query.push_str(&format!(" ORDER BY pinned DESC, {}, order_index, created_at, id", task_sort_expression(sort_by, descending)?));
+        }
+        (None, Some(match_query)) => {
+            // bm25 is lower for better matches
+            query.push_str(&format!(
+                " ORDER BY (SELECT bm25(tasks_fts) FROM tasks_fts WHERE tasks_fts MATCH ? AND tasks_fts.rowid = {}), order_index, created_at, id",
+                if collapse_recurring { "task_rowid" } else { "tasks.rowid" }
+            ));
+            query_params.push(Box::new(match_query));
+        }
+        // Overdue lists skip pinning: the longest overdue comes first
+        (None, None) if filter.and_then(|f| f.overdue_only) == Some(true) => {
+            query.push_str(" ORDER BY due_at, order_index, created_at, id")
+        }
+        (None, None) => query.push_str(" ORDER BY pinned DESC, order_index, created_at, id"),
+    }
+    if limit.is_some() || offset > 0 {
+        // SQLite needs a LIMIT before OFFSET; -1 means no limit
+        query.push_str(" LIMIT ? OFFSET ?");
+        query_params.push(Box::new(limit.unwrap_or(-1)));
+        query_params.push(Box::new(offset));
+    }
     
-    let mut stmt = db.conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    // instance_count, waiting_on and waiting_since follow the task columns
+    let extra_column = TASK_COLUMNS.split(", ").count();
     let rows = stmt.query_map(rusqlite::params_from_iter(query_params.iter()), |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: row.get(14).ok().flatten(),
-            notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
-            tags: None,
-        })
+        let mut task = task_from_row(row)?;
+        task.instance_count = row.get(extra_column)?;
+        task.waiting_on = row.get(extra_column + 1)?;
+        task.waiting_since = row.get(extra_column + 2)?;
+        Ok(task)
     }).map_err(|e| format!("Query execution error: {}", e))?;
     
     let mut tasks = Vec::new();
     for row in rows {
         match row {
-            Ok(mut task) => {
-                // Fetch tags for each task - this will return empty vec if tags table doesn't exist
-                match fetch_task_tags(&db.conn, &task.id) {
-                    Ok(tags) => task.tags = Some(tags),
-                    Err(_) => task.tags = Some(Vec::new()), // Fallback to empty if fetch fails
-                }
-                tasks.push(task);
-            }
+            Ok(task) => tasks.push(task),
             Err(e) => {
                 // Log error but continue processing other tasks
                 eprintln!("Error parsing task row: {}", e);
@@ -338,942 +705,1151 @@ pub fn get_tasks(
         }
     }
     
-    Ok(tasks)
+    attach_tags(conn, &mut tasks)?;
+    
+    if filter.and_then(|f| f.with_block_status) != Some(false) {
+        populate_block_status(conn, &mut tasks)?;
+    }
+    if filter.and_then(|f| f.description_preview_only) == Some(true) {
+        for task in &mut tasks {
+            if let Some(description) = &mut task.description {
+                if let Some((cut, _)) = description.char_indices().nth(DESCRIPTION_PREVIEW_CHARS) {
+                    description.truncate(cut);
+                }
+            }
+        }
+    }
+    
+    let total_count = counted_total.unwrap_or(tasks.len() as i64);
+    Ok(TaskPage { tasks, total_count })
 }
 
 #[tauri::command]
-pub fn get_task(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Task, String> {
+pub fn get_tasks(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    filter: Option<TaskFilter>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    fetch_task(&db.conn, &id)
+    let tasks = get_tasks_internal(&db.conn, filter.as_ref(), false)?;
+    Ok(demo.apply(tasks))
+}
+
+// Open tasks past their due date, most overdue first; the rest of `filter` applies on top
+fn get_overdue_tasks_internal(conn: &rusqlite::Connection, filter: Option<TaskFilter>) -> Result<Vec<Task>, String> {
+    let filter = TaskFilter {
+        overdue_only: Some(true),
+        ..filter.unwrap_or_default()
+    };
+    get_tasks_internal(conn, Some(&filter), false)
 }
 
 #[tauri::command]
-pub fn create_task(
+pub fn get_overdue_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    input: CreateTaskInput,
-) -> Result<Task, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    filter: Option<TaskFilter>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    db.conn.execute(
-        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, reminder_minutes_before, notification_repeat)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
-        params![
-            id.clone(),
-            input.title,
-            input.description,
-            input.due_date,
-            now,
-            now,
-            input.priority,
-            None::<i64>,
-            input.project_id,
-            0,
-            None::<String>,
-            input.recurrence_type.unwrap_or_else(|| "none".to_string()),
-            input.recurrence_interval.unwrap_or(1),
-            None::<String>,
-            input.reminder_minutes_before,
-            if input.notification_repeat.unwrap_or(false) { 1 } else { 0 }
-        ],
-    ).map_err(|e| format!("Failed to create task: {}", e))?;
-    
-    // Schedule notification if reminder is set
-    if let Some(reminder_minutes) = input.reminder_minutes_before {
-        let _ = crate::notifications::schedule_notification(&db, &id, Some(reminder_minutes));
-    }
-    
-    fetch_task(&db.conn, &id)
+    let tasks = get_overdue_tasks_internal(&db.conn, filter)?;
+    Ok(demo.apply(tasks))
+}
+
+// Same tasks as get_overdue_tasks without a filter: archived and waiting tasks don't count
+fn get_overdue_count_internal(conn: &rusqlite::Connection, now: i64) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM tasks
+         WHERE due_at < ?1 AND completed_at IS NULL AND archived = 0 AND waiting_since IS NULL",
+        params![now],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))
 }
 
+/// Number of overdue tasks, for the tray badge
 #[tauri::command]
-pub fn update_task(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    id: String,
-    input: UpdateTaskInput,
-) -> Result<Task, String> {
+pub fn get_overdue_count(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<i64, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let now = now();
-    let mut updates = Vec::new();
-    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-    
-    if let Some(title) = input.title {
-        updates.push("title = ?");
-        query_params.push(Box::new(title));
-    }
-    if let Some(description) = input.description {
-        updates.push("description = ?");
-        query_params.push(Box::new(description));
-    }
-    if let Some(due_date) = input.due_date {
-        updates.push("due_at = ?");
-        query_params.push(Box::new(due_date));
-    }
-    if let Some(priority) = input.priority {
-        updates.push("priority = ?");
-        query_params.push(Box::new(priority));
-    }
-    if let Some(project_id) = input.project_id {
-        updates.push("project_id = ?");
-        query_params.push(Box::new(project_id));
-    }
-    if let Some(order_index) = input.order_index {
-        updates.push("order_index = ?");
-        query_params.push(Box::new(order_index));
-    }
-    if let Some(recurrence_type) = input.recurrence_type {
-        updates.push("recurrence_type = ?");
-        query_params.push(Box::new(recurrence_type));
-    }
-    if let Some(recurrence_interval) = input.recurrence_interval {
-        updates.push("recurrence_interval = ?");
-        query_params.push(Box::new(recurrence_interval));
-    }
-    if let Some(reminder_minutes_before) = input.reminder_minutes_before {
-        updates.push("reminder_minutes_before = ?");
-        query_params.push(Box::new(reminder_minutes_before));
-    }
-    if let Some(notification_repeat) = input.notification_repeat {
-        updates.push("notification_repeat = ?");
-        query_params.push(Box::new(if notification_repeat { 1 } else { 0 }));
-    }
-    
-    if updates.is_empty() {
-        return fetch_task(&db.conn, &id);
-    }
-    
-    updates.push("updated_at = ?");
-    query_params.push(Box::new(now));
-    query_params.push(Box::new(id.clone()));
-    
-    let query = format!("UPDATE tasks SET {} WHERE id = ?", updates.join(", "));
-    db.conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
-        .map_err(|e| format!("Failed to update task: {}", e))?;
-    
-    // Reschedule notifications if reminder settings changed
-    if input.reminder_minutes_before.is_some() || input.notification_repeat.is_some() || input.due_date.is_some() {
-        // Delete existing notifications for this task
-        let _ = db.conn.execute(
-            "DELETE FROM notification_schedule WHERE task_id = ?1",
-            params![id.clone()],
-        );
-        
-        // Schedule new notifications
-        let reminder_minutes: Option<i32> = db.conn.query_row(
-            "SELECT reminder_minutes_before FROM tasks WHERE id = ?1",
-            params![id.clone()],
-            |row| row.get(0),
-        ).ok().flatten();
-        
-        if reminder_minutes.is_some() {
-            let _ = crate::notifications::schedule_notification(&db, &id, reminder_minutes);
-        }
-    }
-    
-    fetch_task(&db.conn, &id)
+    get_overdue_count_internal(&db.conn, now())
+}
+
+// Open, unarchived tasks due in [start, end), soonest first, with their tags
+fn get_tasks_due_between(conn: &rusqlite::Connection, start: i64, end: i64) -> Result<Vec<Task>, String> {
+    let mut tasks = query_tasks(
+        conn,
+        "completed_at IS NULL AND archived = 0 AND due_at >= ?1 AND due_at < ?2",
+        "due_at ASC, order_index ASC",
+        params![start, end],
+    )?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
+}
+
+// The calendar day `now` falls on at `tz_offset_minutes` from UTC
+fn date_at_offset(now: i64, tz_offset_minutes: i32) -> Result<chrono::NaiveDate, String> {
+    now.checked_add(tz_offset_minutes as i64 * 60)
+        .and_then(|local| chrono::DateTime::from_timestamp(local, 0))
+        .map(|dt| dt.date_naive())
+        .ok_or_else(|| "Invalid date".to_string())
+}
+
+// Tasks due on the day `now` falls on; days run from midnight UTC unless an offset is given
+pub(crate) fn get_tasks_due_today_internal(conn: &rusqlite::Connection, now: i64, tz_offset_minutes: Option<i32>) -> Result<Vec<Task>, String> {
+    let offset = tz_offset_minutes.unwrap_or(0);
+    let (day_start, day_end) = day_bounds(date_at_offset(now, offset)?, Some(offset))?;
+    get_tasks_due_between(conn, day_start, day_end)
+}
+
+// Tasks due in the ISO week (Monday to Sunday) `now` falls on, bounded like get_tasks_due_today
+pub(crate) fn get_tasks_due_this_week_internal(conn: &rusqlite::Connection, now: i64, tz_offset_minutes: Option<i32>) -> Result<Vec<Task>, String> {
+    use chrono::Datelike;
+    
+    let offset = tz_offset_minutes.unwrap_or(0);
+    let today = date_at_offset(now, offset)?;
+    let out_of_range = || format!("Date out of range: {}", today);
+    let monday = today.checked_sub_signed(chrono::Duration::days(today.weekday().num_days_from_monday() as i64))
+        .ok_or_else(out_of_range)?;
+    let next_monday = monday.checked_add_signed(chrono::Duration::days(7)).ok_or_else(out_of_range)?;
+    let (week_start, _) = day_bounds(monday, Some(offset))?;
+    let (week_end, _) = day_bounds(next_monday, Some(offset))?;
+    get_tasks_due_between(conn, week_start, week_end)
 }
 
+/// Open tasks due today, soonest first. The day is the UTC one unless tz_offset_minutes is given.
 #[tauri::command]
-pub fn delete_task(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+pub fn get_tasks_due_today(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    tz_offset_minutes: Option<i32>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Get all tags associated with this task before deletion
-    let tag_ids: Vec<String> = db.conn.prepare(
-        "SELECT tag_id FROM task_tags WHERE task_id = ?1"
-    )
-    .map_err(|e| format!("Failed to query task tags: {}", e))?
-    .query_map(params![id], |row| row.get(0))
-    .map_err(|e| format!("Failed to execute query: {}", e))?
-    .collect::<Result<Vec<String>, _>>()
-    .map_err(|e| format!("Failed to collect tag IDs: {}", e))?;
-    
-    // Delete the task (CASCADE will handle task_tags deletion)
-    db.conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete task: {}", e))?;
-    
-    // Update usage_count for each affected tag
-    for tag_id in tag_ids {
-        db.conn.execute(
-            "UPDATE tags SET usage_count = MAX(0, usage_count - 1) WHERE id = ?1",
-            params![tag_id],
-        ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
-    }
-    
-    Ok(())
+    let tasks = get_tasks_due_today_internal(&db.conn, now(), tz_offset_minutes)?;
+    Ok(demo.apply(tasks))
 }
 
+/// Open tasks due this ISO week (Monday to Sunday), soonest first; see get_tasks_due_today
 #[tauri::command]
-pub fn toggle_complete(
+pub fn get_tasks_due_this_week(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    id: String,
-) -> Result<Task, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    tz_offset_minutes: Option<i32>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Get current task state
-    let task_info: (Option<i64>, String, i32, String) = db.conn.query_row(
-        "SELECT completed_at, recurrence_type, recurrence_interval, priority FROM tasks WHERE id = ?1",
-        params![id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
-    ).map_err(|e| format!("Task not found: {}", e))?;
-    
-    let (completed, recurrence_type, recurrence_interval, priority) = task_info;
-    let was_completed = completed.is_some();
-    let now = now();
-    let new_completed = if completed.is_some() { None } else { Some(now) };
-    
-    db.conn.execute(
-        "UPDATE tasks SET completed_at = ?1, updated_at = ?2 WHERE id = ?3",
-        params![new_completed, now, id.clone()],
-    ).map_err(|e| format!("Failed to toggle complete: {}", e))?;
-    
-    // If task is being marked complete and has recurrence, create new instance
-    if new_completed.is_some() && recurrence_type != "none" {
-        create_recurring_instance(&db.conn, &id, &recurrence_type, recurrence_interval)?;
-    }
-    
-    // Handle gamification: grant XP when completing, revoke XP when undoing
-    if new_completed.is_some() && !was_completed {
-        // Task is being completed - grant XP
-        // Determine XP amount based on priority
-        let xp_amount = match priority.as_str() {
-            "low" => 10,
-            "medium" => 25,
-            "high" => 50,
-            _ => 25,
-        };
-        
-        // Grant XP
-        let _ = grant_xp_internal(&db.conn, xp_amount, "task_completion".to_string(), Some(id.clone()));
-        
-        // Update streak
-        let _ = update_streak_internal(&db.conn);
-        
-        // Check for badges
-        let _ = check_and_award_badges_internal(&db.conn);
-    } else if was_completed && new_completed.is_none() {
-        // Task is being uncompleted - revoke XP
-        // Find the most recent XP history entry for this task
-        let xp_entry: Option<(i32, String)> = db.conn.query_row(
-            "SELECT xp_amount, id FROM xp_history WHERE task_id = ?1 AND source = 'task_completion' ORDER BY created_at DESC LIMIT 1",
-            params![id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
-        ).ok();
-        
-        if let Some((xp_amount, history_id)) = xp_entry {
-            // Revoke the XP
-            let _ = revoke_xp_internal(&db.conn, xp_amount, history_id);
-            
-            // Update streak
-            let _ = update_streak_internal(&db.conn);
+    let tasks = get_tasks_due_this_week_internal(&db.conn, now(), tz_offset_minutes)?;
+    Ok(demo.apply(tasks))
+}
+
+/// Like get_tasks, with the total number of matches for paginating by `limit` and `offset`
+#[tauri::command]
+pub fn get_tasks_page(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    filter: Option<TaskFilter>,
+) -> Result<TaskPage, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let page = get_tasks_page_internal(&db.conn, filter.as_ref(), false, true)?;
+    Ok(TaskPage {
+        tasks: demo.apply(page.tasks),
+        total_count: page.total_count,
+    })
+}
+
+/// A search_tasks hit. `snippet` is HTML: the text around the match, escaped, with the matched
+/// words wrapped in <mark>.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSearchResult {
+    #[serde(flatten)]
+    pub task: Task,
+    pub snippet: String,
+    /// Lower is a better match; only comparable within one search
+    pub rank: f64,
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+// Words of context snippet() keeps around a match
+const SNIPPET_TOKENS: i64 = 12;
+// Characters of context either side of a match when falling back to LIKE
+const LIKE_SNIPPET_CONTEXT: usize = 40;
+// Stand-ins for <mark> and </mark> until the snippet has been escaped
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+fn snippet_html(text: &str) -> String {
+    crate::services::weekly_review_service::escape_html(text)
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+// `text` cut down to the first case-insensitive occurrence of `needle` and some context
+fn like_snippet(text: &str, needle: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    let position = (0..chars.len()).find(|start| {
+        let mut lowered = chars[*start..].iter().flat_map(|c| c.to_lowercase());
+        needle.iter().all(|n| lowered.next() == Some(*n))
+    });
+
+    let mut snippet = String::new();
+    match position {
+        Some(start) => {
+            let end = (start + needle.len()).min(chars.len());
+            let from = start.saturating_sub(LIKE_SNIPPET_CONTEXT);
+            let to = (end + LIKE_SNIPPET_CONTEXT).min(chars.len());
+            if from > 0 {
+                snippet.push('…');
+            }
+            snippet.extend(&chars[from..start]);
+            snippet.push(MATCH_START);
+            snippet.extend(&chars[start..end]);
+            snippet.push(MATCH_END);
+            snippet.extend(&chars[end..to]);
+            if to < chars.len() {
+                snippet.push('…');
+            }
         }
+        None => snippet.extend(chars.iter().take(2 * LIKE_SNIPPET_CONTEXT)),
     }
-    
-    fetch_task(&db.conn, &id)
+    snippet_html(&snippet)
 }
 
-// Helper function to create a recurring task instance
-fn create_recurring_instance(conn: &rusqlite::Connection, parent_id: &str, recurrence_type: &str, interval: i32) -> Result<(), String> {
-    // Fetch original task details
-    let original: (String, Option<String>, Option<i64>, String, Option<String>, i32) = conn.query_row(
-        "SELECT title, description, due_at, priority, project_id, order_index FROM tasks WHERE id = ?1",
-        params![parent_id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
-    ).map_err(|e| format!("Failed to fetch original task: {}", e))?;
-    
-    let (title, description, due_date, priority, project_id, order_index) = original;
-    
-    // Calculate new due date based on recurrence type
-    let new_due_date = if let Some(due) = due_date {
-        let days_to_add = match recurrence_type {
-            "daily" => interval,
-            "weekly" => interval * 7,
-            "monthly" => interval * 30, // Approximate
-            _ => 0,
+// Ranked search over titles, descriptions, subtask titles and attachment filenames of tasks
+// that aren't archived. Uses tasks_fts, subtasks_fts and attachments_fts, or LIKE when SQLite
+// was built without FTS5.
+fn search_tasks_internal(conn: &rusqlite::Connection, query: &str, limit: Option<i64>) -> Result<Vec<TaskSearchResult>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.filter(|limit| *limit > 0).unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let columns = TASK_COLUMNS
+        .split(", ")
+        .map(|column| format!("t.{}", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let snippet_column = TASK_COLUMNS.split(", ").count();
+
+    let fts_exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name IN ('tasks_fts', 'subtasks_fts', 'attachments_fts')",
+        [],
+        |row| Ok(row.get::<_, i64>(0)? == 3),
+    ).unwrap_or(false);
+
+    let mut results = Vec::new();
+    if fts_exists {
+        let match_query = match fts_match_query(query) {
+            Some(match_query) => match_query,
+            None => return Ok(Vec::new()),
         };
-        Some(due + (days_to_add as i64 * 24 * 60 * 60))
+        // Title hits count for most, then the description, then subtasks and attachments. Each
+        // task is listed once, with the snippet of its best hit.
+        let sql = format!(
+            "WITH hits AS (
+                SELECT tasks_fts.rowid AS task_rowid, snippet(tasks_fts, -1, ?2, ?3, '…', ?4) AS snippet,
+                    bm25(tasks_fts, 10.0, 4.0) AS score
+                FROM tasks_fts WHERE tasks_fts MATCH ?1
+                UNION ALL
+                SELECT t.rowid, snippet(subtasks_fts, 0, ?2, ?3, '…', ?4), bm25(subtasks_fts) * 2.0
+                FROM subtasks_fts
+                JOIN subtasks s ON s.rowid = subtasks_fts.rowid
+                JOIN tasks t ON t.id = s.task_id
+                WHERE subtasks_fts MATCH ?1
+                UNION ALL
+                SELECT t.rowid, snippet(attachments_fts, 0, ?2, ?3, '…', ?4), bm25(attachments_fts) * 2.0
+                FROM attachments_fts
+                JOIN attachments a ON a.rowid = attachments_fts.rowid
+                JOIN tasks t ON t.id = a.task_id
+                WHERE attachments_fts MATCH ?1
+            ),
+            best AS (
+                SELECT task_rowid, snippet, score,
+                    ROW_NUMBER() OVER (PARTITION BY task_rowid ORDER BY score) AS hit_rank
+                FROM hits
+            )
+            SELECT {}, best.snippet, best.score
+            FROM best JOIN tasks t ON t.rowid = best.task_rowid
+            WHERE best.hit_rank = 1 AND t.archived = 0
+            ORDER BY best.score, t.id
+            LIMIT ?5",
+            columns
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt
+            .query_map(
+                params![match_query, MATCH_START.to_string(), MATCH_END.to_string(), SNIPPET_TOKENS, limit],
+                |row| {
+                    Ok(TaskSearchResult {
+                        task: task_from_row(row)?,
+                        snippet: snippet_html(&row.get::<_, String>(snippet_column)?),
+                        rank: row.get(snippet_column + 1)?,
+                    })
+                },
+            )
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            results.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+        }
     } else {
-        None
-    };
-    
-    let new_id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    conn.execute(
-        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-        params![
-            new_id,
-            title,
-            description,
-            new_due_date,
-            now,
-            now,
-            priority,
-            None::<i64>,
-            project_id,
-            order_index,
-            None::<String>,
-            recurrence_type,
-            interval,
-            Some(parent_id)
-        ],
-    ).map_err(|e| format!("Failed to create recurring task instance: {}", e))?;
-    
-    Ok(())
-}
+        // Same order of preference as the FTS weights: rank is the first field that matched
+        let sql = format!(
+            "SELECT * FROM (
+                SELECT {},
+                    COALESCE(
+                        CASE WHEN t.title LIKE ?1 THEN t.title END,
+                        CASE WHEN t.description LIKE ?1 THEN t.description END,
+                        (SELECT title FROM subtasks WHERE task_id = t.id AND title LIKE ?1 LIMIT 1),
+                        (SELECT filename FROM attachments WHERE task_id = t.id AND filename LIKE ?1 LIMIT 1)
+                    ) AS matched,
+                    CASE
+                        WHEN t.title LIKE ?1 THEN 1
+                        WHEN t.description LIKE ?1 THEN 2
+                        WHEN EXISTS (SELECT 1 FROM subtasks WHERE task_id = t.id AND title LIKE ?1) THEN 3
+                        ELSE 4
+                    END AS score
+                FROM tasks t
+                WHERE t.archived = 0
+            )
+            WHERE matched IS NOT NULL
+            ORDER BY score, updated_at DESC, id
+            LIMIT ?2",
+            columns
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt
+            .query_map(params![format!("%{}%", query), limit], |row| {
+                Ok(TaskSearchResult {
+                    task: task_from_row(row)?,
+                    snippet: like_snippet(&row.get::<_, String>(snippet_column)?, query),
+                    rank: row.get::<_, i64>(snippet_column + 1)? as f64,
+                })
+            })
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            results.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+        }
+    }
 
-// Project commands
-#[tauri::command]
-pub fn get_projects(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Project>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.conn.prepare("SELECT id, name, color, created_at, updated_at FROM projects ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Project {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            color: row.get(2)?,
-            created_at: row.get(3)?,
-            updated_at: row.get(4)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut projects = Vec::new();
-    for row in rows {
-        projects.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    let mut tasks: Vec<Task> = results.iter().map(|result| result.task.clone()).collect();
+    attach_tags(conn, &mut tasks)?;
+    for (result, task) in results.iter_mut().zip(tasks) {
+        result.task = task;
     }
-    
-    Ok(projects)
+    Ok(results)
 }
 
+/// Search task titles, descriptions, subtask titles and attachment filenames, best match first
 #[tauri::command]
-pub fn create_project(
+pub fn search_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    input: CreateProjectInput,
-) -> Result<Project, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<TaskSearchResult>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    db.conn.execute(
-        "INSERT INTO projects (id, name, color, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![id.clone(), input.name, input.color, now, now],
-    ).map_err(|e| format!("Failed to create project: {}", e))?;
-    
-    db.conn.query_row(
-        "SELECT id, name, color, created_at, updated_at FROM projects WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Project {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        },
-    ).map_err(|e| format!("Failed to fetch created project: {}", e))
+    Ok(demo.apply(search_tasks_internal(&db.conn, &query, limit)?))
 }
 
 #[tauri::command]
-pub fn update_project(
+pub fn get_archived_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    id: String,
-    input: UpdateProjectInput,
-) -> Result<Project, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    filter: Option<TaskFilter>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let now = now();
-    let mut updates = Vec::new();
-    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-    
-    if let Some(name) = input.name {
-        updates.push("name = ?");
-        query_params.push(Box::new(name));
-    }
-    if let Some(color) = input.color {
-        updates.push("color = ?");
-        query_params.push(Box::new(color));
+    let tasks = get_tasks_internal(&db.conn, filter.as_ref(), true)?;
+    Ok(demo.apply(tasks))
+}
+
+// Archived tasks are hidden from get_tasks but can still be fetched by id
+fn set_task_archived(conn: &rusqlite::Connection, id: &str, archived: bool) -> Result<Task, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let updated = tx.execute(
+        "UPDATE tasks SET archived = ?1, updated_at = ?2 WHERE id = ?3",
+        params![archived, now(), id],
+    ).map_err(|e| format!("Failed to update task: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Task not found: {}", id));
     }
-    
-    if !updates.is_empty() {
-        updates.push("updated_at = ?");
-        query_params.push(Box::new(now));
-        query_params.push(Box::new(id.clone()));
-        
-        let query = format!("UPDATE projects SET {} WHERE id = ?", updates.join(", "));
-        db.conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
-            .map_err(|e| format!("Failed to update project: {}", e))?;
+    // An archived task shouldn't nag; unarchiving doesn't bring the reminders back
+    if archived {
+        crate::notifications::cancel_task_notifications(&tx, id)
+            .map_err(|e| format!("Failed to cancel reminders: {}", e))?;
     }
-    
-    db.conn.query_row(
-        "SELECT id, name, color, created_at, updated_at FROM projects WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Project {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        },
-    ).map_err(|e| format!("Project not found: {}", e))
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    fetch_task(conn, id)
 }
 
 #[tauri::command]
-pub fn delete_project(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+pub fn archive_task(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    db.conn.execute("DELETE FROM projects WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete project: {}", e))?;
-    
-    Ok(())
+    set_task_archived(&db.conn, &id, true)
 }
 
-// Subtask commands
 #[tauri::command]
-pub fn add_subtask(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-    title: String,
-) -> Result<Subtask, String> {
+pub fn unarchive_task(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let id = uuid::Uuid::new_v4().to_string();
-    
-    db.conn.execute(
-        "INSERT INTO subtasks (id, task_id, title, completed) VALUES (?1, ?2, ?3, ?4)",
-        params![id.clone(), task_id.clone(), title, 0],
-    ).map_err(|e| format!("Failed to create subtask: {}", e))?;
-    
-    db.conn.query_row(
-        "SELECT id, task_id, title, completed FROM subtasks WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Subtask {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                title: row.get(2)?,
-                completed: row.get::<_, i32>(3)? != 0,
-            })
-        },
-    ).map_err(|e| format!("Failed to fetch created subtask: {}", e))
+    set_task_archived(&db.conn, &id, false)
+}
+
+fn set_task_pinned(conn: &rusqlite::Connection, id: &str, pinned: bool) -> Result<Task, String> {
+    let updated = conn.execute(
+        "UPDATE tasks SET pinned = ?1, updated_at = ?2 WHERE id = ?3",
+        params![pinned, now(), id],
+    ).map_err(|e| format!("Failed to update task: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Task not found: {}", id));
+    }
+    fetch_task(conn, id)
 }
 
 #[tauri::command]
-pub fn update_subtask(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    id: String,
-    title: Option<String>,
-    completed: Option<bool>,
-) -> Result<Subtask, String> {
+pub fn pin_task(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut updates = Vec::new();
-    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-    
-    if let Some(title) = title {
-        updates.push("title = ?");
-        query_params.push(Box::new(title));
-    }
-    if let Some(completed) = completed {
-        updates.push("completed = ?");
-        query_params.push(Box::new(if completed { 1 } else { 0 }));
-    }
-    
-    if !updates.is_empty() {
-        query_params.push(Box::new(id.clone()));
-        let query = format!("UPDATE subtasks SET {} WHERE id = ?", updates.join(", "));
-        db.conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
-            .map_err(|e| format!("Failed to update subtask: {}", e))?;
-    }
-    
-    db.conn.query_row(
-        "SELECT id, task_id, title, completed FROM subtasks WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Subtask {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                title: row.get(2)?,
-                completed: row.get::<_, i32>(3)? != 0,
-            })
-        },
-    ).map_err(|e| format!("Subtask not found: {}", e))
+    set_task_pinned(&db.conn, &id, true)
 }
 
 #[tauri::command]
-pub fn delete_subtask(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+pub fn unpin_task(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    db.conn.execute("DELETE FROM subtasks WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete subtask: {}", e))?;
-    
-    Ok(())
+    set_task_pinned(&db.conn, &id, false)
+}
+
+// Pinned tasks that aren't archived, most recently changed first
+fn get_pinned_tasks_internal(conn: &rusqlite::Connection) -> Result<Vec<Task>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM tasks WHERE pinned = 1 AND archived = 0 ORDER BY updated_at DESC, id",
+            TASK_COLUMNS
+        ))
+        .map_err(|e| format!("Query error: {}", e))?;
+    let mut tasks = stmt
+        .query_map([], task_from_row)
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<Task>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
 }
 
 #[tauri::command]
-pub fn get_subtasks(
+pub fn get_pinned_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-) -> Result<Vec<Subtask>, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_pinned_tasks_internal(&db.conn)?))
+}
+
+// Mark a task as waiting on someone, hiding it from the default task list. A follow-up
+// reminder is scheduled `follow_up_days` from now, replacing any earlier one.
+fn set_waiting_internal(
+    conn: &rusqlite::Connection,
+    id: &str,
+    waiting_on: &str,
+    follow_up_days: Option<u32>,
+    now: i64,
+) -> Result<Task, String> {
+    let waiting_on = waiting_on.trim();
+    if waiting_on.is_empty() {
+        return Err("Waiting on cannot be empty".to_string());
+    }
     
-    let mut stmt = db.conn.prepare("SELECT id, task_id, title, completed FROM subtasks WHERE task_id = ?1 ORDER BY id").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map(params![task_id], |row| {
-        Ok(Subtask {
-            id: row.get(0)?,
-            task_id: row.get(1)?,
-            title: row.get(2)?,
-            completed: row.get::<_, i32>(3)? != 0,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut subtasks = Vec::new();
-    for row in rows {
-        subtasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    let previous: Option<String> = conn.query_row(
+        "SELECT waiting_on FROM tasks WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Task not found: {}", id))?;
+    
+    // Changing who a task waits on keeps its original waiting_since
+    conn.execute(
+        "UPDATE tasks SET waiting_on = ?1, waiting_since = COALESCE(waiting_since, ?2), updated_at = ?2 WHERE id = ?3",
+        params![waiting_on, now, id],
+    ).map_err(|e| format!("Failed to update task: {}", e))?;
+    
+    match follow_up_days.filter(|days| *days > 0) {
+        Some(days) => crate::notifications::schedule_follow_up(conn, id, now + days as i64 * 86400, now),
+        None => crate::notifications::cancel_follow_up(conn, id),
     }
+    .map_err(|e| format!("Failed to schedule follow-up: {}", e))?;
     
-    Ok(subtasks)
+    log_activity(conn, id, "waiting_on_changed", previous.as_deref(), Some(waiting_on), "waiting")?;
+    fetch_task(conn, id)
 }
 
-// Attachment commands
-#[tauri::command]
-pub fn get_attachments(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-) -> Result<Vec<Attachment>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.conn.prepare("SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE task_id = ?1 ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map(params![task_id], |row| {
-        Ok(Attachment {
-            id: row.get(0)?,
-            task_id: row.get(1)?,
-            filename: row.get(2)?,
-            path: row.get(3)?,
-            mime: row.get(4)?,
-            size: row.get(5)?,
-            created_at: row.get(6)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
+fn clear_waiting_internal(conn: &rusqlite::Connection, id: &str, now: i64) -> Result<Task, String> {
+    let previous: Option<String> = conn.query_row(
+        "SELECT waiting_on FROM tasks WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Task not found: {}", id))?;
     
-    let mut attachments = Vec::new();
-    for row in rows {
-        attachments.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    if previous.is_some() {
+        conn.execute(
+            "UPDATE tasks SET waiting_on = NULL, waiting_since = NULL, updated_at = ?1 WHERE id = ?2",
+            params![now, id],
+        ).map_err(|e| format!("Failed to update task: {}", e))?;
+        crate::notifications::cancel_follow_up(conn, id)
+            .map_err(|e| format!("Failed to cancel follow-up: {}", e))?;
+        log_activity(conn, id, "waiting_on_changed", previous.as_deref(), None, "waiting")?;
     }
-    
-    Ok(attachments)
+    fetch_task(conn, id)
 }
 
 #[tauri::command]
-pub fn add_attachment(
+pub fn set_waiting(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    app_handle: tauri::AppHandle,
-    task_id: String,
-    file_path: String,
-) -> Result<Attachment, String> {
-    use crate::attachments::{copy_attachment_to_storage, validate_file_type, get_mime_type};
-    use std::fs;
-    
-    // Validate file type
-    validate_file_type(&file_path)?;
-    
+    id: String,
+    waiting_on: String,
+    follow_up_days: Option<u32>,
+) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    set_waiting_internal(&db.conn, &id, &waiting_on, follow_up_days, now())
+}
+
+#[tauri::command]
+pub fn clear_waiting(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Task, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    clear_waiting_internal(&db.conn, &id, now())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentTask {
+    #[serde(flatten)]
+    pub task: Task,
+    /// When the task was completed, modified or created, depending on the feed
+    pub timestamp: i64,
+    pub seconds_ago: i64,
+}
+
+const MAX_RECENT_TASKS: i64 = 100;
+
+// Task ids bound per tag query, well under SQLite's host parameter limit
+const TAG_QUERY_CHUNK: usize = 500;
+
+// Tags for many tasks, one query per TAG_QUERY_CHUNK ids, keyed by task id (empty if the tags
+// tables don't exist)
+fn fetch_tags_for_tasks(conn: &rusqlite::Connection, task_ids: &[String]) -> Result<HashMap<String, Vec<Tag>>, String> {
+    let mut tags_by_task: HashMap<String, Vec<Tag>> = HashMap::new();
+    for chunk in task_ids.chunks(TAG_QUERY_CHUNK) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+        let query = format!(
+            "SELECT tt.task_id, t.id, t.name, t.color, t.created_at, t.usage_count, t.group_id
+             FROM tags t
+             INNER JOIN task_tags tt ON t.id = tt.tag_id
+             WHERE tt.task_id IN ({})
+             ORDER BY t.name",
+            placeholders
+        );
+        let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(chunk.iter()), |row| {
+            Ok((row.get::<_, String>(0)?, Tag {
+                id: row.get(1)?,
+                name: row.get(2)?,
+                color: row.get(3)?,
+                created_at: row.get(4)?,
+                usage_count: row.get(5)?,
+                group_id: row.get(6)?,
+            }))
+        }).map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (task_id, tag) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            tags_by_task.entry(task_id).or_default().push(tag);
+        }
+    }
+    Ok(tags_by_task)
+}
+
+// Fill in `tags` for a list of tasks with batched queries rather than one query per task
+fn attach_tags(conn: &rusqlite::Connection, tasks: &mut [Task]) -> Result<(), String> {
+    let task_ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let mut tags_by_task = fetch_tags_for_tasks(conn, &task_ids)?;
+    for task in tasks {
+        task.tags = Some(tags_by_task.remove(&task.id).unwrap_or_default());
+    }
+    Ok(())
+}
+
+// Fill in is_blocked/blocker_count for every task from one grouped query over the incomplete
+// blockers ('blocks' relationships point from task_id_1 to the blocked task_id_2)
+pub(crate) fn populate_block_status(conn: &rusqlite::Connection, tasks: &mut [Task]) -> Result<(), String> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
     
-    // Get file size before copying
-    let file_size: Option<i64> = fs::metadata(&file_path)
-        .ok()
-        .and_then(|m| m.len().try_into().ok());
-    
-    // Copy file to storage
-    let stored_path = copy_attachment_to_storage(&app_handle, &file_path, &task_id)
-        .map_err(|e| format!("Failed to copy attachment: {}", e))?;
+    let mut stmt = conn.prepare(
+        "SELECT tr.task_id_2, COUNT(DISTINCT tr.task_id_1)
+         FROM task_relationships tr
+         INNER JOIN tasks b ON b.id = tr.task_id_1
+         WHERE tr.relationship_type = 'blocks' AND b.completed_at IS NULL
+         GROUP BY tr.task_id_2"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    let mut blockers: HashMap<String, i64> = HashMap::new();
+    for row in rows {
+        let (task_id, count) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        blockers.insert(task_id, count);
+    }
     
-    // Get filename from original path
-    let filename = std::path::Path::new(&file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    for task in tasks.iter_mut() {
+        let count = blockers.get(&task.id).copied().unwrap_or(0);
+        task.is_blocked = Some(count > 0);
+        task.blocker_count = Some(count);
+    }
+    Ok(())
+}
+
+// Columns read by task_from_row, in order
+pub(crate) const TASK_COLUMNS: &str = "id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, reminder_minutes_before, notification_repeat, pinned, status, estimated_minutes, actual_minutes, recurrence_days_of_week, recurrence_end_date, recurrence_count";
+
+pub(crate) fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        due_date: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        priority: row.get(6)?,
+        completed: row.get::<_, Option<i64>>(7)?.is_some(),
+        project_id: row.get(8)?,
+        order_index: row.get(9).unwrap_or(0),
+        recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
+        recurrence_interval: row.get(12).unwrap_or(1),
+        recurrence_parent_id: row.get(13).ok(),
+        reminder_minutes_before: row.get(14).ok().flatten(),
+        notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).is_some_and(|x| x != 0),
+        tags: None,
+        is_blocked: None,
+        blocker_count: None,
+        reminder_state: None,
+        completion_note_required: None,
+        instance_count: None,
+        waiting_on: None,
+        waiting_since: None,
+        pinned: row.get::<_, i64>(16)? != 0,
+        status: Some(board_status(row.get(17)?, row.get::<_, Option<i64>>(7)?.is_some())),
+        recurrence_days_of_week: row
+            .get::<_, Option<String>>(20)?
+            .map(|days| crate::services::task_service::parse_days_of_week(Some(&days))),
+        recurrence_end_date: row.get(21)?,
+        recurrence_count: row.get(22)?,
+        estimated_minutes: row.get(18)?,
+        actual_minutes: row.get(19)?,
+    })
+}
+
+// Helper for the dashboard's recent feeds: kind is "completed", "modified" or "created"
+fn get_recent_tasks_internal(
+    conn: &rusqlite::Connection,
+    kind: &str,
+    limit: i64,
+    now: i64,
+) -> Result<Vec<RecentTask>, String> {
+    // Completing a task stamps completed_at and updated_at with the same time, so a task whose
+    // last change was its completion toggle doesn't show up as "edited"
+    let (timestamp_column, condition) = match kind {
+        "completed" => ("completed_at", "completed_at IS NOT NULL"),
+        "modified" => ("updated_at", "updated_at > created_at AND (completed_at IS NULL OR updated_at <> completed_at)"),
+        "created" => ("created_at", "1=1"),
+        _ => return Err(format!("Unknown recent tasks kind: {}", kind)),
+    };
     
-    // Get MIME type from extension
-    let mime = get_mime_type(&file_path);
+    let query = format!(
+        "SELECT {columns}, {col} FROM tasks WHERE archived = 0 AND {cond} ORDER BY {col} DESC, id LIMIT ?1",
+        columns = TASK_COLUMNS,
+        col = timestamp_column,
+        cond = condition
+    );
+    let timestamp_index = TASK_COLUMNS.split(", ").count();
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![limit.clamp(1, MAX_RECENT_TASKS)], |row| {
+        let timestamp: i64 = row.get(timestamp_index)?;
+        Ok(RecentTask {
+            task: task_from_row(row)?,
+            timestamp,
+            seconds_ago: (now - timestamp).max(0),
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
     
-    let id = uuid::Uuid::new_v4().to_string();
-    let created_at = now();
+    let mut recent = Vec::new();
+    for row in rows {
+        recent.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
     
-    db.conn.execute(
-        "INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![id.clone(), task_id, filename, stored_path, mime, file_size, created_at],
-    ).map_err(|e| format!("Failed to create attachment record: {}", e))?;
+    let task_ids: Vec<String> = recent.iter().map(|r| r.task.id.clone()).collect();
+    let mut tags_by_task = fetch_tags_for_tasks(conn, &task_ids)?;
+    for item in &mut recent {
+        item.task.tags = Some(tags_by_task.remove(&item.task.id).unwrap_or_default());
+    }
     
-    db.conn.query_row(
-        "SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Attachment {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                filename: row.get(2)?,
-                path: row.get(3)?,
-                mime: row.get(4)?,
-                size: row.get(5)?,
-                created_at: row.get(6)?,
-            })
-        },
-    ).map_err(|e| format!("Failed to fetch created attachment: {}", e))
+    Ok(recent)
 }
 
+/// Recently completed, modified or created tasks for the dashboard widgets
 #[tauri::command]
-pub fn delete_attachment(
+pub fn get_recent_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    app_handle: tauri::AppHandle,
-    id: String,
-) -> Result<(), String> {
-    use std::fs;
-    
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    kind: String,
+    limit: Option<i64>,
+) -> Result<Vec<RecentTask>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_recent_tasks_internal(&db.conn, &kind, limit.unwrap_or(10), now()).map(|value| demo.apply(value))
+}
+
+/// Everything the "Today" screen shows for one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Agenda {
+    pub date: String,
+    pub day_start: i64,
+    pub day_end: i64,
+    /// Open tasks due at a specific time that day, in time order
+    pub timeline: Vec<Task>,
+    /// Open tasks due that day without a time (due at midnight), most important first
+    pub unscheduled: Vec<Task>,
+    /// Open tasks due before the day
+    pub overdue: Vec<Task>,
+    /// Tasks completed during the day
+    pub completed: Vec<Task>,
+    pub focus_minutes: i64,
+    pub focus_sessions: i64,
+}
+
+// Unix bounds of a calendar day, in the given UTC offset (minutes east) or local time
+pub(crate) fn day_bounds(date: chrono::NaiveDate, tz_offset_minutes: Option<i32>) -> Result<(i64, i64), String> {
+    use chrono::TimeZone;
+    
+    let start = date.and_hms_opt(0, 0, 0).unwrap();
+    let end = date.succ_opt()
+        .ok_or_else(|| format!("Date out of range: {}", date))?
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let to_timestamp = |naive: chrono::NaiveDateTime| -> Result<i64, String> {
+        match tz_offset_minutes {
+            Some(minutes) => chrono::FixedOffset::east_opt(minutes * 60)
+                .ok_or_else(|| format!("Invalid timezone offset: {}", minutes))?
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.timestamp())
+                .ok_or_else(|| "Invalid date".to_string()),
+            None => chrono::Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.timestamp())
+                .ok_or_else(|| "Invalid date".to_string()),
+        }
+    };
+    Ok((to_timestamp(start)?, to_timestamp(end)?))
+}
+
+fn query_tasks(
+    conn: &rusqlite::Connection,
+    condition: &str,
+    order: &str,
+    query_params: &[&dyn rusqlite::ToSql],
+) -> Result<Vec<Task>, String> {
+    let query = format!("SELECT {} FROM tasks WHERE {} ORDER BY {}", TASK_COLUMNS, condition, order);
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(query_params, task_from_row)
+        .map_err(|e| format!("Query execution error: {}", e))?;
     
-    // Get attachment path before deleting
-    let path: Option<String> = db.conn.query_row(
-        "SELECT path FROM attachments WHERE id = ?1",
-        params![id.clone()],
-        |row| row.get(0),
-    ).ok();
+    let mut tasks = Vec::new();
+    for row in rows {
+        tasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(tasks)
+}
+
+// Helper to build a day's agenda from a single consistent read of the database
+fn get_agenda_internal(
+    conn: &rusqlite::Connection,
+    date: &str,
+    tz_offset_minutes: Option<i32>,
+) -> Result<Agenda, String> {
+    let day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let (day_start, day_end) = day_bounds(day, tz_offset_minutes)?;
+    
+    let due_today = query_tasks(
+        conn,
+        "completed_at IS NULL AND archived = 0 AND due_at >= ?1 AND due_at < ?2",
+        "due_at ASC, order_index ASC",
+        params![day_start, day_end],
+    )?;
+    let (mut unscheduled, timeline): (Vec<Task>, Vec<Task>) =
+        due_today.into_iter().partition(|t| t.due_date == Some(day_start));
+    // High priority first, then the user's manual order
+    let priority_rank = |priority: &str| match priority {
+        "high" => 0,
+        "medium" => 1,
+        _ => 2,
+    };
+    unscheduled.sort_by_key(|t| (priority_rank(&t.priority), t.order_index));
+    
+    let overdue = query_tasks(
+        conn,
+        "completed_at IS NULL AND archived = 0 AND due_at IS NOT NULL AND due_at < ?1",
+        "due_at ASC, order_index ASC",
+        params![day_start],
+    )?;
+    let completed = query_tasks(
+        conn,
+        "completed_at >= ?1 AND completed_at < ?2 AND archived = 0",
+        "completed_at DESC",
+        params![day_start, day_end],
+    )?;
+    
+    let (focus_seconds, focus_sessions): (i64, i64) = conn.query_row(
+        "SELECT COALESCE(SUM(duration_seconds), 0), COUNT(*) FROM pomodoro_sessions
+         WHERE mode = 'pomodoro' AND completed_at >= ?1 AND completed_at < ?2",
+        params![day_start, day_end],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Query error: {}", e))?;
     
-    // Delete from database
-    db.conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete attachment: {}", e))?;
+    let mut agenda = Agenda {
+        date: date.to_string(),
+        day_start,
+        day_end,
+        timeline,
+        unscheduled,
+        overdue,
+        completed,
+        focus_minutes: focus_seconds / 60,
+        focus_sessions,
+    };
     
-    // Try to delete file
-    if let Some(path) = path {
-        let app_data_dir = app_handle
-            .path_resolver()
-            .app_data_dir()
-            .ok_or_else(|| "Failed to get app data directory".to_string())?;
-        let full_path = app_data_dir.join(&path);
-        let _ = fs::remove_file(full_path); // Ignore errors if file doesn't exist
+    let task_ids: Vec<String> = [&agenda.timeline, &agenda.unscheduled, &agenda.overdue, &agenda.completed]
+        .iter()
+        .flat_map(|tasks| tasks.iter().map(|t| t.id.clone()))
+        .collect();
+    let tags_by_task = fetch_tags_for_tasks(conn, &task_ids)?;
+    for task in agenda.timeline.iter_mut()
+        .chain(agenda.unscheduled.iter_mut())
+        .chain(agenda.overdue.iter_mut())
+        .chain(agenda.completed.iter_mut())
+    {
+        task.tags = Some(tags_by_task.get(&task.id).cloned().unwrap_or_default());
     }
     
-    Ok(())
+    Ok(agenda)
 }
 
+/// A day's agenda (`date` is YYYY-MM-DD) in one call. `tz_offset_minutes` is minutes east of UTC;
+/// without it the system's local time zone is used.
 #[tauri::command]
-pub fn get_attachment(
+pub fn get_agenda(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    id: String,
-) -> Result<Attachment, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    date: String,
+    tz_offset_minutes: Option<i32>,
+) -> Result<Agenda, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    db.conn.query_row(
-        "SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Attachment {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                filename: row.get(2)?,
-                path: row.get(3)?,
-                mime: row.get(4)?,
-                size: row.get(5)?,
-                created_at: row.get(6)?,
-            })
-        },
-    ).map_err(|e| format!("Failed to fetch attachment: {}", e))
+    Ok(demo.apply(get_agenda_internal(&db.conn, &date, tz_offset_minutes)?))
 }
 
+/// Tasks completed on `date` (YYYY-MM-DD), for pasting into a journal. `tz_offset_minutes` as in
+/// get_agenda.
 #[tauri::command]
-pub fn get_attachment_path(
+pub fn get_daily_journal(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    app_handle: tauri::AppHandle,
-    id: String,
-) -> Result<String, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    date: String,
+    tz_offset_minutes: Option<i32>,
+) -> Result<journal_service::DailyJournal, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let path: String = db.conn.query_row(
-        "SELECT path FROM attachments WHERE id = ?1",
-        params![id],
-        |row| row.get(0),
-    ).map_err(|e| format!("Failed to fetch attachment path: {}", e))?;
-    
-    let app_data_dir = app_handle
-        .path_resolver()
-        .app_data_dir()
-        .ok_or_else(|| "Failed to get app data directory".to_string())?;
-    
-    let full_path = app_data_dir.join(&path);
-    
-    if !full_path.exists() {
-        return Err("Attachment file not found".to_string());
-    }
-    
-    Ok(full_path.to_string_lossy().to_string())
+    journal_service::get_daily_journal(&db.conn, &date, tz_offset_minutes).map(|value| demo.apply(value))
 }
 
 #[tauri::command]
-pub fn read_attachment_file_content(
+pub fn export_daily_journal_markdown(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    app_handle: tauri::AppHandle,
-    id: String,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    date: String,
+    tz_offset_minutes: Option<i32>,
 ) -> Result<String, String> {
-    use std::fs;
-    
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let path: String = db.conn.query_row(
-        "SELECT path FROM attachments WHERE id = ?1",
-        params![id],
-        |row| row.get(0),
-    ).map_err(|e| format!("Failed to fetch attachment path: {}", e))?;
-    
-    let app_data_dir = app_handle
-        .path_resolver()
-        .app_data_dir()
-        .ok_or_else(|| "Failed to get app data directory".to_string())?;
-    
-    let full_path = app_data_dir.join(&path);
-    
-    if !full_path.exists() {
-        return Err("Attachment file not found".to_string());
-    }
-    
-    // Read file as text (for text files)
-    fs::read_to_string(&full_path)
-        .map_err(|e| format!("Failed to read file: {}", e))
+    let journal = journal_service::get_daily_journal(&db.conn, &date, tz_offset_minutes)?;
+    Ok(journal_service::render_daily_journal_markdown(&demo.apply(journal), tz_offset_minutes))
 }
 
 #[tauri::command]
-pub fn open_attachment_file(
+pub fn get_task(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    app_handle: tauri::AppHandle,
+    demo: State<'_, crate::demo_mode::DemoMode>,
     id: String,
-) -> Result<(), String> {
-    use std::process::Command as ProcessCommand;
-    
+) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let path: String = db.conn.query_row(
-        "SELECT path FROM attachments WHERE id = ?1",
-        params![id],
-        |row| row.get(0),
-    ).map_err(|e| format!("Failed to fetch attachment path: {}", e))?;
-    
-    let app_data_dir = app_handle
-        .path_resolver()
-        .app_data_dir()
-        .ok_or_else(|| "Failed to get app data directory".to_string())?;
-    
-    let full_path = app_data_dir.join(&path);
-    
-    if !full_path.exists() {
-        return Err("Attachment file not found".to_string());
-    }
-    
-    // Open file with system default application
-    #[cfg(target_os = "windows")]
-    {
-        ProcessCommand::new("cmd")
-            .args(["/C", "start", "", &full_path.to_string_lossy()])
-            .spawn()
-            .map_err(|e| format!("Failed to open file: {}", e))?;
-    }
-    
-    #[cfg(target_os = "macos")]
-    {
-        ProcessCommand::new("open")
-            .arg(&full_path)
-            .spawn()
-            .map_err(|e| format!("Failed to open file: {}", e))?;
-    }
-    
-    #[cfg(target_os = "linux")]
-    {
-        ProcessCommand::new("xdg-open")
-            .arg(&full_path)
-            .spawn()
-            .map_err(|e| format!("Failed to open file: {}", e))?;
-    }
-    
-    Ok(())
+    let mut task = fetch_task(&db.conn, &id)?;
+    populate_block_status(&db.conn, std::slice::from_mut(&mut task))?;
+    Ok(demo.apply(task))
 }
 
-// Settings commands
 #[tauri::command]
-pub fn get_settings(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<HashMap<String, String>, String> {
+pub fn create_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    recent_projects: State<'_, crate::services::quick_add_service::RecentProjects>,
+    input: CreateTaskInput,
+) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.conn.prepare("SELECT key, value FROM settings").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
-        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut settings = HashMap::new();
-    for row in rows {
-        let (key, value) = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        settings.insert(key, value);
+    let task = create_task_internal(&db, input)?;
+    if let Some(project_id) = &task.project_id {
+        recent_projects.record(&db.conn, project_id)?;
     }
-    
-    Ok(settings)
+    Ok(task)
 }
 
-#[tauri::command]
-pub fn update_settings(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    key: String,
-    value: String,
-) -> Result<(), String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    db.conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
-        params![key, value],
-    ).map_err(|e| format!("Failed to update setting: {}", e))?;
-    
+fn validate_minutes(minutes: Option<i32>) -> Result<(), String> {
+    if minutes.is_some_and(|minutes| minutes < 0) {
+        return Err("Minutes can't be negative".to_string());
+    }
     Ok(())
 }
 
-// Backup and restore commands
-#[tauri::command]
-pub fn create_backup(app_handle: tauri::AppHandle) -> Result<String, String> {
-    use std::fs;
-    
-    let app_data_dir = app_handle
-        .path_resolver()
-        .app_data_dir()
-        .ok_or_else(|| "Failed to get app data directory".to_string())?;
-    
-    let db_path = app_data_dir.join("todo.db");
-    let backups_dir = app_data_dir.join("backups");
-    fs::create_dir_all(&backups_dir).map_err(|e| format!("Failed to create backups directory: {}", e))?;
-    
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let backup_filename = format!("todo_backup_{}.db", timestamp);
-    let backup_path = backups_dir.join(&backup_filename);
-    
-    fs::copy(&db_path, &backup_path).map_err(|e| format!("Failed to create backup: {}", e))?;
-    
-    Ok(backup_path.to_string_lossy().to_string())
+// Helper function to insert a task and schedule its reminder (assumes lock is already held). The
+// insert, the rules it triggers and the reminder commit together.
+fn create_task_internal(db: &DbConnection, input: CreateTaskInput) -> Result<Task, String> {
+    let tx = db.conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let task = create_task_rows(db, input)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(task)
 }
 
-#[tauri::command]
-pub fn restore_backup(
-    app_handle: tauri::AppHandle,
-    backup_path: String,
-) -> Result<(), String> {
-    use std::fs;
+// Helper function for create_task_internal, for callers that already hold a transaction
+fn create_task_rows(db: &DbConnection, input: CreateTaskInput) -> Result<Task, String> {
+    validate_reminder_style(input.reminder_sound.as_deref(), input.reminder_urgency.as_deref())?;
+    validate_description(&db.conn, input.description.as_deref())?;
+    let days_of_week = match &input.recurrence_days_of_week {
+        Some(days) => crate::services::task_service::format_days_of_week(days)?,
+        None => None,
+    };
+    if input.recurrence_count.is_some_and(|count| count < 1) {
+        return Err("Recurrence count must be at least 1".to_string());
+    }
+    validate_minutes(input.estimated_minutes)?;
+    validate_minutes(input.actual_minutes)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    let completion_note_required = match (input.completion_note_required, &input.project_id) {
+        (Some(required), _) => required,
+        (None, Some(project_id)) => project_requires_completion_note(&db.conn, project_id)?,
+        (None, None) => false,
+    };
     
-    let app_data_dir = app_handle
-        .path_resolver()
-        .app_data_dir()
-        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    db.conn.execute(
+        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, reminder_minutes_before, notification_repeat, reminder_sound, reminder_urgency, completion_note_required, recurrence_days_of_week, recurrence_end_date, recurrence_count, estimated_minutes, actual_minutes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+        params![
+            id.clone(),
+            input.title,
+            input.description,
+            input.due_date,
+            now,
+            now,
+            input.priority,
+            None::<i64>,
+            input.project_id,
+            next_order_index(&db.conn, input.project_id.as_deref())?,
+            None::<String>,
+            input.recurrence_type.unwrap_or_else(|| "none".to_string()),
+            input.recurrence_interval.unwrap_or(1),
+            None::<String>,
+            input.reminder_minutes_before,
+            if input.notification_repeat.unwrap_or(false) { 1 } else { 0 },
+            input.reminder_sound.filter(|sound| !sound.is_empty()),
+            input.reminder_urgency.unwrap_or_else(|| "normal".to_string()),
+            completion_note_required,
+            days_of_week,
+            input.recurrence_end_date,
+            input.recurrence_count,
+            input.estimated_minutes,
+            input.actual_minutes
+        ],
+    ).map_err(|e| format!("Failed to create task: {}", e))?;
     
-    let db_path = app_data_dir.join("todo.db");
-    let backup_file = std::path::Path::new(&backup_path);
+    let rules = rules_service::get_rules(&db.conn, true)?;
+    let outcome = rules_service::apply_rules(&db.conn, &id, &rules, "rule")?;
+    let reminder_minutes_before = if outcome.reminder_changed {
+        fetch_task(&db.conn, &id)?.reminder_minutes_before
+    } else {
+        input.reminder_minutes_before
+    };
     
-    if !backup_file.exists() {
-        return Err("Backup file does not exist".to_string());
+    // Schedule notification if reminder is set
+    if let Some(reminder_minutes) = reminder_minutes_before {
+        let _ = crate::notifications::schedule_notification(db, &id, Some(reminder_minutes));
     }
     
-    // Create a backup of current DB before restoring
-    let current_backup = db_path.with_extension("db.bak");
-    let _ = fs::copy(&db_path, &current_backup);
-    
-    // Copy backup file to DB location
-    fs::copy(backup_file, &db_path).map_err(|e| format!("Failed to restore backup: {}", e))?;
-    
-    Ok(())
+    fetch_task(&db.conn, &id)
 }
 
-// Export and import commands
-#[tauri::command]
-pub fn export_data(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    app_handle: tauri::AppHandle,
-) -> Result<String, String> {
-    use std::fs;
-    use std::io::Write;
-    
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+/// Reminder settings for create_task_full; they take the place of the ones in the task input
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReminderSpec {
+    pub minutes_before: i32,
+    #[serde(default)]
+    pub repeat: Option<bool>,
+    #[serde(default)]
+    pub sound: Option<String>,
+    #[serde(default)]
+    pub urgency: Option<String>,
+}
+
+/// A file to attach: either a `path` on disk, or `data_base64` content named by `filename`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentPayload {
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub data_base64: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTaskFullInput {
+    #[serde(flatten)]
+    pub task: CreateTaskInput,
+    /// Subtask titles, in order
+    #[serde(default)]
+    pub subtasks: Vec<String>,
+    /// Tag names; missing tags are created
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub reminder: Option<ReminderSpec>,
+    #[serde(default)]
+    pub attachment: Option<AttachmentPayload>,
+}
+
+/// A task with everything that hangs off it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskDetails {
+    #[serde(flatten)]
+    pub task: Task,
+    pub subtasks: Vec<Subtask>,
+    pub attachments: Vec<Attachment>,
+}
+
+// Store an attachment payload for `task_id` and insert its row. Returns the stored file's
+// absolute path so the caller can remove it if the surrounding transaction fails.
+fn store_attachment_payload(
+    conn: &rusqlite::Connection,
+    app_data_dir: &std::path::Path,
+    task_id: &str,
+    payload: &AttachmentPayload,
+) -> Result<std::path::PathBuf, String> {
+    use base64::Engine;
+    use crate::attachments::{copy_into_storage, get_mime_type, validate_file_type, write_into_storage, AttachmentLimits};
+    
+    let max_bytes = AttachmentLimits::default().max_file_bytes;
+    let too_large = || format!("Attachment is larger than {} MB", max_bytes / (1024 * 1024));
+    let (filename, stored_path, size) = match (&payload.path, &payload.data_base64) {
+        (Some(path), None) => {
+            validate_file_type(path)?;
+            let size = std::fs::metadata(path)
+                .map_err(|_| format!("Attachment source file not found: {}", path))?
+                .len();
+            if size > max_bytes {
+                return Err(too_large());
+            }
+            let filename = std::path::Path::new(path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+            (filename, copy_into_storage(app_data_dir, path, task_id)?, size)
+        }
+        (None, Some(data)) => {
+            let filename = payload.filename.clone().ok_or("An attachment with data needs a filename")?;
+            validate_file_type(&filename)?;
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| format!("Invalid attachment data: {}", e))?;
+            if bytes.len() as u64 > max_bytes {
+                return Err(too_large());
+            }
+            (filename.clone(), write_into_storage(app_data_dir, &filename, &bytes, task_id)?, bytes.len() as u64)
+        }
+        _ => return Err("An attachment needs either a path or data".to_string()),
+    };
     
-    // Get all tasks
-    let mut tasks = Vec::new();
-    let mut stmt = db.conn.prepare("SELECT id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id FROM tasks ORDER BY order_index, created_at").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: None, // This query doesn't select these fields
-            notification_repeat: false,
-            tags: None,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    for row in rows {
-        tasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    let full_path = app_data_dir.join(&stored_path);
+    if let Err(e) = conn.execute(
+        "INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![uuid::Uuid::new_v4().to_string(), task_id, filename, stored_path, get_mime_type(&filename), size as i64, now()],
+    ) {
+        let _ = std::fs::remove_file(&full_path);
+        return Err(crate::storage::from_sqlite("create the attachment record", &e).to_string());
     }
-    
-    // Get all projects
-    let mut projects = Vec::new();
-    let mut stmt = db.conn.prepare("SELECT id, name, color, created_at, updated_at FROM projects ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Project {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            color: row.get(2)?,
-            created_at: row.get(3)?,
-            updated_at: row.get(4)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    for row in rows {
-        projects.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    Ok(full_path)
+}
+
+// Create a task with its subtasks, tags, reminder and attachment in one transaction. The
+// attachment file is written before commit and removed again if anything fails.
+fn create_task_full_internal(
+    db: &DbConnection,
+    app_data_dir: &std::path::Path,
+    input: CreateTaskFullInput,
+) -> Result<TaskDetails, String> {
+    let CreateTaskFullInput { mut task, subtasks, tags, reminder, attachment } = input;
+    if task.title.trim().is_empty() {
+        return Err("Task title cannot be empty".to_string());
+    }
+    if let Some(reminder) = reminder {
+        task.reminder_minutes_before = Some(reminder.minutes_before);
+        task.notification_repeat = reminder.repeat;
+        task.reminder_sound = reminder.sound;
+        task.reminder_urgency = reminder.urgency;
     }
+    let subtasks: Vec<&str> = subtasks.iter().map(|title| title.trim()).filter(|title| !title.is_empty()).collect();
     
-    // Get all settings
-    let mut settings = HashMap::new();
-    let mut stmt = db.conn.prepare("SELECT key, value FROM settings").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
-        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    for row in rows {
-        let (key, value) = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        settings.insert(key, value);
+    let tx = db.conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut stored_file = None;
+    let result = (|| {
+        let created = create_task_rows(db, task)?;
+        if let Some(payload) = &attachment {
+            stored_file = Some(store_attachment_payload(&tx, app_data_dir, &created.id, payload)?);
+        }
+        append_subtasks(&tx, &created.id, &subtasks)?;
+        for tag in &tags {
+            attach_tag_by_name(&tx, &created.id, tag)?;
+        }
+        fetch_task_details(&tx, &created.id)
+    })()
+    .and_then(|details| {
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(details)
+    });
+    
+    if result.is_err() {
+        if let Some(path) = stored_file {
+            let _ = std::fs::remove_file(path);
+        }
     }
+    result
+}
+
+// Helper function to fetch a task with its subtasks and attachments (assumes lock is already held)
+fn fetch_task_details(conn: &rusqlite::Connection, id: &str) -> Result<TaskDetails, String> {
+    let task = fetch_task(conn, id)?;
     
-    // Get all subtasks
-    let mut all_subtasks = Vec::new();
-    let mut stmt = db.conn.prepare("SELECT id, task_id, title, completed FROM subtasks ORDER BY id").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
+    let mut stmt = conn.prepare("SELECT id, task_id, title, completed FROM subtasks WHERE task_id = ?1 ORDER BY order_index")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let subtasks = stmt.query_map(params![id], |row| {
         Ok(Subtask {
             id: row.get(0)?,
             task_id: row.get(1)?,
             title: row.get(2)?,
             completed: row.get::<_, i32>(3)? != 0,
         })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    for row in rows {
-        all_subtasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
-    }
+    }).map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
     
-    // Get all attachments
-    let mut all_attachments = Vec::new();
-    let mut stmt = db.conn.prepare("SELECT id, task_id, filename, path, mime, size, created_at FROM attachments ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
+    let mut stmt = conn.prepare("SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE task_id = ?1 ORDER BY created_at")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let attachments = stmt.query_map(params![id], |row| {
         Ok(Attachment {
             id: row.get(0)?,
             task_id: row.get(1)?,
@@ -1283,466 +1859,385 @@ pub fn export_data(
             size: row.get(5)?,
             created_at: row.get(6)?,
         })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    for row in rows {
-        all_attachments.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
-    }
-    
-    let export_data = serde_json::json!({
-        "tasks": tasks,
-        "projects": projects,
-        "subtasks": all_subtasks,
-        "attachments": all_attachments,
-        "settings": settings,
-        "exported_at": now(),
-    });
+    }).map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
     
+    Ok(TaskDetails { task, subtasks, attachments })
+}
+
+/// Create a task together with its subtasks, tags, reminder and an attachment, all or nothing.
+/// Backs the quick-add dialog.
+#[tauri::command]
+pub fn create_task_full(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    recent_projects: State<'_, crate::services::quick_add_service::RecentProjects>,
+    input: CreateTaskFullInput,
+) -> Result<TaskDetails, String> {
     let app_data_dir = app_handle
         .path_resolver()
         .app_data_dir()
-        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+        .ok_or("Failed to get app data directory")?;
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let details = create_task_full_internal(&db, &app_data_dir, input)?;
+    if let Some(project_id) = &details.task.project_id {
+        recent_projects.record(&db.conn, project_id)?;
+    }
+    Ok(details)
+}
+
+// Helper function to file an inbox task into its best matching project, logged with source "auto_file"
+fn auto_file_task(conn: &rusqlite::Connection, task_id: &str, title: &str) -> Result<Option<String>, String> {
+    let setting = |key: &str| -> Option<String> {
+        conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0)).ok()
+    };
+    if setting("auto_file_inbox").as_deref() != Some("true") {
+        return Ok(None);
+    }
+    let threshold = setting("auto_file_inbox_threshold")
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(project_suggestion_service::DEFAULT_AUTO_FILE_THRESHOLD);
     
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let export_filename = format!("todo_export_{}.json", timestamp);
-    let export_path = app_data_dir.join(&export_filename);
+    let top = match project_suggestion_service::suggest_project_for_task(conn, title, None)?.into_iter().next() {
+        Some(top) if top.confidence >= threshold => top,
+        _ => return Ok(None),
+    };
     
-    let json_str = serde_json::to_string_pretty(&export_data)
-        .map_err(|e| format!("Failed to serialize data: {}", e))?;
-    
-    let mut file = fs::File::create(&export_path)
-        .map_err(|e| format!("Failed to create export file: {}", e))?;
-    file.write_all(json_str.as_bytes())
-        .map_err(|e| format!("Failed to write export file: {}", e))?;
+    conn.execute(
+        "UPDATE tasks SET project_id = ?1, updated_at = ?2 WHERE id = ?3",
+        params![top.project_id, now(), task_id],
+    ).map_err(|e| format!("Failed to file task into project: {}", e))?;
+    log_activity(conn, task_id, "project_changed", None, Some(&top.project_id), "auto_file")?;
     
-    Ok(export_path.to_string_lossy().to_string())
+    Ok(Some(top.project_id))
 }
 
+// Create a single task from a quick-add line such as "call dentist tomorrow #health !high"
 #[tauri::command]
-pub fn import_data(
+pub fn quick_add(
+    app_handle: tauri::AppHandle,
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    file_path: String,
-) -> Result<ImportSummary, String> {
-    use std::fs;
-    
+    recent_projects: State<'_, crate::services::quick_add_service::RecentProjects>,
+    text: String,
+    project_id: Option<String>,
+) -> Result<Task, String> {
+    use crate::services::quick_add_service;
+    use tauri::Manager;
+
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let file_contents = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read import file: {}", e))?;
-    
-    let data: serde_json::Value = serde_json::from_str(&file_contents)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    
-    let mut summary = ImportSummary {
-        tasks_added: 0,
-        tasks_updated: 0,
-        projects_added: 0,
-        projects_updated: 0,
-    };
-    
-    let tx = db.conn.unchecked_transaction()
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
-    
-    // Import projects
-    if let Some(projects) = data.get("projects").and_then(|p| p.as_array()) {
-        for project_json in projects {
-            if let Ok(project) = serde_json::from_value::<Project>(project_json.clone()) {
-                let exists: bool = tx.query_row(
-                    "SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)",
-                    params![project.id],
-                    |row| row.get(0),
-                ).unwrap_or(false);
-                
-                if exists {
-                    tx.execute(
-                        "UPDATE projects SET name = ?1, color = ?2, updated_at = ?3 WHERE id = ?4",
-                        params![project.name, project.color, now(), project.id],
-                    ).ok();
-                    summary.projects_updated += 1;
-                } else {
-                    tx.execute(
-                        "INSERT INTO projects (id, name, color, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-                        params![project.id, project.name, project.color, project.created_at, project.updated_at],
-                    ).ok();
-                    summary.projects_added += 1;
-                }
-            }
-        }
-    }
-    
-    // Import tasks
-    if let Some(tasks) = data.get("tasks").and_then(|t| t.as_array()) {
-        for task_json in tasks {
-            if let Ok(task) = serde_json::from_value::<Task>(task_json.clone()) {
-                let exists: bool = tx.query_row(
-                    "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
-                    params![task.id],
-                    |row| row.get(0),
-                ).unwrap_or(false);
-                
-                if exists {
-                    tx.execute(
-                        "UPDATE tasks SET title = ?1, description = ?2, due_at = ?3, priority = ?4, completed_at = ?5, project_id = ?6, order_index = ?7, recurrence_type = ?8, recurrence_interval = ?9, updated_at = ?10 WHERE id = ?11",
-                        params![
-                            task.title,
-                            task.description,
-                            task.due_date,
-                            task.priority,
-                            if task.completed { Some(now()) } else { None::<i64> },
-                            task.project_id,
-                            task.order_index,
-                            task.recurrence_type,
-                            task.recurrence_interval,
-                            now(),
-                            task.id
-                        ],
-                    ).ok();
-                    summary.tasks_updated += 1;
-                } else {
-                    tx.execute(
-                        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-                        params![
-                            task.id,
-                            task.title,
-                            task.description,
-                            task.due_date,
-                            task.created_at,
-                            task.updated_at,
-                            task.priority,
-                            if task.completed { Some(task.updated_at) } else { None::<i64> },
-                            task.project_id,
-                            task.order_index,
-                            None::<String>,
-                            task.recurrence_type,
-                            task.recurrence_interval,
-                            task.recurrence_parent_id
-                        ],
-                    ).ok();
-                    summary.tasks_added += 1;
-                }
-            }
-        }
+
+    let parsed = quick_add_service::parse_quick_add(text.trim(), chrono::Local::now());
+    if parsed.title.is_empty() {
+        return Err("Task title cannot be empty".to_string());
     }
-    
-    // Import subtasks
-    if let Some(subtasks) = data.get("subtasks").and_then(|s| s.as_array()) {
-        for subtask_json in subtasks {
-            if let Ok(subtask) = serde_json::from_value::<Subtask>(subtask_json.clone()) {
-                let _ = tx.execute(
-                    "INSERT OR REPLACE INTO subtasks (id, task_id, title, completed) VALUES (?1, ?2, ?3, ?4)",
-                    params![subtask.id, subtask.task_id, subtask.title, if subtask.completed { 1 } else { 0 }],
-                );
-            }
-        }
+
+    // An explicitly chosen project wins over an @project token in the text
+    let project_id = match (project_id, &parsed.project) {
+        (Some(id), _) => Some(id),
+        (None, Some(token)) => Some(
+            quick_add_service::resolve_project(&db.conn, &recent_projects.ids(), token)
+                .map_err(|e| e.to_error_string())?
+                .id,
+        ),
+        (None, None) => None,
+    };
+
+    let task = create_task_internal(&db, CreateTaskInput {
+        title: parsed.title,
+        description: None,
+        due_date: parsed.due_date,
+        priority: parsed.priority.unwrap_or_else(|| "medium".to_string()),
+        project_id,
+        recurrence_type: None,
+        recurrence_interval: None,
+        reminder_minutes_before: None,
+        notification_repeat: None,
+        reminder_sound: None,
+        reminder_urgency: None,
+        completion_note_required: None,
+        recurrence_days_of_week: None,
+        recurrence_end_date: None,
+        recurrence_count: None,
+        estimated_minutes: None,
+        actual_minutes: None,
+    })?;
+
+    for tag in &parsed.tags {
+        attach_tag_by_name(&db.conn, &task.id, tag)?;
     }
-    
-    // Import settings
-    if let Some(settings) = data.get("settings").and_then(|s| s.as_object()) {
-        for (key, value) in settings {
-            if let Some(value_str) = value.as_str() {
-                let _ = tx.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
-                    params![key, value_str],
-                );
-            }
+
+    match &task.project_id {
+        Some(project_id) => recent_projects.record(&db.conn, project_id)?,
+        None => {
+            auto_file_task(&db.conn, &task.id, &task.title)?;
         }
     }
-    
-    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
-    
-    Ok(summary)
-}
 
-// Notification command
-#[tauri::command]
-pub fn show_notification(title: String, body: String) -> Result<(), String> {
-    crate::notifications::show_notification(&title, &body)
-        .map_err(|e| format!("Failed to show notification: {}", e))
+    let task = fetch_task(&db.conn, &task.id)?;
+
+    // Let the main window refresh its task list when the task came from the quick-add window
+    app_handle.emit_all("task-created", task.clone()).ok();
+
+    Ok(task)
 }
 
-// Auto-start commands using Windows Registry (manual implementation for Tauri 1.8)
-#[cfg(target_os = "windows")]
-#[tauri::command]
-pub fn get_autostart_enabled(app_handle: tauri::AppHandle) -> Result<bool, String> {
-    use winreg::enums::HKEY_CURRENT_USER;
-    use winreg::RegKey;
-    
-    let app_name = app_handle.package_info().name.clone();
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    
-    // Try to open the Run key, return false if it doesn't exist
-    let run_key = match hkcu.open_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Run") {
-        Ok(key) => key,
-        Err(_) => return Ok(false),
-    };
-    
-    // Check if the app name exists in the registry
-    match run_key.get_value::<String, _>(&app_name) {
-        Ok(_) => Ok(true),
-        Err(_) => Ok(false),
+// Recently used projects that still exist, newest first
+fn get_recent_projects_internal(
+    conn: &rusqlite::Connection,
+    recent_ids: &[String],
+    limit: usize,
+) -> Result<Vec<Project>, String> {
+    let mut projects = Vec::new();
+    for id in recent_ids {
+        if projects.len() >= limit {
+            break;
+        }
+        let project = conn.query_row(
+            "SELECT id, name, color, created_at, updated_at FROM projects WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(Project {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    completion_note_required: None,
+                })
+            },
+        ).ok();
+        projects.extend(project);
     }
+    Ok(projects)
 }
 
-#[cfg(target_os = "windows")]
+/// Recently used projects offered as one-keystroke targets in the quick-add window
 #[tauri::command]
-pub fn set_autostart_enabled(
-    app_handle: tauri::AppHandle,
-    enabled: bool,
-) -> Result<(), String> {
-    use winreg::enums::HKEY_CURRENT_USER;
-    use winreg::RegKey;
-    use std::env;
-    
-    let app_name = app_handle.package_info().name.clone();
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let (run_key, _) = hkcu.create_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Run")
-        .map_err(|e| format!("Failed to create/open registry key: {}", e))?;
-    
-    if enabled {
-        // Get the current executable path
-        let exe_path = env::current_exe()
-            .map_err(|e| format!("Failed to get executable path: {}", e))?;
-        let exe_path_str = exe_path.to_string_lossy().to_string();
-        
-        // Wrap path in quotes if it contains spaces (Windows requirement)
-        let registry_value = if exe_path_str.contains(' ') {
-            format!("\"{}\"", exe_path_str)
-        } else {
-            exe_path_str
-        };
-        
-        run_key.set_value(&app_name, &registry_value)
-            .map_err(|e| format!("Failed to set registry value: {}", e))?;
-    } else {
-        // Try to delete the value, but don't error if it doesn't exist
-        let _ = run_key.delete_value(&app_name);
-    }
-    
-    Ok(())
+pub fn get_recent_projects(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    recent_projects: State<'_, crate::services::quick_add_service::RecentProjects>,
+    limit: Option<usize>,
+) -> Result<Vec<Project>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_recent_projects_internal(&db.conn, &recent_projects.ids(), limit.unwrap_or(5)).map(|value| demo.apply(value))
 }
 
-// Fallback for non-Windows platforms
-#[cfg(not(target_os = "windows"))]
 #[tauri::command]
-pub fn get_autostart_enabled(_app_handle: tauri::AppHandle) -> Result<bool, String> {
-    Ok(false)
+pub fn close_quick_add_window(app_handle: tauri::AppHandle) -> Result<(), String> {
+    crate::window_state::close_quick_add_window(&app_handle)
 }
 
-#[cfg(not(target_os = "windows"))]
-#[tauri::command]
-pub fn set_autostart_enabled(
-    _app_handle: tauri::AppHandle,
-    _enabled: bool,
-) -> Result<(), String> {
-    Err("Autostart is only supported on Windows in this version".to_string())
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTasksFromTextResult {
+    pub created: Vec<Task>,
+    pub skipped_lines: Vec<usize>, // 1-based line numbers that were blank or empty after stripping markers
 }
 
-// Notification commands
-#[tauri::command]
-pub fn snooze_notification(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    notification_id: String,
-    minutes: i32,
-) -> Result<(), String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    crate::notifications::snooze_notification(&db, &notification_id, minutes)
-        .map_err(|e| format!("Failed to snooze notification: {}", e))
+// Helper function to attach a tag to a task by name, creating the tag if needed
+pub(crate) fn attach_tag_by_name(conn: &rusqlite::Connection, task_id: &str, name: &str) -> Result<(), String> {
+    let normalized_name = name.trim().to_lowercase();
+    if normalized_name.is_empty() {
+        return Ok(());
+    }
+
+    let now = now();
+    let existing: Option<String> = conn.query_row(
+        "SELECT id FROM tags WHERE name = ?1",
+        params![normalized_name],
+        |row| row.get(0),
+    ).ok();
+
+    let tag_id = match existing {
+        Some(id) => id,
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO tags (id, name, color, created_at, usage_count) VALUES (?1, ?2, ?3, ?4, 0)",
+                params![id.clone(), normalized_name, None::<String>, now],
+            ).map_err(|e| format!("Failed to create tag: {}", e))?;
+            id
+        }
+    };
+
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO task_tags (id, task_id, tag_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![uuid::Uuid::new_v4().to_string(), task_id, tag_id.clone(), now],
+    ).map_err(|e| format!("Failed to add tag to task: {}", e))?;
+
+    if inserted > 0 {
+        conn.execute(
+            "UPDATE tags SET usage_count = usage_count + 1 WHERE id = ?1",
+            params![tag_id],
+        ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+    }
+
+    Ok(())
 }
 
-// Statistics commands
+// Create one task per line of pasted text (e.g. a markdown list)
 #[tauri::command]
-pub fn get_completion_stats(
+pub fn create_tasks_from_text(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    days: i32,
-) -> Result<Vec<stats_service::CompletionStats>, String> {
+    text: String,
+    project_id: Option<String>,
+    default_priority: Option<String>,
+) -> Result<CreateTasksFromTextResult, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    stats_service::get_completion_stats(&db.conn, days)
+    create_tasks_from_text_internal(&db.conn, &text, project_id, default_priority, chrono::Local::now())
 }
 
-#[tauri::command]
-pub fn get_priority_distribution(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<Vec<stats_service::PriorityDistribution>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    stats_service::get_priority_distribution(&db.conn)
-}
+// Helper function to create the tasks of a pasted list (assumes lock is already held)
+fn create_tasks_from_text_internal(
+    conn: &rusqlite::Connection,
+    text: &str,
+    project_id: Option<String>,
+    default_priority: Option<String>,
+    local_now: chrono::DateTime<chrono::Local>,
+) -> Result<CreateTasksFromTextResult, String> {
+    use crate::services::quick_add_service;
 
-#[tauri::command]
-pub fn get_project_stats(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<Vec<stats_service::ProjectStats>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    stats_service::get_project_stats(&db.conn)
-}
+    let default_priority = default_priority.unwrap_or_else(|| "medium".to_string());
+    let now = now();
 
-#[tauri::command]
-pub fn get_productivity_trend(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    start_date: i64,
-    end_date: i64,
-) -> Result<Vec<stats_service::ProductivityTrend>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    stats_service::get_productivity_trend(&db.conn, start_date, end_date)
-}
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
 
-#[tauri::command]
-pub fn get_most_productive_day(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<Option<stats_service::MostProductiveDay>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    stats_service::get_most_productive_day(&db.conn)
-}
+    // Append after the existing tasks of the target project
+    let mut order_index: i32 = tx.query_row(
+        "SELECT COALESCE(MAX(order_index), -1) + 1 FROM tasks WHERE project_id IS ?1",
+        params![project_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))?;
 
-#[tauri::command]
-pub fn get_average_completion_time(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<f64, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    stats_service::get_average_completion_time(&db.conn)
-}
+    let mut created_ids = Vec::new();
+    let mut skipped_lines = Vec::new();
 
-// Template data structures
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Template {
-    pub id: String,
-    pub name: String,
-    pub title: String,
-    pub description: Option<String>,
-    pub priority: String,
-    pub project_id: Option<String>,
-    pub recurrence_type: Option<String>,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+    for (index, line) in text.lines().enumerate() {
+        // Blank lines come out empty here too, so they are reported as skipped
+        let stripped = quick_add_service::strip_list_marker(line);
+        let parsed = quick_add_service::parse_quick_add(stripped, local_now);
+        if parsed.title.is_empty() {
+            skipped_lines.push(index + 1);
+            continue;
+        }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateTemplateInput {
-    pub name: String,
-    pub title: String,
-    pub description: Option<String>,
-    pub priority: String,
-    pub project_id: Option<String>,
-    pub recurrence_type: Option<String>,
-}
+        let id = uuid::Uuid::new_v4().to_string();
+        tx.execute(
+            "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                id.clone(),
+                parsed.title,
+                None::<String>,
+                parsed.due_date,
+                now,
+                now,
+                parsed.priority.unwrap_or_else(|| default_priority.clone()),
+                None::<i64>,
+                project_id,
+                order_index,
+                None::<String>,
+                "none",
+                1,
+                None::<String>
+            ],
+        ).map_err(|e| format!("Failed to create task: {}", e))?;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateTemplateInput {
-    pub name: Option<String>,
-    pub title: Option<String>,
-    pub description: Option<String>,
-    pub priority: Option<String>,
-    pub project_id: Option<String>,
-    pub recurrence_type: Option<String>,
+        for tag in &parsed.tags {
+            attach_tag_by_name(&tx, &id, tag)?;
+        }
+
+        order_index += 1;
+        created_ids.push(id);
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    let mut created = Vec::new();
+    for id in created_ids {
+        created.push(fetch_task(conn, &id)?);
+    }
+
+    Ok(CreateTasksFromTextResult { created, skipped_lines })
 }
 
-// Template commands
-#[tauri::command]
-pub fn create_template(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    input: CreateTemplateInput,
-) -> Result<Template, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    db.conn.execute(
-        "INSERT INTO task_templates (id, name, title, description, priority, project_id, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![
-            id.clone(),
-            input.name,
-            input.title,
-            input.description,
-            input.priority,
-            input.project_id,
-            now,
-            now,
-        ],
-    ).map_err(|e| format!("Failed to create template: {}", e))?;
-    
-    db.conn.query_row(
-        "SELECT id, name, title, description, priority, project_id, created_at, updated_at FROM task_templates WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Template {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                title: row.get(2)?,
-                description: row.get(3)?,
-                priority: row.get(4)?,
-                project_id: row.get(5)?,
-                recurrence_type: None,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        },
-    ).map_err(|e| format!("Failed to fetch created template: {}", e))
+/// Returned (serialized to JSON) by update_task, update_project and update_template when the
+/// input's `expected_updated_at` no longer matches, so the UI can offer to merge with `current`
+/// instead of overwriting an edit made in another window
+#[derive(Debug, Clone, Serialize)]
+pub struct EditConflict<T: Serialize> {
+    pub kind: String, // always "conflict"
+    pub message: String,
+    pub current: T,
 }
 
-#[tauri::command]
-pub fn get_templates(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Template>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.conn.prepare("SELECT id, name, title, description, priority, project_id, created_at, updated_at FROM task_templates ORDER BY created_at DESC").map_err(|e| format!("Query error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Template {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            title: row.get(2)?,
-            description: row.get(3)?,
-            priority: row.get(4)?,
-            project_id: row.get(5)?,
-            recurrence_type: None,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut templates = Vec::new();
-    for row in rows {
-        templates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+/// Error kind of a serialized EditConflict
+pub const EDIT_CONFLICT_KIND: &str = "conflict";
+
+// Update commands set updated_at with this, so it moves forward even for two edits within the
+// same second and expected_updated_at can tell them apart
+pub(crate) const BUMP_UPDATED_AT: &str = "updated_at = MAX(?, updated_at + 1)";
+
+// Helper function to refuse an edit based on an outdated copy of a row; no-op without `expected_updated_at`
+fn ensure_not_stale<T: Serialize>(
+    conn: &rusqlite::Connection,
+    table: &str,
+    id: &str,
+    expected_updated_at: Option<i64>,
+    fetch_current: impl FnOnce() -> Result<T, String>,
+) -> Result<(), String> {
+    let Some(expected_updated_at) = expected_updated_at else {
+        return Ok(());
+    };
+    let updated_at: i64 = conn.query_row(
+        &format!("SELECT updated_at FROM {} WHERE id = ?1", table),
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Not found: {}", e))?;
+    if updated_at == expected_updated_at {
+        return Ok(());
     }
     
-    Ok(templates)
+    let conflict = EditConflict {
+        kind: EDIT_CONFLICT_KIND.to_string(),
+        message: "This was changed elsewhere since you opened it".to_string(),
+        current: fetch_current()?,
+    };
+    Err(serde_json::to_string(&conflict).unwrap_or(conflict.message))
 }
 
 #[tauri::command]
-pub fn get_template(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<Template, String> {
+pub fn update_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    input: UpdateTaskInput,
+) -> Result<Task, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    db.conn.query_row(
-        "SELECT id, name, title, description, priority, project_id, created_at, updated_at FROM task_templates WHERE id = ?1",
-        params![id],
-        |row| {
-            Ok(Template {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                title: row.get(2)?,
-                description: row.get(3)?,
-                priority: row.get(4)?,
-                project_id: row.get(5)?,
-                recurrence_type: None,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        },
-    ).map_err(|e| format!("Template not found: {}", e))
+    update_task_internal(&db.conn, &id, input)
 }
 
-#[tauri::command]
-pub fn update_template(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    id: String,
-    input: UpdateTemplateInput,
-) -> Result<Template, String> {
+fn update_task_internal(conn: &rusqlite::Connection, id: &str, input: UpdateTaskInput) -> Result<Task, String> {
+    update_task_from(conn, id, input, "update", "user")
+}
+
+// Helper function for edits made outside the task editor (the tray), naming their source in
+// due_date_history and in the activity log
+pub(crate) fn update_task_from(
+    conn: &rusqlite::Connection,
+    id: &str,
+    input: UpdateTaskInput,
+    due_date_source: &str,
+    activity_source: &str,
+) -> Result<Task, String> {
+    ensure_not_stale(conn, "tasks", id, input.expected_updated_at, || fetch_task(conn, id))?;
+    validate_description(conn, input.description.as_deref())?;
+    
     let now = now();
     let mut updates = Vec::new();
     let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
     
-    if let Some(name) = input.name {
-        updates.push("name = ?");
-        query_params.push(Box::new(name));
-    }
     if let Some(title) = input.title {
         updates.push("title = ?");
         query_params.push(Box::new(title));
@@ -1751,6 +2246,10 @@ pub fn update_template(
         updates.push("description = ?");
         query_params.push(Box::new(description));
     }
+    if let Some(due_date) = input.due_date {
+        updates.push("due_at = ?");
+        query_params.push(Box::new(due_date));
+    }
     if let Some(priority) = input.priority {
         updates.push("priority = ?");
         query_params.push(Box::new(priority));
@@ -1759,1478 +2258,10598 @@ pub fn update_template(
         updates.push("project_id = ?");
         query_params.push(Box::new(project_id));
     }
+    if let Some(order_index) = input.order_index {
+        updates.push("order_index = ?");
+        query_params.push(Box::new(order_index));
+    }
+    if let Some(recurrence_type) = input.recurrence_type {
+        updates.push("recurrence_type = ?");
+        query_params.push(Box::new(recurrence_type));
+    }
+    if let Some(recurrence_interval) = input.recurrence_interval {
+        updates.push("recurrence_interval = ?");
+        query_params.push(Box::new(recurrence_interval));
+    }
+    if let Some(days) = &input.recurrence_days_of_week {
+        updates.push("recurrence_days_of_week = ?");
+        query_params.push(Box::new(crate::services::task_service::format_days_of_week(days)?));
+    }
+    if let Some(end_date) = input.recurrence_end_date {
+        updates.push("recurrence_end_date = ?");
+        query_params.push(Box::new(Some(end_date).filter(|end_date| *end_date != 0)));
+    }
+    if let Some(count) = input.recurrence_count {
+        if count < 0 {
+            return Err("Recurrence count must be at least 1".to_string());
+        }
+        updates.push("recurrence_count = ?");
+        query_params.push(Box::new(Some(count).filter(|count| *count != 0)));
+    }
+    if let Some(estimated_minutes) = input.estimated_minutes {
+        validate_minutes(Some(estimated_minutes))?;
+        updates.push("estimated_minutes = ?");
+        query_params.push(Box::new(Some(estimated_minutes).filter(|minutes| *minutes != 0)));
+    }
+    if let Some(actual_minutes) = input.actual_minutes {
+        validate_minutes(Some(actual_minutes))?;
+        updates.push("actual_minutes = ?");
+        query_params.push(Box::new(Some(actual_minutes).filter(|minutes| *minutes != 0)));
+    }
+    if let Some(reminder_minutes_before) = input.reminder_minutes_before {
+        updates.push("reminder_minutes_before = ?");
+        query_params.push(Box::new(reminder_minutes_before));
+    }
+    if let Some(notification_repeat) = input.notification_repeat {
+        updates.push("notification_repeat = ?");
+        query_params.push(Box::new(if notification_repeat { 1 } else { 0 }));
+    }
+    validate_reminder_style(input.reminder_sound.as_deref(), input.reminder_urgency.as_deref())?;
+    if let Some(reminder_sound) = input.reminder_sound {
+        if reminder_sound.is_empty() {
+            updates.push("reminder_sound = NULL");
+        } else {
+            updates.push("reminder_sound = ?");
+            query_params.push(Box::new(reminder_sound));
+        }
+    }
+    if let Some(reminder_urgency) = input.reminder_urgency {
+        updates.push("reminder_urgency = ?");
+        query_params.push(Box::new(reminder_urgency));
+    }
+    if let Some(completion_note_required) = input.completion_note_required {
+        updates.push("completion_note_required = ?");
+        query_params.push(Box::new(completion_note_required));
+    }
+    if let Some(status) = input.status.clone() {
+        validate_board_status(&status)?;
+        if !input.force.unwrap_or(false) {
+            crate::services::wip_limit_service::check_wip_limits(conn, id, &status)?;
+        }
+        updates.push("status = ?");
+        query_params.push(Box::new(status));
+    }
     
     if updates.is_empty() {
-        return get_template(db, id);
+        return fetch_task(conn, id);
+    }
+    let before = fetch_task(conn, id)?;
+    if let Some(status) = input.status.as_deref() {
+        complete_for_status(conn, id, status)?;
     }
     
-    {
-        let db_lock = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-        updates.push("updated_at = ?");
-        query_params.push(Box::new(now));
-        query_params.push(Box::new(id.clone()));
+    updates.push(BUMP_UPDATED_AT);
+    query_params.push(Box::new(now));
+    query_params.push(Box::new(id.to_string()));
+    
+    // Capture the previous due date so the change can be recorded in due_date_history
+    let previous_due_date: Option<i64> = if input.due_date.is_some() {
+        conn.query_row(
+            "SELECT due_at FROM tasks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        ).map_err(|e| format!("Task not found: {}", e))?
+    } else {
+        None
+    };
+    
+    let query = format!("UPDATE tasks SET {} WHERE id = ?", updates.join(", "));
+    conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
+        .map_err(|e| format!("Failed to update task: {}", e))?;
+    
+    if let Some(new_due_date) = input.due_date {
+        record_due_date_change(conn, id, previous_due_date, Some(new_due_date), due_date_source)?;
+    }
+    
+    // Reschedule notifications if reminder settings changed
+    let mut reminder_state = None;
+    if input.reminder_minutes_before.is_some() || input.notification_repeat.is_some() || input.due_date.is_some() {
+        let reminder_minutes: Option<i32> = conn.query_row(
+            "SELECT reminder_minutes_before FROM tasks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        ).ok().flatten();
         
-        let query = format!("UPDATE task_templates SET {} WHERE id = ?", updates.join(", "));
-        db_lock.conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
-            .map_err(|e| format!("Failed to update template: {}", e))?;
+        if reminder_minutes.is_some() {
+            // A due date moved into the reminder window (or past it) gets its reminder right away
+            reminder_state = crate::notifications::reschedule_reminder(conn, id, reminder_minutes, now)
+                .ok()
+                .flatten();
+        } else {
+            let _ = conn.execute(
+                "DELETE FROM notification_schedule WHERE task_id = ?1 AND kind = 'reminder'",
+                params![id],
+            );
+        }
     }
     
-    get_template(db, id)
+    let mut task = fetch_task(conn, id)?;
+    log_task_changes(conn, &before, &task, activity_source)?;
+    task.reminder_state = reminder_state;
+    Ok(task)
 }
 
-#[tauri::command]
-pub fn delete_template(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    db.conn.execute("DELETE FROM task_templates WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete template: {}", e))?;
-    
+// One activity row per field update_task changed
+fn log_task_changes(conn: &rusqlite::Connection, before: &Task, after: &Task, source: &str) -> Result<(), String> {
+    let due = |task: &Task| task.due_date.map(|due| due.to_string());
+    let recurrence = |task: &Task| match task.recurrence_interval {
+        1 => task.recurrence_type.clone(),
+        interval => format!("{}/{}", task.recurrence_type, interval),
+    };
+    let reminder = |task: &Task| task.reminder_minutes_before.map(|minutes| minutes.to_string());
+    let changes = [
+        ("title_changed", Some(before.title.clone()), Some(after.title.clone())),
+        ("description_changed", before.description.clone(), after.description.clone()),
+        ("due_date_changed", due(before), due(after)),
+        ("priority_changed", Some(before.priority.clone()), Some(after.priority.clone())),
+        ("project_changed", before.project_id.clone(), after.project_id.clone()),
+        ("status_changed", before.status.clone(), after.status.clone()),
+        ("recurrence_changed", Some(recurrence(before)), Some(recurrence(after))),
+        ("reminder_changed", reminder(before), reminder(after)),
+    ];
+    for (action, old_value, new_value) in changes {
+        if old_value != new_value {
+            log_activity(conn, &after.id, action, old_value.as_deref(), new_value.as_deref(), source)?;
+        }
+    }
     Ok(())
 }
 
-#[tauri::command]
-pub fn create_task_from_template(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    template_id: String,
-    due_date: Option<i64>,
-) -> Result<Task, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Get template
-    let template: (String, Option<String>, String, Option<String>) = db.conn.query_row(
-        "SELECT title, description, priority, project_id FROM task_templates WHERE id = ?1",
-        params![template_id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
-    ).map_err(|e| format!("Template not found: {}", e))?;
-    
-    let (title, description, priority, project_id) = template;
-    
-    // Create task from template
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    db.conn.execute(
-        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-        params![
-            id.clone(),
-            title,
-            description,
-            due_date,
-            now,
-            now,
-            priority,
-            None::<i64>,
-            project_id,
-            0,
-            None::<String>,
-            "none",
-            1,
-            None::<String>
-        ],
-    ).map_err(|e| format!("Failed to create task from template: {}", e))?;
-    
-    fetch_task(&db.conn, &id)
+fn validate_reminder_style(sound: Option<&str>, urgency: Option<&str>) -> Result<(), String> {
+    if let Some(sound) = sound.filter(|sound| !sound.is_empty()) {
+        if !crate::notifications::is_known_sound(sound) {
+            return Err(format!("Unknown reminder sound: {}", sound));
+        }
+    }
+    if let Some(urgency) = urgency {
+        if crate::notifications::Urgency::parse(urgency).is_none() {
+            return Err(format!("Invalid reminder urgency: {} (expected low, normal or critical)", urgency));
+        }
+    }
+    Ok(())
 }
 
-// Gamification data structures
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserProgress {
-    pub id: String,
-    pub total_xp: i64,
-    pub current_level: i32,
-    pub current_streak: i32,
-    pub longest_streak: i32,
-    pub last_completion_date: Option<i64>,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+// Characters of the description kept by TaskFilter::description_preview_only
+const DESCRIPTION_PREVIEW_CHARS: usize = 300;
+// Longest description accepted, in characters, unless overridden by the setting
+const DEFAULT_MAX_DESCRIPTION_LENGTH: usize = 20_000;
+const MAX_DESCRIPTION_LENGTH_SETTING: &str = "max_description_length";
+// Ends a description whose overflow was moved to an attachment by truncate_long_descriptions
+const DESCRIPTION_CONTINUED_MARKER: &str = "\n\n[continued in attachment]";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Badge {
-    pub id: String,
-    pub user_id: String,
-    pub badge_type: String,
-    pub earned_at: i64,
-    pub metadata: Option<String>,
+fn max_description_length(conn: &rusqlite::Connection) -> usize {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![MAX_DESCRIPTION_LENGTH_SETTING],
+        |row| row.get::<_, String>(0),
+    ).ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|max| *max > DESCRIPTION_CONTINUED_MARKER.len())
+        .unwrap_or(DEFAULT_MAX_DESCRIPTION_LENGTH)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct XpHistoryEntry {
-    pub id: String,
-    pub user_id: String,
-    pub xp_amount: i32,
-    pub source: String,
-    pub task_id: Option<String>,
-    pub created_at: i64,
+// Checked on every path that writes a task's description: create, update, duplicate, templates,
+// backup and file imports, and calendar sync
+pub(crate) fn validate_description(conn: &rusqlite::Connection, description: Option<&str>) -> Result<(), String> {
+    let Some(description) = description else {
+        return Ok(());
+    };
+    let max = max_description_length(conn);
+    let length = description.chars().count();
+    if length > max {
+        return Err(format!(
+            "Description is {} characters long; the limit is {}. Attach longer text as a file instead.",
+            length, max
+        ));
+    }
+    Ok(())
 }
 
+// Name of the attachment truncate_long_descriptions moves overflowing text into
+const DESCRIPTION_OVERFLOW_FILENAME: &str = "description (continued).md";
+
+/// A task whose description truncate_long_descriptions cut (or would cut, in a dry run)
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GrantXpResult {
-    pub level_up: bool,
-    pub new_level: i32,
-    pub total_xp: i64,
-    pub current_xp: i64,
-    pub xp_to_next_level: i64,
+pub struct TruncatedDescription {
+    pub task_id: String,
+    pub title: String,
+    /// Characters before truncation
+    pub original_length: usize,
+    /// Characters moved into the attachment
+    pub moved_length: usize,
+    /// The attachment holding the rest; None in a dry run
+    pub attachment_id: Option<String>,
 }
 
-// Helper function to calculate level from total XP
-// Formula: level = floor(sqrt(totalXp / 100)) + 1
-fn calculate_level(total_xp: i64) -> i32 {
-    if total_xp <= 0 {
-        return 1;
+// Move the part of every description past the limit into a .md attachment on its task and end
+// the description with DESCRIPTION_CONTINUED_MARKER, all in one transaction; files written
+// before a failure are removed again. A dry run only reports the tasks.
+fn truncate_long_descriptions_internal(
+    conn: &rusqlite::Connection,
+    app_data_dir: &std::path::Path,
+    dry_run: bool,
+) -> Result<Vec<TruncatedDescription>, String> {
+    use crate::attachments::{get_mime_type, write_into_storage};
+    
+    let max = max_description_length(conn);
+    let keep = max - DESCRIPTION_CONTINUED_MARKER.chars().count();
+    // LENGTH counts characters for text values
+    let mut stmt = conn.prepare("SELECT id, title, description FROM tasks WHERE LENGTH(description) > ?1 ORDER BY created_at, id")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let oversized = stmt.query_map(params![max as i64], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut written = Vec::new();
+    let result = (|| {
+        let mut report = Vec::new();
+        for (task_id, title, description) in oversized {
+            let cut = description.char_indices().nth(keep).map_or(description.len(), |(index, _)| index);
+            let overflow = &description[cut..];
+            let mut truncated = TruncatedDescription {
+                task_id: task_id.clone(),
+                title,
+                original_length: description.chars().count(),
+                moved_length: overflow.chars().count(),
+                attachment_id: None,
+            };
+            if !dry_run {
+                let stored_path = write_into_storage(app_data_dir, DESCRIPTION_OVERFLOW_FILENAME, overflow.as_bytes(), &task_id)?;
+                written.push(app_data_dir.join(&stored_path));
+                let attachment_id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![attachment_id, task_id, DESCRIPTION_OVERFLOW_FILENAME, stored_path, get_mime_type(DESCRIPTION_OVERFLOW_FILENAME), overflow.len() as i64, now()],
+                ).map_err(|e| crate::storage::from_sqlite("create the attachment record", &e).to_string())?;
+                tx.execute(
+                    &format!("UPDATE tasks SET description = ?, {} WHERE id = ?", BUMP_UPDATED_AT),
+                    params![format!("{}{}", &description[..cut], DESCRIPTION_CONTINUED_MARKER), now(), task_id],
+                ).map_err(|e| format!("Failed to update task: {}", e))?;
+                truncated.attachment_id = Some(attachment_id);
+            }
+            report.push(truncated);
+        }
+        Ok(report)
+    })()
+    .and_then(|report| {
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(report)
+    });
+    
+    if result.is_err() {
+        for path in written {
+            let _ = std::fs::remove_file(path);
+        }
     }
-    ((total_xp as f64 / 100.0).sqrt().floor() as i32) + 1
+    result
 }
 
-// Helper function to calculate XP needed for next level
-// Formula: xpToNextLevel = (level * 100) * level
-fn calculate_xp_to_next_level(level: i32) -> i64 {
-    (level as i64 * 100) * level as i64
+/// Cut descriptions longer than the limit (the max_description_length setting, 20000 characters
+/// by default), keeping the rest in a Markdown attachment on the task. Returns the affected tasks.
+#[tauri::command]
+pub fn truncate_long_descriptions(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    dry_run: bool,
+) -> Result<Vec<TruncatedDescription>, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Failed to get app data directory")?;
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    truncate_long_descriptions_internal(&db.conn, &app_data_dir, dry_run)
 }
 
-// Helper function to calculate current XP within current level
-fn calculate_current_xp(total_xp: i64, level: i32) -> i64 {
-    if level == 1 {
-        return total_xp;
-    }
-    // Calculate total XP needed to reach current level
-    let mut xp_for_current_level = 0i64;
-    for i in 1..level {
-        xp_for_current_level += calculate_xp_to_next_level(i);
-    }
-    total_xp - xp_for_current_level
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReminderStyle {
+    pub sound: Option<String>,
+    pub urgency: crate::notifications::Urgency,
 }
 
-// Internal helper functions for gamification (work directly with connection)
-fn grant_xp_internal(conn: &rusqlite::Connection, xp: i32, source: String, task_id: Option<String>) -> Result<GrantXpResult, String> {
-    let progress = get_user_progress_internal(conn)?;
-    
-    let previous_level = progress.current_level;
-    let new_total_xp = (progress.total_xp + xp as i64).max(0);
-    let new_level = calculate_level(new_total_xp);
-    let new_xp_to_next_level = calculate_xp_to_next_level(new_level);
-    let new_current_xp = calculate_current_xp(new_total_xp, new_level);
-    let leveled_up = new_level > previous_level;
-    
-    // Update user progress
-    let now = now();
-    conn.execute(
-        "UPDATE user_progress SET total_xp = ?1, current_level = ?2, updated_at = ?3 WHERE id = 'default'",
-        params![new_total_xp, new_level, now],
-    ).map_err(|e| format!("Failed to update user progress: {}", e))?;
+#[tauri::command]
+pub fn get_task_reminder_style(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+) -> Result<ReminderStyle, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     
-    // Record in XP history
-    let history_id = uuid::Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO xp_history (id, user_id, xp_amount, source, task_id, created_at) VALUES (?1, 'default', ?2, ?3, ?4, ?5)",
-        params![history_id, xp, source, task_id, now],
-    ).map_err(|e| format!("Failed to record XP history: {}", e))?;
+    let (sound, urgency): (Option<String>, Option<String>) = db.conn.query_row(
+        "SELECT reminder_sound, reminder_urgency FROM tasks WHERE id = ?1",
+        params![task_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Task not found: {}", e))?;
     
-    Ok(GrantXpResult {
-        level_up: leveled_up,
-        new_level,
-        total_xp: new_total_xp,
-        current_xp: new_current_xp,
-        xp_to_next_level: new_xp_to_next_level,
+    Ok(ReminderStyle {
+        sound,
+        urgency: urgency
+            .as_deref()
+            .and_then(crate::notifications::Urgency::parse)
+            .unwrap_or(crate::notifications::Urgency::Normal),
     })
 }
 
-// Revoke XP (subtract XP and remove history entry)
-fn revoke_xp_internal(conn: &rusqlite::Connection, xp: i32, history_id: String) -> Result<GrantXpResult, String> {
-    let progress = get_user_progress_internal(conn)?;
-    
-    let _previous_level = progress.current_level;
-    let new_total_xp = (progress.total_xp - xp as i64).max(0);
-    let new_level = calculate_level(new_total_xp);
-    let new_xp_to_next_level = calculate_xp_to_next_level(new_level);
-    let new_current_xp = calculate_current_xp(new_total_xp, new_level);
-    let leveled_up = false; // Can't level up when revoking XP
+/// Sound ids for the reminder settings; the backend maps each to a platform sound
+#[tauri::command]
+pub fn get_available_sounds() -> Vec<crate::notifications::NotificationSound> {
+    crate::notifications::available_sounds()
+}
+
+// Due date history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DueDateChange {
+    pub id: String,
+    pub old_due_date: Option<i64>,
+    pub new_due_date: Option<i64>,
+    pub source: String,
+    pub changed_at: i64,
+    pub delta_days: Option<i64>, // None when either side of the change has no due date
+    pub delta_label: String,     // e.g. "+3 days", "-1 day", "set", "cleared"
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DueDateHistory {
+    pub task_id: String,
+    pub original_due_date: Option<i64>,
+    pub current_due_date: Option<i64>,
+    pub total_slip_days: i64,
+    pub entries: Vec<DueDateChange>,
+}
+
+/// Record a due date change for a task. No-op when the date did not actually change.
+/// Every path that moves a due date (update_task, snoozing, rescheduling) should go through this.
+pub(crate) fn record_due_date_change(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    old_due_date: Option<i64>,
+    new_due_date: Option<i64>,
+    source: &str,
+) -> Result<(), String> {
+    if old_due_date == new_due_date {
+        return Ok(());
+    }
     
-    // Update user progress
-    let now = now();
     conn.execute(
-        "UPDATE user_progress SET total_xp = ?1, current_level = ?2, updated_at = ?3 WHERE id = 'default'",
-        params![new_total_xp, new_level, now],
-    ).map_err(|e| format!("Failed to update user progress: {}", e))?;
+        "INSERT INTO due_date_history (id, task_id, old_due_at, new_due_at, source, changed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![uuid::Uuid::new_v4().to_string(), task_id, old_due_date, new_due_date, source, now()],
+    ).map_err(|e| format!("Failed to record due date change: {}", e))?;
     
-    // Remove the XP history entry
+    Ok(())
+}
+
+pub(crate) fn log_activity(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    action: &str,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+    source: &str,
+) -> Result<(), String> {
     conn.execute(
-        "DELETE FROM xp_history WHERE id = ?1",
-        params![history_id],
-    ).map_err(|e| format!("Failed to remove XP history: {}", e))?;
+        "INSERT INTO activity_log (id, task_id, action, old_value, new_value, source, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![uuid::Uuid::new_v4().to_string(), task_id, action, old_value, new_value, source, now()],
+    ).map_err(|e| format!("Failed to record activity: {}", e))?;
     
-    Ok(GrantXpResult {
-        level_up: leveled_up,
-        new_level,
-        total_xp: new_total_xp,
-        current_xp: new_current_xp,
-        xp_to_next_level: new_xp_to_next_level,
-    })
+    Ok(())
 }
 
-pub(crate) fn update_streak_internal(conn: &rusqlite::Connection) -> Result<UserProgress, String> {
-    let mut progress = get_user_progress_internal(conn)?;
-    
-    // Get today's date at midnight (Unix timestamp)
-    let current_time = now();
-    let today_start = (current_time / 86400) * 86400; // Round down to start of day
-    let today_end = today_start + 86400 - 1; // End of day
-    
-    // Check if user completed at least one task today
-    let tasks_completed_today: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM tasks WHERE completed_at IS NOT NULL AND completed_at >= ?1 AND completed_at < ?2",
-        params![today_start, today_end],
+/// One row of a task's activity log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityLogEntry {
+    pub id: String,
+    pub task_id: String,
+    /// e.g. "title_changed", "completed", "tag_added"
+    pub action: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    /// What made the change: "user", "aging", "rule", ...
+    pub source: String,
+    pub created_at: i64,
+}
+
+const DEFAULT_ACTIVITY_LOG_LIMIT: i32 = 100;
+
+// Newest first
+fn get_task_activity_log_internal(conn: &rusqlite::Connection, task_id: &str, limit: Option<i32>) -> Result<Vec<ActivityLogEntry>, String> {
+    let limit = limit.filter(|limit| *limit > 0).unwrap_or(DEFAULT_ACTIVITY_LOG_LIMIT);
+    let mut stmt = conn.prepare(
+        "SELECT id, task_id, action, old_value, new_value, source, created_at FROM activity_log
+         WHERE task_id = ?1
+         ORDER BY created_at DESC, rowid DESC
+         LIMIT ?2"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let entries = stmt.query_map(params![task_id, limit], |row| {
+        Ok(ActivityLogEntry {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            action: row.get(2)?,
+            old_value: row.get(3)?,
+            new_value: row.get(4)?,
+            source: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    Ok(entries)
+}
+
+/// What happened to a task, newest first; `limit` defaults to 100
+#[tauri::command]
+pub fn get_task_activity_log(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+    limit: Option<i32>,
+) -> Result<Vec<ActivityLogEntry>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let entries = get_task_activity_log_internal(&db.conn, &task_id, limit)?;
+    Ok(demo.apply(entries))
+}
+
+#[tauri::command]
+pub fn clear_task_activity_log(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    db.conn.execute("DELETE FROM activity_log WHERE task_id = ?1", params![task_id])
+        .map_err(|e| format!("Failed to clear activity log: {}", e))?;
+    Ok(())
+}
+
+// Whole days between two timestamps, rounded towards the nearest day
+fn days_between(from: i64, to: i64) -> i64 {
+    let diff = to - from;
+    (diff as f64 / 86400.0).round() as i64
+}
+
+fn format_day_delta(delta_days: i64) -> String {
+    match delta_days {
+        0 => "same day".to_string(),
+        1 => "+1 day".to_string(),
+        -1 => "-1 day".to_string(),
+        d if d > 0 => format!("+{} days", d),
+        d => format!("{} days", d),
+    }
+}
+
+// Helper function to build a task's due date history
+pub(crate) fn get_due_date_history_internal(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+) -> Result<DueDateHistory, String> {
+    let current_due_date: Option<i64> = conn.query_row(
+        "SELECT due_at FROM tasks WHERE id = ?1",
+        params![task_id],
         |row| row.get(0),
-    ).unwrap_or(0);
+    ).map_err(|e| format!("Task not found: {}", e))?;
     
-    let has_completed_today = tasks_completed_today > 0;
+    let mut stmt = conn.prepare(
+        "SELECT id, old_due_at, new_due_at, source, changed_at FROM due_date_history
+         WHERE task_id = ?1
+         ORDER BY changed_at ASC, rowid ASC"
+    ).map_err(|e| format!("Query error: {}", e))?;
     
-    if has_completed_today {
-        if let Some(last_completion) = progress.last_completion_date {
-            let last_completion_day = (last_completion / 86400) * 86400;
-            let yesterday_start = today_start - 86400;
-            
-            if last_completion_day == yesterday_start {
-                // Last completion was yesterday - increment streak
-                progress.current_streak += 1;
-            } else if last_completion_day < yesterday_start {
-                // Last completion was more than 1 day ago - reset streak to 1
-                progress.current_streak = 1;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<i64>>(1)?,
+            row.get::<_, Option<i64>>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, i64>(4)?,
+        ))
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, old_due_date, new_due_date, source, changed_at) =
+            row.map_err(|e| format!("Row parsing error: {}", e))?;
+        
+        let (delta_days, delta_label) = match (old_due_date, new_due_date) {
+            (Some(old), Some(new)) => {
+                let delta = days_between(old, new);
+                (Some(delta), format_day_delta(delta))
             }
-            // If last_completion_day == today_start, no change (already counted today)
-        } else {
-            // No previous completion date - start streak at 1
-            progress.current_streak = 1;
-        }
+            (None, Some(_)) => (None, "set".to_string()),
+            (Some(_), None) => (None, "cleared".to_string()),
+            (None, None) => (None, "unchanged".to_string()),
+        };
         
-        // Update longest streak if current exceeds it
-        if progress.current_streak > progress.longest_streak {
-            progress.longest_streak = progress.current_streak;
-        }
-        
-        // Update last completion date to today
-        progress.last_completion_date = Some(today_start);
+        entries.push(DueDateChange {
+            id,
+            old_due_date,
+            new_due_date,
+            source,
+            changed_at,
+            delta_days,
+            delta_label,
+        });
     }
     
-    // Update database
-    let update_time = now();
-    conn.execute(
-        "UPDATE user_progress SET current_streak = ?1, longest_streak = ?2, last_completion_date = ?3, updated_at = ?4 WHERE id = 'default'",
-        params![progress.current_streak, progress.longest_streak, progress.last_completion_date, update_time],
-    ).map_err(|e| format!("Failed to update streak: {}", e))?;
+    // The original due date is whatever the task had before its first recorded change,
+    // falling back to the first date ever set, or the current one if it never changed
+    let original_due_date = match entries.first() {
+        Some(first) => first.old_due_date.or(first.new_due_date),
+        None => current_due_date,
+    };
     
-    progress.updated_at = update_time;
-    Ok(progress)
+    let total_slip_days = match (original_due_date, current_due_date) {
+        (Some(original), Some(current)) => days_between(original, current),
+        _ => 0,
+    };
+    
+    Ok(DueDateHistory {
+        task_id: task_id.to_string(),
+        original_due_date,
+        current_due_date,
+        total_slip_days,
+        entries,
+    })
 }
 
-fn check_and_award_badges_internal(conn: &rusqlite::Connection) -> Result<Vec<Badge>, String> {
-    let progress = get_user_progress_internal(conn)?;
-    
-    // Get total tasks completed
-    let total_tasks_completed: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM tasks WHERE completed_at IS NOT NULL",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(0);
-    
-    // Get already earned badges
-    let mut stmt = conn.prepare("SELECT id, user_id, badge_type, earned_at, metadata FROM badges WHERE user_id = 'default' ORDER BY earned_at DESC")
-        .map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map([], |row| {
-        Ok(Badge {
-            id: row.get(0)?,
-            user_id: row.get(1)?,
-            badge_type: row.get(2)?,
-            earned_at: row.get(3)?,
-            metadata: row.get(4)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut earned_badges = Vec::new();
-    for row in rows {
-        earned_badges.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+#[tauri::command]
+pub fn get_due_date_history(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+) -> Result<DueDateHistory, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_due_date_history_internal(&db.conn, &task_id)
+}
+
+/// What duplicate_task copies along with the task itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicateTaskOptions {
+    pub include_subtasks: bool,
+    pub include_tags: bool,
+    /// Copies the files as well, so the duplicate never shares them with the original
+    pub include_attachments: bool,
+}
+
+impl Default for DuplicateTaskOptions {
+    fn default() -> Self {
+        Self {
+            include_subtasks: true,
+            include_tags: true,
+            include_attachments: false,
+        }
     }
+}
+
+// Copy a task as a new, open task, with whatever `options` asks for. Subtasks start unchecked,
+// only the current version of each attachment is copied and the copy starts its own recurrence
+// chain. Copied files are removed again if the transaction fails.
+fn duplicate_task_internal(
+    conn: &rusqlite::Connection,
+    app_data_dir: &std::path::Path,
+    id: &str,
+    new_title: Option<String>,
+    options: &DuplicateTaskOptions,
+) -> Result<Task, String> {
+    let original = fetch_task(conn, id)?;
+    let title = new_title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| format!("{} (copy)", original.title));
+    let new_id = uuid::Uuid::new_v4().to_string();
     
-    let earned_types: std::collections::HashSet<String> = earned_badges.iter()
-        .map(|b| b.badge_type.clone())
-        .collect();
-    
-    let mut newly_awarded = Vec::new();
-    let now = now();
-    
-    // Check each badge criteria
-    // first_task: total_tasks_completed >= 1
-    if total_tasks_completed >= 1 && !earned_types.contains("first_task") {
-        let badge_id = uuid::Uuid::new_v4().to_string();
-        let metadata = serde_json::json!({"milestone": 1}).to_string();
-        conn.execute(
-            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'first_task', ?2, ?3)",
-            params![badge_id.clone(), now, metadata],
-        ).map_err(|e| format!("Failed to award badge: {}", e))?;
-        
-        newly_awarded.push(Badge {
-            id: badge_id,
-            user_id: "default".to_string(),
-            badge_type: "first_task".to_string(),
-            earned_at: now,
-            metadata: Some(serde_json::json!({"milestone": 1}).to_string()),
-        });
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut written = Vec::new();
+    let result = duplicate_task_rows(&tx, app_data_dir, &original, &new_id, &title, options, &mut written)
+        .and_then(|_| tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e)));
+    if let Err(e) = result {
+        for path in written {
+            let _ = std::fs::remove_file(path);
+        }
+        return Err(e);
     }
-    
-    // task_master_100: total_tasks_completed >= 100
-    if total_tasks_completed >= 100 && !earned_types.contains("task_master_100") {
-        let badge_id = uuid::Uuid::new_v4().to_string();
-        let metadata = serde_json::json!({"milestone": 100}).to_string();
-        conn.execute(
-            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'task_master_100', ?2, ?3)",
-            params![badge_id.clone(), now, metadata],
-        ).map_err(|e| format!("Failed to award badge: {}", e))?;
-        
-        newly_awarded.push(Badge {
-            id: badge_id,
-            user_id: "default".to_string(),
-            badge_type: "task_master_100".to_string(),
-            earned_at: now,
-            metadata: Some(serde_json::json!({"milestone": 100}).to_string()),
-        });
+    fetch_task(conn, &new_id)
+}
+
+// The inserts behind duplicate_task_internal; every file copied into storage is added to `written`
+fn duplicate_task_rows(
+    tx: &rusqlite::Transaction,
+    app_data_dir: &std::path::Path,
+    original: &Task,
+    new_id: &str,
+    title: &str,
+    options: &DuplicateTaskOptions,
+    written: &mut Vec<std::path::PathBuf>,
+) -> Result<(), String> {
+    let id = original.id.as_str();
+    let now = now();
+    validate_description(tx, original.description.as_deref())?;
+    tx.execute(
+        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, reminder_minutes_before, notification_repeat, reminder_sound, reminder_urgency, completion_note_required, estimated_minutes, recurrence_days_of_week, recurrence_end_date, recurrence_count)
+         SELECT ?1, ?2, description, due_at, ?3, ?3, priority, NULL, project_id, order_index, metadata, recurrence_type, recurrence_interval, reminder_minutes_before, notification_repeat, reminder_sound, reminder_urgency, completion_note_required, estimated_minutes, recurrence_days_of_week, recurrence_end_date, recurrence_count
+         FROM tasks WHERE id = ?4",
+        params![new_id, title, now, id],
+    ).map_err(|e| format!("Failed to duplicate task: {}", e))?;
+    
+    if options.include_subtasks {
+        copy_subtasks(tx, id, new_id)?;
     }
-    
-    // week_warrior: current_streak == 7
-    if progress.current_streak == 7 && !earned_types.contains("week_warrior") {
-        let badge_id = uuid::Uuid::new_v4().to_string();
-        let metadata = serde_json::json!({"streak": 7}).to_string();
-        conn.execute(
-            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'week_warrior', ?2, ?3)",
-            params![badge_id.clone(), now, metadata],
-        ).map_err(|e| format!("Failed to award badge: {}", e))?;
-        
-        newly_awarded.push(Badge {
-            id: badge_id,
-            user_id: "default".to_string(),
-            badge_type: "week_warrior".to_string(),
-            earned_at: now,
-            metadata: Some(serde_json::json!({"streak": 7}).to_string()),
-        });
+    if options.include_tags {
+        for tag in original.tags.iter().flatten() {
+            tx.execute(
+                "INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![uuid::Uuid::new_v4().to_string(), new_id, tag.id, now],
+            ).map_err(|e| format!("Failed to copy tag: {}", e))?;
+            tx.execute(
+                "UPDATE tags SET usage_count = usage_count + 1 WHERE id = ?1",
+                params![tag.id],
+            ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+        }
     }
-    
-    // level_10: level == 10
-    if progress.current_level == 10 && !earned_types.contains("level_10") {
-        let badge_id = uuid::Uuid::new_v4().to_string();
-        let metadata = serde_json::json!({"level": 10}).to_string();
-        conn.execute(
-            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'level_10', ?2, ?3)",
-            params![badge_id.clone(), now, metadata],
-        ).map_err(|e| format!("Failed to award badge: {}", e))?;
-        
-        newly_awarded.push(Badge {
-            id: badge_id,
-            user_id: "default".to_string(),
-            badge_type: "level_10".to_string(),
-            earned_at: now,
-            metadata: Some(serde_json::json!({"level": 10}).to_string()),
-        });
+    if options.include_attachments {
+        copy_attachments(tx, app_data_dir, id, new_id, written)?;
     }
-    
-    Ok(newly_awarded)
+    Ok(())
 }
 
-// Helper function to get user progress from connection (internal use)
-fn get_user_progress_internal(conn: &rusqlite::Connection) -> Result<UserProgress, String> {
-    let result = conn.query_row(
-        "SELECT id, total_xp, current_level, current_streak, longest_streak, last_completion_date, created_at, updated_at FROM user_progress WHERE id = 'default'",
-        [],
-        |row| {
-            Ok(UserProgress {
-                id: row.get(0)?,
-                total_xp: row.get(1)?,
-                current_level: row.get(2)?,
-                current_streak: row.get(3)?,
-                longest_streak: row.get(4)?,
-                last_completion_date: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        },
-    );
-    
-    match result {
-        Ok(progress) => Ok(progress),
-        Err(_) => {
-            // Create default user progress if it doesn't exist
-            let now = now();
-            conn.execute(
-                "INSERT INTO user_progress (id, total_xp, current_level, current_streak, longest_streak, created_at, updated_at) VALUES ('default', 0, 1, 0, 0, ?1, ?2)",
-                params![now, now],
-            ).map_err(|e| format!("Failed to create user progress: {}", e))?;
-            
-            Ok(UserProgress {
-                id: "default".to_string(),
-                total_xp: 0,
-                current_level: 1,
-                current_streak: 0,
-                longest_streak: 0,
-                last_completion_date: None,
-                created_at: now,
-                updated_at: now,
-            })
-        }
+fn copy_subtasks(tx: &rusqlite::Transaction, id: &str, new_id: &str) -> Result<(), String> {
+    let subtasks: Vec<(String, i32)> = tx.prepare("SELECT title, order_index FROM subtasks WHERE task_id = ?1 ORDER BY order_index")
+        .map_err(|e| format!("Query error: {}", e))?
+        .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read subtasks: {}", e))?;
+    for (subtask_title, order_index) in subtasks {
+        tx.execute(
+            "INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES (?1, ?2, ?3, 0, ?4)",
+            params![uuid::Uuid::new_v4().to_string(), new_id, subtask_title, order_index],
+        ).map_err(|e| format!("Failed to copy subtask: {}", e))?;
     }
+    Ok(())
 }
 
-// Gamification commands
-#[tauri::command]
-pub fn get_user_progress(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<UserProgress, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    get_user_progress_internal(&db.conn)
+// Copy the current version of each of the task's attachments into the new task's folder.
+// Attachments whose file is known to be missing are left out.
+fn copy_attachments(
+    tx: &rusqlite::Transaction,
+    app_data_dir: &std::path::Path,
+    id: &str,
+    new_id: &str,
+    written: &mut Vec<std::path::PathBuf>,
+) -> Result<(), String> {
+    let attachments: Vec<(String, String, Option<String>, Option<i64>)> = tx.prepare(
+        "SELECT filename, path, mime, size FROM attachments
+         WHERE task_id = ?1 AND is_current = 1 AND missing_since IS NULL
+         ORDER BY created_at, id",
+    )
+        .map_err(|e| format!("Query error: {}", e))?
+        .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read attachments: {}", e))?;
+    for (filename, path, mime, size) in attachments {
+        let source = app_data_dir.join(&path);
+        let stored_path = crate::attachments::copy_into_storage(app_data_dir, &source.to_string_lossy(), new_id)?;
+        written.push(app_data_dir.join(&stored_path));
+        tx.execute(
+            "INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![uuid::Uuid::new_v4().to_string(), new_id, filename, stored_path, mime, size, now()],
+        ).map_err(|e| crate::storage::from_sqlite("create the attachment record", &e).to_string())?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub fn grant_xp(
+pub fn duplicate_task(
+    app_handle: tauri::AppHandle,
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    xp: i32,
-    source: String,
-    task_id: Option<String>,
-) -> Result<GrantXpResult, String> {
+    id: String,
+    new_title: Option<String>,
+    options: Option<DuplicateTaskOptions>,
+) -> Result<Task, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Failed to get app data directory")?;
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    grant_xp_internal(&db.conn, xp, source, task_id)
+    let task = duplicate_task_internal(&db.conn, &app_data_dir, &id, new_title, &options.unwrap_or_default())?;
+    
+    if let Some(reminder_minutes) = task.reminder_minutes_before {
+        let _ = crate::notifications::schedule_notification(&db, &task.id, Some(reminder_minutes));
+    }
+    
+    Ok(task)
 }
 
-#[tauri::command]
-pub fn update_streak(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<UserProgress, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    update_streak_internal(&db.conn)
+// Gap between neighbouring order indexes, so moving one task between two others only rewrites
+// that task. normalize_order_indexes restores the spacing once a gap is used up.
+const ORDER_INDEX_STEP: i32 = 10;
+
+// Order index that puts a new task after everything else in its project (or the inbox)
+pub(crate) fn next_order_index(conn: &rusqlite::Connection, project_id: Option<&str>) -> Result<i32, String> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(order_index) + ?1, 0) FROM tasks WHERE project_id IS ?2",
+        params![ORDER_INDEX_STEP, project_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to find next order index: {}", e))
 }
 
-#[tauri::command]
-pub fn check_streak_on_startup(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<UserProgress, String> {
-    update_streak(db)
+// Tasks of one project (or the inbox) in the order the list shows them
+fn tasks_in_visual_order(conn: &rusqlite::Connection, project_id: Option<&str>) -> Result<Vec<(String, i32)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, order_index FROM tasks WHERE project_id IS ?1 ORDER BY order_index, created_at, id"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let tasks = stmt.query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read task order: {}", e))?;
+    Ok(tasks)
 }
 
-#[tauri::command]
-pub fn get_badges(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Badge>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.conn.prepare("SELECT id, user_id, badge_type, earned_at, metadata FROM badges WHERE user_id = 'default' ORDER BY earned_at DESC")
-        .map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map([], |row| {
-        Ok(Badge {
-            id: row.get(0)?,
-            user_id: row.get(1)?,
-            badge_type: row.get(2)?,
-            earned_at: row.get(3)?,
-            metadata: row.get(4)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut badges = Vec::new();
-    for row in rows {
-        badges.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+fn has_duplicate_order_indexes(conn: &rusqlite::Connection, project_id: Option<&str>) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT COUNT(*) > COUNT(DISTINCT order_index) FROM tasks WHERE project_id IS ?1",
+        params![project_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check order indexes: {}", e))
+}
+
+// Rewrite one project's order indexes to 0, STEP, 2*STEP... keeping the current visual order.
+// Returns how many tasks changed.
+fn normalize_project_order(conn: &rusqlite::Connection, project_id: Option<&str>) -> Result<usize, String> {
+    let mut changed = 0;
+    for (position, (id, order_index)) in tasks_in_visual_order(conn, project_id)?.into_iter().enumerate() {
+        let normalized = position as i32 * ORDER_INDEX_STEP;
+        if order_index != normalized {
+            conn.execute("UPDATE tasks SET order_index = ?1 WHERE id = ?2", params![normalized, id])
+                .map_err(|e| format!("Failed to update order index: {}", e))?;
+            changed += 1;
+        }
     }
+    Ok(changed)
+}
+
+// Normalize one project, the inbox (Some("")), or with None every project and the inbox
+fn normalize_order_indexes_internal(conn: &rusqlite::Connection, project_id: Option<&str>) -> Result<usize, String> {
+    let projects: Vec<Option<String>> = match project_id {
+        Some("") => vec![None],
+        Some(project_id) => vec![Some(project_id.to_string())],
+        None => conn.prepare("SELECT DISTINCT project_id FROM tasks")
+            .map_err(|e| format!("Query error: {}", e))?
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Query execution error: {}", e))?
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read projects: {}", e))?,
+    };
     
-    Ok(badges)
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut changed = 0;
+    for project_id in projects {
+        changed += normalize_project_order(&tx, project_id.as_deref())?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(changed)
 }
 
 #[tauri::command]
-pub fn check_and_award_badges(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Badge>, String> {
+pub fn normalize_order_indexes(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    project_id: Option<String>,
+) -> Result<usize, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    check_and_award_badges_internal(&db.conn)
-}
-
-// Translation data structures
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TranslatedContent {
-    pub title: String,
-    pub description: Option<String>,
-    pub source_lang: String,
-    pub target_lang: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TranslationRequest {
-    pub task_id: String,
-    pub target_lang: String, // "en" or "tr"
+    normalize_order_indexes_internal(&db.conn, project_id.as_deref())
 }
 
-// Translation commands
-#[tauri::command]
-pub async fn translate_task_content(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    request: TranslationRequest,
-) -> Result<TranslatedContent, String> {
-    // Get task and API key while holding the lock
-    let (task, api_key) = {
-        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-        let task = fetch_task(&db.conn, &request.task_id)?;
-        let api_key = translation_service::get_api_key(&db.conn)?;
-        (task, api_key)
-    };
+// Move a task to just before `before_task_id` in its project, or to the end. Normally only the
+// moved task is written; the project is renumbered first if indexes collide or the gap is used up.
+fn reorder_tasks_internal(conn: &rusqlite::Connection, task_id: &str, before_task_id: Option<&str>) -> Result<Task, String> {
+    let project_id: Option<String> = conn.query_row(
+        "SELECT project_id FROM tasks WHERE id = ?1",
+        params![task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    let project_id = project_id.as_deref();
     
-    // Detect source language (use title for detection)
-    let source_lang = translation_service::detect_language(&task.title, api_key.as_deref()).await?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    if has_duplicate_order_indexes(&tx, project_id)? {
+        normalize_project_order(&tx, project_id)?;
+    }
     
-    // Translate title
-    let translated_title = {
-        // Check cache and user translation first
-        let maybe_cached = {
-            let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-            translation_service::check_cache_and_user_translation(
-                &db.conn,
-                &task.title,
-                &request.target_lang,
-                "title",
-                Some(&request.task_id),
-            )?
+    let mut new_index = None;
+    for _ in 0..2 {
+        let others: Vec<(String, i32)> = tasks_in_visual_order(&tx, project_id)?
+            .into_iter()
+            .filter(|(id, _)| id != task_id)
+            .collect();
+        let position = match before_task_id {
+            Some(before) => others.iter().position(|(id, _)| id == before)
+                .ok_or_else(|| format!("Task {} is not in the same list", before))?,
+            None => others.len(),
         };
+        let previous = position.checked_sub(1).map(|i| others[i].1);
+        let next = others.get(position).map(|(_, order_index)| *order_index);
         
-        if let Some(cached) = maybe_cached {
-            cached
-        } else {
-            // Detect language first (no lock needed)
-            let detected_lang = translation_service::detect_language(&task.title, api_key.as_deref()).await?;
-            
-            // Check regular cache
-            let maybe_cached = {
-                let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-                let source_text_hash = translation_service::hash_text(&task.title);
-                translation_service::get_cached_translation(
-                    &db.conn,
-                    &source_text_hash,
-                    &detected_lang,
-                    &request.target_lang,
-                    "title",
-                )?
-            };
-            
-            if let Some(cached) = maybe_cached {
-                cached
-            } else {
-                // Do async translation
-                let translated = translation_service::translate_text(
-                    &task.title,
-                    &request.target_lang,
-                    api_key.as_deref(),
-                )
-                .await?;
-                
-                // Save to cache
-                {
-                    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-                    translation_service::save_translation(
-                        &db.conn,
-                        &task.title,
-                        &detected_lang,
-                        &request.target_lang,
-                        &translated,
-                        "title",
-                        Some(&request.task_id),
-                        false,
-                    )?;
-                }
-                
-                translated
-            }
+        new_index = match (previous, next) {
+            (None, None) => Some(0),
+            (Some(previous), None) => Some(previous + ORDER_INDEX_STEP),
+            (None, Some(next)) => Some(next - ORDER_INDEX_STEP),
+            (Some(previous), Some(next)) if next - previous >= 2 => Some(previous + (next - previous) / 2),
+            // No room between the neighbours; spread the project out and try again
+            _ => None,
+        };
+        if new_index.is_some() {
+            break;
         }
-    };
+        normalize_project_order(&tx, project_id)?;
+    }
+    let new_index = new_index.ok_or_else(|| "Failed to find a position for the task".to_string())?;
     
-    // Translate description if present
-    let translated_description = if let Some(desc) = &task.description {
-        if !desc.trim().is_empty() {
-            // Check cache and user translation first
-            let maybe_cached = {
-                let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-                translation_service::check_cache_and_user_translation(
-                    &db.conn,
-                    desc,
-                    &request.target_lang,
-                    "description",
-                    Some(&request.task_id),
-                )?
-            };
-            
-            if let Some(cached) = maybe_cached {
-                Some(cached)
-            } else {
-                // Detect language first (no lock needed)
-                let detected_lang = translation_service::detect_language(desc, api_key.as_deref()).await?;
-                
-                // Check regular cache
-                let maybe_cached = {
-                    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-                    let source_text_hash = translation_service::hash_text(desc);
-                    translation_service::get_cached_translation(
-                        &db.conn,
-                        &source_text_hash,
-                        &detected_lang,
-                        &request.target_lang,
-                        "description",
-                    )?
-                };
-                
-                if let Some(cached) = maybe_cached {
-                    Some(cached)
-                } else {
-                    // Do async translation
-                    let translated = translation_service::translate_text(
-                        desc,
-                        &request.target_lang,
-                        api_key.as_deref(),
-                    )
-                    .await?;
-                    
-                    // Save to cache
-                    {
-                        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-                        translation_service::save_translation(
-                            &db.conn,
-                            desc,
-                            &detected_lang,
-                            &request.target_lang,
-                            &translated,
-                            "description",
-                            Some(&request.task_id),
-                            false,
-                        )?;
-                    }
-                    
-                    Some(translated)
-                }
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    tx.execute(
+        "UPDATE tasks SET order_index = ?1, updated_at = ?2 WHERE id = ?3",
+        params![new_index, now(), task_id],
+    ).map_err(|e| format!("Failed to reorder task: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
     
-    Ok(TranslatedContent {
-        title: translated_title,
-        description: translated_description,
-        source_lang,
-        target_lang: request.target_lang,
-    })
+    fetch_task(conn, task_id)
 }
 
 #[tauri::command]
-pub async fn save_translation_override(
+pub fn reorder_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
     task_id: String,
-    field: String, // "title" or "description"
-    target_lang: String,
-    translated_text: String,
-) -> Result<(), String> {
-    // Get original text and API key while holding the lock
-    let (source_text, api_key) = {
-        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-        let task = fetch_task(&db.conn, &task_id)?;
-        let source_text = match field.as_str() {
-            "title" => task.title,
-            "description" => task.description.unwrap_or_default(),
-            _ => return Err("Invalid field type. Must be 'title' or 'description'".to_string()),
-        };
-        let api_key = translation_service::get_api_key(&db.conn)?;
-        (source_text, api_key)
-    };
-    
-    // Detect source language (release lock before await)
-    let source_lang = translation_service::detect_language(&source_text, api_key.as_deref()).await
-        .map_err(|e| format!("Failed to detect language: {}", e))?;
-    
-    // Save user-edited translation (re-lock for database write)
-    {
-        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-        translation_service::save_translation(
-            &db.conn,
-            &source_text,
-            &source_lang,
-            &target_lang,
-            &translated_text,
-            &field,
-            Some(&task_id),
-            true, // is_user_edited
-        )?;
+    before_task_id: Option<String>,
+) -> Result<Task, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    reorder_tasks_internal(&db.conn, &task_id, before_task_id.as_deref())
+}
+
+// Set the order index of every task in `order_map` in one transaction, for drag and drop that
+// already knows the whole list's order. Nothing is written if any task is missing.
+fn reorder_tasks_batch_internal(conn: &rusqlite::Connection, order_map: &[(String, i32)]) -> Result<(), String> {
+    let mut missing = Vec::new();
+    for (task_id, _) in order_map {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+            params![task_id],
+            |row| row.get(0),
+        ).map_err(|e| format!("Failed to look up task: {}", e))?;
+        if !exists && !missing.contains(&task_id.as_str()) {
+            missing.push(task_id.as_str());
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!("Tasks not found: {}", missing.join(", ")));
     }
     
-    Ok(())
+    let now = now();
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for (task_id, order_index) in order_map {
+        tx.execute(
+            "UPDATE tasks SET order_index = ?1, updated_at = ?2 WHERE id = ?3",
+            params![order_index, now, task_id],
+        ).map_err(|e| format!("Failed to reorder task: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))
 }
 
 #[tauri::command]
-pub async fn get_translation(
+pub fn reorder_tasks_batch(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-    field: String, // "title" or "description"
-    target_lang: String,
-) -> Result<Option<String>, String> {
-    // Check for user-edited translation and get source text while holding the lock
-    let (source_text, api_key) = {
-        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-        
-        // First check for user-edited translation
-        if let Ok(Some(user_trans)) = translation_service::get_user_translation(&db.conn, &task_id, &field, &target_lang) {
-            return Ok(Some(user_trans));
-        }
-        
-        // Get task to get source text
-        let task = fetch_task(&db.conn, &task_id)?;
-        let source_text = match field.as_str() {
-            "title" => task.title,
-            "description" => task.description.unwrap_or_default(),
-            _ => return Err("Invalid field type. Must be 'title' or 'description'".to_string()),
-        };
-        
-        if source_text.trim().is_empty() {
-            return Ok(None);
+    order_map: Vec<(String, i32)>,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    reorder_tasks_batch_internal(&db.conn, &order_map)
+}
+
+// Put `ids` in that order (order indexes 0, STEP, 2*STEP...) in one transaction and return them
+// re-fetched in their new order. With `project_id` every task must belong to that project (""
+// meaning the inbox). Tasks not listed keep their order index.
+fn reorder_task_list_internal(conn: &rusqlite::Connection, project_id: Option<&str>, ids: &[String]) -> Result<Vec<Task>, String> {
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = ids.iter().find(|id| !seen.insert(id.as_str())) {
+        return Err(format!("Task {} is listed more than once", duplicate));
+    }
+    if let Some(project_id) = project_id {
+        let project_id = Some(project_id).filter(|id| !id.is_empty());
+        for id in ids {
+            let in_project: Option<bool> = conn.query_row(
+                "SELECT project_id IS ?1 FROM tasks WHERE id = ?2",
+                params![project_id, id],
+                |row| row.get(0),
+            ).optional().map_err(|e| format!("Failed to look up task: {}", e))?;
+            if in_project == Some(false) {
+                return Err(format!("Task {} is not in the same list", id));
+            }
         }
-        
-        let api_key = translation_service::get_api_key(&db.conn)?;
-        (source_text, api_key)
-    };
+    }
     
-    // Detect source language (release lock before await)
-    let source_lang = translation_service::detect_language(&source_text, api_key.as_deref()).await
-        .map_err(|e| format!("Failed to detect language: {}", e))?;
-    
-    // Check cache (re-lock for database read)
-    let source_text_hash = translation_service::hash_text(&source_text);
-    let cached = {
-        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-        translation_service::get_cached_translation(
-            &db.conn,
-            &source_text_hash,
-            &source_lang,
-            &target_lang,
-            &field,
-        )?
-    };
+    let order_map: Vec<(String, i32)> = ids.iter()
+        .enumerate()
+        .map(|(position, id)| (id.clone(), position as i32 * ORDER_INDEX_STEP))
+        .collect();
+    reorder_tasks_batch_internal(conn, &order_map)?;
     
-    // If no cache, translate on the fly
-    if cached.is_none() {
-        let translated = translation_service::translate_text(
-            &source_text,
-            &target_lang,
-            api_key.as_deref(),
-        ).await?;
-        
-        // Save to cache
-        {
-            let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-            translation_service::save_translation(
-                &db.conn,
-                &source_text,
-                &source_lang,
-                &target_lang,
-                &translated,
-                &field,
-                Some(&task_id),
-                false,
-            )?;
-        }
-        
-        Ok(Some(translated))
-    } else {
-        Ok(cached)
-    }
+    let mut tasks = ids.iter().map(|id| fetch_task(conn, id)).collect::<Result<Vec<_>, _>>()?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
 }
 
-// Tag commands
+/// Reorder a whole list after drag and drop: `ids` in their new order, optionally checked to
+/// all be in `project_id` ("" for the inbox). Nothing changes if any task is missing.
 #[tauri::command]
-pub fn get_all_tags(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Tag>, String> {
+pub fn reorder_task_list(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    project_id: Option<String>,
+    ids: Vec<String>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let mut stmt = db.conn.prepare(
-        "SELECT id, name, color, created_at, usage_count FROM tags ORDER BY usage_count DESC, name"
+    reorder_task_list_internal(&db.conn, project_id.as_deref(), &ids)
+}
+
+// Order indexes shared by more than one task in a project, reported by the health check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateOrderIndex {
+    pub project_id: Option<String>,
+    pub order_index: i32,
+    pub task_count: i64,
+}
+
+fn get_duplicate_order_indexes_internal(conn: &rusqlite::Connection) -> Result<Vec<DuplicateOrderIndex>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT project_id, order_index, COUNT(*) FROM tasks
+         GROUP BY project_id, order_index HAVING COUNT(*) > 1
+         ORDER BY project_id, order_index"
     ).map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map([], |row| {
-        Ok(Tag {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            color: row.get(2)?,
-            created_at: row.get(3)?,
-            usage_count: row.get(4)?,
+    let duplicates = stmt.query_map([], |row| {
+        Ok(DuplicateOrderIndex {
+            project_id: row.get(0)?,
+            order_index: row.get(1)?,
+            task_count: row.get(2)?,
         })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
+    })
+    .map_err(|e| format!("Query execution error: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to read order indexes: {}", e))?;
+    Ok(duplicates)
+}
+
+// Delete one task and release its tags; returns false if there was no such task
+pub(crate) fn delete_task_internal(conn: &rusqlite::Connection, id: &str) -> Result<bool, String> {
+    // Get all tags associated with this task before deletion
+    let tag_ids: Vec<String> = conn.prepare(
+        "SELECT tag_id FROM task_tags WHERE task_id = ?1"
+    )
+    .map_err(|e| format!("Failed to query task tags: {}", e))?
+    .query_map(params![id], |row| row.get(0))
+    .map_err(|e| format!("Failed to execute query: {}", e))?
+    .collect::<Result<Vec<String>, _>>()
+    .map_err(|e| format!("Failed to collect tag IDs: {}", e))?;
     
-    let mut tags = Vec::new();
-    for row in rows {
-        tags.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    // Delete the task (CASCADE will handle task_tags deletion)
+    let deleted = conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete task: {}", e))?;
+    
+    // Update usage_count for each affected tag
+    for tag_id in tag_ids {
+        conn.execute(
+            "UPDATE tags SET usage_count = MAX(0, usage_count - 1) WHERE id = ?1",
+            params![tag_id],
+        ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
     }
     
-    Ok(tags)
+    Ok(deleted > 0)
 }
 
 #[tauri::command]
-pub fn get_task_tags(db: State<'_, Arc<Mutex<DbConnection>>>, task_id: String) -> Result<Vec<Tag>, String> {
+pub fn delete_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    id: String,
+) -> Result<(), String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    fetch_task_tags(&db.conn, &task_id)
+    let title: Option<String> = db.conn.query_row("SELECT title FROM tasks WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Query error: {}", e))?;
+    let mut entry = UndoEntry::new(format!("Deleted task \"{}\"", title.unwrap_or_default()));
+    entry.capture_tasks(&db.conn, "id = ?1", &id)?;
+    
+    if delete_task_internal(&db.conn, &id)? {
+        undo.record(entry);
+    }
+    Ok(())
 }
 
+/// Reverse the most recent delete (task, project, subtask, tag or tag removal) recorded this
+/// session. Returns what was restored, for a toast, or None if there is nothing to undo.
 #[tauri::command]
-pub fn create_tag(db: State<'_, Arc<Mutex<DbConnection>>>, input: CreateTagInput) -> Result<Tag, String> {
+pub fn undo_last_operation(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+) -> Result<Option<String>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    undo.undo_last(&db.conn)
+}
+
+// Delete many tasks in one transaction; unknown ids are skipped. Returns how many were deleted.
+fn bulk_delete_tasks_internal(conn: &rusqlite::Connection, ids: &[String]) -> Result<usize, String> {
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
     
-    // Normalize tag name to lowercase and trim
-    let normalized_name = input.name.trim().to_lowercase();
-    
-    if normalized_name.is_empty() {
-        return Err("Tag name cannot be empty".to_string());
-    }
-    
-    // Check if tag already exists
-    let existing: Option<Tag> = db.conn.query_row(
-        "SELECT id, name, color, created_at, usage_count FROM tags WHERE name = ?1",
-        params![normalized_name],
-        |row| {
-            Ok(Tag {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                created_at: row.get(3)?,
-                usage_count: row.get(4)?,
-            })
-        },
-    ).ok();
-    
-    if let Some(tag) = existing {
-        return Ok(tag);
+    let mut deleted = 0;
+    for id in ids {
+        if delete_task_internal(&tx, id)? {
+            deleted += 1;
+        }
     }
     
-    // Create new tag
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    db.conn.execute(
-        "INSERT INTO tags (id, name, color, created_at, usage_count) VALUES (?1, ?2, ?3, ?4, 0)",
-        params![id.clone(), normalized_name, input.color, now],
-    ).map_err(|e| format!("Failed to create tag: {}", e))?;
-    
-    Ok(Tag {
-        id,
-        name: normalized_name,
-        color: input.color,
-        created_at: now,
-        usage_count: 0,
-    })
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(deleted)
 }
 
 #[tauri::command]
-pub fn delete_tag(db: State<'_, Arc<Mutex<DbConnection>>>, tag_id: String) -> Result<(), String> {
+pub fn bulk_delete_tasks(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    ids: Vec<String>,
+) -> Result<usize, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let mut entry = UndoEntry::new("");
+    for id in &ids {
+        entry.capture_tasks(&db.conn, "id = ?1", id)?;
+    }
     
-    // CASCADE will handle task_tags deletion
-    db.conn.execute("DELETE FROM tags WHERE id = ?1", params![tag_id])
-        .map_err(|e| format!("Failed to delete tag: {}", e))?;
-    
-    Ok(())
+    let deleted = bulk_delete_tasks_internal(&db.conn, &ids)?;
+    if deleted > 0 {
+        entry.description = format!("Deleted {} task{}", deleted, if deleted == 1 { "" } else { "s" });
+        undo.record(entry);
+    }
+    Ok(deleted)
+}
+
+// Titles that imports and old versions used for a task without one, compared in lowercase
+const EMPTY_TITLE_PLACEHOLDERS: &[&str] = &["untitled", "untitled task", "new task", "no title", "(no title)", "-", "..."];
+
+// Tasks whose title is blank or one of EMPTY_TITLE_PLACEHOLDERS, limited to `ids` when given
+fn empty_task_ids(conn: &rusqlite::Connection, ids: Option<&[String]>) -> Result<Vec<String>, String> {
+    if ids.is_some_and(|ids| ids.is_empty()) {
+        return Ok(Vec::new());
+    }
+    let mut query = format!(
+        "SELECT id FROM tasks WHERE LOWER(TRIM(title, ' ' || char(9) || char(10) || char(13))) IN ('', {})",
+        vec!["?"; EMPTY_TITLE_PLACEHOLDERS.len()].join(", ")
+    );
+    let mut query_params: Vec<&dyn rusqlite::ToSql> = EMPTY_TITLE_PLACEHOLDERS.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+    if let Some(ids) = ids {
+        query.push_str(&format!(" AND id IN ({})", vec!["?"; ids.len()].join(", ")));
+        query_params.extend(ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+    }
+    query.push_str(" ORDER BY created_at, id");
+    
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let ids = stmt.query_map(query_params.as_slice(), |row| row.get(0))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    Ok(ids)
+}
+
+// Tasks left without a real title by imports or old versions, archived ones included
+fn find_empty_tasks_internal(conn: &rusqlite::Connection) -> Result<Vec<Task>, String> {
+    let mut tasks = Vec::new();
+    for id in empty_task_ids(conn, None)? {
+        tasks.push(fetch_task(conn, &id)?);
+    }
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
 }
 
 #[tauri::command]
-pub fn add_tag_to_task(
+pub fn find_empty_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-    tag_id: String,
-) -> Result<(), String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    
-    // Add tag to task (ignore if already exists due to UNIQUE constraint)
-    match db.conn.execute(
-        "INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES (?1, ?2, ?3, ?4)",
-        params![id, task_id, tag_id.clone(), now],
-    ) {
-        Ok(_) => {
-            // Increment usage count
-            db.conn.execute(
-                "UPDATE tags SET usage_count = usage_count + 1 WHERE id = ?1",
-                params![tag_id],
-            ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
-            Ok(())
-        }
-        Err(e) => {
-            if e.to_string().contains("UNIQUE constraint failed") {
-                Ok(()) // Tag already added, this is fine
-            } else {
-                Err(format!("Failed to add tag to task: {}", e))
-            }
-        }
-    }
+    Ok(demo.apply(find_empty_tasks_internal(&db.conn)?))
 }
 
+/// Delete the given tasks from find_empty_tasks, or all of them when `ids` is omitted. Tasks
+/// that have since been given a title are kept. Like bulk_delete_tasks this can be undone.
 #[tauri::command]
-pub fn remove_tag_from_task(
+pub fn delete_empty_tasks(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-    tag_id: String,
-) -> Result<(), String> {
+    undo: State<'_, UndoJournal>,
+    ids: Option<Vec<String>>,
+) -> Result<usize, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let ids = empty_task_ids(&db.conn, ids.as_deref())?;
+    let mut entry = UndoEntry::new("");
+    for id in &ids {
+        entry.capture_tasks(&db.conn, "id = ?1", id)?;
+    }
     
-    // Remove tag from task
-    let rows_affected = db.conn.execute(
-        "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
-        params![task_id, tag_id.clone()],
-    ).map_err(|e| format!("Failed to remove tag from task: {}", e))?;
+    let deleted = bulk_delete_tasks_internal(&db.conn, &ids)?;
+    if deleted > 0 {
+        entry.description = format!("Deleted {} empty task{}", deleted, if deleted == 1 { "" } else { "s" });
+        undo.record(entry);
+    }
+    Ok(deleted)
+}
+
+const PRIORITIES: &[&str] = &["low", "medium", "high"];
+
+// Set the priority of many tasks in one UPDATE; returns how many rows matched. Completed tasks
+// whose priority actually changes have their completion XP brought in line with the new priority.
+fn bulk_update_priority_internal(conn: &rusqlite::Connection, ids: &[String], priority: &str) -> Result<usize, String> {
+    if !PRIORITIES.contains(&priority) {
+        return Err(format!("Invalid priority: {} (expected low, medium or high)", priority));
+    }
+    if ids.is_empty() {
+        return Ok(0);
+    }
     
-    // Decrement usage count if a row was deleted
-    if rows_affected > 0 {
-        db.conn.execute(
-            "UPDATE tags SET usage_count = MAX(0, usage_count - 1) WHERE id = ?1",
-            params![tag_id],
-        ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    
+    let placeholders = (2..ids.len() + 2).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+    let mut query_params: Vec<&dyn rusqlite::ToSql> = vec![&priority];
+    query_params.extend(ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+    
+    let rescored: Vec<String> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT id FROM tasks WHERE id IN ({}) AND completed_at IS NOT NULL AND priority != ?1",
+            placeholders
+        )).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt.query_map(query_params.as_slice(), |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("Row parsing error: {}", e))?
+    };
+    
+    let now = now();
+    let timestamp_index = ids.len() + 2;
+    query_params.push(&now);
+    let updated = tx.execute(
+        &format!(
+            "UPDATE tasks SET priority = ?1, updated_at = ?{} WHERE id IN ({})",
+            timestamp_index, placeholders
+        ),
+        query_params.as_slice(),
+    ).map_err(|e| format!("Failed to update priority: {}", e))?;
+    
+    for id in &rescored {
+        rescore_completion_xp(&tx, id, priority)?;
     }
     
-    Ok(())
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(updated)
 }
 
 #[tauri::command]
-pub fn get_suggested_tags(
+pub fn bulk_update_priority(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    search: String,
-) -> Result<Vec<Tag>, String> {
+    ids: Vec<String>,
+    priority: String,
+) -> Result<usize, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    bulk_update_priority_internal(&db.conn, &ids, &priority)
+}
+
+// Move the due dates of a project's open tasks and/or specific tasks by `days`. With both a
+// project and ids only tasks in both sets move; tasks without a due date are left alone.
+fn shift_task_due_dates_internal(
+    conn: &rusqlite::Connection,
+    project_id: Option<&str>,
+    ids: Option<&[String]>,
+    days: i64,
+    now: i64,
+) -> Result<usize, String> {
+    if project_id.is_none() && ids.is_none() {
+        return Err("Specify a project or task ids to shift".to_string());
+    }
+    if days == 0 || ids.is_some_and(|ids| ids.is_empty()) {
+        return Ok(0);
+    }
     
-    let search_pattern = format!("%{}%", search.trim().to_lowercase());
+    let mut conditions = String::from("due_at IS NOT NULL");
+    let mut query_params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+    if let Some(project_id) = &project_id {
+        conditions.push_str(" AND project_id = ? AND completed_at IS NULL");
+        query_params.push(project_id);
+    }
+    if let Some(ids) = ids {
+        conditions.push_str(&format!(" AND id IN ({})", vec!["?"; ids.len()].join(", ")));
+        query_params.extend(ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+    }
     
-    let mut stmt = db.conn.prepare(
-        "SELECT id, name, color, created_at, usage_count FROM tags 
-         WHERE name LIKE ?1 
-         ORDER BY usage_count DESC, name 
-         LIMIT 10"
-    ).map_err(|e| format!("Query error: {}", e))?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
     
-    let rows = stmt.query_map(params![search_pattern], |row| {
-        Ok(Tag {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            color: row.get(2)?,
-            created_at: row.get(3)?,
-            usage_count: row.get(4)?,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
+    let tasks: Vec<(String, i64, Option<i32>)> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT id, due_at, reminder_minutes_before FROM tasks WHERE {}",
+            conditions
+        )).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt.query_map(query_params.as_slice(), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("Row parsing error: {}", e))?
+    };
     
-    let mut tags = Vec::new();
-    for row in rows {
-        tags.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    for (id, old_due_at, reminder_minutes) in &tasks {
+        let new_due_at = old_due_at + days * 86400;
+        tx.execute(
+            "UPDATE tasks SET due_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![new_due_at, now, id],
+        ).map_err(|e| format!("Failed to update due date: {}", e))?;
+        record_due_date_change(&tx, id, Some(*old_due_at), Some(new_due_at), "shift")?;
+        log_activity(&tx, id, "due_date_changed", Some(&old_due_at.to_string()), Some(&new_due_at.to_string()), "shift")?;
+        
+        if reminder_minutes.is_some() {
+            crate::notifications::reschedule_reminder(&tx, id, *reminder_minutes, now)
+                .map_err(|e| format!("Failed to reschedule reminder: {}", e))?;
+        }
     }
     
-    Ok(tags)
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(tasks.len())
 }
 
+/// Move due dates by `days` (negative to bring them forward) for a project's open tasks, the
+/// given tasks, or the given tasks within the project. Returns how many tasks moved.
 #[tauri::command]
-pub fn get_tasks_by_tag(
+pub fn shift_task_due_dates(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    tag_id: String,
-) -> Result<Vec<Task>, String> {
+    project_id: Option<String>,
+    ids: Option<Vec<String>>,
+    days: i64,
+) -> Result<usize, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    shift_task_due_dates_internal(&db.conn, project_id.as_deref(), ids.as_deref(), days, now())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToggleCompleteResult {
+    pub task: Task,
+    pub completed: bool,
+    pub xp_granted: i32,
+    pub xp_revoked: i32,
+    pub level_up: bool,
+    pub new_level: i32,
+    pub total_xp: i64,
+    pub streak_extended: bool,
+    pub current_streak: i32,
+    pub first_completion_today: bool,
+    pub new_badges: Vec<Badge>,
+    pub current_task_cleared: bool,
+}
+
+// Helper function to complete or reopen one task, shared by toggle and bulk completion: clears
+// the previous note and board status, logs the change, creates the next recurring instance and
+// forgets the task as current task. Returns whether the current task was cleared.
+fn set_completed_at(
+    conn: &rusqlite::Connection,
+    id: &str,
+    completed_at: Option<i64>,
+    recurrence_type: &str,
+    recurrence_interval: i32,
+    now: i64,
+) -> Result<bool, String> {
+    // Any previous completion note belongs to the previous completion
+    conn.execute(
+        "UPDATE tasks SET completed_at = ?1, updated_at = ?2, completion_note = NULL, status = NULL WHERE id = ?3",
+        params![completed_at, now, id],
+    ).map_err(|e| format!("Failed to toggle complete: {}", e))?;
+    log_activity(conn, id, if completed_at.is_some() { "completed" } else { "reopened" }, None, None, "user")?;
     
-    let mut stmt = db.conn.prepare(
-        "SELECT t.id, t.title, t.description, t.due_at, t.created_at, t.updated_at, t.priority, 
-         t.completed_at, t.project_id, t.order_index, t.metadata, t.recurrence_type, 
-         t.recurrence_interval, t.recurrence_parent_id, t.reminder_minutes_before, t.notification_repeat
-         FROM tasks t
-         INNER JOIN task_tags tt ON t.id = tt.task_id
-         WHERE tt.tag_id = ?1
-         ORDER BY t.order_index, t.created_at"
-    ).map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map(params![tag_id], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: row.get(14).ok().flatten(),
-            notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
-            tags: None,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
+    if completed_at.is_none() {
+        return Ok(false);
+    }
+    if recurrence_type != "none" {
+        crate::services::task_service::create_recurring_instance(conn, id, recurrence_type, recurrence_interval)?;
+    }
+    clear_current_task_if(conn, id)
+}
+
+// Helper function to toggle completion and collect what happened to XP, streak and badges
+pub(crate) fn toggle_complete_internal(conn: &rusqlite::Connection, id: &str) -> Result<ToggleCompleteResult, String> {
+    // Get current task state
+    let task_info: (Option<i64>, String, i32, String) = conn.query_row(
+        "SELECT completed_at, recurrence_type, recurrence_interval, priority FROM tasks WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).map_err(|e| format!("Task not found: {}", e))?;
     
-    let mut tasks = Vec::new();
-    for row in rows {
-        let mut task = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        task.tags = Some(fetch_task_tags(&db.conn, &task.id)?);
-        tasks.push(task);
+    let (completed, recurrence_type, recurrence_interval, priority) = task_info;
+    let was_completed = completed.is_some();
+    let now = now();
+    let new_completed = if completed.is_some() { None } else { Some(now) };
+    
+    let current_task_cleared = set_completed_at(conn, id, new_completed, &recurrence_type, recurrence_interval, now)?;
+    
+    let progress_before = get_user_progress_internal(conn)?;
+    let mut xp_granted = 0;
+    let mut xp_revoked = 0;
+    let mut level_up = false;
+    let mut new_badges = Vec::new();
+    
+    // Handle gamification: grant XP when completing, revoke XP when undoing
+    if new_completed.is_some() && !was_completed {
+        // Task is being completed - grant XP based on priority
+        let xp_amount = xp_for_priority(&priority);
+        
+        // Grant XP
+        if let Ok(grant) = grant_xp_internal(conn, xp_amount, "task_completion".to_string(), Some(id.to_string())) {
+            xp_granted = xp_amount;
+            level_up = grant.level_up;
+        }
+        
+        // Update streak
+        let _ = update_streak_internal(conn);
+        
+        // Check for badges
+        new_badges = check_and_award_badges_internal(conn).unwrap_or_default();
+    } else if was_completed && new_completed.is_none() {
+        // Task is being uncompleted - revoke XP
+        // Find the most recent XP history entry for this task
+        let xp_entry: Option<(i32, String)> = conn.query_row(
+            "SELECT xp_amount, id FROM xp_history WHERE task_id = ?1 AND source = 'task_completion' ORDER BY created_at DESC LIMIT 1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).ok();
+        
+        if let Some((xp_amount, history_id)) = xp_entry {
+            // Revoke the XP
+            if revoke_xp_internal(conn, xp_amount, history_id).is_ok() {
+                xp_revoked = xp_amount;
+            }
+            
+            // Update streak
+            let _ = update_streak_internal(conn);
+        }
     }
     
-    Ok(tasks)
+    let progress_after = get_user_progress_internal(conn)?;
+    let today_start = (now / 86400) * 86400;
+    
+    Ok(ToggleCompleteResult {
+        task: fetch_task(conn, id)?,
+        completed: new_completed.is_some(),
+        xp_granted,
+        xp_revoked,
+        level_up,
+        new_level: progress_after.current_level,
+        total_xp: progress_after.total_xp,
+        streak_extended: progress_after.current_streak > progress_before.current_streak,
+        current_streak: progress_after.current_streak,
+        // Same day boundary as update_streak_internal
+        first_completion_today: new_completed.is_some() && progress_before.last_completion_date != Some(today_start),
+        new_badges,
+        current_task_cleared,
+    })
 }
 
-#[tauri::command]
-pub fn recalculate_tag_usage_counts(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<(), String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompletionWarning {
+    pub kind: String, // "open_subtasks", "open_blockers" or "pending_reminders"
+    pub count: usize,
+    pub samples: Vec<String>,
+}
+
+// Number of example titles/times included per warning
+const COMPLETION_WARNING_SAMPLES: usize = 3;
+// Reminders scheduled further out than this make completing the task look accidental
+const FAR_REMINDER_SECONDS: i64 = 24 * 60 * 60;
+
+// Helper function to collect reasons why completing a task may be a mistake
+fn get_completion_warnings_internal(conn: &rusqlite::Connection, id: &str) -> Result<Vec<CompletionWarning>, String> {
+    let mut warnings = Vec::new();
+    
+    let open_subtasks: Vec<String> = fetch_subtasks(conn, id)?
+        .into_iter()
+        .filter(|subtask| !subtask.completed)
+        .map(|subtask| subtask.title)
+        .collect();
+    if !open_subtasks.is_empty() {
+        warnings.push(CompletionWarning {
+            kind: "open_subtasks".to_string(),
+            count: open_subtasks.len(),
+            samples: open_subtasks.into_iter().take(COMPLETION_WARNING_SAMPLES).collect(),
+        });
+    }
     
-    // Update all tags with correct usage counts based on actual task_tags records
-    db.conn.execute(
-        "UPDATE tags SET usage_count = (
-            SELECT COUNT(*) FROM task_tags WHERE task_tags.tag_id = tags.id
-        )",
-        [],
-    ).map_err(|e| format!("Failed to recalculate tag usage counts: {}", e))?;
+    let open_blockers: Vec<String> = fetch_blocking_tasks(conn, id)?
+        .into_iter()
+        .filter(|task| !task.completed)
+        .map(|task| task.title)
+        .collect();
+    if !open_blockers.is_empty() {
+        warnings.push(CompletionWarning {
+            kind: "open_blockers".to_string(),
+            count: open_blockers.len(),
+            samples: open_blockers.into_iter().take(COMPLETION_WARNING_SAMPLES).collect(),
+        });
+    }
     
-    Ok(())
+    let mut stmt = conn.prepare(
+        "SELECT scheduled_at FROM notification_schedule WHERE task_id = ?1 AND scheduled_at > ?2 ORDER BY scheduled_at"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![id, now() + FAR_REMINDER_SECONDS], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    let mut reminders = Vec::new();
+    for row in rows {
+        reminders.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    if !reminders.is_empty() {
+        use chrono::TimeZone;
+        warnings.push(CompletionWarning {
+            kind: "pending_reminders".to_string(),
+            count: reminders.len(),
+            samples: reminders
+                .into_iter()
+                .take(COMPLETION_WARNING_SAMPLES)
+                .filter_map(|at| chrono::Local.timestamp_opt(at, 0).single())
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .collect(),
+        });
+    }
+    
+    Ok(warnings)
 }
 
-#[tauri::command]
-pub fn get_tasks_by_tags(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    tag_ids: Vec<String>,
-) -> Result<Vec<Task>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+// Helper function to refuse completing a task with open linked items unless acknowledged
+pub(crate) fn ensure_completion_acknowledged(conn: &rusqlite::Connection, id: &str, acknowledge: Option<bool>) -> Result<(), String> {
+    if acknowledge == Some(true) {
+        return Ok(());
+    }
     
-    if tag_ids.is_empty() {
-        return Ok(Vec::new());
+    // Only completing is checked; reopening a task is always allowed
+    let completed_at: Option<i64> = conn.query_row(
+        "SELECT completed_at FROM tasks WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    if completed_at.is_some() {
+        return Ok(());
     }
     
-    // Build query with placeholders for each tag_id
-    let placeholders = tag_ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
-    let query = format!(
-        "SELECT DISTINCT t.id, t.title, t.description, t.due_at, t.created_at, t.updated_at, t.priority, 
-         t.completed_at, t.project_id, t.order_index, t.metadata, t.recurrence_type, 
-         t.recurrence_interval, t.recurrence_parent_id, t.reminder_minutes_before, t.notification_repeat
-         FROM tasks t
-         INNER JOIN task_tags tt ON t.id = tt.task_id
-         WHERE tt.tag_id IN ({})
-         ORDER BY t.order_index, t.created_at",
-        placeholders
-    );
+    let warnings = get_completion_warnings_internal(conn, id)?;
+    if warnings.is_empty() {
+        return Ok(());
+    }
     
-    let mut stmt = db.conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
-    let params: Vec<&dyn rusqlite::ToSql> = tag_ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+    let summary: Vec<String> = warnings.iter().map(|w| format!("{} {}", w.count, w.kind.replace('_', " "))).collect();
+    Err(format!("Task has {}; pass acknowledge to complete it anyway", summary.join(", ")))
+}
+
+/// Returned by toggle_complete when the task requires a completion note and none was given
+pub const COMPLETION_NOTE_REQUIRED_ERROR: &str = "completion_note_required";
+// Stored instead of a note when a task that requires one is completed with skip_note
+const NO_NOTE_MARKER: &str = "(no note)";
+
+// Helper function to work out the note to record when completing a task (None if there is nothing
+// to record). Reopening is always allowed; `force` completes a task that requires a note without one.
+pub(crate) fn resolve_completion_note(
+    conn: &rusqlite::Connection,
+    id: &str,
+    note: Option<&str>,
+    force: bool,
+) -> Result<Option<String>, String> {
+    let (completed_at, required): (Option<i64>, bool) = conn.query_row(
+        "SELECT completed_at, completion_note_required FROM tasks WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    if completed_at.is_some() {
+        return Ok(None);
+    }
     
-    let rows = stmt.query_map(&params[..], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: row.get(14).ok().flatten(),
-            notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
-            tags: None,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
+    match note.map(str::trim).filter(|note| !note.is_empty()) {
+        Some(note) => Ok(Some(note.to_string())),
+        None if !required => Ok(None),
+        None if force => Ok(Some(NO_NOTE_MARKER.to_string())),
+        None => Err(COMPLETION_NOTE_REQUIRED_ERROR.to_string()),
+    }
+}
+
+// Helper function to keep a completion note on the task and in the activity log
+pub(crate) fn record_completion_note(conn: &rusqlite::Connection, id: &str, note: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE tasks SET completion_note = ?1 WHERE id = ?2",
+        params![note, id],
+    ).map_err(|e| format!("Failed to save completion note: {}", e))?;
+    log_activity(conn, id, "completion_note", None, Some(note), "completion")
+}
+
+/// Conditions that make completing a task look accidental, for a confirm dialog before toggle_complete
+#[tauri::command]
+pub fn get_completion_warnings(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    id: String,
+) -> Result<Vec<CompletionWarning>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_completion_warnings_internal(&db.conn, &id)?))
+}
+
+// Helper function for the toggle commands: checks the completion warnings and note, then toggles
+// and records the note in one transaction so a failed note write doesn't leave the task completed
+fn toggle_complete_checked(
+    conn: &rusqlite::Connection,
+    id: &str,
+    acknowledge: Option<bool>,
+    note: Option<&str>,
+    skip_note: bool,
+) -> Result<ToggleCompleteResult, String> {
+    ensure_completion_acknowledged(conn, id, acknowledge)?;
+    let note = resolve_completion_note(conn, id, note, skip_note)?;
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let result = toggle_complete_internal(&tx, id)?;
+    if let Some(note) = &note {
+        record_completion_note(&tx, id, note)?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(result)
+}
+
+/// Toggle a task's completion. `acknowledge` completes it despite completion warnings and
+/// `skip_note` completes a task that requires a note without one.
+#[tauri::command]
+pub fn toggle_complete(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    acknowledge: Option<bool>,
+    note: Option<String>,
+    skip_note: Option<bool>,
+) -> Result<Task, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     
-    let mut tasks = Vec::new();
-    for row in rows {
-        let mut task = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        task.tags = Some(fetch_task_tags(&db.conn, &task.id)?);
-        tasks.push(task);
+    let result = toggle_complete_checked(&db.conn, &id, acknowledge, note.as_deref(), skip_note == Some(true))?;
+    if result.current_task_cleared {
+        refresh_tray_tooltip(&app_handle, &db.conn);
     }
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
     
-    Ok(tasks)
+    Ok(result.task)
 }
 
-// Task relationship commands
+/// Like toggle_complete, but also returns the XP, level, streak and badge changes so the
+/// frontend can pick a celebration without querying each of them afterwards
 #[tauri::command]
-pub fn create_task_relationship(
+pub fn toggle_complete_detailed(
+    app_handle: tauri::AppHandle,
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    input: CreateRelationshipInput,
-) -> Result<TaskRelationship, String> {
+    id: String,
+    acknowledge: Option<bool>,
+    note: Option<String>,
+    skip_note: Option<bool>,
+) -> Result<ToggleCompleteResult, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     
-    // Prevent self-relationships
-    if input.task_id_1 == input.task_id_2 {
-        return Err("Cannot create relationship between a task and itself".to_string());
+    let result = toggle_complete_checked(&db.conn, &id, acknowledge, note.as_deref(), skip_note == Some(true))?;
+    if result.current_task_cleared {
+        refresh_tray_tooltip(&app_handle, &db.conn);
     }
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
     
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = now();
-    let relationship_type = input.relationship_type.unwrap_or_else(|| "related".to_string());
-    
-    // Check for circular dependencies if relationship type is 'blocks'
-    if relationship_type == "blocks" {
-        if check_circular_dependency_internal(&db.conn, &input.task_id_1, &input.task_id_2)? {
-            return Err("Cannot create blocking relationship: would create circular dependency".to_string());
-        }
+    Ok(result)
+}
+
+// Close an open recurring instance without completing it (so no XP or completion stats) and
+// create the next one. The skipped instance is archived. Returns the next instance, or None
+// when the recurrence has reached its end.
+fn skip_recurring_instance_internal(conn: &rusqlite::Connection, id: &str) -> Result<Option<Task>, String> {
+    let (completed_at, archived, recurrence_type, recurrence_interval): (Option<i64>, bool, String, i32) = conn.query_row(
+        "SELECT completed_at, archived, recurrence_type, recurrence_interval FROM tasks WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    if recurrence_type == "none" {
+        return Err("Task is not recurring".to_string());
+    }
+    if completed_at.is_some() || archived {
+        return Err("Only an open instance can be skipped".to_string());
     }
     
-    db.conn.execute(
-        "INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![id.clone(), input.task_id_1.clone(), input.task_id_2.clone(), relationship_type.clone(), now],
-    ).map_err(|e| {
-        if e.to_string().contains("UNIQUE constraint failed") {
-            "Relationship already exists between these tasks".to_string()
-        } else {
-            format!("Failed to create task relationship: {}", e)
-        }
-    })?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "UPDATE tasks SET archived = 1, status = NULL, updated_at = ?1 WHERE id = ?2",
+        params![now(), id],
+    ).map_err(|e| format!("Failed to skip task: {}", e))?;
+    log_activity(&tx, id, "skipped", None, None, "recurrence")?;
+    clear_current_task_if(&tx, id)?;
+    crate::notifications::cancel_task_notifications(&tx, id)
+        .map_err(|e| format!("Failed to cancel reminders: {}", e))?;
+    crate::services::task_service::create_recurring_instance(&tx, id, &recurrence_type, recurrence_interval)?;
+    let next_id: Option<String> = tx.query_row(
+        "SELECT id FROM tasks WHERE recurrence_parent_id = ?1 ORDER BY created_at DESC, rowid DESC LIMIT 1",
+        params![id],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Query error: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
     
-    Ok(TaskRelationship {
-        id,
-        task_id_1: input.task_id_1,
-        task_id_2: input.task_id_2,
-        relationship_type,
-        created_at: now,
-    })
+    next_id.map(|next_id| fetch_task(conn, &next_id)).transpose()
 }
 
 #[tauri::command]
-pub fn delete_task_relationship(
+pub fn skip_recurring_instance(
+    app_handle: tauri::AppHandle,
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    relationship_id: String,
-) -> Result<(), String> {
+    id: String,
+) -> Result<Option<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let next = skip_recurring_instance_internal(&db.conn, &id)?;
+    refresh_tray_tooltip(&app_handle, &db.conn);
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
+    Ok(next)
+}
+
+/// Edits for every open task in a recurrence chain; see UpdateTaskInput for the recurrence fields
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateSeriesInput {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub recurrence_type: Option<String>,
+    #[serde(default)]
+    pub recurrence_interval: Option<i32>,
+    #[serde(default)]
+    pub recurrence_days_of_week: Option<Vec<u32>>,
+    #[serde(default)]
+    pub recurrence_end_date: Option<i64>,
+    #[serde(default)]
+    pub recurrence_count: Option<i32>,
+}
+
+// Apply `input` to the open tasks in the chain `id` belongs to, root included if it is still
+// open; completed (and skipped, i.e. archived) instances keep what they were. Returns the
+// updated tasks.
+fn update_recurring_series_internal(conn: &rusqlite::Connection, id: &str, input: UpdateSeriesInput) -> Result<Vec<Task>, String> {
+    let root_id: String = conn.query_row(
+        "SELECT COALESCE(recurrence_root_id, id) FROM tasks WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    if let Some(title) = &input.title {
+        if title.trim().is_empty() {
+            return Err("Task title cannot be empty".to_string());
+        }
+    }
     
-    db.conn.execute(
-        "DELETE FROM task_relationships WHERE id = ?1",
-        params![relationship_id],
-    ).map_err(|e| format!("Failed to delete task relationship: {}", e))?;
+    let open_ids: Vec<String> = conn.prepare(
+        "SELECT id FROM tasks WHERE COALESCE(recurrence_root_id, id) = ?1 AND completed_at IS NULL AND archived = 0 ORDER BY created_at, rowid"
+    ).map_err(|e| format!("Query error: {}", e))?
+        .query_map(params![root_id], |row| row.get(0))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
     
-    Ok(())
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut updated = Vec::new();
+    for task_id in &open_ids {
+        updated.push(update_task_internal(&tx, task_id, UpdateTaskInput {
+            title: input.title.clone(),
+            description: input.description.clone(),
+            due_date: None,
+            priority: input.priority.clone(),
+            project_id: None,
+            order_index: None,
+            recurrence_type: input.recurrence_type.clone(),
+            recurrence_interval: input.recurrence_interval,
+            reminder_minutes_before: None,
+            notification_repeat: None,
+            reminder_sound: None,
+            reminder_urgency: None,
+            completion_note_required: None,
+            expected_updated_at: None,
+            status: None,
+            force: None,
+            recurrence_days_of_week: input.recurrence_days_of_week.clone(),
+            recurrence_end_date: input.recurrence_end_date,
+            recurrence_count: input.recurrence_count,
+            estimated_minutes: None,
+            actual_minutes: None,
+        })?);
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(updated)
 }
 
 #[tauri::command]
-pub fn get_related_tasks(
+pub fn update_recurring_series(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
+    id: String,
+    input: UpdateSeriesInput,
 ) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Get all tasks related to this task (bidirectional)
-    let mut stmt = db.conn.prepare(
-        "SELECT DISTINCT t.id, t.title, t.description, t.due_at, t.created_at, t.updated_at, t.priority, 
-         t.completed_at, t.project_id, t.order_index, t.metadata, t.recurrence_type, 
-         t.recurrence_interval, t.recurrence_parent_id, t.reminder_minutes_before, t.notification_repeat
-         FROM tasks t
-         WHERE t.id IN (
-            SELECT task_id_2 FROM task_relationships WHERE task_id_1 = ?1
-            UNION
-            SELECT task_id_1 FROM task_relationships WHERE task_id_2 = ?1
-         )
-         ORDER BY t.order_index, t.created_at"
-    ).map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map(params![task_id], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: row.get(14).ok().flatten(),
-            notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
-            tags: None,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut tasks = Vec::new();
-    for row in rows {
-        let mut task = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        task.tags = Some(fetch_task_tags(&db.conn, &task.id)?);
-        tasks.push(task);
-    }
-    
-    Ok(tasks)
+    update_recurring_series_internal(&db.conn, &id, input)
 }
 
-// Helper function to check circular dependencies
-fn check_circular_dependency_internal(
-    conn: &rusqlite::Connection,
-    blocking_task_id: &str,
-    blocked_task_id: &str,
-) -> Result<bool, String> {
-    // Check if adding this relationship would create a cycle
-    // Use recursive CTE to traverse the dependency graph starting from blocked_task_id
-    // If we can reach blocking_task_id, then creating this relationship would create a cycle
-    let query = "
-        WITH RECURSIVE dependency_chain(task_id, depth) AS (
-            SELECT ?1 AS task_id, 0 AS depth
-            UNION ALL
-            SELECT tr.task_id_1, dc.depth + 1
-            FROM task_relationships tr
-            INNER JOIN dependency_chain dc ON tr.task_id_2 = dc.task_id
-            WHERE tr.relationship_type = 'blocks' AND dc.depth < 100
-        )
-        SELECT COUNT(*) FROM dependency_chain WHERE task_id = ?2
-    ";
-    
-    let count: i64 = conn.query_row(
-        query,
-        params![blocked_task_id, blocking_task_id],
+// Current ("working on") task, kept in settings so it survives restarts
+
+const CURRENT_TASK_SETTING: &str = "current_task_id";
+
+pub(crate) fn get_current_task_id(conn: &rusqlite::Connection) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![CURRENT_TASK_SETTING],
         |row| row.get(0),
-    ).map_err(|e| format!("Failed to check circular dependency: {}", e))?;
+    ).ok()
+}
+
+// Helper function to drop the current task once it is completed; returns whether it was the current one
+fn clear_current_task_if(conn: &rusqlite::Connection, task_id: &str) -> Result<bool, String> {
+    let cleared = conn.execute(
+        "DELETE FROM settings WHERE key = ?1 AND value = ?2",
+        params![CURRENT_TASK_SETTING, task_id],
+    ).map_err(|e| format!("Failed to clear current task: {}", e))?;
+    Ok(cleared > 0)
+}
+
+// Helper function to resolve the current task, forgetting it if it was deleted or completed meanwhile
+// (unless `read_only`, as in demo mode, where it is just not returned)
+fn get_current_task_internal(conn: &rusqlite::Connection, read_only: bool) -> Result<Option<Task>, String> {
+    let task_id = match get_current_task_id(conn) {
+        Some(task_id) => task_id,
+        None => return Ok(None),
+    };
     
-    Ok(count > 0)
+    match fetch_task(conn, &task_id) {
+        Ok(task) if !task.completed => Ok(Some(task)),
+        _ if read_only => Ok(None),
+        _ => {
+            clear_current_task_if(conn, &task_id)?;
+            Ok(None)
+        }
+    }
+}
+
+// Today's status line for the local day containing `now`
+fn todays_status_line(conn: &rusqlite::Connection, now: chrono::DateTime<chrono::Local>) -> Result<status_service::StatusLine, String> {
+    let today = now.date_naive();
+    status_service::get_status_line(
+        conn,
+        week_service::local_midnight(today),
+        week_service::local_midnight(today + chrono::Duration::days(1)),
+    )
+}
+
+/// Show the current task and today's status line in the tray tooltip
+pub(crate) fn refresh_tray_tooltip(app_handle: &tauri::AppHandle, conn: &rusqlite::Connection) {
+    let demo = crate::demo_mode::is_enabled_for(app_handle);
+    let mut tooltip = match get_current_task_internal(conn, demo) {
+        Ok(Some(task)) if demo => {
+            format!("Todo App - Working on: {}", crate::demo_mode::placeholder(&task.id, 2, 5))
+        }
+        Ok(Some(task)) => format!("Todo App - Working on: {}", crate::tray::truncate_title(&task.title)),
+        _ => "Todo App".to_string(),
+    };
+    if let Ok(status) = todays_status_line(conn, chrono::Local::now()) {
+        tooltip.push('\n');
+        tooltip.push_str(&status.text);
+    }
+    let _ = app_handle.tray_handle().set_tooltip(&tooltip);
 }
 
+/// Streak, tasks done today against the daily goal, focus minutes and level
 #[tauri::command]
-pub fn check_circular_dependency(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-    blocking_task_id: String,
-    blocked_task_id: String,
-) -> Result<bool, String> {
+pub fn get_status_line(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<status_service::StatusLine, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    check_circular_dependency_internal(&db.conn, &blocking_task_id, &blocked_task_id)
+    todays_status_line(&db.conn, chrono::Local::now())
 }
 
+/// Pick the task being worked on (None clears it). Pomodoro sessions without a task are linked to it.
 #[tauri::command]
-pub fn get_blocking_tasks(
+pub fn set_current_task(
+    app_handle: tauri::AppHandle,
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-) -> Result<Vec<Task>, String> {
+    task_id: Option<String>,
+) -> Result<Option<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
     
-    // Get tasks that block this task (task_id_1 blocks task_id_2 where task_id_2 = task_id)
-    let mut stmt = db.conn.prepare(
-        "SELECT DISTINCT t.id, t.title, t.description, t.due_at, t.created_at, t.updated_at, t.priority, 
-         t.completed_at, t.project_id, t.order_index, t.metadata, t.recurrence_type, 
-         t.recurrence_interval, t.recurrence_parent_id, t.reminder_minutes_before, t.notification_repeat
-         FROM tasks t
-         INNER JOIN task_relationships tr ON t.id = tr.task_id_1
-         WHERE tr.task_id_2 = ?1 AND tr.relationship_type = 'blocks'
-         ORDER BY t.order_index, t.created_at"
-    ).map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map(params![task_id], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: row.get(14).ok().flatten(),
-            notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
-            tags: None,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut tasks = Vec::new();
-    for row in rows {
-        let mut task = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        task.tags = Some(fetch_task_tags(&db.conn, &task.id)?);
-        tasks.push(task);
-    }
+    let task = match task_id {
+        Some(task_id) => {
+            let task = fetch_task(&db.conn, &task_id)?;
+            if task.completed {
+                return Err("Cannot work on a completed task".to_string());
+            }
+            db.conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                params![CURRENT_TASK_SETTING, task_id],
+            ).map_err(|e| format!("Failed to set current task: {}", e))?;
+            Some(task)
+        }
+        None => {
+            db.conn.execute(
+                "DELETE FROM settings WHERE key = ?1",
+                params![CURRENT_TASK_SETTING],
+            ).map_err(|e| format!("Failed to clear current task: {}", e))?;
+            None
+        }
+    };
     
-    Ok(tasks)
+    refresh_tray_tooltip(&app_handle, &db.conn);
+    Ok(task)
 }
 
 #[tauri::command]
-pub fn get_blocked_tasks(
+pub fn get_current_task(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    task_id: String,
-) -> Result<Vec<Task>, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Option<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    
-    // Get tasks blocked by this task (task_id_1 blocks task_id_2 where task_id_1 = task_id)
-    let mut stmt = db.conn.prepare(
-        "SELECT DISTINCT t.id, t.title, t.description, t.due_at, t.created_at, t.updated_at, t.priority, 
-         t.completed_at, t.project_id, t.order_index, t.metadata, t.recurrence_type, 
-         t.recurrence_interval, t.recurrence_parent_id, t.reminder_minutes_before, t.notification_repeat
-         FROM tasks t
-         INNER JOIN task_relationships tr ON t.id = tr.task_id_2
-         WHERE tr.task_id_1 = ?1 AND tr.relationship_type = 'blocks'
-         ORDER BY t.order_index, t.created_at"
-    ).map_err(|e| format!("Query error: {}", e))?;
-    
-    let rows = stmt.query_map(params![task_id], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            due_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            priority: row.get(6)?,
-            completed: row.get::<_, Option<i64>>(7)?.is_some(),
-            project_id: row.get(8)?,
-            order_index: row.get(9).unwrap_or(0),
-            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
-            recurrence_interval: row.get(12).unwrap_or(1),
-            recurrence_parent_id: row.get(13).ok(),
-            reminder_minutes_before: row.get(14).ok().flatten(),
-            notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
-            tags: None,
-        })
-    }).map_err(|e| format!("Query execution error: {}", e))?;
-    
-    let mut tasks = Vec::new();
-    for row in rows {
-        let mut task = row.map_err(|e| format!("Row parsing error: {}", e))?;
-        task.tags = Some(fetch_task_tags(&db.conn, &task.id)?);
-        tasks.push(task);
-    }
-    
-    Ok(tasks)
+    get_current_task_internal(&db.conn, demo.is_enabled()).map(|value| demo.apply(value))
 }
 
-// Pomodoro session commands
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreatePomodoroSessionInput {
-    pub task_id: Option<String>,
-    pub started_at: i64,
-    pub completed_at: i64,
-    pub duration_seconds: i32,
-    pub mode: String,
-    pub was_completed: bool,
-    pub task_completed: bool,
+const DEFAULT_RECURRENCE_INSTANCES_LIMIT: i64 = 50;
+
+// Instances of one recurrence chain, newest first. `root_id` may be any task in the chain,
+// such as the collapsed row returned by get_tasks.
+fn get_recurrence_instances_internal(
+    conn: &rusqlite::Connection,
+    root_id: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Task>, String> {
+    let chain_id: String = conn.query_row(
+        "SELECT COALESCE(recurrence_root_id, id) FROM tasks WHERE id = ?1",
+        params![root_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    
+    let query = format!(
+        "SELECT {} FROM tasks WHERE COALESCE(recurrence_root_id, id) = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2 OFFSET ?3",
+        TASK_COLUMNS
+    );
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let tasks = stmt.query_map(params![chain_id, limit.max(1), offset.max(0)], task_from_row)
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read task: {}", e))?;
+    Ok(tasks)
 }
 
 #[tauri::command]
-pub fn create_pomodoro_session(
+pub fn get_recurrence_instances(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    input: CreatePomodoroSessionInput,
-) -> Result<pomodoro_service::PomodoroSession, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    root_id: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Task>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    pomodoro_service::create_pomodoro_session(
+    let tasks = get_recurrence_instances_internal(
         &db.conn,
-        input.task_id,
-        input.started_at,
-        input.completed_at,
-        input.duration_seconds,
-        input.mode,
-        input.was_completed,
-        input.task_completed,
+        &root_id,
+        limit.unwrap_or(DEFAULT_RECURRENCE_INSTANCES_LIMIT),
+        offset.unwrap_or(0),
+    )?;
+    Ok(demo.apply(tasks))
+}
+
+// Columns read by project_from_row, in order
+const PROJECT_COLUMNS: &str = "id, name, color, created_at, updated_at, completion_note_required";
+
+fn project_from_row(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        completion_note_required: Some(row.get::<_, i64>(5)? != 0),
+    })
+}
+
+fn fetch_project(conn: &rusqlite::Connection, id: &str) -> Result<Project, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM projects WHERE id = ?1", PROJECT_COLUMNS),
+        params![id],
+        project_from_row,
+    ).map_err(|e| format!("Project not found: {}", e))
+}
+
+fn project_requires_completion_note(conn: &rusqlite::Connection, project_id: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT completion_note_required FROM projects WHERE id = ?1",
+        params![project_id],
+        |row| row.get::<_, i64>(0),
     )
-    .map_err(|e| format!("Failed to create pomodoro session: {}", e))
+    .optional()
+    .map(|required| required.is_some_and(|required| required != 0))
+    .map_err(|e| format!("Query error: {}", e))
 }
 
+// Project commands
 #[tauri::command]
-pub fn get_pomodoro_stats(
+pub fn get_projects(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    start_date: Option<i64>,
-    end_date: Option<i64>,
-) -> Result<pomodoro_service::PomodoroStats, String> {
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<Project>, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    pomodoro_service::get_pomodoro_stats(&db.conn, start_date, end_date)
-        .map_err(|e| format!("Failed to get pomodoro stats: {}", e))
+    
+    let mut stmt = db.conn.prepare(&format!("SELECT {} FROM projects ORDER BY created_at", PROJECT_COLUMNS)).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], project_from_row).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut projects = Vec::new();
+    for row in rows {
+        projects.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(demo.apply(projects))
 }
 
 #[tauri::command]
-pub fn get_daily_pomodoro_stats(
+pub fn create_project(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-    start_date: i64,
-    end_date: i64,
-) -> Result<Vec<pomodoro_service::DailyPomodoroStats>, String> {
+    input: CreateProjectInput,
+) -> Result<Project, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    pomodoro_service::get_daily_stats(&db.conn, start_date, end_date)
-        .map_err(|e| format!("Failed to get daily pomodoro stats: {}", e))
+    
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    
+    db.conn.execute(
+        "INSERT INTO projects (id, name, color, created_at, updated_at, completion_note_required) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id.clone(), input.name, input.color, now, now, input.completion_note_required.unwrap_or(false)],
+    ).map_err(|e| format!("Failed to create project: {}", e))?;
+    
+    db.conn.query_row(
+        &format!("SELECT {} FROM projects WHERE id = ?1", PROJECT_COLUMNS),
+        params![id],
+        project_from_row,
+    ).map_err(|e| format!("Failed to fetch created project: {}", e))
 }
 
 #[tauri::command]
-pub fn get_best_focus_times(
+pub fn update_project(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<Vec<pomodoro_service::BestFocusTime>, String> {
+    id: String,
+    input: UpdateProjectInput,
+) -> Result<Project, String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    pomodoro_service::get_best_focus_times(&db.conn)
-        .map_err(|e| format!("Failed to get best focus times: {}", e))
+    update_project_internal(&db.conn, &id, input)
 }
 
-#[tauri::command]
-pub fn get_task_completion_rates(
-    db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<Vec<pomodoro_service::TaskCompletionRate>, String> {
-    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    pomodoro_service::get_task_completion_rates(&db.conn)
-        .map_err(|e| format!("Failed to get task completion rates: {}", e))
+fn update_project_internal(conn: &rusqlite::Connection, id: &str, input: UpdateProjectInput) -> Result<Project, String> {
+    ensure_not_stale(conn, "projects", id, input.expected_updated_at, || fetch_project(conn, id))?;
+    
+    let now = now();
+    let mut updates = Vec::new();
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    if let Some(name) = input.name {
+        updates.push("name = ?");
+        query_params.push(Box::new(name));
+    }
+    if let Some(color) = input.color {
+        updates.push("color = ?");
+        query_params.push(Box::new(color));
+    }
+    // Only affects tasks created afterwards
+    if let Some(completion_note_required) = input.completion_note_required {
+        updates.push("completion_note_required = ?");
+        query_params.push(Box::new(completion_note_required));
+    }
+    
+    if !updates.is_empty() {
+        updates.push(BUMP_UPDATED_AT);
+        query_params.push(Box::new(now));
+        query_params.push(Box::new(id.to_string()));
+        
+        let query = format!("UPDATE projects SET {} WHERE id = ?", updates.join(", "));
+        conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
+            .map_err(|e| format!("Failed to update project: {}", e))?;
+    }
+    
+    fetch_project(conn, id)
 }
 
 #[tauri::command]
-pub fn get_pomodoro_streak(
+pub fn delete_project(
     db: State<'_, Arc<Mutex<DbConnection>>>,
-) -> Result<pomodoro_service::PomodoroStreak, String> {
+    undo: State<'_, UndoJournal>,
+    id: String,
+) -> Result<(), String> {
     let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
-    pomodoro_service::get_pomodoro_streak(&db.conn)
-        .map_err(|e| format!("Failed to get pomodoro streak: {}", e))
+    let entry = project_undo_entry(&db.conn, &id)?;
+    
+    let deleted = db.conn.execute("DELETE FROM projects WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete project: {}", e))?;
+    if deleted > 0 {
+        undo.record(entry);
+    }
+    
+    Ok(())
 }
 
-// Screenshot command
-#[tauri::command]
-pub async fn capture_screenshot(mode: String) -> Result<Vec<u8>, String> {
-    use screenshots::Screen;
-    use image::ImageEncoder;
+// Snapshot a project before it is deleted, including which tasks and templates pointed at it
+fn project_undo_entry(conn: &rusqlite::Connection, id: &str) -> Result<UndoEntry, String> {
+    let name: Option<String> = conn.query_row("SELECT name FROM projects WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Query error: {}", e))?;
+    let task_count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks WHERE project_id = ?1", params![id], |row| row.get(0))
+        .map_err(|e| format!("Query error: {}", e))?;
+    let mut entry = UndoEntry::new(match task_count {
+        0 => format!("Deleted project \"{}\"", name.unwrap_or_default()),
+        count => format!("Deleted project \"{}\" with {} task{}", name.unwrap_or_default(), count, if count == 1 { "" } else { "s" }),
+    });
+    entry.capture(conn, "projects", "id = ?1", id)?;
+    // Tasks and templates outlive the project; undo only links them back
+    entry.capture_column(conn, "tasks", "project_id", "project_id = ?1", id)?;
+    entry.capture(conn, "project_keywords", "project_id = ?1", id)?;
+    entry.capture_column(conn, "task_templates", "project_id", "project_id = ?1", id)?;
+    entry.capture_column(conn, "task_templates", "scope_project_id", "scope_project_id = ?1", id)?;
+    Ok(entry)
+}
+
+// Archive summaries: a project's completed tasks folded into one summary task
+
+const ARCHIVE_SUMMARY_TITLE: &str = "Archive summary";
+
+/// A summarized task as it was before deletion, kept for undo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizedTaskSnapshot {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub due_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub priority: String,
+    pub completed_at: i64,
+    pub order_index: i32,
+    pub recurrence_type: String,
+    pub recurrence_interval: i32,
+    pub recurrence_parent_id: Option<String>,
+    pub metadata: Option<String>,
+    pub tag_ids: Vec<String>,
+    pub subtasks: Vec<Subtask>,
+    /// Attachments moved onto the summary task
+    pub attachment_ids: Vec<String>,
+}
+
+/// Stored in the summary task's metadata under "archive_summary"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSummarySnapshot {
+    pub project_id: String,
+    pub tasks: Vec<SummarizedTaskSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveSummaryResult {
+    pub summary_task: Task,
+    pub summarized_count: usize,
+    pub attachments_moved: usize,
+}
+
+fn summarize_project_internal(conn: &rusqlite::Connection, project_id: &str, now: i64) -> Result<ArchiveSummaryResult, String> {
+    use chrono::TimeZone;
     
-    match mode.as_str() {
-        "fullscreen" => {
-            let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
-            let primary_screen = screens.first().ok_or("No screen found")?;
-            
-            let screenshot = primary_screen
-                .capture()
-                .map_err(|e| format!("Failed to capture screenshot: {}", e))?;
-            
-            // Convert to PNG bytes
-            let mut buffer = Vec::new();
-            let width = screenshot.width();
-            let height = screenshot.height();
-            let rgba = screenshot.rgba();
-            
-            let encoder = image::codecs::png::PngEncoder::new(&mut buffer);
-            encoder
-                .write_image(
-                    rgba,
-                    width,
-                    height,
-                    image::ColorType::Rgba8,
-                )
-                .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
-            
-            Ok(buffer)
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    
+    tx.query_row("SELECT id FROM projects WHERE id = ?1", params![project_id], |row| row.get::<_, String>(0))
+        .map_err(|_| format!("Project not found: {}", project_id))?;
+    
+    let mut stmt = tx.prepare(
+        "SELECT id, title, description, due_at, created_at, updated_at, priority, completed_at, order_index, recurrence_type, recurrence_interval, recurrence_parent_id, metadata
+         FROM tasks
+         WHERE project_id = ?1 AND completed_at IS NOT NULL
+           AND (metadata IS NULL OR json_extract(metadata, '$.archive_summary') IS NULL)
+         ORDER BY completed_at, created_at"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![project_id], |row| {
+        Ok(SummarizedTaskSnapshot {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            due_at: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            priority: row.get(6)?,
+            completed_at: row.get(7)?,
+            order_index: row.get::<_, Option<i32>>(8)?.unwrap_or(0),
+            recurrence_type: row.get::<_, Option<String>>(9)?.unwrap_or_else(|| "none".to_string()),
+            recurrence_interval: row.get::<_, Option<i32>>(10)?.unwrap_or(1),
+            recurrence_parent_id: row.get(11)?,
+            metadata: row.get(12)?,
+            tag_ids: Vec::new(),
+            subtasks: Vec::new(),
+            attachment_ids: Vec::new(),
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    let mut tasks = Vec::new();
+    for row in rows {
+        tasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    drop(stmt);
+    
+    if tasks.is_empty() {
+        return Err("Project has no completed tasks to summarize".to_string());
+    }
+    
+    for task in &mut tasks {
+        task.tag_ids = fetch_task_tags(&tx, &task.id)?.into_iter().map(|tag| tag.id).collect();
+        task.subtasks = fetch_subtasks(&tx, &task.id)?;
+        let mut stmt = tx.prepare("SELECT id FROM attachments WHERE task_id = ?1")
+            .map_err(|e| format!("Query error: {}", e))?;
+        task.attachment_ids = stmt.query_map(params![task.id], |row| row.get(0))
+            .map_err(|e| format!("Query execution error: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Row parsing error: {}", e))?;
+    }
+    
+    let description = tasks
+        .iter()
+        .map(|task| {
+            let date = chrono::Local.timestamp_opt(task.completed_at, 0).single()
+                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            format!("- {} (completed {})", task.title, date)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    let snapshot = ArchiveSummarySnapshot { project_id: project_id.to_string(), tasks };
+    let metadata = serde_json::json!({ "archive_summary": snapshot }).to_string();
+    let summary_id = uuid::Uuid::new_v4().to_string();
+    tx.execute(
+        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata)
+         VALUES (?1, ?2, ?3, NULL, ?4, ?4, 'low', ?4, ?5, 0, ?6)",
+        params![summary_id, ARCHIVE_SUMMARY_TITLE, description, now, project_id, metadata],
+    ).map_err(|e| format!("Failed to create archive summary: {}", e))?;
+    
+    // Attachments are moved, not deleted, so the files stay reachable from the summary
+    let mut attachments_moved = 0;
+    for task in &snapshot.tasks {
+        attachments_moved += tx.execute(
+            "UPDATE attachments SET task_id = ?1 WHERE task_id = ?2",
+            params![summary_id, task.id],
+        ).map_err(|e| format!("Failed to move attachments: {}", e))?;
+        
+        tx.execute("DELETE FROM subtasks WHERE task_id = ?1", params![task.id])
+            .map_err(|e| format!("Failed to delete subtasks: {}", e))?;
+        tx.execute("DELETE FROM task_tags WHERE task_id = ?1", params![task.id])
+            .map_err(|e| format!("Failed to delete task tags: {}", e))?;
+        for tag_id in &task.tag_ids {
+            tx.execute(
+                "UPDATE tags SET usage_count = MAX(0, usage_count - 1) WHERE id = ?1",
+                params![tag_id],
+            ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
         }
-        "window" => {
-            // For now, window mode also captures fullscreen
-            // In the future, this could be enhanced to capture specific windows
-            let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
-            let primary_screen = screens.first().ok_or("No screen found")?;
-            
-            let screenshot = primary_screen
-                .capture()
-                .map_err(|e| format!("Failed to capture screenshot: {}", e))?;
-            
-            // Convert to PNG bytes
-            let mut buffer = Vec::new();
-            let width = screenshot.width();
-            let height = screenshot.height();
-            let rgba = screenshot.rgba();
-            
-            let encoder = image::codecs::png::PngEncoder::new(&mut buffer);
-            encoder
-                .write_image(
-                    rgba,
-                    width,
-                    height,
-                    image::ColorType::Rgba8,
-                )
-                .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
-            
-            Ok(buffer)
+        tx.execute("DELETE FROM tasks WHERE id = ?1", params![task.id])
+            .map_err(|e| format!("Failed to delete summarized task: {}", e))?;
+    }
+    
+    tx.commit().map_err(|e| format!("Failed to commit archive summary: {}", e))?;
+    
+    Ok(ArchiveSummaryResult {
+        summary_task: fetch_task(conn, &summary_id)?,
+        summarized_count: snapshot.tasks.len(),
+        attachments_moved,
+    })
+}
+
+fn undo_archive_summary_internal(conn: &rusqlite::Connection, summary_task_id: &str) -> Result<usize, String> {
+    let metadata: Option<String> = conn.query_row(
+        "SELECT metadata FROM tasks WHERE id = ?1",
+        params![summary_task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    let snapshot = metadata
+        .and_then(|metadata| serde_json::from_str::<serde_json::Value>(&metadata).ok())
+        .and_then(|value| serde_json::from_value::<ArchiveSummarySnapshot>(value.get("archive_summary")?.clone()).ok())
+        .ok_or_else(|| "Task is not an archive summary".to_string())?;
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for task in &snapshot.tasks {
+        tx.execute(
+            "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                task.id, task.title, task.description, task.due_at, task.created_at, task.updated_at,
+                task.priority, task.completed_at, snapshot.project_id, task.order_index, task.metadata,
+                task.recurrence_type, task.recurrence_interval, task.recurrence_parent_id
+            ],
+        ).map_err(|e| format!("Failed to restore task: {}", e))?;
+        for (index, subtask) in task.subtasks.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![subtask.id, task.id, subtask.title, subtask.completed, index as i32],
+            ).map_err(|e| format!("Failed to restore subtask: {}", e))?;
         }
-        _ => Err(format!("Unsupported screenshot mode: {}", mode)),
+        for tag_id in &task.tag_ids {
+            let restored = tx.execute(
+                "INSERT OR IGNORE INTO task_tags (id, task_id, tag_id, created_at)
+                 SELECT ?1, ?2, id, ?3 FROM tags WHERE id = ?4",
+                params![uuid::Uuid::new_v4().to_string(), task.id, now(), tag_id],
+            ).map_err(|e| format!("Failed to restore task tag: {}", e))?;
+            if restored > 0 {
+                tx.execute("UPDATE tags SET usage_count = usage_count + 1 WHERE id = ?1", params![tag_id])
+                    .map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+            }
+        }
+        for attachment_id in &task.attachment_ids {
+            tx.execute(
+                "UPDATE attachments SET task_id = ?1 WHERE id = ?2 AND task_id = ?3",
+                params![task.id, attachment_id, summary_task_id],
+            ).map_err(|e| format!("Failed to move attachment back: {}", e))?;
+        }
+    }
+    tx.execute("DELETE FROM tasks WHERE id = ?1", params![summary_task_id])
+        .map_err(|e| format!("Failed to delete archive summary: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit undo: {}", e))?;
+    
+    Ok(snapshot.tasks.len())
+}
+
+/// Replace a project's completed tasks with one "Archive summary" task listing them.
+/// Their attachments move to the summary task; undo_archive_summary puts everything back.
+#[tauri::command]
+pub fn summarize_and_archive_project(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    project_id: String,
+) -> Result<ArchiveSummaryResult, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    summarize_project_internal(&db.conn, &project_id, now())
+}
+
+/// Restore the tasks folded into an archive summary and remove the summary. Returns how many came back.
+#[tauri::command]
+pub fn undo_archive_summary(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    summary_task_id: String,
+) -> Result<usize, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    undo_archive_summary_internal(&db.conn, &summary_task_id)
+}
+
+// Project view state: how a project is displayed, synced with the data instead of localStorage
+const VIEW_TYPES: &[&str] = &["list", "board"];
+pub(crate) const BOARD_COLUMNS: &[&str] = &["todo", "in_progress", "done"];
+const VIEW_SORT_FIELDS: &[&str] = &["created", "dueDate", "priority", "title", "project"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViewSort {
+    pub field: String,
+    /// "asc" or "desc"
+    pub direction: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectViewState {
+    /// "list" or "board"
+    pub view: String,
+    /// Board columns folded away: todo, in_progress, done
+    #[serde(default)]
+    pub collapsed_columns: Vec<String>,
+    #[serde(default)]
+    pub sort: Option<ViewSort>,
+}
+
+fn validate_view_state(state: &ProjectViewState) -> Result<(), String> {
+    if !VIEW_TYPES.contains(&state.view.as_str()) {
+        return Err(format!("Unknown view type: {}", state.view));
+    }
+    if let Some(column) = state.collapsed_columns.iter().find(|c| !BOARD_COLUMNS.contains(&c.as_str())) {
+        return Err(format!("Unknown board column: {}", column));
+    }
+    if let Some(sort) = &state.sort {
+        if !VIEW_SORT_FIELDS.contains(&sort.field.as_str()) {
+            return Err(format!("Unknown sort field: {}", sort.field));
+        }
+        if sort.direction != "asc" && sort.direction != "desc" {
+            return Err(format!("Sort direction must be asc or desc, got: {}", sort.direction));
+        }
+    }
+    Ok(())
+}
+
+fn get_project_view_state_internal(conn: &rusqlite::Connection, project_id: &str) -> Result<Option<ProjectViewState>, String> {
+    let stored: Option<String> = conn.query_row(
+        "SELECT view_state FROM projects WHERE id = ?1",
+        params![project_id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Project not found: {}", project_id))?;
+    
+    // A state the current app can't read falls back to the default view
+    Ok(stored
+        .and_then(|json| serde_json::from_str::<ProjectViewState>(&json).ok())
+        .filter(|state| validate_view_state(state).is_ok()))
+}
+
+fn set_project_view_state_internal(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    state: Option<&ProjectViewState>,
+) -> Result<(), String> {
+    let json = match state {
+        Some(state) => {
+            validate_view_state(state)?;
+            Some(serde_json::to_string(state).map_err(|e| format!("Failed to serialize view state: {}", e))?)
+        }
+        None => None,
+    };
+    let updated = conn.execute(
+        "UPDATE projects SET view_state = ?1 WHERE id = ?2",
+        params![json, project_id],
+    ).map_err(|e| format!("Failed to save view state: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Project not found: {}", project_id));
+    }
+    Ok(())
+}
+
+/// Saved view for a project, or None to use the default list view
+#[tauri::command]
+pub fn get_project_view_state(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+) -> Result<Option<ProjectViewState>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_project_view_state_internal(&db.conn, &id)
+}
+
+/// Save a project's view; None resets it to the default
+#[tauri::command]
+pub fn set_project_view_state(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    state: Option<ProjectViewState>,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    set_project_view_state_internal(&db.conn, &id, state.as_ref())
+}
+
+// A task's board column: the stored status, or done/todo by completion when none is stored
+fn board_status(stored: Option<String>, completed: bool) -> String {
+    stored.unwrap_or_else(|| if completed { "done" } else { "todo" }.to_string())
+}
+
+fn validate_board_status(status: &str) -> Result<(), String> {
+    if !BOARD_COLUMNS.contains(&status) {
+        return Err(format!("Invalid status: {} (expected {})", status, BOARD_COLUMNS.join(", ")));
+    }
+    Ok(())
+}
+
+// Moving a task into done completes it and moving it out reopens it, with the same XP, streak
+// and recurrence handling as toggling it, so the column and completed_at always agree. A task
+// that requires a completion note can't be moved to done without one.
+fn complete_for_status(conn: &rusqlite::Connection, id: &str, status: &str) -> Result<(), String> {
+    let completed: bool = conn.query_row(
+        "SELECT completed_at IS NOT NULL FROM tasks WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    if completed == (status == "done") {
+        return Ok(());
+    }
+    resolve_completion_note(conn, id, None, false)?;
+    toggle_complete_internal(conn, id)?;
+    Ok(())
+}
+
+// Move a task to another board column. Completing or reopening it clears the stored status,
+// so completed tasks end up in done.
+fn set_task_status_internal(conn: &rusqlite::Connection, id: &str, status: &str, force: bool) -> Result<Task, String> {
+    validate_board_status(status)?;
+    if !force {
+        crate::services::wip_limit_service::check_wip_limits(conn, id, status)?;
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    complete_for_status(&tx, id, status)?;
+    let updated = tx.execute(
+        &format!("UPDATE tasks SET status = ?, {} WHERE id = ?", BUMP_UPDATED_AT),
+        params![status, now(), id],
+    ).map_err(|e| format!("Failed to update task: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Task not found: {}", id));
+    }
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    fetch_task(conn, id)
+}
+
+/// Move a task to a board column, completing or reopening it when it moves into or out of done.
+/// Fails with a serialized WipLimitExceeded when the column is at a WIP limit, unless `force` is set.
+#[tauri::command]
+pub fn set_task_status(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    status: String,
+    force: Option<bool>,
+) -> Result<Task, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let task = set_task_status_internal(&db.conn, &id, &status, force.unwrap_or(false))?;
+    // Moving into or out of done completes or reopens the task
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
+    Ok(task)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardColumn {
+    pub status: String,
+    pub tasks: Vec<Task>,
+    /// WIP limits on this column that apply to the board, with their current counts
+    pub limits: Vec<crate::services::wip_limit_service::WipLimitUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Board {
+    pub columns: Vec<BoardColumn>,
+}
+
+// The task list (optionally one project's or tag's) split into board columns
+fn get_board_internal(conn: &rusqlite::Connection, project_id: Option<&str>, tag_id: Option<&str>) -> Result<Board, String> {
+    let filter = TaskFilter {
+        project_id: project_id.map(str::to_string),
+        completed: None,
+        due_before: None,
+        due_after: None,
+        search: None,
+        tag_id: tag_id.map(str::to_string),
+        with_block_status: None,
+        collapse_recurring: None,
+        fts_search: None,
+        include_waiting: None,
+        limit: None,
+        offset: None,
+        sort_by: None,
+        sort_dir: None,
+        overdue_only: None,
+        has_description: None,
+        has_attachments: None,
+        has_subtasks: None,
+        description_preview_only: None,
+    };
+    let mut tasks = get_tasks_internal(conn, Some(&filter), false)?;
+    
+    let mut columns = Vec::new();
+    for status in BOARD_COLUMNS {
+        let (in_column, rest): (Vec<Task>, Vec<Task>) = tasks
+            .into_iter()
+            .partition(|task| task.status.as_deref() == Some(*status));
+        tasks = rest;
+        columns.push(BoardColumn {
+            status: status.to_string(),
+            tasks: in_column,
+            limits: crate::services::wip_limit_service::board_limits(conn, status, project_id, tag_id)?,
+        });
+    }
+    Ok(Board { columns })
+}
+
+/// Tasks by board column, with each column's WIP limits and how full they are
+#[tauri::command]
+pub fn get_board(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    project_id: Option<String>,
+    tag_id: Option<String>,
+) -> Result<Board, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_board_internal(&db.conn, project_id.as_deref(), tag_id.as_deref())?))
+}
+
+#[tauri::command]
+pub fn get_wip_limits(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<crate::services::wip_limit_service::WipLimit>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::services::wip_limit_service::list_wip_limits(&db.conn)
+}
+
+/// Create or change the WIP limit for a board column within a scope ("global", "project" or
+/// "tag"; project and tag limits need a scope_id)
+#[tauri::command]
+pub fn set_wip_limit(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    scope_kind: String,
+    scope_id: Option<String>,
+    status: String,
+    limit: i64,
+) -> Result<crate::services::wip_limit_service::WipLimit, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::services::wip_limit_service::set_wip_limit(&db.conn, &scope_kind, scope_id.as_deref(), &status, limit, now())
+}
+
+#[tauri::command]
+pub fn delete_wip_limit(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::services::wip_limit_service::delete_wip_limit(&db.conn, &id)
+}
+
+// Subtask commands
+// Helper function to get the order_index that puts a new subtask last
+fn next_subtask_order_index(conn: &rusqlite::Connection, task_id: &str) -> i64 {
+    conn.query_row(
+        "SELECT COALESCE(MAX(order_index) + 1, 0) FROM subtasks WHERE task_id = ?1",
+        params![task_id],
+        |row| row.get(0),
+    ).unwrap_or(0)
+}
+
+#[tauri::command]
+pub fn add_subtask(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+    title: String,
+) -> Result<Subtask, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let id = uuid::Uuid::new_v4().to_string();
+    let order_index = next_subtask_order_index(&db.conn, &task_id);
+    
+    db.conn.execute(
+        "INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id.clone(), task_id.clone(), title, 0, order_index],
+    ).map_err(|e| format!("Failed to create subtask: {}", e))?;
+    log_activity(&db.conn, &task_id, "subtask_added", None, Some(&title), "user")?;
+    
+    db.conn.query_row(
+        "SELECT id, task_id, title, completed FROM subtasks WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Subtask {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                title: row.get(2)?,
+                completed: row.get::<_, i32>(3)? != 0,
+            })
+        },
+    ).map_err(|e| format!("Failed to fetch created subtask: {}", e))
+}
+
+#[tauri::command]
+pub fn update_subtask(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    title: Option<String>,
+    completed: Option<bool>,
+) -> Result<Subtask, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let mut updates = Vec::new();
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    if let Some(title) = title {
+        updates.push("title = ?");
+        query_params.push(Box::new(title));
+    }
+    if let Some(completed) = completed {
+        updates.push("completed = ?");
+        query_params.push(Box::new(if completed { 1 } else { 0 }));
+    }
+    
+    if !updates.is_empty() {
+        query_params.push(Box::new(id.clone()));
+        let query = format!("UPDATE subtasks SET {} WHERE id = ?", updates.join(", "));
+        db.conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
+            .map_err(|e| format!("Failed to update subtask: {}", e))?;
+    }
+    
+    db.conn.query_row(
+        "SELECT id, task_id, title, completed FROM subtasks WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Subtask {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                title: row.get(2)?,
+                completed: row.get::<_, i32>(3)? != 0,
+            })
+        },
+    ).map_err(|e| format!("Subtask not found: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_subtask(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let subtask: Option<(String, String)> = db.conn.query_row(
+        "SELECT task_id, title FROM subtasks WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+        .optional()
+        .map_err(|e| format!("Query error: {}", e))?;
+    let mut entry = UndoEntry::new(format!("Deleted subtask \"{}\"", subtask.as_ref().map_or("", |(_, title)| title.as_str())));
+    entry.capture(&db.conn, "subtasks", "id = ?1", &id)?;
+    
+    let deleted = db.conn.execute("DELETE FROM subtasks WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete subtask: {}", e))?;
+    if let (true, Some((task_id, title))) = (deleted > 0, subtask) {
+        log_activity(&db.conn, &task_id, "subtask_deleted", Some(&title), None, "user")?;
+        undo.record(entry);
+    }
+    
+    Ok(())
+}
+
+// Maximum number of subtasks a single paste may create
+const MAX_BULK_SUBTASKS: usize = 100;
+
+// Helper function to create one subtask per non-empty line of pasted text
+fn add_subtasks_bulk_internal(conn: &rusqlite::Connection, task_id: &str, text: &str) -> Result<Vec<Subtask>, String> {
+    let titles: Vec<&str> = text
+        .lines()
+        .map(crate::services::quick_add_service::strip_list_marker)
+        .filter(|title| !title.is_empty())
+        .collect();
+    
+    if titles.len() > MAX_BULK_SUBTASKS {
+        return Err(format!(
+            "Too many subtasks: {} lines is {} over the limit of {}",
+            titles.len(),
+            titles.len() - MAX_BULK_SUBTASKS,
+            MAX_BULK_SUBTASKS
+        ));
+    }
+    
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+        params![task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))?;
+    if !exists {
+        return Err("Task not found".to_string());
+    }
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let subtasks = append_subtasks(&tx, task_id, &titles)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(subtasks)
+}
+
+// Helper function to add subtasks after a task's existing ones (caller provides the transaction)
+fn append_subtasks<S: AsRef<str>>(conn: &rusqlite::Connection, task_id: &str, titles: &[S]) -> Result<Vec<Subtask>, String> {
+    let first_index = next_subtask_order_index(conn, task_id);
+    let mut subtasks = Vec::with_capacity(titles.len());
+    
+    for (offset, title) in titles.iter().enumerate() {
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES (?1, ?2, ?3, 0, ?4)",
+            params![id, task_id, title.as_ref(), first_index + offset as i64],
+        ).map_err(|e| format!("Failed to create subtask: {}", e))?;
+        
+        subtasks.push(Subtask {
+            id,
+            task_id: task_id.to_string(),
+            title: title.as_ref().to_string(),
+            completed: false,
+        });
+    }
+    
+    Ok(subtasks)
+}
+
+#[tauri::command]
+pub fn add_subtasks_bulk(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+    text: String,
+) -> Result<Vec<Subtask>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    add_subtasks_bulk_internal(&db.conn, &task_id, &text)
+}
+
+#[tauri::command]
+pub fn get_subtasks(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+) -> Result<Vec<Subtask>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    fetch_subtasks(&db.conn, &task_id).map(|value| demo.apply(value))
+}
+
+// Helper function to get a task's subtasks in display order
+fn fetch_subtasks(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Subtask>, String> {
+    let mut stmt = conn.prepare("SELECT id, task_id, title, completed FROM subtasks WHERE task_id = ?1 ORDER BY order_index, id").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok(Subtask {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            title: row.get(2)?,
+            completed: row.get::<_, i32>(3)? != 0,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut subtasks = Vec::new();
+    for row in rows {
+        subtasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(subtasks)
+}
+
+// Attachment commands
+#[tauri::command]
+pub fn get_attachments(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+) -> Result<Vec<Attachment>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let mut stmt = db.conn.prepare("SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE task_id = ?1 ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok(Attachment {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            filename: row.get(2)?,
+            path: row.get(3)?,
+            mime: row.get(4)?,
+            size: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut attachments = Vec::new();
+    for row in rows {
+        attachments.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(demo.apply(attachments))
+}
+
+#[tauri::command]
+pub fn add_attachment(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    task_id: String,
+    file_path: String,
+) -> Result<Attachment, String> {
+    use crate::attachments::{copy_attachment_to_storage, validate_file_type, get_mime_type};
+    use std::fs;
+    
+    // Validate file type
+    validate_file_type(&file_path)?;
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Get file size before copying
+    let file_size: Option<i64> = fs::metadata(&file_path)
+        .ok()
+        .and_then(|m| m.len().try_into().ok());
+    
+    // Copy file to storage first; the row is only inserted once the file is safely written
+    let stored_path = copy_attachment_to_storage(&app_handle, &file_path, &task_id)?;
+    
+    // Get filename from original path
+    let filename = std::path::Path::new(&file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    
+    // Get MIME type from extension
+    let mime = get_mime_type(&file_path);
+    
+    let id = uuid::Uuid::new_v4().to_string();
+    let created_at = now();
+    
+    if let Err(e) = db.conn.execute(
+        "INSERT INTO attachments (id, task_id, filename, path, mime, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![id.clone(), task_id, filename, stored_path, mime, file_size, created_at],
+    ) {
+        // Don't leave an orphaned file behind
+        if let Some(app_data_dir) = app_handle.path_resolver().app_data_dir() {
+            let _ = fs::remove_file(app_data_dir.join(&stored_path));
+        }
+        return Err(crate::storage::from_sqlite("create the attachment record", &e).to_string());
+    }
+    
+    db.conn.query_row(
+        "SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Attachment {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                filename: row.get(2)?,
+                path: row.get(3)?,
+                mime: row.get(4)?,
+                size: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        },
+    ).map_err(|e| format!("Failed to fetch created attachment: {}", e))
+}
+
+/// Attach every allowed file in a folder to a task, reporting skipped and failed files.
+/// Async so large folders copy off the main thread; progress is emitted as "attachment-import-progress".
+#[tauri::command]
+pub async fn add_attachments_from_folder(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    task_id: String,
+    folder_path: String,
+    recursive: bool,
+) -> Result<crate::attachments::FolderAttachmentResult, String> {
+    use tauri::Manager;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::attachments::add_attachments_from_folder(
+        &db.conn,
+        &app_data_dir,
+        &task_id,
+        std::path::Path::new(&folder_path),
+        recursive,
+        crate::attachments::AttachmentLimits::default(),
+        |progress| {
+            app_handle.emit_all("attachment-import-progress", progress).ok();
+        },
+    )
+}
+
+#[tauri::command]
+pub fn delete_attachment(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    use std::fs;
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Get attachment path before deleting
+    let path: Option<String> = db.conn.query_row(
+        "SELECT path FROM attachments WHERE id = ?1",
+        params![id.clone()],
+        |row| row.get(0),
+    ).ok();
+    
+    // Delete from database
+    db.conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete attachment: {}", e))?;
+    
+    // Try to delete file
+    if let Some(path) = path {
+        let app_data_dir = app_handle
+            .path_resolver()
+            .app_data_dir()
+            .ok_or_else(|| "Failed to get app data directory".to_string())?;
+        let full_path = app_data_dir.join(&path);
+        let _ = fs::remove_file(full_path); // Ignore errors if file doesn't exist
+        crate::attachments::remove_thumbnail(&app_data_dir, &id);
+    }
+    
+    Ok(())
+}
+
+// Helper to find each task's first image attachment in one query: (task_id, attachment_id, path)
+fn first_image_attachments(conn: &rusqlite::Connection, task_ids: &[String]) -> Result<Vec<(String, String, String)>, String> {
+    if task_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let placeholders = vec!["?"; task_ids.len()].join(", ");
+    let query = format!(
+        "SELECT task_id, id, path FROM attachments
+         WHERE task_id IN ({}) AND mime LIKE 'image/%'
+         ORDER BY task_id, created_at, id",
+        placeholders
+    );
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(task_ids.iter()), |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut first = Vec::new();
+    for row in rows {
+        let row = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        if first.last().is_none_or(|(task_id, _, _): &(String, String, String)| *task_id != row.0) {
+            first.push(row);
+        }
+    }
+    Ok(first)
+}
+
+/// Base64 PNG previews of each task's first image attachment, for task cards. Tasks without an
+/// image attachment are left out; a preview that can't be generated is null.
+#[tauri::command]
+pub fn get_task_thumbnails(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    app_handle: tauri::AppHandle,
+    task_ids: Vec<String>,
+) -> Result<HashMap<String, Option<String>>, String> {
+    use base64::Engine;
+    
+    // Previews would show real content
+    if demo.is_enabled() {
+        return Ok(HashMap::new());
+    }
+    
+    let attachments = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        first_image_attachments(&db.conn, &task_ids)?
+    };
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    // File reads and resizing happen without holding the database lock
+    Ok(attachments
+        .into_iter()
+        .map(|(task_id, attachment_id, path)| {
+            let thumbnail = crate::attachments::ensure_thumbnail(&app_data_dir, &attachment_id, &path)
+                .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes))
+                .ok();
+            (task_id, thumbnail)
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_attachment(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    id: String,
+) -> Result<Attachment, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let attachment = db.conn.query_row(
+        "SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Attachment {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                filename: row.get(2)?,
+                path: row.get(3)?,
+                mime: row.get(4)?,
+                size: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        },
+    ).map_err(|e| format!("Failed to fetch attachment: {}", e))?;
+    Ok(demo.apply(attachment))
+}
+
+#[tauri::command]
+pub fn get_attachment_path(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<String, String> {
+    // The path has the real filename in it
+    if demo.is_enabled() {
+        return Err("Attachments can't be opened in demo mode".to_string());
+    }
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let path: String = db.conn.query_row(
+        "SELECT path FROM attachments WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to fetch attachment path: {}", e))?;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let full_path = app_data_dir.join(&path);
+    
+    if !full_path.exists() {
+        return Err("Attachment file not found".to_string());
+    }
+    
+    Ok(full_path.to_string_lossy().to_string())
+}
+
+/// Save a copy of an attachment to a folder of the user's choosing (e.g. Downloads)
+#[tauri::command]
+pub fn save_attachment_copy(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    id: String,
+    target_path: String,
+) -> Result<String, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let (filename, path): (String, String) = db.conn.query_row(
+        "SELECT filename, path FROM attachments WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Failed to fetch attachment: {}", e))?;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let written = crate::attachments::save_attachment_copy(&app_data_dir, &path, &filename, std::path::Path::new(&target_path))?;
+    Ok(written.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn read_attachment_file_content(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<String, String> {
+    use std::fs;
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let path: String = db.conn.query_row(
+        "SELECT path FROM attachments WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to fetch attachment path: {}", e))?;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let full_path = app_data_dir.join(&path);
+    
+    if !full_path.exists() {
+        return Err("Attachment file not found".to_string());
+    }
+    
+    // Read file as text (for text files)
+    fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read file: {}", e))
+}
+
+#[tauri::command]
+pub fn open_attachment_file(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let (path, filename): (String, String) = db.conn.query_row(
+        "SELECT path, filename FROM attachments WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Failed to fetch attachment path: {}", e))?;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let full_path = app_data_dir.join(&path);
+    
+    if !full_path.exists() {
+        return Err("Attachment file not found".to_string());
+    }
+    
+    // The file may have been swapped or renamed since it was attached
+    crate::attachments::ensure_safe_to_open(&full_path, &filename)?;
+    
+    // Open with the system default application. No shell is involved (ShellExecuteW on Windows),
+    // so names containing & or other shell metacharacters are passed through intact.
+    open::that(&full_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    
+    Ok(())
+}
+
+// Settings commands
+#[tauri::command]
+pub fn get_settings(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<HashMap<String, String>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_settings_internal(&db.conn)
+}
+
+fn get_settings_internal(conn: &rusqlite::Connection) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut settings = HashMap::new();
+    for row in rows {
+        let (key, value) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        settings.insert(key, value);
+    }
+    
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn update_settings(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    key: String,
+    value: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // The theme is checked and announced to every window, the quick-add one included
+    if key == crate::theme::THEME_SETTING_KEY {
+        crate::theme::set_preference(&db.conn, &value)?;
+        crate::theme::emit_theme_changed(&app_handle, &value, crate::theme::os_theme(&app_handle));
+        return Ok(());
+    }
+    
+    db.conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    ).map_err(|e| format!("Failed to update setting: {}", e))?;
+    
+    Ok(())
+}
+
+/// "light" or "dark": the theme setting, with "system" (the default) resolved from the OS
+#[tauri::command]
+pub fn get_effective_theme(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<String, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(crate::theme::effective_theme(&app_handle, &db.conn).to_string())
+}
+
+// Backup and restore commands
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    pub copied_pages: i32,
+    pub total_pages: i32,
+}
+
+// Async so it runs off the main thread and progress events reach the window while it works
+#[tauri::command]
+pub async fn create_backup(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    operations: State<'_, crate::operations::Operations>,
+    app_handle: tauri::AppHandle,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    use std::fs;
+    use tauri::Manager;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let backups_dir = app_data_dir.join("backups");
+    fs::create_dir_all(&backups_dir).map_err(|e| crate::storage::from_io("create the backups folder", &e))?;
+    
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let backup_filename = format!("todo_backup_{}.db", timestamp);
+    let backup_path = backups_dir.join(&backup_filename);
+    
+    crate::operations::run(&app_handle, &operations, operation_id, |reporter| {
+        // Use the online backup API against the live connection rather than copying todo.db,
+        // which could capture a half-written file while another thread is writing
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        let page_count: i64 = db.conn.query_row("PRAGMA page_count", [], |row| row.get(0))
+            .map_err(|e| format!("Query error: {}", e))?;
+        reporter.stage("copying_pages", page_count.max(0) as usize)?;
+        let copied = crate::db::backup_to_file(&db.conn, &backup_path, |copied_pages, total_pages| {
+            app_handle.emit_all("backup-progress", BackupProgress { copied_pages, total_pages }).ok();
+            reporter.progress(copied_pages.max(0) as usize);
+            // Stopping between steps is safe since the partial file is removed below
+            !reporter.cancelled()
+        });
+        
+        match copied {
+            Ok(true) => Ok(backup_path.to_string_lossy().to_string()),
+            result => {
+                // A half-written backup would look restorable; remove it
+                let _ = fs::remove_file(&backup_path);
+                Err(match result {
+                    Err(e) => crate::storage::from_sqlite("create the backup", &e).to_string(),
+                    _ => crate::operations::OPERATION_CANCELLED_ERROR.to_string(),
+                })
+            }
+        }
+    })
+}
+
+#[tauri::command]
+pub fn restore_backup(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    app_handle: tauri::AppHandle,
+    backup_path: String,
+) -> Result<(), String> {
+    use std::fs;
+    
+    let mut db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let db_path = app_data_dir.join("todo.db");
+    let backup_file = std::path::Path::new(&backup_path);
+    
+    if !backup_file.exists() {
+        return Err("Backup file does not exist".to_string());
+    }
+    
+    // Create a backup of current DB before restoring
+    let current_backup = db_path.with_extension("db.bak");
+    let _ = fs::copy(&db_path, &current_backup);
+    
+    // Copy backup file to DB location
+    fs::copy(backup_file, &db_path).map_err(|e| crate::storage::from_io("restore the backup", &e))?;
+    
+    // Switch the open connection over to the restored file (migrating it if it is older)
+    crate::db::reopen_db(&mut db, &app_handle).map_err(|e| format!("Failed to reopen database: {}", e))?;
+    // Undo entries were taken from the database that was just replaced
+    undo.clear();
+    
+    Ok(())
+}
+
+/// Reopen todo.db after it was changed by another program (see the db-externally-modified event)
+#[tauri::command]
+pub fn reload_database(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    crate::db::reopen_db(&mut db, &app_handle).map_err(|e| format!("Failed to reopen database: {}", e))?;
+    undo.clear();
+    eprintln!("Database reloaded from disk");
+    
+    Ok(())
+}
+
+/// First step of reset_app_data: a one-time token for `scope`, valid for a minute
+#[tauri::command]
+pub fn request_reset_token(
+    tokens: State<'_, crate::data_reset::ResetTokens>,
+    scope: String,
+) -> Result<crate::data_reset::ResetTokenResponse, String> {
+    let scope = crate::data_reset::ResetScope::parse(&scope)?;
+    tokens.issue(scope, now())
+}
+
+/// Wipe "gamification", "pomodoro", "translations", "statistics_caches" or "everything", given a
+/// token from request_reset_token. "everything" takes a safety backup into the backups folder,
+/// then recreates the database empty and reconnects, so no restart is needed. It also moves the
+/// attachment files and thumbnails next to the backup and clears the session's undo history.
+#[tauri::command]
+pub fn reset_app_data(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    tokens: State<'_, crate::data_reset::ResetTokens>,
+    undo: State<'_, UndoJournal>,
+    app_handle: tauri::AppHandle,
+    scope: String,
+    token: String,
+) -> Result<crate::data_reset::ResetReport, String> {
+    let scope = crate::data_reset::ResetScope::parse(&scope)?;
+    tokens.consume(scope, &token, now())?;
+    let mut db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let report = match scope {
+        crate::data_reset::ResetScope::Everything => {
+            let app_data_dir = app_handle
+                .path_resolver()
+                .app_data_dir()
+                .ok_or_else(|| "Failed to get app data directory".to_string())?;
+            let report = crate::data_reset::reset_everything(&mut db, &app_handle, &app_data_dir)?;
+            // Undo entries point at rows the fresh database no longer has
+            undo.clear();
+            report
+        }
+        scope => crate::data_reset::reset_scope(&db.conn, scope, now())?,
+    };
+    
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
+    refresh_tray_tooltip(&app_handle, &db.conn);
+    Ok(report)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DbInfo {
+    pub path: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub modified_at: Option<i64>,
+    pub last_write_at: Option<i64>,
+    pub externally_modified: bool,
+    pub warning: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_db_info(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<DbInfo, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(db_info_internal(&db))
+}
+
+fn db_info_internal(db: &DbConnection) -> DbInfo {
+    // Folds in our own writes since the last check before comparing timestamps
+    db.check_external_modification();
+    
+    let metadata = db.path().and_then(|path| std::fs::metadata(path).ok());
+    let modified_at = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    let last_write_at = db.last_write_at();
+    
+    let newer_than_last_write = match (modified_at, last_write_at) {
+        (Some(modified), Some(last_write)) => modified > last_write,
+        _ => false,
+    };
+    let externally_modified = db.external_change_at().is_some() || newer_than_last_write;
+    let warning = if externally_modified {
+        Some("The database file was changed outside the app (for example by a sync tool). Reload the database before making changes, or they may be lost.".to_string())
+    } else {
+        None
+    };
+    
+    DbInfo {
+        path: db.path().map(|p| p.to_string_lossy().to_string()),
+        size_bytes: metadata.map(|m| m.len()),
+        modified_at,
+        last_write_at,
+        externally_modified,
+        warning,
+    }
+}
+
+// Export and import commands
+// Helper function to collect everything export_data writes (assumes lock is already held)
+// Device-local consent that must be given again on every machine, so never exported or imported
+const NON_EXPORTED_SETTINGS: &[&str] = &[translation_service::TRANSLATION_CONSENT_KEY];
+
+fn build_export_value(conn: &rusqlite::Connection) -> Result<serde_json::Value, String> {
+    // Get all tasks
+    let mut tasks = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id FROM tasks ORDER BY order_index, created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Task {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            due_date: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            priority: row.get(6)?,
+            completed: row.get::<_, Option<i64>>(7)?.is_some(),
+            project_id: row.get(8)?,
+            order_index: row.get(9).unwrap_or(0),
+            recurrence_type: row.get(11).unwrap_or_else(|_| "none".to_string()),
+            recurrence_interval: row.get(12).unwrap_or(1),
+            recurrence_parent_id: row.get(13).ok(),
+            reminder_minutes_before: None, // This query doesn't select these fields
+            notification_repeat: false,
+            tags: None,
+            is_blocked: None,
+            blocker_count: None,
+            reminder_state: None,
+            completion_note_required: None,
+            instance_count: None,
+            waiting_on: None,
+            waiting_since: None,
+            pinned: false,
+            status: None,
+            recurrence_days_of_week: None,
+            recurrence_end_date: None,
+            recurrence_count: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        tasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    // Get all projects
+    let mut projects = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, name, color, created_at, updated_at FROM projects ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            completion_note_required: None,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        projects.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    // Saved project views, kept apart from Project so older exports still parse
+    let mut project_view_states = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, view_state FROM projects WHERE view_state IS NOT NULL ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        let (project_id, view_state) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        if let Ok(view_state) = serde_json::from_str::<serde_json::Value>(&view_state) {
+            project_view_states.push(serde_json::json!({ "project_id": project_id, "view_state": view_state }));
+        }
+    }
+    
+    // Get all settings
+    let mut settings = HashMap::new();
+    let mut stmt = conn.prepare("SELECT key, value FROM settings").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        let (key, value) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        if NON_EXPORTED_SETTINGS.contains(&key.as_str()) {
+            continue;
+        }
+        settings.insert(key, value);
+    }
+    
+    // Get all subtasks
+    let mut all_subtasks = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, task_id, title, completed FROM subtasks ORDER BY id").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Subtask {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            title: row.get(2)?,
+            completed: row.get::<_, i32>(3)? != 0,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        all_subtasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    // Get all attachments
+    let mut all_attachments = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, task_id, filename, path, mime, size, created_at FROM attachments ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Attachment {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            filename: row.get(2)?,
+            path: row.get(3)?,
+            mime: row.get(4)?,
+            size: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        all_attachments.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    // Gamification and focus history, so moving machines keeps level, streak and pomodoro stats
+    let user_progress = get_user_progress_internal(conn)?;
+    
+    let mut badges = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, user_id, badge_type, earned_at, metadata FROM badges ORDER BY earned_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Badge {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            badge_type: row.get(2)?,
+            earned_at: row.get(3)?,
+            metadata: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        badges.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let mut xp_history = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, user_id, xp_amount, source, task_id, created_at FROM xp_history ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(XpHistoryEntry {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            xp_amount: row.get(2)?,
+            source: row.get(3)?,
+            task_id: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        xp_history.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let mut pomodoro_sessions = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, user_id, task_id, started_at, completed_at, duration_seconds, mode, was_completed, task_completed, created_at FROM pomodoro_sessions ORDER BY started_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(pomodoro_service::PomodoroSession {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            task_id: row.get(2)?,
+            started_at: row.get(3)?,
+            completed_at: row.get(4)?,
+            duration_seconds: row.get(5)?,
+            mode: row.get(6)?,
+            was_completed: row.get::<_, i32>(7)? != 0,
+            task_completed: row.get::<_, i32>(8)? != 0,
+            created_at: row.get(9)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        pomodoro_sessions.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    // Missing when the pomodoro feature was never used
+    let pomodoro_streak = pomodoro_service::get_pomodoro_streak(conn).ok();
+    
+    // Tags with their groups, and which tasks carry them
+    let tag_groups = get_tag_groups_internal(conn)?;
+    let mut tags = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, name, color, created_at, usage_count, group_id FROM tags ORDER BY name").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            created_at: row.get(3)?,
+            usage_count: row.get(4)?,
+            group_id: row.get(5)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        tags.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let mut task_tags = Vec::new();
+    let mut stmt = conn.prepare("SELECT task_id, tag_id FROM task_tags ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(serde_json::json!({ "task_id": row.get::<_, String>(0)?, "tag_id": row.get::<_, String>(1)? }))
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        task_tags.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let mut task_relationships = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, task_id_1, task_id_2, relationship_type, created_at FROM task_relationships ORDER BY created_at").map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(TaskRelationship {
+            id: row.get(0)?,
+            task_id_1: row.get(1)?,
+            task_id_2: row.get(2)?,
+            relationship_type: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        task_relationships.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let mut templates = Vec::new();
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM task_templates ORDER BY created_at", TEMPLATE_COLUMNS)).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], template_from_row).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        templates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let checklist_templates = get_checklists_internal(conn)?;
+    
+    Ok(serde_json::json!({
+        "tasks": tasks,
+        "projects": projects,
+        "project_view_states": project_view_states,
+        "subtasks": all_subtasks,
+        "attachments": all_attachments,
+        "settings": settings,
+        "user_progress": user_progress,
+        "badges": badges,
+        "xp_history": xp_history,
+        "pomodoro_sessions": pomodoro_sessions,
+        "pomodoro_streak": pomodoro_streak,
+        "tag_groups": tag_groups,
+        "tags": tags,
+        "task_tags": task_tags,
+        "task_relationships": task_relationships,
+        "templates": templates,
+        "checklist_templates": checklist_templates,
+        "exported_at": now(),
+    }))
+}
+
+/// export_format is "json" (the default), "csv" or "markdown". With include_translation_overrides,
+/// hand-corrected translations are added under "translation_overrides" in the
+/// export_translation_overrides format; JSON only.
+#[tauri::command]
+pub fn export_data(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    include_translation_overrides: Option<bool>,
+    export_format: Option<String>,
+) -> Result<String, String> {
+    use std::fs;
+    use std::io::Write;
+    
+    let format = export_service::ExportFormat::parse(export_format.as_deref())?;
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let contents = match format {
+        export_service::ExportFormat::Json => {
+            let mut export_data = build_export_value(&db.conn)?;
+            if include_translation_overrides.unwrap_or(false) {
+                let overrides = translation_service::get_translation_overrides(&db.conn)?;
+                export_data["translation_overrides"] = serde_json::to_value(overrides)
+                    .map_err(|e| format!("Failed to serialize data: {}", e))?;
+            }
+            serde_json::to_string_pretty(&export_data)
+                .map_err(|e| format!("Failed to serialize data: {}", e))?
+        }
+        export_service::ExportFormat::Csv => export_service::render_csv(&export_service::load_tasks(&db.conn)?),
+        export_service::ExportFormat::Markdown => export_service::render_markdown(&export_service::load_tasks(&db.conn)?),
+    };
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let export_filename = format!("todo_export_{}.{}", timestamp, format.extension());
+    let export_path = app_data_dir.join(&export_filename);
+    
+    let written = fs::File::create(&export_path).and_then(|mut file| file.write_all(contents.as_bytes()));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&export_path);
+        return Err(crate::storage::from_io("write the export file", &e).to_string());
+    }
+    
+    Ok(export_path.to_string_lossy().to_string())
+}
+
+// Tasks for export_to_csv: every task without a filter, otherwise the ones get_tasks would
+// return for it, in the same order
+fn load_csv_export_tasks(
+    conn: &rusqlite::Connection,
+    filter: Option<&TaskFilter>,
+) -> Result<Vec<export_service::ExportTask>, String> {
+    let tasks = export_service::load_tasks(conn)?;
+    let Some(filter) = filter else {
+        return Ok(tasks);
+    };
+    let mut by_id: HashMap<String, export_service::ExportTask> =
+        tasks.into_iter().map(|task| (task.id.clone(), task)).collect();
+    Ok(get_tasks_internal(conn, Some(filter), false)?
+        .into_iter()
+        .filter_map(|task| by_id.remove(&task.id))
+        .collect())
+}
+
+/// Write the tasks matching `filter` as CSV with just `columns`, in that order, and return the
+/// file's path
+#[tauri::command]
+pub fn export_to_csv(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    columns: Vec<String>,
+    filter: Option<TaskFilter>,
+) -> Result<String, String> {
+    use std::fs;
+    use std::io::Write;
+    
+    let columns = export_service::TaskColumn::parse_list(&columns)?;
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let tasks = load_csv_export_tasks(&db.conn, filter.as_ref())?;
+    let contents = export_service::render_csv_columns(&tasks, &columns);
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let export_path = app_data_dir.join(format!("todo_tasks_{}.csv", timestamp));
+    
+    let written = fs::File::create(&export_path).and_then(|mut file| file.write_all(contents.as_bytes()));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&export_path);
+        return Err(crate::storage::from_io("write the export file", &e).to_string());
+    }
+    
+    Ok(export_path.to_string_lossy().to_string())
+}
+
+// Helper function to merge an export document into the database (assumes lock is already held)
+pub(crate) fn import_export_value(conn: &rusqlite::Connection, data: &serde_json::Value) -> Result<ImportSummary, String> {
+    import_export_value_with_progress(conn, data, &mut crate::operations::NoProgress)
+}
+
+// Each table is one progress stage. Everything runs in one transaction, so a cancellation at
+// any stage boundary rolls the whole import back.
+fn import_export_value_with_progress(
+    conn: &rusqlite::Connection,
+    data: &serde_json::Value,
+    reporter: &mut dyn crate::operations::ProgressReporter,
+) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary {
+        tasks_added: 0,
+        tasks_updated: 0,
+        projects_added: 0,
+        projects_updated: 0,
+        badges_added: 0,
+        xp_history_added: 0,
+        pomodoro_sessions_added: 0,
+        user_progress_updated: 0,
+        pomodoro_streaks_updated: 0,
+        tag_groups_added: 0,
+        tags_added: 0,
+        checklists_added: 0,
+        rules_applied: 0,
+        task_tags_added: 0,
+        task_relationships_added: 0,
+        templates_added: 0,
+        templates_updated: 0,
+        translation_overrides_imported: 0,
+    };
+    let mut added_task_ids = Vec::new();
+    
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    
+    // Import projects
+    if let Some(projects) = data.get("projects").and_then(|p| p.as_array()) {
+        reporter.stage("projects", projects.len())?;
+        for (i, project_json) in projects.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(project) = serde_json::from_value::<Project>(project_json.clone()) {
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)",
+                    params![project.id],
+                    |row| row.get(0),
+                ).unwrap_or(false);
+                
+                if exists {
+                    tx.execute(
+                        "UPDATE projects SET name = ?1, color = ?2, updated_at = ?3 WHERE id = ?4",
+                        params![project.name, project.color, now(), project.id],
+                    ).ok();
+                    summary.projects_updated += 1;
+                } else {
+                    tx.execute(
+                        "INSERT INTO projects (id, name, color, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![project.id, project.name, project.color, project.created_at, project.updated_at],
+                    ).ok();
+                    summary.projects_added += 1;
+                }
+            }
+        }
+    }
+    
+    // Import tasks
+    if let Some(tasks) = data.get("tasks").and_then(|t| t.as_array()) {
+        reporter.stage("tasks", tasks.len())?;
+        for (i, task_json) in tasks.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(task) = serde_json::from_value::<Task>(task_json.clone()) {
+                validate_description(&tx, task.description.as_deref())
+                    .map_err(|e| format!("Task \"{}\": {}", task.title, e))?;
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+                    params![task.id],
+                    |row| row.get(0),
+                ).unwrap_or(false);
+                
+                if exists {
+                    tx.execute(
+                        "UPDATE tasks SET title = ?1, description = ?2, due_at = ?3, priority = ?4, completed_at = ?5, project_id = ?6, order_index = ?7, recurrence_type = ?8, recurrence_interval = ?9, updated_at = ?10 WHERE id = ?11",
+                        params![
+                            task.title,
+                            task.description,
+                            task.due_date,
+                            task.priority,
+                            if task.completed { Some(now()) } else { None::<i64> },
+                            task.project_id,
+                            task.order_index,
+                            task.recurrence_type,
+                            task.recurrence_interval,
+                            now(),
+                            task.id
+                        ],
+                    ).ok();
+                    summary.tasks_updated += 1;
+                } else {
+                    tx.execute(
+                        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                        params![
+                            task.id,
+                            task.title,
+                            task.description,
+                            task.due_date,
+                            task.created_at,
+                            task.updated_at,
+                            task.priority,
+                            if task.completed { Some(task.updated_at) } else { None::<i64> },
+                            task.project_id,
+                            task.order_index,
+                            None::<String>,
+                            task.recurrence_type,
+                            task.recurrence_interval,
+                            task.recurrence_parent_id
+                        ],
+                    ).ok();
+                    summary.tasks_added += 1;
+                    added_task_ids.push(task.id.clone());
+                }
+            }
+        }
+    }
+    
+    // Import subtasks
+    if let Some(subtasks) = data.get("subtasks").and_then(|s| s.as_array()) {
+        reporter.stage("subtasks", subtasks.len())?;
+        for (i, subtask_json) in subtasks.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(subtask) = serde_json::from_value::<Subtask>(subtask_json.clone()) {
+                // Updated in place so the row keeps its position and the FTS triggers see the change
+                let _ = tx.execute(
+                    "INSERT INTO subtasks (id, task_id, title, completed, order_index)
+                     VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(order_index) + 1, 0) FROM subtasks WHERE task_id = ?2))
+                     ON CONFLICT(id) DO UPDATE SET title = excluded.title, completed = excluded.completed",
+                    params![subtask.id, subtask.task_id, subtask.title, if subtask.completed { 1 } else { 0 }],
+                );
+            }
+        }
+    }
+    
+    // Import tag groups, matched by id
+    if let Some(groups) = data.get("tag_groups").and_then(|g| g.as_array()) {
+        reporter.stage("tag_groups", groups.len())?;
+        for (i, group_json) in groups.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(group) = serde_json::from_value::<TagGroup>(group_json.clone()) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO tag_groups (id, name, color, order_index, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![group.id, group.name, group.color, group.order_index, group.created_at],
+                ).unwrap_or(0);
+                summary.tag_groups_added += inserted;
+            }
+        }
+    }
+    
+    // Import tags, matched by name (tag names are unique); local groupings win over imported ones
+    let mut tag_ids: HashMap<String, String> = HashMap::new();
+    if let Some(tags) = data.get("tags").and_then(|t| t.as_array()) {
+        reporter.stage("tags", tags.len())?;
+        for (i, tag_json) in tags.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(tag) = serde_json::from_value::<Tag>(tag_json.clone()) {
+                let group_id = tag.group_id.filter(|group_id| {
+                    tx.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM tag_groups WHERE id = ?1)",
+                        params![group_id],
+                        |row| row.get(0),
+                    ).unwrap_or(false)
+                });
+                let existing: Option<String> = tx.query_row(
+                    "SELECT id FROM tags WHERE name = ?1",
+                    params![tag.name],
+                    |row| row.get(0),
+                ).ok();
+                
+                match existing {
+                    Some(local_id) => {
+                        let _ = tx.execute(
+                            "UPDATE tags SET group_id = COALESCE(group_id, ?1) WHERE id = ?2",
+                            params![group_id, local_id],
+                        );
+                        tag_ids.insert(tag.id, local_id);
+                    }
+                    None => {
+                        let inserted = tx.execute(
+                            "INSERT OR IGNORE INTO tags (id, name, color, created_at, usage_count, group_id) VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+                            params![tag.id, tag.name, tag.color, tag.created_at, group_id],
+                        ).unwrap_or(0);
+                        summary.tags_added += inserted;
+                        tag_ids.insert(tag.id.clone(), tag.id);
+                    }
+                }
+            }
+        }
+    }
+    
+    // Import task tags, skipping tasks or tags that didn't make it in
+    if let Some(task_tags) = data.get("task_tags").and_then(|t| t.as_array()) {
+        reporter.stage("task_tags", task_tags.len())?;
+        for (i, link) in task_tags.iter().enumerate() {
+            reporter.progress(i);
+            let task_id = link.get("task_id").and_then(|v| v.as_str());
+            let tag_id = link.get("tag_id").and_then(|v| v.as_str()).and_then(|id| tag_ids.get(id));
+            if let (Some(task_id), Some(tag_id)) = (task_id, tag_id) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO task_tags (id, task_id, tag_id, created_at)
+                     SELECT ?1, ?2, ?3, ?4 WHERE EXISTS(SELECT 1 FROM tasks WHERE id = ?2)",
+                    params![uuid::Uuid::new_v4().to_string(), task_id, tag_id, now()],
+                ).unwrap_or(0);
+                if inserted > 0 {
+                    let _ = tx.execute("UPDATE tags SET usage_count = usage_count + 1 WHERE id = ?1", params![tag_id]);
+                    summary.task_tags_added += 1;
+                }
+            }
+        }
+    }
+    
+    // Import task relationships between tasks that exist; a link already present (either
+    // direction for the same pair) or a block that would close a cycle is skipped
+    if let Some(relationships) = data.get("task_relationships").and_then(|r| r.as_array()) {
+        reporter.stage("task_relationships", relationships.len())?;
+        for (i, relationship_json) in relationships.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(relationship) = serde_json::from_value::<TaskRelationship>(relationship_json.clone()) {
+                if relationship.relationship_type == "blocks"
+                    && check_circular_dependency_internal(&tx, &relationship.task_id_1, &relationship.task_id_2).unwrap_or(true)
+                {
+                    continue;
+                }
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at)
+                     SELECT ?1, ?2, ?3, ?4, ?5
+                     WHERE EXISTS(SELECT 1 FROM tasks WHERE id = ?2) AND EXISTS(SELECT 1 FROM tasks WHERE id = ?3)
+                       AND NOT EXISTS(SELECT 1 FROM task_relationships WHERE task_id_1 = ?3 AND task_id_2 = ?2)",
+                    params![relationship.id, relationship.task_id_1, relationship.task_id_2, relationship.relationship_type, relationship.created_at],
+                ).unwrap_or(0);
+                summary.task_relationships_added += inserted;
+            }
+        }
+    }
+    
+    // Import task templates, matched by id; projects that didn't make it in are dropped
+    if let Some(templates) = data.get("templates").and_then(|t| t.as_array()) {
+        reporter.stage("templates", templates.len())?;
+        for (i, template_json) in templates.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(template) = serde_json::from_value::<Template>(template_json.clone()) {
+                let project_exists = |id: &Option<String>| -> Option<String> {
+                    id.clone().filter(|id| {
+                        tx.query_row("SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)", params![id], |row| row.get(0))
+                            .unwrap_or(false)
+                    })
+                };
+                let project_id = project_exists(&template.project_id);
+                let scope_project_id = project_exists(&template.scope_project_id);
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM task_templates WHERE id = ?1)",
+                    params![template.id],
+                    |row| row.get(0),
+                ).unwrap_or(false);
+                
+                if exists {
+                    tx.execute(
+                        "UPDATE task_templates SET name = ?1, title = ?2, description = ?3, priority = ?4, project_id = ?5,
+                         scope_project_id = ?6, updated_at = ?7 WHERE id = ?8",
+                        params![template.name, template.title, template.description, template.priority, project_id, scope_project_id, now(), template.id],
+                    ).ok();
+                    summary.templates_updated += 1;
+                } else {
+                    tx.execute(
+                        "INSERT INTO task_templates (id, name, title, description, priority, project_id, scope_project_id, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![template.id, template.name, template.title, template.description, template.priority, project_id, scope_project_id, template.created_at, template.updated_at],
+                    ).ok();
+                    summary.templates_added += 1;
+                }
+            }
+        }
+    }
+    
+    // Import checklists, matched by id
+    if let Some(checklists) = data.get("checklist_templates").and_then(|c| c.as_array()) {
+        reporter.stage("checklist_templates", checklists.len())?;
+        for (i, checklist_json) in checklists.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(checklist) = serde_json::from_value::<ChecklistTemplate>(checklist_json.clone()) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO checklist_templates (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![checklist.id, checklist.name, checklist.created_at, checklist.updated_at],
+                ).unwrap_or(0);
+                if inserted > 0 {
+                    set_checklist_items(&tx, &checklist.id, &checklist.items)?;
+                    summary.checklists_added += 1;
+                }
+            }
+        }
+    }
+    
+    // Import project views for projects that exist; invalid states are skipped
+    if let Some(view_states) = data.get("project_view_states").and_then(|v| v.as_array()) {
+        reporter.stage("project_view_states", view_states.len())?;
+        for (i, entry) in view_states.iter().enumerate() {
+            reporter.progress(i);
+            let project_id = entry.get("project_id").and_then(|v| v.as_str());
+            let state = entry.get("view_state").and_then(|v| serde_json::from_value::<ProjectViewState>(v.clone()).ok());
+            if let (Some(project_id), Some(state)) = (project_id, state) {
+                let _ = set_project_view_state_internal(&tx, project_id, Some(&state));
+            }
+        }
+    }
+    
+    // Import settings
+    if let Some(settings) = data.get("settings").and_then(|s| s.as_object()) {
+        reporter.stage("settings", settings.len())?;
+        for (key, value) in settings {
+            if NON_EXPORTED_SETTINGS.contains(&key.as_str()) {
+                continue;
+            }
+            if let Some(value_str) = value.as_str() {
+                let _ = tx.execute(
+                    "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                    params![key, value_str],
+                );
+            }
+        }
+    }
+    
+    // Import user progress: keep whichever side is further along instead of overwriting
+    if let Some(progress_json) = data.get("user_progress") {
+        reporter.stage("user_progress", 1)?;
+        if let Ok(imported) = serde_json::from_value::<UserProgress>(progress_json.clone()) {
+            let current = get_user_progress_internal(&tx)?;
+            
+            let total_xp = current.total_xp.max(imported.total_xp);
+            let (current_streak, last_completion_date) = if imported.last_completion_date.unwrap_or(0) >= current.last_completion_date.unwrap_or(0) {
+                (imported.current_streak, imported.last_completion_date)
+            } else {
+                (current.current_streak, current.last_completion_date)
+            };
+            
+            tx.execute(
+                "UPDATE user_progress SET total_xp = ?1, current_level = ?2, current_streak = ?3, longest_streak = ?4, last_completion_date = ?5, created_at = ?6, updated_at = ?7 WHERE id = 'default'",
+                params![
+                    total_xp,
+                    calculate_level(total_xp),
+                    current_streak,
+                    current.longest_streak.max(imported.longest_streak),
+                    last_completion_date,
+                    current.created_at.min(imported.created_at),
+                    now()
+                ],
+            ).map_err(|e| format!("Failed to import user progress: {}", e))?;
+            summary.user_progress_updated += 1;
+        }
+    }
+    
+    // Import badges, matched by badge type since each badge can only be earned once
+    if let Some(badges) = data.get("badges").and_then(|b| b.as_array()) {
+        reporter.stage("badges", badges.len())?;
+        for (i, badge_json) in badges.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(badge) = serde_json::from_value::<Badge>(badge_json.clone()) {
+                let earned: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM badges WHERE user_id = ?1 AND badge_type = ?2)",
+                    params![badge.user_id, badge.badge_type],
+                    |row| row.get(0),
+                ).unwrap_or(false);
+                
+                if !earned {
+                    let inserted = tx.execute(
+                        "INSERT OR IGNORE INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![badge.id, badge.user_id, badge.badge_type, badge.earned_at, badge.metadata],
+                    ).unwrap_or(0);
+                    summary.badges_added += inserted;
+                }
+            }
+        }
+    }
+    
+    // Import XP history, matched by id
+    if let Some(entries) = data.get("xp_history").and_then(|x| x.as_array()) {
+        reporter.stage("xp_history", entries.len())?;
+        for (i, entry_json) in entries.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(entry) = serde_json::from_value::<XpHistoryEntry>(entry_json.clone()) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO xp_history (id, user_id, xp_amount, source, task_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![entry.id, entry.user_id, entry.xp_amount, entry.source, entry.task_id, entry.created_at],
+                ).unwrap_or(0);
+                summary.xp_history_added += inserted;
+            }
+        }
+    }
+    
+    // Import pomodoro sessions, matched by id
+    if let Some(sessions) = data.get("pomodoro_sessions").and_then(|p| p.as_array()) {
+        reporter.stage("pomodoro_sessions", sessions.len())?;
+        for (i, session_json) in sessions.iter().enumerate() {
+            reporter.progress(i);
+            if let Ok(session) = serde_json::from_value::<pomodoro_service::PomodoroSession>(session_json.clone()) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO pomodoro_sessions (id, user_id, task_id, started_at, completed_at, duration_seconds, mode, was_completed, task_completed, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        session.id,
+                        session.user_id,
+                        session.task_id,
+                        session.started_at,
+                        session.completed_at,
+                        session.duration_seconds,
+                        session.mode,
+                        if session.was_completed { 1 } else { 0 },
+                        if session.task_completed { 1 } else { 0 },
+                        session.created_at
+                    ],
+                ).unwrap_or(0);
+                summary.pomodoro_sessions_added += inserted;
+            }
+        }
+    }
+    
+    // Import pomodoro streak, merged the same way as user progress
+    if let Some(streak_json) = data.get("pomodoro_streak").filter(|s| !s.is_null()) {
+        reporter.stage("pomodoro_streak", 1)?;
+        if let Ok(imported) = serde_json::from_value::<pomodoro_service::PomodoroStreak>(streak_json.clone()) {
+            let current = pomodoro_service::get_pomodoro_streak(&tx).ok();
+            let (current_streak, longest_streak, last_session_date) = match current {
+                Some(current) if current.last_session_date.unwrap_or(0) > imported.last_session_date.unwrap_or(0) => (
+                    current.current_streak,
+                    current.longest_streak.max(imported.longest_streak),
+                    current.last_session_date,
+                ),
+                Some(current) => (
+                    imported.current_streak,
+                    current.longest_streak.max(imported.longest_streak),
+                    imported.last_session_date,
+                ),
+                None => (imported.current_streak, imported.longest_streak, imported.last_session_date),
+            };
+            
+            let now = now();
+            tx.execute(
+                "INSERT INTO pomodoro_streaks (id, user_id, current_streak, longest_streak, last_session_date, created_at, updated_at)
+                 VALUES ('default', 'default', ?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(id) DO UPDATE SET current_streak = ?1, longest_streak = ?2, last_session_date = ?3, updated_at = ?4",
+                params![current_streak, longest_streak, last_session_date, now],
+            ).map_err(|e| format!("Failed to import pomodoro streak: {}", e))?;
+            summary.pomodoro_streaks_updated += 1;
+        }
+    }
+    
+    if let Some(document) = data.get("translation_overrides") {
+        let document: translation_service::TranslationOverridesDocument = serde_json::from_value(document.clone())
+            .map_err(|e| format!("Invalid translation overrides: {}", e))?;
+        reporter.stage("translation_overrides", 1)?;
+        let imported = translation_service::import_translation_overrides(&tx, &document.overrides)?;
+        summary.translation_overrides_imported = imported.added + imported.updated;
+    }
+    
+    // Rules run last so imported tags are already attached when conditions are checked
+    let rules = rules_service::get_rules(&tx, true)?;
+    if !rules.is_empty() {
+        reporter.stage("rules", added_task_ids.len())?;
+        for (i, task_id) in added_task_ids.iter().enumerate() {
+            reporter.progress(i);
+            summary.rules_applied += rules_service::apply_rules(&tx, task_id, &rules, "import")?.applications.len();
+        }
+    }
+    
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(summary)
+}
+
+// Async so it runs off the main thread and progress events reach the window while it works
+#[tauri::command]
+pub async fn import_data(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    operations: State<'_, crate::operations::Operations>,
+    app_handle: tauri::AppHandle,
+    file_path: String,
+    operation_id: Option<String>,
+) -> Result<ImportSummary, String> {
+    use std::fs;
+    
+    crate::operations::run(&app_handle, &operations, operation_id, |reporter| {
+        reporter.stage("reading", 1)?;
+        let file_contents = fs::read_to_string(&file_path)
+            .map_err(|e| format!("Failed to read import file: {}", e))?;
+        
+        let data: serde_json::Value = serde_json::from_str(&file_contents)
+            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        import_export_value_with_progress(&db.conn, &data, reporter)
+    })
+}
+
+fn read_import_table(path: &str, format: &str) -> Result<import_service::ImportTable, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    let (text, encoding_issues) = import_service::decode_text(&bytes)?;
+    import_service::read_table(&text, format, encoding_issues)
+}
+
+/// Columns of a CSV (header row) or JSON task list with sample values, the proposed mapping
+/// to task fields and the row count, for the mapping screen shown before
+/// import_tasks_from_file
+#[tauri::command]
+pub fn inspect_import_file(path: String, format: String) -> Result<import_service::ImportInspection, String> {
+    let table = read_import_table(&path, &format)?;
+    Ok(import_service::inspect(&table, &format))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileImportSummary {
+    pub imported: usize,
+    /// Rows without a title
+    pub skipped: usize,
+    pub projects_created: usize,
+    /// Values that couldn't be read, by row
+    pub warnings: Vec<String>,
+}
+
+// Insert imported tasks. Projects are matched by name (case-insensitive) and created when
+// missing; tags go through attach_tag_by_name. Returns (projects created, tags created).
+fn insert_imported_tasks(
+    conn: &rusqlite::Connection,
+    tasks: &[import_service::MappedTask],
+) -> Result<(usize, usize), String> {
+    let count_tags = |conn: &rusqlite::Connection| -> Result<i64, String> {
+        conn.query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))
+            .map_err(|e| format!("Query error: {}", e))
+    };
+    let tags_before = count_tags(conn)?;
+    let mut projects: HashMap<String, String> = HashMap::new();
+    let mut projects_created = 0;
+    
+    for task in tasks {
+        let project_id = match &task.project {
+            Some(name) => {
+                let key = name.to_lowercase();
+                if !projects.contains_key(&key) {
+                    let existing: Option<String> = conn.query_row(
+                        "SELECT id FROM projects WHERE LOWER(name) = ?1 ORDER BY created_at LIMIT 1",
+                        params![key],
+                        |row| row.get(0),
+                    ).optional().map_err(|e| format!("Query error: {}", e))?;
+                    let id = match existing {
+                        Some(id) => id,
+                        None => {
+                            let id = uuid::Uuid::new_v4().to_string();
+                            conn.execute(
+                                "INSERT INTO projects (id, name, color, created_at, updated_at) VALUES (?1, ?2, NULL, ?3, ?3)",
+                                params![id, name, now()],
+                            ).map_err(|e| format!("Failed to create project: {}", e))?;
+                            projects_created += 1;
+                            id
+                        }
+                    };
+                    projects.insert(key.clone(), id);
+                }
+                projects.get(&key).cloned()
+            }
+            None => None,
+        };
+        
+        validate_description(conn, task.description.as_deref())
+            .map_err(|e| format!("Task \"{}\": {}", task.title, e))?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = now();
+        conn.execute(
+            "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8, ?9)",
+            params![id, task.title, task.description, task.due_at, now, task.priority, task.completed_at, project_id, next_order_index(conn, project_id.as_deref())?],
+        ).map_err(|e| format!("Failed to import task: {}", e))?;
+        for tag in &task.tags {
+            attach_tag_by_name(conn, &id, tag)?;
+        }
+    }
+    
+    Ok((projects_created, (count_tags(conn)? - tags_before).max(0) as usize))
+}
+
+// Insert the mapped rows in one transaction
+fn import_tasks_from_table(
+    conn: &rusqlite::Connection,
+    table: &import_service::ImportTable,
+    mapping: &import_service::ImportMapping,
+) -> Result<FileImportSummary, String> {
+    let (tasks, skipped, warnings) = import_service::map_rows(table, mapping)?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let (projects_created, _) = insert_imported_tasks(&tx, &tasks)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(FileImportSummary {
+        imported: tasks.len(),
+        skipped,
+        projects_created,
+        warnings,
+    })
+}
+
+/// Import tasks from a CSV or JSON task list. `mapping` overrides the proposed one from
+/// inspect_import_file and must only name columns the file has.
+#[tauri::command]
+pub fn import_tasks_from_file(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    path: String,
+    format: String,
+    mapping: Option<import_service::ImportMapping>,
+) -> Result<FileImportSummary, String> {
+    let table = read_import_table(&path, &format)?;
+    let mapping = mapping.unwrap_or_else(|| import_service::propose_mapping(&table.columns));
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    import_tasks_from_table(&db.conn, &table, &mapping)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExternalImportSummary {
+    pub source: external_import_service::ExternalSource,
+    pub imported: usize,
+    /// Rows that aren't tasks, e.g. Todoist sections
+    pub skipped: usize,
+    pub projects_created: usize,
+    pub tags_created: usize,
+    /// Rows that couldn't be imported, with the reason
+    pub failed_rows: Vec<external_import_service::FailedImportRow>,
+    /// Imported tasks that lost a value (an unreadable due date or priority), by row
+    pub warnings: Vec<String>,
+}
+
+fn import_external_internal(
+    conn: &rusqlite::Connection,
+    path: &std::path::Path,
+    source: external_import_service::ExternalSource,
+) -> Result<ExternalImportSummary, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    let (text, encoding_issues) = import_service::decode_text(&bytes)?;
+    let mut rows = match source {
+        // A Todoist export holds one project, named like the file
+        external_import_service::ExternalSource::TodoistCsv => {
+            let project = path.file_stem().map(|stem| stem.to_string_lossy().trim().to_string()).filter(|name| !name.is_empty());
+            external_import_service::read_todoist_csv(&text, project.as_deref())?
+        }
+        external_import_service::ExternalSource::TicktickCsv => external_import_service::read_ticktick_csv(&text, now())?,
+    };
+    
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let (projects_created, tags_created) = insert_imported_tasks(&tx, &rows.tasks)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    rows.warnings.splice(0..0, encoding_issues);
+    Ok(ExternalImportSummary {
+        source,
+        imported: rows.tasks.len(),
+        skipped: rows.skipped,
+        projects_created,
+        tags_created,
+        failed_rows: rows.failed_rows,
+        warnings: rows.warnings,
+    })
+}
+
+/// Import a Todoist ("todoist_csv") or TickTick ("ticktick_csv") CSV export, creating missing
+/// projects and tags. All rows that could be read go in one transaction.
+#[tauri::command]
+pub fn import_external(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    path: String,
+    source: external_import_service::ExternalSource,
+) -> Result<ExternalImportSummary, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    import_external_internal(&db.conn, std::path::Path::new(&path), source)
+}
+
+/// Ask a long-running command started with this operation id to stop at its next safe point.
+/// Returns false when no such operation is running.
+#[tauri::command]
+pub fn cancel_operation(
+    operations: State<'_, crate::operations::Operations>,
+    operation_id: String,
+) -> bool {
+    operations.cancel(&operation_id)
+}
+
+// Notification command
+#[tauri::command]
+pub fn show_notification(title: String, body: String) -> Result<(), String> {
+    crate::notifications::show_notification(&title, &body)
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+// Auto-start commands using Windows Registry (manual implementation for Tauri 1.8)
+#[cfg(target_os = "windows")]
+#[tauri::command]
+pub fn get_autostart_enabled(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+    
+    let app_name = app_handle.package_info().name.clone();
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    
+    // Try to open the Run key, return false if it doesn't exist
+    let run_key = match hkcu.open_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Run") {
+        Ok(key) => key,
+        Err(_) => return Ok(false),
+    };
+    
+    // Check if the app name exists in the registry
+    match run_key.get_value::<String, _>(&app_name) {
+        Ok(_) => Ok(true),
+        Err(_) => Ok(false),
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[tauri::command]
+pub fn set_autostart_enabled(
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+    use std::env;
+    
+    let app_name = app_handle.package_info().name.clone();
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let (run_key, _) = hkcu.create_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Run")
+        .map_err(|e| format!("Failed to create/open registry key: {}", e))?;
+    
+    if enabled {
+        // Get the current executable path
+        let exe_path = env::current_exe()
+            .map_err(|e| format!("Failed to get executable path: {}", e))?;
+        let exe_path_str = exe_path.to_string_lossy().to_string();
+        
+        // Wrap path in quotes if it contains spaces (Windows requirement)
+        let registry_value = if exe_path_str.contains(' ') {
+            format!("\"{}\"", exe_path_str)
+        } else {
+            exe_path_str
+        };
+        
+        run_key.set_value(&app_name, &registry_value)
+            .map_err(|e| format!("Failed to set registry value: {}", e))?;
+    } else {
+        // Try to delete the value, but don't error if it doesn't exist
+        let _ = run_key.delete_value(&app_name);
+    }
+    
+    Ok(())
+}
+
+// Fallback for non-Windows platforms
+#[cfg(not(target_os = "windows"))]
+#[tauri::command]
+pub fn get_autostart_enabled(_app_handle: tauri::AppHandle) -> Result<bool, String> {
+    Ok(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+#[tauri::command]
+pub fn set_autostart_enabled(
+    _app_handle: tauri::AppHandle,
+    _enabled: bool,
+) -> Result<(), String> {
+    Err("Autostart is only supported on Windows in this version".to_string())
+}
+
+// Notification commands
+#[tauri::command]
+pub fn snooze_notification(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    notification_id: String,
+    minutes: i32,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::notifications::snooze_notification(&db, &notification_id, minutes)
+        .map_err(|e| format!("Failed to snooze notification: {}", e))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MissedReminder {
+    pub id: String,
+    pub task_id: String,
+    pub title: String,
+    pub scheduled_at: i64,
+    pub missed_at: i64,
+}
+
+fn get_missed_reminders_internal(conn: &rusqlite::Connection) -> Result<Vec<MissedReminder>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT ns.id, ns.task_id, t.title, ns.scheduled_at, ns.missed_at
+         FROM notification_schedule ns
+         JOIN tasks t ON ns.task_id = t.id
+         WHERE ns.missed_at IS NOT NULL AND t.completed_at IS NULL
+         ORDER BY ns.scheduled_at DESC"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(MissedReminder {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            title: row.get(2)?,
+            scheduled_at: row.get(3)?,
+            missed_at: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut reminders = Vec::new();
+    for row in rows {
+        reminders.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(reminders)
+}
+
+/// Reminders that were coalesced into a "missed while away" notification
+#[tauri::command]
+pub fn get_missed_reminders(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<MissedReminder>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_missed_reminders_internal(&db.conn)?))
+}
+
+/// Clear missed reminders from the list; all of them when `ids` is None
+#[tauri::command]
+pub fn dismiss_missed_reminders(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    ids: Option<Vec<String>>,
+) -> Result<usize, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    match ids {
+        Some(ids) => {
+            let mut removed = 0;
+            for id in ids {
+                removed += db.conn.execute(
+                    "DELETE FROM notification_schedule WHERE id = ?1 AND missed_at IS NOT NULL",
+                    params![id],
+                ).map_err(|e| format!("Failed to dismiss reminder: {}", e))?;
+            }
+            Ok(removed)
+        }
+        None => db.conn.execute("DELETE FROM notification_schedule WHERE missed_at IS NOT NULL", [])
+            .map_err(|e| format!("Failed to dismiss reminders: {}", e)),
+    }
+}
+
+// Statistics commands. Archived tasks are left out unless `include_deleted` is set.
+#[tauri::command]
+pub fn get_completion_stats(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    days: i32,
+    include_deleted: Option<bool>,
+) -> Result<Vec<stats_service::CompletionStats>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_completion_stats(&db.conn, days, include_deleted.unwrap_or(false))
+}
+
+/// Completions per day of `year`, for the stats heatmap
+#[tauri::command]
+pub fn get_completion_heatmap(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    year: i32,
+    include_deleted: Option<bool>,
+) -> Result<Vec<stats_service::HeatmapEntry>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_completion_heatmap(&db.conn, year, include_deleted.unwrap_or(false))
+}
+
+/// Created, completed and overdue tasks, XP and pomodoros for each of the last `weeks_back`
+/// calendar weeks, this one included
+#[tauri::command]
+pub fn get_weekly_summary(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    weeks_back: i32,
+    include_deleted: Option<bool>,
+) -> Result<Vec<stats_service::WeeklySummary>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_weekly_summary(&db.conn, weeks_back, now(), include_deleted.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_priority_distribution(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    include_deleted: Option<bool>,
+) -> Result<Vec<stats_service::PriorityDistribution>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_priority_distribution(&db.conn, include_deleted.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_project_stats(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    include_deleted: Option<bool>,
+) -> Result<Vec<stats_service::ProjectStats>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(stats_service::get_project_stats(&db.conn, include_deleted.unwrap_or(false))?))
+}
+
+#[tauri::command]
+pub fn get_productivity_trend(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    start_date: i64,
+    end_date: i64,
+    include_deleted: Option<bool>,
+) -> Result<Vec<stats_service::ProductivityTrend>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_productivity_trend(&db.conn, start_date, end_date, include_deleted.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_most_productive_day(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    include_deleted: Option<bool>,
+) -> Result<Option<stats_service::MostProductiveDay>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_most_productive_day(&db.conn, include_deleted.unwrap_or(false))
+}
+
+/// Projected finish date range for a project, from its completions over the last 4 weeks
+#[tauri::command]
+pub fn get_project_forecast(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    project_id: String,
+) -> Result<stats_service::ProjectForecast, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_project_forecast(&db.conn, &project_id, now())
+}
+
+#[tauri::command]
+pub fn get_productivity_score(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    window_days: Option<i64>,
+) -> Result<stats_service::ProductivityScore, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_productivity_score(&db.conn, window_days.unwrap_or(7), now())
+}
+
+/// One score per day for a sparkline, each over the rolling window ending that day
+#[tauri::command]
+pub fn get_productivity_score_history(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    window_days: Option<i64>,
+    days: Option<i64>,
+) -> Result<Vec<stats_service::ProductivityScorePoint>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_productivity_score_history(&db.conn, window_days.unwrap_or(7), days.unwrap_or(30), now())
+}
+
+/// Completions per hour of day over the last 90 days
+#[tauri::command]
+pub fn get_completion_time_profile(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<completion_time_service::CompletionTimeProfile, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    completion_time_service::get_completion_time_profile(&db.conn, now())
+}
+
+/// The hour smart reminders use, for "you usually finish tasks around 20:00" in settings
+#[tauri::command]
+pub fn get_learned_reminder_hour(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<completion_time_service::LearnedReminderHour, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    completion_time_service::get_learned_reminder_hour(&db.conn, now())
+}
+
+#[tauri::command]
+pub fn get_average_completion_time(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    include_deleted: Option<bool>,
+) -> Result<f64, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    stats_service::get_average_completion_time(&db.conn, include_deleted.unwrap_or(false))
+}
+
+/// Estimated vs actual minutes for tasks that have both, for time-box analytics
+#[tauri::command]
+pub fn get_time_estimation_accuracy(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<stats_service::TimeEstimationRow>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let rows = stats_service::get_time_estimation_accuracy(&db.conn)?;
+    Ok(demo.apply(rows))
+}
+
+// Template data structures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: String,
+    pub project_id: Option<String>,
+    /// Only offer the template inside this project; None means it's global
+    #[serde(default)]
+    pub scope_project_id: Option<String>,
+    pub recurrence_type: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTemplateInput {
+    pub name: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: String,
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub scope_project_id: Option<String>,
+    pub recurrence_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTemplateInput {
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub priority: Option<String>,
+    pub project_id: Option<String>,
+    /// Empty string makes the template global again
+    #[serde(default)]
+    pub scope_project_id: Option<String>,
+    pub recurrence_type: Option<String>,
+    /// See UpdateTaskInput::expected_updated_at
+    #[serde(default)]
+    pub expected_updated_at: Option<i64>,
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, title, description, priority, project_id, scope_project_id, created_at, updated_at";
+
+fn template_from_row(row: &rusqlite::Row) -> rusqlite::Result<Template> {
+    Ok(Template {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        priority: row.get(4)?,
+        project_id: row.get(5)?,
+        scope_project_id: row.get(6)?,
+        recurrence_type: None,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn fetch_template(conn: &rusqlite::Connection, id: &str) -> Result<Template, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM task_templates WHERE id = ?1", TEMPLATE_COLUMNS),
+        params![id],
+        template_from_row,
+    ).map_err(|e| format!("Template not found: {}", e))
+}
+
+fn ensure_project_exists(conn: &rusqlite::Connection, project_id: &str) -> Result<(), String> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)",
+        params![project_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))?;
+    if !exists {
+        return Err(format!("Project not found: {}", project_id));
+    }
+    Ok(())
+}
+
+fn create_template_internal(conn: &rusqlite::Connection, input: CreateTemplateInput) -> Result<Template, String> {
+    if let Some(scope_project_id) = &input.scope_project_id {
+        ensure_project_exists(conn, scope_project_id)?;
+    }
+    
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    
+    conn.execute(
+        "INSERT INTO task_templates (id, name, title, description, priority, project_id, scope_project_id, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            id.clone(),
+            input.name,
+            input.title,
+            input.description,
+            input.priority,
+            input.project_id,
+            input.scope_project_id,
+            now,
+            now,
+        ],
+    ).map_err(|e| format!("Failed to create template: {}", e))?;
+    
+    fetch_template(conn, &id).map_err(|e| format!("Failed to fetch created template: {}", e))
+}
+
+/// All templates, or with `project_id` only the global ones plus those scoped to that project
+fn get_templates_internal(conn: &rusqlite::Connection, project_id: Option<&str>) -> Result<Vec<Template>, String> {
+    let (condition, query_params): (&str, Vec<&dyn rusqlite::ToSql>) = match &project_id {
+        Some(project_id) => ("WHERE scope_project_id IS NULL OR scope_project_id = ?1", vec![project_id]),
+        None => ("", Vec::new()),
+    };
+    let query = format!("SELECT {} FROM task_templates {} ORDER BY created_at DESC", TEMPLATE_COLUMNS, condition);
+    
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(query_params.as_slice(), template_from_row)
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut templates = Vec::new();
+    for row in rows {
+        templates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(templates)
+}
+
+// Template commands
+#[tauri::command]
+pub fn create_template(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    input: CreateTemplateInput,
+) -> Result<Template, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    create_template_internal(&db.conn, input)
+}
+
+#[tauri::command]
+pub fn get_templates(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    project_id: Option<String>,
+) -> Result<Vec<Template>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_templates_internal(&db.conn, project_id.as_deref()).map(|value| demo.apply(value))
+}
+
+#[tauri::command]
+pub fn get_template(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    id: String,
+) -> Result<Template, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    fetch_template(&db.conn, &id).map(|value| demo.apply(value))
+}
+
+#[tauri::command]
+pub fn update_template(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    input: UpdateTemplateInput,
+) -> Result<Template, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    update_template_internal(&db.conn, &id, input)
+}
+
+fn update_template_internal(conn: &rusqlite::Connection, id: &str, input: UpdateTemplateInput) -> Result<Template, String> {
+    ensure_not_stale(conn, "task_templates", id, input.expected_updated_at, || fetch_template(conn, id))?;
+    
+    let now = now();
+    let mut updates = Vec::new();
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    if let Some(name) = input.name {
+        updates.push("name = ?");
+        query_params.push(Box::new(name));
+    }
+    if let Some(title) = input.title {
+        updates.push("title = ?");
+        query_params.push(Box::new(title));
+    }
+    if let Some(description) = input.description {
+        updates.push("description = ?");
+        query_params.push(Box::new(description));
+    }
+    if let Some(priority) = input.priority {
+        updates.push("priority = ?");
+        query_params.push(Box::new(priority));
+    }
+    if let Some(project_id) = input.project_id {
+        updates.push("project_id = ?");
+        query_params.push(Box::new(project_id));
+    }
+    
+    
+    if let Some(scope_project_id) = input.scope_project_id {
+        if scope_project_id.is_empty() {
+            updates.push("scope_project_id = NULL");
+        } else {
+            ensure_project_exists(conn, &scope_project_id)?;
+            updates.push("scope_project_id = ?");
+            query_params.push(Box::new(scope_project_id));
+        }
+    }
+    
+    if !updates.is_empty() {
+        updates.push(BUMP_UPDATED_AT);
+        query_params.push(Box::new(now));
+        query_params.push(Box::new(id.to_string()));
+        
+        let query = format!("UPDATE task_templates SET {} WHERE id = ?", updates.join(", "));
+        conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
+            .map_err(|e| format!("Failed to update template: {}", e))?;
+    }
+    
+    fetch_template(conn, id)
+}
+
+#[tauri::command]
+pub fn delete_template(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    db.conn.execute("DELETE FROM task_templates WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete template: {}", e))?;
+    
+    Ok(())
+}
+
+/// `project_id` overrides the template's target project, e.g. when using a global template inside a project
+fn create_task_from_template_internal(
+    conn: &rusqlite::Connection,
+    template_id: &str,
+    due_date: Option<i64>,
+    project_id: Option<String>,
+) -> Result<Task, String> {
+    let template = fetch_template(conn, template_id)?;
+    let project_id = match project_id {
+        Some(project_id) => {
+            ensure_project_exists(conn, &project_id)?;
+            Some(project_id)
+        }
+        None => template.project_id,
+    };
+    
+    validate_description(conn, template.description.as_deref())?;
+    
+    // Create task from template
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    
+    conn.execute(
+        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            id.clone(),
+            template.title,
+            template.description,
+            due_date,
+            now,
+            now,
+            template.priority,
+            None::<i64>,
+            project_id,
+            0,
+            None::<String>,
+            "none",
+            1,
+            None::<String>
+        ],
+    ).map_err(|e| format!("Failed to create task from template: {}", e))?;
+    
+    fetch_task(conn, &id)
+}
+
+#[tauri::command]
+pub fn create_task_from_template(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    template_id: String,
+    due_date: Option<i64>,
+    project_id: Option<String>,
+) -> Result<Task, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    create_task_from_template_internal(&db.conn, &template_id, due_date, project_id)
+}
+
+// Checklist templates: named, ordered lists of subtasks that can be appended to any task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistTemplate {
+    pub id: String,
+    pub name: String,
+    pub items: Vec<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateChecklistInput {
+    pub name: String,
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateChecklistInput {
+    pub name: Option<String>,
+    /// Replaces the whole list when given
+    pub items: Option<Vec<String>>,
+}
+
+fn validate_checklist(name: &str, items: &[String]) -> Result<Vec<String>, String> {
+    if name.trim().is_empty() {
+        return Err("Checklist name cannot be empty".to_string());
+    }
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect();
+    if items.is_empty() {
+        return Err("Checklist needs at least one item".to_string());
+    }
+    if items.len() > MAX_BULK_SUBTASKS {
+        return Err(format!("Too many checklist items: the limit is {}", MAX_BULK_SUBTASKS));
+    }
+    Ok(items)
+}
+
+// Helper function to replace a checklist's items (caller provides the transaction)
+fn set_checklist_items(conn: &rusqlite::Connection, checklist_id: &str, items: &[String]) -> Result<(), String> {
+    conn.execute("DELETE FROM checklist_template_items WHERE checklist_id = ?1", params![checklist_id])
+        .map_err(|e| format!("Failed to update checklist items: {}", e))?;
+    for (index, title) in items.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO checklist_template_items (id, checklist_id, title, order_index) VALUES (?1, ?2, ?3, ?4)",
+            params![uuid::Uuid::new_v4().to_string(), checklist_id, title, index as i64],
+        ).map_err(|e| format!("Failed to update checklist items: {}", e))?;
+    }
+    Ok(())
+}
+
+fn fetch_checklist(conn: &rusqlite::Connection, id: &str) -> Result<ChecklistTemplate, String> {
+    let mut checklist = conn.query_row(
+        "SELECT id, name, created_at, updated_at FROM checklist_templates WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(ChecklistTemplate {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                items: Vec::new(),
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        },
+    ).map_err(|e| format!("Checklist not found: {}", e))?;
+    
+    let mut stmt = conn.prepare("SELECT title FROM checklist_template_items WHERE checklist_id = ?1 ORDER BY order_index")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        checklist.items.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(checklist)
+}
+
+fn get_checklists_internal(conn: &rusqlite::Connection) -> Result<Vec<ChecklistTemplate>, String> {
+    let mut stmt = conn.prepare("SELECT id FROM checklist_templates ORDER BY name COLLATE NOCASE, created_at")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let ids = stmt.query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    
+    ids.iter().map(|id| fetch_checklist(conn, id)).collect()
+}
+
+fn create_checklist_internal(conn: &rusqlite::Connection, input: CreateChecklistInput) -> Result<ChecklistTemplate, String> {
+    let items = validate_checklist(&input.name, &input.items)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO checklist_templates (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, input.name.trim(), now, now],
+    ).map_err(|e| format!("Failed to create checklist: {}", e))?;
+    set_checklist_items(&tx, &id, &items)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    fetch_checklist(conn, &id)
+}
+
+fn update_checklist_internal(conn: &rusqlite::Connection, id: &str, input: UpdateChecklistInput) -> Result<ChecklistTemplate, String> {
+    let current = fetch_checklist(conn, id)?;
+    let name = input.name.unwrap_or(current.name);
+    let items = validate_checklist(&name, input.items.as_ref().unwrap_or(&current.items))?;
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "UPDATE checklist_templates SET name = ?1, updated_at = ?2 WHERE id = ?3",
+        params![name.trim(), now(), id],
+    ).map_err(|e| format!("Failed to update checklist: {}", e))?;
+    if input.items.is_some() {
+        set_checklist_items(&tx, id, &items)?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    fetch_checklist(conn, id)
+}
+
+fn delete_checklist_internal(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM checklist_template_items WHERE checklist_id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete checklist: {}", e))?;
+    tx.execute("DELETE FROM checklist_templates WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete checklist: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))
+}
+
+/// Append a checklist's items to a task as new subtasks, after the ones it already has
+fn apply_checklist_to_task_internal(conn: &rusqlite::Connection, task_id: &str, checklist_id: &str) -> Result<Vec<Subtask>, String> {
+    let checklist = fetch_checklist(conn, checklist_id)?;
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+        params![task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))?;
+    if !exists {
+        return Err("Task not found".to_string());
+    }
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let subtasks = append_subtasks(&tx, task_id, &checklist.items)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(subtasks)
+}
+
+/// Save a task's subtasks, in their current order, as a new checklist
+fn create_checklist_from_task_internal(conn: &rusqlite::Connection, task_id: &str, name: String) -> Result<ChecklistTemplate, String> {
+    let items: Vec<String> = fetch_subtasks(conn, task_id)?
+        .into_iter()
+        .map(|subtask| subtask.title)
+        .collect();
+    if items.is_empty() {
+        return Err("Task has no subtasks to save as a checklist".to_string());
+    }
+    create_checklist_internal(conn, CreateChecklistInput { name, items })
+}
+
+#[tauri::command]
+pub fn get_checklists(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<ChecklistTemplate>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_checklists_internal(&db.conn).map(|value| demo.apply(value))
+}
+
+#[tauri::command]
+pub fn create_checklist(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    input: CreateChecklistInput,
+) -> Result<ChecklistTemplate, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    create_checklist_internal(&db.conn, input)
+}
+
+#[tauri::command]
+pub fn update_checklist(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    input: UpdateChecklistInput,
+) -> Result<ChecklistTemplate, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    update_checklist_internal(&db.conn, &id, input)
+}
+
+#[tauri::command]
+pub fn delete_checklist(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    delete_checklist_internal(&db.conn, &id)
+}
+
+#[tauri::command]
+pub fn apply_checklist_to_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+    checklist_id: String,
+) -> Result<Vec<Subtask>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    apply_checklist_to_task_internal(&db.conn, &task_id, &checklist_id)
+}
+
+#[tauri::command]
+pub fn create_checklist_from_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+    name: String,
+) -> Result<ChecklistTemplate, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    create_checklist_from_task_internal(&db.conn, &task_id, name)
+}
+
+// Gamification data structures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserProgress {
+    pub id: String,
+    pub total_xp: i64,
+    pub current_level: i32,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_completion_date: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Badge {
+    pub id: String,
+    pub user_id: String,
+    pub badge_type: String,
+    pub earned_at: i64,
+    pub metadata: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XpHistoryEntry {
+    pub id: String,
+    pub user_id: String,
+    pub xp_amount: i32,
+    pub source: String,
+    pub task_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GrantXpResult {
+    pub level_up: bool,
+    pub new_level: i32,
+    pub total_xp: i64,
+    pub current_xp: i64,
+    pub xp_to_next_level: i64,
+}
+
+// Helper function to calculate level from total XP
+// Formula: level = floor(sqrt(totalXp / 100)) + 1
+fn calculate_level(total_xp: i64) -> i32 {
+    if total_xp <= 0 {
+        return 1;
+    }
+    ((total_xp as f64 / 100.0).sqrt().floor() as i32) + 1
+}
+
+// Helper function to calculate XP needed for next level
+// Formula: xpToNextLevel = (level * 100) * level
+fn calculate_xp_to_next_level(level: i32) -> i64 {
+    (level as i64 * 100) * level as i64
+}
+
+// Helper function to calculate current XP within current level
+fn calculate_current_xp(total_xp: i64, level: i32) -> i64 {
+    if level == 1 {
+        return total_xp;
+    }
+    // Calculate total XP needed to reach current level
+    let mut xp_for_current_level = 0i64;
+    for i in 1..level {
+        xp_for_current_level += calculate_xp_to_next_level(i);
+    }
+    total_xp - xp_for_current_level
+}
+
+// Internal helper functions for gamification (work directly with connection)
+fn grant_xp_internal(conn: &rusqlite::Connection, xp: i32, source: String, task_id: Option<String>) -> Result<GrantXpResult, String> {
+    let progress = get_user_progress_internal(conn)?;
+    
+    let previous_level = progress.current_level;
+    let new_total_xp = (progress.total_xp + xp as i64).max(0);
+    let new_level = calculate_level(new_total_xp);
+    let new_xp_to_next_level = calculate_xp_to_next_level(new_level);
+    let new_current_xp = calculate_current_xp(new_total_xp, new_level);
+    let leveled_up = new_level > previous_level;
+    
+    // Update user progress
+    let now = now();
+    conn.execute(
+        "UPDATE user_progress SET total_xp = ?1, current_level = ?2, updated_at = ?3 WHERE id = 'default'",
+        params![new_total_xp, new_level, now],
+    ).map_err(|e| format!("Failed to update user progress: {}", e))?;
+    
+    // Record in XP history
+    let history_id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO xp_history (id, user_id, xp_amount, source, task_id, created_at) VALUES (?1, 'default', ?2, ?3, ?4, ?5)",
+        params![history_id, xp, source, task_id, now],
+    ).map_err(|e| format!("Failed to record XP history: {}", e))?;
+    
+    Ok(GrantXpResult {
+        level_up: leveled_up,
+        new_level,
+        total_xp: new_total_xp,
+        current_xp: new_current_xp,
+        xp_to_next_level: new_xp_to_next_level,
+    })
+}
+
+// Revoke XP (subtract XP and remove history entry)
+fn revoke_xp_internal(conn: &rusqlite::Connection, xp: i32, history_id: String) -> Result<GrantXpResult, String> {
+    let progress = get_user_progress_internal(conn)?;
+    
+    let _previous_level = progress.current_level;
+    let new_total_xp = (progress.total_xp - xp as i64).max(0);
+    let new_level = calculate_level(new_total_xp);
+    let new_xp_to_next_level = calculate_xp_to_next_level(new_level);
+    let new_current_xp = calculate_current_xp(new_total_xp, new_level);
+    let leveled_up = false; // Can't level up when revoking XP
+    
+    // Update user progress
+    let now = now();
+    conn.execute(
+        "UPDATE user_progress SET total_xp = ?1, current_level = ?2, updated_at = ?3 WHERE id = 'default'",
+        params![new_total_xp, new_level, now],
+    ).map_err(|e| format!("Failed to update user progress: {}", e))?;
+    
+    // Remove the XP history entry
+    conn.execute(
+        "DELETE FROM xp_history WHERE id = ?1",
+        params![history_id],
+    ).map_err(|e| format!("Failed to remove XP history: {}", e))?;
+    
+    Ok(GrantXpResult {
+        level_up: leveled_up,
+        new_level,
+        total_xp: new_total_xp,
+        current_xp: new_current_xp,
+        xp_to_next_level: new_xp_to_next_level,
+    })
+}
+
+// Bring the XP recorded for a completed task in line with `priority`, adjusting the history
+// entry in place so uncompleting later revokes the right amount
+fn rescore_completion_xp(conn: &rusqlite::Connection, task_id: &str, priority: &str) -> Result<(), String> {
+    let xp_entry: Option<(i32, String)> = conn.query_row(
+        "SELECT xp_amount, id FROM xp_history WHERE task_id = ?1 AND source = 'task_completion' ORDER BY created_at DESC LIMIT 1",
+        params![task_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).ok();
+    // Nothing to adjust if the entry was pruned by retention
+    let Some((old_xp, history_id)) = xp_entry else {
+        return Ok(());
+    };
+    let new_xp = xp_for_priority(priority);
+    if new_xp == old_xp {
+        return Ok(());
+    }
+    
+    conn.execute(
+        "UPDATE xp_history SET xp_amount = ?1 WHERE id = ?2",
+        params![new_xp, history_id],
+    ).map_err(|e| format!("Failed to update XP history: {}", e))?;
+    
+    let progress = get_user_progress_internal(conn)?;
+    let new_total_xp = (progress.total_xp + (new_xp - old_xp) as i64).max(0);
+    conn.execute(
+        "UPDATE user_progress SET total_xp = ?1, current_level = ?2, updated_at = ?3 WHERE id = 'default'",
+        params![new_total_xp, calculate_level(new_total_xp), now()],
+    ).map_err(|e| format!("Failed to update user progress: {}", e))?;
+    Ok(())
+}
+
+pub(crate) fn update_streak_internal(conn: &rusqlite::Connection) -> Result<UserProgress, String> {
+    let mut progress = get_user_progress_internal(conn)?;
+    
+    // Get today's date at midnight (Unix timestamp)
+    let current_time = now();
+    let today_start = (current_time / 86400) * 86400; // Round down to start of day
+    let today_end = today_start + 86400 - 1; // End of day
+    
+    // Check if user completed at least one task today
+    let tasks_completed_today: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM tasks WHERE completed_at IS NOT NULL AND completed_at >= ?1 AND completed_at < ?2",
+        params![today_start, today_end],
+        |row| row.get(0),
+    ).unwrap_or(0);
+    
+    let has_completed_today = tasks_completed_today > 0;
+    
+    if has_completed_today {
+        // Vacation days are neutral: completing tasks on them doesn't extend the streak, but a
+        // streak that already broke before the vacation still resets
+        let step = if vacation_service::is_vacation_day(conn, today_start) { 0 } else { 1 };
+        if let Some(last_completion) = progress.last_completion_date {
+            let last_completion_day = (last_completion / 86400) * 86400;
+            let yesterday_start = today_start - 86400;
+            
+            if last_completion_day == yesterday_start {
+                // Last completion was yesterday - increment streak
+                progress.current_streak += step;
+            } else if last_completion_day < yesterday_start && vacation_service::gap_is_vacation(conn, last_completion_day, today_start) {
+                // Only vacation days since the last completion - continue the streak
+                progress.current_streak += step;
+            } else if last_completion_day < yesterday_start {
+                // Last completion was more than 1 day ago - reset streak to 1
+                progress.current_streak = step;
+            }
+            // If last_completion_day == today_start, no change (already counted today)
+        } else {
+            // No previous completion date - start streak at 1
+            progress.current_streak = step;
+        }
+        
+        // Update longest streak if current exceeds it
+        if progress.current_streak > progress.longest_streak {
+            progress.longest_streak = progress.current_streak;
+        }
+        
+        // Update last completion date to today
+        progress.last_completion_date = Some(today_start);
+    }
+    
+    // Update database
+    let update_time = now();
+    conn.execute(
+        "UPDATE user_progress SET current_streak = ?1, longest_streak = ?2, last_completion_date = ?3, updated_at = ?4 WHERE id = 'default'",
+        params![progress.current_streak, progress.longest_streak, progress.last_completion_date, update_time],
+    ).map_err(|e| format!("Failed to update streak: {}", e))?;
+    
+    progress.updated_at = update_time;
+    Ok(progress)
+}
+
+#[cfg(test)]
+thread_local! {
+    // Number of badge evaluations on this thread, used to verify bulk paths defer them
+    static BADGE_CHECK_RUNS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn check_and_award_badges_internal(conn: &rusqlite::Connection) -> Result<Vec<Badge>, String> {
+    #[cfg(test)]
+    BADGE_CHECK_RUNS.with(|runs| runs.set(runs.get() + 1));
+    
+    let progress = get_user_progress_internal(conn)?;
+    
+    // Get total tasks completed
+    let total_tasks_completed: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM tasks WHERE completed_at IS NOT NULL",
+        [],
+        |row| row.get(0),
+    ).unwrap_or(0);
+    
+    // Get already earned badges
+    let mut stmt = conn.prepare("SELECT id, user_id, badge_type, earned_at, metadata FROM badges WHERE user_id = 'default' ORDER BY earned_at DESC")
+        .map_err(|e| format!("Query error: {}", e))?;
+    
+    let rows = stmt.query_map([], |row| {
+        Ok(Badge {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            badge_type: row.get(2)?,
+            earned_at: row.get(3)?,
+            metadata: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut earned_badges = Vec::new();
+    for row in rows {
+        earned_badges.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    let earned_types: std::collections::HashSet<String> = earned_badges.iter()
+        .map(|b| b.badge_type.clone())
+        .collect();
+    
+    let mut newly_awarded = Vec::new();
+    let now = now();
+    
+    // Check each badge criteria
+    // first_task: total_tasks_completed >= 1
+    if total_tasks_completed >= 1 && !earned_types.contains("first_task") {
+        let badge_id = uuid::Uuid::new_v4().to_string();
+        let metadata = serde_json::json!({"milestone": 1}).to_string();
+        conn.execute(
+            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'first_task', ?2, ?3)",
+            params![badge_id.clone(), now, metadata],
+        ).map_err(|e| format!("Failed to award badge: {}", e))?;
+        
+        newly_awarded.push(Badge {
+            id: badge_id,
+            user_id: "default".to_string(),
+            badge_type: "first_task".to_string(),
+            earned_at: now,
+            metadata: Some(serde_json::json!({"milestone": 1}).to_string()),
+        });
+    }
+    
+    // task_master_100: total_tasks_completed >= 100
+    if total_tasks_completed >= 100 && !earned_types.contains("task_master_100") {
+        let badge_id = uuid::Uuid::new_v4().to_string();
+        let metadata = serde_json::json!({"milestone": 100}).to_string();
+        conn.execute(
+            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'task_master_100', ?2, ?3)",
+            params![badge_id.clone(), now, metadata],
+        ).map_err(|e| format!("Failed to award badge: {}", e))?;
+        
+        newly_awarded.push(Badge {
+            id: badge_id,
+            user_id: "default".to_string(),
+            badge_type: "task_master_100".to_string(),
+            earned_at: now,
+            metadata: Some(serde_json::json!({"milestone": 100}).to_string()),
+        });
+    }
+    
+    // week_warrior: current_streak == 7
+    if progress.current_streak == 7 && !earned_types.contains("week_warrior") {
+        let badge_id = uuid::Uuid::new_v4().to_string();
+        let metadata = serde_json::json!({"streak": 7}).to_string();
+        conn.execute(
+            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'week_warrior', ?2, ?3)",
+            params![badge_id.clone(), now, metadata],
+        ).map_err(|e| format!("Failed to award badge: {}", e))?;
+        
+        newly_awarded.push(Badge {
+            id: badge_id,
+            user_id: "default".to_string(),
+            badge_type: "week_warrior".to_string(),
+            earned_at: now,
+            metadata: Some(serde_json::json!({"streak": 7}).to_string()),
+        });
+    }
+    
+    // level_10: level >= 10 (bulk completions can jump past level 10 between checks)
+    if progress.current_level >= 10 && !earned_types.contains("level_10") {
+        let badge_id = uuid::Uuid::new_v4().to_string();
+        let metadata = serde_json::json!({"level": 10}).to_string();
+        conn.execute(
+            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, 'default', 'level_10', ?2, ?3)",
+            params![badge_id.clone(), now, metadata],
+        ).map_err(|e| format!("Failed to award badge: {}", e))?;
+        
+        newly_awarded.push(Badge {
+            id: badge_id,
+            user_id: "default".to_string(),
+            badge_type: "level_10".to_string(),
+            earned_at: now,
+            metadata: Some(serde_json::json!({"level": 10}).to_string()),
+        });
+    }
+    
+    Ok(newly_awarded)
+}
+
+/// Bump whenever a badge is added or its criterion changes, so that existing users are checked
+/// against their history on the next launch
+pub(crate) const BADGE_SCHEMA_VERSION: i64 = 1;
+const BADGE_SCHEMA_VERSION_KEY: &str = "badge_schema_version";
+
+/// A badge criterion that can be checked against history: `met_at` returns when the threshold
+/// was first reached, `now` if it was reached but the moment can't be recovered, or None
+struct BadgeCriterion {
+    badge_type: &'static str,
+    metadata_key: &'static str,
+    threshold: i64,
+    met_at: fn(&rusqlite::Connection, i64, i64) -> Result<Option<i64>, String>,
+}
+
+const BADGE_CRITERIA: &[BadgeCriterion] = &[
+    BadgeCriterion { badge_type: "first_task", metadata_key: "milestone", threshold: 1, met_at: completions_met_at },
+    BadgeCriterion { badge_type: "task_master_100", metadata_key: "milestone", threshold: 100, met_at: completions_met_at },
+    BadgeCriterion { badge_type: "week_warrior", metadata_key: "streak", threshold: 7, met_at: streak_met_at },
+    BadgeCriterion { badge_type: "level_10", metadata_key: "level", threshold: 10, met_at: level_met_at },
+];
+
+// Completion time of the threshold-th completed task
+fn completions_met_at(conn: &rusqlite::Connection, threshold: i64, _now: i64) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT completed_at FROM tasks WHERE completed_at IS NOT NULL ORDER BY completed_at LIMIT 1 OFFSET ?1",
+        params![threshold - 1],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Query error: {}", e))
+}
+
+// First completion on the day a run of `threshold` consecutive completion days was reached,
+// using the same UTC days as update_streak_internal
+fn streak_met_at(conn: &rusqlite::Connection, threshold: i64, now: i64) -> Result<Option<i64>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT completed_at / 86400 AS day, MIN(completed_at) FROM tasks
+         WHERE completed_at IS NOT NULL GROUP BY day ORDER BY day"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut run = 0;
+    let mut previous_day: Option<i64> = None;
+    for row in rows {
+        let (day, first_completion) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        run = if previous_day == Some(day - 1) { run + 1 } else { 1 };
+        if run >= threshold {
+            return Ok(Some(first_completion));
+        }
+        previous_day = Some(day);
+    }
+    
+    // Completed tasks may have been deleted since; the recorded longest streak still counts
+    let progress = get_user_progress_internal(conn)?;
+    Ok((progress.longest_streak as i64 >= threshold).then_some(now))
+}
+
+// Time of the XP entry that took the running total to the level's threshold
+fn level_met_at(conn: &rusqlite::Connection, level: i64, now: i64) -> Result<Option<i64>, String> {
+    let xp_needed = (level - 1) * (level - 1) * 100;
+    let reached: Option<i64> = conn.query_row(
+        "SELECT created_at FROM (
+             SELECT created_at, SUM(xp_amount) OVER (ORDER BY created_at, rowid) AS running_xp
+             FROM xp_history WHERE user_id = 'default'
+         ) WHERE running_xp >= ?1 ORDER BY created_at LIMIT 1",
+        params![xp_needed],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Query error: {}", e))?;
+    if reached.is_some() {
+        return Ok(reached);
+    }
+    
+    // xp_history may have been pruned by retention
+    let progress = get_user_progress_internal(conn)?;
+    Ok((progress.current_level as i64 >= level).then_some(now))
+}
+
+/// When the badge set is newer than the one this database was last evaluated against, award every
+/// badge the user already qualifies for from history, dated to when it was earned where possible
+pub(crate) fn reevaluate_badges_internal(conn: &rusqlite::Connection, now: i64) -> Result<Vec<Badge>, String> {
+    let stored_version: i64 = conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![BADGE_SCHEMA_VERSION_KEY],
+        |row| row.get::<_, String>(0),
+    ).ok().and_then(|value| value.parse().ok()).unwrap_or(0);
+    if stored_version >= BADGE_SCHEMA_VERSION {
+        return Ok(Vec::new());
+    }
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut granted = Vec::new();
+    for criterion in BADGE_CRITERIA {
+        let earned: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM badges WHERE user_id = 'default' AND badge_type = ?1)",
+            params![criterion.badge_type],
+            |row| row.get(0),
+        ).map_err(|e| format!("Query error: {}", e))?;
+        if earned {
+            continue;
+        }
+        let Some(earned_at) = (criterion.met_at)(&tx, criterion.threshold, now)? else {
+            continue;
+        };
+        
+        let badge = Badge {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "default".to_string(),
+            badge_type: criterion.badge_type.to_string(),
+            earned_at,
+            metadata: Some(serde_json::json!({ criterion.metadata_key: criterion.threshold, "retroactive": true }).to_string()),
+        };
+        tx.execute(
+            "INSERT INTO badges (id, user_id, badge_type, earned_at, metadata) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![badge.id, badge.user_id, badge.badge_type, badge.earned_at, badge.metadata],
+        ).map_err(|e| format!("Failed to award badge: {}", e))?;
+        granted.push(badge);
+    }
+    
+    tx.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![BADGE_SCHEMA_VERSION_KEY, BADGE_SCHEMA_VERSION.to_string()],
+    ).map_err(|e| format!("Failed to save badge schema version: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(granted)
+}
+
+// Helper function to get user progress from connection (internal use)
+fn get_user_progress_internal(conn: &rusqlite::Connection) -> Result<UserProgress, String> {
+    let result = conn.query_row(
+        "SELECT id, total_xp, current_level, current_streak, longest_streak, last_completion_date, created_at, updated_at FROM user_progress WHERE id = 'default'",
+        [],
+        |row| {
+            Ok(UserProgress {
+                id: row.get(0)?,
+                total_xp: row.get(1)?,
+                current_level: row.get(2)?,
+                current_streak: row.get(3)?,
+                longest_streak: row.get(4)?,
+                last_completion_date: row.get(5)?,
+                created_at: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        },
+    );
+    
+    match result {
+        Ok(progress) => Ok(progress),
+        Err(_) => {
+            // Create default user progress if it doesn't exist
+            let now = now();
+            conn.execute(
+                "INSERT INTO user_progress (id, total_xp, current_level, current_streak, longest_streak, created_at, updated_at) VALUES ('default', 0, 1, 0, 0, ?1, ?2)",
+                params![now, now],
+            ).map_err(|e| format!("Failed to create user progress: {}", e))?;
+            
+            Ok(UserProgress {
+                id: "default".to_string(),
+                total_xp: 0,
+                current_level: 1,
+                current_streak: 0,
+                longest_streak: 0,
+                last_completion_date: None,
+                created_at: now,
+                updated_at: now,
+            })
+        }
+    }
+}
+
+// XP granted for completing a task of the given priority
+fn xp_for_priority(priority: &str) -> i32 {
+    match priority {
+        "low" => 10,
+        "medium" => 25,
+        "high" => 50,
+        _ => 25,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkGamificationResult {
+    pub tasks_completed: usize,
+    pub xp_granted: i64,
+    pub level_up: bool,
+    pub new_level: i32,
+    pub current_streak: i32,
+    pub new_badges: Vec<Badge>,
+}
+
+/// Deferred gamification for bulk operations: XP is granted per task as it is completed,
+/// but the streak update and badge evaluation run once in `finish` instead of once per task.
+pub(crate) struct DeferredGamification {
+    tasks_completed: usize,
+    xp_granted: i64,
+    starting_level: Option<i32>,
+}
+
+impl DeferredGamification {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks_completed: 0,
+            xp_granted: 0,
+            starting_level: None,
+        }
+    }
+    
+    pub(crate) fn record_completion(&mut self, conn: &rusqlite::Connection, task_id: &str, priority: &str) -> Result<(), String> {
+        if self.starting_level.is_none() {
+            self.starting_level = Some(get_user_progress_internal(conn)?.current_level);
+        }
+        
+        let xp_amount = xp_for_priority(priority);
+        grant_xp_internal(conn, xp_amount, "task_completion".to_string(), Some(task_id.to_string()))?;
+        
+        self.tasks_completed += 1;
+        self.xp_granted += xp_amount as i64;
+        Ok(())
+    }
+    
+    pub(crate) fn finish(self, conn: &rusqlite::Connection) -> Result<BulkGamificationResult, String> {
+        if self.tasks_completed == 0 {
+            let progress = get_user_progress_internal(conn)?;
+            return Ok(BulkGamificationResult {
+                tasks_completed: 0,
+                xp_granted: 0,
+                level_up: false,
+                new_level: progress.current_level,
+                current_streak: progress.current_streak,
+                new_badges: Vec::new(),
+            });
+        }
+        
+        let progress = update_streak_internal(conn)?;
+        let new_badges = check_and_award_badges_internal(conn)?;
+        
+        Ok(BulkGamificationResult {
+            tasks_completed: self.tasks_completed,
+            xp_granted: self.xp_granted,
+            level_up: self.starting_level.is_some_and(|level| progress.current_level > level),
+            new_level: progress.current_level,
+            current_streak: progress.current_streak,
+            new_badges,
+        })
+    }
+}
+
+// Complete many tasks at once; streak and badge checks run once for the whole batch.
+// Tasks that require a completion note are left open, since there is no note to record, and
+// archived tasks are left alone.
+fn bulk_complete_tasks_internal(conn: &rusqlite::Connection, task_ids: &[String]) -> Result<BulkGamificationResult, String> {
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    
+    let mut gamification = DeferredGamification::new();
+    let now = now();
+    
+    for id in task_ids {
+        let task_info: Option<(Option<i64>, String, i32, String, bool)> = tx.query_row(
+            "SELECT completed_at, recurrence_type, recurrence_interval, priority, completion_note_required FROM tasks
+             WHERE id = ?1 AND archived = 0",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        ).ok();
+        
+        // Skip unknown, archived and already completed tasks
+        let (recurrence_type, recurrence_interval, priority) = match task_info {
+            Some((None, recurrence_type, recurrence_interval, priority, false)) => (recurrence_type, recurrence_interval, priority),
+            _ => continue,
+        };
+        
+        set_completed_at(&tx, id, Some(now), &recurrence_type, recurrence_interval, now)?;
+        gamification.record_completion(&tx, id, &priority)?;
+    }
+    
+    let result = gamification.finish(&tx)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn bulk_complete_tasks(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_ids: Vec<String>,
+) -> Result<BulkGamificationResult, String> {
+    use tauri::Manager;
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let result = bulk_complete_tasks_internal(&db.conn, &task_ids)?;
+    refresh_tray_tooltip(&app_handle, &db.conn);
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
+    
+    // One combined event instead of a toast per task
+    app_handle.emit_all("gamification-updated", &result).ok();
+    
+    Ok(result)
+}
+
+// Gamification commands
+#[tauri::command]
+pub fn get_user_progress(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<UserProgress, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_user_progress_internal(&db.conn)
+}
+
+#[tauri::command]
+pub fn grant_xp(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    xp: i32,
+    source: String,
+    task_id: Option<String>,
+) -> Result<GrantXpResult, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    grant_xp_internal(&db.conn, xp, source, task_id)
+}
+
+#[tauri::command]
+pub fn update_streak(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<UserProgress, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    update_streak_internal(&db.conn)
+}
+
+#[tauri::command]
+pub fn check_streak_on_startup(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<UserProgress, String> {
+    update_streak(db)
+}
+
+#[tauri::command]
+pub fn get_badges(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Badge>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_badges_internal(&db.conn)
+}
+
+fn get_badges_internal(conn: &rusqlite::Connection) -> Result<Vec<Badge>, String> {
+    let mut stmt = conn.prepare("SELECT id, user_id, badge_type, earned_at, metadata FROM badges WHERE user_id = 'default' ORDER BY earned_at DESC")
+        .map_err(|e| format!("Query error: {}", e))?;
+    
+    let rows = stmt.query_map([], |row| {
+        Ok(Badge {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            badge_type: row.get(2)?,
+            earned_at: row.get(3)?,
+            metadata: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut badges = Vec::new();
+    for row in rows {
+        badges.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(badges)
+}
+
+#[tauri::command]
+pub fn check_and_award_badges(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Vec<Badge>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    check_and_award_badges_internal(&db.conn)
+}
+
+// Session start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidebarCounts {
+    pub open: i64,
+    /// Open tasks without a project
+    pub inbox: i64,
+    pub today: i64,
+    pub overdue: i64,
+    /// Due in the 7 days after today
+    pub upcoming: i64,
+    pub completed_today: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingNotification {
+    pub id: String,
+    pub task_id: String,
+    pub title: String,
+    pub scheduled_at: i64,
+    pub snooze_until: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupStatus {
+    /// backup_frequency setting: manual, daily or weekly
+    pub frequency: String,
+    pub last_backup_at: Option<i64>,
+    /// A scheduled backup is older than its frequency allows
+    pub overdue: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwaySummary {
+    pub missed_reminders: usize,
+    pub backup: BackupStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    pub progress: UserProgress,
+    pub badges: Vec<Badge>,
+    pub settings: HashMap<String, String>,
+    pub sidebar_counts: SidebarCounts,
+    pub pending_notifications: Vec<PendingNotification>,
+    pub away: AwaySummary,
+}
+
+fn get_sidebar_counts_internal(conn: &rusqlite::Connection, today_start: i64) -> Result<SidebarCounts, String> {
+    let tomorrow_start = today_start + 86400;
+    let upcoming_end = tomorrow_start + 7 * 86400;
+    conn.query_row(
+        "SELECT
+            COUNT(*) FILTER (WHERE completed_at IS NULL),
+            COUNT(*) FILTER (WHERE completed_at IS NULL AND project_id IS NULL),
+            COUNT(*) FILTER (WHERE completed_at IS NULL AND due_at >= ?1 AND due_at < ?2),
+            COUNT(*) FILTER (WHERE completed_at IS NULL AND due_at < ?1),
+            COUNT(*) FILTER (WHERE completed_at IS NULL AND due_at >= ?2 AND due_at < ?3),
+            COUNT(*) FILTER (WHERE completed_at >= ?1 AND completed_at < ?2)
+         FROM tasks
+         WHERE archived = 0",
+        params![today_start, tomorrow_start, upcoming_end],
+        |row| {
+            Ok(SidebarCounts {
+                open: row.get(0)?,
+                inbox: row.get(1)?,
+                today: row.get(2)?,
+                overdue: row.get(3)?,
+                upcoming: row.get(4)?,
+                completed_today: row.get(5)?,
+            })
+        },
+    ).map_err(|e| format!("Failed to count tasks: {}", e))
+}
+
+fn get_pending_notifications_internal(conn: &rusqlite::Connection) -> Result<Vec<PendingNotification>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT ns.id, ns.task_id, t.title, ns.scheduled_at, ns.snooze_until
+         FROM notification_schedule ns
+         JOIN tasks t ON ns.task_id = t.id
+         WHERE ns.missed_at IS NULL AND t.completed_at IS NULL
+         ORDER BY COALESCE(MAX(ns.scheduled_at, ns.snooze_until), ns.scheduled_at) ASC"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(PendingNotification {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            title: row.get(2)?,
+            scheduled_at: row.get(3)?,
+            snooze_until: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut notifications = Vec::new();
+    for row in rows {
+        notifications.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(notifications)
+}
+
+// Newest todo_backup_*.db written by create_backup
+fn last_backup_at(backups_dir: &std::path::Path) -> Option<i64> {
+    std::fs::read_dir(backups_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("todo_backup_") && name.ends_with(".db")
+        })
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .filter_map(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|age| age.as_secs() as i64)
+        .max()
+}
+
+fn backup_status(frequency: Option<&str>, last_backup_at: Option<i64>, now: i64) -> BackupStatus {
+    let frequency = frequency.unwrap_or("manual").to_string();
+    let interval = match frequency.as_str() {
+        "daily" => Some(86400),
+        "weekly" => Some(7 * 86400),
+        _ => None,
+    };
+    let overdue = interval.is_some_and(|interval| last_backup_at.is_none_or(|last| now - last >= interval));
+    BackupStatus { frequency, last_backup_at, overdue }
+}
+
+// `update_streak` is off in demo mode, where nothing may be written
+fn session_start_internal(
+    conn: &rusqlite::Connection,
+    backups_dir: Option<&std::path::Path>,
+    today_start: i64,
+    update_streak: bool,
+) -> Result<SessionState, String> {
+    let progress = if update_streak { update_streak_internal(conn)? } else { get_user_progress_internal(conn)? };
+    let settings = get_settings_internal(conn)?;
+    let backup = backup_status(
+        settings.get("backup_frequency").map(String::as_str),
+        backups_dir.and_then(last_backup_at),
+        now(),
+    );
+    
+    Ok(SessionState {
+        progress,
+        badges: get_badges_internal(conn)?,
+        sidebar_counts: get_sidebar_counts_internal(conn, today_start)?,
+        pending_notifications: get_pending_notifications_internal(conn)?,
+        away: AwaySummary {
+            missed_reminders: get_missed_reminders_internal(conn)?.len(),
+            backup,
+        },
+        settings,
+    })
+}
+
+#[tauri::command]
+pub fn get_sidebar_counts(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<SidebarCounts, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_sidebar_counts_internal(&db.conn, week_service::local_midnight(chrono::Local::now().date_naive()))
+}
+
+/// Reminders scheduled but not yet shown, soonest first
+#[tauri::command]
+pub fn get_pending_notifications(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<PendingNotification>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_pending_notifications_internal(&db.conn)?))
+}
+
+/// Everything the UI needs at launch in one round trip; also runs the startup streak check
+/// and emits "while-you-were-away" when reminders were missed or a scheduled backup is overdue
+#[tauri::command]
+pub fn session_start(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    app_handle: tauri::AppHandle,
+) -> Result<SessionState, String> {
+    use tauri::Manager;
+    
+    let backups_dir = app_handle.path_resolver().app_data_dir().map(|dir| dir.join("backups"));
+    let today_start = week_service::local_midnight(chrono::Local::now().date_naive());
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let state = session_start_internal(&db.conn, backups_dir.as_deref(), today_start, !demo.is_enabled())?;
+    drop(db);
+    
+    if state.away.missed_reminders > 0 || state.away.backup.overdue {
+        app_handle.emit_all("while-you-were-away", &state.away).ok();
+    }
+    
+    Ok(demo.apply(state))
+}
+
+// Translation data structures
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranslatedContent {
+    pub title: String,
+    pub description: Option<String>,
+    pub source_lang: String,
+    pub target_lang: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranslationRequest {
+    pub task_id: String,
+    pub target_lang: String, // "en" or "tr"
+}
+
+// Translation commands
+#[tauri::command]
+pub async fn translate_task_content(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    breakers: State<'_, translation_service::TranslationBreakers>,
+    request: TranslationRequest,
+) -> Result<TranslatedContent, String> {
+    // Get task and API key while holding the lock
+    let (task, api_key) = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        translation_service::require_translation_consent(&db.conn)?;
+        let task = fetch_task(&db.conn, &request.task_id)?;
+        let api_key = translation_service::get_api_key(&db.conn)?;
+        (task, api_key)
+    };
+    
+    // Detect source language (use title for detection)
+    let source_lang = translation_service::detect_language(&task.title, api_key.as_deref()).await?;
+    
+    // Translate title
+    let translated_title = {
+        // Check cache and user translation first
+        let maybe_cached = {
+            let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+            translation_service::check_cache_and_user_translation(
+                &db.conn,
+                &task.title,
+                &request.target_lang,
+                "title",
+                Some(&request.task_id),
+            )?
+        };
+        
+        if let Some(cached) = maybe_cached {
+            cached
+        } else {
+            // Detect language first (no lock needed)
+            let detected_lang = translation_service::detect_language(&task.title, api_key.as_deref()).await?;
+            
+            // Check regular cache
+            let maybe_cached = {
+                let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+                let source_text_hash = translation_service::hash_text(&task.title);
+                translation_service::get_cached_translation(
+                    &db.conn,
+                    &source_text_hash,
+                    &detected_lang,
+                    &request.target_lang,
+                    "title",
+                )?
+            };
+            
+            if let Some(cached) = maybe_cached {
+                cached
+            } else {
+                // Do async translation
+                let translated = translation_service::translate_text(
+                    &task.title,
+                    &request.target_lang,
+                    api_key.as_deref(),
+                    &breakers,
+                )
+                .await?;
+                
+                // Save to cache
+                {
+                    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+                    translation_service::save_translation(
+                        &db.conn,
+                        &task.title,
+                        &detected_lang,
+                        &request.target_lang,
+                        &translated,
+                        "title",
+                        Some(&request.task_id),
+                        false,
+                    )?;
+                }
+                
+                translated
+            }
+        }
+    };
+    
+    // Translate description if present
+    let translated_description = if let Some(desc) = &task.description {
+        if !desc.trim().is_empty() {
+            // Check cache and user translation first
+            let maybe_cached = {
+                let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+                translation_service::check_cache_and_user_translation(
+                    &db.conn,
+                    desc,
+                    &request.target_lang,
+                    "description",
+                    Some(&request.task_id),
+                )?
+            };
+            
+            if let Some(cached) = maybe_cached {
+                Some(cached)
+            } else {
+                // Detect language first (no lock needed)
+                let detected_lang = translation_service::detect_language(desc, api_key.as_deref()).await?;
+                
+                // Check regular cache
+                let maybe_cached = {
+                    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+                    let source_text_hash = translation_service::hash_text(desc);
+                    translation_service::get_cached_translation(
+                        &db.conn,
+                        &source_text_hash,
+                        &detected_lang,
+                        &request.target_lang,
+                        "description",
+                    )?
+                };
+                
+                if let Some(cached) = maybe_cached {
+                    Some(cached)
+                } else {
+                    // Do async translation
+                    let translated = translation_service::translate_text(
+                        desc,
+                        &request.target_lang,
+                        api_key.as_deref(),
+                        &breakers,
+                    )
+                    .await?;
+                    
+                    // Save to cache
+                    {
+                        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+                        translation_service::save_translation(
+                            &db.conn,
+                            desc,
+                            &detected_lang,
+                            &request.target_lang,
+                            &translated,
+                            "description",
+                            Some(&request.task_id),
+                            false,
+                        )?;
+                    }
+                    
+                    Some(translated)
+                }
+            }
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    
+    Ok(TranslatedContent {
+        title: translated_title,
+        description: translated_description,
+        source_lang,
+        target_lang: request.target_lang,
+    })
+}
+
+/// Allow or withdraw sending task text to the translation services
+#[tauri::command]
+pub fn set_translation_consent(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    consent: bool,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    translation_service::set_translation_consent(&db.conn, consent)
+}
+
+/// Every external endpoint the app may call and whether it is currently allowed to
+#[tauri::command]
+pub fn get_external_services_status(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    breakers: State<'_, translation_service::TranslationBreakers>,
+) -> Result<Vec<translation_service::ExternalService>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    translation_service::get_external_services_status(&db.conn, &breakers)
+}
+
+/// Write every hand-corrected translation to `path` as JSON, to carry them to another machine
+#[tauri::command]
+pub fn export_translation_overrides(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    path: String,
+) -> Result<translation_service::TranslationOverridesExport, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    export_translation_overrides_internal(&db.conn, std::path::Path::new(&path))
+}
+
+fn export_translation_overrides_internal(
+    conn: &rusqlite::Connection,
+    path: &std::path::Path,
+) -> Result<translation_service::TranslationOverridesExport, String> {
+    let document = translation_service::get_translation_overrides(conn)?;
+    let json_str = serde_json::to_string_pretty(&document)
+        .map_err(|e| format!("Failed to serialize data: {}", e))?;
+    std::fs::write(path, json_str)
+        .map_err(|e| crate::storage::from_io("write the translation overrides file", &e).to_string())?;
+    
+    Ok(translation_service::TranslationOverridesExport {
+        path: path.to_string_lossy().to_string(),
+        exported: document.overrides.len(),
+    })
+}
+
+/// Read a file written by export_translation_overrides (or an export_data file that includes
+/// them) and upsert its translations as user edits, so they win over machine translations
+#[tauri::command]
+pub fn import_translation_overrides(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    path: String,
+) -> Result<translation_service::TranslationOverridesImport, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    import_translation_overrides_internal(&db.conn, std::path::Path::new(&path))
+}
+
+fn import_translation_overrides_internal(
+    conn: &rusqlite::Connection,
+    path: &std::path::Path,
+) -> Result<translation_service::TranslationOverridesImport, String> {
+    let file_contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read import file: {}", e))?;
+    let mut data: serde_json::Value = serde_json::from_str(&file_contents)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    if let Some(embedded) = data.get_mut("translation_overrides") {
+        data = embedded.take();
+    }
+    let document: translation_service::TranslationOverridesDocument = serde_json::from_value(data)
+        .map_err(|e| format!("Invalid translation overrides: {}", e))?;
+    
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let summary = translation_service::import_translation_overrides(&tx, &document.overrides)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn save_translation_override(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+    field: String, // "title" or "description"
+    target_lang: String,
+    translated_text: String,
+) -> Result<(), String> {
+    // Get original text and API key while holding the lock
+    let (source_text, api_key) = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        let task = fetch_task(&db.conn, &task_id)?;
+        let source_text = match field.as_str() {
+            "title" => task.title,
+            "description" => task.description.unwrap_or_default(),
+            _ => return Err("Invalid field type. Must be 'title' or 'description'".to_string()),
+        };
+        // Without consent the source language is guessed locally instead of asking Google
+        let api_key = if translation_service::has_translation_consent(&db.conn) {
+            translation_service::get_api_key(&db.conn)?
+        } else {
+            None
+        };
+        (source_text, api_key)
+    };
+    
+    // Detect source language (release lock before await)
+    let source_lang = translation_service::detect_language(&source_text, api_key.as_deref()).await
+        .map_err(|e| format!("Failed to detect language: {}", e))?;
+    
+    // Save user-edited translation (re-lock for database write)
+    {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        translation_service::save_translation(
+            &db.conn,
+            &source_text,
+            &source_lang,
+            &target_lang,
+            &translated_text,
+            &field,
+            Some(&task_id),
+            true, // is_user_edited
+        )?;
+    }
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_translation(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    breakers: State<'_, translation_service::TranslationBreakers>,
+    task_id: String,
+    field: String, // "title" or "description"
+    target_lang: String,
+) -> Result<Option<String>, String> {
+    // Check for user-edited translation and get source text while holding the lock
+    let (source_text, api_key) = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        
+        // First check for user-edited translation
+        if let Ok(Some(user_trans)) = translation_service::get_user_translation(&db.conn, &task_id, &field, &target_lang) {
+            return Ok(Some(user_trans));
+        }
+        
+        // Get task to get source text
+        let task = fetch_task(&db.conn, &task_id)?;
+        let source_text = match field.as_str() {
+            "title" => task.title,
+            "description" => task.description.unwrap_or_default(),
+            _ => return Err("Invalid field type. Must be 'title' or 'description'".to_string()),
+        };
+        
+        if source_text.trim().is_empty() {
+            return Ok(None);
+        }
+        
+        translation_service::require_translation_consent(&db.conn)?;
+        let api_key = translation_service::get_api_key(&db.conn)?;
+        (source_text, api_key)
+    };
+    
+    // Detect source language (release lock before await)
+    let source_lang = translation_service::detect_language(&source_text, api_key.as_deref()).await
+        .map_err(|e| format!("Failed to detect language: {}", e))?;
+    
+    // Check cache (re-lock for database read)
+    let source_text_hash = translation_service::hash_text(&source_text);
+    let cached = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        translation_service::get_cached_translation(
+            &db.conn,
+            &source_text_hash,
+            &source_lang,
+            &target_lang,
+            &field,
+        )?
+    };
+    
+    // If no cache, translate on the fly
+    if cached.is_none() {
+        let translated = translation_service::translate_text(
+            &source_text,
+            &target_lang,
+            api_key.as_deref(),
+            &breakers,
+        ).await?;
+        
+        // Save to cache
+        {
+            let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+            translation_service::save_translation(
+                &db.conn,
+                &source_text,
+                &source_lang,
+                &target_lang,
+                &translated,
+                &field,
+                Some(&task_id),
+                false,
+            )?;
+        }
+        
+        Ok(Some(translated))
+    } else {
+        Ok(cached)
+    }
+}
+
+// Tag commands
+#[tauri::command]
+pub fn get_all_tags(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<Tag>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let mut stmt = db.conn.prepare(
+        "SELECT id, name, color, created_at, usage_count, group_id FROM tags ORDER BY usage_count DESC, name"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    
+    let rows = stmt.query_map([], |row| {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            created_at: row.get(3)?,
+            usage_count: row.get(4)?,
+            group_id: row.get(5)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(demo.apply(tags))
+}
+
+#[tauri::command]
+pub fn get_task_tags(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+) -> Result<Vec<Tag>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    fetch_task_tags(&db.conn, &task_id).map(|value| demo.apply(value))
+}
+
+#[tauri::command]
+pub fn create_tag(db: State<'_, Arc<Mutex<DbConnection>>>, input: CreateTagInput) -> Result<Tag, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Normalize tag name to lowercase and trim
+    let normalized_name = input.name.trim().to_lowercase();
+    
+    if normalized_name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+    
+    // Check if tag already exists
+    let existing: Option<Tag> = db.conn.query_row(
+        "SELECT id, name, color, created_at, usage_count, group_id FROM tags WHERE name = ?1",
+        params![normalized_name],
+        |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                created_at: row.get(3)?,
+                usage_count: row.get(4)?,
+                group_id: row.get(5)?,
+            })
+        },
+    ).ok();
+    
+    if let Some(tag) = existing {
+        return Ok(tag);
+    }
+    
+    // Create new tag
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    
+    db.conn.execute(
+        "INSERT INTO tags (id, name, color, created_at, usage_count) VALUES (?1, ?2, ?3, ?4, 0)",
+        params![id.clone(), normalized_name, input.color, now],
+    ).map_err(|e| format!("Failed to create tag: {}", e))?;
+    
+    Ok(Tag {
+        id,
+        name: normalized_name,
+        color: input.color,
+        created_at: now,
+        usage_count: 0,
+        group_id: None,
+    })
+}
+
+#[tauri::command]
+pub fn delete_tag(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    tag_id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let name: Option<String> = db.conn.query_row("SELECT name FROM tags WHERE id = ?1", params![tag_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Query error: {}", e))?;
+    let mut entry = UndoEntry::new(format!("Deleted tag \"{}\"", name.unwrap_or_default()));
+    entry.capture(&db.conn, "tags", "id = ?1", &tag_id)?;
+    entry.capture(&db.conn, "task_tags", "tag_id = ?1", &tag_id)?;
+    entry.capture(&db.conn, "tag_term_cooccurrence", "tag_id = ?1", &tag_id)?;
+    
+    // CASCADE will handle task_tags deletion
+    let deleted = db.conn.execute("DELETE FROM tags WHERE id = ?1", params![tag_id])
+        .map_err(|e| format!("Failed to delete tag: {}", e))?;
+    if deleted > 0 {
+        undo.record(entry);
+    }
+    
+    Ok(())
+}
+
+fn fetch_tag(conn: &rusqlite::Connection, tag_id: &str) -> Result<Tag, String> {
+    conn.query_row(
+        "SELECT id, name, color, created_at, usage_count, group_id FROM tags WHERE id = ?1",
+        params![tag_id],
+        |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                created_at: row.get(3)?,
+                usage_count: row.get(4)?,
+                group_id: row.get(5)?,
+            })
+        },
+    ).map_err(|e| format!("Tag not found: {}", e))
+}
+
+/// Rename, recolour or (re)group a tag
+#[tauri::command]
+pub fn update_tag(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    tag_id: String,
+    input: UpdateTagInput,
+) -> Result<Tag, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    update_tag_internal(&db.conn, &tag_id, input)
+}
+
+// Helper function to update a tag (assumes lock is already held)
+fn update_tag_internal(conn: &rusqlite::Connection, tag_id: &str, input: UpdateTagInput) -> Result<Tag, String> {
+    let mut updates = Vec::new();
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    if let Some(name) = input.name {
+        // Same normalisation as create_tag
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return Err("Tag name cannot be empty".to_string());
+        }
+        updates.push("name = ?");
+        query_params.push(Box::new(name));
+    }
+    if let Some(color) = input.color {
+        updates.push("color = ?");
+        query_params.push(Box::new(color));
+    }
+    if let Some(group_id) = input.group_id {
+        if group_id.is_empty() {
+            updates.push("group_id = NULL");
+        } else {
+            fetch_tag_group(conn, &group_id)?;
+            updates.push("group_id = ?");
+            query_params.push(Box::new(group_id));
+        }
+    }
+    
+    if !updates.is_empty() {
+        query_params.push(Box::new(tag_id.to_string()));
+        let query = format!("UPDATE tags SET {} WHERE id = ?", updates.join(", "));
+        conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
+            .map_err(|e| format!("Failed to update tag: {}", e))?;
+    }
+    
+    fetch_tag(conn, tag_id)
+}
+
+// Tag group commands
+fn fetch_tag_group(conn: &rusqlite::Connection, id: &str) -> Result<TagGroup, String> {
+    conn.query_row(
+        "SELECT id, name, color, order_index, created_at FROM tag_groups WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(TagGroup {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                order_index: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        },
+    ).map_err(|e| format!("Tag group not found: {}", e))
+}
+
+fn get_tag_groups_internal(conn: &rusqlite::Connection) -> Result<Vec<TagGroup>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, color, order_index, created_at FROM tag_groups ORDER BY order_index, name"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    
+    let rows = stmt.query_map([], |row| {
+        Ok(TagGroup {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            order_index: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut groups = Vec::new();
+    for row in rows {
+        groups.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(groups)
+}
+
+#[tauri::command]
+pub fn get_tag_groups(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<TagGroup>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    get_tag_groups_internal(&db.conn).map(|value| demo.apply(value))
+}
+
+// Helper function to create a tag group at the end of the list (assumes lock is already held)
+fn create_tag_group_internal(conn: &rusqlite::Connection, input: CreateTagGroupInput) -> Result<TagGroup, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Tag group name cannot be empty".to_string());
+    }
+    
+    let order_index: i32 = conn.query_row(
+        "SELECT COALESCE(MAX(order_index) + 1, 0) FROM tag_groups",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))?;
+    
+    let group = TagGroup {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        color: input.color,
+        order_index,
+        created_at: now(),
+    };
+    conn.execute(
+        "INSERT INTO tag_groups (id, name, color, order_index, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![group.id, group.name, group.color, group.order_index, group.created_at],
+    ).map_err(|e| format!("Failed to create tag group: {}", e))?;
+    
+    Ok(group)
+}
+
+#[tauri::command]
+pub fn create_tag_group(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    input: CreateTagGroupInput,
+) -> Result<TagGroup, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    create_tag_group_internal(&db.conn, input)
+}
+
+#[tauri::command]
+pub fn update_tag_group(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    input: UpdateTagGroupInput,
+) -> Result<TagGroup, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let mut updates = Vec::new();
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    if let Some(name) = input.name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err("Tag group name cannot be empty".to_string());
+        }
+        updates.push("name = ?");
+        query_params.push(Box::new(name));
+    }
+    if let Some(color) = input.color {
+        updates.push("color = ?");
+        query_params.push(Box::new(color));
+    }
+    if let Some(order_index) = input.order_index {
+        updates.push("order_index = ?");
+        query_params.push(Box::new(order_index));
+    }
+    
+    if !updates.is_empty() {
+        query_params.push(Box::new(id.clone()));
+        let query = format!("UPDATE tag_groups SET {} WHERE id = ?", updates.join(", "));
+        db.conn.execute(&query, rusqlite::params_from_iter(query_params.iter()))
+            .map_err(|e| format!("Failed to update tag group: {}", e))?;
+    }
+    
+    fetch_tag_group(&db.conn, &id)
+}
+
+// Helper function to delete a tag group, keeping its tags (assumes lock is already held)
+fn delete_tag_group_internal(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    // Explicit rather than relying on ON DELETE SET NULL, which needs foreign keys enabled
+    tx.execute("UPDATE tags SET group_id = NULL WHERE group_id = ?1", params![id])
+        .map_err(|e| format!("Failed to ungroup tags: {}", e))?;
+    tx.execute("DELETE FROM tag_groups WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete tag group: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
+
+/// Delete a tag group; its tags become ungrouped
+#[tauri::command]
+pub fn delete_tag_group(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    delete_tag_group_internal(&db.conn, &id)
+}
+
+// Helper function to list tags by group, in group order (assumes lock is already held)
+fn get_tags_grouped_internal(conn: &rusqlite::Connection) -> Result<GroupedTags, String> {
+    let mut groups: Vec<TagGroupWithTags> = get_tag_groups_internal(conn)?
+        .into_iter()
+        .map(|group| TagGroupWithTags { group, tags: Vec::new() })
+        .collect();
+    let mut ungrouped = Vec::new();
+    
+    let mut stmt = conn.prepare(
+        "SELECT id, name, color, created_at, usage_count, group_id FROM tags ORDER BY usage_count DESC, name"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            created_at: row.get(3)?,
+            usage_count: row.get(4)?,
+            group_id: row.get(5)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    for row in rows {
+        let tag = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        let group = tag.group_id.as_ref().and_then(|group_id| groups.iter_mut().find(|g| &g.group.id == group_id));
+        match group {
+            Some(group) => group.tags.push(tag),
+            // Includes tags pointing at a group that no longer exists
+            None => ungrouped.push(tag),
+        }
+    }
+    
+    Ok(GroupedTags { groups, ungrouped })
+}
+
+/// All tags organised by group; get_all_tags keeps returning the flat list
+#[tauri::command]
+pub fn get_tags_grouped(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<GroupedTags, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_tags_grouped_internal(&db.conn)?))
+}
+
+#[tauri::command]
+pub fn add_tag_to_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+    tag_id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    
+    // Add tag to task (ignore if already exists due to UNIQUE constraint)
+    match db.conn.execute(
+        "INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, task_id, tag_id.clone(), now],
+    ) {
+        Ok(_) => {
+            // Increment usage count
+            db.conn.execute(
+                "UPDATE tags SET usage_count = usage_count + 1 WHERE id = ?1",
+                params![tag_id],
+            ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+            let tag_name: Option<String> = db.conn.query_row("SELECT name FROM tags WHERE id = ?1", params![tag_id], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("Query error: {}", e))?;
+            log_activity(&db.conn, &task_id, "tag_added", None, Some(tag_name.as_deref().unwrap_or(&tag_id)), "user")
+        }
+        Err(e) => {
+            if e.to_string().contains("UNIQUE constraint failed") {
+                Ok(()) // Tag already added, this is fine
+            } else {
+                Err(format!("Failed to add tag to task: {}", e))
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remove_tag_from_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    undo: State<'_, UndoJournal>,
+    task_id: String,
+    tag_id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let link: Option<(String, String, String)> = db.conn.query_row(
+        "SELECT tt.id, tags.name, tasks.title FROM task_tags tt
+         JOIN tags ON tags.id = tt.tag_id JOIN tasks ON tasks.id = tt.task_id
+         WHERE tt.task_id = ?1 AND tt.tag_id = ?2",
+        params![task_id, tag_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).optional().map_err(|e| format!("Query error: {}", e))?;
+    let entry = match &link {
+        Some((link_id, tag_name, task_title)) => {
+            let mut entry = UndoEntry::new(format!("Removed tag \"{}\" from \"{}\"", tag_name, task_title));
+            entry.capture(&db.conn, "task_tags", "id = ?1", link_id)?;
+            Some(entry)
+        }
+        None => None,
+    };
+    
+    // Remove tag from task
+    let rows_affected = db.conn.execute(
+        "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
+        params![task_id, tag_id.clone()],
+    ).map_err(|e| format!("Failed to remove tag from task: {}", e))?;
+    
+    // Decrement usage count if a row was deleted
+    if rows_affected > 0 {
+        db.conn.execute(
+            "UPDATE tags SET usage_count = MAX(0, usage_count - 1) WHERE id = ?1",
+            params![tag_id],
+        ).map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+        let tag_name = link.as_ref().map_or(tag_id.as_str(), |(_, tag_name, _)| tag_name.as_str());
+        log_activity(&db.conn, &task_id, "tag_removed", Some(tag_name), None, "user")?;
+        if let Some(entry) = entry {
+            undo.record(entry);
+        }
+    }
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_suggested_tags(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    search: String,
+) -> Result<Vec<Tag>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let search_pattern = format!("%{}%", search.trim().to_lowercase());
+    
+    let mut stmt = db.conn.prepare(
+        "SELECT id, name, color, created_at, usage_count, group_id FROM tags 
+         WHERE name LIKE ?1 
+         ORDER BY usage_count DESC, name 
+         LIMIT 10"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    
+    let rows = stmt.query_map(params![search_pattern], |row| {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            created_at: row.get(3)?,
+            usage_count: row.get(4)?,
+            group_id: row.get(5)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(demo.apply(tags))
+}
+
+fn get_tasks_by_tag_internal(conn: &rusqlite::Connection, tag_id: &str) -> Result<Vec<Task>, String> {
+    let mut tasks = query_tasks(
+        conn,
+        "id IN (SELECT task_id FROM task_tags WHERE tag_id = ?1)",
+        "order_index, created_at",
+        params![tag_id],
+    )?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
+}
+
+#[tauri::command]
+pub fn get_tasks_by_tag(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    tag_id: String,
+) -> Result<Vec<Task>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_tasks_by_tag_internal(&db.conn, &tag_id)?))
+}
+
+#[tauri::command]
+pub fn recalculate_tag_usage_counts(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Update all tags with correct usage counts based on actual task_tags records
+    db.conn.execute(
+        "UPDATE tags SET usage_count = (
+            SELECT COUNT(*) FROM task_tags WHERE task_tags.tag_id = tags.id
+        )",
+        [],
+    ).map_err(|e| format!("Failed to recalculate tag usage counts: {}", e))?;
+    
+    Ok(())
+}
+
+// Tasks with any of the tags
+fn get_tasks_by_tags_internal(conn: &rusqlite::Connection, tag_ids: &[String]) -> Result<Vec<Task>, String> {
+    if tag_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    // Build query with placeholders for each tag_id
+    let placeholders = tag_ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+    let params: Vec<&dyn rusqlite::ToSql> = tag_ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+    let mut tasks = query_tasks(
+        conn,
+        &format!("id IN (SELECT task_id FROM task_tags WHERE tag_id IN ({}))", placeholders),
+        "order_index, created_at",
+        &params,
+    )?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
+}
+
+#[tauri::command]
+pub fn get_tasks_by_tags(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    tag_ids: Vec<String>,
+) -> Result<Vec<Task>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_tasks_by_tags_internal(&db.conn, &tag_ids)?))
+}
+
+// Task relationship commands
+#[tauri::command]
+pub fn create_task_relationship(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    input: CreateRelationshipInput,
+) -> Result<TaskRelationship, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Prevent self-relationships
+    if input.task_id_1 == input.task_id_2 {
+        return Err("Cannot create relationship between a task and itself".to_string());
+    }
+    
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    let relationship_type = input.relationship_type.unwrap_or_else(|| "related".to_string());
+    
+    // Check for circular dependencies if relationship type is 'blocks'
+    if relationship_type == "blocks" {
+        if check_circular_dependency_internal(&db.conn, &input.task_id_1, &input.task_id_2)? {
+            return Err("Cannot create blocking relationship: would create circular dependency".to_string());
+        }
+    }
+    
+    db.conn.execute(
+        "INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at) 
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id.clone(), input.task_id_1.clone(), input.task_id_2.clone(), relationship_type.clone(), now],
+    ).map_err(|e| {
+        if e.to_string().contains("UNIQUE constraint failed") {
+            "Relationship already exists between these tasks".to_string()
+        } else {
+            format!("Failed to create task relationship: {}", e)
+        }
+    })?;
+    
+    Ok(TaskRelationship {
+        id,
+        task_id_1: input.task_id_1,
+        task_id_2: input.task_id_2,
+        relationship_type,
+        created_at: now,
+    })
+}
+
+#[tauri::command]
+pub fn delete_task_relationship(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    relationship_id: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    db.conn.execute(
+        "DELETE FROM task_relationships WHERE id = ?1",
+        params![relationship_id],
+    ).map_err(|e| format!("Failed to delete task relationship: {}", e))?;
+    
+    Ok(())
+}
+
+// Tasks related to a task in either direction
+fn get_related_tasks_internal(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Task>, String> {
+    let mut tasks = query_tasks(
+        conn,
+        "id IN (
+            SELECT task_id_2 FROM task_relationships WHERE task_id_1 = ?1
+            UNION
+            SELECT task_id_1 FROM task_relationships WHERE task_id_2 = ?1
+         )",
+        "order_index, created_at",
+        params![task_id],
+    )?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
+}
+
+#[tauri::command]
+pub fn get_related_tasks(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+) -> Result<Vec<Task>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_related_tasks_internal(&db.conn, &task_id)?))
+}
+
+// Helper function to check circular dependencies
+fn check_circular_dependency_internal(
+    conn: &rusqlite::Connection,
+    blocking_task_id: &str,
+    blocked_task_id: &str,
+) -> Result<bool, String> {
+    // Check if adding this relationship would create a cycle
+    // Use recursive CTE to traverse the dependency graph starting from blocked_task_id
+    // If we can reach blocking_task_id, then creating this relationship would create a cycle
+    let query = "
+        WITH RECURSIVE dependency_chain(task_id, depth) AS (
+            SELECT ?1 AS task_id, 0 AS depth
+            UNION ALL
+            SELECT tr.task_id_1, dc.depth + 1
+            FROM task_relationships tr
+            INNER JOIN dependency_chain dc ON tr.task_id_2 = dc.task_id
+            WHERE tr.relationship_type = 'blocks' AND dc.depth < 100
+        )
+        SELECT COUNT(*) FROM dependency_chain WHERE task_id = ?2
+    ";
+    
+    let count: i64 = conn.query_row(
+        query,
+        params![blocked_task_id, blocking_task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check circular dependency: {}", e))?;
+    
+    Ok(count > 0)
+}
+
+#[tauri::command]
+pub fn check_circular_dependency(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    blocking_task_id: String,
+    blocked_task_id: String,
+) -> Result<bool, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    check_circular_dependency_internal(&db.conn, &blocking_task_id, &blocked_task_id)
+}
+
+#[tauri::command]
+pub fn get_blocking_tasks(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+) -> Result<Vec<Task>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    fetch_blocking_tasks(&db.conn, &task_id).map(|value| demo.apply(value))
+}
+
+// Helper function to get the tasks that block a task (task_id_1 blocks task_id_2)
+fn fetch_blocking_tasks(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Task>, String> {
+    let mut tasks = query_tasks(
+        conn,
+        "id IN (SELECT task_id_1 FROM task_relationships WHERE task_id_2 = ?1 AND relationship_type = 'blocks')",
+        "order_index, created_at",
+        params![task_id],
+    )?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
+}
+
+// Tasks blocked by a task
+fn get_blocked_tasks_internal(conn: &rusqlite::Connection, task_id: &str) -> Result<Vec<Task>, String> {
+    let mut tasks = query_tasks(
+        conn,
+        "id IN (SELECT task_id_2 FROM task_relationships WHERE task_id_1 = ?1 AND relationship_type = 'blocks')",
+        "order_index, created_at",
+        params![task_id],
+    )?;
+    attach_tags(conn, &mut tasks)?;
+    Ok(tasks)
+}
+
+#[tauri::command]
+pub fn get_blocked_tasks(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    task_id: String,
+) -> Result<Vec<Task>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_blocked_tasks_internal(&db.conn, &task_id)?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DependencyConflict {
+    pub relationship_id: String,
+    pub blocker_id: String,
+    pub blocker_title: String,
+    pub blocker_due_at: Option<i64>,
+    pub blocked_id: String,
+    pub blocked_title: String,
+    pub blocked_due_at: i64,
+}
+
+// Default gap left between a blocker's new due date and the task it blocks
+const DEFAULT_DEPENDENCY_BUFFER_HOURS: i64 = 24;
+
+// Helper function to find open 'blocks' relationships where the blocker is due after the blocked task, or has no due date while the blocked task does
+fn get_dependency_conflicts_internal(conn: &rusqlite::Connection) -> Result<Vec<DependencyConflict>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT tr.id, blocker.id, blocker.title, blocker.due_at, blocked.id, blocked.title, blocked.due_at
+         FROM task_relationships tr
+         INNER JOIN tasks blocker ON blocker.id = tr.task_id_1
+         INNER JOIN tasks blocked ON blocked.id = tr.task_id_2
+         WHERE tr.relationship_type = 'blocks'
+         AND blocker.completed_at IS NULL AND blocked.completed_at IS NULL
+         AND blocked.due_at IS NOT NULL
+         AND (blocker.due_at IS NULL OR blocker.due_at > blocked.due_at)
+         ORDER BY blocked.due_at ASC"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    
+    let rows = stmt.query_map([], |row| {
+        Ok(DependencyConflict {
+            relationship_id: row.get(0)?,
+            blocker_id: row.get(1)?,
+            blocker_title: row.get(2)?,
+            blocker_due_at: row.get(3)?,
+            blocked_id: row.get(4)?,
+            blocked_title: row.get(5)?,
+            blocked_due_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut conflicts = Vec::new();
+    for row in rows {
+        conflicts.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(conflicts)
+}
+
+// Helper function to resolve one dependency conflict.
+// Strategies: "move_blocker_earlier" (blocked due date minus `dependency_buffer_hours`) or "clear_blocked_due_date".
+fn fix_dependency_conflict_internal(conn: &rusqlite::Connection, relationship_id: &str, strategy: &str) -> Result<Task, String> {
+    let conflict = get_dependency_conflicts_internal(conn)?
+        .into_iter()
+        .find(|c| c.relationship_id == relationship_id)
+        .ok_or_else(|| "No dependency conflict found for this relationship".to_string())?;
+    
+    let (task_id, old_due, new_due) = match strategy {
+        "move_blocker_earlier" => {
+            let buffer_hours = conn.query_row(
+                "SELECT value FROM settings WHERE key = 'dependency_buffer_hours'",
+                [],
+                |row| row.get::<_, String>(0),
+            ).ok().and_then(|v| v.parse::<i64>().ok()).unwrap_or(DEFAULT_DEPENDENCY_BUFFER_HOURS).max(0);
+            (conflict.blocker_id, conflict.blocker_due_at, Some(conflict.blocked_due_at - buffer_hours * 3600))
+        }
+        "clear_blocked_due_date" => (conflict.blocked_id, Some(conflict.blocked_due_at), None),
+        _ => return Err(format!("Unknown dependency fix strategy: {}", strategy)),
+    };
+    
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "UPDATE tasks SET due_at = ?1, updated_at = ?2 WHERE id = ?3",
+        params![new_due, now(), task_id],
+    ).map_err(|e| format!("Failed to update due date: {}", e))?;
+    record_due_date_change(&tx, &task_id, old_due, new_due, "dependency_fix")?;
+    log_activity(
+        &tx,
+        &task_id,
+        "due_date_changed",
+        old_due.map(|d| d.to_string()).as_deref(),
+        new_due.map(|d| d.to_string()).as_deref(),
+        "dependency_fix",
+    )?;
+    
+    let reminder_minutes: Option<i32> = tx.query_row(
+        "SELECT reminder_minutes_before FROM tasks WHERE id = ?1",
+        params![task_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Task not found: {}", e))?;
+    if reminder_minutes.is_some() {
+        crate::notifications::reschedule_reminder(&tx, &task_id, reminder_minutes, now())
+            .map_err(|e| format!("Failed to reschedule reminder: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    fetch_task(conn, &task_id)
+}
+
+#[tauri::command]
+pub fn get_dependency_conflicts(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<DependencyConflict>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(get_dependency_conflicts_internal(&db.conn)?))
+}
+
+#[tauri::command]
+pub fn fix_dependency_conflict(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    relationship_id: String,
+    strategy: String,
+) -> Result<Task, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    fix_dependency_conflict_internal(&db.conn, &relationship_id, &strategy)
+}
+
+#[tauri::command]
+pub fn backfill_attachment_metadata(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::attachments::AttachmentBackfillResult, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    
+    crate::attachments::backfill_attachment_metadata(&db.conn, &app_data_dir)
+}
+
+// Health check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub checked_at: i64,
+    pub dependency_conflicts: Vec<DependencyConflict>,
+    // Attachment rows whose file was found missing (see backfill_attachment_metadata)
+    pub missing_attachments: Vec<Attachment>,
+    /// Result of a create/delete probe in the app data folder
+    pub storage: Option<crate::storage::StorageStatus>,
+    /// Fixed by normalize_order_indexes
+    pub duplicate_order_indexes: Vec<DuplicateOrderIndex>,
+}
+
+// Helper function to list attachments flagged as missing from disk
+fn get_missing_attachments_internal(conn: &rusqlite::Connection) -> Result<Vec<Attachment>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, task_id, filename, path, mime, size, created_at FROM attachments WHERE missing_since IS NOT NULL ORDER BY created_at"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Attachment {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            filename: row.get(2)?,
+            path: row.get(3)?,
+            mime: row.get(4)?,
+            size: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    
+    let mut attachments = Vec::new();
+    for row in rows {
+        attachments.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    
+    Ok(attachments)
+}
+
+#[tauri::command]
+pub fn get_db_health(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<HealthReport, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    health_report_internal(&app_handle, &db.conn)
+}
+
+fn health_report_internal(app_handle: &tauri::AppHandle, conn: &rusqlite::Connection) -> Result<HealthReport, String> {
+    Ok(HealthReport {
+        checked_at: now(),
+        dependency_conflicts: get_dependency_conflicts_internal(conn)?,
+        missing_attachments: get_missing_attachments_internal(conn)?,
+        storage: app_handle.path_resolver().app_data_dir().map(|dir| crate::storage::storage_status(&dir)),
+        duplicate_order_indexes: get_duplicate_order_indexes_internal(conn)?,
+    })
+}
+
+/// Write a zip with the schema, row counts, migrations, db info, health check and recent logs
+/// to `path` for attaching to bug reports. With include_database, an anonymized copy of the
+/// database is added. Async so zipping runs off the main thread.
+#[tauri::command]
+pub async fn generate_diagnostic_bundle(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    path: String,
+    include_database: Option<bool>,
+) -> Result<crate::diagnostics::DiagnosticBundle, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let to_json = |value: Result<serde_json::Value, serde_json::Error>| value.map_err(|e| format!("Serialization error: {}", e));
+    let reports = [
+        ("db_info", to_json(serde_json::to_value(db_info_internal(&db)))?),
+        ("health", to_json(serde_json::to_value(health_report_internal(&app_handle, &db.conn)?))?),
+    ];
+    let log_dir = app_handle.path_resolver().app_log_dir();
+    
+    crate::diagnostics::write_bundle(
+        &db.conn,
+        std::path::Path::new(&path),
+        &reports,
+        log_dir.as_deref(),
+        include_database.unwrap_or(false),
+    )
+}
+
+/// What's using disk space in the app data folder. Async so the walk runs off the main thread;
+/// the DB lock is only taken to look up task titles once the walk is done.
+#[tauri::command]
+pub async fn get_storage_breakdown(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<crate::storage::StorageBreakdown, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    let log_dir = app_handle.path_resolver().app_log_dir();
+    
+    let mut lookup_error = None;
+    let breakdown = crate::storage::storage_breakdown(&app_data_dir, log_dir.as_deref(), |task_ids| {
+        let lookup = || -> Result<HashMap<String, String>, String> {
+            let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+            let mut stmt = db.conn.prepare("SELECT title FROM tasks WHERE id = ?1")
+                .map_err(|e| format!("Query error: {}", e))?;
+            let mut titles = HashMap::new();
+            for task_id in task_ids {
+                if let Ok(title) = stmt.query_row(params![task_id], |row| row.get::<_, String>(0)) {
+                    titles.insert(task_id.clone(), title);
+                }
+            }
+            Ok(titles)
+        };
+        lookup().unwrap_or_else(|e| {
+            lookup_error = Some(e);
+            HashMap::new()
+        })
+    });
+    
+    // Without titles every attachment folder would look orphaned
+    match lookup_error {
+        Some(e) => Err(e),
+        None => Ok(breakdown),
+    }
+}
+
+// Weekly review commands
+
+/// Bounds of the week containing `timestamp` (default now), honouring the `week_starts_on` setting
+#[tauri::command]
+pub fn get_week_bounds(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    timestamp: Option<i64>,
+) -> Result<week_service::WeekBounds, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let week_starts_on = week_service::load_week_starts_on(&db.conn);
+    Ok(week_service::week_bounds(timestamp.unwrap_or_else(now), week_starts_on))
+}
+
+#[tauri::command]
+pub fn get_weekly_review(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    week_start: i64,
+) -> Result<weekly_review_service::WeeklyReview, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let review = weekly_review_service::get_weekly_review(&db.conn, week_start)
+        .map_err(|e| format!("Failed to get weekly review: {}", e))?;
+    Ok(demo.apply(review))
+}
+
+/// Move unfinished tasks due in the week containing `from_week_start` into the next week.
+/// Strategies: "next_monday", "same_weekday" or "spread"; `dry_run` only returns the plan.
+#[tauri::command]
+pub fn rollover_week(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    from_week_start: i64,
+    strategy: String,
+    dry_run: Option<bool>,
+) -> Result<rollover_service::RolloverResult, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    if dry_run.unwrap_or(false) {
+        return rollover_service::plan_rollover(&db.conn, from_week_start, &strategy);
+    }
+    
+    let result = rollover_service::apply_rollover(&db.conn, from_week_start, &strategy, now())?;
+    crate::tray::refresh_tray_menu(&app_handle, &db.conn);
+    Ok(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeeklySummaryHtml {
+    pub html: String,
+    pub file_path: Option<String>,
+}
+
+#[tauri::command]
+pub fn export_weekly_summary_html(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    app_handle: tauri::AppHandle,
+    week_start: i64,
+    save_to_file: Option<bool>,
+) -> Result<WeeklySummaryHtml, String> {
+    use std::fs;
+    
+    let review = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        weekly_review_service::get_weekly_review(&db.conn, week_start)
+            .map_err(|e| format!("Failed to get weekly review: {}", e))?
+    };
+    
+    let html = weekly_review_service::render_weekly_summary_html(&review);
+    
+    let file_path = if save_to_file.unwrap_or(false) {
+        let exports_dir = app_handle
+            .path_resolver()
+            .app_data_dir()
+            .ok_or_else(|| "Failed to get app data directory".to_string())?
+            .join("exports");
+        fs::create_dir_all(&exports_dir).map_err(|e| crate::storage::from_io("create the exports folder", &e))?;
+        
+        use chrono::TimeZone;
+        let week_label = chrono::Local.timestamp_opt(review.week_start, 0)
+            .single()
+            .map(|dt| dt.format("%Y%m%d").to_string())
+            .unwrap_or_else(|| review.week_start.to_string());
+        let export_path = exports_dir.join(format!("weekly_summary_{}.html", week_label));
+        
+        fs::write(&export_path, &html).map_err(|e| crate::storage::from_io("write the weekly summary", &e))?;
+        Some(export_path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    
+    Ok(WeeklySummaryHtml { html, file_path })
+}
+
+// Pomodoro session commands
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatePomodoroSessionInput {
+    pub task_id: Option<String>,
+    pub started_at: i64,
+    pub completed_at: i64,
+    pub duration_seconds: i32,
+    pub mode: String,
+    pub was_completed: bool,
+    pub task_completed: bool,
+}
+
+#[tauri::command]
+pub fn create_pomodoro_session(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    input: CreatePomodoroSessionInput,
+) -> Result<pomodoro_service::PomodoroSession, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Focus sessions recorded without a task belong to the task being worked on
+    let task_id = match input.task_id {
+        Some(task_id) => Some(task_id),
+        None if input.mode == "pomodoro" => get_current_task_internal(&db.conn, false)?.map(|task| task.id),
+        None => None,
+    };
+    
+    pomodoro_service::create_pomodoro_session(
+        &db.conn,
+        task_id,
+        input.started_at,
+        input.completed_at,
+        input.duration_seconds,
+        input.mode,
+        input.was_completed,
+        input.task_completed,
+    )
+    .map_err(|e| format!("Failed to create pomodoro session: {}", e))
+}
+
+#[tauri::command]
+pub fn get_pomodoro_stats(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<pomodoro_service::PomodoroStats, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    pomodoro_service::get_pomodoro_stats(&db.conn, start_date, end_date)
+        .map_err(|e| format!("Failed to get pomodoro stats: {}", e))
+}
+
+#[tauri::command]
+pub fn get_daily_pomodoro_stats(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<pomodoro_service::DailyPomodoroStats>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    pomodoro_service::get_daily_stats(&db.conn, start_date, end_date)
+        .map_err(|e| format!("Failed to get daily pomodoro stats: {}", e))
+}
+
+#[tauri::command]
+pub fn get_best_focus_times(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<Vec<pomodoro_service::BestFocusTime>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    pomodoro_service::get_best_focus_times(&db.conn)
+        .map_err(|e| format!("Failed to get best focus times: {}", e))
+}
+
+#[tauri::command]
+pub fn get_task_completion_rates(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<pomodoro_service::TaskCompletionRate>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    let rates = pomodoro_service::get_task_completion_rates(&db.conn)
+        .map_err(|e| format!("Failed to get task completion rates: {}", e))?;
+    Ok(demo.apply(rates))
+}
+
+#[tauri::command]
+pub fn get_task_pomodoro_summary(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    task_id: String,
+) -> Result<pomodoro_service::TaskPomodoroSummary, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    pomodoro_service::get_task_pomodoro_summary(&db.conn, &task_id)
+        .map_err(|e| format!("Failed to get task pomodoro summary: {}", e))
+}
+
+#[tauri::command]
+pub fn get_pomodoro_streak(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<pomodoro_service::PomodoroStreak, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    pomodoro_service::get_pomodoro_streak(&db.conn)
+        .map_err(|e| format!("Failed to get pomodoro streak: {}", e))
+}
+
+/// Ranked fuzzy matches across open tasks, projects, tags and templates for the Ctrl+K palette
+#[tauri::command]
+pub fn palette_search(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<palette_service::PaletteEntry>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    palette_service::palette_search(&db.conn, &query, limit.unwrap_or(20)).map(|value| demo.apply(value))
+}
+
+/// Turn demo/screenshot mode on or off. Emits `demo-mode-changed` so every window refetches.
+#[tauri::command]
+pub fn set_demo_mode(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+    enabled: bool,
+) -> Result<bool, String> {
+    use tauri::Manager;
+
+    demo.set_enabled(enabled);
+    app_handle.emit_all("demo-mode-changed", enabled).ok();
+    // The tray shows task titles too
+    if let Ok(db) = db.lock() {
+        crate::tray::refresh_tray_menu(&app_handle, &db.conn);
+        refresh_tray_tooltip(&app_handle, &db.conn);
+    }
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_demo_mode(demo: State<'_, crate::demo_mode::DemoMode>) -> Result<bool, String> {
+    Ok(demo.is_enabled())
+}
+
+// Task links
+
+/// Canonical `todoapp://task/<id>` link for the "copy link" button
+#[tauri::command]
+pub fn get_task_link(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<String, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    fetch_task(&db.conn, &id)?;
+    Ok(crate::deep_link::task_link(&id))
+}
+
+/// Open a `todoapp://task/<id>` link: focuses the main window and emits `navigate-to-task`
+#[tauri::command]
+pub fn open_task_link(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    url: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::deep_link::handle_url(&app_handle, &db, &url)
+}
+
+/// Called once by the frontend on startup to act on the link the app was launched with, if any.
+/// Returns the linked task id instead of emitting `navigate-to-task`.
+#[tauri::command]
+pub fn take_launch_link(
+    app_handle: tauri::AppHandle,
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    launch_url: State<'_, crate::deep_link::LaunchUrl>,
+) -> Result<Option<String>, String> {
+    let url = launch_url.0.lock().map_err(|e| format!("Launch link lock error: {}", e))?.take();
+    let url = match url {
+        Some(url) => url,
+        None => return Ok(None),
+    };
+    
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::deep_link::focus_linked_task(&app_handle, &db, &url).map(Some)
+}
+
+// Vacation mode commands
+#[tauri::command]
+pub fn set_vacation_mode(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    start: i64,
+    end: i64,
+) -> Result<vacation_service::VacationPeriod, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    vacation_service::add_vacation_period(&db.conn, start, end, now())
+}
+
+#[tauri::command]
+pub fn get_vacation_periods(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<Vec<vacation_service::VacationPeriod>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    vacation_service::get_vacation_periods(&db.conn)
+}
+
+#[tauri::command]
+pub fn get_vacation_status(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<vacation_service::VacationStatus, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    vacation_service::get_vacation_status(&db.conn, now())
+}
+
+/// Rank existing projects for a task by keyword overlap with their past task titles
+#[tauri::command]
+pub fn suggest_project_for_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    title: String,
+    description: Option<String>,
+) -> Result<Vec<project_suggestion_service::ProjectSuggestion>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // The job runner refreshes the cache daily; build it right away on first use
+    let cached: i64 = db.conn.query_row("SELECT COUNT(*) FROM project_keywords", [], |row| row.get(0))
+        .map_err(|e| format!("Query error: {}", e))?;
+    if cached == 0 {
+        project_suggestion_service::rebuild_project_keywords(&db.conn, now())?;
+    }
+    
+    project_suggestion_service::suggest_project_for_task(&db.conn, &title, description.as_deref())
+}
+
+// Automatic rules
+#[tauri::command]
+pub fn get_rules(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<rules_service::Rule>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    rules_service::get_rules(&db.conn, false).map(|value| demo.apply(value))
+}
+
+#[tauri::command]
+pub fn create_rule(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    input: rules_service::RuleInput,
+) -> Result<rules_service::Rule, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    rules_service::create_rule(&db.conn, input)
+}
+
+#[tauri::command]
+pub fn update_rule(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    input: rules_service::RuleInput,
+) -> Result<rules_service::Rule, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    rules_service::update_rule(&db.conn, &id, input)
+}
+
+#[tauri::command]
+pub fn delete_rule(db: State<'_, Arc<Mutex<DbConnection>>>, id: String) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    rules_service::delete_rule(&db.conn, &id)
+}
+
+/// Preview a (possibly unsaved) rule against an existing task without changing it
+#[tauri::command]
+pub fn test_rule(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    rule: rules_service::RuleInput,
+    sample_task_id: String,
+) -> Result<rules_service::RuleTestResult, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    rules_service::test_rule(&db.conn, &rule, &sample_task_id)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleBackfillFilter {
+    /// Only run this rule instead of every enabled one
+    pub rule_id: Option<String>,
+    pub project_id: Option<String>,
+    pub include_completed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleBackfillResult {
+    pub tasks_checked: usize,
+    pub tasks_changed: usize,
+    pub applications: usize,
+}
+
+fn run_rules_on_existing_internal(db: &DbConnection, filter: RuleBackfillFilter) -> Result<RuleBackfillResult, String> {
+    let rules = match &filter.rule_id {
+        // A rule picked explicitly runs even while it's disabled
+        Some(rule_id) => vec![rules_service::Rule { enabled: true, ..rules_service::get_rule(&db.conn, rule_id)? }],
+        None => rules_service::get_rules(&db.conn, true)?,
+    };
+    
+    let mut query = "SELECT id FROM tasks WHERE archived = 0".to_string();
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if !filter.include_completed {
+        query.push_str(" AND completed_at IS NULL");
+    }
+    if let Some(project_id) = filter.project_id {
+        query.push_str(" AND project_id = ?");
+        query_params.push(Box::new(project_id));
+    }
+    
+    let task_ids: Vec<String> = {
+        let mut stmt = db.conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(query_params.iter()), |row| row.get(0))
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+        }
+        ids
+    };
+    
+    let mut result = RuleBackfillResult { tasks_checked: task_ids.len(), tasks_changed: 0, applications: 0 };
+    let mut reschedule = Vec::new();
+    
+    let tx = db.conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for task_id in &task_ids {
+        let outcome = rules_service::apply_rules(&tx, task_id, &rules, "rule_backfill")?;
+        if !outcome.applications.is_empty() {
+            result.tasks_changed += 1;
+            result.applications += outcome.applications.len();
+        }
+        if outcome.reminder_changed {
+            reschedule.push(task_id.clone());
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    
+    for task_id in &reschedule {
+        let _ = db.conn.execute("DELETE FROM notification_schedule WHERE task_id = ?1 AND kind = 'reminder'", params![task_id]);
+        let reminder = fetch_task(&db.conn, task_id)?.reminder_minutes_before;
+        let _ = crate::notifications::schedule_notification(db, task_id, reminder);
+    }
+    
+    Ok(result)
+}
+
+/// Apply rules to tasks that already exist (open tasks only unless `include_completed`)
+#[tauri::command]
+pub fn run_rules_on_existing(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    filter: Option<RuleBackfillFilter>,
+) -> Result<RuleBackfillResult, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    run_rules_on_existing_internal(&db, filter.unwrap_or_default())
+}
+
+/// Rank tags for a task by name mentions and by the tags usually applied to tasks with similar wording
+#[tauri::command]
+pub fn suggest_tags_for_task(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    title: String,
+    description: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<tag_suggestion_service::TagSuggestion>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    // Same lazy first build as the project keyword cache
+    let cached: i64 = db.conn.query_row("SELECT COUNT(*) FROM tag_term_cooccurrence", [], |row| row.get(0))
+        .map_err(|e| format!("Query error: {}", e))?;
+    if cached == 0 {
+        tag_suggestion_service::rebuild_tag_cooccurrence(&db.conn, now())?;
+    }
+    
+    tag_suggestion_service::suggest_tags_for_task(&db.conn, &title, description.as_deref(), limit)
+}
+
+/// What the priority aging job would escalate right now (uses 14 days if aging is not enabled yet)
+#[tauri::command]
+pub fn preview_priority_aging(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<priority_aging_service::PriorityAgingPreview, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    
+    priority_aging_service::preview_priority_aging(&db.conn, now()).map(|value| demo.apply(value))
+}
+
+/// Sync with the shared folder right away; None when no sync_folder is set
+#[tauri::command]
+pub fn sync_now(db: State<'_, Arc<Mutex<DbConnection>>>) -> Result<Option<crate::sync::SyncResult>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::sync::run_sync(&db.conn, now())
+}
+
+#[tauri::command]
+pub fn get_sync_conflicts(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    demo: State<'_, crate::demo_mode::DemoMode>,
+) -> Result<Vec<crate::sync::SyncConflict>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(demo.apply(crate::sync::get_sync_conflicts(&db.conn)?))
+}
+
+/// Keep this device's version ("local") or the other device's ("remote")
+#[tauri::command]
+pub fn resolve_sync_conflict(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+    id: String,
+    keep: String,
+) -> Result<(), String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::sync::resolve_sync_conflict(&db.conn, &id, &keep, now())
+}
+
+/// Refresh the ICS feeds in calendar_feed_urls now. Async so the downloads don't hold the
+/// database lock; a feed that can't be fetched is reported and its tasks are left alone.
+#[tauri::command]
+pub async fn sync_calendar_feeds(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<calendar_sync::CalendarSyncReport, String> {
+    let urls = {
+        let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+        calendar_sync::feed_urls(&db.conn)
+    };
+    let fetched = calendar_sync::fetch_feeds(&urls).await;
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    Ok(calendar_sync::apply_fetched(&db.conn, fetched, now()))
+}
+
+/// Row counts, retention settings and the last prune for each append-only table
+#[tauri::command]
+pub fn get_retention_status(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<Vec<retention_service::RetentionStatus>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    retention_service::get_retention_status(&db.conn)
+}
+
+// Background job status for the settings page
+#[tauri::command]
+pub fn get_background_job_status(
+    db: State<'_, Arc<Mutex<DbConnection>>>,
+) -> Result<Vec<crate::jobs::BackgroundJobStatus>, String> {
+    let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+    crate::jobs::get_background_job_status(&db)
+}
+
+// Screenshot command
+#[tauri::command]
+pub async fn capture_screenshot(mode: String) -> Result<Vec<u8>, String> {
+    use screenshots::Screen;
+    use image::ImageEncoder;
+    
+    match mode.as_str() {
+        "fullscreen" => {
+            let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
+            let primary_screen = screens.first().ok_or("No screen found")?;
+            
+            let screenshot = primary_screen
+                .capture()
+                .map_err(|e| format!("Failed to capture screenshot: {}", e))?;
+            
+            // Convert to PNG bytes
+            let mut buffer = Vec::new();
+            let width = screenshot.width();
+            let height = screenshot.height();
+            let rgba = screenshot.rgba();
+            
+            let encoder = image::codecs::png::PngEncoder::new(&mut buffer);
+            encoder
+                .write_image(
+                    rgba,
+                    width,
+                    height,
+                    image::ColorType::Rgba8,
+                )
+                .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+            
+            Ok(buffer)
+        }
+        "window" => {
+            // For now, window mode also captures fullscreen
+            // In the future, this could be enhanced to capture specific windows
+            let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
+            let primary_screen = screens.first().ok_or("No screen found")?;
+            
+            let screenshot = primary_screen
+                .capture()
+                .map_err(|e| format!("Failed to capture screenshot: {}", e))?;
+            
+            // Convert to PNG bytes
+            let mut buffer = Vec::new();
+            let width = screenshot.width();
+            let height = screenshot.height();
+            let rgba = screenshot.rgba();
+            
+            let encoder = image::codecs::png::PngEncoder::new(&mut buffer);
+            encoder
+                .write_image(
+                    rgba,
+                    width,
+                    height,
+                    image::ColorType::Rgba8,
+                )
+                .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+            
+            Ok(buffer)
+        }
+        _ => Err(format!("Unsupported screenshot mode: {}", mode)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT,
+                due_at INTEGER,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                priority TEXT NOT NULL DEFAULT 'medium',
+                completed_at INTEGER,
+                project_id TEXT,
+                order_index INTEGER DEFAULT 0,
+                metadata TEXT,
+                recurrence_type TEXT DEFAULT 'none',
+                recurrence_interval INTEGER DEFAULT 1,
+                recurrence_parent_id TEXT,
+                reminder_minutes_before INTEGER DEFAULT NULL,
+                notification_repeat INTEGER DEFAULT 0
+            );
+            CREATE TABLE user_progress (
+                id TEXT PRIMARY KEY DEFAULT 'default',
+                total_xp INTEGER NOT NULL DEFAULT 0,
+                current_level INTEGER NOT NULL DEFAULT 1,
+                current_streak INTEGER NOT NULL DEFAULT 0,
+                longest_streak INTEGER NOT NULL DEFAULT 0,
+                last_completion_date INTEGER,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE badges (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL DEFAULT 'default',
+                badge_type TEXT NOT NULL,
+                earned_at INTEGER NOT NULL,
+                metadata TEXT
+            );
+            CREATE TABLE xp_history (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL DEFAULT 'default',
+                xp_amount INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                task_id TEXT,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE projects (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                color TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE subtasks (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                completed INTEGER NOT NULL DEFAULT 0,
+                order_index INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE attachments (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                path TEXT NOT NULL,
+                mime TEXT,
+                size INTEGER,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            CREATE TABLE pomodoro_sessions (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL DEFAULT 'default',
+                task_id TEXT,
+                started_at INTEGER NOT NULL,
+                completed_at INTEGER NOT NULL,
+                duration_seconds INTEGER NOT NULL,
+                mode TEXT NOT NULL DEFAULT 'pomodoro',
+                was_completed INTEGER NOT NULL DEFAULT 1,
+                task_completed INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE pomodoro_streaks (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL DEFAULT 'default',
+                current_streak INTEGER NOT NULL DEFAULT 0,
+                longest_streak INTEGER NOT NULL DEFAULT 0,
+                last_session_date INTEGER,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE task_relationships (
+                id TEXT PRIMARY KEY,
+                task_id_1 TEXT NOT NULL,
+                task_id_2 TEXT NOT NULL,
+                relationship_type TEXT DEFAULT 'related',
+                created_at INTEGER NOT NULL
+            );"
+        ).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE notification_schedule (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                scheduled_at INTEGER NOT NULL,
+                snooze_until INTEGER,
+                created_at INTEGER NOT NULL
+            );"
+        ).unwrap();
+        conn.execute_batch(include_str!("../migrations/0010_add_tags_and_relationships.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0013_add_attachment_versioning.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0016_add_due_date_history.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0018_add_activity_log.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0019_add_attachment_missing_flag.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0020_add_vacation_periods.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0022_add_project_keywords.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0023_add_tag_groups.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0005_add_templates.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0025_add_template_scope.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0026_add_reminder_style.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0027_add_rules.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0028_add_missed_reminders.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0029_add_project_view_state.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0031_add_folder_sync.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0032_add_checklist_templates.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0033_add_completion_notes.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0034_add_recurrence_root.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0035_add_task_archive.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0036_add_tasks_fts.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0037_add_waiting_status.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0038_add_subtask_attachment_fts.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0039_add_task_pinning.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0040_add_wip_limits.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0041_add_recurrence_rules.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0042_add_time_estimates.sql")).unwrap();
+        conn
+    }
+    
+    fn insert_task(conn: &rusqlite::Connection, title: &str, priority: &str) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = now();
+        conn.execute(
+            "INSERT INTO tasks (id, title, created_at, updated_at, priority) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id.clone(), title, now, now, priority],
+        ).unwrap();
+        id
+    }
+    
+    #[test]
+    fn test_bulk_complete_defers_badge_checks() {
+        let conn = setup_test_conn();
+        let task_ids: Vec<String> = (0..150)
+            .map(|i| insert_task(&conn, &format!("Task {}", i), "medium"))
+            .collect();
+        
+        BADGE_CHECK_RUNS.with(|runs| runs.set(0));
+        let result = bulk_complete_tasks_internal(&conn, &task_ids).unwrap();
+        
+        assert_eq!(BADGE_CHECK_RUNS.with(|runs| runs.get()), 1);
+        assert_eq!(result.tasks_completed, 150);
+        assert_eq!(result.xp_granted, 150 * 25);
+        
+        let badge_types: Vec<&str> = result.new_badges.iter().map(|b| b.badge_type.as_str()).collect();
+        assert!(badge_types.contains(&"first_task"));
+        assert!(badge_types.contains(&"task_master_100"));
+        
+        let completed: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM tasks WHERE completed_at IS NOT NULL",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(completed, 150);
+        
+        let xp_entries: i64 = conn.query_row("SELECT COUNT(*) FROM xp_history", [], |row| row.get(0)).unwrap();
+        assert_eq!(xp_entries, 150);
+    }
+    
+    #[test]
+    fn test_reevaluate_badges_uses_history_once() {
+        let conn = setup_test_conn();
+        let day = 86400;
+        for i in 0..7 {
+            let task = insert_task(&conn, &format!("Day {}", i), "medium");
+            conn.execute(
+                "UPDATE tasks SET completed_at = ?1 WHERE id = ?2",
+                params![(100 + i) * day + 3600, task],
+            ).unwrap();
+        }
+        conn.execute_batch(&format!(
+            "INSERT INTO xp_history (id, xp_amount, source, created_at) VALUES
+                ('x1', 5000, 'task_completion', {}), ('x2', 4000, 'task_completion', {});",
+            101 * day, 103 * day
+        )).unwrap();
+        conn.execute(
+            "INSERT INTO badges (id, user_id, badge_type, earned_at) VALUES ('b1', 'default', 'first_task', 5)",
+            [],
+        ).unwrap();
+        
+        let granted = reevaluate_badges_internal(&conn, 999 * day).unwrap();
+        let earned: HashMap<&str, i64> = granted.iter().map(|b| (b.badge_type.as_str(), b.earned_at)).collect();
+        assert_eq!(earned.len(), 2);
+        assert_eq!(earned["week_warrior"], 106 * day + 3600);
+        assert_eq!(earned["level_10"], 103 * day);
+        assert!(!earned.contains_key("task_master_100"));
+        
+        // Already evaluated against this badge set
+        conn.execute("UPDATE user_progress SET longest_streak = 0", []).unwrap();
+        assert!(reevaluate_badges_internal(&conn, 999 * day).unwrap().is_empty());
+        let version: String = conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![BADGE_SCHEMA_VERSION_KEY],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(version, BADGE_SCHEMA_VERSION.to_string());
+    }
+    
+    #[test]
+    fn test_bulk_complete_skips_completed_tasks() {
+        let conn = setup_test_conn();
+        let open_task = insert_task(&conn, "Open", "high");
+        let done_task = insert_task(&conn, "Done", "high");
+        let archived_task = insert_task(&conn, "Archived", "high");
+        conn.execute("UPDATE tasks SET completed_at = 1 WHERE id = ?1", params![done_task]).unwrap();
+        conn.execute("UPDATE tasks SET archived = 1 WHERE id = ?1", params![archived_task]).unwrap();
+        // A note left over from an earlier completion, and the task being worked on
+        conn.execute("UPDATE tasks SET completion_note = 'Old note' WHERE id = ?1", params![open_task]).unwrap();
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, ?2)", params![CURRENT_TASK_SETTING, open_task]).unwrap();
+        
+        let ids = [open_task.clone(), done_task, archived_task.clone(), "missing".to_string()];
+        let result = bulk_complete_tasks_internal(&conn, &ids).unwrap();
+        assert_eq!(result.tasks_completed, 1);
+        assert_eq!(result.xp_granted, 50);
+        
+        // Same bookkeeping as completing the task on its own
+        let completed = fetch_task(&conn, &open_task).unwrap();
+        assert!(completed.completed);
+        let note: Option<String> = conn.query_row("SELECT completion_note FROM tasks WHERE id = ?1", params![open_task], |row| row.get(0)).unwrap();
+        assert_eq!(note, None);
+        let logged: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_log WHERE task_id = ?1 AND action = 'completed'",
+            params![open_task],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(logged, 1);
+        assert_eq!(get_current_task_id(&conn), None);
+        assert!(!fetch_task(&conn, &archived_task).unwrap().completed);
+    }
+    
+    #[test]
+    fn test_collapse_recurring_chains() {
+        let conn = setup_test_conn();
+        let root = insert_task(&conn, "Take medication", "medium");
+        conn.execute("UPDATE tasks SET recurrence_type = 'daily', recurrence_interval = 1 WHERE id = ?1", params![root]).unwrap();
+        let one_off = insert_task(&conn, "Renew prescription", "high");
+        
+        // Complete the chain twice, leaving a third instance open
+        toggle_complete_internal(&conn, &root).unwrap();
+        let second: String = conn.query_row("SELECT id FROM tasks WHERE recurrence_parent_id = ?1", params![root], |row| row.get(0)).unwrap();
+        toggle_complete_internal(&conn, &second).unwrap();
+        let third: String = conn.query_row("SELECT id FROM tasks WHERE recurrence_parent_id = ?1", params![second], |row| row.get(0)).unwrap();
+        let third_root: String = conn.query_row("SELECT recurrence_root_id FROM tasks WHERE id = ?1", params![third], |row| row.get(0)).unwrap();
+        assert_eq!(third_root, root);
+        
+        let filter = |completed: Option<bool>| TaskFilter {
+            project_id: None,
+            completed,
+            due_before: None,
+            due_after: None,
+            search: None,
+            tag_id: None,
+            with_block_status: Some(false),
+            collapse_recurring: Some(true),
+            fts_search: None,
+            include_waiting: None,
+            limit: None,
+            offset: None,
+            sort_by: None,
+            sort_dir: None,
+            overdue_only: None,
+            has_description: None,
+            has_attachments: None,
+            has_subtasks: None,
+            description_preview_only: None,
+        };
+        let all = get_tasks_internal(&conn, Some(&filter(None)), false).unwrap();
+        let mut counts: Vec<(String, Option<i64>)> = all.iter().map(|t| (t.id.clone(), t.instance_count)).collect();
+        let mut expected = vec![(third.clone(), Some(3)), (one_off, Some(1))];
+        counts.sort();
+        expected.sort();
+        assert_eq!(counts, expected);
+        
+        // Filters apply before collapsing: the newest completed instance stands for the chain
+        let completed = get_tasks_internal(&conn, Some(&filter(Some(true))), false).unwrap();
+        assert_eq!(completed.len(), 1);
+        assert_eq!((completed[0].id.as_str(), completed[0].instance_count), (second.as_str(), Some(2)));
+        
+        let page: Vec<String> = get_recurrence_instances_internal(&conn, &third, 2, 0).unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(page, vec![third.clone(), second.clone()]);
+        let page: Vec<String> = get_recurrence_instances_internal(&conn, &root, 2, 2).unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(page, vec![root.clone()]);
+    }
+    
+    #[test]
+    fn test_archived_tasks_leave_task_list() {
+        let conn = setup_test_conn();
+        let done = insert_task(&conn, "Old report", "low");
+        let open = insert_task(&conn, "New report", "low");
+        let ids = |archived: bool| -> Vec<String> {
+            get_tasks_internal(&conn, None, archived).unwrap().into_iter().map(|t| t.id).collect()
+        };
+        
+        // Both due on 1970-04-11, with a reminder pending for the one about to be archived
+        let today_start = 100 * 86400;
+        conn.execute("UPDATE tasks SET due_at = ?1", params![today_start + 3600]).unwrap();
+        conn.execute(
+            "INSERT INTO notification_schedule (id, task_id, scheduled_at, created_at, kind) VALUES ('n1', ?1, ?2, 0, 'reminder')",
+            params![done, today_start],
+        ).unwrap();
+        
+        set_task_archived(&conn, &done, true).unwrap();
+        assert_eq!(ids(false), vec![open.clone()]);
+        assert_eq!(ids(true), vec![done.clone()]);
+        assert_eq!(fetch_task(&conn, &done).unwrap().title, "Old report");
+        let reminders: i64 = conn.query_row("SELECT COUNT(*) FROM notification_schedule", [], |row| row.get(0)).unwrap();
+        assert_eq!(reminders, 0);
+        let counts = get_sidebar_counts_internal(&conn, today_start).unwrap();
+        assert_eq!((counts.open, counts.today), (1, 1));
+        let agenda = get_agenda_internal(&conn, "1970-04-11", Some(0)).unwrap();
+        assert_eq!(agenda.timeline.iter().map(|t| t.id.clone()).collect::<Vec<_>>(), vec![open.clone()]);
+        let recent = get_recent_tasks_internal(&conn, "created", 10, now()).unwrap();
+        assert!(recent.iter().all(|r| r.task.id != done));
+        
+        // Completed this week with a note: only the unarchived task counts in the summaries
+        let now = now();
+        conn.execute("UPDATE tasks SET created_at = ?1, completed_at = ?1, completion_note = 'Sent'", params![now]).unwrap();
+        let review = weekly_review_service::get_weekly_review(&conn, now).unwrap();
+        assert_eq!((review.tasks_completed, review.tasks_created), (1, 1));
+        assert_eq!(review.completions_by_project.iter().map(|p| p.completed).sum::<i64>(), 1);
+        assert_eq!(review.completion_notes.iter().map(|n| n.task_id.clone()).collect::<Vec<_>>(), vec![open.clone()]);
+        let status = status_service::get_status_line(&conn, now - 60, now + 60).unwrap();
+        assert_eq!(status.completed_today, 1);
+        
+        // Open, due soon and waiting on someone for two weeks
+        conn.execute(
+            "UPDATE tasks SET completed_at = NULL, due_at = ?1, waiting_on = 'Legal', waiting_since = ?2",
+            params![now + 3600, now - 14 * 86400],
+        ).unwrap();
+        let review = weekly_review_service::get_weekly_review(&conn, now).unwrap();
+        assert_eq!(review.upcoming_deadlines.iter().map(|d| d.task_id.clone()).collect::<Vec<_>>(), vec![open.clone()]);
+        assert_eq!(review.waiting_items.iter().map(|w| w.task_id.clone()).collect::<Vec<_>>(), vec![open.clone()]);
+        
+        set_task_archived(&conn, &done, false).unwrap();
+        assert!(ids(true).is_empty());
+        assert!(set_task_archived(&conn, "missing", true).is_err());
+    }
+    
+    #[test]
+    fn test_create_tasks_from_pasted_list() {
+        use chrono::TimeZone;
+        
+        let conn = setup_test_conn();
+        let existing = insert_task(&conn, "Already here", "low");
+        conn.execute("UPDATE tasks SET order_index = 4 WHERE id = ?1", params![existing]).unwrap();
+        // Wednesday 2024-01-10
+        let local_now = chrono::Local.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+        let text = "- [ ] buy milk #errands\n\n* call dentist tomorrow !high\n- [ ]\n1. Monday standup notes";
+        
+        let result = create_tasks_from_text_internal(&conn, text, None, Some("low".to_string()), local_now).unwrap();
+        let titles: Vec<&str> = result.created.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["buy milk", "call dentist", "Monday standup notes"]);
+        assert_eq!(result.skipped_lines, vec![2, 4]);
+        let priorities: Vec<&str> = result.created.iter().map(|t| t.priority.as_str()).collect();
+        assert_eq!(priorities, vec!["low", "high", "low"]);
+        let tomorrow = chrono::Local.with_ymd_and_hms(2024, 1, 11, 0, 0, 0).unwrap().timestamp();
+        assert_eq!(result.created[1].due_date, Some(tomorrow));
+        assert_eq!(result.created[2].due_date, None);
+        assert_eq!(fetch_task_tags(&conn, &result.created[0].id).unwrap().iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["errands"]);
+        
+        // Appended after the existing task, in pasted order
+        let order: Vec<i32> = result.created.iter().map(|t| t.order_index).collect();
+        assert_eq!(order, vec![5, 6, 7]);
+    }
+    
+    #[test]
+    fn test_duplicate_task_copies_subtasks_and_tags() {
+        let conn = setup_test_conn();
+        let original = insert_task(&conn, "Plan trip", "high");
+        conn.execute(
+            "UPDATE tasks SET description = 'Book early', completed_at = 5, recurrence_type = 'weekly',
+                recurrence_days_of_week = '2,4', recurrence_end_date = 900, recurrence_count = 6 WHERE id = ?1",
+            params![original],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES ('s1', ?1, 'Flights', 1, 0), ('s2', ?1, 'Hotel', 0, 1)",
+            params![original],
+        ).unwrap();
+        conn.execute("INSERT INTO tags (id, name, created_at, usage_count) VALUES ('t1', 'travel', 0, 1)", []).unwrap();
+        conn.execute("INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES ('a', ?1, 't1', 0)", params![original]).unwrap();
+        
+        let storage = tempfile::TempDir::new().unwrap();
+        let defaults = DuplicateTaskOptions::default();
+        let copy = duplicate_task_internal(&conn, storage.path(), &original, None, &defaults).unwrap();
+        assert_ne!(copy.id, original);
+        assert_eq!(copy.title, "Plan trip (copy)");
+        assert_eq!(copy.description.as_deref(), Some("Book early"));
+        assert_eq!(copy.priority, "high");
+        assert!(!copy.completed);
+        assert_eq!(
+            (copy.recurrence_days_of_week, copy.recurrence_end_date, copy.recurrence_count),
+            (Some(vec![2, 4]), Some(900), Some(6))
+        );
+        assert_eq!(copy.tags.unwrap().iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["t1"]);
+        let subtasks: Vec<(String, bool)> = conn.prepare("SELECT title, completed FROM subtasks WHERE task_id = ?1 ORDER BY order_index").unwrap()
+            .query_map(params![copy.id], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(subtasks, vec![("Flights".to_string(), false), ("Hotel".to_string(), false)]);
+        let usage: i64 = conn.query_row("SELECT usage_count FROM tags WHERE id = 't1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(usage, 2);
+        
+        let renamed = duplicate_task_internal(&conn, storage.path(), &original, Some("Plan winter trip".to_string()), &defaults).unwrap();
+        assert_eq!(renamed.title, "Plan winter trip");
+        assert!(duplicate_task_internal(&conn, storage.path(), "missing", None, &defaults).is_err());
+    }
+    
+    #[test]
+    fn test_duplicate_task_copies_attachment_files() {
+        let conn = setup_test_conn();
+        let storage = tempfile::TempDir::new().unwrap();
+        let original = insert_task(&conn, "Meeting prep", "medium");
+        conn.execute("INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES ('s1', ?1, 'Agenda', 0, 0)", params![original]).unwrap();
+        let stored = crate::attachments::write_into_storage(storage.path(), "notes.md", b"# Notes", &original).unwrap();
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, filename, path, size, created_at) VALUES ('a1', ?1, 'notes.md', ?2, 7, 0)",
+            params![original, stored],
+        ).unwrap();
+        
+        let options = DuplicateTaskOptions { include_subtasks: false, include_tags: false, include_attachments: true };
+        let copy = duplicate_task_internal(&conn, storage.path(), &original, None, &options).unwrap();
+        let subtasks: i64 = conn.query_row("SELECT COUNT(*) FROM subtasks WHERE task_id = ?1", params![copy.id], |row| row.get(0)).unwrap();
+        assert_eq!(subtasks, 0);
+        let (filename, path): (String, String) = conn.query_row(
+            "SELECT filename, path FROM attachments WHERE task_id = ?1",
+            params![copy.id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(filename, "notes.md");
+        assert_ne!(path, stored);
+        assert!(std::path::Path::new(&path).starts_with(std::path::Path::new("attachments").join(&copy.id)));
+        assert_eq!(std::fs::read(storage.path().join(&path)).unwrap(), b"# Notes");
+        
+        // A file that can't be copied fails the whole duplicate
+        conn.execute("UPDATE attachments SET path = 'attachments/gone.md' WHERE id = 'a1'", []).unwrap();
+        let tasks_before: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert!(duplicate_task_internal(&conn, storage.path(), &original, None, &options).is_err());
+        let tasks_after: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(tasks_after, tasks_before);
+    }
+    
+    #[test]
+    fn test_reorder_repairs_duplicate_indexes() {
+        let conn = setup_test_conn();
+        // Imported tasks all sit at order_index 0 and are shown in creation order
+        let ids: Vec<String> = ["a", "b", "c", "d"].iter().enumerate().map(|(i, title)| {
+            let id = insert_task(&conn, title, "low");
+            conn.execute("UPDATE tasks SET created_at = ?1, order_index = 0 WHERE id = ?2", params![i as i64, id]).unwrap();
+            id
+        }).collect();
+        let order = |conn: &rusqlite::Connection| -> Vec<String> {
+            tasks_in_visual_order(conn, None).unwrap().into_iter().map(|(id, _)| id).collect()
+        };
+        assert_eq!(get_duplicate_order_indexes_internal(&conn).unwrap()[0].task_count, 4);
+        
+        // Moving d before b renumbers once, then writes only d
+        reorder_tasks_internal(&conn, &ids[3], Some(&ids[1])).unwrap();
+        assert_eq!(order(&conn), vec![ids[0].clone(), ids[3].clone(), ids[1].clone(), ids[2].clone()]);
+        assert!(get_duplicate_order_indexes_internal(&conn).unwrap().is_empty());
+        let indexes: Vec<i32> = tasks_in_visual_order(&conn, None).unwrap().into_iter().map(|(_, i)| i).collect();
+        assert_eq!(indexes, vec![0, 5, 10, 20]);
+        
+        // Keep moving into the same gap until it runs out
+        for _ in 0..5 {
+            reorder_tasks_internal(&conn, &ids[2], Some(&ids[3])).unwrap();
+            reorder_tasks_internal(&conn, &ids[3], Some(&ids[2])).unwrap();
+        }
+        assert_eq!(order(&conn), vec![ids[0].clone(), ids[3].clone(), ids[2].clone(), ids[1].clone()]);
+        reorder_tasks_internal(&conn, &ids[0], None).unwrap();
+        assert_eq!(order(&conn).last(), Some(&ids[0]));
+        assert!(get_duplicate_order_indexes_internal(&conn).unwrap().is_empty());
+        
+        assert_eq!(normalize_order_indexes_internal(&conn, None).unwrap(), 4);
+        assert_eq!(normalize_order_indexes_internal(&conn, Some("")).unwrap(), 0);
+        assert_eq!(next_order_index(&conn, None).unwrap(), 40);
+    }
+    
+    #[test]
+    fn test_reorder_tasks_batch_is_all_or_nothing() {
+        let conn = setup_test_conn();
+        let ids: Vec<String> = ["a", "b", "c"].iter().map(|title| insert_task(&conn, title, "low")).collect();
+        let order = |conn: &rusqlite::Connection| -> Vec<(String, i32)> { tasks_in_visual_order(conn, None).unwrap() };
+        let before = order(&conn);
+        
+        let err = reorder_tasks_batch_internal(&conn, &[
+            (ids[2].clone(), 0),
+            ("gone".to_string(), 10),
+            (ids[0].clone(), 20),
+        ]).unwrap_err();
+        assert_eq!(err, "Tasks not found: gone");
+        assert_eq!(order(&conn), before);
+        
+        reorder_tasks_batch_internal(&conn, &[(ids[2].clone(), 0), (ids[0].clone(), 10), (ids[1].clone(), 20)]).unwrap();
+        assert_eq!(order(&conn), vec![(ids[2].clone(), 0), (ids[0].clone(), 10), (ids[1].clone(), 20)]);
+        reorder_tasks_batch_internal(&conn, &[]).unwrap();
+    }
+    
+    #[test]
+    fn test_reorder_task_list_rewrites_only_listed_tasks() {
+        let conn = setup_test_conn();
+        let ids: Vec<String> = ["a", "b", "c", "d"].iter().map(|title| insert_task(&conn, title, "low")).collect();
+        let untouched = conn.query_row("SELECT order_index FROM tasks WHERE id = ?1", params![ids[3]], |row| row.get::<_, i32>(0)).unwrap();
+        
+        let listed = vec![ids[2].clone(), ids[0].clone(), ids[1].clone()];
+        let tasks = reorder_task_list_internal(&conn, Some(""), &listed).unwrap();
+        let result: Vec<(String, i32)> = tasks.into_iter().map(|t| (t.id, t.order_index)).collect();
+        assert_eq!(result, vec![(ids[2].clone(), 0), (ids[0].clone(), 10), (ids[1].clone(), 20)]);
+        let order_index = conn.query_row("SELECT order_index FROM tasks WHERE id = ?1", params![ids[3]], |row| row.get::<_, i32>(0)).unwrap();
+        assert_eq!(order_index, untouched);
+        
+        conn.execute("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Work', 0, 0)", []).unwrap();
+        conn.execute("UPDATE tasks SET project_id = 'p1' WHERE id = ?1", params![ids[3]]).unwrap();
+        let err = reorder_task_list_internal(&conn, Some("p1"), &[ids[3].clone(), ids[0].clone()]).unwrap_err();
+        assert_eq!(err, format!("Task {} is not in the same list", ids[0]));
+        assert!(reorder_task_list_internal(&conn, None, &[ids[0].clone(), ids[0].clone()]).is_err());
+        assert_eq!(reorder_task_list_internal(&conn, None, &[ids[0].clone(), "gone".to_string()]).unwrap_err(), "Tasks not found: gone");
+        assert_eq!(conn.query_row("SELECT order_index FROM tasks WHERE id = ?1", params![ids[0]], |row| row.get::<_, i32>(0)).unwrap(), 10);
+    }
+    
+    #[test]
+    fn test_fts_search_ranks_by_relevance() {
+        let conn = setup_test_conn();
+        insert_task(&conn, "Send invoice", "medium");
+        insert_task(&conn, "Invoice invoice invoice", "medium");
+        let accountant = insert_task(&conn, "Call accountant", "medium");
+        conn.execute("UPDATE tasks SET description = 'About the March invoice' WHERE id = ?1", params![accountant]).unwrap();
+        let search = |text: &str| -> Vec<String> {
+            let filter = TaskFilter {
+                project_id: None,
+                completed: None,
+                due_before: None,
+                due_after: None,
+                search: None,
+                tag_id: None,
+                with_block_status: Some(false),
+                collapse_recurring: None,
+                fts_search: Some(text.to_string()),
+                include_waiting: None,
+                limit: None,
+                offset: None,
+                sort_by: None,
+                sort_dir: None,
+                overdue_only: None,
+                has_description: None,
+                has_attachments: None,
+                has_subtasks: None,
+                description_preview_only: None,
+            };
+            get_tasks_internal(&conn, Some(&filter), false).unwrap().into_iter().map(|t| t.title).collect()
+        };
+        
+        assert_eq!(search("invoice"), vec!["Invoice invoice invoice", "Send invoice", "Call accountant"]);
+        // Prefixes match and every word is required
+        assert_eq!(search("acc invo"), vec!["Call accountant"]);
+        // Quotes and operators are searched for rather than parsed; punctuation alone filters nothing
+        assert!(search("\"send OR").is_empty());
+        assert_eq!(search("--").len(), 3);
+        
+        // Triggers keep the index in step with edits and deletes
+        conn.execute("UPDATE tasks SET title = 'Pay rent' WHERE title = 'Send invoice'", []).unwrap();
+        conn.execute("DELETE FROM tasks WHERE id = ?1", params![accountant]).unwrap();
+        assert_eq!(search("invoice"), vec!["Invoice invoice invoice"]);
+        assert_eq!(search("rent"), vec!["Pay rent"]);
+    }
+    
+    #[test]
+    fn test_waiting_status_and_follow_up() {
+        let conn = setup_test_conn();
+        let waiting = insert_task(&conn, "Sign contract", "high");
+        let other = insert_task(&conn, "Water plants", "low");
+        let now = now();
+        let day = 86400;
+        
+        assert!(set_waiting_internal(&conn, &waiting, "  ", Some(3), now).is_err());
+        let task = set_waiting_internal(&conn, &waiting, "Legal", Some(3), now).unwrap();
+        assert_eq!(task.waiting_on.as_deref(), Some("Legal"));
+        assert_eq!(task.waiting_since, Some(now));
+        
+        let titles = |include_waiting: Option<bool>| -> Vec<String> {
+            let filter = TaskFilter {
+                project_id: None,
+                completed: None,
+                due_before: None,
+                due_after: None,
+                search: None,
+                tag_id: None,
+                with_block_status: Some(false),
+                collapse_recurring: None,
+                fts_search: None,
+                include_waiting,
+                limit: None,
+                offset: None,
+                sort_by: None,
+                sort_dir: None,
+                overdue_only: None,
+                has_description: None,
+                has_attachments: None,
+                has_subtasks: None,
+                description_preview_only: None,
+            };
+            get_tasks_internal(&conn, Some(&filter), false).unwrap().into_iter().map(|t| t.title).collect()
+        };
+        assert_eq!(titles(None), vec!["Water plants".to_string()]);
+        assert_eq!(titles(Some(true)).len(), 2);
+        assert_eq!(get_tasks_internal(&conn, None, false).unwrap().len(), 1);
+        
+        // Editing the due date replaces reminders but keeps the follow-up
+        conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = ?2", params![now + 10 * day, waiting]).unwrap();
+        crate::notifications::reschedule_reminder(&conn, &waiting, Some(15), now).unwrap();
+        let follow_ups: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM notification_schedule WHERE task_id = ?1 AND kind = 'follow_up' AND scheduled_at = ?2",
+            params![waiting, now + 3 * day],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(follow_ups, 1);
+        let due = crate::notifications::collect_due_reminders(&conn, now + 3 * day, None).unwrap();
+        assert_eq!(due.fresh.len(), 1);
+        assert_eq!(due.fresh[0].waiting_on.as_deref(), Some("Legal"));
+        
+        // Changing who it waits on keeps the original start; the weekly review reports the age
+        conn.execute("UPDATE tasks SET waiting_since = ?1 WHERE id = ?2", params![now - 10 * day, waiting]).unwrap();
+        set_waiting_internal(&conn, &waiting, "Legal and finance", None, now).unwrap();
+        let review = weekly_review_service::get_weekly_review(&conn, now).unwrap();
+        assert_eq!(review.waiting_items.len(), 1);
+        assert_eq!(review.waiting_items[0].waiting_on, "Legal and finance");
+        assert_eq!(review.waiting_items[0].age_days, 10);
+        
+        let task = clear_waiting_internal(&conn, &waiting, now).unwrap();
+        assert_eq!(task.waiting_on, None);
+        assert_eq!(titles(None).len(), 2);
+        let remaining: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM notification_schedule WHERE task_id = ?1 AND kind = 'follow_up'",
+            params![waiting],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(remaining, 0);
+        assert!(clear_waiting_internal(&conn, &other, now).is_ok());
+    }
+    
+    #[test]
+    fn test_get_tasks_page() {
+        let conn = setup_test_conn();
+        for i in 0..25 {
+            insert_task(&conn, &format!("Task {:02}", i), if i % 2 == 0 { "high" } else { "low" });
+        }
+        conn.execute("UPDATE tasks SET due_at = 1000 WHERE title = 'Task 07'", []).unwrap();
+        let page = |limit: Option<i64>, offset: Option<i64>, sort_by: Option<&str>, sort_dir: Option<&str>| {
+            let filter = TaskFilter {
+                project_id: None,
+                completed: None,
+                due_before: None,
+                due_after: None,
+                search: None,
+                tag_id: None,
+                with_block_status: Some(false),
+                collapse_recurring: None,
+                fts_search: None,
+                include_waiting: None,
+                limit,
+                offset,
+                sort_by: sort_by.map(str::to_string),
+                sort_dir: sort_dir.map(str::to_string),
+                overdue_only: None,
+                has_description: None,
+                has_attachments: None,
+                has_subtasks: None,
+                description_preview_only: None,
+            };
+            get_tasks_page_internal(&conn, Some(&filter), false, true)
+        };
+        let titles = |page: TaskPage| -> Vec<String> { page.tasks.into_iter().map(|t| t.title).collect() };
+        
+        let last = page(Some(10), Some(20), Some("title"), None).unwrap();
+        assert_eq!(last.total_count, 25);
+        assert_eq!(titles(last), (20..25).map(|i| format!("Task {:02}", i)).collect::<Vec<_>>());
+        assert_eq!(titles(page(Some(2), None, Some("title"), Some("desc")).unwrap()), vec!["Task 24", "Task 23"]);
+        // Tasks without a due date go last in both directions
+        assert_eq!(titles(page(Some(1), None, Some("due_date"), Some("desc")).unwrap()), vec!["Task 07"]);
+        assert_eq!(page(Some(1), None, Some("priority"), Some("desc")).unwrap().tasks[0].priority, "high");
+        let beyond = page(Some(10), Some(40), None, None).unwrap();
+        assert!(beyond.tasks.is_empty());
+        assert_eq!(beyond.total_count, 25);
+        
+        // Without a limit everything comes back, as before
+        let all = page(None, None, None, None).unwrap();
+        assert_eq!((all.tasks.len(), all.total_count), (25, 25));
+        assert!(page(None, None, Some("color"), None).is_err());
+        assert!(page(None, None, None, Some("sideways")).is_err());
+    }
+    
+    #[test]
+    fn test_bulk_update_priority_rescores_completed_xp() {
+        let conn = setup_test_conn();
+        let open = insert_task(&conn, "Draft plan", "low");
+        let done = insert_task(&conn, "File taxes", "low");
+        let already_high = insert_task(&conn, "Renew passport", "high");
+        toggle_complete_internal(&conn, &done).unwrap();
+        toggle_complete_internal(&conn, &already_high).unwrap();
+        let total_before = get_user_progress_internal(&conn).unwrap().total_xp;
+        
+        assert!(bulk_update_priority_internal(&conn, std::slice::from_ref(&open), "urgent").is_err());
+        assert_eq!(bulk_update_priority_internal(&conn, &[], "high").unwrap(), 0);
+        
+        let ids = vec![open.clone(), done.clone(), already_high.clone(), "missing".to_string()];
+        assert_eq!(bulk_update_priority_internal(&conn, &ids, "high").unwrap(), 3);
+        assert_eq!(fetch_task(&conn, &open).unwrap().priority, "high");
+        
+        // Only the completed low task is rescored, from 10 to 50 XP
+        let progress = get_user_progress_internal(&conn).unwrap();
+        assert_eq!(progress.total_xp, total_before + 40);
+        assert_eq!(progress.current_level, calculate_level(progress.total_xp));
+        let result = toggle_complete_internal(&conn, &done).unwrap();
+        assert_eq!(result.xp_revoked, 50);
+    }
+    
+    #[test]
+    fn test_update_task_from_tray_records_its_source() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Call the bank", "low");
+        let before = fetch_task(&conn, &task).unwrap().updated_at;
+        
+        let input = UpdateTaskInput { due_date: Some(now() + 86400), priority: Some("medium".to_string()), ..Default::default() };
+        let updated = update_task_from(&conn, &task, input, "snooze", "tray").unwrap();
+        assert!(updated.updated_at > before);
+        let history_source: String = conn.query_row(
+            "SELECT source FROM due_date_history WHERE task_id = ?1",
+            params![task],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(history_source, "snooze");
+        let activity_sources: Vec<String> = conn.prepare("SELECT DISTINCT source FROM activity_log WHERE task_id = ?1").unwrap()
+            .query_map(params![task], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(activity_sources, vec!["tray"]);
+    }
+
+    #[test]
+    fn test_due_date_history_tracks_reschedules_and_clears() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "File taxes", "high");
+        let day = 86400;
+        let original = 1_700_000_000;
+        conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = ?2", params![original, task]).unwrap();
+
+        // Never rescheduled: no entries and no slip
+        let history = get_due_date_history_internal(&conn, &task).unwrap();
+        assert!(history.entries.is_empty());
+        assert_eq!(history.original_due_date, Some(original));
+        assert_eq!(history.total_slip_days, 0);
+
+        let reschedule = |due: i64| {
+            let input = UpdateTaskInput { due_date: Some(due), ..Default::default() };
+            update_task_internal(&conn, &task, input).unwrap();
+        };
+        reschedule(original + 2 * day);
+        reschedule(original + day);
+        conn.execute("UPDATE tasks SET due_at = NULL WHERE id = ?1", params![task]).unwrap();
+        record_due_date_change(&conn, &task, Some(original + day), None, "update").unwrap();
+
+        // Cleared: the original survives but there is nothing to slip against
+        let history = get_due_date_history_internal(&conn, &task).unwrap();
+        assert_eq!(history.original_due_date, Some(original));
+        assert_eq!(history.current_due_date, None);
+        assert_eq!(history.total_slip_days, 0);
+
+        reschedule(original + 5 * day);
+        let history = get_due_date_history_internal(&conn, &task).unwrap();
+        let labels: Vec<&str> = history.entries.iter().map(|e| e.delta_label.as_str()).collect();
+        assert_eq!(labels, vec!["+2 days", "-1 day", "cleared", "set"]);
+        let deltas: Vec<Option<i64>> = history.entries.iter().map(|e| e.delta_days).collect();
+        assert_eq!(deltas, vec![Some(2), Some(-1), None, None]);
+        assert_eq!(history.original_due_date, Some(original));
+        assert_eq!(history.current_due_date, Some(original + 5 * day));
+        assert_eq!(history.total_slip_days, 5);
+
+        assert!(get_due_date_history_internal(&conn, "missing").is_err());
+    }
+
+    #[test]
+    fn test_run_rules_on_existing_skips_archived_tasks() {
+        let db = DbConnection::from_connection(setup_test_conn());
+        let open = insert_task(&db.conn, "Send invoice to ACME", "low");
+        let archived = insert_task(&db.conn, "Old invoice", "low");
+        db.conn.execute("UPDATE tasks SET archived = 1 WHERE id = ?1", params![archived]).unwrap();
+        rules_service::create_rule(&db.conn, rules_service::RuleInput {
+            name: "Invoices".to_string(),
+            enabled: None,
+            condition: rules_service::RuleCondition {
+                field: "title".to_string(),
+                operator: "contains".to_string(),
+                value: "invoice".to_string(),
+            },
+            actions: rules_service::RuleActions { set_priority: Some("high".to_string()), ..Default::default() },
+        }).unwrap();
+        
+        let result = run_rules_on_existing_internal(&db, RuleBackfillFilter::default()).unwrap();
+        assert_eq!((result.tasks_checked, result.tasks_changed), (1, 1));
+        assert_eq!(fetch_task(&db.conn, &open).unwrap().priority, "high");
+        assert_eq!(fetch_task(&db.conn, &archived).unwrap().priority, "low");
+    }
+    
+    #[test]
+    fn test_create_task_full_is_all_or_nothing() {
+        let db = DbConnection::from_connection(setup_test_conn());
+        let storage = tempfile::TempDir::new().unwrap();
+        let input = |title: &str| CreateTaskFullInput {
+            task: CreateTaskInput {
+                title: title.to_string(),
+                description: None,
+                due_date: Some(now() + 86400),
+                priority: "high".to_string(),
+                project_id: None,
+                recurrence_type: None,
+                recurrence_interval: None,
+                reminder_minutes_before: None,
+                notification_repeat: None,
+                reminder_sound: None,
+                reminder_urgency: None,
+                completion_note_required: None,
+                recurrence_days_of_week: None,
+                recurrence_end_date: None,
+                recurrence_count: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+            },
+            subtasks: vec!["Book venue".to_string(), " ".to_string(), "Send invites".to_string()],
+            tags: vec!["Events".to_string(), "planning".to_string()],
+            reminder: Some(ReminderSpec { minutes_before: 60, repeat: None, sound: None, urgency: None }),
+            attachment: Some(AttachmentPayload {
+                path: None,
+                filename: Some("agenda.txt".to_string()),
+                data_base64: Some("MTAgYW0ga2lja29mZg==".to_string()),
+            }),
+        };
+        let stored_files = || walk_files(storage.path());
+        
+        let details = create_task_full_internal(&db, storage.path(), input("Team offsite")).unwrap();
+        assert_eq!(details.task.reminder_minutes_before, Some(60));
+        assert_eq!(details.subtasks.iter().map(|s| s.title.as_str()).collect::<Vec<_>>(), vec!["Book venue", "Send invites"]);
+        let mut tags: Vec<String> = details.task.tags.clone().unwrap().into_iter().map(|t| t.name).collect();
+        tags.sort();
+        assert_eq!(tags, vec!["events", "planning"]);
+        assert_eq!(details.attachments.len(), 1);
+        assert_eq!(details.attachments[0].size, Some(13));
+        assert_eq!(std::fs::read(storage.path().join(&details.attachments[0].path)).unwrap(), b"10 am kickoff");
+        
+        // A failure after the file was written rolls back the task and removes the file
+        db.conn.execute_batch("DROP TABLE task_tags").unwrap();
+        assert!(create_task_full_internal(&db, storage.path(), input("Second offsite")).is_err());
+        assert_eq!(stored_files().len(), 1);
+        let tasks: i64 = db.conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(tasks, 1);
+        
+        let mut bad = input("Third offsite");
+        bad.attachment = Some(AttachmentPayload { path: None, filename: Some("run.exe".to_string()), data_base64: Some("TVo=".to_string()) });
+        assert!(create_task_full_internal(&db, storage.path(), bad).is_err());
+    }
+    
+    fn walk_files(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(walk_files(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+    
+    static TRACED_STATEMENTS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    
+    fn count_statement(_sql: &str) {
+        TRACED_STATEMENTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+    
+    #[test]
+    fn test_get_tasks_tag_queries_do_not_grow_with_task_count() {
+        type TaskQuery<'a> = Box<dyn Fn(&rusqlite::Connection) -> Result<Vec<Task>, String> + 'a>;
+        let mut conn = setup_test_conn();
+        let mut statements_for = |count: usize| -> Vec<usize> {
+            conn.execute_batch("DELETE FROM task_relationships; DELETE FROM task_tags; DELETE FROM tasks;").unwrap();
+            let hub = insert_task(&conn, "Hub", "high");
+            for i in 0..count {
+                let task = insert_task(&conn, &format!("Task {}", i), "medium");
+                attach_tag_by_name(&conn, &task, "work").unwrap();
+                attach_tag_by_name(&conn, &task, if i % 2 == 0 { "errands" } else { "calls" }).unwrap();
+                // Every task both blocks the hub and is blocked by it
+                conn.execute(
+                    "INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at)
+                     VALUES (?1 || '-in', ?1, ?2, 'blocks', 0), (?1 || '-out', ?2, ?1, 'blocks', 0)",
+                    params![task, hub],
+                ).unwrap();
+            }
+            let tag_ids: Vec<String> = conn.prepare("SELECT id FROM tags ORDER BY name").unwrap()
+                .query_map([], |row| row.get(0)).unwrap()
+                .collect::<Result<_, _>>().unwrap();
+            
+            let queries: Vec<TaskQuery> = vec![
+                Box::new(|conn| get_tasks_internal(conn, None, false).map(|tasks| tasks.into_iter().filter(|t| t.title != "Hub").collect())),
+                Box::new(|conn| get_tasks_by_tag_internal(conn, &tag_ids[2])),
+                Box::new(|conn| get_tasks_by_tags_internal(conn, &tag_ids)),
+                Box::new(|conn| get_related_tasks_internal(conn, &hub)),
+                Box::new(|conn| fetch_blocking_tasks(conn, &hub)),
+                Box::new(|conn| get_blocked_tasks_internal(conn, &hub)),
+            ];
+            queries.iter().map(|query| {
+                conn.trace(Some(count_statement));
+                TRACED_STATEMENTS.store(0, std::sync::atomic::Ordering::SeqCst);
+                let tasks = query(&conn).unwrap();
+                let statements = TRACED_STATEMENTS.load(std::sync::atomic::Ordering::SeqCst);
+                conn.trace(None);
+                
+                assert_eq!(tasks.len(), count);
+                assert!(statements > 0);
+                // Tags still come back ordered by name
+                let names: Vec<String> = tasks[0].tags.clone().unwrap().into_iter().map(|t| t.name).collect();
+                assert!(names == ["errands", "work"] || names == ["calls", "work"], "{:?}", names);
+                statements
+            }).collect()
+        };
+        
+        assert_eq!(statements_for(3), statements_for(120));
+    }
+    
+    #[test]
+    fn test_search_tasks_covers_subtasks_and_attachments() {
+        let conn = setup_test_conn();
+        let trip = insert_task(&conn, "Plan trip", "medium");
+        let taxes = insert_task(&conn, "Do taxes", "high");
+        let lisbon = insert_task(&conn, "Lisbon hotels", "low");
+        conn.execute("INSERT INTO subtasks (id, task_id, title) VALUES ('s1', ?1, 'Book Lisbon flights')", params![trip]).unwrap();
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, filename, path, created_at) VALUES ('a1', ?1, 'receipts_2024.pdf', 'a1.pdf', 0)",
+            params![taxes],
+        )
+        .unwrap();
+        let search = |query: &str| -> Vec<(String, String)> {
+            search_tasks_internal(&conn, query, None)
+                .unwrap()
+                .into_iter()
+                .map(|result| (result.task.title, result.snippet))
+                .collect()
+        };
+        
+        // A title hit outranks a subtask hit
+        let hits = search("lisbon");
+        assert_eq!(hits.iter().map(|(title, _)| title.as_str()).collect::<Vec<_>>(), vec!["Lisbon hotels", "Plan trip"]);
+        assert_eq!(hits[1].1, "Book <mark>Lisbon</mark> flights");
+        assert_eq!(search("receipts").len(), 1);
+        assert!(search("   ").is_empty());
+        // Subtasks are indexed one by one: the task is listed once, and words from different
+        // subtasks don't add up to a match
+        conn.execute("INSERT INTO subtasks (id, task_id, title) VALUES ('s2', ?1, 'Book hotel')", params![trip]).unwrap();
+        assert_eq!(search("book").len(), 1);
+        assert!(search("flights hotel").is_empty());
+        conn.execute("DELETE FROM subtasks WHERE id = 's2'", []).unwrap();
+        
+        // Edits, removed children and deleted tasks all reach the index
+        conn.execute("UPDATE subtasks SET title = 'Book Porto flights' WHERE id = 's1'", []).unwrap();
+        conn.execute("DELETE FROM attachments WHERE id = 'a1'", []).unwrap();
+        conn.execute("UPDATE tasks SET title = 'Hotels <cheap>' WHERE id = ?1", params![lisbon]).unwrap();
+        assert_eq!(search("porto"), vec![("Plan trip".to_string(), "Book <mark>Porto</mark> flights".to_string())]);
+        assert!(search("receipts").is_empty());
+        assert!(search("lisbon").is_empty());
+        conn.execute("DELETE FROM tasks WHERE id = ?1", params![trip]).unwrap();
+        assert!(search("porto").is_empty());
+        
+        // Without the FTS table the same fields are searched with LIKE
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, filename, path, created_at) VALUES ('a2', ?1, 'W2 form.pdf', 'a2.pdf', 0)",
+            params![taxes],
+        )
+        .unwrap();
+        let triggers: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'trigger' AND name LIKE 'attachments_fts%'")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        for trigger in triggers {
+            conn.execute(&format!("DROP TRIGGER {}", trigger), []).unwrap();
+        }
+        conn.execute("DROP TABLE attachments_fts", []).unwrap();
+        assert_eq!(search("w2 FORM"), vec![("Do taxes".to_string(), "<mark>W2 form</mark>.pdf".to_string())]);
+        assert_eq!(search("cheap"), vec![("Hotels <cheap>".to_string(), "Hotels &lt;<mark>cheap</mark>&gt;".to_string())]);
+    }
+    
+    #[test]
+    fn test_pinned_tasks_lead_the_list() {
+        let conn = setup_test_conn();
+        insert_task(&conn, "First", "low");
+        let second = insert_task(&conn, "Second", "low");
+        let third = insert_task(&conn, "Third", "low");
+        conn.execute("UPDATE tasks SET order_index = 1 WHERE id = ?1", params![second]).unwrap();
+        conn.execute("UPDATE tasks SET order_index = 2 WHERE id = ?1", params![third]).unwrap();
+        let titles = |tasks: Vec<Task>| -> Vec<String> { tasks.into_iter().map(|t| t.title).collect() };
+        
+        assert!(set_task_pinned(&conn, &third, true).unwrap().pinned);
+        set_task_pinned(&conn, &second, true).unwrap();
+        conn.execute("UPDATE tasks SET updated_at = updated_at + 10 WHERE id = ?1", params![third]).unwrap();
+        assert_eq!(titles(get_tasks_internal(&conn, None, false).unwrap()), vec!["Second", "Third", "First"]);
+        assert_eq!(titles(get_pinned_tasks_internal(&conn).unwrap()), vec!["Third", "Second"]);
+        assert!(fetch_task(&conn, &second).unwrap().pinned);
+        
+        assert!(!set_task_pinned(&conn, &third, false).unwrap().pinned);
+        assert_eq!(titles(get_tasks_internal(&conn, None, false).unwrap()), vec!["Second", "First", "Third"]);
+        assert_eq!(titles(get_pinned_tasks_internal(&conn).unwrap()), vec!["Second"]);
+        assert!(set_task_pinned(&conn, "missing", true).is_err());
+    }
+    
+    #[test]
+    fn test_shift_task_due_dates() {
+        let conn = setup_test_conn();
+        let now = now();
+        let due = now + 86400;
+        let reminded = insert_task(&conn, "Write report", "medium");
+        let plain = insert_task(&conn, "Review slides", "medium");
+        let done = insert_task(&conn, "Book room", "medium");
+        let undated = insert_task(&conn, "Think about it", "medium");
+        let elsewhere = insert_task(&conn, "Other project", "medium");
+        conn.execute("UPDATE tasks SET project_id = 'p1', due_at = ?1", params![due]).unwrap();
+        conn.execute("UPDATE tasks SET due_at = NULL WHERE id = ?1", params![undated]).unwrap();
+        conn.execute("UPDATE tasks SET project_id = 'p2' WHERE id = ?1", params![elsewhere]).unwrap();
+        conn.execute("UPDATE tasks SET completed_at = ?1 WHERE id = ?2", params![now, done]).unwrap();
+        conn.execute("UPDATE tasks SET reminder_minutes_before = 30 WHERE id = ?1", params![reminded]).unwrap();
+        crate::notifications::reschedule_reminder(&conn, &reminded, Some(30), now).unwrap();
+        let due_at = |id: &str| fetch_task(&conn, id).unwrap().due_date;
+        
+        assert!(shift_task_due_dates_internal(&conn, None, None, 3, now).is_err());
+        assert_eq!(shift_task_due_dates_internal(&conn, Some("p1"), None, 0, now).unwrap(), 0);
+        
+        // The project's open, dated tasks move and the reminder follows
+        assert_eq!(shift_task_due_dates_internal(&conn, Some("p1"), None, 3, now).unwrap(), 2);
+        assert_eq!(due_at(&reminded), Some(due + 3 * 86400));
+        assert_eq!(due_at(&plain), Some(due + 3 * 86400));
+        assert_eq!((due_at(&done), due_at(&undated), due_at(&elsewhere)), (Some(due), None, Some(due)));
+        let reminder: i64 = conn.query_row(
+            "SELECT scheduled_at FROM notification_schedule WHERE task_id = ?1 AND kind = 'reminder'",
+            params![reminded],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(reminder, due + 3 * 86400 - 30 * 60);
+        let history: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM due_date_history WHERE task_id = ?1 AND source = 'shift'",
+            params![plain],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(history, 1);
+        
+        // Ids alone move those tasks, completed or not; with a project only the overlap moves
+        assert_eq!(shift_task_due_dates_internal(&conn, None, Some(std::slice::from_ref(&done)), -1, now).unwrap(), 1);
+        assert_eq!(due_at(&done), Some(due - 86400));
+        let ids = vec![plain.clone(), elsewhere.clone()];
+        assert_eq!(shift_task_due_dates_internal(&conn, Some("p2"), Some(&ids), 1, now).unwrap(), 1);
+        assert_eq!((due_at(&plain), due_at(&elsewhere)), (Some(due + 3 * 86400), Some(due + 86400)));
+    }
+    
+    #[test]
+    fn test_board_columns_and_forced_status_moves() {
+        let conn = setup_test_conn();
+        let doing = insert_task(&conn, "Write tests", "high");
+        let next = insert_task(&conn, "Fix build", "medium");
+        let done = insert_task(&conn, "Plan sprint", "low");
+        toggle_complete_internal(&conn, &done).unwrap();
+        set_task_status_internal(&conn, &doing, "in_progress", false).unwrap();
+        crate::services::wip_limit_service::set_wip_limit(&conn, "global", None, "in_progress", 1, 0).unwrap();
+        
+        assert!(set_task_status_internal(&conn, &next, "blocked", false).is_err());
+        let error = set_task_status_internal(&conn, &next, "in_progress", false).unwrap_err();
+        assert!(error.contains(crate::services::wip_limit_service::WIP_LIMIT_KIND) && error.contains(&doing));
+        
+        let board = get_board_internal(&conn, None, None).unwrap();
+        let titles: Vec<Vec<String>> = board
+            .columns
+            .iter()
+            .map(|column| column.tasks.iter().map(|t| t.title.clone()).collect())
+            .collect();
+        assert_eq!(titles, vec![vec!["Fix build"], vec!["Write tests"], vec!["Plan sprint"]]);
+        assert_eq!((board.columns[1].limits[0].count, board.columns[1].limits[0].limit.limit), (1, 1));
+        
+        // update_task goes through the same check, and force lets the move through
+        let mut input: UpdateTaskInput = serde_json::from_value(serde_json::json!({ "status": "in_progress" })).unwrap();
+        assert!(update_task_internal(&conn, &next, input).is_err());
+        input = serde_json::from_value(serde_json::json!({ "status": "in_progress", "force": true })).unwrap();
+        assert_eq!(update_task_internal(&conn, &next, input).unwrap().status.as_deref(), Some("in_progress"));
+        
+        // Completing a task moves it to done
+        toggle_complete_internal(&conn, &doing).unwrap();
+        assert_eq!(fetch_task(&conn, &doing).unwrap().status.as_deref(), Some("done"));
+        
+        // Moving into done completes the task, with its XP; moving out reopens it and takes the XP back
+        let xp = |conn: &rusqlite::Connection| get_user_progress_internal(conn).unwrap().total_xp;
+        let xp_before = xp(&conn);
+        let moved = set_task_status_internal(&conn, &next, "done", true).unwrap();
+        assert!(moved.completed && xp(&conn) > xp_before);
+        let moved = set_task_status_internal(&conn, &next, "in_progress", true).unwrap();
+        assert_eq!((moved.completed, moved.status.as_deref()), (false, Some("in_progress")));
+        assert_eq!(xp(&conn), xp_before);
+        input = serde_json::from_value(serde_json::json!({ "status": "done" })).unwrap();
+        assert!(update_task_internal(&conn, &next, input).unwrap().completed);
+        
+        // A task that needs a completion note stays where it is
+        conn.execute("UPDATE tasks SET completion_note_required = 1 WHERE id = ?1", params![done]).unwrap();
+        toggle_complete_internal(&conn, &done).unwrap();
+        assert_eq!(set_task_status_internal(&conn, &done, "done", true).unwrap_err(), COMPLETION_NOTE_REQUIRED_ERROR);
+        let task = fetch_task(&conn, &done).unwrap();
+        assert_eq!((task.completed, task.status.as_deref()), (false, Some("todo")));
+    }
+    
+    #[test]
+    fn test_overdue_tasks_most_overdue_first() {
+        let conn = setup_test_conn();
+        let now = now();
+        let tasks = [
+            ("Yesterday", Some(now - 86400), None),
+            ("Last week", Some(now - 7 * 86400), None),
+            ("Tomorrow", Some(now + 86400), None),
+            ("Done late", Some(now - 3 * 86400), Some(now)),
+            ("No date", None, None),
+        ];
+        for (title, due_at, completed_at) in tasks {
+            let id = insert_task(&conn, title, "medium");
+            conn.execute(
+                "UPDATE tasks SET due_at = ?1, completed_at = ?2 WHERE id = ?3",
+                params![due_at, completed_at, id],
+            ).unwrap();
+        }
+        let waiting = insert_task(&conn, "Waiting", "medium");
+        conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = ?2", params![now - 2 * 86400, waiting]).unwrap();
+        set_waiting_internal(&conn, &waiting, "Sam", None, now).unwrap();
+        let titles = |tasks: Vec<Task>| -> Vec<String> { tasks.into_iter().map(|t| t.title).collect() };
+        
+        assert_eq!(titles(get_overdue_tasks_internal(&conn, None).unwrap()), vec!["Last week", "Yesterday"]);
+        assert_eq!(get_overdue_count_internal(&conn, now).unwrap(), 2);
+        
+        // The rest of the filter still applies, and get_tasks can take the same path
+        let search: TaskFilter = serde_json::from_value(serde_json::json!({ "search": "day", "include_waiting": true })).unwrap();
+        assert_eq!(titles(get_overdue_tasks_internal(&conn, Some(search)).unwrap()), vec!["Yesterday"]);
+        let filter = TaskFilter { overdue_only: Some(true), include_waiting: Some(true), ..TaskFilter::default() };
+        assert_eq!(titles(get_tasks_internal(&conn, Some(&filter), false).unwrap()), vec!["Last week", "Waiting", "Yesterday"]);
+    }
+    
+    #[test]
+    fn test_recurrence_rules_pick_weekdays_and_stop_the_chain() {
+        use chrono::TimeZone;
+        let conn = setup_test_conn();
+        let at = |day: u32| chrono::Local.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap().timestamp();
+        let next = |parent: &str| -> Option<(String, i64)> {
+            conn.query_row(
+                "SELECT id, due_at FROM tasks WHERE recurrence_parent_id = ?1",
+                params![parent],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ).ok()
+        };
+        
+        // Monday, Wednesday and Friday, three times in all; 2024-01-01 is a Monday
+        let standup = insert_task(&conn, "Standup notes", "medium");
+        conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = ?2", params![at(1), standup]).unwrap();
+        let rule: UpdateTaskInput = serde_json::from_value(serde_json::json!({
+            "recurrence_type": "weekly",
+            "recurrence_days_of_week": [5, 1, 3],
+            "recurrence_count": 3,
+        })).unwrap();
+        let task = update_task_internal(&conn, &standup, rule).unwrap();
+        assert_eq!(task.recurrence_days_of_week, Some(vec![1, 3, 5]));
+        assert_eq!(task.recurrence_count, Some(3));
+        conn.execute("UPDATE tasks SET recurrence_end_date = ?1 WHERE id = ?2", params![at(31), standup]).unwrap();
+        // The list reads the rule the same way a single fetch does
+        let listed = get_tasks_internal(&conn, None, false).unwrap().into_iter().find(|t| t.id == standup).unwrap();
+        assert_eq!(
+            (listed.recurrence_days_of_week, listed.recurrence_end_date, listed.recurrence_count),
+            (Some(vec![1, 3, 5]), Some(at(31)), Some(3))
+        );
+        conn.execute("UPDATE tasks SET recurrence_end_date = NULL WHERE id = ?1", params![standup]).unwrap();
+        
+        toggle_complete_internal(&conn, &standup).unwrap();
+        let (second, due) = next(&standup).unwrap();
+        assert_eq!(due, at(3));
+        toggle_complete_internal(&conn, &second).unwrap();
+        let (third, due) = next(&second).unwrap();
+        assert_eq!(due, at(5));
+        toggle_complete_internal(&conn, &third).unwrap();
+        assert!(next(&third).is_none());
+        
+        // Daily until the end date
+        let course = insert_task(&conn, "Course module", "medium");
+        conn.execute(
+            "UPDATE tasks SET due_at = ?1, recurrence_type = 'daily', recurrence_end_date = ?2 WHERE id = ?3",
+            params![at(1), at(2) + 3600, course],
+        ).unwrap();
+        toggle_complete_internal(&conn, &course).unwrap();
+        let (second, due) = next(&course).unwrap();
+        assert_eq!(due, at(2));
+        toggle_complete_internal(&conn, &second).unwrap();
+        assert!(next(&second).is_none());
+        
+        // Clearing the rule leaves a plain weekly recurrence
+        let clear: UpdateTaskInput = serde_json::from_value(serde_json::json!({
+            "recurrence_days_of_week": [],
+            "recurrence_count": 0,
+        })).unwrap();
+        let task = update_task_internal(&conn, &third, clear).unwrap();
+        assert_eq!((task.recurrence_days_of_week, task.recurrence_count), (None, None));
+        toggle_complete_internal(&conn, &third).unwrap();
+        toggle_complete_internal(&conn, &third).unwrap();
+        let (_, due) = next(&third).unwrap();
+        assert_eq!(due, at(12));
+        
+        assert!(update_task_internal(&conn, &third, serde_json::from_value(serde_json::json!({
+            "recurrence_days_of_week": [8],
+        })).unwrap()).is_err());
+    }
+    
+    #[test]
+    fn test_translation_overrides_round_trip_through_files() {
+        let source = setup_test_conn();
+        source.execute_batch(include_str!("../migrations/0009_add_translations.sql")).unwrap();
+        translation_service::save_translation(&source, "Pay rent", "en", "fr", "Payer le loyer", "title", None, true).unwrap();
+        translation_service::save_translation(&source, "Pay rent", "en", "es", "Pagar renta", "title", None, false).unwrap();
+        
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("overrides.json");
+        let exported = export_translation_overrides_internal(&source, &path).unwrap();
+        assert_eq!(exported.exported, 1);
+        
+        let target = setup_test_conn();
+        target.execute_batch(include_str!("../migrations/0009_add_translations.sql")).unwrap();
+        let imported = import_translation_overrides_internal(&target, &path).unwrap();
+        assert_eq!((imported.added, imported.updated), (1, 0));
+        let cached = translation_service::get_cached_translation(&target, &translation_service::hash_text("Pay rent"), "en", "fr", "title").unwrap();
+        assert_eq!(cached.as_deref(), Some("Payer le loyer"));
+        
+        // The same document embedded in a full export is picked up by both imports
+        let mut export = build_export_value(&source).unwrap();
+        export["translation_overrides"] = serde_json::to_value(translation_service::get_translation_overrides(&source).unwrap()).unwrap();
+        source.execute("UPDATE translations SET translated_text = 'Régler le loyer'", []).unwrap();
+        let summary = import_export_value(&target, &export).unwrap();
+        assert_eq!(summary.translation_overrides_imported, 0);
+        std::fs::write(&path, serde_json::to_string(&export).unwrap()).unwrap();
+        let imported = import_translation_overrides_internal(&target, &path).unwrap();
+        assert_eq!(imported.unchanged, 1);
+        assert!(import_translation_overrides_internal(&target, &dir.path().join("missing.json")).is_err());
+    }
+    
+    #[test]
+    fn test_content_filters_and_empty_task_cleanup() {
+        let conn = setup_test_conn();
+        let plain = insert_task(&conn, "Plain", "low");
+        let described = insert_task(&conn, "Described", "low");
+        conn.execute("UPDATE tasks SET description = 'Details' WHERE id = ?1", params![described]).unwrap();
+        conn.execute("UPDATE tasks SET description = '   ' WHERE id = ?1", params![plain]).unwrap();
+        let with_subtask = insert_task(&conn, "Checklist", "low");
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title) VALUES ('s1', ?1, 'Step')",
+            params![with_subtask],
+        ).unwrap();
+        let with_file = insert_task(&conn, "Scan", "low");
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, filename, path, created_at) VALUES ('a1', ?1, 'scan.pdf', 'scan.pdf', 0)",
+            params![with_file],
+        ).unwrap();
+        
+        let ids = |filter: TaskFilter| -> Vec<String> {
+            let mut ids: Vec<String> = get_tasks_internal(&conn, Some(&filter), false).unwrap().into_iter().map(|t| t.id).collect();
+            ids.sort();
+            ids
+        };
+        let sorted = |mut ids: Vec<String>| { ids.sort(); ids };
+        assert_eq!(ids(TaskFilter { has_description: Some(true), ..TaskFilter::default() }), vec![described.clone()]);
+        assert_eq!(ids(TaskFilter { has_subtasks: Some(true), ..TaskFilter::default() }), vec![with_subtask.clone()]);
+        assert_eq!(ids(TaskFilter { has_attachments: Some(true), ..TaskFilter::default() }), vec![with_file.clone()]);
+        assert_eq!(
+            ids(TaskFilter { has_description: Some(false), has_subtasks: Some(false), has_attachments: Some(false), ..TaskFilter::default() }),
+            vec![plain.clone()],
+        );
+        
+        let blank = insert_task(&conn, " \t", "low");
+        let placeholder = insert_task(&conn, "Untitled Task", "low");
+        let empty = find_empty_tasks_internal(&conn).unwrap();
+        assert_eq!(sorted(empty.into_iter().map(|t| t.id).collect()), sorted(vec![blank.clone(), placeholder.clone()]));
+        
+        // Ids that aren't empty tasks are ignored
+        assert_eq!(empty_task_ids(&conn, Some(&[plain.clone(), blank.clone()])).unwrap(), vec![blank]);
+        assert!(empty_task_ids(&conn, Some(&[])).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_skipped_instance_reminder_is_not_collected() {
+        let conn = setup_test_conn();
+        let now = now();
+        let root = insert_task(&conn, "Stretch", "low");
+        let archived = insert_task(&conn, "Old errand", "low");
+        conn.execute("UPDATE tasks SET recurrence_type = 'daily', due_at = ?1 WHERE id = ?2", params![now - 60, root]).unwrap();
+        conn.execute("UPDATE tasks SET archived = 1 WHERE id = ?1", params![archived]).unwrap();
+        conn.execute(
+            "INSERT INTO notification_schedule (id, task_id, scheduled_at, created_at, kind) VALUES
+                ('n1', ?1, ?3, 0, 'reminder'), ('n2', ?1, ?3, 0, 'follow_up'), ('n3', ?2, ?3, 0, 'reminder')",
+            params![root, archived, now - 30],
+        ).unwrap();
+        
+        skip_recurring_instance_internal(&conn, &root).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM notification_schedule WHERE task_id = ?1", params![root], |row| row.get(0)).unwrap();
+        assert_eq!(left, 0);
+        // Archived tasks never fire, even with a reminder row left over
+        let due = crate::notifications::collect_due_reminders(&conn, now, None).unwrap();
+        assert!(due.fresh.is_empty() && due.missed.is_empty());
+    }
+    
+    #[test]
+    fn test_skip_and_update_recurring_series() {
+        let conn = setup_test_conn();
+        let root = insert_task(&conn, "Water plants", "low");
+        conn.execute("UPDATE tasks SET recurrence_type = 'daily', due_at = 86400 WHERE id = ?1", params![root]).unwrap();
+        let xp = |conn: &rusqlite::Connection| get_user_progress_internal(conn).unwrap().total_xp;
+        let xp_before = xp(&conn);
+        
+        let second = skip_recurring_instance_internal(&conn, &root).unwrap().unwrap();
+        assert_eq!(second.due_date, Some(2 * 86400));
+        let skipped = fetch_task(&conn, &root).unwrap();
+        assert!(!skipped.completed);
+        assert_eq!(xp(&conn), xp_before);
+        assert!(get_tasks_internal(&conn, None, false).unwrap().iter().all(|t| t.id != root));
+        assert!(skip_recurring_instance_internal(&conn, &root).is_err());
+        
+        toggle_complete_internal(&conn, &second.id).unwrap();
+        let third: String = conn.query_row("SELECT id FROM tasks WHERE recurrence_parent_id = ?1", params![second.id], |row| row.get(0)).unwrap();
+        
+        // Only the open instance changes; the skipped and the completed ones stay as they were
+        let updated = update_recurring_series_internal(&conn, &second.id, UpdateSeriesInput {
+            title: Some("Water the plants".to_string()),
+            priority: Some("high".to_string()),
+            recurrence_interval: Some(2),
+            ..UpdateSeriesInput::default()
+        }).unwrap();
+        assert_eq!(updated.iter().map(|t| t.id.clone()).collect::<Vec<_>>(), vec![third.clone()]);
+        assert_eq!((updated[0].title.as_str(), updated[0].priority.as_str(), updated[0].recurrence_interval), ("Water the plants", "high", 2));
+        for id in [&root, &second.id] {
+            assert_eq!(fetch_task(&conn, id).unwrap().title, "Water plants");
+        }
+        
+        // The next instance follows the new settings
+        let fourth = skip_recurring_instance_internal(&conn, &third).unwrap().unwrap();
+        assert_eq!((fourth.title.as_str(), fourth.due_date), ("Water the plants", Some(5 * 86400)));
+        
+        let one_off = insert_task(&conn, "Repot cactus", "low");
+        assert_eq!(skip_recurring_instance_internal(&conn, &one_off).unwrap_err(), "Task is not recurring");
+    }
+    
+    #[test]
+    fn test_description_limit_and_truncation() {
+        let conn = setup_test_conn();
+        conn.execute("INSERT INTO settings (key, value) VALUES ('max_description_length', '100')", []).unwrap();
+        let task = insert_task(&conn, "Meeting notes", "medium");
+        let long: String = "Notizen über das Treffen. ".repeat(12);
+        assert_eq!(long.chars().count(), 312);
+        
+        let edit = |description: &str| -> UpdateTaskInput {
+            serde_json::from_value(serde_json::json!({ "description": description })).unwrap()
+        };
+        let err = update_task_internal(&conn, &task, edit(&long)).unwrap_err();
+        assert!(err.starts_with("Description is 312 characters long; the limit is 100"), "{}", err);
+        update_task_internal(&conn, &task, edit(&long[..long.char_indices().nth(100).unwrap().0])).unwrap();
+        
+        // Rows written before the limit existed are cut by the maintenance command
+        conn.execute("UPDATE tasks SET description = ?1 WHERE id = ?2", params![long, task]).unwrap();
+        let filter = TaskFilter { description_preview_only: Some(true), ..TaskFilter::default() };
+        let preview = get_tasks_internal(&conn, Some(&filter), false).unwrap().remove(0).description.unwrap();
+        assert_eq!(preview.chars().count(), 300);
+        assert!(long.starts_with(&preview));
+        
+        let dir = tempfile::TempDir::new().unwrap();
+        let dry_run = truncate_long_descriptions_internal(&conn, dir.path(), true).unwrap();
+        assert_eq!((dry_run.len(), dry_run[0].original_length, dry_run[0].attachment_id.is_none()), (1, 312, true));
+        assert_eq!(fetch_task(&conn, &task).unwrap().description.as_deref(), Some(long.as_str()));
+        
+        let report = truncate_long_descriptions_internal(&conn, dir.path(), false).unwrap();
+        let description = fetch_task(&conn, &task).unwrap().description.unwrap();
+        assert_eq!(description.chars().count(), 100);
+        assert!(description.ends_with("[continued in attachment]"));
+        let path: String = conn.query_row(
+            "SELECT path FROM attachments WHERE id = ?1",
+            params![report[0].attachment_id.as_deref().unwrap()],
+            |row| row.get(0),
+        ).unwrap();
+        let overflow = std::fs::read_to_string(dir.path().join(path)).unwrap();
+        assert_eq!(overflow.chars().count(), report[0].moved_length);
+        assert_eq!(format!("{}{}", description.strip_suffix(DESCRIPTION_CONTINUED_MARKER).unwrap(), overflow), long);
+        assert!(truncate_long_descriptions_internal(&conn, dir.path(), false).unwrap().is_empty());
+        
+        // Imports and copies are held to the same limit, and a rejected import adds nothing
+        let mut imported = serde_json::to_value(fetch_task(&conn, &task).unwrap()).unwrap();
+        imported["id"] = "imported".into();
+        imported["title"] = "Pasted notes".into();
+        imported["description"] = long.clone().into();
+        let export = serde_json::json!({ "tasks": [imported] });
+        let err = import_export_value(&conn, &export).unwrap_err();
+        assert!(err.starts_with("Task \"Pasted notes\": Description is 312 characters long"), "{}", err);
+        let mapped = import_service::MappedTask {
+            title: "Pasted notes".to_string(),
+            description: Some(long.clone()),
+            due_at: None,
+            priority: "low".to_string(),
+            tags: Vec::new(),
+            project: None,
+            completed_at: None,
+        };
+        assert!(insert_imported_tasks(&conn, &[mapped]).is_err());
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        conn.execute("UPDATE tasks SET description = ?1 WHERE id = ?2", params![long, task]).unwrap();
+        assert!(duplicate_task_internal(&conn, dir.path(), &task, None, &DuplicateTaskOptions::default()).is_err());
+    }
+    
+    #[test]
+    fn test_time_estimates_are_stored_and_listed() {
+        let conn = setup_test_conn();
+        let id = insert_task(&conn, "Write report", "high");
+        let edit = |value: serde_json::Value| {
+            update_task_internal(&conn, &id, serde_json::from_value(value).unwrap())
+        };
+        
+        let task = edit(serde_json::json!({ "estimated_minutes": 60, "actual_minutes": 75 })).unwrap();
+        assert_eq!((task.estimated_minutes, task.actual_minutes), (Some(60), Some(75)));
+        let listed = get_tasks_internal(&conn, None, false).unwrap();
+        assert_eq!(listed[0].estimated_minutes, Some(60));
+        let rows = stats_service::get_time_estimation_accuracy(&conn).unwrap();
+        assert_eq!(rows[0].deviation_percent, 25.0);
+        
+        assert!(edit(serde_json::json!({ "actual_minutes": -5 })).is_err());
+        let task = edit(serde_json::json!({ "actual_minutes": 0 })).unwrap();
+        assert_eq!((task.estimated_minutes, task.actual_minutes), (Some(60), None));
+        assert!(stats_service::get_time_estimation_accuracy(&conn).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_activity_log_records_task_changes() {
+        let conn = setup_test_conn();
+        let id = insert_task(&conn, "Draft budget", "low");
+        let edit: UpdateTaskInput = serde_json::from_value(serde_json::json!({
+            "title": "Draft Q3 budget",
+            "priority": "high",
+            "description": null,
+        })).unwrap();
+        update_task_internal(&conn, &id, edit).unwrap();
+        toggle_complete_internal(&conn, &id).unwrap();
+        toggle_complete_internal(&conn, &id).unwrap();
+        
+        let log = get_task_activity_log_internal(&conn, &id, None).unwrap();
+        let actions: Vec<(&str, Option<&str>, Option<&str>)> = log
+            .iter()
+            .map(|entry| (entry.action.as_str(), entry.old_value.as_deref(), entry.new_value.as_deref()))
+            .collect();
+        // Newest first; unchanged fields aren't logged
+        assert_eq!(actions, vec![
+            ("reopened", None, None),
+            ("completed", None, None),
+            ("priority_changed", Some("low"), Some("high")),
+            ("title_changed", Some("Draft budget"), Some("Draft Q3 budget")),
+        ]);
+        assert_eq!(get_task_activity_log_internal(&conn, &id, Some(1)).unwrap()[0].action, "reopened");
+    }
+    
+    #[test]
+    fn test_import_tasks_from_table_with_mapping() {
+        let conn = setup_test_conn();
+        conn.execute("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Home', 0, 0)", []).unwrap();
+        let table = import_service::read_table(
+            "When,What,Where,Labels\n2024-05-01,Pay rent,home,bills\n,Book flights,Travel,\"trips, summer\"\n2024-05-02,,Travel,\n",
+            "csv",
+            Vec::new(),
+        ).unwrap();
+        
+        // Nothing in the header looks like a title, so the mapping has to be given
+        assert!(import_tasks_from_table(&conn, &table, &import_service::propose_mapping(&table.columns)).is_err());
+        let mapping = import_service::ImportMapping {
+            title: Some("What".to_string()),
+            due: Some("When".to_string()),
+            project: Some("Where".to_string()),
+            tags: Some("Labels".to_string()),
+            ..Default::default()
+        };
+        let summary = import_tasks_from_table(&conn, &table, &mapping).unwrap();
+        assert_eq!((summary.imported, summary.skipped, summary.projects_created), (2, 1, 1));
+        
+        let rent = query_tasks(&conn, "title = ?1", "id", &[&"Pay rent"]).unwrap().remove(0);
+        assert_eq!(rent.project_id.as_deref(), Some("p1"));
+        assert!(rent.due_date.is_some());
+        let flights = fetch_task(&conn, &query_tasks(&conn, "title = ?1", "id", &[&"Book flights"]).unwrap()[0].id).unwrap();
+        let mut tags: Vec<String> = flights.tags.unwrap().into_iter().map(|tag| tag.name).collect();
+        tags.sort();
+        assert_eq!(tags, vec!["summer", "trips"]);
+    }
+    
+    #[test]
+    fn test_export_tasks_resolve_project_and_tags() {
+        let conn = setup_test_conn();
+        conn.execute("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Home', 0, 0)", []).unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, title, description, created_at, updated_at, priority, project_id, order_index)
+            VALUES ('t1', 'Fix sink', 'Washer, \"the small one\"', 0, 0, 'high', 'p1', 0)",
+            [],
+        ).unwrap();
+        attach_tag_by_name(&conn, "t1", "plumbing").unwrap();
+        attach_tag_by_name(&conn, "t1", "diy").unwrap();
+        
+        let tasks = export_service::load_tasks(&conn).unwrap();
+        assert_eq!(tasks[0].project_name.as_deref(), Some("Home"));
+        assert_eq!(tasks[0].tags, vec!["diy", "plumbing"]);
+        let csv = export_service::render_csv(&tasks);
+        assert!(csv.contains("t1,Fix sink,\"Washer, \"\"the small one\"\"\",Home,\"diy, plumbing\",high,"));
+    }
+    
+    #[test]
+    fn test_import_external_todoist_creates_project_and_tags() {
+        let conn = setup_test_conn();
+        attach_tag_by_name(&conn, &insert_task(&conn, "Existing", "low"), "paint").unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("Home Renovation.csv");
+        std::fs::write(
+            &path,
+            "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE\n\
+            task,Sand the door @paint @weekend,,3,1,Me,,,en,UTC\n\
+            task,,,1,1,Me,,,en,UTC\n\
+            task,Buy primer @paint,,4,1,Me,,2024-06-01,en,UTC\n",
+        ).unwrap();
+        
+        let summary = import_external_internal(&conn, &path, external_import_service::ExternalSource::TodoistCsv).unwrap();
+        assert_eq!((summary.imported, summary.projects_created, summary.tags_created), (2, 1, 1));
+        assert_eq!(summary.failed_rows[0].row, 3);
+        let primer = query_tasks(&conn, "title = ?1", "id", &[&"Buy primer"]).unwrap().remove(0);
+        let project: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", params![primer.project_id], |row| row.get(0)).unwrap();
+        assert_eq!((project.as_str(), primer.priority.as_str()), ("Home Renovation", "high"));
+        
+        // The same export again goes into the same project
+        let again = import_external_internal(&conn, &path, external_import_service::ExternalSource::TodoistCsv).unwrap();
+        assert_eq!((again.projects_created, again.tags_created), (0, 0));
+        assert!(import_external_internal(&conn, &path, external_import_service::ExternalSource::TicktickCsv).is_err());
+    }
+    
+    #[test]
+    fn test_csv_export_tasks_follow_filter() {
+        let conn = setup_test_conn();
+        let open = insert_task(&conn, "Open", "high");
+        let done = insert_task(&conn, "Done", "low");
+        conn.execute("UPDATE tasks SET completed_at = ?1 WHERE id = ?2", params![now(), done]).unwrap();
+
+        assert_eq!(load_csv_export_tasks(&conn, None).unwrap().len(), 2);
+        let filter = TaskFilter { completed: Some(false), ..Default::default() };
+        let tasks = load_csv_export_tasks(&conn, Some(&filter)).unwrap();
+        assert_eq!(tasks.iter().map(|task| task.id.as_str()).collect::<Vec<_>>(), vec![open.as_str()]);
+    }
+
+    #[test]
+    fn test_bulk_delete_skips_missing_tasks() {
+        let conn = setup_test_conn();
+        let first = insert_task(&conn, "First", "low");
+        let second = insert_task(&conn, "Second", "low");
+        let kept = insert_task(&conn, "Kept", "low");
+        conn.execute("INSERT INTO tags (id, name, created_at, usage_count) VALUES ('t1', 'errand', 0, 2)", []).unwrap();
+        conn.execute("INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES ('a', ?1, 't1', 0), ('b', ?2, 't1', 0)", params![first, kept]).unwrap();
+        
+        let deleted = bulk_delete_tasks_internal(&conn, &[first, "missing".to_string(), second]).unwrap();
+        assert_eq!(deleted, 2);
+        let remaining: Vec<String> = conn.prepare("SELECT id FROM tasks").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(remaining, vec![kept]);
+        let usage: i64 = conn.query_row("SELECT usage_count FROM tags WHERE id = 't1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(usage, 1);
+    }
+    
+    #[test]
+    fn test_cancelled_import_rolls_back() {
+        // Records each stage and asks to stop once the tasks stage is reached
+        struct CancelAtTasks(Vec<(String, usize)>);
+        impl crate::operations::ProgressReporter for CancelAtTasks {
+            fn stage(&mut self, stage: &str, total: usize) -> Result<(), String> {
+                if stage == "tasks" {
+                    return Err(crate::operations::OPERATION_CANCELLED_ERROR.to_string());
+                }
+                self.0.push((stage.to_string(), total));
+                Ok(())
+            }
+            fn progress(&mut self, _done: usize) {}
+            fn cancelled(&self) -> bool {
+                false
+            }
+        }
+        
+        let conn = setup_test_conn();
+        let export = serde_json::json!({
+            "projects": [{ "id": "p1", "name": "Imported", "color": null, "created_at": 1, "updated_at": 1 }],
+            "tasks": [],
+        });
+        let mut reporter = CancelAtTasks(Vec::new());
+        let result = import_export_value_with_progress(&conn, &export, &mut reporter);
+        
+        assert_eq!(result.unwrap_err(), crate::operations::OPERATION_CANCELLED_ERROR);
+        assert_eq!(reporter.0, vec![("projects".to_string(), 1)]);
+        let projects: i64 = conn.query_row("SELECT COUNT(*) FROM projects", [], |row| row.get(0)).unwrap();
+        assert_eq!(projects, 0);
+    }
+    
+    #[test]
+    fn test_import_updates_existing_subtask_in_place() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Plan trip", "low");
+        conn.execute(
+            "INSERT INTO subtasks (id, task_id, title, completed, order_index) VALUES ('s1', ?1, 'Book flights', 0, 3)",
+            params![task],
+        ).unwrap();
+        let export = serde_json::json!({
+            "subtasks": [
+                { "id": "s1", "task_id": task, "title": "Book hotel", "completed": true },
+                { "id": "s2", "task_id": task, "title": "Pack", "completed": false },
+            ],
+        });
+        
+        import_export_value(&conn, &export).unwrap();
+        
+        let (title, completed, order_index): (String, bool, i64) = conn.query_row(
+            "SELECT title, completed, order_index FROM subtasks WHERE id = 's1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert_eq!((title.as_str(), completed, order_index), ("Book hotel", true, 3));
+        let new_order: i64 = conn.query_row("SELECT order_index FROM subtasks WHERE id = 's2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(new_order, 4);
+        let fts_matches = |term: &str| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM subtasks_fts WHERE subtasks_fts MATCH ?1", params![term], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(fts_matches("flights"), 0);
+        assert_eq!(fts_matches("hotel"), 1);
+    }
+    
+    #[test]
+    fn test_export_import_round_trip_keeps_tags_relationships_and_templates() {
+        let source = setup_test_conn();
+        source.execute("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Home', 0, 0)", []).unwrap();
+        let first = insert_task(&source, "Buy paint", "low");
+        let second = insert_task(&source, "Paint fence", "low");
+        attach_tag_by_name(&source, &first, "diy").unwrap();
+        attach_tag_by_name(&source, &second, "diy").unwrap();
+        source.execute(
+            "INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at) VALUES ('r1', ?1, ?2, 'blocks', 5)",
+            params![first, second],
+        ).unwrap();
+        source.execute(
+            "INSERT INTO task_templates (id, name, title, priority, project_id, scope_project_id, created_at, updated_at)
+             VALUES ('tpl1', 'Chore', 'Weekly chore', 'high', 'p1', 'p1', 1, 1)",
+            [],
+        ).unwrap();
+        let export = build_export_value(&source).unwrap();
+        
+        let target = setup_test_conn();
+        let summary = import_export_value(&target, &export).unwrap();
+        assert_eq!((summary.tags_added, summary.task_tags_added), (1, 2));
+        assert_eq!(summary.task_relationships_added, 1);
+        assert_eq!((summary.templates_added, summary.templates_updated), (1, 0));
+        let blocks: String = target.query_row(
+            "SELECT relationship_type FROM task_relationships WHERE task_id_1 = ?1 AND task_id_2 = ?2",
+            params![first, second],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(blocks, "blocks");
+        let template = fetch_template(&target, "tpl1").unwrap();
+        assert_eq!((template.title.as_str(), template.scope_project_id.as_deref()), ("Weekly chore", Some("p1")));
+        
+        // Again: templates are updated in place, links already there are left alone
+        let again = import_export_value(&target, &export).unwrap();
+        assert_eq!((again.task_tags_added, again.task_relationships_added), (0, 0));
+        assert_eq!((again.templates_added, again.templates_updated), (0, 1));
+        
+        // An export from before these sections existed still imports
+        let mut old = export.clone();
+        for key in ["task_relationships", "templates"] {
+            old.as_object_mut().unwrap().remove(key);
+        }
+        let fresh = setup_test_conn();
+        let summary = import_export_value(&fresh, &old).unwrap();
+        assert_eq!((summary.tasks_added, summary.task_relationships_added, summary.templates_added), (2, 0, 0));
+    }
+    
+    #[test]
+    fn test_export_import_round_trip_keeps_progress() {
+        let conn = setup_test_conn();
+        let task_ids: Vec<String> = (0..12)
+            .map(|i| insert_task(&conn, &format!("Task {}", i), "high"))
+            .collect();
+        bulk_complete_tasks_internal(&conn, &task_ids).unwrap();
+        conn.execute(
+            "UPDATE user_progress SET current_streak = 6, longest_streak = 9, last_completion_date = 1700000000 WHERE id = 'default'",
+            [],
+        ).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pomodoro_sessions (id, task_id, started_at, completed_at, duration_seconds, created_at)
+             VALUES ('session-1', NULL, 1700000000, 1700001500, 1500, 1700001500);
+             INSERT INTO pomodoro_streaks (id, user_id, current_streak, longest_streak, last_session_date, created_at, updated_at)
+             VALUES ('default', 'default', 4, 7, 1700000000, 1700000000, 1700000000);"
+        ).unwrap();
+        
+        let before = get_user_progress_internal(&conn).unwrap();
+        let badge_count = |conn: &rusqlite::Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM badges", [], |row| row.get(0)).unwrap()
+        };
+        let badges_before = badge_count(&conn);
+        let export = build_export_value(&conn).unwrap();
+        
+        conn.execute_batch(
+            "DELETE FROM tasks; DELETE FROM user_progress; DELETE FROM badges; DELETE FROM xp_history;
+             DELETE FROM pomodoro_sessions; DELETE FROM pomodoro_streaks;"
+        ).unwrap();
+        
+        let summary = import_export_value(&conn, &export).unwrap();
+        assert_eq!(summary.tasks_added, 12);
+        assert_eq!(summary.badges_added as i64, badges_before);
+        assert_eq!(summary.xp_history_added, 12);
+        assert_eq!(summary.pomodoro_sessions_added, 1);
+        assert_eq!(summary.user_progress_updated, 1);
+        assert_eq!(summary.pomodoro_streaks_updated, 1);
+        
+        let after = get_user_progress_internal(&conn).unwrap();
+        assert_eq!(after.total_xp, before.total_xp);
+        assert_eq!(after.current_level, before.current_level);
+        assert_eq!(after.current_streak, 6);
+        assert_eq!(after.longest_streak, 9);
+        
+        let streak = pomodoro_service::get_pomodoro_streak(&conn).unwrap();
+        assert_eq!((streak.current_streak, streak.longest_streak), (4, 7));
+        
+        // Importing the same file again merges instead of duplicating
+        let again = import_export_value(&conn, &export).unwrap();
+        assert_eq!(again.badges_added, 0);
+        assert_eq!(again.xp_history_added, 0);
+        assert_eq!(again.pomodoro_sessions_added, 0);
+        assert_eq!(badge_count(&conn), badges_before);
+        assert_eq!(get_user_progress_internal(&conn).unwrap().total_xp, before.total_xp);
+    }
+    
+    #[test]
+    fn test_toggle_complete_detailed_reports_celebration_data() {
+        let conn = setup_test_conn();
+        let first = insert_task(&conn, "First", "medium");
+        let second = insert_task(&conn, "Second", "high");
+        
+        let result = toggle_complete_internal(&conn, &first).unwrap();
+        assert!(result.completed);
+        assert_eq!(result.xp_granted, 25);
+        assert!(result.streak_extended);
+        assert_eq!(result.current_streak, 1);
+        assert!(result.first_completion_today);
+        assert!(result.new_badges.iter().any(|b| b.badge_type == "first_task"));
+        assert!(result.task.completed);
+        
+        let result = toggle_complete_internal(&conn, &second).unwrap();
+        assert_eq!(result.xp_granted, 50);
+        assert!(!result.streak_extended);
+        assert!(!result.first_completion_today);
+        assert!(result.new_badges.is_empty());
+        
+        let result = toggle_complete_internal(&conn, &second).unwrap();
+        assert!(!result.completed);
+        assert_eq!((result.xp_granted, result.xp_revoked), (0, 50));
+        assert_eq!(result.total_xp, 25);
+    }
+    
+    #[test]
+    fn test_populate_block_status() {
+        let conn = setup_test_conn();
+        let blocked = insert_task(&conn, "Ship release", "high");
+        let open_blocker = insert_task(&conn, "Fix regression", "high");
+        let done_blocker = insert_task(&conn, "Write changelog", "low");
+        let free = insert_task(&conn, "Tidy desk", "low");
+        conn.execute_batch(&format!(
+            "UPDATE tasks SET completed_at = 100 WHERE id = '{done_blocker}';
+             INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at) VALUES
+                ('r1', '{open_blocker}', '{blocked}', 'blocks', 0),
+                ('r2', '{done_blocker}', '{blocked}', 'blocks', 0),
+                ('r3', '{free}', '{blocked}', 'related', 0);"
+        )).unwrap();
+        
+        let mut tasks: Vec<Task> = [&blocked, &free].iter().map(|id| fetch_task(&conn, id).unwrap()).collect();
+        assert!(tasks[0].is_blocked.is_none());
+        populate_block_status(&conn, &mut tasks).unwrap();
+        assert_eq!((tasks[0].is_blocked, tasks[0].blocker_count), (Some(true), Some(1)));
+        assert_eq!((tasks[1].is_blocked, tasks[1].blocker_count), (Some(false), Some(0)));
+        
+        conn.execute("UPDATE tasks SET completed_at = 200 WHERE id = ?1", params![open_blocker]).unwrap();
+        populate_block_status(&conn, &mut tasks).unwrap();
+        assert_eq!(tasks[0].is_blocked, Some(false));
+    }
+    
+    #[test]
+    fn test_dependency_conflicts_and_fixes() {
+        let conn = setup_test_conn();
+        let blocker = insert_task(&conn, "Write spec", "medium");
+        let blocked = insert_task(&conn, "Build feature", "medium");
+        let undated_blocker = insert_task(&conn, "Get approval", "low");
+        let fine_blocker = insert_task(&conn, "Research", "low");
+        let base = now() + 30 * 86400;
+        conn.execute_batch(&format!(
+            "UPDATE tasks SET due_at = {base} + 2000000 WHERE id = '{blocker}';
+             UPDATE tasks SET due_at = {base} + 1000000 WHERE id = '{blocked}';
+             UPDATE tasks SET due_at = {base} + 900000 WHERE id = '{fine_blocker}';
+             UPDATE tasks SET reminder_minutes_before = 30 WHERE id IN ('{blocker}', '{blocked}');
+             INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at) VALUES
+                ('late', '{blocker}', '{blocked}', 'blocks', 0),
+                ('undated', '{undated_blocker}', '{blocked}', 'blocks', 0),
+                ('ok', '{fine_blocker}', '{blocked}', 'blocks', 0),
+                ('related', '{blocked}', '{blocker}', 'related', 0);"
+        )).unwrap();
+        
+        let mut ids: Vec<String> = get_dependency_conflicts_internal(&conn).unwrap()
+            .into_iter().map(|c| c.relationship_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["late", "undated"]);
+        for id in [&blocker, &blocked] {
+            crate::notifications::reschedule_reminder(&conn, id, Some(30), now()).unwrap();
+        }
+        let reminders = |id: &str| -> Vec<i64> {
+            conn.prepare("SELECT scheduled_at FROM notification_schedule WHERE task_id = ?1 AND kind = 'reminder'").unwrap()
+                .query_map(params![id], |row| row.get(0)).unwrap()
+                .collect::<Result<_, _>>().unwrap()
+        };
+        
+        let task = fix_dependency_conflict_internal(&conn, "late", "move_blocker_earlier").unwrap();
+        assert_eq!(task.id, blocker);
+        assert_eq!(task.due_date, Some(base + 1000000 - 24 * 3600));
+        // The reminder follows the new due date
+        assert_eq!(reminders(&blocker), vec![base + 1000000 - 24 * 3600 - 30 * 60]);
+        let logged: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_log WHERE task_id = ?1 AND source = 'dependency_fix'",
+            params![blocker],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(logged, 1);
+        
+        assert!(fix_dependency_conflict_internal(&conn, "undated", "bogus").is_err());
+        let task = fix_dependency_conflict_internal(&conn, "undated", "clear_blocked_due_date").unwrap();
+        assert_eq!(task.due_date, None);
+        assert!(reminders(&blocked).is_empty());
+        assert!(get_dependency_conflicts_internal(&conn).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_vacation_gap_keeps_streak() {
+        let conn = setup_test_conn();
+        let today_start = (now() / 86400) * 86400;
+        get_user_progress_internal(&conn).unwrap();
+        // Last completion four days ago, vacation covering the three days in between
+        conn.execute(
+            "UPDATE user_progress SET current_streak = 5, longest_streak = 5, last_completion_date = ?1 WHERE id = 'default'",
+            params![today_start - 4 * 86400],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO vacation_periods (id, start_date, end_date, created_at) VALUES ('v1', ?1, ?2, 0)",
+            params![today_start - 3 * 86400, today_start - 86400],
+        ).unwrap();
+        
+        let task = insert_task(&conn, "Back to work", "low");
+        let result = toggle_complete_internal(&conn, &task).unwrap();
+        assert_eq!(result.current_streak, 6);
+        assert!(result.streak_extended);
+        
+        // Completing on a vacation day leaves the streak alone
+        conn.execute(
+            "UPDATE vacation_periods SET end_date = ?1 WHERE id = 'v1'",
+            params![today_start],
+        ).unwrap();
+        let task = insert_task(&conn, "Holiday chore", "low");
+        let result = toggle_complete_internal(&conn, &task).unwrap();
+        assert_eq!(result.current_streak, 6);
+        assert!(!result.streak_extended);
+    }
+    
+    #[test]
+    fn test_streak_broken_before_vacation_resets_on_vacation_day() {
+        let conn = setup_test_conn();
+        let today_start = (now() / 86400) * 86400;
+        get_user_progress_internal(&conn).unwrap();
+        // Last completion five days ago; the vacation only started yesterday
+        conn.execute(
+            "UPDATE user_progress SET current_streak = 5, longest_streak = 5, last_completion_date = ?1 WHERE id = 'default'",
+            params![today_start - 5 * 86400],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO vacation_periods (id, start_date, end_date, created_at) VALUES ('v1', ?1, ?2, 0)",
+            params![today_start - 86400, today_start + 86400],
+        ).unwrap();
+        
+        let task = insert_task(&conn, "Holiday chore", "low");
+        let result = toggle_complete_internal(&conn, &task).unwrap();
+        assert_eq!(result.current_streak, 0);
+        assert!(!result.streak_extended);
+        let progress = get_user_progress_internal(&conn).unwrap();
+        assert_eq!((progress.longest_streak, progress.last_completion_date), (5, Some(today_start)));
+    }
+    
+    #[test]
+    fn test_add_subtasks_bulk() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Party", "medium");
+        
+        let created = add_subtasks_bulk_internal(&conn, &task, "- book venue\n\n- send invites\n  * order cake  \n-\n").unwrap();
+        let titles: Vec<&str> = created.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["book venue", "send invites", "order cake"]);
+        
+        // A second paste continues after the existing ones
+        add_subtasks_bulk_internal(&conn, &task, "1. clean up").unwrap();
+        let ordered: Vec<String> = conn
+            .prepare("SELECT title FROM subtasks WHERE task_id = ?1 ORDER BY order_index").unwrap()
+            .query_map(params![task], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(ordered, vec!["book venue", "send invites", "order cake", "clean up"]);
+        
+        let too_many = (0..103).map(|i| format!("- item {}", i)).collect::<Vec<_>>().join("\n");
+        let err = add_subtasks_bulk_internal(&conn, &task, &too_many).unwrap_err();
+        assert!(err.contains("3 over the limit"));
+        assert!(add_subtasks_bulk_internal(&conn, "missing", "- x").is_err());
+    }
+    
+    #[test]
+    fn test_current_task_cleared_on_completion() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Focus", "medium");
+        let other = insert_task(&conn, "Other", "medium");
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+            params![CURRENT_TASK_SETTING, task],
+        ).unwrap();
+        
+        assert!(!toggle_complete_internal(&conn, &other).unwrap().current_task_cleared);
+        assert_eq!(get_current_task_internal(&conn, false).unwrap().map(|t| t.id), Some(task.clone()));
+        
+        assert!(toggle_complete_internal(&conn, &task).unwrap().current_task_cleared);
+        assert!(get_current_task_internal(&conn, false).unwrap().is_none());
+        assert!(get_current_task_id(&conn).is_none());
+        
+        // Completed elsewhere: a read-only lookup (demo mode) leaves the setting for later
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, ?2)", params![CURRENT_TASK_SETTING, task]).unwrap();
+        assert!(get_current_task_internal(&conn, true).unwrap().is_none());
+        assert_eq!(get_current_task_id(&conn), Some(task.clone()));
+        assert!(get_current_task_internal(&conn, false).unwrap().is_none());
+        assert!(get_current_task_id(&conn).is_none());
+    }
+    
+    #[test]
+    fn test_completion_warnings() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Launch", "high");
+        let blocker = insert_task(&conn, "Sign-off", "high");
+        assert!(get_completion_warnings_internal(&conn, &task).unwrap().is_empty());
+        
+        add_subtasks_bulk_internal(&conn, &task, "- a\n- b").unwrap();
+        conn.execute("UPDATE subtasks SET completed = 1 WHERE title = 'a'", []).unwrap();
+        conn.execute(
+            "INSERT INTO task_relationships (id, task_id_1, task_id_2, relationship_type, created_at) VALUES ('r1', ?1, ?2, 'blocks', 0)",
+            params![blocker, task],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO notification_schedule (id, task_id, scheduled_at, created_at) VALUES ('n1', ?1, ?2, 0), ('n2', ?1, ?3, 0)",
+            params![task, now() + 3 * 86400, now() + 60],
+        ).unwrap();
+        
+        let warnings = get_completion_warnings_internal(&conn, &task).unwrap();
+        let summary: Vec<(&str, usize)> = warnings.iter().map(|w| (w.kind.as_str(), w.count)).collect();
+        assert_eq!(summary, vec![("open_subtasks", 1), ("open_blockers", 1), ("pending_reminders", 1)]);
+        assert_eq!(warnings[0].samples, vec!["b"]);
+        assert_eq!(warnings[1].samples, vec!["Sign-off"]);
+        
+        assert!(ensure_completion_acknowledged(&conn, &task, None).is_err());
+        assert!(ensure_completion_acknowledged(&conn, &task, Some(true)).is_ok());
+        // Tasks without warnings complete without acknowledgement
+        assert!(ensure_completion_acknowledged(&conn, &blocker, None).is_ok());
+    }
+    
+    #[test]
+    fn test_interleaved_updates_conflict() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Draft", "medium");
+        conn.execute("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Home', 0, 0)", []).unwrap();
+        let template = create_template_internal(&conn, CreateTemplateInput {
+            name: "Weekly".to_string(),
+            title: "Review".to_string(),
+            description: None,
+            priority: "low".to_string(),
+            project_id: None,
+            scope_project_id: None,
+            recurrence_type: None,
+        }).unwrap();
+        let conflict_of = |err: String| -> serde_json::Value {
+            let conflict: serde_json::Value = serde_json::from_str(&err).unwrap();
+            assert_eq!(conflict["kind"], EDIT_CONFLICT_KIND);
+            conflict["current"].clone()
+        };
+        
+        // Both windows loaded the task before either saved; both saves land within the same second
+        let loaded = fetch_task(&conn, &task).unwrap().updated_at;
+        let edit = |title: &str, expected_updated_at: Option<i64>| UpdateTaskInput {
+            title: Some(title.to_string()),
+            description: None,
+            due_date: None,
+            priority: None,
+            project_id: None,
+            order_index: None,
+            recurrence_type: None,
+            recurrence_interval: None,
+            reminder_minutes_before: None,
+            notification_repeat: None,
+            reminder_sound: None,
+            reminder_urgency: None,
+            completion_note_required: None,
+            expected_updated_at,
+            status: None,
+            force: None,
+            recurrence_days_of_week: None,
+            recurrence_end_date: None,
+            recurrence_count: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+        };
+        let first = update_task_internal(&conn, &task, edit("From main window", Some(loaded))).unwrap();
+        assert!(first.updated_at > loaded);
+        let current = conflict_of(update_task_internal(&conn, &task, edit("From quick add", Some(loaded))).unwrap_err());
+        assert_eq!(current["title"], "From main window");
+        assert_eq!(fetch_task(&conn, &task).unwrap().title, "From main window");
+        // Retrying against the current row, or without a version, goes through
+        update_task_internal(&conn, &task, edit("Merged", Some(first.updated_at))).unwrap();
+        assert_eq!(update_task_internal(&conn, &task, edit("Forced", None)).unwrap().title, "Forced");
+        
+        let rename = |name: &str, expected_updated_at: Option<i64>| UpdateProjectInput {
+            name: Some(name.to_string()),
+            color: None,
+            completion_note_required: None,
+            expected_updated_at,
+        };
+        update_project_internal(&conn, "p1", rename("House", Some(0))).unwrap();
+        let current = conflict_of(update_project_internal(&conn, "p1", rename("Flat", Some(0))).unwrap_err());
+        assert_eq!(current["name"], "House");
+        
+        let retitle = |title: &str, expected_updated_at: Option<i64>| UpdateTemplateInput {
+            name: None,
+            title: Some(title.to_string()),
+            description: None,
+            priority: None,
+            project_id: None,
+            scope_project_id: None,
+            recurrence_type: None,
+            expected_updated_at,
+        };
+        update_template_internal(&conn, &template.id, retitle("Weekly review", Some(template.updated_at))).unwrap();
+        let current = conflict_of(update_template_internal(&conn, &template.id, retitle("Review week", Some(template.updated_at))).unwrap_err());
+        assert_eq!(current["title"], "Weekly review");
+    }
+    
+    #[test]
+    fn test_completion_note_required() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Audit", "high");
+        let forced = insert_task(&conn, "Audit again", "high");
+        let other = insert_task(&conn, "Water plants", "low");
+        conn.execute("UPDATE tasks SET completion_note_required = 1 WHERE id IN (?1, ?2)", params![task, forced]).unwrap();
+        assert_eq!(fetch_task(&conn, &task).unwrap().completion_note_required, Some(true));
+        
+        assert_eq!(resolve_completion_note(&conn, &task, None, false).unwrap_err(), COMPLETION_NOTE_REQUIRED_ERROR);
+        assert_eq!(resolve_completion_note(&conn, &task, Some("  "), false).unwrap_err(), COMPLETION_NOTE_REQUIRED_ERROR);
+        assert_eq!(resolve_completion_note(&conn, &other, None, false).unwrap(), None);
+        
+        toggle_complete_checked(&conn, &task, None, Some(" Passed, two findings "), false).unwrap();
+        // Acknowledging the completion warnings doesn't waive the note; skip_note does
+        assert_eq!(toggle_complete_checked(&conn, &forced, Some(true), None, false).unwrap_err(), COMPLETION_NOTE_REQUIRED_ERROR);
+        assert!(toggle_complete_checked(&conn, &forced, None, None, true).unwrap().completed);
+        
+        let review = weekly_review_service::get_weekly_review(&conn, now()).unwrap();
+        let notes: Vec<&str> = review.completion_notes.iter().map(|entry| entry.note.as_str()).collect();
+        assert_eq!(notes.len(), 2);
+        assert!(notes.contains(&"Passed, two findings") && notes.contains(&NO_NOTE_MARKER));
+        let logged: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_log WHERE action = 'completion_note' AND source = 'completion'",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(logged, 2);
+        
+        // Reopening needs no note and drops the old one; bulk completion leaves the task open
+        assert_eq!(resolve_completion_note(&conn, &task, None, false).unwrap(), None);
+        toggle_complete_internal(&conn, &task).unwrap();
+        bulk_complete_tasks_internal(&conn, std::slice::from_ref(&task)).unwrap();
+        assert!(!fetch_task(&conn, &task).unwrap().completed);
+        assert_eq!(weekly_review_service::get_weekly_review(&conn, now()).unwrap().completion_notes.len(), 1);
+    }
+    
+    #[test]
+    fn test_auto_file_inbox_task() {
+        let conn = setup_test_conn();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('garden', 'Garden', 0, 0), ('work', 'Work', 0, 0);"
+        ).unwrap();
+        for (title, project) in [("Prune roses", "garden"), ("Plant roses", "garden"), ("Write report", "work")] {
+            let id = insert_task(&conn, title, "low");
+            conn.execute("UPDATE tasks SET project_id = ?1 WHERE id = ?2", params![project, id]).unwrap();
+        }
+        project_suggestion_service::rebuild_project_keywords(&conn, now()).unwrap();
+        let task = insert_task(&conn, "Water roses", "low");
+        
+        // Off unless enabled
+        assert_eq!(auto_file_task(&conn, &task, "Water roses").unwrap(), None);
+        
+        conn.execute("INSERT INTO settings (key, value) VALUES ('auto_file_inbox', 'true')", []).unwrap();
+        assert_eq!(auto_file_task(&conn, &task, "Water roses").unwrap().as_deref(), Some("garden"));
+        assert_eq!(fetch_task(&conn, &task).unwrap().project_id.as_deref(), Some("garden"));
+        let source: String = conn.query_row(
+            "SELECT source FROM activity_log WHERE task_id = ?1 AND action = 'project_changed'",
+            params![task],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(source, "auto_file");
+    }
+    
+    #[test]
+    fn test_recent_tasks_feeds() {
+        let conn = setup_test_conn();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, created_at, updated_at, completed_at) VALUES
+                ('new', 'Just created', 300, 300, NULL),
+                ('edited', 'Edited', 100, 250, NULL),
+                ('toggled', 'Only completed', 100, 200, 200),
+                ('done-edited', 'Completed then edited', 50, 280, 150);
+            INSERT INTO tags (id, name, created_at) VALUES ('t1', 'home', 0);
+            INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES ('tt1', 'edited', 't1', 0);"
+        ).unwrap();
+        
+        let ids = |kind: &str| -> Vec<String> {
+            get_recent_tasks_internal(&conn, kind, 10, 400).unwrap().into_iter().map(|r| r.task.id).collect()
+        };
+        assert_eq!(ids("completed"), vec!["toggled", "done-edited"]);
+        assert_eq!(ids("modified"), vec!["done-edited", "edited"]);
+        assert_eq!(ids("created"), vec!["new", "edited", "toggled", "done-edited"]);
+        assert!(get_recent_tasks_internal(&conn, "deleted", 10, 400).is_err());
+        
+        let modified = get_recent_tasks_internal(&conn, "modified", 10, 400).unwrap();
+        assert_eq!(modified[1].seconds_ago, 150);
+        assert_eq!(modified[1].task.tags.as_ref().unwrap()[0].name, "home");
+        assert!(modified[0].task.tags.as_ref().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_agenda_groups_day() {
+        let conn = setup_test_conn();
+        // 2024-03-10 in UTC+02:00 starts at 2024-03-09 22:00 UTC
+        let day_start = 1_710_021_600;
+        let hour = 3600;
+        conn.execute_batch(&format!(
+            "INSERT INTO tasks (id, title, created_at, updated_at, priority, due_at, completed_at, order_index) VALUES
+                ('meeting', 'Meeting', 0, 0, 'medium', {meeting}, NULL, 0),
+                ('standup', 'Standup', 0, 0, 'low', {standup}, NULL, 1),
+                ('allday-low', 'All day low', 0, 0, 'low', {day_start}, NULL, 0),
+                ('allday-high', 'All day high', 0, 0, 'high', {day_start}, NULL, 5),
+                ('late', 'Late', 0, 0, 'medium', {late}, NULL, 0),
+                ('tomorrow', 'Tomorrow', 0, 0, 'medium', {tomorrow}, NULL, 0),
+                ('done', 'Done', 0, 0, 'medium', {late}, {done_at}, 0);
+            INSERT INTO pomodoro_sessions (id, started_at, completed_at, duration_seconds, mode, created_at) VALUES
+                ('p1', 0, {done_at}, 1500, 'pomodoro', 0),
+                ('p2', 0, {done_at}, 300, 'short_break', 0),
+                ('p3', 0, {yesterday}, 1500, 'pomodoro', 0);",
+            meeting = day_start + 14 * hour,
+            standup = day_start + 9 * hour,
+            late = day_start - 30 * hour,
+            tomorrow = day_start + 24 * hour,
+            done_at = day_start + 10 * hour,
+            yesterday = day_start - hour,
+        )).unwrap();
+        
+        let agenda = get_agenda_internal(&conn, "2024-03-10", Some(120)).unwrap();
+        assert_eq!((agenda.day_start, agenda.day_end), (day_start, day_start + 24 * hour));
+        let ids = |tasks: &[Task]| tasks.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&agenda.timeline), vec!["standup", "meeting"]);
+        assert_eq!(ids(&agenda.unscheduled), vec!["allday-high", "allday-low"]);
+        assert_eq!(ids(&agenda.overdue), vec!["late"]);
+        assert_eq!(ids(&agenda.completed), vec!["done"]);
+        assert_eq!((agenda.focus_minutes, agenda.focus_sessions), (25, 1));
+        
+        assert!(get_agenda_internal(&conn, "10/03/2024", None).is_err());
+    }
+    
+    #[test]
+    fn test_tag_groups_ungroup_on_delete_and_round_trip() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Call client", "medium");
+        conn.execute_batch(&format!(
+            "INSERT INTO tags (id, name, created_at) VALUES ('t-acme', 'acme', 0), ('t-phone', 'phone', 0), ('t-misc', 'misc', 0);
+             INSERT INTO task_tags (id, task_id, tag_id, created_at) VALUES ('tt1', '{task}', 't-acme', 0);"
+        )).unwrap();
+        
+        let client = create_tag_group_internal(&conn, CreateTagGroupInput { name: "Client".to_string(), color: None }).unwrap();
+        let context = create_tag_group_internal(&conn, CreateTagGroupInput { name: " Context ".to_string(), color: None }).unwrap();
+        assert_eq!((client.order_index, context.order_index, context.name.as_str()), (0, 1, "Context"));
+        
+        let assign = |tag: &str, group: &str| {
+            update_tag_internal(&conn, tag, UpdateTagInput { name: None, color: None, group_id: Some(group.to_string()) })
+        };
+        assert_eq!(assign("t-acme", &client.id).unwrap().group_id.as_deref(), Some(client.id.as_str()));
+        assign("t-phone", &context.id).unwrap();
+        assert!(assign("t-misc", "no-such-group").is_err());
+        
+        let grouped = get_tags_grouped_internal(&conn).unwrap();
+        assert_eq!(grouped.groups[0].tags[0].name, "acme");
+        assert_eq!(grouped.groups[1].tags[0].name, "phone");
+        assert_eq!(grouped.ungrouped.len(), 1);
+        
+        let export = build_export_value(&conn).unwrap();
+        
+        // Deleting a group keeps its tags
+        delete_tag_group_internal(&conn, &context.id).unwrap();
+        assert!(fetch_tag(&conn, "t-phone").unwrap().group_id.is_none());
+        assert!(assign("t-acme", "").unwrap().group_id.is_none());
+        
+        conn.execute_batch("DELETE FROM task_tags; DELETE FROM tags WHERE id = 't-acme';").unwrap();
+        let summary = import_export_value(&conn, &export).unwrap();
+        assert_eq!((summary.tag_groups_added, summary.tags_added), (1, 1));
+        assert_eq!(fetch_tag(&conn, "t-acme").unwrap().group_id.as_deref(), Some(client.id.as_str()));
+        assert_eq!(fetch_tag(&conn, "t-phone").unwrap().group_id.as_deref(), Some(context.id.as_str()));
+        assert_eq!(fetch_task_tags(&conn, &task).unwrap()[0].name, "acme");
+    }
+    
+    #[test]
+    fn test_checklists_apply_and_round_trip() {
+        let conn = setup_test_conn();
+        let task = insert_task(&conn, "Cut release", "high");
+        add_subtasks_bulk_internal(&conn, &task, "Freeze branch").unwrap();
+        
+        assert!(create_checklist_internal(&conn, CreateChecklistInput { name: " ".into(), items: vec!["a".into()] }).is_err());
+        let checklist = create_checklist_internal(&conn, CreateChecklistInput {
+            name: "Release checklist".into(),
+            items: vec!["Bump version".into(), "  ".into(), "Tag build".into()],
+        }).unwrap();
+        assert_eq!(checklist.items, vec!["Bump version", "Tag build"]);
+        
+        let added = apply_checklist_to_task_internal(&conn, &task, &checklist.id).unwrap();
+        assert_eq!(added.len(), 2);
+        let titles: Vec<String> = fetch_subtasks(&conn, &task).unwrap().into_iter().map(|s| s.title).collect();
+        assert_eq!(titles, vec!["Freeze branch", "Bump version", "Tag build"]);
+        assert!(apply_checklist_to_task_internal(&conn, "missing", &checklist.id).is_err());
+        
+        let copied = create_checklist_from_task_internal(&conn, &task, "Full release".into()).unwrap();
+        assert_eq!(copied.items, titles);
+        let renamed = update_checklist_internal(&conn, &copied.id, UpdateChecklistInput { name: Some("Ship it".into()), items: None }).unwrap();
+        assert_eq!((renamed.name.as_str(), renamed.items.len()), ("Ship it", 3));
+        
+        let export = build_export_value(&conn).unwrap();
+        delete_checklist_internal(&conn, &checklist.id).unwrap();
+        assert_eq!(get_checklists_internal(&conn).unwrap().len(), 1);
+        let summary = import_export_value(&conn, &export).unwrap();
+        assert_eq!(summary.checklists_added, 1);
+        assert_eq!(fetch_checklist(&conn, &checklist.id).unwrap().items, vec!["Bump version", "Tag build"]);
+    }
+    
+    #[test]
+    fn test_project_scoped_templates() {
+        let conn = setup_test_conn();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p-home', 'Home', 0, 0), ('p-work', 'Work', 0, 0);"
+        ).unwrap();
+        let template = |name: &str, project_id: Option<&str>, scope_project_id: Option<&str>| CreateTemplateInput {
+            name: name.to_string(),
+            title: name.to_string(),
+            description: None,
+            priority: "medium".to_string(),
+            project_id: project_id.map(str::to_string),
+            scope_project_id: scope_project_id.map(str::to_string),
+            recurrence_type: None,
+        };
+        
+        let global = create_template_internal(&conn, template("Global", None, None)).unwrap();
+        let standup = create_template_internal(&conn, template("Standup", Some("p-work"), Some("p-work"))).unwrap();
+        create_template_internal(&conn, template("Chores", Some("p-home"), Some("p-home"))).unwrap();
+        assert_eq!(standup.scope_project_id.as_deref(), Some("p-work"));
+        assert!(create_template_internal(&conn, template("Orphan", None, Some("p-missing")))
+            .unwrap_err()
+            .starts_with("Project not found"));
+        
+        assert_eq!(get_templates_internal(&conn, None).unwrap().len(), 3);
+        let mut in_work: Vec<String> = get_templates_internal(&conn, Some("p-work")).unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        in_work.sort();
+        assert_eq!(in_work, vec!["Global", "Standup"]);
+        
+        // The template's target project is used unless the caller overrides it
+        let task = create_task_from_template_internal(&conn, &standup.id, None, None).unwrap();
+        assert_eq!(task.project_id.as_deref(), Some("p-work"));
+        let task = create_task_from_template_internal(&conn, &global.id, None, Some("p-home".to_string())).unwrap();
+        assert_eq!(task.project_id.as_deref(), Some("p-home"));
+        assert!(create_task_from_template_internal(&conn, &global.id, None, Some("p-missing".to_string())).is_err());
+    }
+    
+    #[test]
+    fn test_session_start_gathers_launch_state() {
+        let conn = setup_test_conn();
+        let today = 1_700_000_000 / 86400 * 86400;
+        conn.execute_batch(&format!(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p-work', 'Work', 0, 0);
+            INSERT INTO tasks (id, title, due_at, project_id, completed_at, created_at, updated_at) VALUES
+                ('today', 'Due today', {today} + 3600, NULL, NULL, 0, 0),
+                ('late', 'Overdue', {today} - 3600, 'p-work', NULL, 0, 0),
+                ('soon', 'Due in three days', {today} + 3 * 86400, 'p-work', NULL, 0, 0),
+                ('done', 'Finished', NULL, NULL, {today} + 60, 0, 0);
+            INSERT INTO notification_schedule (id, task_id, scheduled_at, created_at, missed_at) VALUES
+                ('n-missed', 'late', {today} - 7200, 0, {today}),
+                ('n-pending', 'soon', {today} + 2 * 86400, 0, NULL);
+            INSERT INTO settings (key, value) VALUES ('backup_frequency', 'daily');",
+            today = today,
+        )).unwrap();
+        
+        let state = session_start_internal(&conn, None, today, true).unwrap();
+        assert_eq!(state.progress.id, "default");
+        assert_eq!(state.settings.get("backup_frequency").map(String::as_str), Some("daily"));
+        let counts = &state.sidebar_counts;
+        assert_eq!((counts.open, counts.inbox, counts.today, counts.overdue, counts.upcoming, counts.completed_today), (3, 1, 1, 1, 1, 1));
+        assert_eq!(state.pending_notifications.len(), 1);
+        assert_eq!(state.pending_notifications[0].task_id, "soon");
+        assert_eq!(state.away.missed_reminders, 1);
+        // Daily backups with none on disk
+        assert!(state.away.backup.overdue);
+        
+        assert!(!backup_status(Some("weekly"), Some(today), today + 86400).overdue);
+        assert!(backup_status(Some("weekly"), Some(today), today + 7 * 86400).overdue);
+        assert!(!backup_status(None, None, today).overdue);
+    }
+    
+    #[test]
+    fn test_project_view_state_round_trip() {
+        let conn = setup_test_conn();
+        conn.execute_batch("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p-work', 'Work', 0, 0);").unwrap();
+        assert_eq!(get_project_view_state_internal(&conn, "p-work").unwrap(), None);
+        
+        let board = ProjectViewState {
+            view: "board".to_string(),
+            collapsed_columns: vec!["done".to_string()],
+            sort: Some(ViewSort { field: "dueDate".to_string(), direction: "asc".to_string() }),
+        };
+        set_project_view_state_internal(&conn, "p-work", Some(&board)).unwrap();
+        assert_eq!(get_project_view_state_internal(&conn, "p-work").unwrap().as_ref(), Some(&board));
+        
+        let mut invalid = board.clone();
+        invalid.collapsed_columns = vec!["archived".to_string()];
+        assert!(set_project_view_state_internal(&conn, "p-work", Some(&invalid)).is_err());
+        assert!(set_project_view_state_internal(&conn, "p-missing", Some(&board)).is_err());
+        assert!(serde_json::from_value::<ProjectViewState>(serde_json::json!({ "view": "list", "zoom": 2 })).is_err());
+        
+        // Views travel with exports
+        let export = build_export_value(&conn).unwrap();
+        set_project_view_state_internal(&conn, "p-work", None).unwrap();
+        assert_eq!(get_project_view_state_internal(&conn, "p-work").unwrap(), None);
+        import_export_value(&conn, &export).unwrap();
+        assert_eq!(get_project_view_state_internal(&conn, "p-work").unwrap(), Some(board));
+    }
+    
+    #[test]
+    fn test_folder_sync_between_devices() {
+        let folder = tempfile::TempDir::new().unwrap();
+        let now = now();
+        let (laptop, desktop) = (setup_test_conn(), setup_test_conn());
+        for conn in [&laptop, &desktop] {
+            conn.execute("INSERT INTO settings (key, value) VALUES ('sync_folder', ?1)", params![folder.path().to_string_lossy()]).unwrap();
+        }
+        laptop.execute_batch(&format!(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Home', {t}, {t});
+            INSERT INTO tasks (id, title, project_id, created_at, updated_at) VALUES
+                ('t1', 'Water plants', 'p1', {t}, {t}), ('t2', 'Call plumber', NULL, {t}, {t});",
+            t = now - 1000,
+        )).unwrap();
+        
+        assert_eq!(crate::sync::run_sync(&laptop, now - 900).unwrap().unwrap().exported, 3);
+        let result = crate::sync::run_sync(&desktop, now - 800).unwrap().unwrap();
+        assert_eq!((result.files_ingested, result.applied.tasks_added, result.applied.projects_added), (1, 2, 1));
+        assert_eq!(fetch_task(&desktop, "t1").unwrap().updated_at, now - 1000);
+        
+        // Desktop edits both tasks; the laptop deletes one of them before it hears about the edit
+        desktop.execute_batch(&format!(
+            "UPDATE tasks SET title = 'Water plants twice', updated_at = {} WHERE id = 't1';
+            UPDATE tasks SET title = 'Call plumber today', updated_at = {} WHERE id = 't2';",
+            now - 500, now + 100,
+        )).unwrap();
+        laptop.execute("DELETE FROM tasks WHERE id = 't2'", []).unwrap();
+        crate::sync::run_sync(&laptop, now + 150).unwrap();
+        let result = crate::sync::run_sync(&desktop, now + 200).unwrap().unwrap();
+        assert_eq!((result.deleted, result.conflicts), (0, 1));
+        assert!(fetch_task(&desktop, "t2").is_ok());
+        
+        let result = crate::sync::run_sync(&laptop, now + 300).unwrap().unwrap();
+        assert_eq!((result.applied.tasks_updated, result.conflicts), (1, 1));
+        let t1 = fetch_task(&laptop, "t1").unwrap();
+        assert_eq!((t1.title.as_str(), t1.updated_at), ("Water plants twice", now - 500));
+        
+        let conflicts = crate::sync::get_sync_conflicts(&laptop).unwrap();
+        assert_eq!(conflicts[0].entity_id, "t2");
+        assert!(conflicts[0].local.is_none());
+        crate::sync::resolve_sync_conflict(&laptop, &conflicts[0].id, "remote", now + 400).unwrap();
+        assert_eq!(fetch_task(&laptop, "t2").unwrap().title, "Call plumber today");
+        assert!(crate::sync::get_sync_conflicts(&laptop).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_translation_consent_gates_and_stays_local() {
+        let conn = setup_test_conn();
+        assert_eq!(
+            translation_service::require_translation_consent(&conn).unwrap_err(),
+            translation_service::CONSENT_REQUIRED_ERROR
+        );
+        let status = translation_service::get_external_services_status(&conn, &Default::default()).unwrap();
+        assert!(status.iter().all(|service| !service.enabled));
+        
+        translation_service::set_translation_consent(&conn, true).unwrap();
+        assert!(translation_service::require_translation_consent(&conn).is_ok());
+        let status = translation_service::get_external_services_status(&conn, &Default::default()).unwrap();
+        assert!(status.iter().any(|service| service.id == "libre_translate" && service.enabled));
+        
+        // Consent is per device: it is neither exported nor taken from an imported file
+        let export = build_export_value(&conn).unwrap();
+        assert!(export["settings"].get(translation_service::TRANSLATION_CONSENT_KEY).is_none());
+        
+        translation_service::set_translation_consent(&conn, false).unwrap();
+        let mut incoming = export.clone();
+        incoming["settings"][translation_service::TRANSLATION_CONSENT_KEY] = serde_json::json!("true");
+        import_export_value(&conn, &incoming).unwrap();
+        assert!(!translation_service::has_translation_consent(&conn));
+    }
+    
+    #[test]
+    fn test_summarize_project_and_undo() {
+        let conn = setup_test_conn();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Client', 0, 0);
+             INSERT INTO tasks (id, title, created_at, updated_at, priority, project_id, completed_at) VALUES
+                ('done1', 'Send invoice', 0, 0, 'high', 'p1', 1700000000),
+                ('done2', 'Kickoff call', 0, 0, 'medium', 'p1', 1700100000),
+                ('open1', 'Write report', 0, 0, 'medium', 'p1', NULL);
+             INSERT INTO subtasks (id, task_id, title, completed) VALUES ('s1', 'done1', 'Attach PDF', 1);
+             INSERT INTO attachments (id, task_id, filename, path, created_at) VALUES ('a1', 'done1', 'invoice.pdf', 'x/invoice.pdf', 0);"
+        ).unwrap();
+        attach_tag_by_name(&conn, "done2", "calls").unwrap();
+        
+        let result = summarize_project_internal(&conn, "p1", 1700200000).unwrap();
+        assert_eq!(result.summarized_count, 2);
+        assert_eq!(result.attachments_moved, 1);
+        let summary = &result.summary_task;
+        assert_eq!(summary.title, ARCHIVE_SUMMARY_TITLE);
+        assert_eq!(summary.project_id.as_deref(), Some("p1"));
+        let description = summary.description.clone().unwrap();
+        assert!(description.starts_with("- Send invoice (completed "));
+        assert!(description.contains("- Kickoff call (completed "));
+        
+        assert!(fetch_task(&conn, "done1").is_err());
+        assert!(fetch_task(&conn, "open1").is_ok());
+        let attachment_owner: String = conn.query_row("SELECT task_id FROM attachments WHERE id = 'a1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(attachment_owner, summary.id);
+        // The summary itself is never folded into a later summary
+        assert!(summarize_project_internal(&conn, "p1", 1700300000).is_err());
+        
+        assert_eq!(undo_archive_summary_internal(&conn, &summary.id).unwrap(), 2);
+        assert!(fetch_task(&conn, &summary.id).is_err());
+        
+        let restored = fetch_task(&conn, "done2").unwrap();
+        assert!(restored.completed);
+        assert_eq!(restored.tags.unwrap()[0].name, "calls");
+        assert_eq!(fetch_subtasks(&conn, "done1").unwrap()[0].title, "Attach PDF");
+        let attachment_owner: String = conn.query_row("SELECT task_id FROM attachments WHERE id = 'a1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(attachment_owner, "done1");
+        assert!(undo_archive_summary_internal(&conn, "open1").is_err());
     }
 }
@@ -0,0 +1,322 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+// A confirmation token has to be used within this many seconds of being requested
+const TOKEN_TTL_SECONDS: i64 = 60;
+
+/// What reset_app_data wipes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetScope {
+    /// XP, level, streaks and rank back to the start; badges and XP history deleted
+    Gamification,
+    /// Pomodoro sessions and streaks
+    Pomodoro,
+    /// Cached and hand-corrected translations
+    Translations,
+    /// Tables the daily jobs rebuild: project keywords and tag/term co-occurrence
+    StatisticsCaches,
+    /// All data; the database is recreated empty after a safety backup
+    Everything,
+}
+
+impl ResetScope {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "gamification" => Ok(Self::Gamification),
+            "pomodoro" => Ok(Self::Pomodoro),
+            "translations" => Ok(Self::Translations),
+            "statistics_caches" => Ok(Self::StatisticsCaches),
+            "everything" => Ok(Self::Everything),
+            other => Err(format!(
+                "Unknown reset scope: {} (expected gamification, pomodoro, translations, statistics_caches or everything)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gamification => "gamification",
+            Self::Pomodoro => "pomodoro",
+            Self::Translations => "translations",
+            Self::StatisticsCaches => "statistics_caches",
+            Self::Everything => "everything",
+        }
+    }
+
+    // Tables emptied by the scope; Everything is handled separately
+    fn tables(self) -> &'static [&'static str] {
+        match self {
+            Self::Gamification => &["badges", "xp_history"],
+            Self::Pomodoro => &["pomodoro_sessions", "pomodoro_streaks"],
+            Self::Translations => &["translations"],
+            Self::StatisticsCaches => &["project_keywords", "tag_term_cooccurrence"],
+            Self::Everything => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResetTokenResponse {
+    pub scope: String,
+    pub token: String,
+    pub expires_at: i64,
+}
+
+/// What a reset removed
+#[derive(Debug, Clone, Serialize)]
+pub struct ResetReport {
+    pub scope: String,
+    /// Rows deleted (or reset, for user_progress) per table
+    pub deleted: BTreeMap<String, usize>,
+    /// The safety backup taken before resetting everything
+    pub backup_path: Option<String>,
+    /// Where resetting everything moved the attachment files and thumbnails, next to the backup
+    pub files_backup_path: Option<String>,
+    /// App data folders resetting everything could not move out, so they are still there
+    pub kept_files: Vec<String>,
+}
+
+/// Confirmation tokens handed out by request_reset_token, one per scope; each can be used once
+#[derive(Default)]
+pub struct ResetTokens(Mutex<HashMap<&'static str, (String, i64)>>);
+
+impl ResetTokens {
+    /// A new token for `scope`, replacing any earlier one
+    pub fn issue(&self, scope: ResetScope, now: i64) -> Result<ResetTokenResponse, String> {
+        let token = uuid::Uuid::new_v4().to_string();
+        let expires_at = now + TOKEN_TTL_SECONDS;
+        self.0
+            .lock()
+            .map_err(|e| format!("Reset token lock error: {}", e))?
+            .insert(scope.as_str(), (token.clone(), expires_at));
+        Ok(ResetTokenResponse {
+            scope: scope.as_str().to_string(),
+            token,
+            expires_at,
+        })
+    }
+
+    /// Check and use up the token for `scope`. A wrong token also discards the issued one, so
+    /// a new one has to be requested.
+    pub fn consume(&self, scope: ResetScope, token: &str, now: i64) -> Result<(), String> {
+        let issued = self
+            .0
+            .lock()
+            .map_err(|e| format!("Reset token lock error: {}", e))?
+            .remove(scope.as_str());
+        match issued {
+            Some((expected, expires_at)) if expected == token && now <= expires_at => Ok(()),
+            Some((expected, _)) if expected == token => Err("The reset confirmation has expired; request a new one".to_string()),
+            _ => Err(format!("Invalid confirmation token for resetting {}", scope.as_str())),
+        }
+    }
+}
+
+/// Wipe one of the partial scopes in a single transaction. Everything goes through
+/// reset_everything instead, since it replaces the database file.
+pub fn reset_scope(conn: &rusqlite::Connection, scope: ResetScope, now: i64) -> Result<ResetReport, String> {
+    if scope == ResetScope::Everything {
+        return Err("Resetting everything needs the app data folder".to_string());
+    }
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut deleted = BTreeMap::new();
+    for table in scope.tables() {
+        let rows = tx.execute(&format!("DELETE FROM {}", table), [])
+            .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+        deleted.insert(table.to_string(), rows);
+    }
+    if scope == ResetScope::Gamification {
+        let rows = tx.execute(
+            "UPDATE user_progress SET total_xp = 0, current_level = 1, current_streak = 0, longest_streak = 0,
+                last_completion_date = NULL, current_rank_tier = 'iron', current_rank_division = 4, rank_progress = 0,
+                updated_at = ?1",
+            params![now],
+        ).map_err(|e| format!("Failed to reset progress: {}", e))?;
+        deleted.insert("user_progress".to_string(), rows);
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(ResetReport {
+        scope: scope.as_str().to_string(),
+        deleted,
+        backup_path: None,
+        files_backup_path: None,
+        kept_files: Vec::new(),
+    })
+}
+
+/// Rows in every data table, skipping SQLite's own tables, the migration log and the shadow
+/// tables behind full-text search
+pub fn count_all_rows(conn: &rusqlite::Connection) -> Result<BTreeMap<String, usize>, String> {
+    let tables: Vec<String> = conn
+        .prepare(
+            "SELECT name FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'migrations'
+              AND name NOT LIKE '%fts%'",
+        )
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let mut counts = BTreeMap::new();
+    for table in tables {
+        let rows: i64 = conn
+            .query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+        if rows > 0 {
+            counts.insert(table, rows as usize);
+        }
+    }
+    Ok(counts)
+}
+
+// Folders under app data that only hold files belonging to database rows
+const FILE_FOLDERS: [&str; 2] = ["attachments", crate::attachments::THUMBNAILS_DIR];
+
+/// Move the attachment and thumbnail folders out of `app_data_dir` into `dest`, so a restored
+/// safety backup can get its files back. Returns the folders that could not be moved.
+pub fn move_file_folders(app_data_dir: &std::path::Path, dest: &std::path::Path) -> Vec<String> {
+    let mut kept = Vec::new();
+    for folder in FILE_FOLDERS {
+        let source = app_data_dir.join(folder);
+        if !source.exists() {
+            continue;
+        }
+        let moved = std::fs::create_dir_all(dest).and_then(|_| std::fs::rename(&source, dest.join(folder)));
+        if moved.is_err() {
+            kept.push(source.to_string_lossy().to_string());
+        }
+    }
+    kept
+}
+
+/// Back up the database into app_data_dir/backups, recreate it empty and reconnect, then move
+/// the attachment files and thumbnails next to the backup
+pub fn reset_everything(
+    db: &mut crate::db::DbConnection,
+    app_handle: &tauri::AppHandle,
+    app_data_dir: &std::path::Path,
+) -> Result<ResetReport, String> {
+    let backups_dir = app_data_dir.join("backups");
+    std::fs::create_dir_all(&backups_dir).map_err(|e| crate::storage::from_io("create the backups folder", &e).to_string())?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let backup_path = backups_dir.join(format!("todo_before_reset_{}.db", timestamp));
+    match crate::db::backup_to_file(&db.conn, &backup_path, |_, _| true) {
+        Ok(true) => {}
+        result => {
+            let _ = std::fs::remove_file(&backup_path);
+            return Err(match result {
+                Err(e) => crate::storage::from_sqlite("create the safety backup", &e).to_string(),
+                _ => "The safety backup did not finish".to_string(),
+            });
+        }
+    }
+
+    let deleted = count_all_rows(&db.conn)?;
+    crate::db::recreate_db(db, app_handle).map_err(|e| format!("Failed to recreate the database: {}", e))?;
+
+    let files_dir = backups_dir.join(format!("todo_before_reset_{}_files", timestamp));
+    let kept_files = move_file_folders(app_data_dir, &files_dir);
+
+    Ok(ResetReport {
+        scope: ResetScope::Everything.as_str().to_string(),
+        deleted,
+        backup_path: Some(backup_path.to_string_lossy().to_string()),
+        files_backup_path: files_dir.exists().then(|| files_dir.to_string_lossy().to_string()),
+        kept_files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_single_use_and_expire() {
+        let tokens = ResetTokens::default();
+        let issued = tokens.issue(ResetScope::Pomodoro, 1000).unwrap();
+        assert!(tokens.consume(ResetScope::Gamification, &issued.token, 1000).is_err());
+        assert!(tokens.consume(ResetScope::Pomodoro, &issued.token, 1010).is_ok());
+        assert!(tokens.consume(ResetScope::Pomodoro, &issued.token, 1010).is_err());
+
+        let issued = tokens.issue(ResetScope::Pomodoro, 1000).unwrap();
+        assert!(tokens.consume(ResetScope::Pomodoro, "guess", 1000).is_err());
+        // The failed attempt used it up
+        assert!(tokens.consume(ResetScope::Pomodoro, &issued.token, 1000).is_err());
+
+        let issued = tokens.issue(ResetScope::Everything, 1000).unwrap();
+        let expired = tokens.consume(ResetScope::Everything, &issued.token, 1000 + TOKEN_TTL_SECONDS + 1);
+        assert!(expired.unwrap_err().contains("expired"));
+        assert!(ResetScope::parse("all").is_err());
+    }
+
+    #[test]
+    fn test_reset_scopes_only_touch_their_tables() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE user_progress (id TEXT PRIMARY KEY, total_xp INTEGER, current_level INTEGER, current_streak INTEGER,
+                longest_streak INTEGER, last_completion_date INTEGER, current_rank_tier TEXT, current_rank_division INTEGER,
+                rank_progress INTEGER, updated_at INTEGER);
+            CREATE TABLE badges (id TEXT PRIMARY KEY);
+            CREATE TABLE xp_history (id TEXT PRIMARY KEY);
+            CREATE TABLE pomodoro_sessions (id TEXT PRIMARY KEY);
+            CREATE TABLE pomodoro_streaks (id TEXT PRIMARY KEY);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY);
+            INSERT INTO user_progress VALUES ('default', 900, 5, 3, 7, 100, 'gold', 2, 40, 100);
+            INSERT INTO badges VALUES ('b1');
+            INSERT INTO xp_history VALUES ('x1'), ('x2');
+            INSERT INTO pomodoro_sessions VALUES ('p1');
+            INSERT INTO pomodoro_streaks VALUES ('default');
+            INSERT INTO tasks VALUES ('t1');",
+        )
+        .unwrap();
+
+        let report = reset_scope(&conn, ResetScope::Gamification, 200).unwrap();
+        let expected: BTreeMap<String, usize> =
+            [("badges", 1), ("user_progress", 1), ("xp_history", 2)].iter().map(|(t, n)| (t.to_string(), *n)).collect();
+        assert_eq!(report.deleted, expected);
+        let progress: (i64, i64, i64, String) = conn
+            .query_row("SELECT total_xp, current_level, longest_streak, current_rank_tier FROM user_progress", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap();
+        assert_eq!(progress, (0, 1, 0, "iron".to_string()));
+
+        let counts = count_all_rows(&conn).unwrap();
+        assert_eq!(counts.get("pomodoro_sessions"), Some(&1));
+        assert_eq!(counts.get("tasks"), Some(&1));
+        assert!(!counts.contains_key("badges"));
+
+        reset_scope(&conn, ResetScope::Pomodoro, 200).unwrap();
+        let counts = count_all_rows(&conn).unwrap();
+        assert_eq!(counts.keys().collect::<Vec<_>>(), vec!["tasks", "user_progress"]);
+        assert!(reset_scope(&conn, ResetScope::Everything, 200).is_err());
+    }
+
+    #[test]
+    fn test_file_folders_move_beside_the_backup() {
+        let app_data_dir = std::env::temp_dir().join(format!("todo_reset_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(app_data_dir.join("attachments").join("t1")).unwrap();
+        std::fs::write(app_data_dir.join("attachments").join("t1").join("a.pdf"), b"pdf").unwrap();
+        std::fs::create_dir_all(app_data_dir.join("thumbnails")).unwrap();
+        std::fs::write(app_data_dir.join("thumbnails").join("a.png"), b"png").unwrap();
+        std::fs::create_dir_all(app_data_dir.join("exports")).unwrap();
+
+        let dest = app_data_dir.join("backups").join("todo_before_reset_files");
+        assert!(move_file_folders(&app_data_dir, &dest).is_empty());
+        assert!(!app_data_dir.join("attachments").exists());
+        assert!(!app_data_dir.join("thumbnails").exists());
+        assert!(app_data_dir.join("exports").exists());
+        assert_eq!(std::fs::read(dest.join("attachments").join("t1").join("a.pdf")).unwrap(), b"pdf");
+        assert!(dest.join("thumbnails").join("a.png").exists());
+
+        // Nothing left to move the second time
+        assert!(move_file_folders(&app_data_dir, &app_data_dir.join("again")).is_empty());
+        assert!(!app_data_dir.join("again").exists());
+        std::fs::remove_dir_all(&app_data_dir).unwrap();
+    }
+}
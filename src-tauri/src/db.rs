@@ -1,19 +1,156 @@
+use rusqlite::backup::{Backup, StepResult};
 use rusqlite::{Connection, Result as SqlResult, params};
+use std::cell::Cell;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// What the database file looked like on disk at a given moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DbFileStamp {
+    modified_ms: i64,
+    len: u64,
+    // Inode on Unix, so a file swapped in by a sync tool is recognised; always 0 elsewhere
+    file_id: u64,
+}
+
+fn file_stamp(path: &Path) -> Option<DbFileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified_ms = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some(DbFileStamp {
+        modified_ms,
+        len: metadata.len(),
+        file_id: file_id(&metadata),
+    })
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+fn data_version(conn: &Connection) -> i64 {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0)).unwrap_or(0)
+}
+
 pub struct DbConnection {
     pub conn: Connection,
+    path: Option<PathBuf>,
+    // File stamp after the last write we know was ours, and the connection's data_version at that time
+    last_write: Cell<Option<DbFileStamp>>,
+    data_version: Cell<i64>,
+    // Set once an external change is detected, cleared by reopen()
+    external_change_at: Cell<Option<i64>>,
 }
 
 impl DbConnection {
     pub fn new(db_path: PathBuf) -> SqlResult<Self> {
-        let conn = Connection::open(db_path)?;
-        Ok(Self { conn })
+        let conn = Connection::open(&db_path)?;
+        let db = Self {
+            path: Some(db_path),
+            ..Self::from_connection(conn)
+        };
+        db.reset_file_state();
+        Ok(db)
+    }
+    
+    /// Wrap an already open connection (e.g. in-memory); file change detection is disabled
+    pub fn from_connection(conn: Connection) -> Self {
+        Self {
+            conn,
+            path: None,
+            last_write: Cell::new(None),
+            data_version: Cell::new(0),
+            external_change_at: Cell::new(None),
+        }
+    }
+    
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+    
+    /// Close the current connection and open the file again, e.g. after it was restored or
+    /// replaced by another program. Does not run migrations; see `reopen_db`.
+    pub fn reopen(&mut self) -> SqlResult<()> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return Ok(()),
+        };
+        
+        // Release the file handle before opening the new one
+        let old = std::mem::replace(&mut self.conn, Connection::open_in_memory()?);
+        if let Err((_, e)) = old.close() {
+            return Err(e);
+        }
+        
+        self.conn = Connection::open(&path)?;
+        self.reset_file_state();
+        Ok(())
+    }
+    
+    fn reset_file_state(&self) {
+        self.last_write.set(self.path.as_deref().and_then(file_stamp));
+        self.data_version.set(data_version(&self.conn));
+        self.external_change_at.set(None);
+    }
+    
+    /// Compare the file on disk with what this connection last wrote.
+    /// Returns a description the first time an external change is seen; later calls return
+    /// None until the database is reopened. Changes made through this connection are recorded
+    /// as our own writes.
+    pub fn check_external_modification(&self) -> Option<String> {
+        let stamp = file_stamp(self.path.as_deref()?)?;
+        
+        let replaced = self.last_write.get().is_some_and(|last| last.file_id != stamp.file_id);
+        // data_version only changes for commits made by other connections
+        let written_elsewhere = data_version(&self.conn) != self.data_version.get();
+        
+        if !replaced && !written_elsewhere {
+            if self.external_change_at.get().is_none() {
+                self.last_write.set(Some(stamp));
+            }
+            return None;
+        }
+        
+        if self.external_change_at.get().is_some() {
+            return None;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        self.external_change_at.set(Some(now));
+        
+        Some(if replaced {
+            "The database file was replaced by another program".to_string()
+        } else {
+            "The database file was modified by another program".to_string()
+        })
+    }
+    
+    /// When an external change was detected, if it has not been reloaded yet
+    pub fn external_change_at(&self) -> Option<i64> {
+        self.external_change_at.get()
+    }
+    
+    /// Modification time (Unix seconds) of the file after the last write known to be ours
+    pub fn last_write_at(&self) -> Option<i64> {
+        self.last_write.get().map(|stamp| stamp.modified_ms / 1000)
     }
 }
 
+
 pub fn init_db(app_handle: &tauri::AppHandle) -> anyhow::Result<DbConnection> {
     // Get app data directory
     let app_data_dir = app_handle
@@ -37,6 +174,94 @@ pub fn init_db(app_handle: &tauri::AppHandle) -> anyhow::Result<DbConnection> {
     Ok(db)
 }
 
+/// Reopen the database file and bring its schema up to date.
+/// Used after restoring a backup and when the file was changed by another program.
+pub fn reopen_db(db: &mut DbConnection, app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
+    db.reopen()?;
+    run_migrations(&db.conn, app_handle)?;
+    // Migrations are our own writes
+    db.reset_file_state();
+    Ok(())
+}
+
+/// Delete the database file and start over with an empty, fully migrated one (no seed data).
+/// Callers take a backup first; see data_reset.
+pub fn recreate_db(db: &mut DbConnection, app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
+    let path = db.path().map(Path::to_path_buf).ok_or_else(|| anyhow::anyhow!("The database is not a file"))?;
+    
+    // Release the file handle so the files can be removed
+    let old = std::mem::replace(&mut db.conn, Connection::open_in_memory()?);
+    if let Err((conn, e)) = old.close() {
+        db.conn = conn;
+        return Err(e.into());
+    }
+    
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let mut file = path.clone().into_os_string();
+        file.push(suffix);
+        match fs::remove_file(&file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                // Back onto whatever is left rather than the in-memory placeholder
+                db.reopen()?;
+                return Err(e.into());
+            }
+        }
+    }
+    
+    reopen_db(db, app_handle)
+}
+
+// Pages copied per backup step (~4MB with the default 4KB page size)
+const BACKUP_PAGES_PER_STEP: i32 = 1024;
+// How long a backup keeps retrying while another connection holds a lock before giving up
+const BACKUP_BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Copy the live database into `dest` using SQLite's online backup API.
+/// Unlike a filesystem copy this yields a consistent snapshot even if another
+/// connection writes mid-way. `on_progress` receives (copied_pages, total_pages) after each step
+/// and returns false to stop early, in which case this returns Ok(false) and `dest` is incomplete.
+/// Fails with SQLITE_BUSY if a lock blocks the copy for longer than BACKUP_BUSY_TIMEOUT.
+pub fn backup_to_file<F: FnMut(i32, i32) -> bool>(conn: &Connection, dest: &Path, mut on_progress: F) -> SqlResult<bool> {
+    let mut dest_conn = Connection::open(dest)?;
+    // The retry loop below does the waiting, so steps report Busy straight away
+    dest_conn.busy_timeout(std::time::Duration::ZERO)?;
+    let backup = Backup::new(conn, &mut dest_conn)?;
+    let mut busy_since: Option<std::time::Instant> = None;
+    
+    loop {
+        let result = backup.step(BACKUP_PAGES_PER_STEP)?;
+        let progress = backup.progress();
+        let keep_going = on_progress(progress.pagecount - progress.remaining, progress.pagecount);
+        
+        match result {
+            StepResult::Done => break,
+            _ if !keep_going => return Ok(false),
+            StepResult::More => busy_since = None,
+            // Another connection holds a lock; wait briefly and retry the step
+            _ => {
+                let since = *busy_since.get_or_insert_with(std::time::Instant::now);
+                if since.elapsed() >= BACKUP_BUSY_TIMEOUT {
+                    return Err(rusqlite::Error::SqliteFailure(
+                        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+                        Some("The database stayed locked for the whole backup".to_string()),
+                    ));
+                }
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+        }
+    }
+    
+    Ok(true)
+}
+
+// Whether this SQLite build has the FTS5 extension compiled in
+pub(crate) fn fts5_available(conn: &Connection) -> bool {
+    conn.query_row("SELECT sqlite_compileoption_used('ENABLE_FTS5')", [], |row| row.get::<_, bool>(0))
+        .unwrap_or(false)
+}
+
 fn run_migrations(conn: &Connection, app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
     // Create migrations table if it doesn't exist
     conn.execute(
@@ -98,6 +323,12 @@ fn run_migrations(conn: &Connection, app_handle: &tauri::AppHandle) -> anyhow::R
             if !applied.contains(&migration_file) {
                 let migration_path = migrations_dir.join(&migration_file);
                 if let Ok(sql) = fs::read_to_string(&migration_path) {
+                    // Full-text indexes need FTS5. Without it they are left unapplied (and so
+                    // retried on a later launch) and search falls back to LIKE.
+                    if migration_file.ends_with("_fts.sql") && !fts5_available(conn) {
+                        continue;
+                    }
+
                     // Execute migration in a transaction
                     let tx = conn.unchecked_transaction()?;
                     
@@ -823,5 +1054,156 @@ mod tests {
         ).unwrap();
         assert_eq!(updated_value, "updated_value");
     }
+    
+    #[test]
+    fn test_backup_to_file() {
+        let (temp_dir, db) = setup_test_db();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        for i in 0..50 {
+            db.conn.execute(
+                "INSERT INTO tasks (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                params![Uuid::new_v4().to_string(), format!("Task {}", i), now, now],
+            ).unwrap();
+        }
+        
+        let backup_path = temp_dir.path().join("backup.db");
+        let mut last_progress = (0, 0);
+        let completed = backup_to_file(&db.conn, &backup_path, |copied, total| {
+            last_progress = (copied, total);
+            true
+        }).unwrap();
+        assert!(completed);
+        
+        // Final progress report covers every page
+        assert!(last_progress.1 > 0);
+        assert_eq!(last_progress.0, last_progress.1);
+        
+        let backup = Connection::open(&backup_path).unwrap();
+        let count: i64 = backup.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 50);
+    }
+    
+    #[test]
+    fn test_backup_gives_up_on_a_locked_destination() {
+        let (temp_dir, db) = setup_test_db();
+        let backup_path = temp_dir.path().join("locked.db");
+        let holder = Connection::open(&backup_path).unwrap();
+        holder.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        
+        let started = std::time::Instant::now();
+        let result = backup_to_file(&db.conn, &backup_path, |_, _| true);
+        assert!(matches!(
+            result,
+            Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::DatabaseBusy
+        ));
+        assert!(started.elapsed() >= BACKUP_BUSY_TIMEOUT);
+        holder.execute_batch("ROLLBACK").unwrap();
+    }
+    
+    #[test]
+    fn test_detects_writes_from_other_connections() {
+        let (temp_dir, mut db) = setup_test_db();
+        
+        // Our own writes are not reported
+        db.conn.execute("INSERT INTO settings (key, value) VALUES ('a', '1')", []).unwrap();
+        assert_eq!(db.check_external_modification(), None);
+        assert!(db.external_change_at().is_none());
+        
+        let other = Connection::open(temp_dir.path().join("test.db")).unwrap();
+        other.execute("INSERT INTO settings (key, value) VALUES ('b', '2')", []).unwrap();
+        
+        assert!(db.check_external_modification().is_some());
+        assert!(db.external_change_at().is_some());
+        // Reported once until reloaded
+        assert_eq!(db.check_external_modification(), None);
+        
+        db.reopen().unwrap();
+        assert!(db.external_change_at().is_none());
+        assert_eq!(db.check_external_modification(), None);
+        let value: String = db.conn
+            .query_row("SELECT value FROM settings WHERE key = 'b'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, "2");
+    }
+    
+    // Columns and tables the task list queries read that setup_test_db's schema predates
+    fn add_task_list_schema(db: &DbConnection) {
+        db.conn.execute_batch(
+            "ALTER TABLE tasks ADD COLUMN recurrence_type TEXT NOT NULL DEFAULT 'none';
+            ALTER TABLE tasks ADD COLUMN recurrence_interval INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE tasks ADD COLUMN recurrence_parent_id TEXT;
+            ALTER TABLE tasks ADD COLUMN reminder_minutes_before INTEGER;
+            ALTER TABLE tasks ADD COLUMN notification_repeat INTEGER DEFAULT 0;
+            ALTER TABLE tasks ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE tasks ADD COLUMN status TEXT;
+            ALTER TABLE tasks ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE tasks ADD COLUMN estimated_minutes INTEGER;
+            ALTER TABLE tasks ADD COLUMN actual_minutes INTEGER;
+            ALTER TABLE tasks ADD COLUMN recurrence_days_of_week TEXT;
+            ALTER TABLE tasks ADD COLUMN recurrence_end_date INTEGER;
+            ALTER TABLE tasks ADD COLUMN recurrence_count INTEGER;
+            CREATE TABLE tags (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                color TEXT,
+                created_at INTEGER NOT NULL,
+                usage_count INTEGER NOT NULL DEFAULT 0,
+                group_id TEXT
+            );
+            CREATE TABLE task_tags (task_id TEXT NOT NULL, tag_id TEXT NOT NULL);"
+        ).unwrap();
+    }
+    
+    fn insert_due_task(db: &DbConnection, title: &str, due_at: i64) -> String {
+        let task_id = Uuid::new_v4().to_string();
+        db.conn.execute(
+            "INSERT INTO tasks (id, title, due_at, created_at, updated_at) VALUES (?1, ?2, ?3, 0, 0)",
+            params![task_id, title, due_at],
+        ).unwrap();
+        task_id
+    }
+    
+    #[test]
+    fn test_tasks_due_today_and_this_week() {
+        let (_temp_dir, db) = setup_test_db();
+        add_task_list_schema(&db);
+        // Wednesday 2024-01-10 12:00 UTC; that week runs from Monday the 8th to Sunday the 14th
+        let day = 86400;
+        let monday = 1704672000;
+        let now = monday + 2 * day + day / 2;
+        
+        let afternoon = insert_due_task(&db, "Afternoon", monday + 2 * day + 15 * 3600);
+        let morning = insert_due_task(&db, "Morning", monday + 2 * day + 9 * 3600);
+        let done = insert_due_task(&db, "Done", monday + 2 * day + 10 * 3600);
+        db.conn.execute("UPDATE tasks SET completed_at = ?1 WHERE id = ?2", params![now, done]).unwrap();
+        let archived = insert_due_task(&db, "Archived", monday + 2 * day + 11 * 3600);
+        db.conn.execute("UPDATE tasks SET archived = 1 WHERE id = ?1", params![archived]).unwrap();
+        let friday = insert_due_task(&db, "Friday", monday + 4 * day);
+        let week_start = insert_due_task(&db, "Monday midnight", monday);
+        insert_due_task(&db, "Last Sunday", monday - 3600);
+        insert_due_task(&db, "Next Monday", monday + 7 * day);
+        db.conn.execute_batch("INSERT INTO tags (id, name, created_at) VALUES ('tag', 'errand', 0);").unwrap();
+        db.conn.execute("INSERT INTO task_tags (task_id, tag_id) VALUES (?1, 'tag')", params![friday]).unwrap();
+        
+        let ids = |tasks: Vec<crate::commands::Task>| -> Vec<String> { tasks.into_iter().map(|t| t.id).collect() };
+        let today = crate::commands::get_tasks_due_today_internal(&db.conn, now, None).unwrap();
+        assert_eq!(ids(today), vec![morning.clone(), afternoon.clone()]);
+        
+        let week = crate::commands::get_tasks_due_this_week_internal(&db.conn, now, None).unwrap();
+        assert_eq!(week[3].tags.as_ref().unwrap()[0].name, "errand");
+        assert_eq!(ids(week), vec![week_start, morning, afternoon, friday]);
+        
+        // At 22:00 UTC it is already Thursday three hours east
+        let thursday = crate::commands::get_tasks_due_today_internal(&db.conn, monday + 2 * day + 22 * 3600, Some(180)).unwrap();
+        assert!(thursday.is_empty());
+        
+        // The last representable day has no day after it to end on
+        let last_day = chrono::NaiveDate::MAX.and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp();
+        assert!(crate::commands::get_tasks_due_today_internal(&db.conn, last_day, None).is_err());
+        assert!(crate::commands::get_tasks_due_this_week_internal(&db.conn, last_day, None).is_err());
+    }
 }
-
@@ -0,0 +1,91 @@
+use crate::db::DbConnection;
+use rusqlite::params;
+use std::sync::Mutex;
+use tauri::Manager;
+
+pub const URL_SCHEME: &str = "todoapp";
+
+/// Link the app was launched with, held until the frontend is ready to navigate
+#[derive(Default)]
+pub struct LaunchUrl(pub Mutex<Option<String>>);
+
+/// Canonical link to a task, e.g. `todoapp://task/<id>`
+pub fn task_link(task_id: &str) -> String {
+    format!("{}://task/{}", URL_SCHEME, task_id)
+}
+
+/// Extract the task id from a `todoapp://task/<id>` link. Trailing slashes, query strings and
+/// fragments are ignored; anything else is not a task link.
+pub fn parse_task_link(url: &str) -> Option<String> {
+    let rest = url.trim().strip_prefix(URL_SCHEME)?.strip_prefix("://")?;
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let id = rest.strip_prefix("task/")?.trim_end_matches('/');
+
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+/// Bring up the main window for a `todoapp://task/<id>` link and return the task id.
+/// Unknown or deleted tasks produce a notification instead.
+pub fn focus_linked_task(app_handle: &tauri::AppHandle, db: &DbConnection, url: &str) -> Result<String, String> {
+    let task_id = parse_task_link(url).ok_or_else(|| format!("Unsupported link: {}", url))?;
+
+    let exists: bool = db.conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+        params![task_id],
+        |row| row.get(0),
+    ).unwrap_or(false);
+    if !exists {
+        let _ = crate::notifications::show_notification("Task not found", "The linked task no longer exists.");
+        return Err(format!("Task not found: {}", task_id));
+    }
+
+    if let Some(window) = app_handle.get_window("main") {
+        window.show().map_err(|e| format!("Failed to show window: {}", e))?;
+        window.unminimize().ok();
+        window.set_focus().ok();
+    }
+
+    Ok(task_id)
+}
+
+/// Handle a link while the app is running: focus the main window and emit `navigate-to-task`
+pub fn handle_url(app_handle: &tauri::AppHandle, db: &DbConnection, url: &str) -> Result<(), String> {
+    let task_id = focus_linked_task(app_handle, db, url)?;
+    if let Some(window) = app_handle.get_window("main") {
+        window.emit("navigate-to-task", task_id)
+            .map_err(|e| format!("Failed to emit navigate-to-task: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// The first `todoapp://` URL among the process arguments (how the OS hands the link to the app)
+pub fn url_from_args<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    args.into_iter()
+        .find(|arg| arg.starts_with(&format!("{}://", URL_SCHEME)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_links_round_trip() {
+        let id = "3f2b8c1e-0000-4000-8000-000000000001";
+        assert_eq!(task_link(id), format!("todoapp://task/{}", id));
+        assert_eq!(parse_task_link(&task_link(id)).as_deref(), Some(id));
+        assert_eq!(parse_task_link("todoapp://task/abc/").as_deref(), Some("abc"));
+        assert_eq!(parse_task_link("todoapp://task/abc?from=notes#top").as_deref(), Some("abc"));
+
+        assert_eq!(parse_task_link("todoapp://task/"), None);
+        assert_eq!(parse_task_link("todoapp://project/abc"), None);
+        assert_eq!(parse_task_link("todoapp://task/abc/def"), None);
+        assert_eq!(parse_task_link("https://task/abc"), None);
+
+        let args = vec!["todo-app".to_string(), "todoapp://task/abc".to_string()];
+        assert_eq!(url_from_args(args).as_deref(), Some("todoapp://task/abc"));
+    }
+}
@@ -0,0 +1,533 @@
+use crate::commands::{
+    ActivityLogEntry, Agenda, Attachment, Board, ChecklistTemplate, CompletionWarning, DependencyConflict, GroupedTags,
+    MissedReminder, PendingNotification, Project, RecentTask, SessionState, Subtask, Tag, TagGroup, Task, TaskSearchResult,
+    Template,
+};
+use crate::services::journal_service::{DailyJournal, JournalEntry};
+use crate::services::palette_service::PaletteEntry;
+use crate::services::pomodoro_service::TaskCompletionRate;
+use crate::services::priority_aging_service::PriorityAgingPreview;
+use crate::services::rules_service::Rule;
+use crate::services::stats_service::{ProjectStats, TimeEstimationRow};
+use crate::services::weekly_review_service::WeeklyReview;
+use crate::sync::SyncConflict;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+
+/// Demo/screenshot mode. While enabled, read commands return placeholder text instead of real
+/// titles and names, and everything that could change data is rejected. Never persisted, so a
+/// restart always comes back in normal mode.
+#[derive(Default)]
+pub struct DemoMode(pub AtomicBool);
+
+impl DemoMode {
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Pass a read command's result through the obfuscation layer when demo mode is on
+    pub fn apply<T: Obfuscate>(&self, mut value: T) -> T {
+        if self.is_enabled() {
+            value.obfuscate();
+        }
+        value
+    }
+}
+
+/// Whether demo mode is on, for code that runs outside a command such as the tray
+pub fn is_enabled_for(app_handle: &tauri::AppHandle) -> bool {
+    app_handle.try_state::<DemoMode>().is_some_and(|demo| demo.is_enabled())
+}
+
+/// Commands that only read data and stay available in demo mode, besides every `get_*` and
+/// `preview_*` command
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "set_demo_mode",
+    "palette_search",
+    // Skips the streak update while demo mode is on
+    "session_start",
+    "close_quick_add_window",
+    "take_launch_link",
+    "export_daily_journal_markdown",
+    "cancel_operation",
+    "open_task_link",
+    "search_tasks",
+    "find_empty_tasks",
+];
+
+/// `get_*` commands that still write or reach out to other services, so they are rejected
+const BLOCKED_READ_COMMANDS: &[&str] = &[
+    // Calls the translation service and fills the translation cache
+    "get_translation",
+];
+
+pub fn is_read_only_command(command: &str) -> bool {
+    if BLOCKED_READ_COMMANDS.contains(&command) {
+        return false;
+    }
+    command.starts_with("get_") || command.starts_with("preview_") || READ_ONLY_COMMANDS.contains(&command)
+}
+
+/// Wrap the generated invoke handler so that, in demo mode, commands that could modify data are
+/// rejected before they run
+pub fn guard<F>(handler: F) -> impl Fn(tauri::Invoke) + Send + Sync + 'static
+where
+    F: Fn(tauri::Invoke) + Send + Sync + 'static,
+{
+    move |invoke: tauri::Invoke| {
+        let command = invoke.message.command().to_string();
+        let enabled = invoke
+            .message
+            .window()
+            .try_state::<DemoMode>()
+            .is_some_and(|demo| demo.is_enabled());
+        if enabled && !is_read_only_command(&command) {
+            invoke.resolver.reject(format!("\"{}\" is not available in demo mode", command));
+            return;
+        }
+        handler(invoke)
+    }
+}
+
+const WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "labore", "magna", "aliqua", "enim", "minim", "veniam",
+    "quis", "nostrud", "exercitation", "ullamco", "laboris", "nisi", "aliquip", "commodo",
+    "consequat", "duis", "aute", "irure", "voluptate", "velit", "esse", "cillum", "fugiat",
+    "nulla", "pariatur", "excepteur", "sint", "occaecat", "cupidatat", "proident", "sunt",
+    "culpa", "officia", "deserunt", "mollit", "anim", "laborum",
+];
+
+// FNV-1a, so placeholders are the same on every run and platform
+fn seed(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// `min..=max` placeholder words picked deterministically from `key`, first letter capitalised
+pub fn placeholder(key: &str, min: usize, max: usize) -> String {
+    let mut state = seed(key);
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let count = min + (next() as usize) % (max - min + 1);
+    let text = (0..count)
+        .map(|_| WORDS[(next() as usize) % WORDS.len()])
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
+
+// Tag names are unique and lowercase; the suffix keeps two placeholders from colliding
+fn tag_placeholder(id: &str) -> String {
+    format!("{}-{:02x}", placeholder(id, 1, 1).to_lowercase(), seed(id) % 256)
+}
+
+pub trait Obfuscate {
+    fn obfuscate(&mut self);
+}
+
+impl<T: Obfuscate> Obfuscate for Vec<T> {
+    fn obfuscate(&mut self) {
+        self.iter_mut().for_each(Obfuscate::obfuscate);
+    }
+}
+
+impl<T: Obfuscate> Obfuscate for Option<T> {
+    fn obfuscate(&mut self) {
+        if let Some(value) = self {
+            value.obfuscate();
+        }
+    }
+}
+
+impl Obfuscate for Task {
+    fn obfuscate(&mut self) {
+        self.title = placeholder(&self.id, 2, 5);
+        if self.description.is_some() {
+            self.description = Some(placeholder(&format!("{}:description", self.id), 8, 16));
+        }
+        self.tags.obfuscate();
+    }
+}
+
+impl Obfuscate for RecentTask {
+    fn obfuscate(&mut self) {
+        self.task.obfuscate();
+    }
+}
+
+impl Obfuscate for TaskSearchResult {
+    fn obfuscate(&mut self) {
+        self.task.obfuscate();
+        // The snippet can come from any of the task's text, subtasks and attachments included
+        self.snippet = placeholder(&format!("{}:snippet", self.task.id), 4, 10);
+    }
+}
+
+impl Obfuscate for Board {
+    fn obfuscate(&mut self) {
+        for column in &mut self.columns {
+            column.tasks.obfuscate();
+            // Same placeholder as the task itself gets
+            for task in column.limits.iter_mut().flat_map(|usage| usage.tasks.iter_mut()) {
+                task.title = placeholder(&task.id, 2, 5);
+            }
+        }
+    }
+}
+
+impl Obfuscate for Subtask {
+    fn obfuscate(&mut self) {
+        self.title = placeholder(&self.id, 2, 4);
+    }
+}
+
+impl Obfuscate for Project {
+    fn obfuscate(&mut self) {
+        self.name = placeholder(&self.id, 1, 2);
+    }
+}
+
+impl Obfuscate for Tag {
+    fn obfuscate(&mut self) {
+        self.name = tag_placeholder(&self.id);
+    }
+}
+
+impl Obfuscate for Attachment {
+    fn obfuscate(&mut self) {
+        self.filename.clear();
+        self.path.clear();
+    }
+}
+
+impl Obfuscate for Template {
+    fn obfuscate(&mut self) {
+        self.name = placeholder(&self.id, 1, 3);
+        self.title = placeholder(&format!("{}:title", self.id), 2, 5);
+        if self.description.is_some() {
+            self.description = Some(placeholder(&format!("{}:description", self.id), 8, 16));
+        }
+    }
+}
+
+impl Obfuscate for ChecklistTemplate {
+    fn obfuscate(&mut self) {
+        self.name = placeholder(&self.id, 1, 3);
+        for (index, item) in self.items.iter_mut().enumerate() {
+            *item = placeholder(&format!("{}:{}", self.id, index), 2, 4);
+        }
+    }
+}
+
+impl Obfuscate for JournalEntry {
+    fn obfuscate(&mut self) {
+        self.title = placeholder(&self.task_id, 2, 5);
+        self.project_name = self.project_name.as_deref().map(|name| placeholder(name, 1, 2));
+        self.tags = self.tags.iter().map(|tag| tag_placeholder(tag)).collect();
+    }
+}
+
+impl Obfuscate for DailyJournal {
+    fn obfuscate(&mut self) {
+        self.entries.obfuscate();
+    }
+}
+
+impl Obfuscate for PaletteEntry {
+    fn obfuscate(&mut self) {
+        match self.kind.as_str() {
+            "project" => self.title = placeholder(&self.id, 1, 2),
+            "tag" => self.title = tag_placeholder(&self.id),
+            "template" => self.title = placeholder(&self.id, 1, 3),
+            _ => self.title = placeholder(&self.id, 2, 5),
+        }
+        // Task subtitles are project names and template subtitles are task titles; the counts
+        // shown for projects and tags are kept
+        match self.kind.as_str() {
+            "task" => self.subtitle = self.subtitle.as_deref().map(|name| placeholder(name, 1, 2)),
+            "template" => self.subtitle = Some(placeholder(&format!("{}:title", self.id), 2, 5)),
+            _ => {}
+        }
+    }
+}
+
+impl Obfuscate for ActivityLogEntry {
+    fn obfuscate(&mut self) {
+        // Titles, descriptions, notes and names; dates, priorities and the like stay as they are
+        if matches!(
+            self.action.as_str(),
+            "title_changed" | "description_changed" | "subtask_added" | "subtask_deleted" | "tag_added" | "tag_removed" | "waiting_on_changed" | "completion_note"
+        ) {
+            let id = self.id.clone();
+            for (value, side) in [(&mut self.old_value, "old"), (&mut self.new_value, "new")] {
+                if value.is_some() {
+                    *value = Some(placeholder(&format!("{}:{}", id, side), 2, 5));
+                }
+            }
+        }
+    }
+}
+
+impl Obfuscate for TimeEstimationRow {
+    fn obfuscate(&mut self) {
+        self.title = placeholder(&self.task_id, 2, 5);
+    }
+}
+
+impl Obfuscate for Agenda {
+    fn obfuscate(&mut self) {
+        self.timeline.obfuscate();
+        self.unscheduled.obfuscate();
+        self.overdue.obfuscate();
+        self.completed.obfuscate();
+    }
+}
+
+impl Obfuscate for WeeklyReview {
+    fn obfuscate(&mut self) {
+        for project in &mut self.completions_by_project {
+            if let Some(id) = &project.project_id {
+                project.project_name = Some(placeholder(id, 1, 2));
+            }
+        }
+        for deadline in &mut self.upcoming_deadlines {
+            deadline.title = placeholder(&deadline.task_id, 2, 5);
+            deadline.project_name = deadline.project_name.as_deref().map(|name| placeholder(name, 1, 2));
+        }
+        for note in &mut self.completion_notes {
+            note.title = placeholder(&note.task_id, 2, 5);
+            note.note = placeholder(&format!("{}:note", note.task_id), 4, 10);
+        }
+        for item in &mut self.waiting_items {
+            item.title = placeholder(&item.task_id, 2, 5);
+            item.waiting_on = placeholder(&format!("{}:waiting_on", item.task_id), 1, 2);
+        }
+    }
+}
+
+impl Obfuscate for MissedReminder {
+    fn obfuscate(&mut self) {
+        self.title = placeholder(&self.task_id, 2, 5);
+    }
+}
+
+impl Obfuscate for PendingNotification {
+    fn obfuscate(&mut self) {
+        self.title = placeholder(&self.task_id, 2, 5);
+    }
+}
+
+impl Obfuscate for SessionState {
+    fn obfuscate(&mut self) {
+        self.pending_notifications.obfuscate();
+    }
+}
+
+impl Obfuscate for ProjectStats {
+    fn obfuscate(&mut self) {
+        if let Some(id) = &self.project_id {
+            self.project_name = Some(placeholder(id, 1, 2));
+        }
+    }
+}
+
+impl Obfuscate for GroupedTags {
+    fn obfuscate(&mut self) {
+        for group in &mut self.groups {
+            group.group.name = placeholder(&group.group.id, 1, 2);
+            group.tags.obfuscate();
+        }
+        self.ungrouped.obfuscate();
+    }
+}
+
+impl Obfuscate for TagGroup {
+    fn obfuscate(&mut self) {
+        self.name = placeholder(&self.id, 1, 2);
+    }
+}
+
+impl Obfuscate for Rule {
+    fn obfuscate(&mut self) {
+        self.name = placeholder(&self.id, 1, 3);
+        // Project and priority conditions hold an id or a priority level, not user text
+        if matches!(self.condition.field.as_str(), "title" | "description") {
+            self.condition.value = placeholder(&format!("{}:condition", self.id), 1, 2).to_lowercase();
+        }
+        self.actions.add_tags = self.actions.add_tags.iter().map(|tag| tag_placeholder(tag)).collect();
+    }
+}
+
+impl Obfuscate for PriorityAgingPreview {
+    fn obfuscate(&mut self) {
+        for task in &mut self.tasks {
+            task.title = placeholder(&task.task_id, 2, 5);
+        }
+    }
+}
+
+impl Obfuscate for TaskCompletionRate {
+    fn obfuscate(&mut self) {
+        self.task_title = placeholder(&self.task_id, 2, 5);
+    }
+}
+
+impl Obfuscate for DependencyConflict {
+    fn obfuscate(&mut self) {
+        self.blocker_title = placeholder(&self.blocker_id, 2, 5);
+        self.blocked_title = placeholder(&self.blocked_id, 2, 5);
+    }
+}
+
+impl Obfuscate for CompletionWarning {
+    fn obfuscate(&mut self) {
+        // Reminder samples are times; the others are subtask or task titles
+        if self.kind != "pending_reminders" {
+            self.samples = self.samples.iter().map(|title| placeholder(title, 2, 4)).collect();
+        }
+    }
+}
+
+// Text fields of a task, project, tag or subtask snapshot in a sync conflict
+const SYNC_TEXT_FIELDS: &[&str] = &["title", "description", "name", "completion_note", "waiting_on", "metadata"];
+
+impl Obfuscate for SyncConflict {
+    fn obfuscate(&mut self) {
+        let entity_id = self.entity_id.clone();
+        for snapshot in [&mut self.local, &mut self.remote].into_iter().flatten() {
+            let Some(fields) = snapshot.as_object_mut() else { continue };
+            for field in SYNC_TEXT_FIELDS {
+                if let Some(value) = fields.get_mut(*field).filter(|value| value.is_string()) {
+                    *value = serde_json::Value::String(placeholder(&format!("{}:{}", entity_id, field), 1, 5));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_are_stable_per_id() {
+        let a = placeholder("task-1", 2, 5);
+        assert_eq!(a, placeholder("task-1", 2, 5));
+        assert_ne!(a, placeholder("task-2", 2, 5));
+        let words = a.split(' ').count();
+        assert!((2..=5).contains(&words));
+        assert!(a.chars().next().unwrap().is_uppercase());
+
+        let demo = DemoMode::default();
+        let project = Project { id: "p1".into(), name: "Acme Corp".into(), color: None, created_at: 0, updated_at: 0, completion_note_required: None };
+        assert_eq!(demo.apply(project.clone()).name, "Acme Corp");
+        demo.set_enabled(true);
+        let shown = demo.apply(vec![project]);
+        assert_eq!(shown[0].name, placeholder("p1", 1, 2));
+
+        let mut conflict = DependencyConflict {
+            relationship_id: "r1".into(),
+            blocker_id: "t1".into(),
+            blocker_title: "Sign the lease".into(),
+            blocker_due_at: None,
+            blocked_id: "t2".into(),
+            blocked_title: "Move in".into(),
+            blocked_due_at: 0,
+        };
+        conflict.obfuscate();
+        assert_eq!((conflict.blocker_title.as_str(), conflict.blocked_title.as_str()), (placeholder("t1", 2, 5).as_str(), placeholder("t2", 2, 5).as_str()));
+        let mut sync = SyncConflict {
+            id: "c1".into(),
+            entity: "task".into(),
+            entity_id: "t1".into(),
+            device_id: "d1".into(),
+            reason: "edited on both".into(),
+            local: Some(serde_json::json!({ "title": "Sign the lease", "priority": "high" })),
+            remote: None,
+            detected_at: 0,
+        };
+        sync.obfuscate();
+        let local = sync.local.unwrap();
+        assert_ne!(local["title"], "Sign the lease");
+        assert_eq!(local["priority"], "high");
+
+        assert!(is_read_only_command("get_tasks"));
+        assert!(is_read_only_command("set_demo_mode"));
+        assert!(!is_read_only_command("delete_task"));
+    }
+
+    #[test]
+    fn test_get_translation_is_rejected() {
+        assert!(is_read_only_command("get_translation_overrides"));
+        assert!(!is_read_only_command("get_translation"));
+    }
+
+    #[test]
+    fn test_tag_groups_are_obfuscated() {
+        let demo = DemoMode::default();
+        demo.set_enabled(true);
+        let groups = demo.apply(vec![TagGroup { id: "g1".into(), name: "Clients".into(), color: None, order_index: 0, created_at: 0 }]);
+        assert_eq!(groups[0].name, placeholder("g1", 1, 2));
+    }
+
+    #[test]
+    fn test_rules_are_obfuscated() {
+        use crate::services::rules_service::{RuleActions, RuleCondition};
+
+        let rule = |field: &str, value: &str| Rule {
+            id: "r1".into(),
+            name: "Acme invoices".into(),
+            enabled: true,
+            condition: RuleCondition { field: field.into(), operator: "contains".into(), value: value.into() },
+            actions: RuleActions { add_tags: vec!["finance".into()], set_priority: Some("high".into()), ..Default::default() },
+            order_index: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let demo = DemoMode::default();
+        demo.set_enabled(true);
+        let shown = demo.apply(vec![rule("title", "acme"), rule("priority", "high")]);
+        assert_eq!(shown[0].name, placeholder("r1", 1, 3));
+        assert_ne!(shown[0].condition.value, "acme");
+        assert_eq!(shown[0].actions.add_tags, vec![tag_placeholder("finance")]);
+        assert_eq!(shown[0].actions.set_priority.as_deref(), Some("high"));
+        assert_eq!(shown[1].condition.value, "high");
+    }
+
+    #[test]
+    fn test_priority_aging_preview_is_obfuscated() {
+        use crate::services::priority_aging_service::AgedTask;
+
+        let demo = DemoMode::default();
+        demo.set_enabled(true);
+        let preview = demo.apply(PriorityAgingPreview {
+            enabled: true,
+            threshold_days: 14,
+            exempt_undated: false,
+            tasks: vec![AgedTask {
+                task_id: "t1".into(),
+                title: "Renew passport".into(),
+                old_priority: "low".into(),
+                new_priority: "medium".into(),
+                updated_at: 0,
+            }],
+        });
+        assert_eq!(preview.tasks[0].title, placeholder("t1", 2, 5));
+        assert_eq!(preview.tasks[0].new_priority, "medium");
+    }
+}
@@ -0,0 +1,353 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+// Only the end of each log file goes into the bundle
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// User-written text replaced by anonymize_database. Tables or columns missing from an
+/// older schema are skipped.
+const ANONYMIZED_COLUMNS: &[(&str, &[&str])] = &[
+    ("tasks", &["title", "description", "completion_note", "waiting_on", "metadata"]),
+    ("projects", &["name"]),
+    ("tags", &["name"]),
+    ("tag_groups", &["name"]),
+    ("subtasks", &["title"]),
+    ("task_templates", &["name", "title", "description"]),
+    ("checklist_templates", &["name"]),
+    ("checklist_template_items", &["title"]),
+    ("translations", &["source_text", "translated_text", "source_text_hash"]),
+    ("attachments", &["filename", "path"]),
+    ("activity_log", &["old_value", "new_value"]),
+    ("rules", &["name", "condition_value"]),
+    ("project_keywords", &["term"]),
+    ("tag_term_cooccurrence", &["term"]),
+    ("sync_conflicts", &["local_json", "remote_json"]),
+];
+
+/// Settings whose values are secret or point at the user's files
+const SENSITIVE_SETTINGS: &[&str] = &[
+    "google_translate_api_key",
+    crate::sync::SYNC_FOLDER_SETTING,
+    // Private feed URLs carry an access token
+    crate::services::calendar_sync::CALENDAR_FEEDS_SETTING,
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticBundle {
+    pub path: String,
+    pub entries: Vec<String>,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct TableCount {
+    table: String,
+    rows: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct AppliedMigration {
+    name: String,
+    applied_at: i64,
+}
+
+/// Write a zip for bug reports: the schema and statistics about the data, but no row contents.
+/// `reports` are extra JSON entries (db info, health check); with `include_database`, an
+/// anonymized copy of the database is added as well.
+pub fn write_bundle(
+    conn: &Connection,
+    dest: &Path,
+    reports: &[(&str, serde_json::Value)],
+    log_dir: Option<&Path>,
+    include_database: bool,
+) -> Result<DiagnosticBundle, String> {
+    let file = fs::File::create(dest).map_err(|e| crate::storage::from_io("create the diagnostic bundle", &e).to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let mut entries = Vec::new();
+
+    add_entry(&mut zip, &mut entries, "schema.sql", schema_dump(conn)?.as_bytes())?;
+    add_json(&mut zip, &mut entries, "row_counts.json", &row_counts(conn)?)?;
+    add_json(&mut zip, &mut entries, "migrations.json", &applied_migrations(conn)?)?;
+    for (name, report) in reports {
+        add_json(&mut zip, &mut entries, &format!("{}.json", name), report)?;
+    }
+
+    if let Some(log_dir) = log_dir.filter(|dir| dir.is_dir()) {
+        let mut logs: Vec<_> = fs::read_dir(log_dir)
+            .map_err(|e| format!("Failed to read log folder: {}", e))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .collect();
+        logs.sort_by_key(|entry| entry.file_name());
+        for entry in logs {
+            let tail = read_tail(&entry.path(), LOG_TAIL_BYTES)
+                .map_err(|e| format!("Failed to read log file: {}", e))?;
+            add_entry(&mut zip, &mut entries, &format!("logs/{}", entry.file_name().to_string_lossy()), &tail)?;
+        }
+    }
+
+    if include_database {
+        let temp_dir = tempfile::TempDir::new().map_err(|e| format!("Failed to create temporary folder: {}", e))?;
+        let copy_path = temp_dir.path().join("todo_anonymized.db");
+        crate::db::backup_to_file(conn, &copy_path, |_, _| true)
+            .map_err(|e| format!("Failed to copy database: {}", e))?;
+        {
+            let copy = Connection::open(&copy_path).map_err(|e| format!("Failed to open database copy: {}", e))?;
+            anonymize_database(&copy, &uuid::Uuid::new_v4().to_string())?;
+        }
+        let bytes = fs::read(&copy_path).map_err(|e| format!("Failed to read database copy: {}", e))?;
+        add_entry(&mut zip, &mut entries, "todo_anonymized.db", &bytes)?;
+    }
+
+    let file = zip.finish().map_err(|e| format!("Failed to write diagnostic bundle: {}", e))?;
+    let size_bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    Ok(DiagnosticBundle {
+        path: dest.to_string_lossy().to_string(),
+        entries,
+        size_bytes,
+    })
+}
+
+fn add_entry(zip: &mut ZipWriter<fs::File>, entries: &mut Vec<String>, name: &str, bytes: &[u8]) -> Result<(), String> {
+    zip.start_file(name, FileOptions::default())
+        .map_err(|e| format!("Failed to write diagnostic bundle: {}", e))?;
+    zip.write_all(bytes)
+        .map_err(|e| crate::storage::from_io("write the diagnostic bundle", &e).to_string())?;
+    entries.push(name.to_string());
+    Ok(())
+}
+
+fn add_json<T: Serialize + ?Sized>(zip: &mut ZipWriter<fs::File>, entries: &mut Vec<String>, name: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(value).map_err(|e| format!("Serialization error: {}", e))?;
+    add_entry(zip, entries, name, &json)
+}
+
+fn read_tail(path: &Path, max_bytes: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    Ok(tail)
+}
+
+fn schema_dump(conn: &Connection) -> Result<String, String> {
+    let mut stmt = conn.prepare(
+        "SELECT sql FROM sqlite_master WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' ORDER BY type = 'table' DESC, name"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let statements = stmt.query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    Ok(statements.iter().map(|sql| format!("{};\n", sql)).collect::<Vec<_>>().join("\n"))
+}
+
+fn table_names(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let names = stmt.query_map([], |row| row.get(0))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("Failed to read table names: {}", e))?;
+    Ok(names)
+}
+
+fn row_counts(conn: &Connection) -> Result<Vec<TableCount>, String> {
+    table_names(conn)?
+        .into_iter()
+        .map(|table| {
+            let rows = conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| row.get(0))
+                .map_err(|e| format!("Failed to count rows in {}: {}", table, e))?;
+            Ok(TableCount { table, rows })
+        })
+        .collect()
+}
+
+fn applied_migrations(conn: &Connection) -> Result<Vec<AppliedMigration>, String> {
+    if !table_names(conn)?.iter().any(|name| name == "migrations") {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare("SELECT name, applied_at FROM migrations ORDER BY name")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let migrations = stmt.query_map([], |row| Ok(AppliedMigration { name: row.get(0)?, applied_at: row.get(1)? }))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read migrations: {}", e))?;
+    Ok(migrations)
+}
+
+fn column_names(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info(\"{}\")", table))
+        .map_err(|e| format!("Query error: {}", e))?;
+    let columns = stmt.query_map([], |row| row.get(1))
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("Failed to read columns of {}: {}", table, e))?;
+    Ok(columns)
+}
+
+/// Same input and salt give the same output, so repeated values (a tag used on many tasks,
+/// duplicate titles) stay recognisable. The salt keeps hashes from being matched against guesses.
+fn anonymize_text(salt: &str, value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(value.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    digest.chars().cycle().take(value.chars().count()).collect()
+}
+
+/// Replace user-written text in a copy of the database with same-length hashes. Overwritten
+/// pages are zeroed and the file vacuumed so the original text isn't left in free space.
+fn anonymize_database(conn: &Connection, salt: &str) -> Result<(), String> {
+    conn.execute_batch("PRAGMA secure_delete = ON;")
+        .map_err(|e| format!("Failed to enable secure delete: {}", e))?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let tables = table_names(&tx)?;
+    for (table, columns) in ANONYMIZED_COLUMNS {
+        if !tables.iter().any(|name| name == table) {
+            continue;
+        }
+        let existing = column_names(&tx, table)?;
+        for column in columns.iter().filter(|column| existing.iter().any(|name| name == *column)) {
+            let values: Vec<(i64, String)> = tx.prepare(&format!("SELECT rowid, \"{}\" FROM \"{}\" WHERE typeof(\"{}\") = 'text'", column, table, column))
+                .map_err(|e| format!("Query error: {}", e))?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| format!("Query execution error: {}", e))?
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read {}.{}: {}", table, column, e))?;
+            let mut update = tx.prepare(&format!("UPDATE \"{}\" SET \"{}\" = ?1 WHERE rowid = ?2", table, column))
+                .map_err(|e| format!("Query error: {}", e))?;
+            for (rowid, value) in values {
+                update.execute(params![anonymize_text(salt, &value), rowid])
+                    .map_err(|e| format!("Failed to anonymize {}.{}: {}", table, column, e))?;
+            }
+        }
+    }
+
+    if tables.iter().any(|name| name == "settings") {
+        let mut stmt = tx.prepare("SELECT key, value FROM settings")
+            .map_err(|e| format!("Query error: {}", e))?;
+        let sensitive: Vec<(String, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Query execution error: {}", e))?
+            .collect::<Result<Vec<(String, String)>, _>>()
+            .map_err(|e| format!("Failed to read settings: {}", e))?
+            .into_iter()
+            .filter(|(key, _)| SENSITIVE_SETTINGS.contains(&key.as_str()))
+            .collect();
+        for (key, value) in sensitive {
+            tx.execute("UPDATE settings SET value = ?1 WHERE key = ?2", params![anonymize_text(salt, &value), key])
+                .map_err(|e| format!("Failed to anonymize setting {}: {}", key, e))?;
+        }
+    }
+
+    // Full-text indexes keep the replaced words in their segments until those are merged, so
+    // they're rebuilt from the anonymized text
+    let fts_tables: Vec<String> = tx.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND sql LIKE 'CREATE VIRTUAL TABLE%USING fts5%'"
+    )
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+        .map_err(|e| format!("Failed to list full-text indexes: {}", e))?;
+    for table in fts_tables {
+        tx.execute(&format!("INSERT INTO \"{0}\" (\"{0}\") VALUES ('rebuild')", table), [])
+            .map_err(|e| format!("Failed to rebuild {}: {}", table, e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    conn.execute_batch("VACUUM;").map_err(|e| format!("Failed to vacuum database copy: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, description TEXT, priority TEXT NOT NULL);
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE migrations (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE, applied_at INTEGER NOT NULL);
+            INSERT INTO tasks VALUES ('t1', 'Call the dentist', NULL, 'high'), ('t2', 'Call the dentist', 'Ask about Tuesday', 'low');
+            INSERT INTO settings VALUES ('google_translate_api_key', 'secret-key'), ('week_starts_on', 'monday'),
+                ('calendar_feed_urls', 'https://cal.example.com/private-token123/basic.ics');
+            INSERT INTO migrations (name, applied_at) VALUES ('0001_initial.sql', 1);"
+        ).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_bundle_contains_no_row_data() {
+        let conn = setup_conn();
+        // The full-text indexes hold their own copy of the words
+        conn.execute_batch(
+            "CREATE TABLE subtasks (id TEXT PRIMARY KEY, task_id TEXT NOT NULL, title TEXT NOT NULL);
+            CREATE TABLE attachments (id TEXT PRIMARY KEY, task_id TEXT NOT NULL, filename TEXT NOT NULL);
+            ALTER TABLE tasks ADD COLUMN waiting_on TEXT;
+            UPDATE tasks SET waiting_on = 'Dr. Okafor' WHERE id = 't2';"
+        ).unwrap();
+        conn.execute_batch(include_str!("../migrations/0036_add_tasks_fts.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0038_add_subtask_attachment_fts.sql")).unwrap();
+        conn.execute_batch(
+            "INSERT INTO subtasks VALUES ('s1', 't1', 'Bring insurance card');
+            INSERT INTO attachments VALUES ('a1', 't2', 'xray_scan.png');"
+        ).unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let log_dir = dir.path().join("logs");
+        fs::create_dir(&log_dir).unwrap();
+        fs::write(log_dir.join("app.log"), "started\n").unwrap();
+        let dest = dir.path().join("bundle.zip");
+
+        let bundle = write_bundle(&conn, &dest, &[("health", serde_json::json!({ "ok": true }))], Some(&log_dir), true).unwrap();
+        assert_eq!(
+            bundle.entries,
+            vec!["schema.sql", "row_counts.json", "migrations.json", "health.json", "logs/app.log", "todo_anonymized.db"]
+        );
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&dest).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut bytes = Vec::new();
+            archive.by_name(name).unwrap().read_to_end(&mut bytes).unwrap();
+            bytes
+        };
+        let counts: serde_json::Value = serde_json::from_slice(&read("row_counts.json")).unwrap();
+        assert!(counts.as_array().unwrap().contains(&serde_json::json!({ "table": "tasks", "rows": 2 })));
+        let database = read("todo_anonymized.db");
+        for secret in ["dentist", "Tuesday", "tuesday", "about", "call", "insurance", "xray", "Okafor", "secret-key", "private-token123"] {
+            assert!(!database.windows(secret.len()).any(|window| window == secret.as_bytes()), "{} leaked", secret);
+        }
+        assert!(!String::from_utf8(read("schema.sql")).unwrap().contains("dentist"));
+    }
+
+    #[test]
+    fn test_anonymize_keeps_length_and_equality() {
+        let conn = setup_conn();
+        anonymize_database(&conn, "salt").unwrap();
+
+        let titles: Vec<(String, Option<String>, String)> = conn.prepare("SELECT title, description, priority FROM tasks ORDER BY id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(titles[0].0, titles[1].0);
+        assert_eq!(titles[0].0.len(), "Call the dentist".len());
+        assert_ne!(titles[0].0, "Call the dentist");
+        assert_eq!(titles[0].1, None);
+        assert_eq!(titles[1].1.as_deref().map(str::len), Some("Ask about Tuesday".len()));
+        assert_eq!(titles[1].2, "low");
+
+        let setting = |key: &str| -> String {
+            conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0)).unwrap()
+        };
+        assert_ne!(setting("google_translate_api_key"), "secret-key");
+        assert!(!setting(crate::services::calendar_sync::CALENDAR_FEEDS_SETTING).contains("private-token123"));
+        assert_eq!(setting("week_starts_on"), "monday");
+    }
+}
@@ -0,0 +1,415 @@
+use crate::db::DbConnection;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// How often the runner wakes up to look for due jobs
+const TICK_SECONDS: u64 = 60;
+
+/// A periodic job. The DB lock is held for the duration of `run`.
+pub struct Job {
+    pub name: &'static str,
+    pub interval_secs: i64,
+    pub run: fn(&tauri::AppHandle, &DbConnection) -> Result<(), String>,
+}
+
+/// Registered background jobs. Adding a job is one entry here.
+pub const JOBS: &[Job] = &[
+    Job { name: "due_notifications", interval_secs: 60, run: run_due_notifications },
+    Job { name: "db_file_watch", interval_secs: 60, run: run_db_file_watch },
+    Job { name: "priority_aging", interval_secs: 24 * 60 * 60, run: run_priority_aging },
+    Job { name: "project_keywords", interval_secs: 24 * 60 * 60, run: run_project_keywords },
+    Job { name: "tag_cooccurrence", interval_secs: 24 * 60 * 60, run: run_tag_cooccurrence },
+    Job { name: "folder_sync", interval_secs: 5 * 60, run: run_folder_sync },
+    Job { name: "calendar_sync", interval_secs: 30 * 60, run: run_calendar_sync },
+    Job { name: "retention", interval_secs: 24 * 60 * 60, run: run_retention },
+    Job { name: "tray_menu", interval_secs: 5 * 60, run: run_tray_menu },
+    Job { name: "tray_status", interval_secs: 60, run: run_tray_status },
+    Job { name: "storage_probe", interval_secs: 5 * 60, run: run_storage_probe },
+    Job { name: "badge_reevaluation", interval_secs: 24 * 60 * 60, run: run_badge_reevaluation },
+    Job { name: "completion_time_profile", interval_secs: 24 * 60 * 60, run: run_completion_time_profile },
+];
+
+/// Relearn the usual completion hour for smart reminders (opt-in). Checked daily, but the stored
+/// hour is only recalculated once a week.
+fn run_completion_time_profile(_app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    crate::services::completion_time_service::refresh_learned_hour(&db.conn, now(), false).map(|_| ())
+}
+
+/// After an update that adds badges, grant the ones already earned. Runs on the first tick after
+/// launch, once the frontend is listening, and does nothing when the badge set is unchanged.
+fn run_badge_reevaluation(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    use tauri::Manager;
+    
+    let granted = crate::commands::reevaluate_badges_internal(&db.conn, now())?;
+    if !granted.is_empty() {
+        app_handle
+            .emit_all("badges-retroactively-granted", &granted)
+            .map_err(|e| format!("Failed to emit badges-retroactively-granted: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Notice the app data folder filling up or losing write permission while the app runs
+fn run_storage_probe(app_handle: &tauri::AppHandle, _db: &DbConnection) -> Result<(), String> {
+    crate::storage::check_app_data_writable(app_handle);
+    Ok(())
+}
+
+/// Keep the tray's "today" list current as tasks change and the day rolls over
+fn run_tray_menu(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    crate::tray::refresh_tray_menu(app_handle, &db.conn);
+    Ok(())
+}
+
+/// Keep the tooltip's streak and today's progress current
+fn run_tray_status(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    crate::commands::refresh_tray_tooltip(app_handle, &db.conn);
+    Ok(())
+}
+
+fn run_due_notifications(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    // Toasts would show real titles; the reminders stay scheduled and fire once demo mode ends
+    if crate::demo_mode::is_enabled_for(app_handle) {
+        return Ok(());
+    }
+    crate::notifications::check_due_notifications(db)
+        .map_err(|e| format!("Failed to check due notifications: {}", e))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DbExternallyModified {
+    reason: String,
+    detected_at: Option<i64>,
+}
+
+/// Notice todo.db being changed underneath us (e.g. by a sync tool pointed at the data folder)
+fn run_db_file_watch(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    use tauri::Manager;
+    
+    if let Some(reason) = db.check_external_modification() {
+        eprintln!("{}; a reload is needed", reason);
+        app_handle
+            .emit_all("db-externally-modified", DbExternallyModified { reason, detected_at: db.external_change_at() })
+            .map_err(|e| format!("Failed to emit db-externally-modified: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Escalate tasks left untouched for `priority_aging_days` (opt-in)
+fn run_priority_aging(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    use tauri::Manager;
+    
+    // Escalations pause while on vacation
+    if crate::services::vacation_service::is_vacation_day(&db.conn, now()) {
+        return Ok(());
+    }
+    
+    let escalated = crate::services::priority_aging_service::apply_priority_aging(&db.conn, now())?;
+    if !escalated.is_empty() {
+        app_handle
+            .emit_all("priority-aging-applied", &escalated)
+            .map_err(|e| format!("Failed to emit priority-aging-applied: {}", e))?;
+    }
+    Ok(())
+}
+
+fn run_project_keywords(_app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    crate::services::project_suggestion_service::rebuild_project_keywords(&db.conn, now()).map(|_| ())
+}
+
+fn run_tag_cooccurrence(_app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    crate::services::tag_suggestion_service::rebuild_tag_cooccurrence(&db.conn, now()).map(|_| ())
+}
+
+/// Exchange change files with other devices through the sync_folder setting (opt-in)
+fn run_folder_sync(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    use tauri::Manager;
+    
+    if let Some(result) = crate::sync::run_sync(&db.conn, now())? {
+        if result.files_ingested > 0 {
+            app_handle
+                .emit_all("sync-applied", &result)
+                .map_err(|e| format!("Failed to emit sync-applied: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Set while a calendar sync thread is downloading or applying feeds
+static CALENDAR_SYNC_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Clears CALENDAR_SYNC_RUNNING when the sync thread finishes, even if it panics
+struct CalendarSyncRunning;
+
+impl Drop for CalendarSyncRunning {
+    fn drop(&mut self) {
+        CALENDAR_SYNC_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Refresh the calendar_feed_urls ICS feeds (opt-in). The downloads run on their own thread so
+/// the database isn't locked while waiting on the network; a tick that finds the previous sync
+/// still running is skipped. Feed failures are recorded against the job once the thread is done.
+fn run_calendar_sync(app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    let urls = crate::services::calendar_sync::feed_urls(&db.conn);
+    if urls.is_empty() || CALENDAR_SYNC_RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let running = CalendarSyncRunning;
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        use tauri::Manager;
+        
+        let _running = running;
+        let db = app_handle.state::<Arc<Mutex<DbConnection>>>();
+        let result = (|| -> Result<(), String> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| format!("Failed to start runtime: {}", e))?;
+            let fetched = runtime.block_on(crate::services::calendar_sync::fetch_feeds(&urls));
+            let report = {
+                let db = db.lock().map_err(|e| format!("Database lock error: {}", e))?;
+                crate::services::calendar_sync::apply_fetched(&db.conn, fetched, now())
+            };
+            if report.has_changes() {
+                app_handle
+                    .emit_all("calendar-synced", &report)
+                    .map_err(|e| format!("Failed to emit calendar-synced: {}", e))?;
+            }
+            // Feeds are named by host only: private feed URLs carry their access token
+            let failures: Vec<String> = report
+                .feeds
+                .iter()
+                .filter_map(|feed| {
+                    let error = feed.error.as_ref()?;
+                    Some(format!("{}: {}", crate::services::calendar_sync::feed_label(&feed.url), error))
+                })
+                .collect();
+            if failures.is_empty() {
+                Ok(())
+            } else {
+                Err(format!("{} of {} feeds failed ({})", failures.len(), report.feeds.len(), failures.join("; ")))
+            }
+        })();
+        if let Err(error) = result {
+            match db.lock() {
+                Ok(db) => record_late_failure(&db, "calendar_sync", &error),
+                Err(_) => eprintln!("Background job 'calendar_sync' failed: {}", error),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Prune xp_history and activity_log past their configured retention (off by default)
+fn run_retention(_app_handle: &tauri::AppHandle, db: &DbConnection) -> Result<(), String> {
+    crate::services::retention_service::prune_retained_tables(&db.conn, now()).map(|_| ())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackgroundJobStatus {
+    pub name: String,
+    pub interval_secs: i64,
+    pub last_run_at: Option<i64>,
+    pub last_success_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<i64>,
+    pub run_count: i64,
+    pub failure_count: i64,
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Run a single job, converting a panic into an error, and record the outcome in background_jobs.
+/// The panic is caught inside the caller's DB lock scope, so the mutex is never poisoned.
+pub fn run_guarded<F>(db: &DbConnection, name: &str, job: F) -> Result<(), String>
+where
+    F: FnOnce() -> Result<(), String>,
+{
+    let started_at = now();
+    let result = match catch_unwind(AssertUnwindSafe(job)) {
+        Ok(result) => result,
+        Err(payload) => Err(format!("Job panicked: {}", panic_message(payload.as_ref()))),
+    };
+
+    let recorded = match &result {
+        Ok(()) => db.conn.execute(
+            "INSERT INTO background_jobs (name, last_run_at, last_success_at, run_count, failure_count)
+             VALUES (?1, ?2, ?2, 1, 0)
+             ON CONFLICT(name) DO UPDATE SET last_run_at = ?2, last_success_at = ?2, run_count = run_count + 1",
+            params![name, started_at],
+        ),
+        Err(error) => {
+            eprintln!("Background job '{}' failed: {}", name, error);
+            db.conn.execute(
+                "INSERT INTO background_jobs (name, last_run_at, last_error, last_error_at, run_count, failure_count)
+                 VALUES (?1, ?2, ?3, ?2, 1, 1)
+                 ON CONFLICT(name) DO UPDATE SET last_run_at = ?2, last_error = ?3, last_error_at = ?2,
+                     run_count = run_count + 1, failure_count = failure_count + 1",
+                params![name, started_at, error],
+            )
+        }
+    };
+    if let Err(e) = recorded {
+        eprintln!("Failed to record status for background job '{}': {}", name, e);
+    }
+
+    result
+}
+
+/// Record a failure found by a job's own thread after the runner already recorded the run
+fn record_late_failure(db: &DbConnection, name: &str, error: &str) {
+    eprintln!("Background job '{}' failed: {}", name, error);
+    if let Err(e) = db.conn.execute(
+        "UPDATE background_jobs SET last_error = ?2, last_error_at = ?3, failure_count = failure_count + 1
+         WHERE name = ?1",
+        params![name, error, now()],
+    ) {
+        eprintln!("Failed to record status for background job '{}': {}", name, e);
+    }
+}
+
+/// Spawn the thread that drives all registered jobs
+pub fn spawn_job_runner(app_handle: tauri::AppHandle, db: Arc<Mutex<DbConnection>>) {
+    std::thread::spawn(move || {
+        let mut last_run: HashMap<&'static str, i64> = HashMap::new();
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(TICK_SECONDS));
+
+            let current_time = now();
+            for job in JOBS {
+                let due = match last_run.get(job.name) {
+                    Some(last) => current_time - last >= job.interval_secs,
+                    None => true,
+                };
+                if !due {
+                    continue;
+                }
+                last_run.insert(job.name, current_time);
+
+                if let Ok(db_lock) = db.lock() {
+                    let _ = run_guarded(&db_lock, job.name, || (job.run)(&app_handle, &db_lock));
+                }
+            }
+        }
+    });
+}
+
+pub fn get_background_job_status(db: &DbConnection) -> Result<Vec<BackgroundJobStatus>, String> {
+    let mut statuses = Vec::new();
+    for job in JOBS {
+        let status = db.conn.query_row(
+            "SELECT last_run_at, last_success_at, last_error, last_error_at, run_count, failure_count
+             FROM background_jobs WHERE name = ?1",
+            params![job.name],
+            |row| {
+                Ok(BackgroundJobStatus {
+                    name: job.name.to_string(),
+                    interval_secs: job.interval_secs,
+                    last_run_at: row.get(0)?,
+                    last_success_at: row.get(1)?,
+                    last_error: row.get(2)?,
+                    last_error_at: row.get(3)?,
+                    run_count: row.get(4)?,
+                    failure_count: row.get(5)?,
+                })
+            },
+        );
+
+        match status {
+            Ok(status) => statuses.push(status),
+            // Never ran yet
+            Err(rusqlite::Error::QueryReturnedNoRows) => statuses.push(BackgroundJobStatus {
+                name: job.name.to_string(),
+                interval_secs: job.interval_secs,
+                last_run_at: None,
+                last_success_at: None,
+                last_error: None,
+                last_error_at: None,
+                run_count: 0,
+                failure_count: 0,
+            }),
+            Err(e) => return Err(format!("Query error: {}", e)),
+        }
+    }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_db() -> DbConnection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("../migrations/0017_add_background_jobs.sql")).unwrap();
+        DbConnection::from_connection(conn)
+    }
+
+    fn job_row(db: &DbConnection, name: &str) -> (Option<i64>, Option<String>, i64, i64) {
+        db.conn.query_row(
+            "SELECT last_success_at, last_error, run_count, failure_count FROM background_jobs WHERE name = ?1",
+            params![name],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_run_guarded_catches_panics() {
+        let db = setup_test_db();
+
+        let result = run_guarded(&db, "exploding", || panic!("boom"));
+        assert_eq!(result, Err("Job panicked: boom".to_string()));
+
+        let (last_success, last_error, runs, failures) = job_row(&db, "exploding");
+        assert_eq!(last_success, None);
+        assert_eq!(last_error.as_deref(), Some("Job panicked: boom"));
+        assert_eq!((runs, failures), (1, 1));
+
+        // The runner keeps going: a later successful run is recorded too
+        assert!(run_guarded(&db, "exploding", || Ok(())).is_ok());
+        let (last_success, _, runs, failures) = job_row(&db, "exploding");
+        assert!(last_success.is_some());
+        assert_eq!((runs, failures), (2, 1));
+    }
+
+    #[test]
+    fn test_late_failure_is_recorded_against_the_run() {
+        let db = setup_test_db();
+        assert!(run_guarded(&db, "calendar_sync", || Ok(())).is_ok());
+
+        record_late_failure(&db, "calendar_sync", "1 of 2 feeds failed");
+
+        let (_, last_error, runs, failures) = job_row(&db, "calendar_sync");
+        assert_eq!(last_error.as_deref(), Some("1 of 2 feeds failed"));
+        assert_eq!((runs, failures), (1, 1));
+    }
+
+    #[test]
+    fn test_job_status_lists_registered_jobs() {
+        let db = setup_test_db();
+        let statuses = get_background_job_status(&db).unwrap();
+        assert_eq!(statuses.len(), JOBS.len());
+        assert!(statuses.iter().all(|s| s.run_count == 0 && s.last_run_at.is_none()));
+    }
+}
@@ -3,26 +3,27 @@
 
 mod db;
 mod commands;
+mod data_reset;
+mod deep_link;
+mod demo_mode;
+mod diagnostics;
 mod attachments;
 mod notifications;
+mod operations;
+mod jobs;
 mod services;
+mod storage;
+mod sync;
+mod theme;
+mod tray;
+mod window_state;
 
 use std::sync::{Arc, Mutex};
-use tauri::{Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, CustomMenuItem, GlobalShortcutManager};
+use tauri::{Manager, SystemTray, SystemTrayEvent, GlobalShortcutManager, WindowEvent};
 
 fn main() {
-    let quit = CustomMenuItem::new("quit".to_string(), "Quit");
-    let open = CustomMenuItem::new("open".to_string(), "Open App");
-    let quick_add = CustomMenuItem::new("quick_add".to_string(), "Quick Add");
-    let toggle_theme = CustomMenuItem::new("toggle_theme".to_string(), "Toggle Theme");
-    
-    let tray_menu = SystemTrayMenu::new()
-        .add_item(open)
-        .add_item(quick_add)
-        .add_item(toggle_theme)
-        .add_item(quit);
-    
-    let system_tray = SystemTray::new().with_menu(tray_menu);
+    // Today's tasks are added once the database is open
+    let system_tray = SystemTray::new().with_menu(tray::build_tray_menu(&[]));
     
     tauri::Builder::default()
         .system_tray(system_tray)
@@ -31,6 +32,11 @@ fn main() {
                 SystemTrayEvent::MenuItemClick { id, .. } => {
                     match id.as_str() {
                         "quit" => {
+                            // Persist window geometry before exiting, since close-requested never fires here
+                            if let Some(window) = app.get_window("main") {
+                                let db = app.state::<Arc<Mutex<db::DbConnection>>>();
+                                let _ = db.lock().map(|db_lock| window_state::save_window_state(&window, &db_lock));
+                            }
                             std::process::exit(0);
                         }
                         "open" => {
@@ -48,25 +54,95 @@ fn main() {
                             }
                         }
                         "toggle_theme" => {
-                            if let Some(window) = app.get_window("main") {
-                                window.emit("toggle-theme", ()).ok();
-                            }
+                            // Cycles light, dark, system and stores the choice; windows follow theme-changed
+                            let db = app.state::<Arc<Mutex<db::DbConnection>>>();
+                            if let Ok(db_lock) = db.lock() {
+                                if let Err(e) = theme::cycle_theme(app, &db_lock.conn) {
+                                    eprintln!("Failed to change theme: {}", e);
+                                }
+                            };
+                        }
+                        // Per-task actions from the "today" submenus
+                        id => {
+                            tray::handle_task_item(app, id);
                         }
-                        _ => {}
                     }
                 }
                 _ => {}
             }
         })
+        .on_window_event(|event| {
+            // An OS-level theme switch only matters while the preference is "system". Every window
+            // reports it, so only the main window's report is passed on.
+            if let WindowEvent::ThemeChanged(os_theme) = event.event() {
+                let window = event.window();
+                if window.label() != "main" {
+                    return;
+                }
+                let preference = window
+                    .try_state::<Arc<Mutex<db::DbConnection>>>()
+                    .and_then(|db| db.lock().ok().map(|db_lock| theme::get_preference(&db_lock.conn)));
+                if preference.as_deref() == Some("system") {
+                    window.emit_all(theme::THEME_CHANGED_EVENT, theme::ThemeChanged::new("system", Some(*os_theme))).ok();
+                }
+            }
+        })
         .setup(|app| {
             // Initialize database
             let app_handle = app.handle().clone();
-            let db = db::init_db(&app_handle)
-                .expect("Failed to initialize database");
+            
+            // Check up front that the data folder takes writes (full disk, lost permissions)
+            if let Some(app_data_dir) = app_handle.path_resolver().app_data_dir() {
+                let _ = std::fs::create_dir_all(&app_data_dir);
+            }
+            storage::check_app_data_writable(&app_handle);
+            
+            let db = match db::init_db(&app_handle) {
+                Ok(db) => db,
+                Err(e) => {
+                    // Exit with an explanation instead of a panic
+                    let error = storage::from_anyhow("open the database", &e);
+                    eprintln!("{}", error);
+                    let _ = notifications::show_notification("Todo App couldn't start", &error.to_string());
+                    return Err(Box::new(error));
+                }
+            };
+            
+            // One-time backfill of size/mime for attachments added before those were recorded
+            let backfilled = db.conn.query_row(
+                "SELECT value FROM settings WHERE key = 'attachment_metadata_backfilled'",
+                [],
+                |row| row.get::<_, String>(0),
+            ).is_ok();
+            if !backfilled {
+                if let Some(app_data_dir) = app_handle.path_resolver().app_data_dir() {
+                    match attachments::backfill_attachment_metadata(&db.conn, &app_data_dir) {
+                        Ok(_) => {
+                            let _ = db.conn.execute(
+                                "INSERT OR REPLACE INTO settings (key, value) VALUES ('attachment_metadata_backfilled', '1')",
+                                [],
+                            );
+                        }
+                        Err(e) => eprintln!("Failed to backfill attachment metadata: {}", e),
+                    }
+                }
+            }
             
             // Check for notifications on startup
             let _ = notifications::check_and_schedule_notifications(&app_handle, &db);
             
+            // Restore the main window's last size/position
+            if let Some(window) = app.get_window("main") {
+                let _ = window_state::restore_window_state(&window, &db);
+            }
+            
+            // Show the task being worked on in the tray tooltip
+            commands::refresh_tray_tooltip(&app_handle, &db.conn);
+            tray::refresh_tray_menu(&app_handle, &db.conn);
+            
+            // Recently used projects for the quick-add window, restored from settings
+            app.manage(services::quick_add_service::RecentProjects::load(&db.conn));
+            
             // Store database connection in app state
             let db_for_app = Arc::new(Mutex::new(db));
             let db_for_thread = db_for_app.clone();
@@ -81,31 +157,49 @@ fn main() {
             
             app.manage(db_for_app);
             
-            // Set up periodic notification checker (every minute)
-            std::thread::spawn(move || {
-                loop {
-                    std::thread::sleep(std::time::Duration::from_secs(60));
-                    if let Ok(db_lock) = db_for_thread.lock() {
-                        let _ = notifications::check_due_notifications(&db_lock);
-                    }
-                }
-            });
+            // A todoapp://task/<id> link is passed as an argument when the OS launches us for it
+            app.manage(deep_link::LaunchUrl(Mutex::new(deep_link::url_from_args(std::env::args().skip(1)))));
+            
+            // Demo mode always starts off
+            app.manage(demo_mode::DemoMode::default());
+            
+            // Translation circuit breakers start closed on every launch
+            app.manage(services::translation_service::TranslationBreakers::default());
+            
+            // Long-running commands started with an operation id, so they can be cancelled
+            app.manage(operations::Operations::default());
+            
+            // Recent deletes for undo_last_operation; kept in memory for this session only
+            app.manage(services::undo_service::UndoJournal::default());
+            
+            // Confirmation tokens for reset_app_data
+            app.manage(data_reset::ResetTokens::default());
+            
+            // Start the background job runner (due notifications and other periodic jobs)
+            jobs::spawn_job_runner(app.handle(), db_for_thread);
             
             // Register global shortcuts
             let app_handle_shortcuts = app.handle().clone();
             app.global_shortcut_manager().register("Ctrl+Shift+A", move || {
-                if let Some(window) = app_handle_shortcuts.get_window("main") {
-                    window.show().ok();
-                    window.set_focus().ok();
-                    window.emit("global-shortcut-add-task", ()).ok();
-                }
+                // Open the small quick-add window instead of raising the main window.
+                // Window creation is done off the event loop thread to avoid a deadlock on Windows.
+                let app_handle = app_handle_shortcuts.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = window_state::open_quick_add_window(&app_handle) {
+                        eprintln!("Failed to open quick-add window: {}", e);
+                    }
+                });
             }).expect("Failed to register Ctrl+Shift+A");
             
+            // Same as the tray's "Toggle Theme"
             let app_handle_theme = app.handle().clone();
             app.global_shortcut_manager().register("Ctrl+Shift+T", move || {
-                if let Some(window) = app_handle_theme.get_window("main") {
-                    window.emit("global-shortcut-toggle-theme", ()).ok();
-                }
+                let db = app_handle_theme.state::<Arc<Mutex<db::DbConnection>>>();
+                if let Ok(db_lock) = db.lock() {
+                    if let Err(e) = theme::cycle_theme(&app_handle_theme, &db_lock.conn) {
+                        eprintln!("Failed to change theme: {}", e);
+                    }
+                };
             }).expect("Failed to register Ctrl+Shift+T");
             
             let app_handle_open = app.handle().clone();
@@ -132,6 +226,10 @@ fn main() {
                     // Check if minimize to tray is enabled
                     // For now, always minimize to tray
                     if let Some(window) = app_handle_clone.get_window("main") {
+                        let db = app_handle_clone.state::<Arc<Mutex<db::DbConnection>>>();
+                        if let Ok(db_lock) = db.lock() {
+                            let _ = window_state::save_window_state(&window, &db_lock);
+                        }
                         window.hide().ok();
                     }
                 });
@@ -139,63 +237,165 @@ fn main() {
             
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(demo_mode::guard(tauri::generate_handler![
             commands::get_tasks,
+            commands::get_tasks_page,
+            commands::get_overdue_tasks,
+            commands::get_overdue_count,
+            commands::get_tasks_due_today,
+            commands::get_tasks_due_this_week,
+            commands::search_tasks,
+            commands::get_archived_tasks,
+            commands::archive_task,
+            commands::unarchive_task,
+            commands::pin_task,
+            commands::unpin_task,
+            commands::get_pinned_tasks,
+            commands::set_waiting,
+            commands::clear_waiting,
+            commands::get_recurrence_instances,
             commands::get_task,
+            commands::get_recent_tasks,
+            commands::get_agenda,
+            commands::get_daily_journal,
+            commands::export_daily_journal_markdown,
             commands::create_task,
+            commands::create_task_full,
+            commands::create_tasks_from_text,
+            commands::quick_add,
+            commands::get_recent_projects,
+            commands::close_quick_add_window,
             commands::update_task,
+            commands::get_due_date_history,
+            commands::get_task_activity_log,
+            commands::clear_task_activity_log,
             commands::delete_task,
+            commands::undo_last_operation,
+            commands::bulk_delete_tasks,
+            commands::find_empty_tasks,
+            commands::delete_empty_tasks,
+            commands::bulk_update_priority,
+            commands::shift_task_due_dates,
+            commands::duplicate_task,
+            commands::reorder_tasks,
+            commands::reorder_tasks_batch,
+            commands::reorder_task_list,
+            commands::normalize_order_indexes,
+            commands::truncate_long_descriptions,
             commands::toggle_complete,
+            commands::toggle_complete_detailed,
+            commands::skip_recurring_instance,
+            commands::update_recurring_series,
+            commands::get_completion_warnings,
+            commands::get_task_link,
+            commands::open_task_link,
+            commands::take_launch_link,
+            commands::set_current_task,
+            commands::get_current_task,
+            commands::bulk_complete_tasks,
             commands::get_projects,
             commands::create_project,
             commands::update_project,
             commands::delete_project,
+            commands::summarize_and_archive_project,
+            commands::undo_archive_summary,
+            commands::get_project_view_state,
+            commands::set_project_view_state,
+            commands::set_task_status,
+            commands::get_board,
+            commands::get_wip_limits,
+            commands::set_wip_limit,
+            commands::delete_wip_limit,
             commands::add_subtask,
+            commands::add_subtasks_bulk,
             commands::update_subtask,
             commands::delete_subtask,
             commands::get_subtasks,
             commands::get_attachments,
             commands::add_attachment,
+            commands::add_attachments_from_folder,
             commands::delete_attachment,
+            commands::get_task_thumbnails,
             commands::get_attachment,
             commands::get_attachment_path,
+            commands::save_attachment_copy,
             commands::read_attachment_file_content,
             commands::open_attachment_file,
             commands::get_settings,
             commands::update_settings,
+            commands::get_effective_theme,
             commands::create_backup,
             commands::restore_backup,
+            commands::reload_database,
+            commands::request_reset_token,
+            commands::reset_app_data,
+            commands::get_db_info,
             commands::export_data,
+            commands::export_to_csv,
             commands::import_data,
+            commands::inspect_import_file,
+            commands::import_tasks_from_file,
+            commands::import_external,
+            commands::cancel_operation,
             commands::show_notification,
             commands::get_autostart_enabled,
             commands::set_autostart_enabled,
             commands::get_completion_stats,
+            commands::get_completion_heatmap,
+            commands::get_weekly_summary,
+            commands::get_status_line,
             commands::get_priority_distribution,
             commands::get_project_stats,
             commands::get_productivity_trend,
             commands::get_most_productive_day,
             commands::get_average_completion_time,
+            commands::get_time_estimation_accuracy,
+            commands::get_project_forecast,
+            commands::get_productivity_score,
+            commands::get_productivity_score_history,
+            commands::get_completion_time_profile,
+            commands::get_learned_reminder_hour,
             commands::snooze_notification,
+            commands::get_missed_reminders,
+            commands::dismiss_missed_reminders,
             commands::create_template,
             commands::get_templates,
             commands::get_template,
             commands::update_template,
             commands::delete_template,
+            commands::get_checklists,
+            commands::create_checklist,
+            commands::update_checklist,
+            commands::delete_checklist,
+            commands::apply_checklist_to_task,
+            commands::create_checklist_from_task,
             commands::create_task_from_template,
             commands::get_user_progress,
             commands::grant_xp,
             commands::update_streak,
             commands::check_streak_on_startup,
+            commands::session_start,
+            commands::get_sidebar_counts,
+            commands::get_pending_notifications,
             commands::get_badges,
             commands::check_and_award_badges,
             commands::translate_task_content,
+            commands::set_translation_consent,
+            commands::get_external_services_status,
+            commands::export_translation_overrides,
+            commands::import_translation_overrides,
             commands::save_translation_override,
             commands::get_translation,
             commands::get_all_tags,
             commands::get_task_tags,
             commands::create_tag,
             commands::delete_tag,
+            commands::update_tag,
+            commands::get_tags_grouped,
+            commands::get_tag_groups,
+            commands::create_tag_group,
+            commands::update_tag_group,
+            commands::delete_tag_group,
             commands::add_tag_to_task,
             commands::remove_tag_from_task,
             commands::get_suggested_tags,
@@ -208,14 +408,48 @@ fn main() {
             commands::check_circular_dependency,
             commands::get_blocking_tasks,
             commands::get_blocked_tasks,
+            commands::get_dependency_conflicts,
+            commands::fix_dependency_conflict,
+            commands::get_db_health,
+            commands::generate_diagnostic_bundle,
+            commands::get_storage_breakdown,
+            commands::backfill_attachment_metadata,
+            commands::get_week_bounds,
+            commands::get_weekly_review,
+            commands::rollover_week,
+            commands::export_weekly_summary_html,
             commands::create_pomodoro_session,
             commands::get_pomodoro_stats,
             commands::get_daily_pomodoro_stats,
             commands::get_best_focus_times,
             commands::get_task_completion_rates,
             commands::get_pomodoro_streak,
+            commands::get_task_pomodoro_summary,
+            commands::palette_search,
+            commands::set_demo_mode,
+            commands::get_demo_mode,
             commands::capture_screenshot,
-        ])
+            commands::get_background_job_status,
+            commands::get_retention_status,
+            commands::sync_now,
+            commands::get_sync_conflicts,
+            commands::resolve_sync_conflict,
+            commands::sync_calendar_feeds,
+            commands::preview_priority_aging,
+            commands::suggest_project_for_task,
+            commands::suggest_tags_for_task,
+            commands::get_task_reminder_style,
+            commands::get_available_sounds,
+            commands::get_rules,
+            commands::create_rule,
+            commands::update_rule,
+            commands::delete_rule,
+            commands::test_rule,
+            commands::run_rules_on_existing,
+            commands::set_vacation_mode,
+            commands::get_vacation_periods,
+            commands::get_vacation_status,
+        ]))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,7 +1,112 @@
-use tauri::api::notification::Notification;
+use tauri::api::notification::{Notification, Sound};
 use rusqlite::params;
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Global switch for reminder sounds; notifications still show when it's off
+pub const NOTIFICATION_SOUND_SETTING: &str = "notification_sound_enabled";
+
+/// Sounds offered in the reminder settings: (id, label). Each id maps to a sound every platform ships.
+pub const AVAILABLE_SOUNDS: &[(&str, &str)] = &[
+    ("default", "System default"),
+    ("chime", "Chime"),
+    ("bell", "Bell"),
+    ("alarm", "Alarm"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub fn parse(value: &str) -> Option<Urgency> {
+        match value {
+            "low" => Some(Urgency::Low),
+            "normal" => Some(Urgency::Normal),
+            "critical" => Some(Urgency::Critical),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSound {
+    pub id: String,
+    pub label: String,
+}
+
+pub fn available_sounds() -> Vec<NotificationSound> {
+    AVAILABLE_SOUNDS
+        .iter()
+        .map(|(id, label)| NotificationSound { id: id.to_string(), label: label.to_string() })
+        .collect()
+}
+
+pub fn is_known_sound(id: &str) -> bool {
+    AVAILABLE_SOUNDS.iter().any(|(known, _)| *known == id)
+}
+
+// Platform sound names: Windows toast audio, macOS system sounds, freedesktop sound naming spec
+fn platform_sound(id: &str) -> Sound {
+    #[cfg(windows)]
+    let name = match id {
+        "chime" => "IM",
+        "bell" => "Reminder",
+        "alarm" => "Alarm",
+        _ => return Sound::Default,
+    };
+    #[cfg(target_os = "macos")]
+    let name = match id {
+        "chime" => "Glass",
+        "bell" => "Ping",
+        "alarm" => "Sosumi",
+        _ => return Sound::Default,
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let name = match id {
+        "chime" => "complete",
+        "bell" => "bell",
+        "alarm" => "alarm-clock-elapsed",
+        _ => return Sound::Default,
+    };
+    Sound::Custom(name.to_string())
+}
+
+/// The sound to play for a reminder, or None for a silent one. Low urgency is always silent;
+/// critical reminders without a chosen sound use the alarm.
+pub fn resolve_sound(urgency: Urgency, sound: Option<&str>, sound_enabled: bool) -> Option<String> {
+    if !sound_enabled || urgency == Urgency::Low {
+        return None;
+    }
+    let sound = sound.filter(|id| is_known_sound(id));
+    Some(match (sound, urgency) {
+        (Some(id), _) => id.to_string(),
+        (None, Urgency::Critical) => "alarm".to_string(),
+        (None, _) => "default".to_string(),
+    })
+}
+
+/// Notification title for the urgency. The notification API has no priority or toast scenario
+/// we can set, so critical reminders are marked in the title instead.
+pub fn title_for_urgency(title: &str, urgency: Urgency) -> String {
+    match urgency {
+        Urgency::Critical => format!("\u{26A0} {}", title),
+        _ => title.to_string(),
+    }
+}
+
+fn is_notification_sound_enabled(db: &crate::db::DbConnection) -> bool {
+    db.conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![NOTIFICATION_SOUND_SETTING],
+        |row| row.get::<_, String>(0),
+    ).map(|value| value != "false").unwrap_or(true)
+}
+
 fn now() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -29,6 +134,23 @@ pub fn show_notification(title: &str, body: &str) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
+/// Show a reminder with its urgency and sound (already resolved with `resolve_sound`)
+pub fn show_reminder_notification(
+    title: &str,
+    body: &str,
+    urgency: Urgency,
+    sound: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut notification = Notification::new("com.todoapp.dev")
+        .title(title_for_urgency(title, urgency))
+        .body(body);
+    if let Some(sound) = sound {
+        notification = notification.sound(platform_sound(sound));
+    }
+    notification.show()?;
+    Ok(())
+}
+
 /// Schedule a notification for a task based on reminder preferences
 pub fn schedule_notification(
     db: &crate::db::DbConnection,
@@ -45,41 +167,161 @@ pub fn schedule_notification(
     if let Some((due_at, notification_repeat)) = task_info {
         if let Some(due_at) = due_at {
             let reminder_minutes = reminder_minutes_before.unwrap_or(15); // Default 15 minutes
-            let scheduled_at = due_at - (reminder_minutes as i64 * 60);
+            let scheduled_at = reminder_time(&db.conn, due_at, reminder_minutes);
             let now = now();
             
             // Only schedule if notification is in the future
             if scheduled_at > now {
-                let notification_id = uuid::Uuid::new_v4().to_string();
-                
-                db.conn.execute(
+                insert_reminder_rows(&db.conn, task_id, scheduled_at, scheduled_at, notification_repeat == 1, now)?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+// When to remind about a task due at `due_at`. Normally `reminder_minutes` before it; with
+// smart_reminder_time on, a date-only task is reminded at the learned hour the day before
+// rather than minutes before midnight.
+fn reminder_time(conn: &rusqlite::Connection, due_at: i64, reminder_minutes: i32) -> i64 {
+    use crate::services::completion_time_service::{all_day_reminder_at, is_all_day, smart_reminder_hour};
+    
+    let default = due_at - (reminder_minutes as i64 * 60);
+    match smart_reminder_hour(conn) {
+        Some(hour) if is_all_day(due_at) => all_day_reminder_at(due_at, hour).unwrap_or(default),
+        _ => default,
+    }
+}
+
+// Insert the first reminder at `first_at` and, if repeat is enabled, daily reminders from the
+// day after `scheduled_at` until the task is completed
+fn insert_reminder_rows(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    first_at: i64,
+    scheduled_at: i64,
+    repeat: bool,
+    now: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT INTO notification_schedule (id, task_id, scheduled_at, snooze_until, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![uuid::Uuid::new_v4().to_string(), task_id, first_at, None::<i64>, now],
+    )?;
+    
+    if repeat {
+        let mut next_scheduled = scheduled_at + (24 * 60 * 60); // Next day
+        let max_future = now + (30 * 24 * 60 * 60); // Max 30 days ahead
+        
+        while next_scheduled <= max_future {
+            if next_scheduled > now {
+                let repeat_id = uuid::Uuid::new_v4().to_string();
+                let _ = conn.execute(
                     "INSERT INTO notification_schedule (id, task_id, scheduled_at, snooze_until, created_at)
                      VALUES (?1, ?2, ?3, ?4, ?5)",
-                    params![notification_id, task_id, scheduled_at, None::<i64>, now],
-                )?;
-                
-                // If repeat is enabled, schedule daily reminders until task is completed
-                if notification_repeat == 1 {
-                    let mut next_scheduled = scheduled_at + (24 * 60 * 60); // Next day
-                    let max_future = now + (30 * 24 * 60 * 60); // Max 30 days ahead
-                    
-                    while next_scheduled <= max_future {
-                        let repeat_id = uuid::Uuid::new_v4().to_string();
-                        let _ = db.conn.execute(
-                            "INSERT INTO notification_schedule (id, task_id, scheduled_at, snooze_until, created_at)
-                             VALUES (?1, ?2, ?3, ?4, ?5)",
-                            params![repeat_id, task_id, next_scheduled, None::<i64>, now],
-                        );
-                        next_scheduled += 24 * 60 * 60;
-                    }
-                }
+                    params![repeat_id, task_id, next_scheduled, None::<i64>, now],
+                );
             }
+            next_scheduled += 24 * 60 * 60;
         }
     }
     
     Ok(())
 }
 
+/// What happened to a task's reminder after its due date or reminder offset was edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderState {
+    /// The reminder time is still ahead
+    Scheduled,
+    /// The reminder window had already opened, so a "due soon"/"overdue" reminder goes out on
+    /// the next check instead
+    FiredImmediately,
+    /// The task was already overdue by more than the stale reminder threshold
+    SkippedPast,
+}
+
+/// Where a reminder `reminder_minutes` before `due_at` lands relative to `now`. Overdue tasks
+/// still get a reminder unless they are more than `stale_after_secs` late.
+pub fn reminder_state(due_at: i64, reminder_minutes: i32, now: i64, stale_after_secs: Option<i64>) -> ReminderState {
+    if due_at - (reminder_minutes as i64 * 60) > now {
+        ReminderState::Scheduled
+    } else if stale_after_secs.is_some_and(|stale_after| now - due_at > stale_after) {
+        ReminderState::SkippedPast
+    } else {
+        ReminderState::FiredImmediately
+    }
+}
+
+/// Replace a task's scheduled reminders after an edit. Unlike `schedule_notification`, a reminder
+/// whose time has already passed isn't dropped silently: it is queued for right now. Returns None
+/// when the task has no due date or is completed.
+pub fn reschedule_reminder(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    reminder_minutes_before: Option<i32>,
+    now: i64,
+) -> Result<Option<ReminderState>, Box<dyn std::error::Error>> {
+    conn.execute(
+        "DELETE FROM notification_schedule WHERE task_id = ?1 AND kind = 'reminder'",
+        params![task_id],
+    )?;
+    
+    let task_info: Option<(Option<i64>, i32)> = conn.query_row(
+        "SELECT due_at, notification_repeat FROM tasks WHERE id = ?1 AND completed_at IS NULL",
+        params![task_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).ok();
+    let Some((Some(due_at), notification_repeat)) = task_info else {
+        return Ok(None);
+    };
+    
+    let scheduled_at = reminder_time(conn, due_at, reminder_minutes_before.unwrap_or(15));
+    let stale_after = Some(stale_reminder_hours(conn)).filter(|hours| *hours > 0).map(|hours| hours * 60 * 60);
+    let state = reminder_state(due_at, ((due_at - scheduled_at) / 60) as i32, now, stale_after);
+    match state {
+        ReminderState::Scheduled => insert_reminder_rows(conn, task_id, scheduled_at, scheduled_at, notification_repeat == 1, now)?,
+        ReminderState::FiredImmediately => insert_reminder_rows(conn, task_id, now, scheduled_at, notification_repeat == 1, now)?,
+        ReminderState::SkippedPast => {}
+    }
+    Ok(Some(state))
+}
+
+/// Replace a waiting task's follow-up reminder with one at `follow_up_at`
+pub fn schedule_follow_up(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    follow_up_at: i64,
+    now: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    cancel_follow_up(conn, task_id)?;
+    conn.execute(
+        "INSERT INTO notification_schedule (id, task_id, scheduled_at, snooze_until, created_at, kind)
+         VALUES (?1, ?2, ?3, NULL, ?4, 'follow_up')",
+        params![uuid::Uuid::new_v4().to_string(), task_id, follow_up_at, now],
+    )?;
+    Ok(())
+}
+
+/// Drop a task's pending follow-up reminder, leaving due date reminders alone
+pub fn cancel_follow_up(conn: &rusqlite::Connection, task_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "DELETE FROM notification_schedule WHERE task_id = ?1 AND kind = 'follow_up'",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+/// Drop every pending reminder and follow-up of a task that's been archived or skipped
+pub fn cancel_task_notifications(conn: &rusqlite::Connection, task_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "DELETE FROM notification_schedule WHERE task_id = ?1 AND missed_at IS NULL",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
 /// Snooze a notification for specified duration (in minutes)
 pub fn snooze_notification(
     db: &crate::db::DbConnection,
@@ -97,60 +339,155 @@ pub fn snooze_notification(
     Ok(())
 }
 
-/// Check and send due notifications from the schedule table
-pub fn check_due_notifications(
-    db: &crate::db::DbConnection,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Check if notifications are enabled before proceeding
-    if !are_notifications_enabled(db) {
-        return Ok(());
-    }
-    
-    let now = now();
-    
-    // Get notifications that are due and not snoozed
-    let mut stmt = db.conn.prepare(
-        "SELECT ns.id, ns.task_id, t.title, t.completed_at
+/// Reminders more than this many hours overdue (e.g. after the machine slept overnight) are
+/// collected into one "missed reminders" notification instead of each getting a toast. 0 disables this.
+pub const STALE_REMINDER_HOURS_SETTING: &str = "stale_reminder_hours";
+pub const DEFAULT_STALE_REMINDER_HOURS: i64 = 6;
+
+fn stale_reminder_hours(conn: &rusqlite::Connection) -> i64 {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![STALE_REMINDER_HOURS_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| value.trim().parse::<i64>().ok())
+    .filter(|hours| *hours >= 0)
+    .unwrap_or(DEFAULT_STALE_REMINDER_HOURS)
+}
+
+pub struct DueReminder {
+    pub title: String,
+    /// The task's due date had already passed when the reminder fired
+    pub overdue: bool,
+    pub urgency: Urgency,
+    pub sound: Option<String>,
+    /// Set for follow-up reminders on a waiting task: who it is waiting on
+    pub waiting_on: Option<String>,
+}
+
+#[derive(Default)]
+pub struct DueReminders {
+    /// Due now; each gets its own toast. Their schedule rows are deleted.
+    pub fresh: Vec<DueReminder>,
+    /// Stale rows, kept with `missed_at` set so the app can list them
+    pub missed: Vec<DueReminder>,
+}
+
+/// Take the reminders due at `now` off the schedule, splitting off the ones that are more than
+/// `stale_after_secs` late (None never treats a reminder as stale). Snoozed reminders count from
+/// the end of the snooze.
+pub fn collect_due_reminders(
+    conn: &rusqlite::Connection,
+    now: i64,
+    stale_after_secs: Option<i64>,
+) -> Result<DueReminders, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT ns.id, t.title, t.reminder_sound, t.reminder_urgency,
+                MAX(ns.scheduled_at, COALESCE(ns.snooze_until, 0)), t.due_at < ?1,
+                CASE WHEN ns.kind = 'follow_up' THEN COALESCE(t.waiting_on, '') END
          FROM notification_schedule ns
          JOIN tasks t ON ns.task_id = t.id
          WHERE ns.scheduled_at <= ?1
            AND (ns.snooze_until IS NULL OR ns.snooze_until <= ?1)
-           AND t.completed_at IS NULL"
+           AND ns.missed_at IS NULL
+           AND t.completed_at IS NULL
+           AND t.archived = 0
+         ORDER BY ns.scheduled_at"
     )?;
     
     let rows = stmt.query_map([now], |row| {
         Ok((
             row.get::<_, String>(0)?,
             row.get::<_, String>(1)?,
-            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, Option<bool>>(5)?.unwrap_or(false),
+            row.get::<_, Option<String>>(6)?,
         ))
     })?;
     
-    let mut notifications_to_send = Vec::new();
-    let mut notification_ids_to_delete = Vec::new();
-    
+    let mut due = DueReminders::default();
     for row in rows {
-        if let Ok((notification_id, task_id, title)) = row {
-            notifications_to_send.push((task_id.clone(), title.clone()));
-            notification_ids_to_delete.push(notification_id);
+        let (notification_id, title, sound, urgency, due_at, overdue, waiting_on) = row?;
+        let reminder = DueReminder {
+            title,
+            overdue,
+            urgency: urgency.as_deref().and_then(Urgency::parse).unwrap_or(Urgency::Normal),
+            sound,
+            waiting_on,
+        };
+        
+        if stale_after_secs.is_some_and(|stale_after| now - due_at > stale_after) {
+            conn.execute(
+                "UPDATE notification_schedule SET missed_at = ?1 WHERE id = ?2",
+                params![now, notification_id],
+            )?;
+            due.missed.push(reminder);
+        } else {
+            // Repeating reminders have one row per day, so the next one is already scheduled
+            conn.execute("DELETE FROM notification_schedule WHERE id = ?1", params![notification_id])?;
+            due.fresh.push(reminder);
         }
     }
     
+    Ok(due)
+}
+
+fn missed_reminders_body(missed: &[DueReminder]) -> String {
+    const LISTED: usize = 3;
+    let mut titles: Vec<&str> = missed.iter().take(LISTED).map(|r| r.title.as_str()).collect();
+    if missed.len() > LISTED {
+        titles.push("…");
+    }
+    format!("{}. Open the app to review them.", titles.join(", "))
+}
+
+/// Check and send due notifications from the schedule table
+pub fn check_due_notifications(
+    db: &crate::db::DbConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Check if notifications are enabled before proceeding
+    if !are_notifications_enabled(db) {
+        return Ok(());
+    }
+    
+    let sound_enabled = is_notification_sound_enabled(db);
+    let stale_after = Some(stale_reminder_hours(&db.conn)).filter(|hours| *hours > 0).map(|hours| hours * 60 * 60);
+    let due = collect_due_reminders(&db.conn, now(), stale_after)?;
+    
     // Send notifications
-    for (_task_id, title) in &notifications_to_send {
-        let _ = show_notification(
-            "Task Reminder",
-            &format!("{} is due soon", title),
+    for reminder in &due.fresh {
+        let sound = resolve_sound(reminder.urgency, reminder.sound.as_deref(), sound_enabled);
+        let _ = show_reminder_notification(
+            if reminder.waiting_on.is_some() { "Follow Up" } else { "Task Reminder" },
+            &if let Some(waiting_on) = reminder.waiting_on.as_deref().filter(|who| !who.is_empty()) {
+                format!("Still waiting on {} for {}", waiting_on, reminder.title)
+            } else if reminder.waiting_on.is_some() {
+                format!("Still waiting on {}", reminder.title)
+            } else if reminder.overdue {
+                format!("{} is overdue", reminder.title)
+            } else {
+                format!("{} is due soon", reminder.title)
+            },
+            reminder.urgency,
+            sound.as_deref(),
         );
     }
     
-    // Delete sent notifications (non-repeating ones)
-    // For repeating notifications, we'll let them reschedule naturally
-    for notification_id in &notification_ids_to_delete {
-        let _ = db.conn.execute(
-            "DELETE FROM notification_schedule WHERE id = ?1",
-            params![notification_id],
-        );
+    // One quiet summary instead of a burst of stale toasts
+    match due.missed.len() {
+        0 => {}
+        1 => {
+            let _ = show_notification("Missed reminder", &format!("{} was due while you were away", due.missed[0].title));
+        }
+        count => {
+            let _ = show_notification(
+                &format!("You missed {} reminders while away", count),
+                &missed_reminders_body(&due.missed),
+            );
+        }
     }
     
     Ok(())
@@ -158,11 +495,13 @@ pub fn check_due_notifications(
 
 /// Check and schedule notifications for all tasks with reminder preferences
 pub fn check_and_schedule_notifications(
-    _app_handle: &tauri::AppHandle,
+    app_handle: &tauri::AppHandle,
     db: &crate::db::DbConnection,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // First, check for due notifications
-    check_due_notifications(db)?;
+    // First, check for due notifications (held back in demo mode, where toasts would show real titles)
+    if !crate::demo_mode::is_enabled_for(app_handle) {
+        check_due_notifications(db)?;
+    }
     
     // Then, schedule new notifications for tasks that need them
     let mut stmt = db.conn.prepare(
@@ -173,7 +512,7 @@ pub fn check_and_schedule_notifications(
            AND reminder_minutes_before IS NOT NULL
            AND NOT EXISTS (
                SELECT 1 FROM notification_schedule ns
-               WHERE ns.task_id = tasks.id
+               WHERE ns.task_id = tasks.id AND ns.kind = 'reminder'
            )"
     )?;
     
@@ -196,3 +535,169 @@ pub fn check_and_schedule_notifications(
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                completed_at INTEGER,
+                due_at INTEGER,
+                notification_repeat INTEGER DEFAULT 0,
+                reminder_sound TEXT,
+                reminder_urgency TEXT NOT NULL DEFAULT 'normal',
+                archived INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE notification_schedule (
+                id TEXT PRIMARY KEY NOT NULL,
+                task_id TEXT NOT NULL,
+                scheduled_at INTEGER NOT NULL,
+                snooze_until INTEGER,
+                created_at INTEGER NOT NULL
+            );",
+        ).unwrap();
+        conn.execute_batch(include_str!("../migrations/0028_add_missed_reminders.sql")).unwrap();
+        conn.execute_batch(include_str!("../migrations/0037_add_waiting_status.sql")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_coalesces_stale_reminders_after_sleep() {
+        let conn = setup_test_conn();
+        let now = 1_700_000_000;
+        let hour = 60 * 60;
+        conn.execute_batch("INSERT INTO tasks (id, title) VALUES ('t1', 'Pay rent'), ('t2', 'Call mum'), ('t3', 'Ship release');").unwrap();
+        let schedule = |id: &str, task_id: &str, scheduled_at: i64, snooze_until: Option<i64>| {
+            conn.execute(
+                "INSERT INTO notification_schedule (id, task_id, scheduled_at, snooze_until, created_at) VALUES (?1, ?2, ?3, ?4, 0)",
+                params![id, task_id, scheduled_at, snooze_until],
+            ).unwrap();
+        };
+        // Ten daily repeats that piled up over a long sleep, plus one due a minute ago
+        for day in 1..=10 {
+            schedule(&format!("old{}", day), "t1", now - day * 24 * hour, None);
+        }
+        schedule("recent", "t2", now - 60, None);
+        // Snoozed until half an hour ago: counts from the snooze, so it's still fresh
+        schedule("snoozed", "t3", now - 12 * hour, Some(now - 30 * 60));
+        schedule("future", "t3", now + hour, None);
+        
+        let due = collect_due_reminders(&conn, now, Some(6 * hour)).unwrap();
+        let fresh: Vec<&str> = due.fresh.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(fresh, vec!["Ship release", "Call mum"]);
+        assert_eq!(due.missed.len(), 10);
+        assert!(missed_reminders_body(&due.missed).starts_with("Pay rent, Pay rent, Pay rent, …"));
+        
+        // Fresh rows are gone, missed rows stay for the in-app list, the future one is untouched
+        let remaining: Vec<(String, Option<i64>)> = conn
+            .prepare("SELECT id, missed_at FROM notification_schedule ORDER BY scheduled_at")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining.len(), 11);
+        assert!(remaining[..10].iter().all(|(_, missed_at)| *missed_at == Some(now)));
+        assert_eq!(remaining[10], ("future".to_string(), None));
+        
+        // Nothing fires twice
+        let again = collect_due_reminders(&conn, now + 60, Some(6 * hour)).unwrap();
+        assert!(again.fresh.is_empty() && again.missed.is_empty());
+        
+        // With coalescing off, late reminders are toasted as before
+        schedule("late", "t2", now - 24 * hour, None);
+        let due = collect_due_reminders(&conn, now + 60, None).unwrap();
+        assert_eq!((due.fresh.len(), due.missed.len()), (1, 0));
+    }
+
+    #[test]
+    fn test_smart_reminder_time_for_all_day_tasks() {
+        use chrono::{Local, TimeZone};
+        use crate::services::completion_time_service::{LEARNED_HOUR_SETTING, SMART_REMINDER_TIME_SETTING};
+        
+        let conn = setup_test_conn();
+        let now = Local.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).earliest().unwrap().timestamp();
+        let all_day = Local.with_ymd_and_hms(2024, 3, 15, 0, 0, 0).earliest().unwrap().timestamp();
+        let timed = Local.with_ymd_and_hms(2024, 3, 15, 14, 0, 0).earliest().unwrap().timestamp();
+        conn.execute_batch("INSERT INTO tasks (id, title) VALUES ('t1', 'Pay rent');").unwrap();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, '20'), (?2, 'true')",
+            params![LEARNED_HOUR_SETTING, SMART_REMINDER_TIME_SETTING],
+        ).unwrap();
+        let scheduled_for = |due_at: i64| -> i64 {
+            conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = 't1'", params![due_at]).unwrap();
+            assert_eq!(reschedule_reminder(&conn, "t1", Some(15), now).unwrap(), Some(ReminderState::Scheduled));
+            conn.query_row("SELECT scheduled_at FROM notification_schedule WHERE task_id = 't1'", [], |row| row.get(0)).unwrap()
+        };
+        
+        // The evening before rather than 23:45
+        let evening_before = Local.with_ymd_and_hms(2024, 3, 14, 20, 0, 0).earliest().unwrap().timestamp();
+        assert_eq!(scheduled_for(all_day), evening_before);
+        // Tasks with a time keep the usual offset
+        assert_eq!(scheduled_for(timed), timed - 15 * 60);
+        
+        conn.execute("UPDATE settings SET value = 'false' WHERE key = ?1", params![SMART_REMINDER_TIME_SETTING]).unwrap();
+        assert_eq!(scheduled_for(all_day), all_day - 15 * 60);
+    }
+    
+    #[test]
+    fn test_reschedule_reminder_after_due_date_edit() {
+        let conn = setup_test_conn();
+        let now = 1_700_000_000;
+        let hour = 60 * 60;
+        conn.execute("INSERT INTO tasks (id, title) VALUES ('t1', 'Pay rent')", []).unwrap();
+        let reschedule_for = |due_at: i64| {
+            conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = 't1'", params![due_at]).unwrap();
+            let state = reschedule_reminder(&conn, "t1", Some(30), now).unwrap();
+            let rows: Vec<i64> = conn
+                .prepare("SELECT scheduled_at FROM notification_schedule WHERE task_id = 't1'")
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            (state, rows)
+        };
+        
+        // Far enough ahead: scheduled as usual
+        assert_eq!(reschedule_for(now + 2 * hour), (Some(ReminderState::Scheduled), vec![now + 2 * hour - 30 * 60]));
+        // Moved into the reminder window: due soon, right away
+        assert_eq!(reschedule_for(now + 10 * 60), (Some(ReminderState::FiredImmediately), vec![now]));
+        // Exactly now
+        assert_eq!(reschedule_for(now), (Some(ReminderState::FiredImmediately), vec![now]));
+        // Recently past: overdue reminder, worded as such
+        assert_eq!(reschedule_for(now - hour), (Some(ReminderState::FiredImmediately), vec![now]));
+        let due = collect_due_reminders(&conn, now, Some(6 * hour)).unwrap();
+        assert!(due.fresh[0].overdue);
+        // Long past: nothing to remind about
+        assert_eq!(reschedule_for(now - 2 * 24 * hour), (Some(ReminderState::SkippedPast), vec![]));
+        
+        // With stale coalescing turned off, even old due dates get a reminder
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, '0')", params![STALE_REMINDER_HOURS_SETTING]).unwrap();
+        assert_eq!(reschedule_for(now - 2 * 24 * hour).0, Some(ReminderState::FiredImmediately));
+        
+        conn.execute("UPDATE tasks SET completed_at = ?1", params![now]).unwrap();
+        assert_eq!(reschedule_for(now + 2 * hour), (None, vec![]));
+    }
+    
+    #[test]
+    fn test_reminder_sound_and_title_by_urgency() {
+        assert_eq!(resolve_sound(Urgency::Normal, None, true).as_deref(), Some("default"));
+        assert_eq!(resolve_sound(Urgency::Normal, Some("chime"), true).as_deref(), Some("chime"));
+        assert_eq!(resolve_sound(Urgency::Normal, Some("unknown"), true).as_deref(), Some("default"));
+        assert_eq!(resolve_sound(Urgency::Critical, None, true).as_deref(), Some("alarm"));
+        assert_eq!(resolve_sound(Urgency::Low, Some("chime"), true), None);
+        // The global setting silences everything
+        assert_eq!(resolve_sound(Urgency::Critical, Some("bell"), false), None);
+
+        assert_eq!(title_for_urgency("Task Reminder", Urgency::Normal), "Task Reminder");
+        assert!(title_for_urgency("Task Reminder", Urgency::Critical).ends_with(" Task Reminder"));
+        assert_eq!(Urgency::parse("urgent"), None);
+    }
+}
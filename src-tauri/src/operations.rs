@@ -0,0 +1,211 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// Long-running commands take an optional `operation_id`. With one, they emit `operation-progress`
+/// while working and `operation-finished` at the end, and can be stopped with cancel_operation.
+pub const PROGRESS_EVENT: &str = "operation-progress";
+pub const FINISHED_EVENT: &str = "operation-finished";
+/// Error returned by an operation that stopped because it was cancelled
+pub const OPERATION_CANCELLED_ERROR: &str = "operation_cancelled";
+
+// Progress within a stage is throttled; stage changes are always reported
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationProgress {
+    pub operation_id: String,
+    pub stage: String,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationFinished {
+    pub operation_id: String,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+/// Running operations, and whether each has been asked to stop
+#[derive(Default)]
+pub struct Operations(Mutex<HashMap<String, bool>>);
+
+impl Operations {
+    fn start(&self, operation_id: &str) {
+        if let Ok(mut running) = self.0.lock() {
+            running.insert(operation_id.to_string(), false);
+        }
+    }
+
+    fn finish(&self, operation_id: &str) {
+        if let Ok(mut running) = self.0.lock() {
+            running.remove(operation_id);
+        }
+    }
+
+    /// Ask a running operation to stop at its next safe point; false if it isn't running
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.0.lock() {
+            Ok(mut running) => match running.get_mut(operation_id) {
+                Some(cancelled) => {
+                    *cancelled = true;
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    fn is_cancelled(&self, operation_id: &str) -> bool {
+        self.0
+            .lock()
+            .map(|running| running.get(operation_id).copied().unwrap_or(false))
+            .unwrap_or(false)
+    }
+}
+
+/// Where a long-running helper reports how far it got. Stage boundaries are where a
+/// cancellation takes effect, so helpers only start a stage when stopping there is safe.
+pub(crate) trait ProgressReporter {
+    /// Begin a stage of `total` items; errors with OPERATION_CANCELLED_ERROR if cancelled
+    fn stage(&mut self, stage: &str, total: usize) -> Result<(), String>;
+    /// `done` items of the current stage are finished
+    fn progress(&mut self, done: usize);
+    /// Whether a cancellation was requested, for helpers that can stop between stages
+    fn cancelled(&self) -> bool;
+}
+
+/// For callers that don't track progress (sync, tests)
+pub(crate) struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn stage(&mut self, _stage: &str, _total: usize) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn progress(&mut self, _done: usize) {}
+
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+struct EventReporter<'a> {
+    app_handle: &'a tauri::AppHandle,
+    operations: &'a Operations,
+    operation_id: String,
+    stage: String,
+    total: usize,
+    last_emit: Option<Instant>,
+}
+
+impl EventReporter<'_> {
+    fn emit(&mut self, done: usize) {
+        self.last_emit = Some(Instant::now());
+        self.app_handle
+            .emit_all(
+                PROGRESS_EVENT,
+                OperationProgress {
+                    operation_id: self.operation_id.clone(),
+                    stage: self.stage.clone(),
+                    done,
+                    total: self.total,
+                },
+            )
+            .ok();
+    }
+}
+
+impl ProgressReporter for EventReporter<'_> {
+    fn stage(&mut self, stage: &str, total: usize) -> Result<(), String> {
+        if !self.stage.is_empty() {
+            self.emit(self.total);
+        }
+        if self.cancelled() {
+            return Err(OPERATION_CANCELLED_ERROR.to_string());
+        }
+        self.stage = stage.to_string();
+        self.total = total;
+        self.emit(0);
+        Ok(())
+    }
+
+    fn progress(&mut self, done: usize) {
+        if done >= self.total || self.last_emit.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+            self.emit(done);
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        self.operations.is_cancelled(&self.operation_id)
+    }
+}
+
+/// Run a long-running command body. Without an operation id this is a plain call; with one,
+/// progress is emitted as events and `operation-finished` carries the result or error.
+pub(crate) fn run<T, F>(
+    app_handle: &tauri::AppHandle,
+    operations: &Operations,
+    operation_id: Option<String>,
+    body: F,
+) -> Result<T, String>
+where
+    T: Serialize,
+    F: FnOnce(&mut dyn ProgressReporter) -> Result<T, String>,
+{
+    let operation_id = match operation_id {
+        Some(operation_id) => operation_id,
+        None => return body(&mut NoProgress),
+    };
+
+    operations.start(&operation_id);
+    let mut reporter = EventReporter {
+        app_handle,
+        operations,
+        operation_id: operation_id.clone(),
+        stage: String::new(),
+        total: 0,
+        last_emit: None,
+    };
+    let result = body(&mut reporter);
+    if result.is_ok() && !reporter.stage.is_empty() {
+        reporter.emit(reporter.total);
+    }
+    operations.finish(&operation_id);
+
+    app_handle
+        .emit_all(
+            FINISHED_EVENT,
+            OperationFinished {
+                operation_id,
+                result: result.as_ref().ok().and_then(|value| serde_json::to_value(value).ok()),
+                error: result.as_ref().err().cloned(),
+            },
+        )
+        .ok();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_only_running_operations() {
+        let operations = Operations::default();
+        assert!(!operations.cancel("import-1"));
+
+        operations.start("import-1");
+        assert!(!operations.is_cancelled("import-1"));
+        assert!(operations.cancel("import-1"));
+        assert!(operations.is_cancelled("import-1"));
+
+        operations.finish("import-1");
+        assert!(!operations.is_cancelled("import-1"));
+        assert!(!operations.cancel("import-1"));
+    }
+}
@@ -0,0 +1,442 @@
+use crate::services::week_service;
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// ICS feed URLs to import, one per line; none configured means calendar sync is off
+pub const CALENDAR_FEEDS_SETTING: &str = "calendar_feed_urls";
+/// What happens to a task whose event disappeared from its feed: "complete" (default) or "delete"
+pub const CALENDAR_REMOVED_SETTING: &str = "calendar_removed_events";
+/// Every feed's tasks go into this project, created on first sync
+pub const CALENDAR_PROJECT_NAME: &str = "Calendar";
+
+const FETCH_TIMEOUT_SECONDS: u64 = 30;
+
+/// One VEVENT from a feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    /// Local midnight for all-day events
+    pub start: Option<i64>,
+}
+
+/// How one feed went
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedSyncResult {
+    pub url: String,
+    pub created: usize,
+    pub updated: usize,
+    /// Completed or deleted, per calendar_removed_events
+    pub removed: usize,
+    /// Why the feed was skipped; its tasks are left untouched then
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarSyncReport {
+    pub feeds: Vec<FeedSyncResult>,
+}
+
+impl CalendarSyncReport {
+    pub fn has_changes(&self) -> bool {
+        self.feeds.iter().any(|feed| feed.created + feed.updated + feed.removed > 0)
+    }
+}
+
+fn get_setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+        .ok()
+}
+
+/// The configured feed URLs, without blanks or duplicates
+pub fn feed_urls(conn: &rusqlite::Connection) -> Vec<String> {
+    let mut seen = HashSet::new();
+    get_setting(conn, CALENDAR_FEEDS_SETTING)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|url| !url.is_empty() && seen.insert(url.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn delete_removed_events(conn: &rusqlite::Connection) -> bool {
+    get_setting(conn, CALENDAR_REMOVED_SETTING).is_some_and(|value| value.trim() == "delete")
+}
+
+// webcal:// is the same feed over https
+async fn fetch_feed(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch feed: {}", e.without_url()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Feed returned HTTP {}", status));
+    }
+    response.text().await.map_err(|e| format!("Failed to read feed: {}", e.without_url()))
+}
+
+/// A feed's host, for logs: the rest of a private feed URL is as good as a password
+pub fn feed_label(url: &str) -> String {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    reqwest::Url::parse(&url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "calendar feed".to_string())
+}
+
+/// Download every feed; a failure only affects its own entry
+pub async fn fetch_feeds(urls: &[String]) -> Vec<(String, Result<String, String>)> {
+    let client = match reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(FETCH_TIMEOUT_SECONDS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            let error = format!("Failed to create HTTP client: {}", e);
+            return urls.iter().map(|url| (url.clone(), Err(error.clone()))).collect();
+        }
+    };
+    let mut fetched = Vec::new();
+    for url in urls {
+        fetched.push((url.clone(), fetch_feed(&client, url).await));
+    }
+    fetched
+}
+
+// Undo RFC 5545 text escaping
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+// Dates without a time are all-day and land on local midnight. Times with a TZID are read as
+// local time, since there's no time zone database to resolve them with.
+fn parse_ics_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if !value.contains('T') {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(week_service::local_midnight);
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok().map(|naive| naive.and_utc().timestamp());
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local.from_local_datetime(&naive).earliest().map(|datetime| datetime.timestamp())
+}
+
+/// The events in an ICS document. Cancelled events and edits to single occurrences of a
+/// recurring event (RECURRENCE-ID) are left out; events without a UID can't be tracked and are
+/// skipped too.
+pub fn parse_ics(text: &str) -> Result<Vec<CalendarEvent>, String> {
+    if !text.contains("BEGIN:VCALENDAR") {
+        return Err("Not an iCalendar feed".to_string());
+    }
+
+    // Continuation lines start with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<HashMap<String, String>> = None;
+    for line in lines {
+        match line.trim_end() {
+            "BEGIN:VEVENT" => current = Some(HashMap::new()),
+            "END:VEVENT" => {
+                let Some(props) = current.take() else { continue };
+                let value = |name: &str| props.get(name).map(String::as_str);
+                let cancelled = value("STATUS").is_some_and(|status| status.eq_ignore_ascii_case("CANCELLED"));
+                let Some(uid) = value("UID").map(str::trim).filter(|uid| !uid.is_empty()) else { continue };
+                if cancelled || props.contains_key("RECURRENCE-ID") {
+                    continue;
+                }
+                let summary = value("SUMMARY").map(unescape_text).unwrap_or_default();
+                events.push(CalendarEvent {
+                    uid: uid.to_string(),
+                    summary: if summary.trim().is_empty() { "(No title)".to_string() } else { summary.trim().to_string() },
+                    description: value("DESCRIPTION").map(unescape_text).filter(|text| !text.trim().is_empty()),
+                    start: value("DTSTART").and_then(parse_ics_time),
+                });
+            }
+            line => {
+                let Some(props) = current.as_mut() else { continue };
+                let Some((key, value)) = line.split_once(':') else { continue };
+                // Parameters such as TZID or VALUE=DATE aren't needed
+                let name = key.split(';').next().unwrap_or(key);
+                props.entry(name.to_uppercase()).or_insert_with(|| value.to_string());
+            }
+        }
+    }
+    Ok(events)
+}
+
+fn calendar_project_id(conn: &rusqlite::Connection, now: i64) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM projects WHERE LOWER(name) = LOWER(?1) ORDER BY created_at LIMIT 1",
+            params![CALENDAR_PROJECT_NAME],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Query error: {}", e))?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO projects (id, name, color, created_at, updated_at) VALUES (?1, ?2, NULL, ?3, ?3)",
+        params![id, CALENDAR_PROJECT_NAME, now],
+    )
+    .map_err(|e| format!("Failed to create project: {}", e))?;
+    Ok(id)
+}
+
+struct SyncedTask {
+    id: String,
+    title: String,
+    description: Option<String>,
+    due_at: Option<i64>,
+    completed: bool,
+}
+
+// Tasks created from `url`, by event UID
+fn synced_tasks(conn: &rusqlite::Connection, url: &str) -> Result<HashMap<String, SyncedTask>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT json_extract(metadata, '$.calendar_event.uid'), id, title, description, due_at, completed_at IS NOT NULL
+            FROM tasks
+            WHERE json_valid(metadata) AND json_extract(metadata, '$.calendar_event.feed') = ?1",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![url], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                SyncedTask {
+                    id: row.get(1)?,
+                    title: row.get(2)?,
+                    description: row.get(3)?,
+                    due_at: row.get(4)?,
+                    completed: row.get(5)?,
+                },
+            ))
+        })
+        .map_err(|e| format!("Query error: {}", e))?;
+    rows.collect::<Result<HashMap<_, _>, _>>().map_err(|e| format!("Row error: {}", e))
+}
+
+/// Bring the tasks of one feed in line with its events, in a single transaction. Tasks the user
+/// completed stay completed; only the title, description and due date follow the feed.
+pub fn apply_feed(
+    conn: &rusqlite::Connection,
+    url: &str,
+    events: &[CalendarEvent],
+    now: i64,
+) -> Result<FeedSyncResult, String> {
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut result = FeedSyncResult { url: url.to_string(), created: 0, updated: 0, removed: 0, error: None };
+    let mut existing = synced_tasks(&tx, url)?;
+    let mut project_id: Option<String> = None;
+
+    for event in events {
+        crate::commands::validate_description(&tx, event.description.as_deref())
+            .map_err(|e| format!("Event \"{}\": {}", event.summary, e))?;
+        match existing.remove(&event.uid) {
+            Some(task) => {
+                if task.title != event.summary || task.description != event.description || task.due_at != event.start {
+                    tx.execute(
+                        "UPDATE tasks SET title = ?1, description = ?2, due_at = ?3, updated_at = ?4 WHERE id = ?5",
+                        params![event.summary, event.description, event.start, now, task.id],
+                    )
+                    .map_err(|e| format!("Failed to update task: {}", e))?;
+                    result.updated += 1;
+                }
+            }
+            None => {
+                let project_id = match &project_id {
+                    Some(id) => id.clone(),
+                    None => project_id.insert(calendar_project_id(&tx, now)?).clone(),
+                };
+                let metadata = serde_json::json!({ "calendar_event": { "feed": url, "uid": event.uid } }).to_string();
+                tx.execute(
+                    "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, project_id, order_index, metadata)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?5, 'medium', ?6, ?7, ?8)",
+                    params![
+                        uuid::Uuid::new_v4().to_string(),
+                        event.summary,
+                        event.description,
+                        event.start,
+                        now,
+                        project_id,
+                        crate::commands::next_order_index(&tx, Some(&project_id))?,
+                        metadata
+                    ],
+                )
+                .map_err(|e| format!("Failed to create task: {}", e))?;
+                result.created += 1;
+            }
+        }
+    }
+
+    // Whatever is left was removed upstream
+    let delete = delete_removed_events(&tx);
+    for task in existing.into_values() {
+        if delete {
+            if crate::commands::delete_task_internal(&tx, &task.id)? {
+                result.removed += 1;
+            }
+        } else if !task.completed {
+            tx.execute(
+                "UPDATE tasks SET completed_at = ?1, updated_at = ?1 WHERE id = ?2",
+                params![now, task.id],
+            )
+            .map_err(|e| format!("Failed to complete task: {}", e))?;
+            result.removed += 1;
+        }
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(result)
+}
+
+/// Apply what fetch_feeds downloaded. A feed that failed to download or parse is reported and
+/// its tasks are left alone, rather than treated as if all its events were removed.
+pub fn apply_fetched(
+    conn: &rusqlite::Connection,
+    fetched: Vec<(String, Result<String, String>)>,
+    now: i64,
+) -> CalendarSyncReport {
+    let feeds = fetched
+        .into_iter()
+        .map(|(url, body)| {
+            match body.and_then(|text| parse_ics(&text)).and_then(|events| apply_feed(conn, &url, &events, now)) {
+                Ok(result) => result,
+                Err(error) => FeedSyncResult { url, created: 0, updated: 0, removed: 0, error: Some(error) },
+            }
+        })
+        .collect();
+    CalendarSyncReport { feeds }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+        BEGIN:VEVENT\r\nUID:standup@example.com\r\nSUMMARY:Team standup\\, daily\r\nDTSTART:20240603T070000Z\r\n\
+        DESCRIPTION:Room 4\\nBring notes\r\nEND:VEVENT\r\n\
+        BEGIN:VEVENT\r\nUID:holiday@example.com\r\nSUMMARY:Public\r\n  holiday\r\nDTSTART;VALUE=DATE:20240610\r\nEND:VEVENT\r\n\
+        BEGIN:VEVENT\r\nUID:gone@example.com\r\nSUMMARY:Cancelled\r\nSTATUS:CANCELLED\r\nEND:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, color TEXT, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, description TEXT, due_at INTEGER, created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL, priority TEXT NOT NULL DEFAULT 'medium', completed_at INTEGER, project_id TEXT,
+                order_index INTEGER DEFAULT 0, metadata TEXT);
+            CREATE TABLE tags (id TEXT PRIMARY KEY, usage_count INTEGER DEFAULT 0);
+            CREATE TABLE task_tags (task_id TEXT, tag_id TEXT);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn titles(conn: &rusqlite::Connection) -> Vec<(String, bool)> {
+        conn.prepare("SELECT title, completed_at IS NOT NULL FROM tasks ORDER BY title")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_ics_events() {
+        let events = parse_ics(FEED).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Team standup, daily");
+        assert_eq!(events[0].description.as_deref(), Some("Room 4\nBring notes"));
+        assert_eq!(events[0].start, Some(1717398000));
+        assert_eq!(events[1].summary, "Public holiday");
+        assert_eq!(events[1].start, Some(week_service::local_midnight(NaiveDate::from_ymd_opt(2024, 6, 10).unwrap())));
+        assert!(parse_ics("<html>Not found</html>").is_err());
+    }
+
+    #[test]
+    fn test_feed_sync_creates_updates_and_removes() {
+        let conn = setup_test_conn();
+        let url = "https://example.com/team.ics";
+        let mut events = parse_ics(FEED).unwrap();
+
+        let result = apply_feed(&conn, url, &events, 100).unwrap();
+        assert_eq!((result.created, result.updated, result.removed), (2, 0, 0));
+        let project: String = conn.query_row("SELECT name FROM projects", [], |row| row.get(0)).unwrap();
+        assert_eq!(project, CALENDAR_PROJECT_NAME);
+
+        // Unchanged events are left alone; a renamed one is updated, a missing one completed
+        events[0].summary = "Standup".to_string();
+        let result = apply_feed(&conn, url, &events[..1], 200).unwrap();
+        assert_eq!((result.created, result.updated, result.removed), (0, 1, 1));
+        assert_eq!(titles(&conn), vec![("Public holiday".to_string(), true), ("Standup".to_string(), false)]);
+
+        // A failed download doesn't count as every event being removed
+        let report = apply_fetched(&conn, vec![(url.to_string(), Err("timed out".to_string()))], 300);
+        assert_eq!(report.feeds[0].error.as_deref(), Some("timed out"));
+        assert!(!report.has_changes());
+
+        conn.execute("INSERT INTO settings VALUES (?1, 'delete')", params![CALENDAR_REMOVED_SETTING]).unwrap();
+        let result = apply_feed(&conn, url, &[], 400).unwrap();
+        assert_eq!(result.removed, 2);
+        assert!(titles(&conn).is_empty());
+    }
+
+    #[test]
+    fn test_feed_urls_skip_blanks_and_duplicates() {
+        let conn = setup_test_conn();
+        conn.execute(
+            "INSERT INTO settings VALUES (?1, ?2)",
+            params![CALENDAR_FEEDS_SETTING, "https://a.test/cal.ics\n\n  webcal://b.test/x.ics \nhttps://a.test/cal.ics"],
+        )
+        .unwrap();
+        assert_eq!(feed_urls(&conn), vec!["https://a.test/cal.ics", "webcal://b.test/x.ics"]);
+    }
+
+    #[test]
+    fn test_feed_label_hides_path_and_token() {
+        assert_eq!(feed_label("https://calendar.example.com/private-a1b2c3/basic.ics?token=secret"), "calendar.example.com");
+        assert_eq!(feed_label("webcal://b.test/x.ics"), "b.test");
+        assert_eq!(feed_label("not a url"), "calendar feed");
+    }
+}
@@ -0,0 +1,228 @@
+use chrono::{Duration, Local, NaiveTime, TimeZone, Timelike};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Completions older than this are left out of the profile
+pub const PROFILE_DAYS: i64 = 90;
+/// Below this many completions there is no reliable modal hour to learn
+pub const MIN_COMPLETIONS: i64 = 10;
+/// How often the learned hour is recalculated
+pub const RELEARN_INTERVAL_SECS: i64 = 7 * DAY_SECONDS;
+
+/// Opt-in: remind about date-only tasks at the learned hour instead of minutes before midnight
+pub const SMART_REMINDER_TIME_SETTING: &str = "smart_reminder_time";
+pub const LEARNED_HOUR_SETTING: &str = "learned_reminder_hour";
+const LEARNED_AT_SETTING: &str = "learned_reminder_hour_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionTimeProfile {
+    /// Completions per local hour of day, index 0 = midnight to 1am
+    pub hours: Vec<i64>,
+    pub total_completions: i64,
+    /// Hour with the most completions; None until there are MIN_COMPLETIONS
+    pub modal_hour: Option<u32>,
+    pub days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedReminderHour {
+    pub enabled: bool,
+    pub hour: Option<u32>,
+    pub learned_at: Option<i64>,
+}
+
+fn get_setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).ok()
+}
+
+fn set_setting(conn: &rusqlite::Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    ).map_err(|e| format!("Failed to save setting: {}", e))?;
+    Ok(())
+}
+
+pub fn is_enabled(conn: &rusqlite::Connection) -> bool {
+    get_setting(conn, SMART_REMINDER_TIME_SETTING).as_deref() == Some("true")
+}
+
+/// Histogram of completion hours (local time) over the PROFILE_DAYS before `now`
+pub fn get_completion_time_profile(conn: &rusqlite::Connection, now: i64) -> Result<CompletionTimeProfile, String> {
+    let mut stmt = conn
+        .prepare("SELECT completed_at FROM tasks WHERE completed_at >= ?1 AND completed_at <= ?2")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![now - PROFILE_DAYS * DAY_SECONDS, now], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut hours = vec![0i64; 24];
+    for row in rows {
+        let completed_at = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        if let Some(local) = Local.timestamp_opt(completed_at, 0).single() {
+            hours[local.hour() as usize] += 1;
+        }
+    }
+
+    let total_completions: i64 = hours.iter().sum();
+    // Ties go to the later hour, which is the safer side for a reminder
+    let modal_hour = (0..24u32)
+        .max_by_key(|hour| (hours[*hour as usize], *hour))
+        .filter(|_| total_completions >= MIN_COMPLETIONS);
+
+    Ok(CompletionTimeProfile {
+        hours,
+        total_completions,
+        modal_hour,
+        days: PROFILE_DAYS,
+    })
+}
+
+/// Recalculate and store the learned hour if smart reminders are on and the stored value is
+/// more than RELEARN_INTERVAL_SECS old (or `force`). Returns the hour now in effect.
+pub fn refresh_learned_hour(conn: &rusqlite::Connection, now: i64, force: bool) -> Result<Option<u32>, String> {
+    if !is_enabled(conn) {
+        return Ok(None);
+    }
+    let learned_at = get_setting(conn, LEARNED_AT_SETTING).and_then(|v| v.parse::<i64>().ok());
+    if !force && learned_at.is_some_and(|at| now - at < RELEARN_INTERVAL_SECS) {
+        return Ok(learned_hour(conn));
+    }
+
+    let profile = get_completion_time_profile(conn, now)?;
+    match profile.modal_hour {
+        Some(hour) => set_setting(conn, LEARNED_HOUR_SETTING, &hour.to_string())?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = ?1", params![LEARNED_HOUR_SETTING])
+                .map_err(|e| format!("Failed to save setting: {}", e))?;
+        }
+    }
+    set_setting(conn, LEARNED_AT_SETTING, &now.to_string())?;
+    Ok(profile.modal_hour)
+}
+
+fn learned_hour(conn: &rusqlite::Connection) -> Option<u32> {
+    get_setting(conn, LEARNED_HOUR_SETTING)
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|hour| *hour < 24)
+}
+
+/// The learned hour, if smart reminders are on and one has been learned
+pub fn smart_reminder_hour(conn: &rusqlite::Connection) -> Option<u32> {
+    if is_enabled(conn) {
+        learned_hour(conn)
+    } else {
+        None
+    }
+}
+
+/// Learned hour for the settings page, learning it first if the setting was just turned on
+pub fn get_learned_reminder_hour(conn: &rusqlite::Connection, now: i64) -> Result<LearnedReminderHour, String> {
+    let enabled = is_enabled(conn);
+    if enabled && get_setting(conn, LEARNED_AT_SETTING).is_none() {
+        refresh_learned_hour(conn, now, true)?;
+    }
+    Ok(LearnedReminderHour {
+        enabled,
+        hour: learned_hour(conn),
+        learned_at: get_setting(conn, LEARNED_AT_SETTING).and_then(|v| v.parse::<i64>().ok()),
+    })
+}
+
+/// Whether `due_at` is a date without a time, which quick add and the date picker store as
+/// local midnight
+pub fn is_all_day(due_at: i64) -> bool {
+    Local
+        .timestamp_opt(due_at, 0)
+        .single()
+        .is_some_and(|due| due.time() == NaiveTime::MIN)
+}
+
+/// When to remind about an all-day task due at `due_at`: the learned hour on the evening (or
+/// morning) before, so the reminder still comes ahead of the due date
+pub fn all_day_reminder_at(due_at: i64, hour: u32) -> Option<i64> {
+    let due = Local.timestamp_opt(due_at, 0).single()?;
+    let day_before = due.date_naive() - Duration::days(1);
+    Local
+        .from_local_datetime(&day_before.and_time(NaiveTime::from_hms_opt(hour, 0, 0)?))
+        .earliest()
+        .map(|at| at.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (id TEXT PRIMARY KEY, completed_at INTEGER);
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn at(days_ago: i64, hour: u32, now: i64) -> i64 {
+        let date = Local.timestamp_opt(now, 0).unwrap().date_naive() - Duration::days(days_ago);
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::from_hms_opt(hour, 30, 0).unwrap()))
+            .earliest()
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn test_learns_modal_completion_hour_weekly() {
+        let conn = setup_conn();
+        let now = Local::now().timestamp();
+        let insert = |id: &str, completed_at: i64| {
+            conn.execute("INSERT INTO tasks (id, completed_at) VALUES (?1, ?2)", params![id, completed_at]).unwrap();
+        };
+        for day in 1..=6 {
+            insert(&format!("evening{}", day), at(day, 20, now));
+        }
+        for day in 1..=4 {
+            insert(&format!("morning{}", day), at(day, 9, now));
+        }
+        // Too old to count
+        for day in 0..5 {
+            insert(&format!("old{}", day), at(PROFILE_DAYS + 2 + day, 9, now));
+        }
+
+        let profile = get_completion_time_profile(&conn, now).unwrap();
+        assert_eq!(profile.total_completions, 10);
+        assert_eq!((profile.hours[20], profile.hours[9]), (6, 4));
+        assert_eq!(profile.modal_hour, Some(20));
+
+        // Nothing is learned until the setting is on
+        assert_eq!(refresh_learned_hour(&conn, now, false).unwrap(), None);
+        set_setting(&conn, SMART_REMINDER_TIME_SETTING, "true").unwrap();
+        let learned = get_learned_reminder_hour(&conn, now).unwrap();
+        assert_eq!((learned.enabled, learned.hour), (true, Some(20)));
+        assert_eq!(smart_reminder_hour(&conn), Some(20));
+
+        // Mornings take over, but the stored hour only moves once a week has passed
+        for day in 1..=5 {
+            insert(&format!("late-morning{}", day), at(day, 9, now));
+        }
+        assert_eq!(refresh_learned_hour(&conn, now + DAY_SECONDS, false).unwrap(), Some(20));
+        assert_eq!(refresh_learned_hour(&conn, now + RELEARN_INTERVAL_SECS, false).unwrap(), Some(9));
+    }
+
+    #[test]
+    fn test_all_day_reminder_lands_on_the_day_before() {
+        let due = Local.with_ymd_and_hms(2024, 3, 15, 0, 0, 0).earliest().unwrap().timestamp();
+        assert!(is_all_day(due));
+        assert!(!is_all_day(due + 9 * 60 * 60));
+
+        let remind = Local.timestamp_opt(all_day_reminder_at(due, 20).unwrap(), 0).unwrap();
+        assert_eq!(remind.format("%Y-%m-%d %H:%M").to_string(), "2024-03-14 20:00");
+    }
+}
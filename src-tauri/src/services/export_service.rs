@@ -0,0 +1,368 @@
+use chrono::{Local, TimeZone, Timelike};
+use std::collections::HashMap;
+
+/// File formats export_data can write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    /// "json" (the default when none is given), "csv" or "markdown"
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|value| value.trim().to_lowercase()).as_deref() {
+            None | Some("json") => Ok(Self::Json),
+            Some("csv") => Ok(Self::Csv),
+            Some("markdown") | Some("md") => Ok(Self::Markdown),
+            Some(other) => Err(format!("Unknown export format: {} (expected json, csv or markdown)", other)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// A task with its project name and tag names resolved
+#[derive(Debug, Clone)]
+pub struct ExportTask {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: String,
+    pub due_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub project_name: Option<String>,
+    /// Alphabetical
+    pub tags: Vec<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Every task, in list order
+pub fn load_tasks(conn: &rusqlite::Connection) -> Result<Vec<ExportTask>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.title, t.description, t.priority, t.due_at, t.completed_at, p.name, t.created_at, t.updated_at
+            FROM tasks t
+            LEFT JOIN projects p ON p.id = t.project_id
+            ORDER BY t.order_index, t.created_at",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let mut tasks = stmt
+        .query_map([], |row| {
+            Ok(ExportTask {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                description: row.get(2)?,
+                priority: row.get(3)?,
+                due_at: row.get(4)?,
+                completed_at: row.get(5)?,
+                project_name: row.get(6)?,
+                tags: Vec::new(),
+                created_at: row.get(7)?,
+                updated_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| format!("Query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row error: {}", e))?;
+
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    let mut stmt = conn
+        .prepare(
+            "SELECT tt.task_id, tg.name FROM task_tags tt
+            JOIN tags tg ON tg.id = tt.tag_id
+            ORDER BY tg.name COLLATE NOCASE",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Query error: {}", e))?;
+    for row in rows {
+        let (task_id, name) = row.map_err(|e| format!("Row error: {}", e))?;
+        tags.entry(task_id).or_default().push(name);
+    }
+    for task in &mut tasks {
+        if let Some(names) = tags.remove(&task.id) {
+            task.tags = names;
+        }
+    }
+    Ok(tasks)
+}
+
+// Leading characters that make spreadsheets read a cell as a formula
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Quote a CSV value when it holds a comma, quote or line break; quotes inside are doubled.
+/// A value that would start a formula gets a leading `'` so spreadsheets show it as text.
+pub fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// One CSV line (with its line ending) from already-formatted values
+pub fn csv_row<S: AsRef<str>>(values: &[S]) -> String {
+    let mut line = values.iter().map(|value| csv_field(value.as_ref())).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+// Local time, RFC 3339, so spreadsheets and scripts can both read it
+pub fn format_timestamp(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default()
+}
+
+const CSV_HEADER: [&str; 10] = [
+    "id", "title", "description", "project", "tags", "priority", "due_date", "completed", "completed_at", "created_at",
+];
+
+/// One row per task; tags are joined with ", " inside a single (quoted) field
+pub fn render_csv(tasks: &[ExportTask]) -> String {
+    let mut csv = csv_row(&CSV_HEADER);
+    for task in tasks {
+        csv.push_str(&csv_row(&[
+            task.id.clone(),
+            task.title.clone(),
+            task.description.clone().unwrap_or_default(),
+            task.project_name.clone().unwrap_or_default(),
+            task.tags.join(", "),
+            task.priority.clone(),
+            task.due_at.map(format_timestamp).unwrap_or_default(),
+            task.completed_at.is_some().to_string(),
+            task.completed_at.map(format_timestamp).unwrap_or_default(),
+            format_timestamp(task.created_at),
+        ]));
+    }
+    csv
+}
+
+/// Columns export_to_csv can write, by the name used in its header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskColumn {
+    Id,
+    Title,
+    Description,
+    Priority,
+    DueDate,
+    Completed,
+    ProjectName,
+    Tags,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl TaskColumn {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "id" => Ok(Self::Id),
+            "title" => Ok(Self::Title),
+            "description" => Ok(Self::Description),
+            "priority" => Ok(Self::Priority),
+            "due_date" => Ok(Self::DueDate),
+            "completed" => Ok(Self::Completed),
+            "project_name" => Ok(Self::ProjectName),
+            "tags" => Ok(Self::Tags),
+            "created_at" => Ok(Self::CreatedAt),
+            "updated_at" => Ok(Self::UpdatedAt),
+            other => Err(format!(
+                "Unknown export column: {} (expected id, title, description, priority, due_date, completed, project_name, tags, created_at or updated_at)",
+                other
+            )),
+        }
+    }
+
+    /// At least one column, each one known
+    pub fn parse_list(values: &[String]) -> Result<Vec<Self>, String> {
+        if values.is_empty() {
+            return Err("Pick at least one column to export".to_string());
+        }
+        values.iter().map(|value| Self::parse(value)).collect()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Title => "title",
+            Self::Description => "description",
+            Self::Priority => "priority",
+            Self::DueDate => "due_date",
+            Self::Completed => "completed",
+            Self::ProjectName => "project_name",
+            Self::Tags => "tags",
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+        }
+    }
+
+    fn value(self, task: &ExportTask) -> String {
+        match self {
+            Self::Id => task.id.clone(),
+            Self::Title => task.title.clone(),
+            Self::Description => task.description.clone().unwrap_or_default(),
+            Self::Priority => task.priority.clone(),
+            Self::DueDate => task.due_at.map(format_timestamp).unwrap_or_default(),
+            Self::Completed => task.completed_at.is_some().to_string(),
+            Self::ProjectName => task.project_name.clone().unwrap_or_default(),
+            Self::Tags => task.tags.join(", "),
+            Self::CreatedAt => format_timestamp(task.created_at),
+            Self::UpdatedAt => format_timestamp(task.updated_at),
+        }
+    }
+}
+
+/// Like render_csv, but only `columns`, in the order given
+pub fn render_csv_columns(tasks: &[ExportTask], columns: &[TaskColumn]) -> String {
+    let mut csv = csv_row(&columns.iter().map(|column| column.as_str()).collect::<Vec<_>>());
+    for task in tasks {
+        csv.push_str(&csv_row(&columns.iter().map(|column| column.value(task)).collect::<Vec<_>>()));
+    }
+    csv
+}
+
+// All-day due dates are stored as local midnight; those show just the date
+fn format_due(timestamp: i64) -> String {
+    match Local.timestamp_opt(timestamp, 0).single() {
+        Some(dt) if dt.hour() == 0 && dt.minute() == 0 => dt.format("%Y-%m-%d").to_string(),
+        Some(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        None => String::new(),
+    }
+}
+
+// Line breaks would end the list item, so they're folded into spaces
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A checklist per project (alphabetical, tasks without a project last), due dates in local time
+pub fn render_markdown(tasks: &[ExportTask]) -> String {
+    let mut groups: Vec<(Option<&str>, Vec<&ExportTask>)> = Vec::new();
+    for task in tasks {
+        let project = task.project_name.as_deref();
+        match groups.iter_mut().find(|(name, _)| *name == project) {
+            Some((_, members)) => members.push(task),
+            None => groups.push((project, vec![task])),
+        }
+    }
+    groups.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    let mut markdown = String::from("# Tasks\n");
+    if groups.is_empty() {
+        markdown.push_str("\n_No tasks._\n");
+    }
+    for (project, members) in groups {
+        markdown.push_str(&format!("\n## {}\n\n", project.unwrap_or("No project")));
+        for task in members {
+            let check = if task.completed_at.is_some() { "x" } else { " " };
+            markdown.push_str(&format!("- [{}] {}", check, single_line(&task.title)));
+            if let Some(due) = task.due_at {
+                markdown.push_str(&format!(" (due {})", format_due(due)));
+            }
+            for tag in &task.tags {
+                markdown.push_str(&format!(" #{}", tag));
+            }
+            markdown.push('\n');
+            if let Some(description) = task.description.as_deref().filter(|text| !text.trim().is_empty()) {
+                markdown.push_str(&format!("  {}\n", single_line(description)));
+            }
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, title: &str, project: Option<&str>) -> ExportTask {
+        ExportTask {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: None,
+            priority: "medium".to_string(),
+            due_at: None,
+            completed_at: None,
+            project_name: project.map(str::to_string),
+            tags: Vec::new(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_csv_quotes_commas_quotes_and_newlines() {
+        let mut first = task("t1", "Say \"hi\", then leave", Some("Home"));
+        first.description = Some("line one\nline two".to_string());
+        first.tags = vec!["a".to_string(), "b".to_string()];
+        let csv = render_csv(&[first, task("t2", "plain", None)]);
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], "id,title,description,project,tags,priority,due_date,completed,completed_at,created_at");
+        assert!(lines[1].starts_with("t1,\"Say \"\"hi\"\", then leave\",\"line one\nline two\",Home,\"a, b\",medium,,false,,"));
+        assert!(lines[2].starts_with("t2,plain,,,,medium,,false,,"));
+    }
+
+    #[test]
+    fn test_csv_neutralizes_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"http://x.test\")"), "\"'=HYPERLINK(\"\"http://x.test\"\")\"");
+        assert_eq!(csv_field("+1 call"), "'+1 call");
+        assert_eq!(csv_field("-rf"), "'-rf");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("\tindented"), "'\tindented");
+        assert_eq!(csv_field("\rnote"), "\"'\rnote\"");
+        assert_eq!(csv_field("a = b"), "a = b");
+    }
+
+    #[test]
+    fn test_csv_columns_in_requested_order() {
+        let mut first = task("t1", "Buy milk, eggs", Some("Home"));
+        first.completed_at = Some(1);
+        first.tags = vec!["shop".to_string()];
+        let columns = TaskColumn::parse_list(&["tags".to_string(), "title".to_string(), "completed".to_string()]).unwrap();
+        let csv = render_csv_columns(&[first, task("t2", "plain", None)], &columns);
+        assert_eq!(csv, "tags,title,completed\r\nshop,\"Buy milk, eggs\",true\r\n,plain,false\r\n");
+        assert!(TaskColumn::parse_list(&[]).is_err());
+        assert!(TaskColumn::parse_list(&["title".to_string(), "status".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_markdown_groups_by_project() {
+        let due = Local.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap().timestamp();
+        let timed = Local.with_ymd_and_hms(2024, 6, 2, 14, 30, 0).unwrap().timestamp();
+        let mut done = task("t1", "Pay rent", Some("home"));
+        done.completed_at = Some(1);
+        done.due_at = Some(due);
+        let mut call = task("t2", "Call\nbank", Some("Errands"));
+        call.due_at = Some(timed);
+        call.tags = vec!["phone".to_string()];
+        let markdown = render_markdown(&[task("t3", "Loose end", None), done, call]);
+        assert_eq!(
+            markdown,
+            "# Tasks\n\n## Errands\n\n- [ ] Call bank (due 2024-06-02 14:30) #phone\n\n## home\n\n- [x] Pay rent (due 2024-06-01)\n\n## No project\n\n- [ ] Loose end\n"
+        );
+        assert!(ExportFormat::parse(Some("xml")).is_err());
+        assert_eq!(ExportFormat::parse(None).unwrap(), ExportFormat::Json);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::services::import_service::{self, MappedTask};
+use crate::services::week_service;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// Exports import_external understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalSource {
+    /// One project's "Export as CSV" from Todoist. The file has no project column, so the
+    /// project is named after the file.
+    TodoistCsv,
+    /// TickTick's "Backup" CSV, all lists in one file
+    TicktickCsv,
+}
+
+/// A row that wasn't imported. Row numbers are records in the file, the header included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedImportRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+/// Tasks read from an export, ready to insert
+#[derive(Debug, Default)]
+pub struct ExternalRows {
+    pub tasks: Vec<MappedTask>,
+    /// Rows that aren't tasks (Todoist sections, empty rows)
+    pub skipped: usize,
+    pub failed_rows: Vec<FailedImportRow>,
+    /// Tasks imported with a value left out, by row
+    pub warnings: Vec<String>,
+}
+
+// Position of a column, ignoring case and surrounding spaces
+fn column(header: &[String], name: &str) -> Option<usize> {
+    header.iter().position(|column| column.trim().eq_ignore_ascii_case(name))
+}
+
+fn cell(record: &[String], index: Option<usize>) -> &str {
+    index.and_then(|index| record.get(index)).map_or("", |value| value.trim())
+}
+
+/// Due dates as the exports write them: what parse_due reads, "+0000"-style offsets, and
+/// Todoist's "5 Jan 2024" / "Jan 5 2024" forms with an optional time
+pub fn parse_external_due(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Some(timestamp) = import_service::parse_due(value) {
+        return Some(timestamp);
+    }
+    if let Ok(datetime) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%z") {
+        return Some(datetime.timestamp());
+    }
+    let value = value.replace(',', "");
+    for pattern in ["%d %b %Y %H:%M", "%b %d %Y %H:%M", "%d %B %Y %H:%M", "%B %d %Y %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(&value, pattern) {
+            return Local.from_local_datetime(&naive).earliest().map(|datetime| datetime.timestamp());
+        }
+    }
+    ["%d %b %Y", "%b %d %Y", "%d %B %Y", "%B %d %Y"]
+        .iter()
+        .find_map(|pattern| NaiveDate::parse_from_str(&value, pattern).ok())
+        .map(week_service::local_midnight)
+}
+
+// A due value that couldn't be read; recurring ones get their own message since they're common
+fn due_warning(row: usize, value: &str) -> String {
+    if value.to_lowercase().starts_with("every") {
+        format!("Row {}: recurring due date \"{}\" isn't supported; imported without a due date", row, value)
+    } else {
+        format!("Row {}: couldn't read due date \"{}\"; imported without one", row, value)
+    }
+}
+
+// Todoist writes priority 4 for p1 (the highest) down to 1 for "no priority", which is our
+// default
+fn todoist_priority(value: &str) -> Option<&'static str> {
+    match value {
+        "" | "1" => Some("medium"),
+        "2" => Some("low"),
+        "3" => Some("medium"),
+        "4" => Some("high"),
+        _ => None,
+    }
+}
+
+// TickTick: 0 none, 1 low, 3 medium, 5 high
+fn ticktick_priority(value: &str) -> Option<&'static str> {
+    match value {
+        "" | "0" | "3" => Some("medium"),
+        "1" => Some("low"),
+        "5" => Some("high"),
+        _ => None,
+    }
+}
+
+// Todoist keeps labels in the task text as "@label"; they become tags and leave the title
+fn split_todoist_labels(content: &str) -> (String, Vec<String>) {
+    let mut labels = Vec::new();
+    let mut words = Vec::new();
+    for word in content.split_whitespace() {
+        match word.strip_prefix('@') {
+            Some(label) if !label.is_empty() => labels.push(label.to_string()),
+            _ => words.push(word),
+        }
+    }
+    (words.join(" "), labels)
+}
+
+/// Todoist project CSV: TYPE, CONTENT, DESCRIPTION, PRIORITY, DATE, ... Task rows become tasks
+/// in `project`; a note row is added to the description of the task above it.
+pub fn read_todoist_csv(text: &str, project: Option<&str>) -> Result<ExternalRows, String> {
+    let mut records = import_service::parse_csv(text)?.into_iter();
+    let header = records.next().ok_or("The file is empty")?;
+    let (kind, content) = match (column(&header, "TYPE"), column(&header, "CONTENT")) {
+        (Some(kind), Some(content)) => (kind, content),
+        _ => return Err("Not a Todoist CSV export: the TYPE and CONTENT columns are missing".to_string()),
+    };
+    let description = column(&header, "DESCRIPTION");
+    let priority = column(&header, "PRIORITY");
+    let date = column(&header, "DATE");
+
+    let mut rows = ExternalRows::default();
+    for (index, record) in records.enumerate() {
+        let row = index + 2;
+        match cell(&record, Some(kind)).to_lowercase().as_str() {
+            "task" => {}
+            "note" => {
+                let note = cell(&record, Some(content));
+                match rows.tasks.last_mut() {
+                    Some(task) if !note.is_empty() => {
+                        task.description = Some(match task.description.take() {
+                            Some(existing) => format!("{}\n\n{}", existing, note),
+                            None => note.to_string(),
+                        });
+                    }
+                    _ => rows.skipped += 1,
+                }
+                continue;
+            }
+            _ => {
+                rows.skipped += 1;
+                continue;
+            }
+        }
+
+        let (title, tags) = split_todoist_labels(cell(&record, Some(content)));
+        if title.is_empty() {
+            rows.failed_rows.push(FailedImportRow { row, reason: "The task has no text".to_string() });
+            continue;
+        }
+        let priority_value = cell(&record, priority);
+        let task_priority = todoist_priority(priority_value).unwrap_or_else(|| {
+            rows.warnings.push(format!("Row {}: unknown priority \"{}\"; imported as medium", row, priority_value));
+            "medium"
+        });
+        let due_value = cell(&record, date);
+        let due_at = if due_value.is_empty() {
+            None
+        } else {
+            let parsed = parse_external_due(due_value);
+            if parsed.is_none() {
+                rows.warnings.push(due_warning(row, due_value));
+            }
+            parsed
+        };
+
+        rows.tasks.push(MappedTask {
+            title,
+            description: Some(cell(&record, description).to_string()).filter(|text| !text.is_empty()),
+            due_at,
+            priority: task_priority.to_string(),
+            tags,
+            project: project.map(str::to_string),
+            completed_at: None,
+        });
+    }
+    Ok(rows)
+}
+
+/// TickTick backup CSV. A few lines of export details come before the header, which is found
+/// by its "Title" and "List Name" columns. Lists become projects; completed tasks keep their
+/// completion time (or `now` when it's missing).
+pub fn read_ticktick_csv(text: &str, now: i64) -> Result<ExternalRows, String> {
+    let records = import_service::parse_csv(text)?;
+    let header_index = records
+        .iter()
+        .position(|record| column(record, "Title").is_some() && column(record, "List Name").is_some())
+        .ok_or("Not a TickTick backup: no header row with Title and List Name columns")?;
+    let header = &records[header_index];
+    let title = column(header, "Title");
+    let list = column(header, "List Name");
+    let tags = column(header, "Tags");
+    let content = column(header, "Content");
+    let due = column(header, "Due Date");
+    let all_day = column(header, "Is All Day");
+    let priority = column(header, "Priority");
+    let status = column(header, "Status");
+    let completed_time = column(header, "Completed Time");
+
+    let mut rows = ExternalRows::default();
+    for (index, record) in records.iter().enumerate().skip(header_index + 1) {
+        let row = index + 1;
+        let task_title = cell(record, title);
+        if task_title.is_empty() {
+            rows.failed_rows.push(FailedImportRow { row, reason: "The task has no title".to_string() });
+            continue;
+        }
+        let priority_value = cell(record, priority);
+        let task_priority = ticktick_priority(priority_value).unwrap_or_else(|| {
+            rows.warnings.push(format!("Row {}: unknown priority \"{}\"; imported as medium", row, priority_value));
+            "medium"
+        });
+        let due_value = cell(record, due);
+        let due_at = if due_value.is_empty() {
+            None
+        } else {
+            match parse_external_due(due_value) {
+                // All-day dates are midnight in the exporting time zone; keep just the day
+                Some(timestamp) if cell(record, all_day).eq_ignore_ascii_case("true") => Local
+                    .timestamp_opt(timestamp, 0)
+                    .single()
+                    .map(|datetime| week_service::local_midnight(datetime.date_naive())),
+                Some(timestamp) => Some(timestamp),
+                None => {
+                    rows.warnings.push(due_warning(row, due_value));
+                    None
+                }
+            }
+        };
+        let completed_at = match cell(record, status) {
+            "" | "0" => None,
+            _ => Some(parse_external_due(cell(record, completed_time)).unwrap_or(now)),
+        };
+
+        rows.tasks.push(MappedTask {
+            title: task_title.to_string(),
+            description: Some(cell(record, content).to_string()).filter(|text| !text.is_empty()),
+            due_at,
+            priority: task_priority.to_string(),
+            tags: cell(record, tags)
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            project: Some(cell(record, list).to_string()).filter(|name| !name.is_empty()),
+            completed_at,
+        });
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_todoist_csv() {
+        let csv = "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE\n\
+            section,Errands,,,,,,,,\n\
+            task,Buy milk @shopping @quick,,4,1,Me,,2024-05-01,en,Europe/Berlin\n\
+            note,Semi-skimmed,,,,,,,,\n\
+            task,Water plants,Balcony too,1,1,Me,,every monday,en,Europe/Berlin\n\
+            task,@orphan,,2,1,Me,,,en,Europe/Berlin\n\
+            task,Call bank,,9,1,Me,,5 Jan 2024 10:00,en,Europe/Berlin\n";
+        let rows = read_todoist_csv(csv, Some("Home")).unwrap();
+        assert_eq!(rows.skipped, 1);
+        assert_eq!(rows.failed_rows, vec![FailedImportRow { row: 6, reason: "The task has no text".to_string() }]);
+
+        let milk = &rows.tasks[0];
+        assert_eq!((milk.title.as_str(), milk.priority.as_str()), ("Buy milk", "high"));
+        assert_eq!(milk.tags, vec!["shopping", "quick"]);
+        assert_eq!(milk.description.as_deref(), Some("Semi-skimmed"));
+        assert_eq!(milk.due_at, Some(week_service::local_midnight(NaiveDate::from_ymd_opt(2024, 5, 1).unwrap())));
+        assert_eq!(milk.project.as_deref(), Some("Home"));
+
+        assert_eq!((rows.tasks[1].priority.as_str(), rows.tasks[1].due_at), ("medium", None));
+        let call = &rows.tasks[2];
+        assert_eq!(call.due_at, Some(Local.with_ymd_and_hms(2024, 1, 5, 10, 0, 0).unwrap().timestamp()));
+        assert_eq!(rows.warnings, vec![
+            "Row 5: recurring due date \"every monday\" isn't supported; imported without a due date",
+            "Row 7: unknown priority \"9\"; imported as medium",
+        ]);
+        assert!(read_todoist_csv("Title,Notes\nA,B\n", None).is_err());
+    }
+
+    #[test]
+    fn test_read_ticktick_csv() {
+        let csv = "\"Date: 2024-05-10+0000\"\n\"Version: 7.1\"\n\"Status: \n0 Normal\n1 Completed\n2 Archived\"\n\
+            \"Folder Name\",\"List Name\",\"Title\",\"Kind\",\"Tags\",\"Content\",\"Is Check list\",\"Start Date\",\"Due Date\",\"Reminder\",\"Repeat\",\"Priority\",\"Status\",\"Created Time\",\"Completed Time\",\"Order\",\"Timezone\",\"Is All Day\"\n\
+            \"\",\"Work\",\"Send report\",\"TEXT\",\"#reports, urgent\",\"Q2 numbers\",\"N\",\"\",\"2024-05-02T07:30:00+0000\",\"\",\"\",\"5\",\"0\",\"2024-05-01T08:00:00+0000\",\"\",\"1\",\"UTC\",\"false\"\n\
+            \"\",\"Inbox\",\"Renew passport\",\"TEXT\",\"\",\"\",\"N\",\"\",\"\",\"\",\"\",\"0\",\"2\",\"2024-04-01T08:00:00+0000\",\"2024-04-03T09:00:00+0000\",\"2\",\"UTC\",\"false\"\n\
+            \"\",\"Inbox\",\"\",\"TEXT\",\"\",\"\",\"N\",\"\",\"\",\"\",\"\",\"0\",\"0\",\"\",\"\",\"3\",\"UTC\",\"false\"\n";
+        let rows = read_ticktick_csv(csv, 42).unwrap();
+        assert_eq!(rows.failed_rows, vec![FailedImportRow { row: 7, reason: "The task has no title".to_string() }]);
+        assert!(rows.warnings.is_empty());
+
+        let report = &rows.tasks[0];
+        assert_eq!((report.project.as_deref(), report.priority.as_str()), (Some("Work"), "high"));
+        assert_eq!(report.tags, vec!["reports", "urgent"]);
+        assert_eq!(report.due_at, Some(1714635000));
+        assert_eq!(report.completed_at, None);
+        assert_eq!(rows.tasks[1].completed_at, Some(1712134800));
+        assert!(read_ticktick_csv("Title\nA\n", 0).is_err());
+    }
+}
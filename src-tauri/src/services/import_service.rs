@@ -0,0 +1,416 @@
+use crate::services::week_service;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Rows looked at for sample values, and samples kept per column
+const SAMPLE_ROWS: usize = 20;
+const MAX_SAMPLES: usize = 3;
+// Unix timestamps above this are taken to be in milliseconds
+const MAX_SECONDS_TIMESTAMP: i64 = 100_000_000_000;
+
+/// Which column feeds each task field; None leaves the field empty. Column names are matched
+/// exactly, as returned by inspect_import_file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportMapping {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub due: Option<String>,
+    pub priority: Option<String>,
+    /// Comma-separated tag names
+    pub tags: Option<String>,
+    /// Project name; missing projects are created
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportColumn {
+    pub name: String,
+    /// First few non-empty values
+    pub samples: Vec<String>,
+}
+
+/// What the mapping screen shows before an import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportInspection {
+    pub format: String,
+    pub columns: Vec<ImportColumn>,
+    pub proposed_mapping: ImportMapping,
+    pub row_count: usize,
+    pub encoding_issues: Vec<String>,
+}
+
+/// A file read into rows of strings, one value per column
+#[derive(Debug, Clone, Default)]
+pub struct ImportTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub encoding_issues: Vec<String>,
+}
+
+/// One row turned into task fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappedTask {
+    pub title: String,
+    pub description: Option<String>,
+    pub due_at: Option<i64>,
+    pub priority: String,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    /// Set for tasks that were already done in the source
+    pub completed_at: Option<i64>,
+}
+
+// Header names (lowercase) each field is proposed for, best match first
+const FIELD_ALIASES: [(&str, &[&str]); 6] = [
+    ("title", &["title", "name", "task", "content", "subject", "summary"]),
+    ("description", &["description", "notes", "note", "details", "body"]),
+    ("due", &["due", "due date", "due_date", "duedate", "deadline", "date"]),
+    ("priority", &["priority", "importance"]),
+    ("tags", &["tags", "labels", "tag", "label"]),
+    ("project", &["project", "list", "folder", "category"]),
+];
+
+/// Decode file bytes as UTF-8, noting anything that had to be fixed up
+pub fn decode_text(bytes: &[u8]) -> Result<(String, Vec<String>), String> {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return Err("The file is UTF-16 encoded; save it as UTF-8 and try again".to_string());
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    let mut issues = Vec::new();
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => {
+            let text = String::from_utf8_lossy(bytes).into_owned();
+            let replaced = text.matches('\u{FFFD}').count();
+            issues.push(format!(
+                "{} invalid UTF-8 sequence(s) replaced; the file may be in another encoding (e.g. Windows-1252)",
+                replaced
+            ));
+            text
+        }
+    };
+    Ok((text, issues))
+}
+
+/// Split CSV text into records. Handles quoted fields with commas, doubled quotes and line
+/// breaks inside them; blank lines are skipped.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|value| !value.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("Unterminated quoted field in record {}", records.len() + 1));
+    }
+    record.push(field);
+    if record.iter().any(|value| !value.is_empty()) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn json_value_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(items) => items.iter().map(json_value_text).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// Read a CSV (first record is the header) or a JSON list of objects. A JSON object holding
+/// such a list under "tasks" or "items" works too; columns are the keys in first-seen order.
+pub fn read_table(text: &str, format: &str, mut encoding_issues: Vec<String>) -> Result<ImportTable, String> {
+    match format {
+        "csv" => {
+            let mut records = parse_csv(text)?.into_iter();
+            let columns: Vec<String> = records
+                .next()
+                .ok_or("The file is empty")?
+                .into_iter()
+                .map(|name| name.trim().to_string())
+                .collect();
+            let mut rows = Vec::new();
+            for (index, mut row) in records.enumerate() {
+                if row.len() > columns.len() {
+                    // Header row is line 1
+                    encoding_issues.push(format!("Row {} has {} values for {} columns; the extra values are ignored", index + 2, row.len(), columns.len()));
+                }
+                row.resize(columns.len(), String::new());
+                rows.push(row);
+            }
+            Ok(ImportTable { columns, rows, encoding_issues })
+        }
+        "json" => {
+            let value: serde_json::Value = serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+            let items = match &value {
+                serde_json::Value::Array(items) => items,
+                serde_json::Value::Object(object) => object
+                    .get("tasks")
+                    .or_else(|| object.get("items"))
+                    .and_then(|items| items.as_array())
+                    .ok_or("Expected a list of tasks, or an object with a \"tasks\" or \"items\" list")?,
+                _ => return Err("Expected a list of tasks".to_string()),
+            };
+            let mut columns: Vec<String> = Vec::new();
+            for object in items.iter().filter_map(|item| item.as_object()) {
+                for key in object.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            let rows = items
+                .iter()
+                .filter_map(|item| item.as_object())
+                .map(|object| columns.iter().map(|column| object.get(column).map(json_value_text).unwrap_or_default()).collect())
+                .collect();
+            Ok(ImportTable { columns, rows, encoding_issues })
+        }
+        _ => Err(format!("Unsupported import format: {} (expected csv or json)", format)),
+    }
+}
+
+/// Guess the mapping from the column names
+pub fn propose_mapping(columns: &[String]) -> ImportMapping {
+    let mut proposed: HashMap<&str, String> = HashMap::new();
+    for (field, aliases) in FIELD_ALIASES {
+        let found = aliases.iter().find_map(|alias| {
+            columns
+                .iter()
+                .find(|column| column.trim().to_lowercase() == *alias && !proposed.values().any(|taken| taken == *column))
+        });
+        if let Some(column) = found {
+            proposed.insert(field, column.clone());
+        }
+    }
+    ImportMapping {
+        title: proposed.remove("title"),
+        description: proposed.remove("description"),
+        due: proposed.remove("due"),
+        priority: proposed.remove("priority"),
+        tags: proposed.remove("tags"),
+        project: proposed.remove("project"),
+    }
+}
+
+pub fn inspect(table: &ImportTable, format: &str) -> ImportInspection {
+    let columns = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, name)| ImportColumn {
+            name: name.clone(),
+            samples: table
+                .rows
+                .iter()
+                .take(SAMPLE_ROWS)
+                .map(|row| row[index].trim())
+                .filter(|value| !value.is_empty())
+                .take(MAX_SAMPLES)
+                .map(str::to_string)
+                .collect(),
+        })
+        .collect();
+    ImportInspection {
+        format: format.to_string(),
+        columns,
+        proposed_mapping: propose_mapping(&table.columns),
+        row_count: table.rows.len(),
+        encoding_issues: table.encoding_issues.clone(),
+    }
+}
+
+/// A title column is required and every mapped column has to be one of `columns`
+pub fn validate_mapping(mapping: &ImportMapping, columns: &[String]) -> Result<(), String> {
+    if mapping.title.is_none() {
+        return Err("Choose the column that holds the task titles".to_string());
+    }
+    let mapped = [
+        ("title", &mapping.title),
+        ("description", &mapping.description),
+        ("due", &mapping.due),
+        ("priority", &mapping.priority),
+        ("tags", &mapping.tags),
+        ("project", &mapping.project),
+    ];
+    for (field, column) in mapped {
+        if let Some(column) = column {
+            if !columns.contains(column) {
+                return Err(format!("Column \"{}\" mapped to {} is not in the file", column, field));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Unix seconds or milliseconds, RFC 3339, "YYYY-MM-DD HH:MM[:SS]" or "YYYY-MM-DD" (local
+/// midnight)
+pub fn parse_due(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Some(if timestamp > MAX_SECONDS_TIMESTAMP { timestamp / 1000 } else { timestamp });
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.timestamp());
+    }
+    for pattern in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, pattern) {
+            return Local.from_local_datetime(&naive).earliest().map(|datetime| datetime.timestamp());
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(week_service::local_midnight)
+}
+
+/// low, medium or high; anything else is medium
+pub fn parse_priority(value: &str) -> String {
+    match value.trim().to_lowercase().as_str() {
+        "high" | "urgent" | "important" => "high",
+        "low" => "low",
+        _ => "medium",
+    }
+    .to_string()
+}
+
+/// Turn rows into tasks. Rows without a title are skipped; values that can't be read (e.g. an
+/// unparseable due date) are dropped with a warning naming the row.
+pub fn map_rows(table: &ImportTable, mapping: &ImportMapping) -> Result<(Vec<MappedTask>, usize, Vec<String>), String> {
+    validate_mapping(mapping, &table.columns)?;
+    let index = |column: &Option<String>| column.as_ref().and_then(|name| table.columns.iter().position(|c| c == name));
+    let (title, description, due, priority, tags, project) = (
+        index(&mapping.title),
+        index(&mapping.description),
+        index(&mapping.due),
+        index(&mapping.priority),
+        index(&mapping.tags),
+        index(&mapping.project),
+    );
+    let value = |row: &[String], column: Option<usize>| {
+        column.map(|column| row[column].trim().to_string()).filter(|value| !value.is_empty())
+    };
+
+    let mut tasks = Vec::new();
+    let mut skipped = 0;
+    let mut warnings = Vec::new();
+    // Header row is line 1, matching the numbers read_table reports
+    for (row_number, row) in table.rows.iter().enumerate().map(|(index, row)| (index + 2, row)) {
+        let Some(task_title) = value(row, title) else {
+            skipped += 1;
+            continue;
+        };
+        let due_at = value(row, due).and_then(|text| {
+            let parsed = parse_due(&text);
+            if parsed.is_none() {
+                warnings.push(format!("Row {}: couldn't read due date \"{}\"", row_number, text));
+            }
+            parsed
+        });
+        tasks.push(MappedTask {
+            title: task_title,
+            description: value(row, description),
+            due_at,
+            priority: value(row, priority).map_or_else(|| "medium".to_string(), |text| parse_priority(&text)),
+            tags: value(row, tags)
+                .map(|text| text.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect())
+                .unwrap_or_default(),
+            project: value(row, project),
+            completed_at: None,
+        });
+    }
+    Ok((tasks, skipped, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_quotes_and_line_breaks() {
+        let records = parse_csv("Title,Notes\r\n\"Call \"\"Bob\"\"\",\"line one\nline two\"\n\nPay rent,\n").unwrap();
+        assert_eq!(records, vec![
+            vec!["Title".to_string(), "Notes".to_string()],
+            vec!["Call \"Bob\"".to_string(), "line one\nline two".to_string()],
+            vec!["Pay rent".to_string(), String::new()],
+        ]);
+        assert!(parse_csv("Title\n\"open").is_err());
+    }
+
+    #[test]
+    fn test_inspect_and_map_csv() {
+        let (text, issues) = decode_text(b"\xEF\xBB\xBFDue Date,Task,Labels,List,Notes\n2024-05-01,Pay rent,\"home, bills\",Personal,\n31/12/2024,File taxes,,,Before the deadline\n,,,,\n").unwrap();
+        assert!(issues.is_empty());
+        let table = read_table(&text, "csv", issues).unwrap();
+        let inspection = inspect(&table, "csv");
+        assert_eq!(inspection.row_count, 2);
+        assert_eq!(inspection.columns[0].samples, vec!["2024-05-01", "31/12/2024"]);
+        assert_eq!(inspection.proposed_mapping, ImportMapping {
+            title: Some("Task".to_string()),
+            description: Some("Notes".to_string()),
+            due: Some("Due Date".to_string()),
+            priority: None,
+            tags: Some("Labels".to_string()),
+            project: Some("List".to_string()),
+        });
+
+        let (tasks, skipped, warnings) = map_rows(&table, &inspection.proposed_mapping).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(tasks[0].tags, vec!["home", "bills"]);
+        assert_eq!(tasks[0].due_at, Some(week_service::local_midnight(NaiveDate::from_ymd_opt(2024, 5, 1).unwrap())));
+        assert_eq!(tasks[1].due_at, None);
+        assert_eq!(warnings, vec!["Row 3: couldn't read due date \"31/12/2024\""]);
+
+        // Dates as titles are the user's call, but unknown columns and a missing title are not
+        let mut mapping = inspection.proposed_mapping.clone();
+        mapping.title = Some("Due Date".to_string());
+        assert_eq!(map_rows(&table, &mapping).unwrap().0[0].title, "2024-05-01");
+        mapping.title = Some("Subject".to_string());
+        assert!(map_rows(&table, &mapping).is_err());
+        mapping.title = None;
+        assert!(map_rows(&table, &mapping).is_err());
+    }
+
+    #[test]
+    fn test_read_json_and_encoding_issues() {
+        let table = read_table(r#"{"items": [{"name": "Plan trip", "priority": "High", "tags": ["travel", "summer"]}, {"name": "Gym", "due": 1714521600}]}"#, "json", Vec::new()).unwrap();
+        assert_eq!(table.columns, vec!["name", "priority", "tags", "due"]);
+        let (tasks, _, _) = map_rows(&table, &propose_mapping(&table.columns)).unwrap();
+        assert_eq!((tasks[0].priority.as_str(), tasks[0].tags.len()), ("high", 2));
+        assert_eq!(tasks[1].due_at, Some(1714521600));
+
+        let (text, issues) = decode_text(b"Title\nCaf\xE9\n").unwrap();
+        assert_eq!(text, "Title\nCaf\u{FFFD}\n");
+        assert_eq!(issues.len(), 1);
+        assert!(decode_text(&[0xFF, 0xFE, b'T', 0]).is_err());
+    }
+}
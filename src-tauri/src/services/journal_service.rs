@@ -0,0 +1,193 @@
+use chrono::{FixedOffset, Local, NaiveDate, TimeZone};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One completed task in the daily journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub task_id: String,
+    pub title: String,
+    pub project_name: Option<String>,
+    pub tags: Vec<String>,
+    pub completed_at: i64,
+    /// Focus time logged against the task during the day
+    pub pomodoro_minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyJournal {
+    pub date: String,
+    pub day_start: i64,
+    pub day_end: i64,
+    /// In completion order
+    pub entries: Vec<JournalEntry>,
+    /// All focus time that day, including sessions without a task
+    pub pomodoro_minutes: i64,
+}
+
+/// Tasks completed on `date` (YYYY-MM-DD) in the given UTC offset (minutes east) or local time.
+/// Archive summary tasks are left out; they stand for work done on other days.
+pub fn get_daily_journal(
+    conn: &rusqlite::Connection,
+    date: &str,
+    tz_offset_minutes: Option<i32>,
+) -> Result<DailyJournal, String> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let (day_start, day_end) = crate::commands::day_bounds(day, tz_offset_minutes)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.title, p.name, t.completed_at,
+                (SELECT COALESCE(SUM(ps.duration_seconds), 0) FROM pomodoro_sessions ps
+                 WHERE ps.task_id = t.id AND ps.mode = 'pomodoro'
+                   AND ps.completed_at >= ?1 AND ps.completed_at < ?2)
+            FROM tasks t
+            LEFT JOIN projects p ON p.id = t.project_id
+            WHERE t.completed_at >= ?1 AND t.completed_at < ?2
+              AND (t.metadata IS NULL OR json_extract(t.metadata, '$.archive_summary') IS NULL)
+            ORDER BY t.completed_at, t.order_index",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![day_start, day_end], |row| {
+            Ok(JournalEntry {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                project_name: row.get(2)?,
+                tags: Vec::new(),
+                completed_at: row.get(3)?,
+                pomodoro_minutes: row.get::<_, i64>(4)? / 60,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tt.task_id, tg.name FROM task_tags tt
+            JOIN tags tg ON tg.id = tt.tag_id
+            JOIN tasks t ON t.id = tt.task_id
+            WHERE t.completed_at >= ?1 AND t.completed_at < ?2
+            ORDER BY tg.name",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![day_start, day_end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    let mut tags_by_task: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (task_id, name) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        tags_by_task.entry(task_id).or_default().push(name);
+    }
+    for entry in &mut entries {
+        entry.tags = tags_by_task.remove(&entry.task_id).unwrap_or_default();
+    }
+
+    let focus_seconds: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(duration_seconds), 0) FROM pomodoro_sessions
+            WHERE mode = 'pomodoro' AND completed_at >= ?1 AND completed_at < ?2",
+            params![day_start, day_end],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    Ok(DailyJournal {
+        date: date.to_string(),
+        day_start,
+        day_end,
+        entries,
+        pomodoro_minutes: focus_seconds / 60,
+    })
+}
+
+fn format_time(timestamp: i64, tz_offset_minutes: Option<i32>) -> String {
+    let offset = tz_offset_minutes.and_then(|minutes| FixedOffset::east_opt(minutes * 60));
+    match offset {
+        Some(offset) => offset.timestamp_opt(timestamp, 0).single().map(|dt| dt.format("%H:%M").to_string()),
+        None => Local.timestamp_opt(timestamp, 0).single().map(|dt| dt.format("%H:%M").to_string()),
+    }
+    .unwrap_or_default()
+}
+
+/// The journal as a Markdown section, ready to paste
+pub fn render_daily_journal_markdown(journal: &DailyJournal, tz_offset_minutes: Option<i32>) -> String {
+    let mut markdown = format!("## {}\n\n", journal.date);
+    if journal.entries.is_empty() {
+        markdown.push_str("_Nothing completed._\n");
+        return markdown;
+    }
+
+    for entry in &journal.entries {
+        markdown.push_str(&format!("- {} {}", format_time(entry.completed_at, tz_offset_minutes), entry.title));
+        if let Some(project) = &entry.project_name {
+            markdown.push_str(&format!(" ({})", project));
+        }
+        for tag in &entry.tags {
+            markdown.push_str(&format!(" #{}", tag));
+        }
+        if entry.pomodoro_minutes > 0 {
+            markdown.push_str(&format!(" — {} min focus", entry.pomodoro_minutes));
+        }
+        markdown.push('\n');
+    }
+
+    let count = journal.entries.len();
+    markdown.push_str(&format!(
+        "\n_{} task{} completed, {} min focus_\n",
+        count,
+        if count == 1 { "" } else { "s" },
+        journal.pomodoro_minutes
+    ));
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_journal_in_offset() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, project_id TEXT, completed_at INTEGER, order_index INTEGER DEFAULT 0, metadata TEXT);
+            CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE task_tags (task_id TEXT NOT NULL, tag_id TEXT NOT NULL);
+            CREATE TABLE pomodoro_sessions (task_id TEXT, mode TEXT NOT NULL, completed_at INTEGER NOT NULL, duration_seconds INTEGER NOT NULL);
+            INSERT INTO projects VALUES ('p1', 'Work');
+            -- 2024-03-10 in UTC+3 runs from 2024-03-09T21:00Z (1710018000)
+            INSERT INTO tasks VALUES
+                ('t1', 'Write report', 'p1', 1710018000 + 9 * 3600, 0, NULL),
+                ('t2', 'Water plants', NULL, 1710018000 + 8 * 3600, 0, NULL),
+                ('t3', 'Yesterday', NULL, 1710018000 - 60, 0, NULL),
+                ('t4', 'Archive summary', 'p1', 1710018000 + 10 * 3600, 0, '{\"archive_summary\": {}}');
+            INSERT INTO tags VALUES ('g1', 'writing');
+            INSERT INTO task_tags VALUES ('t1', 'g1');
+            INSERT INTO pomodoro_sessions VALUES
+                ('t1', 'pomodoro', 1710018000 + 8 * 3600, 1500),
+                (NULL, 'pomodoro', 1710018000 + 7 * 3600, 1500),
+                ('t1', 'short_break', 1710018000 + 8 * 3600, 300);",
+        )
+        .unwrap();
+
+        let journal = get_daily_journal(&conn, "2024-03-10", Some(180)).unwrap();
+        let titles: Vec<&str> = journal.entries.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Water plants", "Write report"]);
+        assert_eq!(journal.entries[1].tags, vec!["writing"]);
+        assert_eq!(journal.entries[1].pomodoro_minutes, 25);
+        assert_eq!(journal.pomodoro_minutes, 50);
+
+        let markdown = render_daily_journal_markdown(&journal, Some(180));
+        assert!(markdown.starts_with("## 2024-03-10\n\n- 08:00 Water plants\n"));
+        assert!(markdown.contains("- 09:00 Write report (Work) #writing — 25 min focus\n"));
+        assert!(markdown.ends_with("_2 tasks completed, 50 min focus_\n"));
+
+        assert!(get_daily_journal(&conn, "10/03/2024", None).is_err());
+    }
+}
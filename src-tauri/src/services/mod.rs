@@ -1,6 +1,26 @@
+pub mod calendar_sync;
+pub mod completion_time_service;
+pub mod export_service;
+pub mod external_import_service;
+pub mod import_service;
+pub mod journal_service;
+pub mod palette_service;
 pub mod pomodoro_service;
+pub mod priority_aging_service;
+pub mod project_suggestion_service;
+pub mod quick_add_service;
+pub mod retention_service;
+pub mod rollover_service;
+pub mod rules_service;
 pub mod stats_service;
+pub mod status_service;
+pub mod tag_suggestion_service;
 pub mod template_service;
 pub mod task_service;
 pub mod translation_service;
+pub mod undo_service;
+pub mod vacation_service;
+pub mod week_service;
+pub mod weekly_review_service;
+pub mod wip_limit_service;
 
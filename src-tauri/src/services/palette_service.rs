@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+
+/// One ranked result of the Ctrl+K command palette
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteEntry {
+    pub kind: String, // 'task', 'project', 'tag', 'template'
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub score: f64,
+}
+
+// Projects and tags are navigation targets and few in number, so they rank above tasks with
+// a similar match
+const KIND_BOOSTS: [(&str, f64); 4] = [
+    ("project", 1.3),
+    ("tag", 1.2),
+    ("template", 1.1),
+    ("task", 1.0),
+];
+
+fn kind_boost(kind: &str) -> f64 {
+    KIND_BOOSTS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, boost)| *boost)
+        .unwrap_or(1.0)
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '-' | '_' | '/' | '.' | ':' | '#' | '@')
+}
+
+/// Subsequence fuzzy score of `query` (already lowercased, whitespace removed) against
+/// `candidate`. Every query character must appear in order; matches at the start, after a
+/// word separator or right after the previous match earn bonuses, and gaps cost a little.
+/// Returns None when the query is not a subsequence of the candidate.
+pub fn fuzzy_score(query: &[char], candidate: &str) -> Option<f64> {
+    if query.is_empty() {
+        return None;
+    }
+
+    let mut score = 0.0;
+    let mut query_index = 0;
+    let mut previous: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+
+    for (position, c) in candidate.chars().flat_map(char::to_lowercase).enumerate() {
+        if query_index < query.len() && c == query[query_index] {
+            score += 1.0;
+            if position == 0 {
+                score += 2.0;
+            } else if previous.is_some_and(is_separator) {
+                score += 1.5;
+            }
+            match last_match {
+                Some(last) if last + 1 == position => score += 1.0,
+                Some(last) => score -= 0.05 * (position - last - 1) as f64,
+                None => score -= 0.05 * position as f64,
+            }
+            last_match = Some(position);
+            query_index += 1;
+        }
+        previous = Some(c);
+    }
+
+    if query_index < query.len() {
+        return None;
+    }
+
+    // Prefer the shorter of two otherwise equal matches
+    let length = candidate.chars().count() as f64;
+    Some(score - 0.01 * length)
+}
+
+struct Candidate {
+    kind: &'static str,
+    id: String,
+    title: String,
+    subtitle: Option<String>,
+}
+
+fn load_candidates(conn: &rusqlite::Connection) -> Result<Vec<Candidate>, String> {
+    let mut candidates = Vec::new();
+
+    // Completed tasks are left out to keep the candidate set bounded by the open workload
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.title, p.name FROM tasks t
+         LEFT JOIN projects p ON p.id = t.project_id
+         WHERE t.completed_at IS NULL AND t.archived = 0"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Candidate { kind: "task", id: row.get(0)?, title: row.get(1)?, subtitle: row.get(2)? })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        candidates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT p.id, p.name, COUNT(t.id) FROM projects p
+         LEFT JOIN tasks t ON t.project_id = p.id AND t.completed_at IS NULL AND t.archived = 0
+         GROUP BY p.id"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        let open: i64 = row.get(2)?;
+        Ok(Candidate { kind: "project", id: row.get(0)?, title: row.get(1)?, subtitle: Some(format!("{} open", open)) })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        candidates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    let mut stmt = conn.prepare("SELECT id, name, COALESCE(usage_count, 0) FROM tags")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        let usage: i64 = row.get(2)?;
+        Ok(Candidate { kind: "tag", id: row.get(0)?, title: row.get(1)?, subtitle: Some(format!("{} tasks", usage)) })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        candidates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    let mut stmt = conn.prepare("SELECT id, name, title FROM task_templates")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Candidate { kind: "template", id: row.get(0)?, title: row.get(1)?, subtitle: row.get(2)? })
+    }).map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        candidates.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    Ok(candidates)
+}
+
+/// Fuzzy-match `query` across open tasks, projects, tags and templates, best matches first
+pub fn palette_search(
+    conn: &rusqlite::Connection,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<PaletteEntry>, String> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut entries: Vec<PaletteEntry> = load_candidates(conn)?
+        .into_iter()
+        .filter_map(|candidate| {
+            let score = fuzzy_score(&query, &candidate.title)? * kind_boost(candidate.kind);
+            Some(PaletteEntry {
+                kind: candidate.kind.to_string(),
+                id: candidate.id,
+                title: candidate.title,
+                subtitle: candidate.subtitle,
+                score,
+            })
+        })
+        .collect();
+
+    entries.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.title.cmp(&b.title)));
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_word_starts() {
+        assert!(fuzzy_score(&chars("xyz"), "Buy milk").is_none());
+        assert!(fuzzy_score(&chars("bm"), "Buy milk").is_some());
+
+        let initials = fuzzy_score(&chars("bm"), "Buy milk").unwrap();
+        let scattered = fuzzy_score(&chars("bm"), "Submit form").unwrap();
+        assert!(initials > scattered);
+
+        let prefix = fuzzy_score(&chars("wor"), "Work").unwrap();
+        let inner = fuzzy_score(&chars("wor"), "Homework").unwrap();
+        assert!(prefix > inner);
+    }
+
+    #[test]
+    fn test_palette_search_ranks_mixed_kinds() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, project_id TEXT, completed_at INTEGER, archived INTEGER NOT NULL DEFAULT 0);
+            CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT NOT NULL, usage_count INTEGER);
+            CREATE TABLE task_templates (id TEXT PRIMARY KEY, name TEXT NOT NULL, title TEXT NOT NULL);
+            INSERT INTO projects VALUES ('p1', 'Work');
+            INSERT INTO tasks VALUES ('t1', 'Write weekly report', 'p1', NULL, 0), ('t2', 'Wash car', NULL, 5, 0),
+                ('t3', 'Water plants', NULL, NULL, 1);
+            INSERT INTO tags VALUES ('g1', 'writing', 3);
+            INSERT INTO task_templates VALUES ('m1', 'Weekly review', 'Review the week');",
+        )
+        .unwrap();
+
+        let results = palette_search(&conn, "w", 10).unwrap();
+        let kinds: Vec<&str> = results.iter().map(|r| r.kind.as_str()).collect();
+        // Completed and archived tasks are not candidates
+        assert!(results.iter().all(|r| r.id != "t2" && r.id != "t3"));
+        assert_eq!(kinds.first(), Some(&"project"));
+
+        let results = palette_search(&conn, "wkly rep", 10).unwrap();
+        assert_eq!(results[0].id, "t1");
+        assert_eq!(results[0].subtitle.as_deref(), Some("Work"));
+
+        assert!(palette_search(&conn, "   ", 10).unwrap().is_empty());
+        assert_eq!(palette_search(&conn, "w", 2).unwrap().len(), 2);
+    }
+}
@@ -56,6 +56,20 @@ pub struct TaskCompletionRate {
     pub completion_rate: f64, // percentage of Pomodoros that led to task completion
 }
 
+/// Focus history of one task for the task detail pane. Break sessions are not counted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskPomodoroSummary {
+    pub task_id: String,
+    pub pomodoro_count: i64,
+    pub completed_count: i64,
+    pub total_minutes: i64,
+    pub average_minutes: f64,
+    pub last_session_at: Option<i64>,
+    pub recent_sessions: Vec<PomodoroSession>, // newest first, at most RECENT_TASK_SESSIONS
+}
+
+pub const RECENT_TASK_SESSIONS: i64 = 5;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PomodoroStreak {
     pub current_streak: i32,
@@ -345,6 +359,62 @@ pub fn get_task_completion_rates(
     Ok(rates)
 }
 
+pub fn get_task_pomodoro_summary(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+) -> Result<TaskPomodoroSummary, rusqlite::Error> {
+    let (pomodoro_count, completed_count, total_seconds, last_session_at): (i64, i64, i64, Option<i64>) = conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM(CASE WHEN was_completed THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(duration_seconds), 0),
+                MAX(completed_at)
+         FROM pomodoro_sessions
+         WHERE task_id = ?1 AND mode = 'pomodoro'",
+        params![task_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, user_id, task_id, started_at, completed_at, duration_seconds, mode, was_completed, task_completed, created_at
+         FROM pomodoro_sessions
+         WHERE task_id = ?1 AND mode = 'pomodoro'
+         ORDER BY completed_at DESC
+         LIMIT ?2"
+    )?;
+    let recent_sessions = stmt
+        .query_map(params![task_id, RECENT_TASK_SESSIONS], |row| {
+            Ok(PomodoroSession {
+                id: row.get(0)?,
+                user_id: row.get(1)?,
+                task_id: row.get(2)?,
+                started_at: row.get(3)?,
+                completed_at: row.get(4)?,
+                duration_seconds: row.get(5)?,
+                mode: row.get(6)?,
+                was_completed: row.get(7)?,
+                task_completed: row.get(8)?,
+                created_at: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let average_minutes = if pomodoro_count > 0 {
+        total_seconds as f64 / 60.0 / pomodoro_count as f64
+    } else {
+        0.0
+    };
+
+    Ok(TaskPomodoroSummary {
+        task_id: task_id.to_string(),
+        pomodoro_count,
+        completed_count,
+        total_minutes: total_seconds / 60,
+        average_minutes,
+        last_session_at,
+        recent_sessions,
+    })
+}
+
 pub fn get_pomodoro_streak(
     conn: &rusqlite::Connection,
 ) -> Result<PomodoroStreak, rusqlite::Error> {
@@ -366,3 +436,56 @@ pub fn get_pomodoro_streak(
 
     Ok(streak)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_pomodoro_summary_excludes_breaks() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE user_progress (id TEXT PRIMARY KEY);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY);
+            INSERT INTO user_progress (id) VALUES ('default');
+            INSERT INTO tasks (id) VALUES ('t1'), ('t2');",
+        )
+        .unwrap();
+        conn.execute_batch(include_str!("../../migrations/0011_add_pomodoro_sessions.sql")).unwrap();
+
+        let sessions = [
+            ("t1", 1_000, 25 * 60, "pomodoro", true),
+            ("t1", 3_000, 5 * 60, "shortBreak", true),
+            ("t1", 5_000, 50 * 60, "pomodoro", true),
+            ("t1", 9_000, 10 * 60, "pomodoro", false),
+            ("t2", 9_500, 25 * 60, "pomodoro", true),
+        ];
+        for (task_id, started_at, duration, mode, was_completed) in sessions {
+            create_pomodoro_session(
+                &conn,
+                Some(task_id.to_string()),
+                started_at,
+                started_at + duration as i64,
+                duration,
+                mode.to_string(),
+                was_completed,
+                false,
+            )
+            .unwrap();
+        }
+
+        let summary = get_task_pomodoro_summary(&conn, "t1").unwrap();
+        assert_eq!(summary.pomodoro_count, 3);
+        assert_eq!(summary.completed_count, 2);
+        assert_eq!(summary.total_minutes, 85);
+        assert!((summary.average_minutes - 85.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.last_session_at, Some(9_000 + 10 * 60));
+        let starts: Vec<i64> = summary.recent_sessions.iter().map(|s| s.started_at).collect();
+        assert_eq!(starts, vec![9_000, 5_000, 1_000]);
+
+        let empty = get_task_pomodoro_summary(&conn, "missing").unwrap();
+        assert_eq!(empty.pomodoro_count, 0);
+        assert_eq!(empty.average_minutes, 0.0);
+        assert_eq!(empty.last_session_at, None);
+    }
+}
@@ -0,0 +1,239 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+/// Longest threshold honored; larger settings are read as this (ten years)
+pub const MAX_THRESHOLD_DAYS: i64 = 3650;
+
+// Tasks carrying this tag never age
+const SOMEDAY_TAG: &str = "someday";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgedTask {
+    pub task_id: String,
+    pub title: String,
+    pub old_priority: String,
+    pub new_priority: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityAgingPreview {
+    pub enabled: bool,
+    pub threshold_days: i64,
+    pub exempt_undated: bool,
+    pub tasks: Vec<AgedTask>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PriorityAgingSettings {
+    /// 0 (the default) disables aging
+    pub threshold_days: i64,
+    /// Also leave tasks without a due date alone
+    pub exempt_undated: bool,
+}
+
+fn get_setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).ok()
+}
+
+pub fn load_settings(conn: &rusqlite::Connection) -> PriorityAgingSettings {
+    PriorityAgingSettings {
+        threshold_days: get_setting(conn, "priority_aging_days")
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(0)
+            .clamp(0, MAX_THRESHOLD_DAYS),
+        exempt_undated: get_setting(conn, "priority_aging_exempt_undated").as_deref() == Some("true"),
+    }
+}
+
+fn next_priority(priority: &str) -> Option<&'static str> {
+    match priority {
+        "low" => Some("medium"),
+        "medium" => Some("high"),
+        _ => None,
+    }
+}
+
+/// Tasks that would be bumped one priority level for the given threshold
+pub fn find_aging_candidates(
+    conn: &rusqlite::Connection,
+    settings: PriorityAgingSettings,
+    now: i64,
+) -> Result<Vec<AgedTask>, String> {
+    let cutoff = now.saturating_sub(settings.threshold_days.saturating_mul(DAY_SECONDS));
+
+    let mut query = "SELECT t.id, t.title, t.priority, t.updated_at FROM tasks t
+        WHERE t.completed_at IS NULL
+        AND t.archived = 0
+        AND t.priority IN ('low', 'medium')
+        AND t.updated_at < ?1
+        AND NOT EXISTS (
+            SELECT 1 FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+            WHERE tt.task_id = t.id AND LOWER(g.name) = ?2
+        )".to_string();
+    if settings.exempt_undated {
+        query.push_str(" AND t.due_at IS NOT NULL");
+    }
+    query.push_str(" ORDER BY t.updated_at ASC");
+
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![cutoff, SOMEDAY_TAG], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)?))
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut tasks = Vec::new();
+    for row in rows {
+        let (task_id, title, old_priority, updated_at) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        if let Some(new_priority) = next_priority(&old_priority) {
+            tasks.push(AgedTask {
+                task_id,
+                title,
+                old_priority,
+                new_priority: new_priority.to_string(),
+                updated_at,
+            });
+        }
+    }
+
+    Ok(tasks)
+}
+
+pub fn preview_priority_aging(conn: &rusqlite::Connection, now: i64) -> Result<PriorityAgingPreview, String> {
+    let mut settings = load_settings(conn);
+    let enabled = settings.threshold_days > 0;
+    // Show what the default two weeks would do when aging is still off
+    if !enabled {
+        settings.threshold_days = 14;
+    }
+
+    Ok(PriorityAgingPreview {
+        enabled,
+        threshold_days: settings.threshold_days,
+        exempt_undated: settings.exempt_undated,
+        tasks: find_aging_candidates(conn, settings, now)?,
+    })
+}
+
+/// Bump every aging candidate one level and record it in the activity log with source "aging".
+/// Does nothing unless `priority_aging_days` is set.
+pub fn apply_priority_aging(conn: &rusqlite::Connection, now: i64) -> Result<Vec<AgedTask>, String> {
+    let settings = load_settings(conn);
+    if settings.threshold_days == 0 {
+        return Ok(Vec::new());
+    }
+
+    let tasks = find_aging_candidates(conn, settings, now)?;
+    if tasks.is_empty() {
+        return Ok(tasks);
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for task in &tasks {
+        // Touching updated_at restarts the clock, so the next bump is another threshold away
+        tx.execute(
+            "UPDATE tasks SET priority = ?1, updated_at = ?2 WHERE id = ?3",
+            params![task.new_priority, now, task.task_id],
+        ).map_err(|e| format!("Failed to update priority: {}", e))?;
+        crate::commands::log_activity(
+            &tx,
+            &task.task_id,
+            "priority_changed",
+            Some(&task.old_priority),
+            Some(&task.new_priority),
+            "aging",
+        )?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                due_at INTEGER,
+                updated_at INTEGER NOT NULL,
+                priority TEXT NOT NULL DEFAULT 'medium',
+                completed_at INTEGER,
+                archived INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+            CREATE TABLE task_tags (id TEXT PRIMARY KEY, task_id TEXT NOT NULL, tag_id TEXT NOT NULL);
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        ).unwrap();
+        conn.execute_batch(include_str!("../../migrations/0018_add_activity_log.sql")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_aging_bumps_stale_tasks_once() {
+        let conn = setup_test_conn();
+        let now = 100 * DAY_SECONDS;
+        let old = now - 30 * DAY_SECONDS;
+        conn.execute_batch(&format!(
+            "INSERT INTO tasks (id, title, due_at, updated_at, priority) VALUES
+                ('stale-low', 'Stale low', {now}, {old}, 'low'),
+                ('stale-high', 'Stale high', {now}, {old}, 'high'),
+                ('fresh', 'Fresh', {now}, {now}, 'low'),
+                ('undated', 'Undated', NULL, {old}, 'medium'),
+                ('someday', 'Someday', {now}, {old}, 'low');
+            INSERT INTO tasks (id, title, updated_at, priority, completed_at) VALUES ('done', 'Done', {old}, 'low', {old});
+            INSERT INTO tasks (id, title, due_at, updated_at, priority, archived) VALUES ('archived', 'Archived', {now}, {old}, 'low', 1);
+            INSERT INTO tags (id, name) VALUES ('tag-1', 'Someday');
+            INSERT INTO task_tags (id, task_id, tag_id) VALUES ('tt-1', 'someday', 'tag-1');"
+        )).unwrap();
+
+        // Off by default
+        assert!(apply_priority_aging(&conn, now).unwrap().is_empty());
+        let preview = preview_priority_aging(&conn, now).unwrap();
+        assert!(!preview.enabled);
+        assert_eq!(preview.tasks.len(), 2);
+
+        conn.execute_batch(
+            "INSERT INTO settings (key, value) VALUES ('priority_aging_days', '14'), ('priority_aging_exempt_undated', 'true');"
+        ).unwrap();
+        let aged = apply_priority_aging(&conn, now).unwrap();
+        let ids: Vec<&str> = aged.iter().map(|t| t.task_id.as_str()).collect();
+        assert_eq!(ids, vec!["stale-low"]);
+
+        let priority: String = conn
+            .query_row("SELECT priority FROM tasks WHERE id = 'stale-low'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(priority, "medium");
+        let source: String = conn
+            .query_row("SELECT source FROM activity_log WHERE task_id = 'stale-low'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(source, "aging");
+
+        // The bump touched updated_at, so running again the same day changes nothing
+        assert!(apply_priority_aging(&conn, now).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_huge_threshold_is_clamped() {
+        let conn = setup_test_conn();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES ('priority_aging_days', ?1)",
+            params![i64::MAX.to_string()],
+        ).unwrap();
+        assert_eq!(load_settings(&conn).threshold_days, MAX_THRESHOLD_DAYS);
+        assert!(apply_priority_aging(&conn, 100 * DAY_SECONDS).unwrap().is_empty());
+
+        let settings = PriorityAgingSettings { threshold_days: i64::MAX, exempt_undated: false };
+        assert!(find_aging_candidates(&conn, settings, 0).unwrap().is_empty());
+    }
+}
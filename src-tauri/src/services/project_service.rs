@@ -20,6 +20,7 @@ pub fn get_projects(conn: &rusqlite::Connection) -> Result<Vec<Project>, String>
             color: row.get(2)?,
             created_at: row.get(3)?,
             updated_at: row.get(4)?,
+            completion_note_required: None,
         })
     }).map_err(|e| format!("Query execution error: {}", e))?;
     
@@ -50,6 +51,7 @@ pub fn create_project(conn: &rusqlite::Connection, input: CreateProjectInput) ->
                 color: row.get(2)?,
                 created_at: row.get(3)?,
                 updated_at: row.get(4)?,
+                completion_note_required: None,
             })
         },
     ).map_err(|e| format!("Failed to fetch created project: {}", e))
@@ -89,6 +91,7 @@ pub fn update_project(conn: &rusqlite::Connection, id: &str, input: UpdateProjec
                 color: row.get(2)?,
                 created_at: row.get(3)?,
                 updated_at: row.get(4)?,
+                completion_note_required: None,
             })
         },
     ).map_err(|e| format!("Project not found: {}", e))
@@ -0,0 +1,196 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Suggestions below this raw score are noise (a single weak shared word)
+const MIN_SCORE: f64 = 0.05;
+const MAX_SUGGESTIONS: usize = 3;
+// Raw score at which confidence reaches about 63% on its own; one specific keyword scores ~0.17
+const CONFIDENCE_SCALE: f64 = 0.25;
+/// Confidence the top suggestion needs before quick-add files a task automatically
+pub const DEFAULT_AUTO_FILE_THRESHOLD: f64 = 0.6;
+
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "into", "about", "this", "that", "are", "was", "have", "has", "not",
+    "you", "your", "our", "get", "all", "new", "out", "off", "via", "per",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSuggestion {
+    pub project_id: String,
+    pub project_name: String,
+    pub score: f64,
+    /// How strong the match is on its own, scaled by its share of the total score across all
+    /// matching projects (0..1)
+    pub confidence: f64,
+}
+
+/// Lowercased words of at least three characters, without stop words
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.chars().count() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Recompute the per-project keyword weights (term frequency within the project's task titles,
+/// scaled by how specific the term is across projects).
+pub fn rebuild_project_keywords(conn: &rusqlite::Connection, now: i64) -> Result<usize, String> {
+    let mut term_counts: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT project_id, title FROM tasks WHERE project_id IS NOT NULL")
+            .map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (project_id, title) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            let counts = term_counts.entry(project_id).or_default();
+            for term in tokenize(&title) {
+                *counts.entry(term).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let project_count = term_counts.len() as f64;
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for counts in term_counts.values() {
+        for term in counts.keys() {
+            *document_frequency.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM project_keywords", [])
+        .map_err(|e| format!("Failed to clear project keywords: {}", e))?;
+
+    let mut written = 0;
+    for (project_id, counts) in &term_counts {
+        let total: usize = counts.values().sum();
+        for (term, count) in counts {
+            // Smoothed IDF so a term shared by every project still counts a little
+            let idf = ((project_count + 1.0) / (document_frequency[term.as_str()] as f64 + 0.5)).ln();
+            let weight = (*count as f64 / total as f64) * idf;
+            tx.execute(
+                "INSERT INTO project_keywords (project_id, term, weight, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                params![project_id, term, weight, now],
+            )
+            .map_err(|e| format!("Failed to save project keyword: {}", e))?;
+            written += 1;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(written)
+}
+
+/// Rank projects by keyword overlap with the task text
+pub fn suggest_project_for_task(
+    conn: &rusqlite::Connection,
+    title: &str,
+    description: Option<&str>,
+) -> Result<Vec<ProjectSuggestion>, String> {
+    let mut terms: HashSet<String> = tokenize(title).into_iter().collect();
+    if let Some(description) = description {
+        terms.extend(tokenize(description));
+    }
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT pk.project_id, p.name, pk.weight FROM project_keywords pk
+            INNER JOIN projects p ON p.id = pk.project_id
+            WHERE pk.term = ?1",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let mut scores: HashMap<String, (String, f64)> = HashMap::new();
+    for term in &terms {
+        let rows = stmt
+            .query_map(params![term], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+            })
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (project_id, name, weight) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            scores.entry(project_id).or_insert((name, 0.0)).1 += weight;
+        }
+    }
+
+    let total: f64 = scores.values().map(|(_, score)| score).sum();
+    let mut suggestions: Vec<ProjectSuggestion> = scores
+        .into_iter()
+        .filter(|(_, (_, score))| *score >= MIN_SCORE)
+        .map(|(project_id, (project_name, score))| ProjectSuggestion {
+            project_id,
+            project_name,
+            score,
+            confidence: (1.0 - (-score / CONFIDENCE_SCALE).exp()) * (score / total),
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    suggestions.truncate(MAX_SUGGESTIONS);
+
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, project_id TEXT);
+            INSERT INTO projects (id, name) VALUES ('home', 'Home'), ('work', 'Work');
+            INSERT INTO tasks (id, title, project_id) VALUES
+                ('1', 'Fix the kitchen sink', 'home'),
+                ('2', 'Buy paint for the kitchen', 'home'),
+                ('3', 'Water the garden', 'home'),
+                ('4', 'Prepare quarterly report', 'work'),
+                ('5', 'Review report with team', 'work'),
+                ('6', 'Email the client', 'work'),
+                ('7', 'Buy groceries', NULL);",
+        )
+        .unwrap();
+        conn.execute_batch(include_str!("../../migrations/0022_add_project_keywords.sql")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_suggests_project_by_keywords() {
+        let conn = setup_test_conn();
+        assert!(rebuild_project_keywords(&conn, 0).unwrap() > 0);
+
+        let suggestions = suggest_project_for_task(&conn, "Clean kitchen floor", None).unwrap();
+        assert_eq!(suggestions[0].project_id, "home");
+        assert!((suggestions[0].confidence - 0.5).abs() < 0.01);
+
+        let suggestions = suggest_project_for_task(&conn, "Fix kitchen sink", None).unwrap();
+        assert_eq!(suggestions[0].project_id, "home");
+        assert!(suggestions[0].confidence >= DEFAULT_AUTO_FILE_THRESHOLD);
+
+        let suggestions = suggest_project_for_task(&conn, "Send report", Some("to the client")).unwrap();
+        assert_eq!(suggestions[0].project_id, "work");
+
+        assert!(suggest_project_for_task(&conn, "Walk the dog", None).unwrap().is_empty());
+        assert!(suggest_project_for_task(&conn, "a b", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_single_weak_match_has_low_confidence() {
+        let conn = setup_test_conn();
+        rebuild_project_keywords(&conn, 0).unwrap();
+
+        // "water" appears once among home's titles: the only match, but not a convincing one
+        let suggestions = suggest_project_for_task(&conn, "Water plants", None).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].project_id, "home");
+        assert!(suggestions[0].confidence < 0.35);
+        assert!(suggestions[0].confidence < DEFAULT_AUTO_FILE_THRESHOLD);
+    }
+}
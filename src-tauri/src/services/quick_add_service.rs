@@ -0,0 +1,466 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Weekday};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Settings key holding the most recently used project ids, newest first, as a JSON list
+pub const RECENT_PROJECTS_KEY: &str = "recent_project_ids";
+pub const MAX_RECENT_PROJECTS: usize = 8;
+
+/// Result of running a single line of free text through the quick-add parser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedQuickAdd {
+    pub title: String,
+    pub due_date: Option<i64>, // Unix timestamp (local midnight of the parsed day)
+    pub priority: Option<String>,
+    pub tags: Vec<String>,
+    /// Raw `@project` token without the `@`, resolved against project names by the caller
+    pub project: Option<String>,
+}
+
+// Abbreviations such as "sun" or "sat" are also ordinary words, so they only count when
+// `allow_short` is set (after "on" or "next")
+fn parse_weekday(word: &str, allow_short: bool) -> Option<Weekday> {
+    let weekday = match word {
+        "monday" => Weekday::Mon,
+        "tuesday" => Weekday::Tue,
+        "wednesday" => Weekday::Wed,
+        "thursday" => Weekday::Thu,
+        "friday" => Weekday::Fri,
+        "saturday" => Weekday::Sat,
+        "sunday" => Weekday::Sun,
+        _ if !allow_short => return None,
+        "mon" => Weekday::Mon,
+        "tue" | "tues" => Weekday::Tue,
+        "wed" => Weekday::Wed,
+        "thu" | "thurs" => Weekday::Thu,
+        "fri" => Weekday::Fri,
+        "sat" => Weekday::Sat,
+        "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+fn parse_priority(token: &str) -> Option<String> {
+    let value = token.strip_prefix('!')?.to_lowercase();
+    let priority = match value.as_str() {
+        "high" | "h" | "1" => "high",
+        "medium" | "med" | "m" | "2" => "medium",
+        "low" | "l" | "3" => "low",
+        _ => return None,
+    };
+    Some(priority.to_string())
+}
+
+fn parse_tag(token: &str) -> Option<String> {
+    let name = token
+        .strip_prefix('#')?
+        .trim_end_matches([',', '.', ';'])
+        .to_lowercase();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn parse_project(token: &str) -> Option<String> {
+    let name = token.strip_prefix('@')?.trim_end_matches([',', '.', ';']);
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+// #tag, @project or !priority, which never end up in the title
+fn is_marker_token(token: &str) -> bool {
+    parse_tag(token).is_some() || parse_project(token).is_some() || parse_priority(token).is_some()
+}
+
+fn local_midnight(date: NaiveDate) -> Option<i64> {
+    let naive = date.and_hms_opt(0, 0, 0)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp())
+}
+
+// None when the result falls outside the dates chrono can represent
+fn add_days(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    date.checked_add_signed(Duration::try_days(days)?)
+}
+
+/// Try to interpret the words starting at `words[0]` as a date expression.
+/// Returns the parsed date and the number of words consumed.
+fn parse_date_at(words: &[String], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let first = words.first()?.to_lowercase();
+    let first = first.trim_end_matches([',', '.']);
+
+    match first {
+        "today" | "tonight" => return Some((today, 1)),
+        "tomorrow" | "tmr" => return Some((today.succ_opt()?, 1)),
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+        return Some((date, 1));
+    }
+
+    // "in 3 days", "in 2 weeks"; amounts too large for a date are left in the title
+    if first == "in" && words.len() >= 3 {
+        if let Ok(amount) = words[1].parse::<i64>() {
+            let unit = words[2].to_lowercase();
+            let unit = unit.trim_end_matches([',', '.']);
+            let days = match unit {
+                "day" | "days" => Some(amount),
+                "week" | "weeks" => amount.checked_mul(7),
+                _ => None,
+            };
+            if let Some(date) = days.and_then(|days| add_days(today, days)) {
+                return Some((date, 3));
+            }
+        }
+    }
+
+    // "friday", "on fri", "next friday"
+    let (weekday_word, consumed, force_next_week) = match first {
+        "on" if words.len() >= 2 => (words[1].to_lowercase(), 2, false),
+        "next" if words.len() >= 2 => (words[1].to_lowercase(), 2, true),
+        _ => (first.to_string(), 1, false),
+    };
+    let weekday = parse_weekday(weekday_word.trim_end_matches([',', '.']), consumed == 2)?;
+    // A bare full name is only a date at the end of the line, so "Monday standup notes" keeps it
+    if consumed == 1 && !words[1..].iter().all(|word| is_marker_token(word)) {
+        return None;
+    }
+    let mut days_ahead = (weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64
+        + 7)
+        % 7;
+    if days_ahead == 0 || force_next_week {
+        days_ahead += 7;
+    }
+    Some((add_days(today, days_ahead)?, consumed))
+}
+
+/// Parse a line of quick-add text like "call dentist tomorrow #health !high".
+/// Recognised tokens (#tags, !priority, @project, date expressions) are removed from the title.
+/// When several date expressions or project tokens appear, the last one wins.
+pub fn parse_quick_add(input: &str, now: DateTime<Local>) -> ParsedQuickAdd {
+    let today = now.date_naive();
+    let words: Vec<String> = input.split_whitespace().map(|w| w.to_string()).collect();
+
+    let mut title_words: Vec<&str> = Vec::new();
+    let mut due_date = None;
+    let mut priority = None;
+    let mut tags: Vec<String> = Vec::new();
+    let mut project = None;
+
+    let mut i = 0;
+    while i < words.len() {
+        let word = &words[i];
+
+        if let Some(tag) = parse_tag(word) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+            i += 1;
+            continue;
+        }
+
+        if let Some(name) = parse_project(word) {
+            project = Some(name);
+            i += 1;
+            continue;
+        }
+
+        if let Some(p) = parse_priority(word) {
+            priority = Some(p);
+            i += 1;
+            continue;
+        }
+
+        if let Some((date, consumed)) = parse_date_at(&words[i..], today) {
+            due_date = local_midnight(date);
+            i += consumed;
+            continue;
+        }
+
+        title_words.push(word);
+        i += 1;
+    }
+
+    ParsedQuickAdd {
+        title: title_words.join(" "),
+        due_date,
+        priority,
+        tags,
+        project,
+    }
+}
+
+/// Most recently used projects, newest first. Held as managed state so the quick-add window
+/// can offer them without a settings round trip, and written through to settings on change.
+#[derive(Default)]
+pub struct RecentProjects(pub Mutex<Vec<String>>);
+
+impl RecentProjects {
+    pub fn load(conn: &rusqlite::Connection) -> Self {
+        let ids = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![RECENT_PROJECTS_KEY],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|value| serde_json::from_str::<Vec<String>>(&value).ok())
+            .unwrap_or_default();
+        RecentProjects(Mutex::new(ids))
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.0.lock().map(|ids| ids.clone()).unwrap_or_default()
+    }
+
+    /// Move `project_id` to the front of the list and persist it
+    pub fn record(&self, conn: &rusqlite::Connection, project_id: &str) -> Result<(), String> {
+        let mut ids = self.0.lock().map_err(|e| format!("Recent projects lock error: {}", e))?;
+        ids.retain(|id| id != project_id);
+        ids.insert(0, project_id.to_string());
+        ids.truncate(MAX_RECENT_PROJECTS);
+
+        let value = serde_json::to_string(&*ids)
+            .map_err(|e| format!("Failed to serialize recent projects: {}", e))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![RECENT_PROJECTS_KEY, value],
+        ).map_err(|e| format!("Failed to save recent projects: {}", e))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectCandidate {
+    pub id: String,
+    pub name: String,
+}
+
+/// Returned (serialized to JSON) when an `@project` token does not pick out a single project,
+/// so the quick-add window can offer the candidates instead of a bare message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectResolutionError {
+    pub message: String,
+    pub token: String,
+    pub candidates: Vec<ProjectCandidate>,
+}
+
+impl ProjectResolutionError {
+    pub fn to_error_string(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| self.message.clone())
+    }
+}
+
+/// Resolve an `@project` token to a project. An exact (case-insensitive) name match always wins;
+/// otherwise the token is treated as a name prefix, first among recent projects and then among
+/// all projects, and must match exactly one of them ("@wo" -> "Work").
+pub fn resolve_project(
+    conn: &rusqlite::Connection,
+    recent_ids: &[String],
+    token: &str,
+) -> Result<ProjectCandidate, ProjectResolutionError> {
+    let needle = token.to_lowercase();
+
+    let mut stmt = conn
+        .prepare("SELECT id, name FROM projects ORDER BY created_at")
+        .map_err(|e| resolution_error(token, format!("Query error: {}", e), Vec::new()))?;
+    let projects = stmt
+        .query_map([], |row| Ok(ProjectCandidate { id: row.get(0)?, name: row.get(1)? }))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| resolution_error(token, format!("Query execution error: {}", e), Vec::new()))?;
+
+    if let Some(exact) = projects.iter().find(|p| p.name.to_lowercase() == needle) {
+        return Ok(exact.clone());
+    }
+
+    let matching: Vec<&ProjectCandidate> = projects
+        .iter()
+        .filter(|p| p.name.to_lowercase().starts_with(&needle))
+        .collect();
+
+    let mut recent_matches: Vec<ProjectCandidate> = recent_ids
+        .iter()
+        .filter_map(|id| matching.iter().find(|p| &p.id == id).map(|p| (*p).clone()))
+        .collect();
+    if recent_matches.len() == 1 {
+        return Ok(recent_matches.remove(0));
+    }
+    if recent_matches.len() > 1 {
+        return Err(resolution_error(
+            token,
+            format!("\"@{}\" matches several recent projects", token),
+            recent_matches,
+        ));
+    }
+
+    match matching.len() {
+        0 => Err(resolution_error(token, format!("No project matches \"@{}\"", token), Vec::new())),
+        1 => Ok(matching[0].clone()),
+        _ => Err(resolution_error(
+            token,
+            format!("\"@{}\" matches several projects", token),
+            matching.into_iter().cloned().collect(),
+        )),
+    }
+}
+
+fn resolution_error(token: &str, message: String, candidates: Vec<ProjectCandidate>) -> ProjectResolutionError {
+    ProjectResolutionError {
+        message,
+        token: token.to_string(),
+        candidates,
+    }
+}
+
+/// Strip common list markers from the start of a pasted line:
+/// "- ", "* ", "+ ", "1. ", "1) " and markdown checkboxes "[ ]" / "[x]".
+pub fn strip_list_marker(line: &str) -> &str {
+    let mut rest = line.trim();
+
+    if let Some(stripped) = rest
+        .strip_prefix("- ")
+        .or_else(|| rest.strip_prefix("* "))
+        .or_else(|| rest.strip_prefix("+ "))
+        .or_else(|| rest.strip_prefix('•'))
+    {
+        rest = stripped.trim_start();
+    } else if rest == "-" || rest == "*" || rest == "+" {
+        return "";
+    } else {
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            let after = &rest[digits..];
+            if let Some(stripped) = after.strip_prefix('.').or_else(|| after.strip_prefix(')')) {
+                if stripped.is_empty() || stripped.starts_with(char::is_whitespace) {
+                    rest = stripped.trim_start();
+                }
+            }
+        }
+    }
+
+    for checkbox in ["[ ]", "[x]", "[X]", "[]"] {
+        if let Some(stripped) = rest.strip_prefix(checkbox) {
+            rest = stripped.trim_start();
+            break;
+        }
+    }
+
+    rest.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_now() -> DateTime<Local> {
+        // Wednesday 2024-01-10, noon local time
+        Local.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap()
+    }
+
+    fn midnight(y: i32, m: u32, d: u32) -> Option<i64> {
+        local_midnight(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    #[test]
+    fn test_strip_list_marker() {
+        assert_eq!(strip_list_marker("- buy milk"), "buy milk");
+        assert_eq!(strip_list_marker("* call dentist"), "call dentist");
+        assert_eq!(strip_list_marker("12. book flights"), "book flights");
+        assert_eq!(strip_list_marker("3) pay rent"), "pay rent");
+        assert_eq!(strip_list_marker("- [ ] water plants"), "water plants");
+        assert_eq!(strip_list_marker("- [x] done already"), "done already");
+        assert_eq!(strip_list_marker("[ ] bare checkbox"), "bare checkbox");
+        assert_eq!(strip_list_marker("- [ ]"), "");
+        assert_eq!(strip_list_marker("  -  "), "");
+        // Numbers that are part of the title are kept
+        assert_eq!(strip_list_marker("2024 taxes"), "2024 taxes");
+    }
+
+    #[test]
+    fn test_parse_quick_add_tokens() {
+        let parsed = parse_quick_add("call dentist tomorrow #health !high", fixed_now());
+        assert_eq!(parsed.title, "call dentist");
+        assert_eq!(parsed.due_date, midnight(2024, 1, 11));
+        assert_eq!(parsed.priority.as_deref(), Some("high"));
+        assert_eq!(parsed.tags, vec!["health".to_string()]);
+        assert_eq!(parsed.project, None);
+    }
+
+    #[test]
+    fn test_resolve_project_prefix() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, created_at INTEGER NOT NULL);
+             CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             INSERT INTO projects VALUES ('p-work', 'Work', 1), ('p-workout', 'Workout', 2), ('p-home', 'Home', 3);",
+        ).unwrap();
+
+        let parsed = parse_quick_add("send report @wo, tomorrow", fixed_now());
+        assert_eq!(parsed.title, "send report");
+        assert_eq!(parsed.project.as_deref(), Some("wo"));
+
+        // Two projects share the prefix and neither was used recently
+        let err = resolve_project(&conn, &[], "wo").unwrap_err();
+        let ids: Vec<&str> = err.candidates.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["p-work", "p-workout"]);
+        assert!(serde_json::from_str::<ProjectResolutionError>(&err.to_error_string()).is_ok());
+
+        // Recent use breaks the tie, and the recent list survives a reload from settings
+        let recent = RecentProjects::default();
+        recent.record(&conn, "p-home").unwrap();
+        recent.record(&conn, "p-work").unwrap();
+        let reloaded = RecentProjects::load(&conn);
+        assert_eq!(reloaded.ids(), vec!["p-work".to_string(), "p-home".to_string()]);
+        assert_eq!(resolve_project(&conn, &reloaded.ids(), "wo").unwrap().name, "Work");
+
+        // Exact names win over the recent list; unknown prefixes fail without candidates
+        assert_eq!(resolve_project(&conn, &reloaded.ids(), "WORKOUT").unwrap().id, "p-workout");
+        assert!(resolve_project(&conn, &[], "garden").unwrap_err().candidates.is_empty());
+    }
+
+    #[test]
+    fn test_parse_quick_add_dates() {
+        assert_eq!(parse_quick_add("a friday", fixed_now()).due_date, midnight(2024, 1, 12));
+        assert_eq!(parse_quick_add("a wednesday", fixed_now()).due_date, midnight(2024, 1, 17));
+        assert_eq!(parse_quick_add("a next friday", fixed_now()).due_date, midnight(2024, 1, 19));
+        assert_eq!(parse_quick_add("a in 2 weeks", fixed_now()).due_date, midnight(2024, 1, 24));
+        assert_eq!(parse_quick_add("a 2024-03-01", fixed_now()).due_date, midnight(2024, 3, 1));
+        assert_eq!(parse_quick_add("a on fri", fixed_now()).due_date, midnight(2024, 1, 12));
+
+        // Too far out to be a date: kept as words instead of overflowing
+        for input in ["a in 9223372036854775807 weeks", "a in 2000000000000 days"] {
+            let parsed = parse_quick_add(input, fixed_now());
+            assert_eq!(parsed.title, input);
+            assert_eq!(parsed.due_date, None);
+        }
+        assert_eq!(parse_quick_add("a next sat", fixed_now()).due_date, midnight(2024, 1, 20));
+
+        // A bare weekday name followed by more title is just a word
+        let standup = parse_quick_add("Monday standup notes", fixed_now());
+        assert_eq!((standup.title.as_str(), standup.due_date), ("Monday standup notes", None));
+        let call = parse_quick_add("call dentist friday #health", fixed_now());
+        assert_eq!((call.title.as_str(), call.due_date), ("call dentist", midnight(2024, 1, 12)));
+        assert_eq!(parse_quick_add("prep on Monday for review", fixed_now()).due_date, midnight(2024, 1, 15));
+
+        // A bare abbreviation is just a word
+        let sunrise = parse_quick_add("Watch sun rise", fixed_now());
+        assert_eq!(sunrise.title, "Watch sun rise");
+        assert_eq!(sunrise.due_date, None);
+
+        let plain = parse_quick_add("buy milk", fixed_now());
+        assert_eq!(plain.title, "buy milk");
+        assert_eq!(plain.due_date, None);
+        assert!(plain.tags.is_empty());
+    }
+}
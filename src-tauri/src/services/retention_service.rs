@@ -0,0 +1,202 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+// Source of the per-month summary rows that replace pruned xp_history entries
+pub const XP_ROLLUP_SOURCE: &str = "monthly_rollup";
+// "rollup" (default) keeps XP totals intact; "delete" drops old rows outright
+const XP_RETENTION_MODE_SETTING: &str = "xp_history_retention_mode";
+
+/// Append-only tables that can be pruned. Retention is read from `retention_days_<table>`.
+pub const RETAINED_TABLES: &[&str] = &["xp_history", "activity_log"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionStatus {
+    pub table: String,
+    /// 0 keeps rows forever
+    pub retention_days: i64,
+    pub row_count: i64,
+    pub oldest_at: Option<i64>,
+    pub last_pruned_at: Option<i64>,
+    pub last_removed: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneResult {
+    pub table: String,
+    pub removed: usize,
+    /// Summary rows written in place of removed xp_history rows
+    pub rolled_up: usize,
+}
+
+fn get_setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).ok()
+}
+
+pub fn retention_days(conn: &rusqlite::Connection, table: &str) -> i64 {
+    get_setting(conn, &format!("retention_days_{}", table))
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0)
+        .max(0)
+}
+
+pub fn get_retention_status(conn: &rusqlite::Connection) -> Result<Vec<RetentionStatus>, String> {
+    RETAINED_TABLES
+        .iter()
+        .map(|table| {
+            let (row_count, oldest_at): (i64, Option<i64>) = conn.query_row(
+                &format!("SELECT COUNT(*), MIN(created_at) FROM {}", table),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ).map_err(|e| format!("Query error: {}", e))?;
+            let last = conn.query_row(
+                "SELECT last_pruned_at, last_removed FROM retention_state WHERE table_name = ?1",
+                params![table],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            ).ok();
+            Ok(RetentionStatus {
+                table: table.to_string(),
+                retention_days: retention_days(conn, table),
+                row_count,
+                oldest_at,
+                last_pruned_at: last.map(|(at, _)| at),
+                last_removed: last.map(|(_, removed)| removed).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+// Fold xp_history rows older than `cutoff` into one row per user and month, so SUM(xp_amount) is unchanged
+fn roll_up_xp_history(tx: &rusqlite::Transaction, cutoff: i64) -> Result<(usize, usize), String> {
+    let rolled_up = tx.execute(
+        "INSERT INTO xp_history (id, user_id, xp_amount, source, task_id, created_at)
+         SELECT 'rollup-' || user_id || '-' || strftime('%Y-%m', created_at, 'unixepoch'),
+                user_id, SUM(xp_amount), ?1, NULL,
+                CAST(strftime('%s', created_at, 'unixepoch', 'start of month') AS INTEGER)
+         FROM xp_history
+         WHERE created_at < ?2 AND source != ?1
+         GROUP BY user_id, strftime('%Y-%m', created_at, 'unixepoch')
+         ON CONFLICT(id) DO UPDATE SET xp_amount = xp_amount + excluded.xp_amount",
+        params![XP_ROLLUP_SOURCE, cutoff],
+    ).map_err(|e| format!("Failed to roll up XP history: {}", e))?;
+    let removed = tx.execute(
+        "DELETE FROM xp_history WHERE created_at < ?1 AND source != ?2",
+        params![cutoff, XP_ROLLUP_SOURCE],
+    ).map_err(|e| format!("Failed to prune XP history: {}", e))?;
+    Ok((removed, rolled_up))
+}
+
+/// Remove rows older than each table's retention. Tables with retention 0 are left alone.
+pub fn prune_retained_tables(conn: &rusqlite::Connection, now: i64) -> Result<Vec<PruneResult>, String> {
+    let mut results = Vec::new();
+    for table in RETAINED_TABLES {
+        let days = retention_days(conn, table);
+        if days == 0 {
+            continue;
+        }
+        let cutoff = now - days * DAY_SECONDS;
+        
+        let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let rollup = *table == "xp_history" && get_setting(conn, XP_RETENTION_MODE_SETTING).as_deref() != Some("delete");
+        let (removed, rolled_up) = if rollup {
+            roll_up_xp_history(&tx, cutoff)?
+        } else {
+            let removed = tx.execute(&format!("DELETE FROM {} WHERE created_at < ?1", table), params![cutoff])
+                .map_err(|e| format!("Failed to prune {}: {}", table, e))?;
+            (removed, 0)
+        };
+        tx.execute(
+            "INSERT INTO retention_state (table_name, last_pruned_at, last_removed) VALUES (?1, ?2, ?3)
+             ON CONFLICT(table_name) DO UPDATE SET last_pruned_at = ?2, last_removed = ?3",
+            params![table, now, removed as i64],
+        ).map_err(|e| format!("Failed to record prune: {}", e))?;
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        
+        results.push(PruneResult { table: table.to_string(), removed, rolled_up });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE xp_history (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL DEFAULT 'default',
+                xp_amount INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                task_id TEXT,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE activity_log (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                source TEXT NOT NULL DEFAULT 'user',
+                created_at INTEGER NOT NULL
+            );",
+        )
+        .unwrap();
+        conn.execute_batch(include_str!("../../migrations/0030_add_retention_state.sql")).unwrap();
+        conn
+    }
+
+    fn xp_total(conn: &rusqlite::Connection) -> i64 {
+        conn.query_row("SELECT SUM(xp_amount) FROM xp_history", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_xp_rollup_preserves_totals() {
+        let conn = setup_test_conn();
+        // 2024-01-10, 2024-01-20, 2024-02-05 and a recent entry
+        let now = 1_720_000_000;
+        conn.execute_batch(&format!(
+            "INSERT INTO xp_history (id, xp_amount, source, task_id, created_at) VALUES
+                ('a', 10, 'task_completion', 't1', 1704844800),
+                ('b', 25, 'task_completion', 't2', 1705708800),
+                ('c', -10, 'task_uncomplete', 't1', 1707091200),
+                ('d', 40, 'task_completion', 't3', {recent});
+            INSERT INTO activity_log (id, task_id, action, created_at) VALUES
+                ('old', 't1', 'priority_changed', 1704844800), ('new', 't1', 'priority_changed', {recent});",
+            recent = now - 60,
+        )).unwrap();
+        
+        // Nothing happens until a retention is configured
+        assert!(prune_retained_tables(&conn, now).unwrap().is_empty());
+        
+        let before = xp_total(&conn);
+        conn.execute_batch(
+            "INSERT INTO settings (key, value) VALUES ('retention_days_xp_history', '90'), ('retention_days_activity_log', '90');"
+        ).unwrap();
+        let results = prune_retained_tables(&conn, now).unwrap();
+        assert_eq!((results[0].removed, results[0].rolled_up), (3, 2));
+        assert_eq!(results[1].removed, 1);
+        assert_eq!(xp_total(&conn), before);
+        
+        let january: i64 = conn.query_row(
+            "SELECT xp_amount FROM xp_history WHERE id = 'rollup-default-2024-01'", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(january, 35);
+        
+        // Pruning again folds newly expired rows into the existing summary
+        conn.execute("INSERT INTO xp_history (id, xp_amount, source, created_at) VALUES ('e', 5, 'bonus', 1705000000)", []).unwrap();
+        prune_retained_tables(&conn, now).unwrap();
+        assert_eq!(xp_total(&conn), before + 5);
+        
+        let status = get_retention_status(&conn).unwrap();
+        assert_eq!((status[0].table.as_str(), status[0].row_count), ("xp_history", 3));
+        assert_eq!((status[1].row_count, status[1].last_pruned_at), (1, Some(now)));
+    }
+}
@@ -0,0 +1,314 @@
+use super::week_service;
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone, Weekday};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// Planned minutes per day that the "spread" strategy fills before using another weekday.
+/// Unset or 0 means no limit, so tasks are just spread evenly.
+pub const DAILY_CAPACITY_SETTING: &str = "daily_capacity_minutes";
+// Tasks don't carry an estimate yet, so each one counts as this much planned time
+const DEFAULT_TASK_MINUTES: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloverMove {
+    pub task_id: String,
+    pub title: String,
+    pub old_due_date: i64,
+    pub new_due_date: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloverResult {
+    pub from_week_start: i64,
+    pub to_week_start: i64,
+    pub dry_run: bool,
+    pub moves: Vec<RolloverMove>,
+}
+
+struct OpenTask {
+    id: String,
+    title: String,
+    due_at: i64,
+}
+
+fn local_date(timestamp: i64) -> NaiveDate {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+// Same local time of day on another date
+fn move_to_date(due_at: i64, date: NaiveDate) -> i64 {
+    let time_of_day = due_at - week_service::local_midnight(local_date(due_at));
+    week_service::local_midnight(date) + time_of_day
+}
+
+fn load_daily_capacity(conn: &rusqlite::Connection) -> Option<i64> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![DAILY_CAPACITY_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|v| v.trim().parse::<i64>().ok())
+    .filter(|minutes| *minutes > 0)
+}
+
+fn open_tasks_due_between(conn: &rusqlite::Connection, start: i64, end: i64) -> Result<Vec<OpenTask>, String> {
+    // Most important first, so "spread" gives them the emptiest days
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, due_at FROM tasks
+            WHERE completed_at IS NULL AND archived = 0 AND due_at >= ?1 AND due_at < ?2
+            ORDER BY CASE priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 ELSE 2 END, due_at, id",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok(OpenTask {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                due_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut tasks = Vec::new();
+    for row in rows {
+        tasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(tasks)
+}
+
+// Pick a weekday for each task: the least planned day that still has room for it, or the least
+// planned day overall once every day is full. Ties go to the earlier day.
+fn spread_over_days(conn: &rusqlite::Connection, tasks: &[OpenTask], days: &[NaiveDate]) -> Result<Vec<NaiveDate>, String> {
+    let capacity = load_daily_capacity(conn);
+    let mut planned = Vec::with_capacity(days.len());
+    for day in days {
+        let already_due = open_tasks_due_between(
+            conn,
+            week_service::local_midnight(*day),
+            week_service::local_midnight(*day + Duration::days(1)),
+        )?;
+        planned.push(already_due.len() as i64 * DEFAULT_TASK_MINUTES);
+    }
+
+    let mut dates = Vec::with_capacity(tasks.len());
+    for _ in tasks {
+        let has_room = |load: i64| capacity.is_none_or(|capacity| load + DEFAULT_TASK_MINUTES <= capacity);
+        let least_planned = |only_with_room: bool| {
+            (0..days.len())
+                .filter(|i| !only_with_room || has_room(planned[*i]))
+                .min_by_key(|i| (planned[*i], *i))
+        };
+        let Some(index) = least_planned(true).or_else(|| least_planned(false)) else {
+            break;
+        };
+        planned[index] += DEFAULT_TASK_MINUTES;
+        dates.push(days[index]);
+    }
+    Ok(dates)
+}
+
+/// Work out where each incomplete task due in the week containing `from_week_start` goes in the
+/// following week, without changing anything. Strategies: "next_monday", "same_weekday" or
+/// "spread" (over next week's weekdays, see DAILY_CAPACITY_SETTING). Times of day are kept.
+pub fn plan_rollover(conn: &rusqlite::Connection, from_week_start: i64, strategy: &str) -> Result<RolloverResult, String> {
+    let bounds = week_service::week_bounds(from_week_start, week_service::load_week_starts_on(conn));
+    let next_week: Vec<NaiveDate> = (0..7).map(|i| local_date(bounds.week_end) + Duration::days(i)).collect();
+    let weekdays: Vec<NaiveDate> = next_week
+        .iter()
+        .copied()
+        .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+        .collect();
+
+    let tasks = open_tasks_due_between(conn, bounds.week_start, bounds.week_end)?;
+    let dates: Vec<NaiveDate> = match strategy {
+        "next_monday" => {
+            let monday = next_week
+                .iter()
+                .copied()
+                .find(|date| date.weekday() == Weekday::Mon)
+                .unwrap_or(next_week[0]);
+            vec![monday; tasks.len()]
+        }
+        "same_weekday" => tasks.iter().map(|task| local_date(task.due_at) + Duration::days(7)).collect(),
+        "spread" => spread_over_days(conn, &tasks, &weekdays)?,
+        _ => return Err(format!("Unknown rollover strategy: {}", strategy)),
+    };
+
+    Ok(RolloverResult {
+        from_week_start: bounds.week_start,
+        to_week_start: bounds.week_end,
+        dry_run: true,
+        moves: tasks
+            .into_iter()
+            .zip(dates)
+            .map(|(task, date)| RolloverMove {
+                new_due_date: move_to_date(task.due_at, date),
+                task_id: task.id,
+                title: task.title,
+                old_due_date: task.due_at,
+            })
+            .collect(),
+    })
+}
+
+/// Move the planned tasks: due dates change, reminders are rescheduled, and each move is
+/// recorded in the due date history and activity log with source "rollover"
+pub fn apply_rollover(conn: &rusqlite::Connection, from_week_start: i64, strategy: &str, now: i64) -> Result<RolloverResult, String> {
+    let mut result = plan_rollover(conn, from_week_start, strategy)?;
+    result.dry_run = false;
+    if result.moves.is_empty() {
+        return Ok(result);
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for entry in &result.moves {
+        tx.execute(
+            "UPDATE tasks SET due_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![entry.new_due_date, now, entry.task_id],
+        )
+        .map_err(|e| format!("Failed to update due date: {}", e))?;
+        crate::commands::record_due_date_change(&tx, &entry.task_id, Some(entry.old_due_date), Some(entry.new_due_date), "rollover")?;
+        crate::commands::log_activity(
+            &tx,
+            &entry.task_id,
+            "due_date_changed",
+            Some(&entry.old_due_date.to_string()),
+            Some(&entry.new_due_date.to_string()),
+            "rollover",
+        )?;
+
+        let reminder_minutes: Option<i32> = tx
+            .query_row(
+                "SELECT reminder_minutes_before FROM tasks WHERE id = ?1",
+                params![entry.task_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Task not found: {}", e))?;
+        if reminder_minutes.is_some() {
+            crate::notifications::reschedule_reminder(&tx, &entry.task_id, reminder_minutes, now)
+                .map_err(|e| format!("Failed to reschedule reminder: {}", e))?;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                due_at INTEGER,
+                updated_at INTEGER NOT NULL DEFAULT 0,
+                priority TEXT NOT NULL DEFAULT 'medium',
+                completed_at INTEGER,
+                archived INTEGER NOT NULL DEFAULT 0,
+                reminder_minutes_before INTEGER DEFAULT NULL
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        ).unwrap();
+        conn.execute_batch(include_str!("../../migrations/0016_add_due_date_history.sql")).unwrap();
+        conn.execute_batch(include_str!("../../migrations/0018_add_activity_log.sql")).unwrap();
+        conn
+    }
+
+    fn at(date: NaiveDate, hour: i64) -> i64 {
+        week_service::local_midnight(date) + hour * 3600
+    }
+
+    #[test]
+    fn test_rollover_strategies() {
+        let conn = setup_test_conn();
+        // Week of Mon 2024-04-29; the next week starts Mon 2024-05-06
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, if d >= 29 { 4 } else { 5 }, d).unwrap();
+        let week_start = at(day(29), 0);
+        conn.execute(
+            "INSERT INTO tasks (id, title, due_at, priority) VALUES
+                ('wed', 'Wednesday', ?1, 'low'), ('fri', 'Friday', ?2, 'high'), ('thu', 'Thursday', ?3, 'medium')",
+            params![at(day(1), 9), at(day(3), 17), at(day(2), 12)],
+        ).unwrap();
+        conn.execute_batch(&format!(
+            "INSERT INTO tasks (id, title, due_at, completed_at) VALUES ('done', 'Done', {}, 1);
+             INSERT INTO tasks (id, title, due_at) VALUES ('planned', 'Already planned', {});",
+            at(day(1), 9),
+            at(day(6), 10),
+        )).unwrap();
+
+        let moves = |strategy: &str| -> Vec<(String, i64)> {
+            plan_rollover(&conn, week_start + 3600, strategy)
+                .unwrap()
+                .moves
+                .into_iter()
+                .map(|entry| (entry.task_id, entry.new_due_date))
+                .collect()
+        };
+        assert_eq!(moves("next_monday"), vec![
+            ("fri".to_string(), at(day(6), 17)),
+            ("thu".to_string(), at(day(6), 12)),
+            ("wed".to_string(), at(day(6), 9)),
+        ]);
+        assert_eq!(moves("same_weekday"), vec![
+            ("fri".to_string(), at(day(10), 17)),
+            ("thu".to_string(), at(day(9), 12)),
+            ("wed".to_string(), at(day(8), 9)),
+        ]);
+        // Monday already has a task, so the high priority one gets Tuesday
+        assert_eq!(moves("spread"), vec![
+            ("fri".to_string(), at(day(7), 17)),
+            ("thu".to_string(), at(day(8), 12)),
+            ("wed".to_string(), at(day(9), 9)),
+        ]);
+        // With room for one task a day, Monday is full and the rest fill up in order
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, '30')", params![DAILY_CAPACITY_SETTING]).unwrap();
+        assert_eq!(moves("spread")[0].1, at(day(7), 17));
+        assert!(plan_rollover(&conn, week_start, "someday").is_err());
+
+        // Dry runs leave the tasks alone
+        let due = |id: &str| -> i64 {
+            conn.query_row("SELECT due_at FROM tasks WHERE id = ?1", params![id], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(due("wed"), at(day(1), 9));
+
+        let result = apply_rollover(&conn, week_start, "same_weekday", 1_000).unwrap();
+        assert!(!result.dry_run);
+        assert_eq!(result.to_week_start, at(day(6), 0));
+        assert_eq!(due("wed"), at(day(8), 9));
+        assert_eq!(due("done"), at(day(1), 9));
+        let logged: i64 = conn
+            .query_row("SELECT COUNT(*) FROM activity_log WHERE source = 'rollover'", [], |row| row.get(0))
+            .unwrap();
+        let history: i64 = conn
+            .query_row("SELECT COUNT(*) FROM due_date_history WHERE source = 'rollover'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!((logged, history), (3, 3));
+        // Nothing left to roll over
+        assert!(apply_rollover(&conn, week_start, "same_weekday", 1_000).unwrap().moves.is_empty());
+    }
+
+    #[test]
+    fn test_rollover_leaves_archived_tasks_alone() {
+        let conn = setup_test_conn();
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, title, due_at, archived, reminder_minutes_before) VALUES ('old', 'Archived', ?1, 1, 15)",
+            params![at(monday, 9)],
+        ).unwrap();
+
+        let result = apply_rollover(&conn, at(monday, 0), "next_monday", 1_000).unwrap();
+        assert!(result.moves.is_empty());
+        let due: i64 = conn.query_row("SELECT due_at FROM tasks WHERE id = 'old'", [], |row| row.get(0)).unwrap();
+        assert_eq!(due, at(monday, 9));
+    }
+}
@@ -0,0 +1,483 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PRIORITIES: &[&str] = &["low", "medium", "high"];
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// `field` is "title", "description", "project" or "priority". Text fields use the "contains"
+/// operator (case-insensitive); project (by id) and priority use "equals".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleCondition {
+    pub field: String,
+    pub operator: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleActions {
+    /// Tag names; missing tags are created
+    pub add_tags: Vec<String>,
+    pub set_project_id: Option<String>,
+    pub set_priority: Option<String>,
+    pub set_reminder_minutes: Option<i32>,
+}
+
+impl RuleActions {
+    fn is_empty(&self) -> bool {
+        self.add_tags.is_empty()
+            && self.set_project_id.is_none()
+            && self.set_priority.is_none()
+            && self.set_reminder_minutes.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub condition: RuleCondition,
+    pub actions: RuleActions,
+    pub order_index: i32,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleInput {
+    pub name: String,
+    pub enabled: Option<bool>,
+    pub condition: RuleCondition,
+    #[serde(default)]
+    pub actions: RuleActions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleApplication {
+    pub rule_id: String,
+    pub rule_name: String,
+    /// What the rule changed, e.g. "Added tag #finance"
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplyOutcome {
+    pub applications: Vec<RuleApplication>,
+    /// The caller needs to reschedule the task's reminder
+    pub reminder_changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTestResult {
+    pub matches: bool,
+    /// What the rule would do to the task, skipping actions that wouldn't change anything
+    pub changes: Vec<String>,
+}
+
+// The task fields conditions look at
+struct TaskSnapshot {
+    title: String,
+    description: Option<String>,
+    project_id: Option<String>,
+    priority: String,
+    reminder_minutes_before: Option<i32>,
+}
+
+fn load_snapshot(conn: &rusqlite::Connection, task_id: &str) -> Result<TaskSnapshot, String> {
+    conn.query_row(
+        "SELECT title, description, project_id, priority, reminder_minutes_before FROM tasks WHERE id = ?1",
+        params![task_id],
+        |row| {
+            Ok(TaskSnapshot {
+                title: row.get(0)?,
+                description: row.get(1)?,
+                project_id: row.get(2)?,
+                priority: row.get(3)?,
+                reminder_minutes_before: row.get(4)?,
+            })
+        },
+    ).map_err(|e| format!("Task not found: {}", e))
+}
+
+fn project_name(conn: &rusqlite::Connection, project_id: &str) -> Option<String> {
+    conn.query_row("SELECT name FROM projects WHERE id = ?1", params![project_id], |row| row.get(0)).ok()
+}
+
+fn normalize_tag(name: &str) -> String {
+    name.trim().trim_start_matches('#').trim().to_lowercase()
+}
+
+pub fn validate_rule(conn: &rusqlite::Connection, input: &RuleInput) -> Result<(), String> {
+    if input.name.trim().is_empty() {
+        return Err("Rule name cannot be empty".to_string());
+    }
+
+    let condition = &input.condition;
+    let expected_operator = match condition.field.as_str() {
+        "title" | "description" => "contains",
+        "project" | "priority" => "equals",
+        other => return Err(format!("Unsupported rule field: {} (expected title, description, project or priority)", other)),
+    };
+    if condition.operator != expected_operator {
+        return Err(format!("The {} field only supports the \"{}\" operator", condition.field, expected_operator));
+    }
+    if condition.value.trim().is_empty() {
+        return Err("Rule condition value cannot be empty".to_string());
+    }
+    if condition.field == "priority" && !PRIORITIES.contains(&condition.value.as_str()) {
+        return Err(format!("Invalid priority: {}", condition.value));
+    }
+    if condition.field == "project" && project_name(conn, &condition.value).is_none() {
+        return Err(format!("Project not found: {}", condition.value));
+    }
+
+    let actions = &input.actions;
+    if actions.is_empty() {
+        return Err("A rule needs at least one action".to_string());
+    }
+    if actions.add_tags.iter().any(|tag| normalize_tag(tag).is_empty()) {
+        return Err("Tag names cannot be empty".to_string());
+    }
+    if let Some(project_id) = &actions.set_project_id {
+        if project_name(conn, project_id).is_none() {
+            return Err(format!("Project not found: {}", project_id));
+        }
+    }
+    if let Some(priority) = &actions.set_priority {
+        if !PRIORITIES.contains(&priority.as_str()) {
+            return Err(format!("Invalid priority: {}", priority));
+        }
+    }
+    if actions.set_reminder_minutes.is_some_and(|minutes| minutes < 0) {
+        return Err("Reminder minutes cannot be negative".to_string());
+    }
+
+    Ok(())
+}
+
+fn rule_from_row(row: &rusqlite::Row) -> rusqlite::Result<Rule> {
+    let actions: String = row.get(6)?;
+    Ok(Rule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        enabled: row.get::<_, i32>(2)? != 0,
+        condition: RuleCondition {
+            field: row.get(3)?,
+            operator: row.get(4)?,
+            value: row.get(5)?,
+        },
+        // Unknown keys from a newer version are ignored rather than breaking the rule list
+        actions: serde_json::from_str(&actions).unwrap_or_default(),
+        order_index: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+const RULE_COLUMNS: &str =
+    "id, name, enabled, condition_field, condition_operator, condition_value, actions, order_index, created_at, updated_at";
+
+pub fn get_rules(conn: &rusqlite::Connection, enabled_only: bool) -> Result<Vec<Rule>, String> {
+    let query = format!(
+        "SELECT {} FROM rules {} ORDER BY order_index ASC, created_at ASC",
+        RULE_COLUMNS,
+        if enabled_only { "WHERE enabled = 1" } else { "" }
+    );
+    let mut stmt = conn.prepare(&query).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map([], rule_from_row).map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut rules = Vec::new();
+    for row in rows {
+        rules.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(rules)
+}
+
+pub fn get_rule(conn: &rusqlite::Connection, id: &str) -> Result<Rule, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM rules WHERE id = ?1", RULE_COLUMNS),
+        params![id],
+        rule_from_row,
+    ).map_err(|e| format!("Rule not found: {}", e))
+}
+
+pub fn create_rule(conn: &rusqlite::Connection, input: RuleInput) -> Result<Rule, String> {
+    validate_rule(conn, &input)?;
+    let actions = serde_json::to_string(&input.actions).map_err(|e| format!("Failed to serialize rule actions: {}", e))?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+
+    conn.execute(
+        "INSERT INTO rules (id, name, enabled, condition_field, condition_operator, condition_value, actions, order_index, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, (SELECT COALESCE(MAX(order_index), -1) + 1 FROM rules), ?8, ?8)",
+        params![
+            id,
+            input.name.trim(),
+            input.enabled.unwrap_or(true) as i32,
+            input.condition.field,
+            input.condition.operator,
+            input.condition.value.trim(),
+            actions,
+            now,
+        ],
+    ).map_err(|e| format!("Failed to create rule: {}", e))?;
+
+    get_rule(conn, &id)
+}
+
+pub fn update_rule(conn: &rusqlite::Connection, id: &str, input: RuleInput) -> Result<Rule, String> {
+    let existing = get_rule(conn, id)?;
+    validate_rule(conn, &input)?;
+    let actions = serde_json::to_string(&input.actions).map_err(|e| format!("Failed to serialize rule actions: {}", e))?;
+
+    conn.execute(
+        "UPDATE rules SET name = ?1, enabled = ?2, condition_field = ?3, condition_operator = ?4, condition_value = ?5, actions = ?6, updated_at = ?7
+         WHERE id = ?8",
+        params![
+            input.name.trim(),
+            input.enabled.unwrap_or(existing.enabled) as i32,
+            input.condition.field,
+            input.condition.operator,
+            input.condition.value.trim(),
+            actions,
+            now(),
+            id,
+        ],
+    ).map_err(|e| format!("Failed to update rule: {}", e))?;
+
+    get_rule(conn, id)
+}
+
+pub fn delete_rule(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM rules WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete rule: {}", e))?;
+    Ok(())
+}
+
+fn condition_matches(condition: &RuleCondition, task: &TaskSnapshot) -> bool {
+    let value = condition.value.trim();
+    match condition.field.as_str() {
+        "title" => task.title.to_lowercase().contains(&value.to_lowercase()),
+        "description" => task
+            .description
+            .as_deref()
+            .is_some_and(|description| description.to_lowercase().contains(&value.to_lowercase())),
+        "project" => task.project_id.as_deref() == Some(value),
+        "priority" => task.priority == value,
+        _ => false,
+    }
+}
+
+fn task_has_tag(conn: &rusqlite::Connection, task_id: &str, tag: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM task_tags tt INNER JOIN tags t ON t.id = tt.tag_id WHERE tt.task_id = ?1 AND t.name = ?2)",
+        params![task_id, tag],
+        |row| row.get(0),
+    ).map_err(|e| format!("Query error: {}", e))
+}
+
+// Changes the actions would make to the task as it is now. Deleted projects are skipped.
+fn planned_changes(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    actions: &RuleActions,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let task = load_snapshot(conn, task_id)?;
+    let mut tags = Vec::new();
+    let mut changes = Vec::new();
+
+    for tag in &actions.add_tags {
+        let tag = normalize_tag(tag);
+        if !tag.is_empty() && !tags.contains(&tag) && !task_has_tag(conn, task_id, &tag)? {
+            changes.push(format!("Added tag #{}", tag));
+            tags.push(tag);
+        }
+    }
+    if let Some(project_id) = actions.set_project_id.as_deref().filter(|id| task.project_id.as_deref() != Some(*id)) {
+        if let Some(name) = project_name(conn, project_id) {
+            changes.push(format!("Moved to project {}", name));
+        }
+    }
+    if let Some(priority) = actions.set_priority.as_deref().filter(|priority| *priority != task.priority) {
+        changes.push(format!("Set priority to {}", priority));
+    }
+    if let Some(minutes) = actions.set_reminder_minutes.filter(|minutes| task.reminder_minutes_before != Some(*minutes)) {
+        changes.push(format!("Set reminder to {} minutes before due", minutes));
+    }
+
+    Ok((tags, changes))
+}
+
+/// Whether the rule matches the task and what it would change, without changing anything
+pub fn test_rule(conn: &rusqlite::Connection, input: &RuleInput, task_id: &str) -> Result<RuleTestResult, String> {
+    validate_rule(conn, input)?;
+    let task = load_snapshot(conn, task_id)?;
+    if !condition_matches(&input.condition, &task) {
+        return Ok(RuleTestResult { matches: false, changes: Vec::new() });
+    }
+    let (_, changes) = planned_changes(conn, task_id, &input.actions)?;
+    Ok(RuleTestResult { matches: true, changes })
+}
+
+/// Apply every matching rule to the task, in rule order. Conditions are checked against the task
+/// as it was before any rule ran, so rules don't trigger each other; when two rules set the same
+/// field the later one wins. Each rule that changed something is recorded in the activity log.
+pub fn apply_rules(
+    conn: &rusqlite::Connection,
+    task_id: &str,
+    rules: &[Rule],
+    source: &str,
+) -> Result<ApplyOutcome, String> {
+    let task = load_snapshot(conn, task_id)?;
+    let mut outcome = ApplyOutcome::default();
+
+    for rule in rules.iter().filter(|rule| rule.enabled && condition_matches(&rule.condition, &task)) {
+        let (tags, changes) = planned_changes(conn, task_id, &rule.actions)?;
+        if changes.is_empty() {
+            continue;
+        }
+        let current = load_snapshot(conn, task_id)?;
+
+        for tag in &tags {
+            crate::commands::attach_tag_by_name(conn, task_id, tag)?;
+        }
+        if let Some(project_id) = rule.actions.set_project_id.as_deref() {
+            if current.project_id.as_deref() != Some(project_id) && project_name(conn, project_id).is_some() {
+                conn.execute(
+                    "UPDATE tasks SET project_id = ?1 WHERE id = ?2",
+                    params![project_id, task_id],
+                ).map_err(|e| format!("Failed to apply rule: {}", e))?;
+                crate::commands::log_activity(conn, task_id, "project_changed", current.project_id.as_deref(), Some(project_id), source)?;
+            }
+        }
+        if let Some(priority) = rule.actions.set_priority.as_deref().filter(|priority| *priority != current.priority) {
+            conn.execute(
+                "UPDATE tasks SET priority = ?1 WHERE id = ?2",
+                params![priority, task_id],
+            ).map_err(|e| format!("Failed to apply rule: {}", e))?;
+            crate::commands::log_activity(conn, task_id, "priority_changed", Some(&current.priority), Some(priority), source)?;
+        }
+        if let Some(minutes) = rule.actions.set_reminder_minutes.filter(|minutes| current.reminder_minutes_before != Some(*minutes)) {
+            conn.execute(
+                "UPDATE tasks SET reminder_minutes_before = ?1 WHERE id = ?2",
+                params![minutes, task_id],
+            ).map_err(|e| format!("Failed to apply rule: {}", e))?;
+            outcome.reminder_changed = true;
+        }
+
+        conn.execute(
+            &format!("UPDATE tasks SET {} WHERE id = ?", crate::commands::BUMP_UPDATED_AT),
+            params![now(), task_id],
+        ).map_err(|e| format!("Failed to apply rule: {}", e))?;
+        crate::commands::log_activity(conn, task_id, "rule_applied", None, Some(&rule.name), source)?;
+        outcome.applications.push(RuleApplication {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            changes,
+        });
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT,
+                project_id TEXT,
+                priority TEXT NOT NULL DEFAULT 'medium',
+                reminder_minutes_before INTEGER,
+                updated_at INTEGER NOT NULL DEFAULT 0
+            );
+            INSERT INTO projects (id, name) VALUES ('billing', 'Billing');
+            INSERT INTO tasks (id, title) VALUES ('t1', 'Send Invoice to ACME'), ('t2', 'Water plants');",
+        )
+        .unwrap();
+        conn.execute_batch(include_str!("../../migrations/0010_add_tags_and_relationships.sql")).unwrap();
+        conn.execute_batch(include_str!("../../migrations/0018_add_activity_log.sql")).unwrap();
+        conn.execute_batch(include_str!("../../migrations/0027_add_rules.sql")).unwrap();
+        conn
+    }
+
+    fn invoice_rule() -> RuleInput {
+        RuleInput {
+            name: "Invoices".to_string(),
+            enabled: None,
+            condition: RuleCondition {
+                field: "title".to_string(),
+                operator: "contains".to_string(),
+                value: "invoice".to_string(),
+            },
+            actions: RuleActions {
+                add_tags: vec!["#Finance".to_string()],
+                set_project_id: Some("billing".to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_validates_rules() {
+        let conn = setup_test_conn();
+        let mut rule = invoice_rule();
+        rule.condition.operator = "equals".to_string();
+        assert!(validate_rule(&conn, &rule).is_err());
+
+        let mut rule = invoice_rule();
+        rule.actions = RuleActions::default();
+        assert!(validate_rule(&conn, &rule).is_err());
+
+        let mut rule = invoice_rule();
+        rule.actions.set_project_id = Some("missing".to_string());
+        assert!(validate_rule(&conn, &rule).unwrap_err().starts_with("Project not found"));
+
+        assert!(validate_rule(&conn, &invoice_rule()).is_ok());
+    }
+
+    #[test]
+    fn test_applies_matching_rules_once() {
+        let conn = setup_test_conn();
+        let rule = create_rule(&conn, invoice_rule()).unwrap();
+
+        let preview = test_rule(&conn, &invoice_rule(), "t1").unwrap();
+        assert!(preview.matches);
+        assert_eq!(preview.changes, vec!["Added tag #finance", "Moved to project Billing"]);
+        assert!(!test_rule(&conn, &invoice_rule(), "t2").unwrap().matches);
+
+        let rules = get_rules(&conn, true).unwrap();
+        let outcome = apply_rules(&conn, "t1", &rules, "rule").unwrap();
+        assert_eq!(outcome.applications.len(), 1);
+        assert_eq!(outcome.applications[0].rule_id, rule.id);
+        assert!(!outcome.reminder_changed);
+
+        let project: Option<String> = conn.query_row("SELECT project_id FROM tasks WHERE id = 't1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(project.as_deref(), Some("billing"));
+        assert!(task_has_tag(&conn, "t1", "finance").unwrap());
+        let logged: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_log WHERE task_id = 't1' AND action = 'rule_applied'",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(logged, 1);
+
+        // Nothing left to change, so a second run is a no-op
+        assert!(apply_rules(&conn, "t1", &rules, "rule").unwrap().applications.is_empty());
+        assert!(apply_rules(&conn, "t2", &rules, "rule").unwrap().applications.is_empty());
+    }
+}
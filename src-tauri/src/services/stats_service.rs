@@ -1,5 +1,8 @@
+use crate::services::week_service;
+use chrono::{Duration, Local, NaiveDate, TimeZone};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -8,6 +11,28 @@ pub struct CompletionStats {
     pub count: i64,
 }
 
+/// Activity in one calendar week (see week_service for where weeks start)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeeklySummary {
+    pub week_start: i64,
+    pub tasks_created: i64,
+    pub tasks_completed: i64,
+    /// Tasks due that week whose due time passed before they were completed (or still hasn't)
+    pub tasks_overdue: i64,
+    pub xp_earned: i64,
+    pub pomodoro_sessions: i64,
+}
+
+/// One day of the completion heatmap
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeatmapEntry {
+    pub date: String, // YYYY-MM-DD format
+    pub count: i64,
+    /// 1-5: at or below the first quartile of the year's busy days, second, third, above the
+    /// third, and the busiest day itself
+    pub intensity: u8,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PriorityDistribution {
     pub priority: String,
@@ -35,6 +60,91 @@ pub struct MostProductiveDay {
     pub count: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectForecast {
+    pub project_id: String,
+    pub remaining_tasks: i64,
+    /// Completions in each of the last 4 weeks, oldest first
+    pub weekly_completions: Vec<i64>,
+    pub average_per_week: Option<f64>,
+    /// Finish date at the best recent week's pace
+    pub optimistic_date: Option<i64>,
+    /// Finish date at the average pace
+    pub expected_date: Option<i64>,
+    /// Finish date at the worst recent week's pace; None when a recent week had no completions
+    pub pessimistic_date: Option<i64>,
+    /// Why no forecast could be made
+    pub reason: Option<String>,
+}
+
+/// How far a task's tracked time ended up from its estimate
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeEstimationRow {
+    pub task_id: String,
+    pub title: String,
+    pub estimated_minutes: i32,
+    pub actual_minutes: i32,
+    /// actual - estimated; positive means the task ran over
+    pub deviation_minutes: i32,
+    /// Deviation relative to the estimate, in percent
+    pub deviation_percent: f64,
+    pub completed_at: Option<i64>,
+}
+
+/// Settings blob (JSON) tuning the productivity score; missing fields use the defaults
+pub const PRODUCTIVITY_SCORE_CONFIG_KEY: &str = "productivity_score_config";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductivityScoreConfig {
+    pub on_time_weight: f64,
+    pub throughput_weight: f64,
+    pub focus_weight: f64,
+    pub streak_weight: f64,
+    /// Focus minutes per day that count as a full day
+    pub daily_focus_minutes: f64,
+}
+
+impl Default for ProductivityScoreConfig {
+    fn default() -> Self {
+        ProductivityScoreConfig {
+            on_time_weight: 0.35,
+            throughput_weight: 0.25,
+            focus_weight: 0.25,
+            streak_weight: 0.15,
+            daily_focus_minutes: 120.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoreComponent {
+    pub name: String, // 'on_time', 'throughput', 'focus', 'streak'
+    /// Normalized 0-1 value; None when the window has nothing to measure it on
+    pub value: Option<f64>,
+    pub weight: f64,
+    /// Points this component adds to the 0-100 score
+    pub points: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductivityScore {
+    pub window_days: i64,
+    pub score: f64, // 0-100
+    pub components: Vec<ScoreComponent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductivityScorePoint {
+    pub date: String, // YYYY-MM-DD format, last day of the window
+    pub score: f64,
+}
+
+const FORECAST_WEEKS: i64 = 4;
+// Fewer completions than this over FORECAST_WEEKS is too little to extrapolate from
+const MIN_FORECAST_COMPLETIONS: i64 = 3;
+const WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+
 // Helper function to get current timestamp
 fn now() -> i64 {
     SystemTime::now()
@@ -43,10 +153,14 @@ fn now() -> i64 {
         .as_secs() as i64
 }
 
+// The stats below leave archived tasks out, matching the task lists, unless `include_deleted`
+// asks for them too
+
 /// Get tasks completed per day for the specified number of days
 pub fn get_completion_stats(
     conn: &rusqlite::Connection,
     days: i32,
+    include_deleted: bool,
 ) -> Result<Vec<CompletionStats>, String> {
     let now = now();
     let start_timestamp = now - (days as i64 * 24 * 60 * 60);
@@ -59,13 +173,14 @@ pub fn get_completion_stats(
             FROM tasks
             WHERE completed_at IS NOT NULL 
                 AND completed_at >= ?1
+                AND (archived = 0 OR ?2)
             GROUP BY completion_date
             ORDER BY completion_date",
         )
         .map_err(|e| format!("Query error: {}", e))?;
 
     let rows = stmt
-        .query_map(params![start_timestamp], |row| {
+        .query_map(params![start_timestamp, include_deleted], |row| {
             Ok(CompletionStats {
                 date: row.get(0)?,
                 count: row.get(1)?,
@@ -81,14 +196,151 @@ pub fn get_completion_stats(
     Ok(stats)
 }
 
+// Nearest-rank percentile of sorted, non-empty `counts`
+fn percentile(counts: &[i64], percent: usize) -> i64 {
+    let rank = (counts.len() * percent).div_ceil(100).max(1);
+    counts[rank - 1]
+}
+
+fn heatmap_intensity(count: i64, quartiles: [i64; 3], max: i64) -> u8 {
+    if count <= quartiles[0] {
+        1
+    } else if count <= quartiles[1] {
+        2
+    } else if count <= quartiles[2] {
+        3
+    } else if count < max {
+        4
+    } else {
+        5
+    }
+}
+
+/// Completions per local day of `year`, only days with at least one
+pub fn get_completion_heatmap(conn: &rusqlite::Connection, year: i32, include_deleted: bool) -> Result<Vec<HeatmapEntry>, String> {
+    let start = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| format!("Invalid year: {}", year))?;
+    let end = NaiveDate::from_ymd_opt(year + 1, 1, 1).ok_or_else(|| format!("Invalid year: {}", year))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(completed_at, 'unixepoch', 'localtime') as completion_date, COUNT(*)
+            FROM tasks
+            WHERE completed_at >= ?1 AND completed_at < ?2 AND (archived = 0 OR ?3)
+            GROUP BY completion_date
+            ORDER BY completion_date",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let days = stmt
+        .query_map(params![week_service::local_midnight(start), week_service::local_midnight(end), include_deleted], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+
+    let mut counts: Vec<i64> = days.iter().map(|(_, count)| *count).collect();
+    counts.sort_unstable();
+    let Some(&max) = counts.last() else {
+        return Ok(Vec::new());
+    };
+    let quartiles = [percentile(&counts, 25), percentile(&counts, 50), percentile(&counts, 75)];
+
+    Ok(days
+        .into_iter()
+        .map(|(date, count)| HeatmapEntry {
+            date,
+            count,
+            intensity: heatmap_intensity(count, quartiles, max),
+        })
+        .collect())
+}
+
+/// The last `weeks_back` calendar weeks up to and including the one containing `now`, oldest first.
+/// Every count comes from one query: the week bounds go in as JSON and each table's rows are
+/// bucketed with conditional aggregation.
+pub fn get_weekly_summary(conn: &rusqlite::Connection, weeks_back: i32, now: i64, include_deleted: bool) -> Result<Vec<WeeklySummary>, String> {
+    if !(1..=520).contains(&weeks_back) {
+        return Err(format!("weeks_back must be between 1 and 520, got {}", weeks_back));
+    }
+    let current = week_service::week_bounds(now, week_service::load_week_starts_on(conn));
+    let current_start = Local
+        .timestamp_opt(current.week_start, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+        .ok_or_else(|| "Invalid week start".to_string())?;
+    let weeks: Vec<[i64; 2]> = (0..weeks_back as i64)
+        .rev()
+        .map(|ago| {
+            let start = current_start - Duration::weeks(ago);
+            [week_service::local_midnight(start), week_service::local_midnight(start + Duration::weeks(1))]
+        })
+        .collect();
+    let range_start = weeks[0][0];
+    let range_end = current.week_end;
+    let weeks_json = serde_json::to_string(&weeks).map_err(|e| format!("Failed to serialize weeks: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "WITH weeks AS (
+                SELECT json_extract(value, '$[0]') AS week_start, json_extract(value, '$[1]') AS week_end
+                FROM json_each(?1)
+            ),
+            events AS (
+                SELECT 'created' AS kind, created_at AS at, 0 AS amount FROM tasks
+                WHERE created_at >= ?2 AND created_at < ?3 AND (archived = 0 OR ?5)
+                UNION ALL
+                SELECT 'completed', completed_at, 0 FROM tasks
+                WHERE completed_at >= ?2 AND completed_at < ?3 AND (archived = 0 OR ?5)
+                UNION ALL
+                SELECT 'overdue', due_at, 0 FROM tasks
+                WHERE due_at >= ?2 AND due_at < ?3 AND due_at < ?4 AND (archived = 0 OR ?5)
+                  AND (completed_at IS NULL OR completed_at > due_at)
+                UNION ALL
+                SELECT 'xp', created_at, xp_amount FROM xp_history
+                WHERE created_at >= ?2 AND created_at < ?3
+                UNION ALL
+                SELECT 'pomodoro', completed_at, 0 FROM pomodoro_sessions
+                WHERE mode = 'pomodoro' AND completed_at >= ?2 AND completed_at < ?3
+            )
+            SELECT w.week_start,
+                COALESCE(SUM(e.kind = 'created'), 0),
+                COALESCE(SUM(e.kind = 'completed'), 0),
+                COALESCE(SUM(e.kind = 'overdue'), 0),
+                COALESCE(SUM(CASE WHEN e.kind = 'xp' THEN e.amount ELSE 0 END), 0),
+                COALESCE(SUM(e.kind = 'pomodoro'), 0)
+            FROM weeks w
+            LEFT JOIN events e ON e.at >= w.week_start AND e.at < w.week_end
+            GROUP BY w.week_start
+            ORDER BY w.week_start",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![weeks_json, range_start, range_end, now, include_deleted], |row| {
+            Ok(WeeklySummary {
+                week_start: row.get(0)?,
+                tasks_created: row.get(1)?,
+                tasks_completed: row.get(2)?,
+                tasks_overdue: row.get(3)?,
+                xp_earned: row.get(4)?,
+                pomodoro_sessions: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))
+}
+
 /// Get count of tasks grouped by priority level
 pub fn get_priority_distribution(
     conn: &rusqlite::Connection,
+    include_deleted: bool,
 ) -> Result<Vec<PriorityDistribution>, String> {
     let mut stmt = conn
         .prepare(
             "SELECT priority, COUNT(*) as count
             FROM tasks
+            WHERE (archived = 0 OR ?1)
             GROUP BY priority
             ORDER BY 
                 CASE priority
@@ -101,7 +353,7 @@ pub fn get_priority_distribution(
         .map_err(|e| format!("Query error: {}", e))?;
 
     let rows = stmt
-        .query_map([], |row| {
+        .query_map(params![include_deleted], |row| {
             Ok(PriorityDistribution {
                 priority: row.get(0)?,
                 count: row.get(1)?,
@@ -118,7 +370,7 @@ pub fn get_priority_distribution(
 }
 
 /// Get task counts and completion rates per project
-pub fn get_project_stats(conn: &rusqlite::Connection) -> Result<Vec<ProjectStats>, String> {
+pub fn get_project_stats(conn: &rusqlite::Connection, include_deleted: bool) -> Result<Vec<ProjectStats>, String> {
     let mut stmt = conn
         .prepare(
             "SELECT 
@@ -128,13 +380,14 @@ pub fn get_project_stats(conn: &rusqlite::Connection) -> Result<Vec<ProjectStats
                 SUM(CASE WHEN t.completed_at IS NOT NULL THEN 1 ELSE 0 END) as completed_tasks
             FROM tasks t
             LEFT JOIN projects p ON t.project_id = p.id
+            WHERE (t.archived = 0 OR ?1)
             GROUP BY t.project_id, p.name
             ORDER BY total_tasks DESC",
         )
         .map_err(|e| format!("Query error: {}", e))?;
 
     let rows = stmt
-        .query_map([], |row| {
+        .query_map(params![include_deleted], |row| {
             let total: i64 = row.get(2)?;
             let completed: i64 = row.get(3)?;
             let completion_rate = if total > 0 {
@@ -166,6 +419,7 @@ pub fn get_productivity_trend(
     conn: &rusqlite::Connection,
     start_date: i64,
     end_date: i64,
+    include_deleted: bool,
 ) -> Result<Vec<ProductivityTrend>, String> {
     let mut stmt = conn
         .prepare(
@@ -176,21 +430,22 @@ pub fn get_productivity_trend(
             WHERE completed_at IS NOT NULL 
                 AND completed_at >= ?1 
                 AND completed_at <= ?2
+                AND (archived = 0 OR ?3)
             GROUP BY completion_date
             ORDER BY completion_date",
         )
         .map_err(|e| format!("Query error: {}", e))?;
 
     let rows = stmt
-        .query_map(params![start_date, end_date], |row| {
+        .query_map(params![start_date, end_date, include_deleted], |row| {
             let date: String = row.get(0)?;
             let completed_count: i64 = row.get(1)?;
 
             // Get total tasks created on or before this date
             let total_tasks: i64 = conn
                 .query_row(
-                    "SELECT COUNT(*) FROM tasks WHERE created_at <= (SELECT MAX(completed_at) FROM tasks WHERE date(completed_at, 'unixepoch', 'localtime') = ?1)",
-                    params![date.clone()],
+                    "SELECT COUNT(*) FROM tasks WHERE (archived = 0 OR ?2) AND created_at <= (SELECT MAX(completed_at) FROM tasks WHERE (archived = 0 OR ?2) AND date(completed_at, 'unixepoch', 'localtime') = ?1)",
+                    params![date.clone(), include_deleted],
                     |row| row.get(0),
                 )
                 .unwrap_or(0);
@@ -219,6 +474,7 @@ pub fn get_productivity_trend(
 /// Get the day of week with most task completions
 pub fn get_most_productive_day(
     conn: &rusqlite::Connection,
+    include_deleted: bool,
 ) -> Result<Option<MostProductiveDay>, String> {
     let mut stmt = conn
         .prepare(
@@ -235,14 +491,14 @@ pub fn get_most_productive_day(
                 END as day_of_week,
                 COUNT(*) as count
             FROM tasks
-            WHERE completed_at IS NOT NULL
+            WHERE completed_at IS NOT NULL AND (archived = 0 OR ?1)
             GROUP BY day_of_week
             ORDER BY count DESC
             LIMIT 1",
         )
         .map_err(|e| format!("Query error: {}", e))?;
 
-    let result = match stmt.query_row([], |row| {
+    let result = match stmt.query_row(params![include_deleted], |row| {
         Ok(MostProductiveDay {
             day_of_week: row.get(0)?,
             count: row.get(1)?,
@@ -257,12 +513,12 @@ pub fn get_most_productive_day(
 }
 
 /// Get average time from task creation to completion (in days)
-pub fn get_average_completion_time(conn: &rusqlite::Connection) -> Result<f64, String> {
+pub fn get_average_completion_time(conn: &rusqlite::Connection, include_deleted: bool) -> Result<f64, String> {
     let result = match conn.query_row(
         "SELECT AVG(CAST(completed_at - created_at AS REAL) / 86400.0) as avg_days
         FROM tasks
-        WHERE completed_at IS NOT NULL",
-        [],
+        WHERE completed_at IS NOT NULL AND (archived = 0 OR ?1)",
+        params![include_deleted],
         |row| row.get::<_, Option<f64>>(0),
     ) {
         Ok(Some(val)) => val,
@@ -274,3 +530,593 @@ pub fn get_average_completion_time(conn: &rusqlite::Connection) -> Result<f64, S
     Ok(result)
 }
 
+/// Estimate vs tracked time for every task that has both, most recently finished first
+pub fn get_time_estimation_accuracy(conn: &rusqlite::Connection) -> Result<Vec<TimeEstimationRow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, estimated_minutes, actual_minutes, completed_at
+            FROM tasks
+            WHERE estimated_minutes > 0 AND actual_minutes IS NOT NULL AND archived = 0
+            ORDER BY completed_at IS NULL, completed_at DESC, updated_at DESC, id",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let estimated_minutes: i32 = row.get(2)?;
+            let actual_minutes: i32 = row.get(3)?;
+            let deviation_minutes = actual_minutes - estimated_minutes;
+            Ok(TimeEstimationRow {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                estimated_minutes,
+                actual_minutes,
+                deviation_minutes,
+                deviation_percent: deviation_minutes as f64 / estimated_minutes as f64 * 100.0,
+                completed_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))
+}
+
+// When `remaining` tasks will be done at `per_week` completions per week
+fn finish_date(now: i64, remaining: i64, per_week: f64) -> Option<i64> {
+    if per_week <= 0.0 {
+        return None;
+    }
+    let days = (remaining as f64 / per_week * 7.0).ceil() as i64;
+    Some(now + days * 24 * 60 * 60)
+}
+
+/// Project a finish date from the project's completions over the last 4 weeks
+pub fn get_project_forecast(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    now: i64,
+) -> Result<ProjectForecast, String> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)",
+            params![project_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    if !exists {
+        return Err(format!("Project not found: {}", project_id));
+    }
+
+    let remaining_tasks: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM tasks WHERE project_id = ?1 AND completed_at IS NULL AND archived = 0",
+            params![project_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    // Rolling weeks ending now, so a forecast made mid-week isn't skewed by a partial week
+    let mut weekly_completions = Vec::new();
+    for week in (0..FORECAST_WEEKS).rev() {
+        let end = now - week * WEEK_SECONDS;
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM tasks WHERE project_id = ?1 AND completed_at >= ?2 AND completed_at < ?3 AND archived = 0",
+                params![project_id, end - WEEK_SECONDS, end],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Query error: {}", e))?;
+        weekly_completions.push(count);
+    }
+
+    let total: i64 = weekly_completions.iter().sum();
+    let mut forecast = ProjectForecast {
+        project_id: project_id.to_string(),
+        remaining_tasks,
+        weekly_completions,
+        average_per_week: None,
+        optimistic_date: None,
+        expected_date: None,
+        pessimistic_date: None,
+        reason: None,
+    };
+
+    if remaining_tasks == 0 {
+        forecast.reason = Some("No open tasks in this project".to_string());
+        return Ok(forecast);
+    }
+    if total < MIN_FORECAST_COMPLETIONS {
+        forecast.reason = Some(format!(
+            "Not enough history: {} task{} completed in the last {} weeks, at least {} needed",
+            total,
+            if total == 1 { "" } else { "s" },
+            FORECAST_WEEKS,
+            MIN_FORECAST_COMPLETIONS
+        ));
+        return Ok(forecast);
+    }
+
+    let average = total as f64 / FORECAST_WEEKS as f64;
+    let best = forecast.weekly_completions.iter().copied().max().unwrap_or(0);
+    let worst = forecast.weekly_completions.iter().copied().min().unwrap_or(0);
+    forecast.average_per_week = Some(average);
+    forecast.optimistic_date = finish_date(now, remaining_tasks, best as f64);
+    forecast.expected_date = finish_date(now, remaining_tasks, average);
+    forecast.pessimistic_date = finish_date(now, remaining_tasks, worst as f64);
+
+    Ok(forecast)
+}
+
+pub fn load_productivity_score_config(conn: &rusqlite::Connection) -> ProductivityScoreConfig {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![PRODUCTIVITY_SCORE_CONFIG_KEY],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| serde_json::from_str(&value).ok())
+    .unwrap_or_default()
+}
+
+#[derive(Default, Clone, Copy)]
+struct DayTotals {
+    completed: i64,
+    completed_with_due: i64,
+    completed_on_time: i64,
+    created: i64,
+    focus_seconds: i64,
+}
+
+// Per-day totals for [first, last] in three grouped queries
+fn load_day_totals(
+    conn: &rusqlite::Connection,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<HashMap<String, DayTotals>, String> {
+    let start = week_service::local_midnight(first);
+    let end = week_service::local_midnight(last + Duration::days(1));
+    let mut totals: HashMap<String, DayTotals> = HashMap::new();
+
+    // A task due at midnight is due that day, so finishing any time that day is on time
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(completed_at, 'unixepoch', 'localtime') AS day,
+                COUNT(*),
+                SUM(CASE WHEN due_at IS NOT NULL THEN 1 ELSE 0 END),
+                SUM(CASE WHEN due_at IS NOT NULL AND (completed_at <= due_at
+                    OR (time(due_at, 'unixepoch', 'localtime') = '00:00:00'
+                        AND date(completed_at, 'unixepoch', 'localtime') = date(due_at, 'unixepoch', 'localtime')))
+                    THEN 1 ELSE 0 END)
+            FROM tasks
+            WHERE completed_at >= ?1 AND completed_at < ?2 AND archived = 0
+            GROUP BY day",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        let (day, completed, with_due, on_time) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        let entry = totals.entry(day).or_default();
+        entry.completed = completed;
+        entry.completed_with_due = with_due;
+        entry.completed_on_time = on_time;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(created_at, 'unixepoch', 'localtime') AS day, COUNT(*)
+            FROM tasks
+            WHERE created_at >= ?1 AND created_at < ?2 AND archived = 0
+            GROUP BY day",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        let (day, created) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        totals.entry(day).or_default().created = created;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(completed_at, 'unixepoch', 'localtime') AS day, SUM(duration_seconds)
+            FROM pomodoro_sessions
+            WHERE mode = 'pomodoro' AND completed_at >= ?1 AND completed_at < ?2
+            GROUP BY day",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+    for row in rows {
+        let (day, seconds) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+        totals.entry(day).or_default().focus_seconds = seconds;
+    }
+
+    Ok(totals)
+}
+
+fn score_window(days: &[DayTotals], config: &ProductivityScoreConfig) -> ProductivityScore {
+    let window_days = days.len() as i64;
+    let sum = |f: fn(&DayTotals) -> i64| days.iter().map(f).sum::<i64>();
+    let completed = sum(|d| d.completed);
+    let with_due = sum(|d| d.completed_with_due);
+    let on_time = sum(|d| d.completed_on_time);
+    let created = sum(|d| d.created);
+    let focus_minutes = sum(|d| d.focus_seconds) as f64 / 60.0;
+    let active_days = days.iter().filter(|d| d.completed > 0).count() as f64;
+
+    let on_time_value = (with_due > 0).then(|| on_time as f64 / with_due as f64);
+    let throughput_value = match (completed, created) {
+        (0, 0) => None,
+        (_, 0) => Some(1.0),
+        _ => Some((completed as f64 / created as f64).min(1.0)),
+    };
+    let focus_capacity = config.daily_focus_minutes * window_days as f64;
+    let focus_value = (focus_capacity > 0.0).then(|| (focus_minutes / focus_capacity).min(1.0));
+    // Share of days in the window with at least one completion
+    let streak_value = (window_days > 0).then(|| active_days / window_days as f64);
+
+    let mut components = vec![
+        ScoreComponent { name: "on_time".to_string(), value: on_time_value, weight: config.on_time_weight, points: 0.0 },
+        ScoreComponent { name: "throughput".to_string(), value: throughput_value, weight: config.throughput_weight, points: 0.0 },
+        ScoreComponent { name: "focus".to_string(), value: focus_value, weight: config.focus_weight, points: 0.0 },
+        ScoreComponent { name: "streak".to_string(), value: streak_value, weight: config.streak_weight, points: 0.0 },
+    ];
+
+    // Components without data drop out and the remaining weights are scaled up to 100 points
+    let total_weight: f64 = components
+        .iter()
+        .filter(|c| c.value.is_some())
+        .map(|c| c.weight.max(0.0))
+        .sum();
+    if total_weight > 0.0 {
+        for component in &mut components {
+            if let Some(value) = component.value {
+                component.points = value * component.weight.max(0.0) / total_weight * 100.0;
+            }
+        }
+    }
+    let score = components.iter().map(|c| c.points).sum::<f64>().clamp(0.0, 100.0);
+
+    ProductivityScore { window_days, score, components }
+}
+
+/// 0-100 score over the `window_days` days ending today, with its per-component breakdown
+pub fn get_productivity_score(
+    conn: &rusqlite::Connection,
+    window_days: i64,
+    now: i64,
+) -> Result<ProductivityScore, String> {
+    let mut history = productivity_windows(conn, window_days, 1, now)?;
+    Ok(history.pop().map(|(_, score)| score).unwrap_or_else(|| score_window(&[], &load_productivity_score_config(conn))))
+}
+
+/// Score of the rolling `window_days` window ending on each of the last `days` days, oldest first
+pub fn get_productivity_score_history(
+    conn: &rusqlite::Connection,
+    window_days: i64,
+    days: i64,
+    now: i64,
+) -> Result<Vec<ProductivityScorePoint>, String> {
+    Ok(productivity_windows(conn, window_days, days, now)?
+        .into_iter()
+        .map(|(date, score)| ProductivityScorePoint { date, score: score.score })
+        .collect())
+}
+
+/// Longest window and history the productivity score covers, about ten years
+pub const MAX_PRODUCTIVITY_DAYS: i64 = 3650;
+
+fn productivity_windows(
+    conn: &rusqlite::Connection,
+    window_days: i64,
+    days: i64,
+    now: i64,
+) -> Result<Vec<(String, ProductivityScore)>, String> {
+    if window_days < 1 {
+        return Err("Window must be at least one day".to_string());
+    }
+    if days < 1 {
+        return Err("History must cover at least one day".to_string());
+    }
+    let (window_days, days) = (window_days.min(MAX_PRODUCTIVITY_DAYS), days.min(MAX_PRODUCTIVITY_DAYS));
+    let config = load_productivity_score_config(conn);
+    let today = Local
+        .timestamp_opt(now, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+        .ok_or_else(|| "Invalid timestamp".to_string())?;
+    let out_of_range = || "Date range is out of range".to_string();
+    let first = today
+        .checked_sub_signed(Duration::days(days - 1 + window_days - 1))
+        .ok_or_else(out_of_range)?;
+    let totals = load_day_totals(conn, first, today)?;
+
+    // Every date in between lies between `first` and `today`, so these additions can't overflow
+    let series: Vec<DayTotals> = (0..days + window_days - 1)
+        .map(|offset| {
+            let date = (first + Duration::days(offset)).format("%Y-%m-%d").to_string();
+            totals.get(&date).copied().unwrap_or_default()
+        })
+        .collect();
+
+    Ok(series
+        .windows(window_days as usize)
+        .enumerate()
+        .map(|(index, window)| {
+            let end = first + Duration::days(index as i64 + window_days - 1);
+            (end.format("%Y-%m-%d").to_string(), score_window(window, &config))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_project_forecast() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, project_id TEXT, completed_at INTEGER, archived INTEGER NOT NULL DEFAULT 0);
+            INSERT INTO projects (id) VALUES ('p1'), ('p2');",
+        )
+        .unwrap();
+        let now = 1_700_000_000;
+        // Weeks oldest to newest: 1, 0, 2, 3 completions; 6 tasks left
+        for (i, days_ago) in [25, 10, 9, 5, 3, 1].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO tasks (id, project_id, completed_at) VALUES (?1, 'p1', ?2)",
+                params![format!("done{}", i), now - days_ago * DAY],
+            )
+            .unwrap();
+        }
+        for i in 0..6 {
+            conn.execute("INSERT INTO tasks (id, project_id) VALUES (?1, 'p1')", params![format!("open{}", i)]).unwrap();
+        }
+
+        let forecast = get_project_forecast(&conn, "p1", now).unwrap();
+        assert_eq!(forecast.weekly_completions, vec![1, 0, 2, 3]);
+        assert_eq!(forecast.remaining_tasks, 6);
+        assert_eq!(forecast.average_per_week, Some(1.5));
+        assert_eq!(forecast.optimistic_date, Some(now + 14 * DAY));
+        assert_eq!(forecast.expected_date, Some(now + 28 * DAY));
+        // A week without completions gives no pessimistic bound
+        assert_eq!(forecast.pessimistic_date, None);
+        assert!(forecast.reason.is_none());
+
+        conn.execute("INSERT INTO tasks (id, project_id) VALUES ('solo', 'p2')", []).unwrap();
+        let forecast = get_project_forecast(&conn, "p2", now).unwrap();
+        assert!(forecast.expected_date.is_none());
+        assert!(forecast.reason.unwrap().starts_with("Not enough history"));
+        assert!(get_project_forecast(&conn, "missing", now).is_err());
+    }
+
+    #[test]
+    fn test_time_estimation_accuracy() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, updated_at INTEGER NOT NULL, completed_at INTEGER,
+                estimated_minutes INTEGER, actual_minutes INTEGER, archived INTEGER NOT NULL DEFAULT 0);
+            INSERT INTO tasks VALUES ('over', 'Write report', 10, 200, 60, 90, 0);
+            INSERT INTO tasks VALUES ('under', 'Call bank', 20, 300, 40, 30, 0);
+            INSERT INTO tasks VALUES ('open', 'Plan trip', 30, NULL, 20, 5, 0);
+            INSERT INTO tasks VALUES ('untracked', 'Inbox zero', 40, 400, 15, NULL, 0);
+            INSERT INTO tasks VALUES ('no-estimate', 'Gym', 50, 500, NULL, 45, 0);",
+        )
+        .unwrap();
+
+        let rows = get_time_estimation_accuracy(&conn).unwrap();
+        let ids: Vec<&str> = rows.iter().map(|row| row.task_id.as_str()).collect();
+        assert_eq!(ids, vec!["under", "over", "open"]);
+        assert_eq!((rows[0].deviation_minutes, rows[0].deviation_percent), (-10, -25.0));
+        assert_eq!((rows[1].deviation_minutes, rows[1].deviation_percent), (30, 50.0));
+        assert_eq!(rows[2].completed_at, None);
+    }
+
+    #[test]
+    fn test_completion_heatmap() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks (id TEXT PRIMARY KEY, completed_at INTEGER, archived INTEGER NOT NULL DEFAULT 0);").unwrap();
+        // Completions on eight days of 2024, plus New Year's Eve before and New Year's Day after
+        let days = [(2023, 12, 31, 4), (2024, 1, 1, 1), (2024, 1, 2, 1), (2024, 3, 5, 2), (2024, 3, 6, 2),
+            (2024, 6, 1, 3), (2024, 6, 2, 4), (2024, 9, 9, 5), (2024, 12, 31, 9), (2025, 1, 1, 1)];
+        let mut id = 0;
+        for (year, month, day, count) in days {
+            let at = Local.with_ymd_and_hms(year, month, day, 23, 30, 0).unwrap().timestamp();
+            for _ in 0..count {
+                id += 1;
+                conn.execute("INSERT INTO tasks (id, completed_at) VALUES (?1, ?2)", params![id.to_string(), at]).unwrap();
+            }
+        }
+        conn.execute("INSERT INTO tasks (id) VALUES ('open')", []).unwrap();
+
+        let heatmap = get_completion_heatmap(&conn, 2024, false).unwrap();
+        let summary: Vec<(&str, i64, u8)> = heatmap.iter().map(|e| (e.date.as_str(), e.count, e.intensity)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("2024-01-01", 1, 1),
+                ("2024-01-02", 1, 1),
+                ("2024-03-05", 2, 2),
+                ("2024-03-06", 2, 2),
+                ("2024-06-01", 3, 3),
+                ("2024-06-02", 4, 3),
+                ("2024-09-09", 5, 4),
+                ("2024-12-31", 9, 5),
+            ]
+        );
+        assert!(get_completion_heatmap(&conn, 2020, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_archived_tasks_only_count_when_included() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let now = now();
+        let day = 24 * 60 * 60;
+        conn.execute_batch(&format!(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, created_at INTEGER NOT NULL, completed_at INTEGER,
+                priority TEXT NOT NULL, project_id TEXT, archived INTEGER NOT NULL DEFAULT 0);
+            INSERT INTO projects VALUES ('p1', 'Home');
+            INSERT INTO tasks VALUES ('done', {two_days_ago}, {yesterday}, 'high', 'p1', 0);
+            INSERT INTO tasks VALUES ('open', {two_days_ago}, NULL, 'low', 'p1', 0);
+            INSERT INTO tasks VALUES ('archived-done', {five_days_ago}, {yesterday}, 'high', 'p1', 1);
+            INSERT INTO tasks VALUES ('archived-open', {now}, NULL, 'medium', NULL, 1);",
+            two_days_ago = now - 2 * day,
+            five_days_ago = now - 5 * day,
+            yesterday = now - day,
+        ))
+        .unwrap();
+
+        let completions = |include| -> Vec<i64> {
+            get_completion_stats(&conn, 7, include).unwrap().iter().map(|s| s.count).collect()
+        };
+        assert_eq!((completions(false), completions(true)), (vec![1], vec![2]));
+
+        let priorities = |include| -> Vec<(String, i64)> {
+            get_priority_distribution(&conn, include).unwrap().into_iter().map(|p| (p.priority, p.count)).collect()
+        };
+        assert_eq!(priorities(false), vec![("high".to_string(), 1), ("low".to_string(), 1)]);
+        assert_eq!(
+            priorities(true),
+            vec![("high".to_string(), 2), ("medium".to_string(), 1), ("low".to_string(), 1)]
+        );
+
+        let projects = get_project_stats(&conn, false).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!((projects[0].total_tasks, projects[0].completed_tasks), (2, 1));
+        let projects = get_project_stats(&conn, true).unwrap();
+        assert_eq!(projects.len(), 2);
+        assert_eq!((projects[0].total_tasks, projects[0].completed_tasks), (3, 2));
+
+        assert_eq!(get_most_productive_day(&conn, false).unwrap().unwrap().count, 1);
+        assert_eq!(get_most_productive_day(&conn, true).unwrap().unwrap().count, 2);
+        assert!((get_average_completion_time(&conn, false).unwrap() - 1.0).abs() < 1e-9);
+        assert!((get_average_completion_time(&conn, true).unwrap() - 2.5).abs() < 1e-9);
+
+        let trend = get_productivity_trend(&conn, now - 7 * day, now, false).unwrap();
+        assert!((trend[0].completion_rate - 50.0).abs() < 1e-9);
+        let trend = get_productivity_trend(&conn, now - 7 * day, now, true).unwrap();
+        assert!((trend[0].completion_rate - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weekly_summary() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, created_at INTEGER NOT NULL, completed_at INTEGER, due_at INTEGER, archived INTEGER NOT NULL DEFAULT 0);
+            CREATE TABLE xp_history (id TEXT PRIMARY KEY, xp_amount INTEGER NOT NULL, created_at INTEGER NOT NULL);
+            CREATE TABLE pomodoro_sessions (id TEXT PRIMARY KEY, mode TEXT, completed_at INTEGER);",
+        )
+        .unwrap();
+        // Weeks start on Monday by default: 2024-04-29, 2024-05-06 and 2024-05-13
+        let at = |month: u32, day: u32, hour: u32| Local.with_ymd_and_hms(2024, month, day, hour, 0, 0).unwrap().timestamp();
+        let now = at(5, 15, 12);
+        conn.execute_batch(&format!(
+            "INSERT INTO tasks VALUES ('old', {old}, NULL, NULL, 0);
+            INSERT INTO tasks VALUES ('late', {w1}, {w2}, {w1_due}, 0);
+            INSERT INTO tasks VALUES ('on-time', {w1}, {w1}, {w1_due}, 0);
+            INSERT INTO tasks VALUES ('open-past', {w2}, NULL, {w2}, 0);
+            INSERT INTO tasks VALUES ('open-future', {w3}, NULL, {later_today}, 0);
+            INSERT INTO tasks VALUES ('archived', {w3}, NULL, {w3}, 1);
+            INSERT INTO xp_history VALUES ('x1', 10, {w2}), ('x2', 15, {w2}), ('x3', 5, {w3});
+            INSERT INTO pomodoro_sessions VALUES ('p1', 'pomodoro', {w3}), ('p2', 'shortBreak', {w3}), ('p3', 'pomodoro', {old});",
+            old = at(4, 20, 9),
+            w1 = at(4, 29, 9),
+            w1_due = at(5, 1, 9),
+            w2 = at(5, 8, 9),
+            w3 = at(5, 13, 9),
+            later_today = at(5, 15, 18),
+        ))
+        .unwrap();
+
+        let weeks = get_weekly_summary(&conn, 3, now, false).unwrap();
+        let starts: Vec<i64> = weeks.iter().map(|week| week.week_start).collect();
+        assert_eq!(starts, vec![at(4, 29, 0), at(5, 6, 0), at(5, 13, 0)]);
+        let counts: Vec<(i64, i64, i64, i64, i64)> = weeks
+            .iter()
+            .map(|w| (w.tasks_created, w.tasks_completed, w.tasks_overdue, w.xp_earned, w.pomodoro_sessions))
+            .collect();
+        assert_eq!(counts, vec![(2, 1, 1, 0, 0), (1, 1, 1, 25, 0), (1, 0, 0, 5, 1)]);
+        let with_archived = get_weekly_summary(&conn, 3, now, true).unwrap();
+        assert_eq!((with_archived[2].tasks_created, with_archived[2].tasks_overdue), (2, 1));
+
+        assert!(get_weekly_summary(&conn, 0, now, false).is_err());
+    }
+
+    #[test]
+    fn test_productivity_score_components() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, created_at INTEGER NOT NULL, due_at INTEGER, completed_at INTEGER, archived INTEGER NOT NULL DEFAULT 0);
+            CREATE TABLE pomodoro_sessions (id TEXT PRIMARY KEY, completed_at INTEGER NOT NULL, duration_seconds INTEGER NOT NULL, mode TEXT NOT NULL);",
+        )
+        .unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 14).unwrap();
+        let day = |offset: i64| week_service::local_midnight(today - Duration::days(offset));
+        let now = day(0) + 12 * 3600;
+
+        // Nothing to measure: only focus and streak apply, and both are zero
+        let empty = get_productivity_score(&conn, 7, now).unwrap();
+        assert_eq!(empty.score, 0.0);
+        assert_eq!(empty.components[0].value, None);
+
+        // Four tasks created and completed on two days; one of the two with a due date was late.
+        // The task due at midnight and done that afternoon counts as on time.
+        conn.execute_batch(&format!(
+            "INSERT INTO tasks VALUES
+                ('a', {d3}, {d2}, {d2} + 50000, 0),
+                ('b', {d3}, {d3}, {d2} + 100, 0),
+                ('c', {d3}, NULL, {d2} + 200, 0),
+                ('d', {d3}, NULL, {d0} + 300, 0),
+                ('old', {d30}, NULL, {d30}, 0);
+            INSERT INTO pomodoro_sessions VALUES
+                ('s1', {d1}, 3600, 'pomodoro'),
+                ('s2', {d1}, 3600, 'shortBreak');",
+            d0 = day(0), d1 = day(1), d2 = day(2), d3 = day(3), d30 = day(30),
+        ))
+        .unwrap();
+
+        let score = get_productivity_score(&conn, 7, now).unwrap();
+        let values: Vec<Option<f64>> = score.components.iter().map(|c| c.value).collect();
+        assert_eq!(values[0], Some(0.5));
+        assert_eq!(values[1], Some(1.0));
+        assert!((values[2].unwrap() - 60.0 / 840.0).abs() < 1e-9);
+        assert!((values[3].unwrap() - 2.0 / 7.0).abs() < 1e-9);
+        let expected = (0.35 * 0.5 + 0.25 + 0.25 * 60.0 / 840.0 + 0.15 * 2.0 / 7.0) * 100.0;
+        assert!((score.score - expected).abs() < 1e-9);
+        let points: f64 = score.components.iter().map(|c| c.points).sum();
+        assert!((points - score.score).abs() < 1e-9);
+
+        let history = get_productivity_score_history(&conn, 7, 5, now).unwrap();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[4].date, "2024-03-14");
+        assert!((history[4].score - score.score).abs() < 1e-9);
+        assert_eq!(history[0].score, 0.0);
+        // Out-of-range requests are rejected or clamped instead of overflowing
+        assert!(get_productivity_score_history(&conn, 7, 0, now).is_err());
+        assert!(get_productivity_score(&conn, -3, now).is_err());
+        let clamped = get_productivity_score_history(&conn, i64::MAX, i64::MAX, now).unwrap();
+        assert_eq!(clamped.len() as i64, MAX_PRODUCTIVITY_DAYS);
+
+        // Weights come from the settings blob; focus alone now decides the score
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+            params![PRODUCTIVITY_SCORE_CONFIG_KEY, r#"{"on_time_weight":0,"throughput_weight":0,"streak_weight":0,"daily_focus_minutes":60}"#],
+        )
+        .unwrap();
+        let focus_only = get_productivity_score(&conn, 7, now).unwrap();
+        assert!((focus_only.score - 100.0 / 7.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,112 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Tasks to complete per day; unset or not a positive number means the default
+pub const DAILY_GOAL_SETTING: &str = "daily_goal";
+const DEFAULT_DAILY_GOAL: i64 = 5;
+
+/// Today at a glance, for the tray tooltip and the quick-add status bar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusLine {
+    pub current_streak: i32,
+    pub completed_today: i64,
+    pub daily_goal: i64,
+    /// Finished pomodoros only, not breaks
+    pub focus_minutes_today: i64,
+    pub level: i32,
+    /// The fields above as one short line
+    pub text: String,
+}
+
+fn load_daily_goal(conn: &rusqlite::Connection) -> i64 {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![DAILY_GOAL_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| value.trim().parse::<i64>().ok())
+    .filter(|goal| *goal > 0)
+    .unwrap_or(DEFAULT_DAILY_GOAL)
+}
+
+fn format_status_text(current_streak: i32, completed_today: i64, daily_goal: i64, focus_minutes_today: i64, level: i32) -> String {
+    format!(
+        "{}-day streak · {}/{} today · {} min focus · Level {}",
+        current_streak, completed_today, daily_goal, focus_minutes_today, level
+    )
+}
+
+/// Status for the day [day_start, day_end). One query per table, since the tray refreshes it
+/// every minute.
+pub fn get_status_line(conn: &rusqlite::Connection, day_start: i64, day_end: i64) -> Result<StatusLine, String> {
+    let (level, current_streak) = conn
+        .query_row(
+            "SELECT current_level, current_streak FROM user_progress WHERE id = 'default'",
+            [],
+            |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Query error: {}", e))?
+        .unwrap_or((1, 0));
+    let completed_today: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM tasks WHERE archived = 0 AND completed_at >= ?1 AND completed_at < ?2",
+            params![day_start, day_end],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let focus_seconds: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(duration_seconds), 0) FROM pomodoro_sessions
+            WHERE mode = 'pomodoro' AND completed_at >= ?1 AND completed_at < ?2",
+            params![day_start, day_end],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    let daily_goal = load_daily_goal(conn);
+    let focus_minutes_today = focus_seconds / 60;
+
+    Ok(StatusLine {
+        current_streak,
+        completed_today,
+        daily_goal,
+        focus_minutes_today,
+        level,
+        text: format_status_text(current_streak, completed_today, daily_goal, focus_minutes_today, level),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_line_counts_today_only() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE user_progress (id TEXT PRIMARY KEY, current_level INTEGER, current_streak INTEGER);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, completed_at INTEGER, archived INTEGER NOT NULL DEFAULT 0);
+            CREATE TABLE pomodoro_sessions (id TEXT PRIMARY KEY, mode TEXT, completed_at INTEGER, duration_seconds INTEGER);
+            INSERT INTO tasks (id, completed_at) VALUES ('yesterday', 50), ('a', 100), ('b', 150), ('open', NULL);
+            INSERT INTO pomodoro_sessions VALUES ('p1', 'pomodoro', 120, 1500), ('p2', 'shortBreak', 130, 300),
+                ('p3', 'pomodoro', 250, 1500);",
+        )
+        .unwrap();
+
+        // No progress row yet and no goal set
+        let status = get_status_line(&conn, 100, 200).unwrap();
+        assert_eq!((status.current_streak, status.level, status.daily_goal), (0, 1, 5));
+        assert_eq!((status.completed_today, status.focus_minutes_today), (2, 25));
+        assert_eq!(status.text, "0-day streak · 2/5 today · 25 min focus · Level 1");
+
+        conn.execute_batch(
+            "INSERT INTO user_progress VALUES ('default', 4, 3);
+            INSERT INTO settings VALUES ('daily_goal', '8');",
+        )
+        .unwrap();
+        let status = get_status_line(&conn, 100, 200).unwrap();
+        assert_eq!(status.text, "3-day streak · 2/8 today · 25 min focus · Level 4");
+    }
+}
@@ -0,0 +1,267 @@
+use super::project_suggestion_service::tokenize;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 5;
+const MAX_SUGGESTION_LIMIT: usize = 20;
+// A term seen on a single task says nothing about the tags that usually go with it
+const MIN_TERM_TASKS: i64 = 2;
+// Share of a term's tasks that must carry the tag before it is suggested
+const MIN_COOCCURRENCE_RATIO: f64 = 0.3;
+const NAME_MATCH_SCORE: f64 = 1.0;
+// History alone never outranks a tag the user actually typed
+const HISTORY_WEIGHT: f64 = 0.8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSuggestion {
+    pub tag_id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub score: f64,
+    /// Why the tag was suggested, shown next to the chip
+    pub reason: String,
+}
+
+// The term that best explains a tag's history with the task text
+struct CooccurrenceMatch {
+    name: String,
+    color: Option<String>,
+    ratio: f64,
+    term: String,
+    tag_count: i64,
+    term_count: i64,
+}
+
+// Lowercased words of any length, for matching short tag names like "ui" or "q3"
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Recompute how often each tag appears on tasks containing each title/description term
+pub fn rebuild_tag_cooccurrence(conn: &rusqlite::Connection, now: i64) -> Result<usize, String> {
+    let mut task_terms: HashMap<String, HashSet<String>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT id, title, description FROM tasks")
+            .map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (task_id, title, description) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            let mut terms: HashSet<String> = tokenize(&title).into_iter().collect();
+            if let Some(description) = description {
+                terms.extend(tokenize(&description));
+            }
+            task_terms.insert(task_id, terms);
+        }
+    }
+
+    let mut term_counts: HashMap<&str, i64> = HashMap::new();
+    for terms in task_terms.values() {
+        for term in terms {
+            *term_counts.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let mut pair_counts: HashMap<(String, &str), i64> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT task_id, tag_id FROM task_tags")
+            .map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (task_id, tag_id) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            if let Some(terms) = task_terms.get(&task_id) {
+                for term in terms {
+                    *pair_counts.entry((tag_id.clone(), term.as_str())).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM tag_term_cooccurrence", [])
+        .map_err(|e| format!("Failed to clear tag co-occurrence: {}", e))?;
+
+    let mut written = 0;
+    for ((tag_id, term), tag_count) in &pair_counts {
+        let term_count = term_counts[term];
+        if term_count < MIN_TERM_TASKS {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO tag_term_cooccurrence (tag_id, term, tag_count, term_count, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![tag_id, term, tag_count, term_count, now],
+        )
+        .map_err(|e| format!("Failed to save tag co-occurrence: {}", e))?;
+        written += 1;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(written)
+}
+
+/// Rank tags for a task: tags named in the text first, then tags often used on tasks sharing its terms
+pub fn suggest_tags_for_task(
+    conn: &rusqlite::Connection,
+    title: &str,
+    description: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<TagSuggestion>, String> {
+    let limit = limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT).clamp(1, MAX_SUGGESTION_LIMIT);
+    let text = match description {
+        Some(description) => format!("{}\n{}", title, description),
+        None => title.to_string(),
+    };
+    let text_words = words(&text);
+    let terms: HashSet<String> = tokenize(&text).into_iter().collect();
+    if text_words.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // tag_id -> suggestion, keeping the strongest reason
+    let mut suggestions: HashMap<String, TagSuggestion> = HashMap::new();
+
+    {
+        let mut stmt = conn
+            .prepare("SELECT id, name, color FROM tags")
+            .map_err(|e| format!("Query error: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (tag_id, name, color) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            let name_words = words(&name);
+            // Multi-word tags match when their words appear consecutively
+            let matched = !name_words.is_empty()
+                && text_words.windows(name_words.len()).any(|window| window == name_words.as_slice());
+            if matched {
+                suggestions.insert(tag_id.clone(), TagSuggestion {
+                    reason: format!("Mentioned in the task: \"{}\"", name),
+                    tag_id,
+                    name,
+                    color,
+                    score: NAME_MATCH_SCORE,
+                });
+            }
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.tag_id, t.name, t.color, c.tag_count, c.term_count FROM tag_term_cooccurrence c
+            INNER JOIN tags t ON t.id = c.tag_id
+            WHERE c.term = ?1",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let mut best: HashMap<String, CooccurrenceMatch> = HashMap::new();
+    for term in &terms {
+        let rows = stmt
+            .query_map(params![term], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .map_err(|e| format!("Query execution error: {}", e))?;
+        for row in rows {
+            let (tag_id, name, color, tag_count, term_count) = row.map_err(|e| format!("Row parsing error: {}", e))?;
+            let ratio = tag_count as f64 / term_count as f64;
+            if ratio < MIN_COOCCURRENCE_RATIO {
+                continue;
+            }
+            let better = best
+                .get(&tag_id)
+                .is_none_or(|m| ratio > m.ratio || (ratio == m.ratio && tag_count > m.tag_count));
+            if better {
+                best.insert(tag_id, CooccurrenceMatch { name, color, ratio, term: term.clone(), tag_count, term_count });
+            }
+        }
+    }
+
+    for (tag_id, m) in best {
+        match suggestions.get_mut(&tag_id) {
+            // Already named in the text; history only breaks ties between name matches
+            Some(existing) => existing.score += m.ratio * 0.1,
+            None => {
+                let reason = format!("Used on {} of {} tasks mentioning \"{}\"", m.tag_count, m.term_count, m.term);
+                suggestions.insert(tag_id.clone(), TagSuggestion {
+                    tag_id,
+                    name: m.name,
+                    color: m.color,
+                    score: m.ratio * HISTORY_WEIGHT,
+                    reason,
+                });
+            }
+        }
+    }
+
+    let mut suggestions: Vec<TagSuggestion> = suggestions.into_values().collect();
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    suggestions.truncate(limit);
+
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, description TEXT);
+            CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE, color TEXT);
+            CREATE TABLE task_tags (task_id TEXT NOT NULL, tag_id TEXT NOT NULL);
+            INSERT INTO tags (id, name) VALUES ('t-urgent', 'urgent'), ('t-finance', 'finance'), ('t-ui', 'ui');
+            INSERT INTO tasks (id, title, description) VALUES
+                ('1', 'Pay invoice for hosting', NULL),
+                ('2', 'Send invoice to client', 'before Friday'),
+                ('3', 'Reconcile invoice totals', NULL),
+                ('4', 'Fix login button', NULL),
+                ('5', 'Review hosting costs', NULL);
+            INSERT INTO task_tags (task_id, tag_id) VALUES
+                ('1', 't-finance'), ('2', 't-finance'), ('3', 't-finance'), ('2', 't-urgent');",
+        )
+        .unwrap();
+        conn.execute_batch(include_str!("../../migrations/0024_add_tag_term_cooccurrence.sql")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_suggests_tags_by_name_and_history() {
+        let conn = setup_test_conn();
+        assert!(rebuild_tag_cooccurrence(&conn, 0).unwrap() > 0);
+
+        let suggestions = suggest_tags_for_task(&conn, "Chase overdue invoice", None, None).unwrap();
+        assert_eq!(suggestions[0].tag_id, "t-finance");
+        assert_eq!(suggestions[0].reason, "Used on 3 of 3 tasks mentioning \"invoice\"");
+        // 1 of 3 invoice tasks is urgent, just over the threshold
+        assert!(suggestions.iter().any(|s| s.tag_id == "t-urgent"));
+
+        // Short tag names match whole words, and a name match outranks history
+        let suggestions = suggest_tags_for_task(&conn, "Polish UI for invoice page", None, Some(1)).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].tag_id, "t-ui");
+        assert!(suggestions[0].reason.starts_with("Mentioned"));
+
+        assert!(suggest_tags_for_task(&conn, "Build guide", None, None).unwrap().is_empty());
+        assert!(suggest_tags_for_task(&conn, "", None, None).unwrap().is_empty());
+    }
+}
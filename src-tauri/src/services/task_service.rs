@@ -1,3 +1,4 @@
+use chrono::{Datelike, Days, Local, Months, NaiveDate, TimeZone, Weekday};
 use rusqlite::params;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -34,6 +35,20 @@ pub fn fetch_task(conn: &rusqlite::Connection, id: &str) -> Result<Task, String>
                 reminder_minutes_before: row.get(14).ok().flatten(),
                 notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
                 tags: None,
+                is_blocked: None,
+                blocker_count: None,
+                reminder_state: None,
+                completion_note_required: None,
+                instance_count: None,
+                waiting_on: None,
+                waiting_since: None,
+                pinned: false,
+                status: None,
+                recurrence_days_of_week: None,
+                recurrence_end_date: None,
+                recurrence_count: None,
+                estimated_minutes: None,
+                actual_minutes: None,
             })
         },
     ).map_err(|e| format!("Task not found: {}", e))
@@ -92,6 +107,20 @@ pub fn get_tasks(conn: &rusqlite::Connection, filter: Option<TaskFilter>) -> Res
             reminder_minutes_before: row.get(14).ok().flatten(),
             notification_repeat: row.get::<_, Option<i32>>(15).unwrap_or(None).map_or(false, |x| x != 0),
             tags: None,
+            is_blocked: None,
+            blocker_count: None,
+            reminder_state: None,
+            completion_note_required: None,
+            instance_count: None,
+            waiting_on: None,
+            waiting_since: None,
+            pinned: false,
+            status: None,
+            recurrence_days_of_week: None,
+            recurrence_end_date: None,
+            recurrence_count: None,
+            estimated_minutes: None,
+            actual_minutes: None,
         })
     }).map_err(|e| format!("Query execution error: {}", e))?;
     
@@ -214,7 +243,7 @@ pub fn toggle_complete(conn: &rusqlite::Connection, id: &str) -> Result<Task, St
     let new_completed = if completed.is_some() { None } else { Some(now) };
     
     conn.execute(
-        "UPDATE tasks SET completed_at = ?1, updated_at = ?2 WHERE id = ?3",
+        "UPDATE tasks SET completed_at = ?1, updated_at = ?2, status = NULL WHERE id = ?3",
         params![new_completed, now, id],
     ).map_err(|e| format!("Failed to toggle complete: {}", e))?;
     
@@ -226,49 +255,265 @@ pub fn toggle_complete(conn: &rusqlite::Connection, id: &str) -> Result<Task, St
     fetch_task(conn, id)
 }
 
-// Helper function to create a recurring task instance
-fn create_recurring_instance(conn: &rusqlite::Connection, parent_id: &str, recurrence_type: &str, interval: i32) -> Result<(), String> {
-    // Fetch original task details
-    let original: (String, Option<String>, Option<i64>, String, Option<String>, i32) = conn.query_row(
-        "SELECT title, description, due_at, priority, project_id, order_index FROM tasks WHERE id = ?1",
+// Last day of the month `date` falls in
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(28, |last| last.day())
+}
+
+/// Due date of the next instance of a recurring task, in local time with the time of day kept.
+/// Monthly and yearly steps follow the calendar: the 31st recurs on the last day of shorter
+/// months and Feb 29 on Feb 28. `anchor_day` is the day of month the chain started on; a due
+/// date that was clamped to a month end moves back up to it when the month is long enough.
+/// Unknown recurrence types keep the due date.
+pub fn next_due_date(due_at: i64, recurrence_type: &str, interval: i32, anchor_day: Option<u32>) -> i64 {
+    let Some(due) = Local.timestamp_opt(due_at, 0).earliest() else {
+        return due_at;
+    };
+    let due = due.naive_local();
+    let interval = interval.max(1) as u32;
+    let months = match recurrence_type {
+        "monthly" => interval,
+        "yearly" => 12 * interval,
+        _ => 0,
+    };
+
+    let next = match recurrence_type {
+        "daily" => due.checked_add_days(Days::new(interval as u64)),
+        "weekly" => due.checked_add_days(Days::new(7 * interval as u64)),
+        "monthly" | "yearly" => due.checked_add_months(Months::new(months)).map(|next| {
+            match anchor_day {
+                Some(anchor) if due.day() == last_day_of_month(due.date()) && anchor > next.day() => {
+                    next.with_day(anchor.min(last_day_of_month(next.date()))).unwrap_or(next)
+                }
+                _ => next,
+            }
+        }),
+        _ => None,
+    };
+    next.and_then(|next| Local.from_local_datetime(&next).earliest())
+        .map_or(due_at, |next| next.timestamp())
+}
+
+/// Parse a stored recurrence_days_of_week ("1,3,5") into ISO weekdays, Monday = 1
+pub fn parse_days_of_week(stored: Option<&str>) -> Vec<u32> {
+    stored
+        .unwrap_or("")
+        .split(',')
+        .filter_map(|day| day.trim().parse::<u32>().ok())
+        .filter(|day| (1..=7).contains(day))
+        .collect()
+}
+
+/// Validate ISO weekdays for storage: sorted, without duplicates, None when empty
+pub fn format_days_of_week(days: &[u32]) -> Result<Option<String>, String> {
+    if let Some(day) = days.iter().find(|day| !(1..=7).contains(*day)) {
+        return Err(format!("Invalid weekday: {} (expected 1 = Monday to 7 = Sunday)", day));
+    }
+    let mut days = days.to_vec();
+    days.sort_unstable();
+    days.dedup();
+    if days.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(days.iter().map(u32::to_string).collect::<Vec<_>>().join(",")))
+}
+
+/// Next due date of a weekly recurrence limited to some weekdays: the next of `days` after
+/// `due_at`, in the due date's week or every `interval` weeks after it, at the same local time
+pub fn next_weekday_due_date(due_at: i64, interval: i32, days: &[u32]) -> i64 {
+    let Some(due) = Local.timestamp_opt(due_at, 0).earliest() else {
+        return due_at;
+    };
+    let due = due.naive_local();
+    let interval = interval.max(1) as i64;
+    let first_week = due.date().week(Weekday::Mon).first_day();
+
+    (1..=7 * (interval as u64 + 1))
+        .filter_map(|offset| due.checked_add_days(Days::new(offset)))
+        .find(|candidate| {
+            let weeks = (candidate.date().week(Weekday::Mon).first_day() - first_week).num_days() / 7;
+            weeks % interval == 0 && days.contains(&candidate.weekday().number_from_monday())
+        })
+        .and_then(|next| Local.from_local_datetime(&next).earliest())
+        .map_or(due_at, |next| next.timestamp())
+}
+
+// The task an instance is copied from, and the due date its chain started on
+struct RecurrenceSource {
+    title: String,
+    description: Option<String>,
+    due_at: Option<i64>,
+    priority: String,
+    project_id: Option<String>,
+    order_index: i32,
+    completion_note_required: bool,
+    root_due_at: Option<i64>,
+    days_of_week: Option<String>,
+    end_date: Option<i64>,
+    count: Option<i32>,
+    // Tasks in the chain so far
+    chain_size: i32,
+}
+
+/// Create the next instance of a recurring task, due one recurrence step after `parent_id`.
+/// Nothing is created once the chain has reached its recurrence_end_date or recurrence_count.
+pub fn create_recurring_instance(conn: &rusqlite::Connection, parent_id: &str, recurrence_type: &str, interval: i32) -> Result<(), String> {
+    let original = conn.query_row(
+        "SELECT title, description, due_at, priority, project_id, order_index, completion_note_required,
+                (SELECT root.due_at FROM tasks root WHERE root.id = COALESCE(tasks.recurrence_root_id, tasks.id)),
+                recurrence_days_of_week, recurrence_end_date, recurrence_count,
+                (SELECT COUNT(*) FROM tasks chain
+                 WHERE COALESCE(chain.recurrence_root_id, chain.id) = COALESCE(tasks.recurrence_root_id, tasks.id))
+         FROM tasks WHERE id = ?1",
         params![parent_id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        |row| {
+            Ok(RecurrenceSource {
+                title: row.get(0)?,
+                description: row.get(1)?,
+                due_at: row.get(2)?,
+                priority: row.get(3)?,
+                project_id: row.get(4)?,
+                order_index: row.get(5)?,
+                completion_note_required: row.get(6)?,
+                root_due_at: row.get(7)?,
+                days_of_week: row.get(8)?,
+                end_date: row.get(9)?,
+                count: row.get(10)?,
+                chain_size: row.get(11)?,
+            })
+        },
     ).map_err(|e| format!("Failed to fetch original task: {}", e))?;
     
-    let (title, description, due_date, priority, project_id, order_index) = original;
+    let anchor_day = original
+        .root_due_at
+        .and_then(|root| Local.timestamp_opt(root, 0).earliest())
+        .map(|root| root.day());
+    let days = parse_days_of_week(original.days_of_week.as_deref());
+    let new_due_date = original.due_at.map(|due| {
+        if recurrence_type == "weekly" && !days.is_empty() {
+            next_weekday_due_date(due, interval, &days)
+        } else {
+            next_due_date(due, recurrence_type, interval, anchor_day)
+        }
+    });
     
-    // Calculate next due date based on recurrence type
-    let next_due_date = match recurrence_type {
-        "daily" => due_date.map(|d| d + (interval as i64 * 24 * 60 * 60)),
-        "weekly" => due_date.map(|d| d + (interval as i64 * 7 * 24 * 60 * 60)),
-        "monthly" => due_date.map(|d| d + (interval as i64 * 30 * 24 * 60 * 60)),
-        _ => due_date,
-    };
+    if original.count.is_some_and(|count| original.chain_size >= count) {
+        return Ok(());
+    }
+    if let Some(end_date) = original.end_date {
+        // Without a due date the chain ends once the end date has passed
+        if new_due_date.unwrap_or_else(now) > end_date {
+            return Ok(());
+        }
+    }
     
     let new_id = uuid::Uuid::new_v4().to_string();
     let now = now();
     
     conn.execute(
-        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        "INSERT INTO tasks (id, title, description, due_at, created_at, updated_at, priority, completed_at, project_id, order_index, metadata, recurrence_type, recurrence_interval, recurrence_parent_id, completion_note_required, recurrence_root_id, recurrence_days_of_week, recurrence_end_date, recurrence_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                 (SELECT COALESCE(recurrence_root_id, id) FROM tasks WHERE id = ?14), ?16, ?17, ?18)",
         params![
             new_id,
-            title,
-            description,
-            next_due_date,
+            original.title,
+            original.description,
+            new_due_date,
             now,
             now,
-            priority,
+            original.priority,
             None::<i64>,
-            project_id,
-            order_index,
+            original.project_id,
+            original.order_index,
             None::<String>,
             recurrence_type,
             interval,
-            parent_id,
+            Some(parent_id),
+            original.completion_note_required,
+            original.days_of_week,
+            original.end_date,
+            original.count
         ],
-    ).map_err(|e| format!("Failed to create recurring instance: {}", e))?;
+    ).map_err(|e| format!("Failed to create recurring task instance: {}", e))?;
     
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn at(date: &str) -> i64 {
+        let naive = NaiveDateTime::parse_from_str(&format!("{} 09:30", date), "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap().timestamp()
+    }
+
+    fn date(timestamp: i64) -> String {
+        Local.timestamp_opt(timestamp, 0).unwrap().format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    // Follow a chain of `count` instances from `start`, anchored on its first day
+    fn chain(start: &str, recurrence_type: &str, interval: i32, count: usize) -> Vec<String> {
+        let first = at(start);
+        let anchor = Some(Local.timestamp_opt(first, 0).unwrap().day());
+        let mut due = first;
+        (0..count)
+            .map(|_| {
+                due = next_due_date(due, recurrence_type, interval, anchor);
+                date(due)[..10].to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_monthly_recurrence_clamps_to_month_end_without_drifting() {
+        assert_eq!(date(next_due_date(at("2023-01-31"), "monthly", 1, None)), "2023-02-28 09:30");
+        assert_eq!(date(next_due_date(at("2024-01-31"), "monthly", 1, None)), "2024-02-29 09:30");
+        assert_eq!(chain("2023-01-31", "monthly", 1, 4), vec!["2023-02-28", "2023-03-31", "2023-04-30", "2023-05-31"]);
+        assert_eq!(chain("2023-01-30", "monthly", 1, 2), vec!["2023-02-28", "2023-03-30"]);
+        assert_eq!(chain("2023-08-31", "monthly", 3, 2), vec!["2023-11-30", "2024-02-29"]);
+        // A date moved by hand to the middle of a month stays there
+        assert_eq!(date(next_due_date(at("2023-03-15"), "monthly", 1, Some(31))), "2023-04-15 09:30");
+    }
+
+    #[test]
+    fn test_recurrence_rolls_over_the_year() {
+        assert_eq!(chain("2023-12-31", "monthly", 1, 2), vec!["2024-01-31", "2024-02-29"]);
+        assert_eq!(chain("2023-11-15", "monthly", 2, 1), vec!["2024-01-15"]);
+        assert_eq!(chain("2023-12-30", "daily", 3, 1), vec!["2024-01-02"]);
+        assert_eq!(chain("2023-12-28", "weekly", 1, 1), vec!["2024-01-04"]);
+    }
+
+    #[test]
+    fn test_yearly_recurrence_handles_leap_days() {
+        assert_eq!(chain("2024-02-29", "yearly", 1, 4), vec!["2025-02-28", "2026-02-28", "2027-02-28", "2028-02-29"]);
+        assert_eq!(chain("2023-03-01", "yearly", 2, 1), vec!["2025-03-01"]);
+        assert_eq!(next_due_date(at("2023-03-01"), "custom", 1, None), at("2023-03-01"));
+    }
+
+    #[test]
+    fn test_weekday_recurrence_picks_the_next_listed_day() {
+        let mon_wed_fri = parse_days_of_week(format_days_of_week(&[5, 1, 3, 3]).unwrap().as_deref());
+        assert_eq!(mon_wed_fri, vec![1, 3, 5]);
+        assert!(format_days_of_week(&[0]).is_err());
+        assert_eq!(format_days_of_week(&[]).unwrap(), None);
+
+        // 2024-01-01 is a Monday
+        let mut due = at("2024-01-01");
+        let mut dates = Vec::new();
+        for _ in 0..4 {
+            due = next_weekday_due_date(due, 1, &mon_wed_fri);
+            dates.push(date(due));
+        }
+        assert_eq!(dates, vec!["2024-01-03 09:30", "2024-01-05 09:30", "2024-01-08 09:30", "2024-01-10 09:30"]);
+
+        // Every other week skips the week in between, across the year end too
+        assert_eq!(date(next_weekday_due_date(at("2024-01-05"), 2, &mon_wed_fri)), "2024-01-15 09:30");
+        assert_eq!(date(next_weekday_due_date(at("2023-12-29"), 1, &[2])), "2024-01-02 09:30");
+    }
+}
@@ -1,7 +1,8 @@
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GoogleTranslateResponse {
@@ -58,6 +59,282 @@ fn now() -> i64 {
         .as_secs() as i64
 }
 
+/// Setting that must be "true" before task text is sent to a translation service
+pub const TRANSLATION_CONSENT_KEY: &str = "translation_consent";
+/// Error returned instead of translating when consent is missing; the UI turns it into a prompt
+pub const CONSENT_REQUIRED_ERROR: &str = "translation_consent_required";
+
+pub const GOOGLE_TRANSLATE_ENDPOINT: &str = "https://translation.googleapis.com/language/translate/v2";
+pub const LIBRE_TRANSLATE_ENDPOINT: &str = "https://translate.argosopentech.com/translate";
+
+/// Error returned while a provider's circuit breaker is open
+pub const TRANSLATION_UNAVAILABLE_ERROR: &str = "translation_temporarily_unavailable";
+/// Consecutive failed calls (after retries) that open a provider's breaker
+pub const BREAKER_FAILURE_THRESHOLD: u32 = 3;
+/// How long an open breaker short-circuits calls before letting one through again
+pub const BREAKER_COOLDOWN_SECS: i64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Google,
+    Libre,
+}
+
+/// A failed request to a provider
+#[derive(Debug, Clone)]
+pub struct RequestError {
+    pub message: String,
+    /// Network errors, 429 and 5xx responses; worth retrying
+    pub transient: bool,
+}
+
+impl RequestError {
+    fn from_status(status: reqwest::StatusCode, body: String) -> Self {
+        RequestError {
+            message: format!("Translation failed ({}): {}", status, body),
+            transient: status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    fn transient(message: String) -> Self {
+        RequestError { message, transient: true }
+    }
+
+    fn permanent(message: String) -> Self {
+        RequestError { message, transient: false }
+    }
+}
+
+/// Sends a single translation request, without retries. Implemented over HTTP, and by a mock in tests.
+pub(crate) trait TranslationClient {
+    async fn send(&self, provider: Provider, text: &str, source_lang: &str, target_lang: &str) -> Result<String, RequestError>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Doubled after each failed attempt
+    pub base_delay: Duration,
+}
+
+pub const DEFAULT_RETRY_POLICY: RetryPolicy = RetryPolicy { max_retries: 2, base_delay: Duration::from_millis(500) };
+
+/// Send a request, retrying transient failures with exponential backoff
+pub(crate) async fn send_with_retry<C: TranslationClient>(
+    client: &C,
+    policy: RetryPolicy,
+    provider: Provider,
+    text: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<String, String> {
+    let mut attempt = 0;
+    loop {
+        match client.send(provider, text, source_lang, target_lang).await {
+            Ok(translated) => return Ok(translated),
+            Err(error) if error.transient && attempt < policy.max_retries => {
+                if !policy.base_delay.is_zero() {
+                    tokio::time::sleep(policy.base_delay * 2u32.pow(attempt)).await;
+                }
+                attempt += 1;
+            }
+            Err(error) => return Err(error.message),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<i64>,
+}
+
+/// State of a provider's circuit breaker, for get_external_services_status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakerStatus {
+    /// "closed", "open", or "half_open" once the cooldown is over and the next call is a trial
+    pub state: String,
+    pub consecutive_failures: u32,
+    pub open_until: Option<i64>,
+}
+
+/// Stops calling a provider for BREAKER_COOLDOWN_SECS after BREAKER_FAILURE_THRESHOLD failed calls
+/// in a row. Any success closes it again.
+#[derive(Debug, Default)]
+pub struct CircuitBreaker(Mutex<BreakerState>);
+
+impl CircuitBreaker {
+    fn state(&self) -> BreakerState {
+        self.0.lock().map(|state| *state).unwrap_or_default()
+    }
+
+    pub fn check(&self, now: i64) -> Result<(), String> {
+        match self.state().open_until {
+            Some(open_until) if open_until > now => Err(TRANSLATION_UNAVAILABLE_ERROR.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_success(&self) {
+        if let Ok(mut state) = self.0.lock() {
+            *state = BreakerState::default();
+        }
+    }
+
+    pub fn record_failure(&self, now: i64) {
+        if let Ok(mut state) = self.0.lock() {
+            state.consecutive_failures += 1;
+            if state.consecutive_failures >= BREAKER_FAILURE_THRESHOLD {
+                state.open_until = Some(now + BREAKER_COOLDOWN_SECS);
+            }
+        }
+    }
+
+    pub fn status(&self, now: i64) -> BreakerStatus {
+        let state = self.state();
+        let label = match state.open_until {
+            Some(open_until) if open_until > now => "open",
+            Some(_) => "half_open",
+            None => "closed",
+        };
+        BreakerStatus {
+            state: label.to_string(),
+            consecutive_failures: state.consecutive_failures,
+            open_until: state.open_until,
+        }
+    }
+}
+
+/// One circuit breaker per provider, kept in managed state
+#[derive(Debug, Default)]
+pub struct TranslationBreakers {
+    pub google: CircuitBreaker,
+    pub libre: CircuitBreaker,
+}
+
+impl TranslationBreakers {
+    pub fn for_provider(&self, provider: Provider) -> &CircuitBreaker {
+        match provider {
+            Provider::Google => &self.google,
+            Provider::Libre => &self.libre,
+        }
+    }
+}
+
+/// Send through the provider's breaker: short-circuit while it is open, otherwise retry and
+/// record the outcome
+pub(crate) async fn send_guarded<C: TranslationClient>(
+    client: &C,
+    breakers: &TranslationBreakers,
+    policy: RetryPolicy,
+    provider: Provider,
+    text: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<String, String> {
+    let breaker = breakers.for_provider(provider);
+    breaker.check(now())?;
+    match send_with_retry(client, policy, provider, text, source_lang, target_lang).await {
+        Ok(translated) => {
+            breaker.record_success();
+            Ok(translated)
+        }
+        Err(error) => {
+            breaker.record_failure(now());
+            Err(error)
+        }
+    }
+}
+
+/// Google (with the key, if any) and LibreTranslate over HTTP
+struct HttpTranslationClient<'a> {
+    api_key: Option<&'a str>,
+}
+
+impl TranslationClient for HttpTranslationClient<'_> {
+    async fn send(&self, provider: Provider, text: &str, source_lang: &str, target_lang: &str) -> Result<String, RequestError> {
+        match (provider, self.api_key) {
+            (Provider::Google, Some(api_key)) => translate_text_google(text, source_lang, target_lang, api_key).await,
+            (Provider::Google, None) => Err(RequestError::permanent("No Google Translate API key".to_string())),
+            (Provider::Libre, _) => translate_text_libre(text, source_lang, target_lang).await,
+        }
+    }
+}
+
+pub fn has_translation_consent(conn: &rusqlite::Connection) -> bool {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![TRANSLATION_CONSENT_KEY],
+        |row| row.get::<_, String>(0),
+    )
+    .map(|value| value == "true")
+    .unwrap_or(false)
+}
+
+pub fn require_translation_consent(conn: &rusqlite::Connection) -> Result<(), String> {
+    if has_translation_consent(conn) {
+        Ok(())
+    } else {
+        Err(CONSENT_REQUIRED_ERROR.to_string())
+    }
+}
+
+pub fn set_translation_consent(conn: &rusqlite::Connection, consent: bool) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![TRANSLATION_CONSENT_KEY, consent.to_string()],
+    ).map_err(|e| format!("Failed to save translation consent: {}", e))?;
+    Ok(())
+}
+
+/// An external endpoint the app may send data to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExternalService {
+    pub id: String,
+    pub name: String,
+    pub endpoint: String,
+    pub enabled: bool,
+    /// Why the service is off, when it is
+    pub reason: Option<String>,
+    pub breaker: BreakerStatus,
+}
+
+pub fn get_external_services_status(
+    conn: &rusqlite::Connection,
+    breakers: &TranslationBreakers,
+) -> Result<Vec<ExternalService>, String> {
+    let now = now();
+    let consent = has_translation_consent(conn);
+    let has_api_key = get_api_key(conn)?.is_some();
+    let no_consent = || Some("Translation consent not given".to_string());
+
+    Ok(vec![
+        ExternalService {
+            id: "google_translate".to_string(),
+            name: "Google Translate".to_string(),
+            endpoint: GOOGLE_TRANSLATE_ENDPOINT.to_string(),
+            enabled: consent && has_api_key,
+            reason: if !consent {
+                no_consent()
+            } else if !has_api_key {
+                Some("No Google Translate API key".to_string())
+            } else {
+                None
+            },
+            breaker: breakers.google.status(now),
+        },
+        // Used without an API key, and as the fallback when Google fails
+        ExternalService {
+            id: "libre_translate".to_string(),
+            name: "LibreTranslate".to_string(),
+            endpoint: LIBRE_TRANSLATE_ENDPOINT.to_string(),
+            enabled: consent,
+            reason: if consent { None } else { no_consent() },
+            breaker: breakers.libre.status(now),
+        },
+    ])
+}
+
 // Get API key from environment variable or settings (optional - returns None if not found)
 pub fn get_api_key(conn: &rusqlite::Connection) -> Result<Option<String>, String> {
     // First try environment variable
@@ -96,8 +373,8 @@ pub async fn detect_language(text: &str, api_key: Option<&str>) -> Result<String
     // If API key is available, use Google Translate detection
     if let Some(key) = api_key {
         let url = format!(
-            "https://translation.googleapis.com/language/translate/v2/detect?key={}",
-            key
+            "{}/detect?key={}",
+            GOOGLE_TRANSLATE_ENDPOINT, key
         );
         
         let client = reqwest::Client::new();
@@ -130,18 +407,10 @@ pub async fn detect_language(text: &str, api_key: Option<&str>) -> Result<String
     Ok("en".to_string())
 }
 
-// Translate text using LibreTranslate (free, no API key required)
-pub async fn translate_text_libre(
-    text: &str,
-    source_lang: &str,
-    target_lang: &str,
-) -> Result<String, String> {
-    if text.trim().is_empty() {
-        return Ok(text.to_string());
-    }
-    
+// Translate text using LibreTranslate (free, no API key required); one attempt, see send_with_retry
+async fn translate_text_libre(text: &str, source_lang: &str, target_lang: &str) -> Result<String, RequestError> {
     // Use public LibreTranslate instance (no API key required)
-    let url = "https://translate.argosopentech.com/translate";
+    let url = LIBRE_TRANSLATE_ENDPOINT;
     
     let client = reqwest::Client::new();
     let request_body = LibreTranslateRequest {
@@ -157,35 +426,27 @@ pub async fn translate_text_libre(
         .header("Content-Type", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to translate: {}", e))?;
+        .map_err(|e| RequestError::transient(format!("Failed to translate: {}", e)))?;
     
     let status = response.status();
     if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Translation failed ({}): {}", status, error_text));
+        return Err(RequestError::from_status(status, error_text));
     }
     
     let translate_response: LibreTranslateResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse translation response: {}", e))?;
+        .map_err(|e| RequestError::permanent(format!("Failed to parse translation response: {}", e)))?;
     
     Ok(translate_response.translated_text)
 }
 
-// Translate text using Google Translate API (if API key available)
-pub async fn translate_text_google(
-    text: &str,
-    source_lang: &str,
-    target_lang: &str,
-    api_key: &str,
-) -> Result<String, String> {
-    if text.trim().is_empty() {
-        return Ok(text.to_string());
-    }
-    
+// Translate text using Google Translate API (if API key available); one attempt, see send_with_retry
+async fn translate_text_google(text: &str, source_lang: &str, target_lang: &str, api_key: &str) -> Result<String, RequestError> {
     let url = format!(
-        "https://translation.googleapis.com/language/translate/v2?key={}",
+        "{}?key={}",
+        GOOGLE_TRANSLATE_ENDPOINT,
         api_key
     );
     
@@ -199,27 +460,27 @@ pub async fn translate_text_google(
         ])
         .send()
         .await
-        .map_err(|e| format!("Failed to translate: {}", e))?;
+        .map_err(|e| RequestError::transient(format!("Failed to translate: {}", e)))?;
     
     let status = response.status();
     if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Translation failed ({}): {}", status, error_text));
+        return Err(RequestError::from_status(status, error_text));
     }
     
     let translate_response: GoogleTranslateResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse translation response: {}", e))?;
+        .map_err(|e| RequestError::permanent(format!("Failed to parse translation response: {}", e)))?;
     
     if let Some(translation) = translate_response.data.translations.first() {
         Ok(translation.translated_text.clone())
     } else {
-        Err("No translation returned".to_string())
+        Err(RequestError::permanent("No translation returned".to_string()))
     }
 }
 
-// Get cached translation
+// Get cached translation; a user-edited one wins over a machine translation of the same text
 pub fn get_cached_translation(
     conn: &rusqlite::Connection,
     source_text_hash: &str,
@@ -228,7 +489,8 @@ pub fn get_cached_translation(
     field_type: &str,
 ) -> Result<Option<String>, String> {
     let result: Result<String, rusqlite::Error> = conn.query_row(
-        "SELECT translated_text FROM translations WHERE source_text_hash = ?1 AND source_lang = ?2 AND target_lang = ?3 AND field_type = ?4",
+        "SELECT translated_text FROM translations WHERE source_text_hash = ?1 AND source_lang = ?2 AND target_lang = ?3 AND field_type = ?4
+         ORDER BY is_user_edited DESC, updated_at DESC LIMIT 1",
         params![source_text_hash, source_lang, target_lang, field_type],
         |row| row.get(0),
     );
@@ -297,6 +559,109 @@ pub fn get_user_translation(
     }
 }
 
+/// A hand-corrected translation as written by export_translation_overrides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationOverride {
+    pub source_text: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub field_type: String,
+    pub translated_text: String,
+}
+
+/// The overrides file: also embedded in export_data under "translation_overrides"
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranslationOverridesDocument {
+    pub overrides: Vec<TranslationOverride>,
+    #[serde(default)]
+    pub exported_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranslationOverridesExport {
+    pub path: String,
+    pub exported: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TranslationOverridesImport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+/// All user-edited translations, one per source text, languages and field (the latest edit)
+pub fn get_translation_overrides(conn: &rusqlite::Connection) -> Result<TranslationOverridesDocument, String> {
+    let mut stmt = conn.prepare(
+        "SELECT source_text, source_lang, target_lang, field_type, translated_text FROM translations t
+         WHERE is_user_edited = 1
+           AND NOT EXISTS (
+               SELECT 1 FROM translations newer
+               WHERE newer.is_user_edited = 1
+                 AND newer.source_text_hash = t.source_text_hash
+                 AND newer.source_lang = t.source_lang
+                 AND newer.target_lang = t.target_lang
+                 AND newer.field_type = t.field_type
+                 AND (newer.updated_at > t.updated_at OR (newer.updated_at = t.updated_at AND newer.id > t.id))
+           )
+         ORDER BY source_lang, target_lang, field_type, source_text",
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let overrides = stmt.query_map([], |row| {
+        Ok(TranslationOverride {
+            source_text: row.get(0)?,
+            source_lang: row.get(1)?,
+            target_lang: row.get(2)?,
+            field_type: row.get(3)?,
+            translated_text: row.get(4)?,
+        })
+    }).map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    
+    Ok(TranslationOverridesDocument { overrides, exported_at: now() })
+}
+
+/// Upsert overrides keyed by source text hash, languages and field. Every matching row,
+/// whichever task it was cached for, takes the imported text and is marked user-edited;
+/// with no match a row without a task is added. Callers run it inside a transaction.
+pub fn import_translation_overrides(
+    conn: &rusqlite::Connection,
+    overrides: &[TranslationOverride],
+) -> Result<TranslationOverridesImport, String> {
+    let mut summary = TranslationOverridesImport::default();
+    for item in overrides {
+        if item.source_text.trim().is_empty() || !matches!(item.field_type.as_str(), "title" | "description") {
+            return Err(format!("Invalid translation override for field '{}'", item.field_type));
+        }
+        let source_text_hash = hash_text(&item.source_text);
+        let key = params![source_text_hash, item.source_lang, item.target_lang, item.field_type];
+        let existing: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM translations WHERE source_text_hash = ?1 AND source_lang = ?2 AND target_lang = ?3 AND field_type = ?4",
+            key,
+            |row| row.get(0),
+        ).map_err(|e| format!("Database error: {}", e))?;
+        
+        if existing == 0 {
+            save_translation(conn, &item.source_text, &item.source_lang, &item.target_lang, &item.translated_text, &item.field_type, None, true)?;
+            summary.added += 1;
+            continue;
+        }
+        let changed = conn.execute(
+            "UPDATE translations SET translated_text = ?5, is_user_edited = 1, updated_at = ?6
+             WHERE source_text_hash = ?1 AND source_lang = ?2 AND target_lang = ?3 AND field_type = ?4
+               AND (translated_text != ?5 OR is_user_edited = 0)",
+            params![source_text_hash, item.source_lang, item.target_lang, item.field_type, item.translated_text, now()],
+        ).map_err(|e| format!("Failed to save translation: {}", e))?;
+        if changed > 0 {
+            summary.updated += 1;
+        } else {
+            summary.unchanged += 1;
+        }
+    }
+    
+    Ok(summary)
+}
+
 // Helper function to check cache and get user translation (synchronous, no await)
 pub fn check_cache_and_user_translation(
     conn: &rusqlite::Connection,
@@ -321,11 +686,13 @@ pub fn check_cache_and_user_translation(
 
 // Main translation function that handles API calls (no connection needed)
 // This function does NOT handle caching - that must be done by the caller
-// Uses Google Translate if API key is provided, otherwise falls back to LibreTranslate (free)
+// Uses Google Translate if API key is provided, otherwise falls back to LibreTranslate (free).
+// Providers whose circuit breaker is open are skipped.
 pub async fn translate_text(
     text: &str,
     target_lang: &str,
     api_key: Option<&str>,
+    breakers: &TranslationBreakers,
 ) -> Result<String, String> {
     if text.trim().is_empty() {
         return Ok(text.to_string());
@@ -339,21 +706,161 @@ pub async fn translate_text(
         return Ok(text.to_string());
     }
     
-    // Translate via API - use Google if API key available, otherwise LibreTranslate
-    let translated = if let Some(key) = api_key {
-        // Try Google Translate first
-        match translate_text_google(text, &source_lang, target_lang, key).await {
-            Ok(result) => result,
-            Err(_) => {
-                // Fallback to LibreTranslate if Google fails
-                translate_text_libre(text, &source_lang, target_lang).await?
-            }
+    let client = HttpTranslationClient { api_key };
+    translate_with(&client, breakers, DEFAULT_RETRY_POLICY, api_key.is_some(), text, &source_lang, target_lang).await
+}
+
+// Google first when there is an API key, LibreTranslate otherwise or when Google fails
+async fn translate_with<C: TranslationClient>(
+    client: &C,
+    breakers: &TranslationBreakers,
+    policy: RetryPolicy,
+    use_google: bool,
+    text: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<String, String> {
+    if use_google {
+        if let Ok(result) = send_guarded(client, breakers, policy, Provider::Google, text, source_lang, target_lang).await {
+            return Ok(result);
         }
-    } else {
-        // Use free LibreTranslate
-        translate_text_libre(text, &source_lang, target_lang).await?
-    };
-    
-    Ok(translated)
+    }
+    send_guarded(client, breakers, policy, Provider::Libre, text, source_lang, target_lang).await
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Replays scripted responses per provider and counts the requests it gets
+    #[derive(Default)]
+    struct MockClient {
+        responses: RefCell<Vec<(Provider, Result<String, RequestError>)>>,
+        calls: RefCell<Vec<Provider>>,
+    }
+
+    impl MockClient {
+        fn respond(&self, provider: Provider, result: Result<&str, bool>) {
+            let result = result
+                .map(str::to_string)
+                .map_err(|transient| RequestError { message: "boom".to_string(), transient });
+            self.responses.borrow_mut().push((provider, result));
+        }
+
+        fn calls(&self, provider: Provider) -> usize {
+            self.calls.borrow().iter().filter(|p| **p == provider).count()
+        }
+    }
+
+    impl TranslationClient for MockClient {
+        async fn send(&self, provider: Provider, _text: &str, _source: &str, _target: &str) -> Result<String, RequestError> {
+            self.calls.borrow_mut().push(provider);
+            let mut responses = self.responses.borrow_mut();
+            let index = responses.iter().position(|(p, _)| *p == provider).expect("unexpected request");
+            responses.remove(index).1
+        }
+    }
+
+    const NO_DELAY: RetryPolicy = RetryPolicy { max_retries: 2, base_delay: Duration::ZERO };
+
+    #[tokio::test]
+    async fn test_retries_only_transient_failures() {
+        let client = MockClient::default();
+        client.respond(Provider::Libre, Err(true));
+        client.respond(Provider::Libre, Err(true));
+        client.respond(Provider::Libre, Ok("merhaba"));
+        assert_eq!(send_with_retry(&client, NO_DELAY, Provider::Libre, "hello", "en", "tr").await.unwrap(), "merhaba");
+        assert_eq!(client.calls(Provider::Libre), 3);
+
+        // Two retries at most
+        let client = MockClient::default();
+        for _ in 0..3 {
+            client.respond(Provider::Libre, Err(true));
+        }
+        assert!(send_with_retry(&client, NO_DELAY, Provider::Libre, "hello", "en", "tr").await.is_err());
+        assert_eq!(client.calls(Provider::Libre), 3);
+
+        // A 4xx isn't retried
+        let client = MockClient::default();
+        client.respond(Provider::Libre, Err(false));
+        assert!(send_with_retry(&client, NO_DELAY, Provider::Libre, "hello", "en", "tr").await.is_err());
+        assert_eq!(client.calls(Provider::Libre), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let breakers = TranslationBreakers::default();
+        let client = MockClient::default();
+        let no_retry = RetryPolicy { max_retries: 0, ..NO_DELAY };
+
+        for _ in 0..BREAKER_FAILURE_THRESHOLD {
+            assert_eq!(breakers.libre.status(now()).state, "closed");
+            client.respond(Provider::Libre, Err(true));
+            assert!(send_guarded(&client, &breakers, no_retry, Provider::Libre, "hello", "en", "tr").await.is_err());
+        }
+        assert_eq!(breakers.libre.status(now()).state, "open");
+
+        // Open: short-circuited without a request
+        let error = translate_with(&client, &breakers, no_retry, false, "hello", "en", "tr").await.unwrap_err();
+        assert_eq!(error, TRANSLATION_UNAVAILABLE_ERROR);
+        assert_eq!(client.calls(Provider::Libre), BREAKER_FAILURE_THRESHOLD as usize);
+
+        // After the cooldown one trial call goes through; a failure reopens it straight away
+        let later = now() + BREAKER_COOLDOWN_SECS + 1;
+        assert_eq!(breakers.libre.status(later).state, "half_open");
+        assert!(breakers.libre.check(later).is_ok());
+        breakers.libre.record_failure(later);
+        assert_eq!(breakers.libre.status(later).state, "open");
+
+        // A success resets it
+        breakers.libre.record_success();
+        let status = breakers.libre.status(now());
+        assert_eq!((status.state.as_str(), status.consecutive_failures, status.open_until), ("closed", 0, None));
+
+        // Google's breaker is separate: while it's open, LibreTranslate is used directly
+        for _ in 0..BREAKER_FAILURE_THRESHOLD {
+            breakers.google.record_failure(now());
+        }
+        client.respond(Provider::Libre, Ok("merhaba"));
+        let calls_before = client.calls(Provider::Google);
+        assert_eq!(translate_with(&client, &breakers, no_retry, true, "hello", "en", "tr").await.unwrap(), "merhaba");
+        assert_eq!(client.calls(Provider::Google), calls_before);
+    }
+
+    #[test]
+    fn test_imported_overrides_win_over_machine_translations() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks (id TEXT PRIMARY KEY);").unwrap();
+        conn.execute_batch(include_str!("../../migrations/0009_add_translations.sql")).unwrap();
+        save_translation(&conn, "Buy milk", "en", "de", "Milch kaufen", "title", None, true).unwrap();
+        save_translation(&conn, "Call mom", "en", "de", "Mama rufen", "title", None, false).unwrap();
+        
+        let exported = get_translation_overrides(&conn).unwrap().overrides;
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].translated_text, "Milch kaufen");
+        
+        let overrides = vec![
+            TranslationOverride {
+                source_text: "Call mom".to_string(),
+                source_lang: "en".to_string(),
+                target_lang: "de".to_string(),
+                field_type: "title".to_string(),
+                translated_text: "Mama anrufen".to_string(),
+            },
+            TranslationOverride { source_text: "Water plants".to_string(), translated_text: "Pflanzen gießen".to_string(), ..exported[0].clone() },
+            exported[0].clone(),
+        ];
+        let summary = import_translation_overrides(&conn, &overrides).unwrap();
+        assert_eq!((summary.added, summary.updated, summary.unchanged), (1, 1, 1));
+        
+        let cached = |text: &str| get_cached_translation(&conn, &hash_text(text), "en", "de", "title").unwrap();
+        assert_eq!(cached("Call mom").as_deref(), Some("Mama anrufen"));
+        assert_eq!(cached("Water plants").as_deref(), Some("Pflanzen gießen"));
+        // The corrected row is a user edit now and is exported from here on
+        assert_eq!(get_translation_overrides(&conn).unwrap().overrides.len(), 3);
+        
+        let invalid = TranslationOverride { field_type: "notes".to_string(), ..exported[0].clone() };
+        assert!(import_translation_overrides(&conn, &[invalid]).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use rusqlite::types::Value;
+use rusqlite::params_from_iter;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Undo entries kept per session; the oldest is dropped beyond this
+pub const MAX_UNDO_ENTRIES: usize = 20;
+
+// Tables whose rows belong to a task and go with it on delete, with the column pointing at the task
+const TASK_CHILD_TABLES: &[(&str, &str)] = &[
+    ("subtasks", "task_id"),
+    ("attachments", "task_id"),
+    ("task_tags", "task_id"),
+    ("notification_schedule", "task_id"),
+    ("due_date_history", "task_id"),
+    ("activity_log", "task_id"),
+    ("translations", "task_id"),
+    ("task_relationships", "task_id_1"),
+    ("task_relationships", "task_id_2"),
+];
+
+// Tables that keep their rows when a task is deleted and only lose the link to it
+const TASK_LINKED_TABLES: &[(&str, &str)] = &[
+    ("pomodoro_sessions", "task_id"),
+    ("xp_history", "task_id"),
+];
+
+/// Rows of one table as they were before a delete
+#[derive(Debug, Clone)]
+struct TableRows {
+    table: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    /// Only `id` and one column were captured: restoring sets that column on rows that still
+    /// exist and leaves the rest of the row as it is now
+    column_only: bool,
+}
+
+/// A destructive operation and the rows needed to reverse it. Rows are captured before the
+/// delete, parents before children, and restored in the same order.
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub description: String,
+    tables: Vec<TableRows>,
+}
+
+impl UndoEntry {
+    pub fn new(description: impl Into<String>) -> Self {
+        UndoEntry {
+            description: description.into(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// Remember the rows of `table` matching `condition` (with `?1` bound to `id`).
+    /// Tables missing from an older schema are skipped.
+    pub fn capture(&mut self, conn: &rusqlite::Connection, table: &str, condition: &str, id: &str) -> Result<(), String> {
+        self.capture_select(conn, table, "*", condition, id, false)
+    }
+
+    /// Remember only `column` of the rows of `table` matching `condition`, for rows the delete
+    /// keeps but unlinks (e.g. a deleted project's tasks). Undo puts back just that column.
+    pub fn capture_column(
+        &mut self,
+        conn: &rusqlite::Connection,
+        table: &str,
+        column: &str,
+        condition: &str,
+        id: &str,
+    ) -> Result<(), String> {
+        self.capture_select(conn, table, &format!("id, {}", column), condition, id, true)
+    }
+
+    fn capture_select(
+        &mut self,
+        conn: &rusqlite::Connection,
+        table: &str,
+        select: &str,
+        condition: &str,
+        id: &str,
+        column_only: bool,
+    ) -> Result<(), String> {
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Query error: {}", e))?;
+        if !exists {
+            return Ok(());
+        }
+
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM {} WHERE {}", select, table, condition))
+            .map_err(|e| format!("Query error: {}", e))?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|name| name.to_string()).collect();
+        let rows = stmt
+            .query_map([id], |row| (0..columns.len()).map(|i| row.get::<_, Value>(i)).collect())
+            .map_err(|e| format!("Query execution error: {}", e))?
+            .collect::<Result<Vec<Vec<Value>>, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+
+        if !rows.is_empty() {
+            self.tables.push(TableRows {
+                table: table.to_string(),
+                columns,
+                rows,
+                column_only,
+            });
+        }
+        Ok(())
+    }
+
+    /// Remember the tasks matching `condition` together with everything that belongs to them
+    pub fn capture_tasks(&mut self, conn: &rusqlite::Connection, condition: &str, id: &str) -> Result<(), String> {
+        self.capture(conn, "tasks", condition, id)?;
+        for (table, column) in TASK_CHILD_TABLES {
+            self.capture(
+                conn,
+                table,
+                &format!("{} IN (SELECT id FROM tasks WHERE {})", column, condition),
+                id,
+            )?;
+        }
+        for (table, column) in TASK_LINKED_TABLES {
+            self.capture_column(
+                conn,
+                table,
+                column,
+                &format!("{} IN (SELECT id FROM tasks WHERE {})", column, condition),
+                id,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Put the captured rows back. Rows that still exist (for example tasks whose project_id was
+/// cleared) are overwritten in place rather than replaced, so nothing cascades.
+pub fn restore(conn: &rusqlite::Connection, entry: &UndoEntry) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut tag_ids = Vec::new();
+    for table in &entry.tables {
+        if table.column_only {
+            let sql = format!("UPDATE {} SET {} = ?2 WHERE id = ?1", table.table, table.columns[1]);
+            let mut stmt = tx.prepare(&sql).map_err(|e| format!("Query error: {}", e))?;
+            for row in &table.rows {
+                stmt.execute(params_from_iter(row.iter()))
+                    .map_err(|e| format!("Failed to restore {}: {}", table.table, e))?;
+            }
+            continue;
+        }
+
+        let placeholders = (1..=table.columns.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+        let assignments = table
+            .columns
+            .iter()
+            .map(|column| format!("{} = excluded.{}", column, column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT DO UPDATE SET {}",
+            table.table,
+            table.columns.join(", "),
+            placeholders,
+            assignments
+        );
+        let mut stmt = tx.prepare(&sql).map_err(|e| format!("Query error: {}", e))?;
+        for row in &table.rows {
+            stmt.execute(params_from_iter(row.iter()))
+                .map_err(|e| format!("Failed to restore {}: {}", table.table, e))?;
+        }
+
+        if table.table == "task_tags" {
+            if let Some(index) = table.columns.iter().position(|column| column == "tag_id") {
+                tag_ids.extend(table.rows.iter().map(|row| row[index].clone()));
+            }
+        }
+    }
+
+    // Restored tag links count towards usage again
+    for tag_id in tag_ids {
+        tx.execute(
+            "UPDATE tags SET usage_count = (SELECT COUNT(*) FROM task_tags WHERE tag_id = tags.id) WHERE id = ?1",
+            [tag_id],
+        )
+        .map_err(|e| format!("Failed to update tag usage count: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
+
+/// Recent destructive operations, newest last. Held as managed state, so it only lasts for
+/// the session.
+#[derive(Default)]
+pub struct UndoJournal(Mutex<VecDeque<UndoEntry>>);
+
+impl UndoJournal {
+    pub fn record(&self, entry: UndoEntry) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.push_back(entry);
+            while entries.len() > MAX_UNDO_ENTRIES {
+                entries.pop_front();
+            }
+        }
+    }
+
+    /// Restore the most recent operation and return its description, or None if there is
+    /// nothing to undo. An entry that fails to restore is dropped, so it can't block the ones
+    /// behind it.
+    pub fn undo_last(&self, conn: &rusqlite::Connection) -> Result<Option<String>, String> {
+        let mut entries = self.0.lock().map_err(|e| format!("Undo journal lock error: {}", e))?;
+        let entry = match entries.pop_back() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        restore(conn, &entry).map_err(|e| format!("Could not undo \"{}\": {}", entry.description, e))?;
+        Ok(Some(entry.description))
+    }
+
+    /// Forget every entry, for when the rows they would restore into are gone
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn setup_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+            CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, title TEXT NOT NULL, project_id TEXT REFERENCES projects(id) ON DELETE SET NULL);
+            CREATE TABLE subtasks (id TEXT PRIMARY KEY, task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE, title TEXT NOT NULL);
+            CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT NOT NULL, usage_count INTEGER DEFAULT 0);
+            CREATE TABLE task_tags (id TEXT PRIMARY KEY, task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE, tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE);
+            CREATE TABLE pomodoro_sessions (id TEXT PRIMARY KEY, task_id TEXT REFERENCES tasks(id) ON DELETE SET NULL, duration_seconds INTEGER);
+            INSERT INTO projects VALUES ('p1', 'Garden');
+            INSERT INTO tasks VALUES ('t1', 'Plant bulbs', 'p1'), ('t2', 'Mow lawn', 'p1');
+            INSERT INTO subtasks VALUES ('s1', 't1', 'Buy tulips');
+            INSERT INTO tags VALUES ('g1', 'outdoors', 1);
+            INSERT INTO task_tags VALUES ('tt1', 't1', 'g1');
+            INSERT INTO pomodoro_sessions VALUES ('ps1', 't1', 1500);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn count(conn: &rusqlite::Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_undo_task_delete_restores_children() {
+        let conn = setup_conn();
+        let journal = UndoJournal::default();
+
+        let mut entry = UndoEntry::new("Deleted task \"Plant bulbs\"");
+        entry.capture_tasks(&conn, "id = ?1", "t1").unwrap();
+        conn.execute("DELETE FROM tasks WHERE id = 't1'", []).unwrap();
+        conn.execute("UPDATE tags SET usage_count = 0 WHERE id = 'g1'", []).unwrap();
+        journal.record(entry);
+
+        assert_eq!(journal.undo_last(&conn).unwrap().as_deref(), Some("Deleted task \"Plant bulbs\""));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM subtasks WHERE task_id = 't1'"), 1);
+        assert_eq!(count(&conn, "SELECT usage_count FROM tags WHERE id = 'g1'"), 1);
+        // Sessions outlive the task and only get their link back
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM pomodoro_sessions WHERE task_id = 't1'"), 1);
+        assert_eq!(journal.undo_last(&conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_undo_project_delete_reattaches_tasks() {
+        let conn = setup_conn();
+        let journal = UndoJournal::default();
+
+        let mut entry = UndoEntry::new("Deleted project \"Garden\"");
+        entry.capture(&conn, "projects", "id = ?1", "p1").unwrap();
+        entry.capture_column(&conn, "tasks", "project_id", "project_id = ?1", "p1").unwrap();
+        conn.execute("DELETE FROM projects WHERE id = 'p1'", params![]).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM tasks WHERE project_id IS NULL"), 2);
+        // Edited after the delete: undo keeps the edit
+        conn.execute("UPDATE tasks SET title = 'Plant tulips' WHERE id = 't1'", params![]).unwrap();
+        journal.record(entry);
+
+        journal.undo_last(&conn).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM tasks WHERE project_id = 'p1'"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM tasks WHERE title = 'Plant tulips'"), 1);
+        // Overwriting the existing task rows must not cascade into their children
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM subtasks"), 1);
+    }
+
+    #[test]
+    fn test_journal_keeps_latest_entries() {
+        let journal = UndoJournal::default();
+        let conn = setup_conn();
+        for i in 0..MAX_UNDO_ENTRIES + 5 {
+            journal.record(UndoEntry::new(format!("operation {}", i)));
+        }
+        let mut undone = 0;
+        while let Some(description) = journal.undo_last(&conn).unwrap() {
+            if undone == 0 {
+                assert_eq!(description, format!("operation {}", MAX_UNDO_ENTRIES + 4));
+            }
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO_ENTRIES);
+
+        journal.record(UndoEntry::new("operation after".to_string()));
+        journal.clear();
+        assert_eq!(journal.undo_last(&conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_undo_does_not_block_older_entries() {
+        let conn = setup_conn();
+        let journal = UndoJournal::default();
+        journal.record(UndoEntry::new("older"));
+
+        // The subtask's task is gone for good, so restoring it breaks the foreign key
+        let mut broken = UndoEntry::new("broken");
+        broken.capture(&conn, "subtasks", "id = ?1", "s1").unwrap();
+        conn.execute("DELETE FROM tasks WHERE id = 't1'", params![]).unwrap();
+        journal.record(broken);
+
+        assert!(journal.undo_last(&conn).is_err());
+        assert_eq!(journal.undo_last(&conn).unwrap().as_deref(), Some("older"));
+    }
+}
@@ -0,0 +1,169 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacationPeriod {
+    pub id: String,
+    pub start_date: i64,
+    pub end_date: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacationStatus {
+    pub on_vacation: bool,
+    pub current: Option<VacationPeriod>,
+    pub next: Option<VacationPeriod>,
+}
+
+// Round down to the start of the (UTC) day, matching update_streak_internal
+fn day_start(timestamp: i64) -> i64 {
+    timestamp.div_euclid(DAY_SECONDS) * DAY_SECONDS
+}
+
+fn map_period(row: &rusqlite::Row) -> rusqlite::Result<VacationPeriod> {
+    Ok(VacationPeriod {
+        id: row.get(0)?,
+        start_date: row.get(1)?,
+        end_date: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
+/// Record a vacation covering every day from `start` to `end` (inclusive, any time within those days).
+/// Ranges that end in the past or overlap an existing vacation are rejected.
+pub fn add_vacation_period(
+    conn: &rusqlite::Connection,
+    start: i64,
+    end: i64,
+    now: i64,
+) -> Result<VacationPeriod, String> {
+    let start_date = day_start(start);
+    let end_date = day_start(end);
+
+    if end_date < start_date {
+        return Err("Vacation end must not be before its start".to_string());
+    }
+    if end_date < day_start(now) {
+        return Err("Vacation cannot be entirely in the past".to_string());
+    }
+
+    let overlapping: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM vacation_periods WHERE start_date <= ?2 AND end_date >= ?1",
+            params![start_date, end_date],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+    if overlapping > 0 {
+        return Err("Vacation overlaps an existing vacation".to_string());
+    }
+
+    let period = VacationPeriod {
+        id: uuid::Uuid::new_v4().to_string(),
+        start_date,
+        end_date,
+        created_at: now,
+    };
+    conn.execute(
+        "INSERT INTO vacation_periods (id, start_date, end_date, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![period.id, period.start_date, period.end_date, period.created_at],
+    )
+    .map_err(|e| format!("Failed to save vacation: {}", e))?;
+
+    Ok(period)
+}
+
+pub fn get_vacation_periods(conn: &rusqlite::Connection) -> Result<Vec<VacationPeriod>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, start_date, end_date, created_at FROM vacation_periods ORDER BY start_date")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt
+        .query_map([], map_period)
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut periods = Vec::new();
+    for row in rows {
+        periods.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(periods)
+}
+
+/// Whether the day containing `timestamp` falls inside a vacation
+pub fn is_vacation_day(conn: &rusqlite::Connection, timestamp: i64) -> bool {
+    let day = day_start(timestamp);
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM vacation_periods WHERE start_date <= ?1 AND end_date >= ?1)",
+        params![day],
+        |row| row.get(0),
+    )
+    .unwrap_or(false)
+}
+
+/// Whether every day strictly between the two day starts is a vacation day.
+/// Used by streaks so a vacation gap counts as if no day was missed.
+pub fn gap_is_vacation(conn: &rusqlite::Connection, from_day: i64, to_day: i64) -> bool {
+    let mut day = day_start(from_day) + DAY_SECONDS;
+    while day < day_start(to_day) {
+        if !is_vacation_day(conn, day) {
+            return false;
+        }
+        day += DAY_SECONDS;
+    }
+    true
+}
+
+pub fn get_vacation_status(conn: &rusqlite::Connection, now: i64) -> Result<VacationStatus, String> {
+    let today = day_start(now);
+    let periods = get_vacation_periods(conn)?;
+
+    let current = periods
+        .iter()
+        .find(|p| p.start_date <= today && p.end_date >= today)
+        .cloned();
+    let next = periods.into_iter().find(|p| p.start_date > today);
+
+    Ok(VacationStatus {
+        on_vacation: current.is_some(),
+        current,
+        next,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("../../migrations/0020_add_vacation_periods.sql")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_vacation_validation_and_gaps() {
+        let conn = setup_test_conn();
+        let today = 1000 * DAY_SECONDS;
+
+        assert!(add_vacation_period(&conn, today - 5 * DAY_SECONDS, today - 2 * DAY_SECONDS, today).is_err());
+        assert!(add_vacation_period(&conn, today + 3 * DAY_SECONDS, today + DAY_SECONDS, today).is_err());
+
+        let period = add_vacation_period(&conn, today + 3600, today + 4 * DAY_SECONDS + 3600, today).unwrap();
+        assert_eq!((period.start_date, period.end_date), (today, today + 4 * DAY_SECONDS));
+        assert!(add_vacation_period(&conn, today + 4 * DAY_SECONDS, today + 6 * DAY_SECONDS, today).is_err());
+        assert!(add_vacation_period(&conn, today + 5 * DAY_SECONDS, today + 6 * DAY_SECONDS, today).is_ok());
+
+        assert!(is_vacation_day(&conn, today + 2 * DAY_SECONDS + 100));
+        assert!(!is_vacation_day(&conn, today - 1));
+        // Last completion the day before the vacation, next one the day after both vacations
+        assert!(gap_is_vacation(&conn, today - DAY_SECONDS, today + 7 * DAY_SECONDS));
+        assert!(!gap_is_vacation(&conn, today - 2 * DAY_SECONDS, today + 7 * DAY_SECONDS));
+
+        let status = get_vacation_status(&conn, today + DAY_SECONDS).unwrap();
+        assert!(status.on_vacation);
+        assert_eq!(status.next.map(|p| p.start_date), Some(today + 5 * DAY_SECONDS));
+        assert_eq!(get_vacation_periods(&conn).unwrap().len(), 2);
+    }
+}
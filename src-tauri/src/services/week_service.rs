@@ -0,0 +1,105 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// Setting holding the first day of the week: 0 = Sunday, 1 = Monday (default), ... 6 = Saturday
+pub const WEEK_STARTS_ON_SETTING: &str = "week_starts_on";
+pub const DEFAULT_WEEK_STARTS_ON: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeekBounds {
+    pub week_starts_on: u32,
+    /// Local midnight on the first day of the week
+    pub week_start: i64,
+    /// Local midnight on the first day of the following week (exclusive)
+    pub week_end: i64,
+}
+
+pub fn load_week_starts_on(conn: &rusqlite::Connection) -> u32 {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![WEEK_STARTS_ON_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|v| v.trim().parse::<u32>().ok())
+    .filter(|day| *day <= 6)
+    .unwrap_or(DEFAULT_WEEK_STARTS_ON)
+}
+
+/// First day of the week containing `date`
+pub fn week_start_date(date: NaiveDate, week_starts_on: u32) -> NaiveDate {
+    let offset = (date.weekday().num_days_from_sunday() + 7 - week_starts_on % 7) % 7;
+    date - Duration::days(offset as i64)
+}
+
+pub(crate) fn local_midnight(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        // Midnight skipped by a DST change; close enough for grouping
+        .unwrap_or_else(|| midnight.and_utc().timestamp())
+}
+
+/// Local-time bounds of the week containing `timestamp`. The end is computed from the calendar,
+/// so weeks spanning a DST change are not exactly seven days of seconds.
+pub fn week_bounds(timestamp: i64, week_starts_on: u32) -> WeekBounds {
+    let date = Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+        .unwrap_or_else(|| Local::now().date_naive());
+    let start = week_start_date(date, week_starts_on);
+
+    WeekBounds {
+        week_starts_on: week_starts_on % 7,
+        week_start: local_midnight(start),
+        week_end: local_midnight(start + Duration::days(7)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_monday_start_across_month_boundary() {
+        // Wed 2024-05-01 belongs to the week starting Mon 2024-04-29
+        assert_eq!(week_start_date(date(2024, 5, 1), 1), date(2024, 4, 29));
+        assert_eq!(week_start_date(date(2024, 4, 29), 1), date(2024, 4, 29));
+        // Sunday is the last day of a Monday week
+        assert_eq!(week_start_date(date(2024, 5, 5), 1), date(2024, 4, 29));
+        assert_eq!(week_start_date(date(2024, 5, 6), 1), date(2024, 5, 6));
+    }
+
+    #[test]
+    fn test_sunday_start_across_month_boundary() {
+        // Sat 2024-06-01 belongs to the week starting Sun 2024-05-26
+        assert_eq!(week_start_date(date(2024, 6, 1), 0), date(2024, 5, 26));
+        assert_eq!(week_start_date(date(2024, 6, 2), 0), date(2024, 6, 2));
+        assert_eq!(week_start_date(date(2024, 5, 27), 0), date(2024, 5, 26));
+    }
+
+    #[test]
+    fn test_week_bounds_and_setting() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);").unwrap();
+        assert_eq!(load_week_starts_on(&conn), DEFAULT_WEEK_STARTS_ON);
+        conn.execute("INSERT INTO settings (key, value) VALUES ('week_starts_on', '9')", []).unwrap();
+        assert_eq!(load_week_starts_on(&conn), DEFAULT_WEEK_STARTS_ON);
+        conn.execute("UPDATE settings SET value = '0' WHERE key = 'week_starts_on'", []).unwrap();
+        assert_eq!(load_week_starts_on(&conn), 0);
+
+        let noon = local_midnight(date(2024, 6, 1)) + 12 * 3600;
+        let bounds = week_bounds(noon, 0);
+        assert_eq!(bounds.week_start, local_midnight(date(2024, 5, 26)));
+        assert_eq!(bounds.week_end, local_midnight(date(2024, 6, 2)));
+        assert!(bounds.week_start <= noon && noon < bounds.week_end);
+    }
+}
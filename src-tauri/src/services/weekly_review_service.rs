@@ -0,0 +1,448 @@
+use chrono::{Local, TimeZone};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+/// Tasks waiting on someone for at least this many days are listed for a nudge
+pub const LONG_WAITING_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectCompletion {
+    pub project_id: Option<String>,
+    pub project_name: Option<String>,
+    pub completed: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpcomingDeadline {
+    pub task_id: String,
+    pub title: String,
+    pub due_date: i64,
+    pub priority: String,
+    pub project_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionNote {
+    pub task_id: String,
+    pub title: String,
+    pub note: String,
+    pub completed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitingItem {
+    pub task_id: String,
+    pub title: String,
+    pub waiting_on: String,
+    pub waiting_since: i64,
+    pub age_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyReview {
+    pub week_start: i64,
+    pub week_end: i64,
+    pub tasks_completed: i64,
+    pub tasks_created: i64,
+    pub completions_by_project: Vec<ProjectCompletion>,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub xp_earned: i64,
+    pub total_xp: i64,
+    pub current_level: i32,
+    pub pomodoro_sessions: i64,
+    pub pomodoro_minutes: i64,
+    pub upcoming_deadlines: Vec<UpcomingDeadline>,
+    pub completion_notes: Vec<CompletionNote>,
+    /// Open tasks that have been waiting on someone for LONG_WAITING_DAYS or more, oldest first
+    pub waiting_items: Vec<WaitingItem>,
+}
+
+// Helper function to get current timestamp
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Compute the weekly review for the week containing `timestamp`, with weeks starting on the
+/// configured `week_starts_on` day
+pub fn get_weekly_review(conn: &rusqlite::Connection, timestamp: i64) -> Result<WeeklyReview, String> {
+    let bounds = super::week_service::week_bounds(timestamp, super::week_service::load_week_starts_on(conn));
+    let (week_start, week_end) = (bounds.week_start, bounds.week_end);
+
+    let tasks_completed: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM tasks WHERE archived = 0 AND completed_at >= ?1 AND completed_at < ?2",
+            params![week_start, week_end],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let tasks_created: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM tasks WHERE archived = 0 AND created_at >= ?1 AND created_at < ?2",
+            params![week_start, week_end],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.project_id, p.name, COUNT(*) as completed
+            FROM tasks t
+            LEFT JOIN projects p ON t.project_id = p.id
+            WHERE t.archived = 0 AND t.completed_at >= ?1 AND t.completed_at < ?2
+            GROUP BY t.project_id, p.name
+            ORDER BY completed DESC",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![week_start, week_end], |row| {
+            Ok(ProjectCompletion {
+                project_id: row.get(0)?,
+                project_name: row.get(1)?,
+                completed: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut completions_by_project = Vec::new();
+    for row in rows {
+        completions_by_project.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    let (current_streak, longest_streak, total_xp, current_level): (i32, i32, i64, i32) = conn
+        .query_row(
+            "SELECT current_streak, longest_streak, total_xp, current_level FROM user_progress WHERE id = 'default'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .unwrap_or((0, 0, 0, 1));
+
+    let xp_earned: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(xp_amount), 0) FROM xp_history WHERE created_at >= ?1 AND created_at < ?2",
+            params![week_start, week_end],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let (pomodoro_sessions, pomodoro_seconds): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(duration_seconds), 0) FROM pomodoro_sessions
+            WHERE mode = 'pomodoro' AND was_completed = 1 AND completed_at >= ?1 AND completed_at < ?2",
+            params![week_start, week_end],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap_or((0, 0));
+
+    // Upcoming: open tasks due from now (or the week start, for future weeks) until a week after the reviewed week
+    let upcoming_from = now().max(week_start);
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.title, t.due_at, t.priority, p.name
+            FROM tasks t
+            LEFT JOIN projects p ON t.project_id = p.id
+            WHERE t.completed_at IS NULL AND t.archived = 0 AND t.due_at >= ?1 AND t.due_at < ?2
+            ORDER BY t.due_at ASC
+            LIMIT 20",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![upcoming_from, week_end + WEEK_SECONDS], |row| {
+            Ok(UpcomingDeadline {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                due_date: row.get(2)?,
+                priority: row.get(3)?,
+                project_name: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut upcoming_deadlines = Vec::new();
+    for row in rows {
+        upcoming_deadlines.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, completion_note, completed_at FROM tasks
+            WHERE completion_note IS NOT NULL AND archived = 0 AND completed_at >= ?1 AND completed_at < ?2
+            ORDER BY completed_at ASC",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![week_start, week_end], |row| {
+            Ok(CompletionNote {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                note: row.get(2)?,
+                completed_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut completion_notes = Vec::new();
+    for row in rows {
+        completion_notes.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    // Ages count up to now, or to the end of a past week
+    let waiting_as_of = now().min(week_end);
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, waiting_on, waiting_since FROM tasks
+            WHERE completed_at IS NULL AND archived = 0 AND waiting_since IS NOT NULL AND waiting_since <= ?1
+            ORDER BY waiting_since ASC",
+        )
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![waiting_as_of - LONG_WAITING_DAYS * DAY_SECONDS], |row| {
+            let waiting_since: i64 = row.get(3)?;
+            Ok(WaitingItem {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                waiting_on: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                waiting_since,
+                age_days: (waiting_as_of - waiting_since) / DAY_SECONDS,
+            })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut waiting_items = Vec::new();
+    for row in rows {
+        waiting_items.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+
+    Ok(WeeklyReview {
+        week_start,
+        week_end,
+        tasks_completed,
+        tasks_created,
+        completions_by_project,
+        current_streak,
+        longest_streak,
+        xp_earned,
+        total_xp,
+        current_level,
+        pomodoro_sessions,
+        pomodoro_minutes: pomodoro_seconds / 60,
+        upcoming_deadlines,
+        completion_notes,
+        waiting_items,
+    })
+}
+
+pub(crate) fn escape_html(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_date(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.format("%a, %b %-d").to_string())
+        .unwrap_or_default()
+}
+
+const TD_STYLE: &str = "padding:6px 8px;border-bottom:1px solid #e5e7eb;";
+const STAT_STYLE: &str = "display:inline-block;min-width:120px;margin:0 12px 12px 0;padding:12px;border-radius:8px;background:#f3f4f6;";
+
+fn stat_block(label: &str, value: &str) -> String {
+    format!(
+        "<div style=\"{}\"><div style=\"font-size:12px;color:#6b7280;\">{}</div><div style=\"font-size:20px;font-weight:bold;color:#111827;\">{}</div></div>",
+        STAT_STYLE,
+        escape_html(label),
+        escape_html(value)
+    )
+}
+
+/// Render a weekly review as a self-contained HTML document.
+/// All styling is inline so the markup survives being pasted into email clients.
+pub fn render_weekly_summary_html(review: &WeeklyReview) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Weekly Summary</title></head>");
+    html.push_str("<body style=\"margin:0;padding:24px;font-family:Arial,Helvetica,sans-serif;color:#111827;background:#ffffff;\">");
+    html.push_str("<div style=\"max-width:640px;margin:0 auto;\">");
+
+    html.push_str(&format!(
+        "<h1 style=\"font-size:22px;margin:0 0 4px 0;\">Weekly Summary</h1><p style=\"margin:0 0 20px 0;color:#6b7280;\">{} &ndash; {}</p>",
+        escape_html(&format_date(review.week_start)),
+        escape_html(&format_date(review.week_end - 1))
+    ));
+
+    html.push_str("<div>");
+    html.push_str(&stat_block("Tasks completed", &review.tasks_completed.to_string()));
+    html.push_str(&stat_block("Tasks created", &review.tasks_created.to_string()));
+    html.push_str(&stat_block("XP earned", &format!("+{}", review.xp_earned)));
+    html.push_str(&stat_block("Level", &review.current_level.to_string()));
+    html.push_str(&stat_block("Streak", &format!("{} days (best {})", review.current_streak, review.longest_streak)));
+    html.push_str(&stat_block(
+        "Focus time",
+        &format!("{} min in {} sessions", review.pomodoro_minutes, review.pomodoro_sessions),
+    ));
+    html.push_str("</div>");
+
+    html.push_str("<h2 style=\"font-size:16px;margin:20px 0 8px 0;\">Completed by project</h2>");
+    if review.completions_by_project.is_empty() {
+        html.push_str("<p style=\"color:#6b7280;margin:0;\">No tasks completed this week.</p>");
+    } else {
+        html.push_str("<table style=\"border-collapse:collapse;width:100%;font-size:14px;\">");
+        for project in &review.completions_by_project {
+            let name = project.project_name.as_deref().unwrap_or("No project");
+            html.push_str(&format!(
+                "<tr><td style=\"{}\">{}</td><td style=\"{}text-align:right;\">{}</td></tr>",
+                TD_STYLE,
+                escape_html(name),
+                TD_STYLE,
+                project.completed
+            ));
+        }
+        html.push_str("</table>");
+    }
+
+    html.push_str("<h2 style=\"font-size:16px;margin:20px 0 8px 0;\">Upcoming deadlines</h2>");
+    if review.upcoming_deadlines.is_empty() {
+        html.push_str("<p style=\"color:#6b7280;margin:0;\">Nothing due soon.</p>");
+    } else {
+        html.push_str("<table style=\"border-collapse:collapse;width:100%;font-size:14px;\">");
+        for task in &review.upcoming_deadlines {
+            let priority_color = match task.priority.as_str() {
+                "high" => "#dc2626",
+                "low" => "#6b7280",
+                _ => "#d97706",
+            };
+            let project = task
+                .project_name
+                .as_deref()
+                .map(|name| format!(" <span style=\"color:#6b7280;\">&middot; {}</span>", escape_html(name)))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<tr><td style=\"{}\">{}{}</td><td style=\"{}color:{};\">{}</td><td style=\"{}text-align:right;white-space:nowrap;\">{}</td></tr>",
+                TD_STYLE,
+                escape_html(&task.title),
+                project,
+                TD_STYLE,
+                priority_color,
+                escape_html(&task.priority),
+                TD_STYLE,
+                escape_html(&format_date(task.due_date))
+            ));
+        }
+        html.push_str("</table>");
+    }
+
+    if !review.completion_notes.is_empty() {
+        html.push_str("<h2 style=\"font-size:16px;margin:20px 0 8px 0;\">Completion notes</h2>");
+        html.push_str("<table style=\"border-collapse:collapse;width:100%;font-size:14px;\">");
+        for entry in &review.completion_notes {
+            html.push_str(&format!(
+                "<tr><td style=\"{}\">{}</td><td style=\"{}color:#374151;\">{}</td></tr>",
+                TD_STYLE,
+                escape_html(&entry.title),
+                TD_STYLE,
+                escape_html(&entry.note)
+            ));
+        }
+        html.push_str("</table>");
+    }
+
+    if !review.waiting_items.is_empty() {
+        html.push_str("<h2 style=\"font-size:16px;margin:20px 0 8px 0;\">Still waiting</h2>");
+        html.push_str("<table style=\"border-collapse:collapse;width:100%;font-size:14px;\">");
+        for item in &review.waiting_items {
+            html.push_str(&format!(
+                "<tr><td style=\"{}\">{}</td><td style=\"{}color:#374151;\">{}</td><td style=\"{}text-align:right;white-space:nowrap;\">{} days</td></tr>",
+                TD_STYLE,
+                escape_html(&item.title),
+                TD_STYLE,
+                escape_html(&item.waiting_on),
+                TD_STYLE,
+                item.age_days
+            ));
+        }
+        html.push_str("</table>");
+    }
+
+    html.push_str("</div></body></html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_escapes_user_content() {
+        let review = WeeklyReview {
+            week_start: 1_704_067_200,
+            week_end: 1_704_067_200 + WEEK_SECONDS,
+            tasks_completed: 3,
+            tasks_created: 5,
+            completions_by_project: vec![ProjectCompletion {
+                project_id: Some("p1".to_string()),
+                project_name: Some("R&D <core>".to_string()),
+                completed: 3,
+            }],
+            current_streak: 2,
+            longest_streak: 4,
+            xp_earned: 75,
+            total_xp: 400,
+            current_level: 3,
+            pomodoro_sessions: 2,
+            pomodoro_minutes: 50,
+            upcoming_deadlines: vec![UpcomingDeadline {
+                task_id: "t1".to_string(),
+                title: "Fix \"quotes\" & <tags>".to_string(),
+                due_date: 1_704_067_200 + WEEK_SECONDS + 3600,
+                priority: "high".to_string(),
+                project_name: None,
+            }],
+            completion_notes: vec![CompletionNote {
+                task_id: "t2".to_string(),
+                title: "Ship release".to_string(),
+                note: "Rolled out to 10% <first>".to_string(),
+                completed_at: 1_704_067_200 + 3600,
+            }],
+            waiting_items: vec![WaitingItem {
+                task_id: "t3".to_string(),
+                title: "Contract review".to_string(),
+                waiting_on: "Legal <team>".to_string(),
+                waiting_since: 1_704_067_200 - 2 * WEEK_SECONDS,
+                age_days: 14,
+            }],
+        };
+
+        let html = render_weekly_summary_html(&review);
+        assert!(html.contains("R&amp;D &lt;core&gt;"));
+        assert!(html.contains("Fix &quot;quotes&quot; &amp; &lt;tags&gt;"));
+        assert!(!html.contains("<core>"));
+        assert!(html.contains("50 min in 2 sessions"));
+        assert!(html.contains("Rolled out to 10% &lt;first&gt;"));
+        assert!(html.contains("Legal &lt;team&gt;") && html.contains("14 days"));
+        // No stylesheet: everything is inline
+        assert!(!html.contains("<style"));
+    }
+}
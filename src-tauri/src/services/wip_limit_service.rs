@@ -0,0 +1,341 @@
+use crate::commands::BOARD_COLUMNS;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+pub const SCOPE_KINDS: &[&str] = &["global", "project", "tag"];
+
+/// A task's board column: its stored status, or "done"/"todo" from completion when unset
+pub const TASK_STATUS_SQL: &str = "COALESCE(status, CASE WHEN completed_at IS NULL THEN 'todo' ELSE 'done' END)";
+
+/// Error kind of a serialized WipLimitExceeded
+pub const WIP_LIMIT_KIND: &str = "wip_limit";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WipLimit {
+    pub id: String,
+    /// "global", "project" or "tag"
+    pub scope_kind: String,
+    /// Project or tag id; None for global
+    pub scope_id: Option<String>,
+    pub status: String,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WipTask {
+    pub id: String,
+    pub title: String,
+}
+
+/// A limit with the tasks currently counting towards it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WipLimitUsage {
+    #[serde(flatten)]
+    pub limit: WipLimit,
+    pub count: i64,
+    pub tasks: Vec<WipTask>,
+}
+
+/// Returned (serialized) when a status change would go over one or more limits
+#[derive(Debug, Clone, Serialize)]
+pub struct WipLimitExceeded {
+    pub kind: String, // always "wip_limit"
+    pub message: String,
+    pub status: String,
+    pub exceeded: Vec<WipLimitUsage>,
+}
+
+fn limit_from_row(row: &rusqlite::Row) -> rusqlite::Result<WipLimit> {
+    Ok(WipLimit {
+        id: row.get(0)?,
+        scope_kind: row.get(1)?,
+        scope_id: row.get(2)?,
+        status: row.get(3)?,
+        limit: row.get(4)?,
+    })
+}
+
+pub fn list_wip_limits(conn: &rusqlite::Connection) -> Result<Vec<WipLimit>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, scope_kind, scope_id, status, wip_limit FROM wip_limits ORDER BY scope_kind, scope_id, status")
+        .map_err(|e| format!("Query error: {}", e))?;
+    let limits = stmt
+        .query_map([], limit_from_row)
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    Ok(limits)
+}
+
+/// Create the limit for a scope and status, or change the existing one
+pub fn set_wip_limit(
+    conn: &rusqlite::Connection,
+    scope_kind: &str,
+    scope_id: Option<&str>,
+    status: &str,
+    limit: i64,
+    now: i64,
+) -> Result<WipLimit, String> {
+    if !SCOPE_KINDS.contains(&scope_kind) {
+        return Err(format!("Invalid scope kind: {} (expected global, project or tag)", scope_kind));
+    }
+    let scope_id = scope_id.filter(|_| scope_kind != "global");
+    if scope_kind != "global" && scope_id.is_none() {
+        return Err(format!("A {} limit needs a scope id", scope_kind));
+    }
+    if !BOARD_COLUMNS.contains(&status) {
+        return Err(format!("Invalid status: {} (expected {})", status, BOARD_COLUMNS.join(", ")));
+    }
+    if limit < 1 {
+        return Err("WIP limit must be at least 1".to_string());
+    }
+
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM wip_limits WHERE scope_kind = ?1 AND COALESCE(scope_id, '') = COALESCE(?2, '') AND status = ?3",
+            params![scope_kind, scope_id, status],
+            |row| row.get(0),
+        )
+        .ok();
+    let id = match existing {
+        Some(id) => {
+            conn.execute(
+                "UPDATE wip_limits SET wip_limit = ?1, updated_at = ?2 WHERE id = ?3",
+                params![limit, now, id],
+            )
+            .map_err(|e| format!("Failed to update WIP limit: {}", e))?;
+            id
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO wip_limits (id, scope_kind, scope_id, status, wip_limit, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                params![id, scope_kind, scope_id, status, limit, now],
+            )
+            .map_err(|e| format!("Failed to create WIP limit: {}", e))?;
+            id
+        }
+    };
+
+    Ok(WipLimit {
+        id,
+        scope_kind: scope_kind.to_string(),
+        scope_id: scope_id.map(str::to_string),
+        status: status.to_string(),
+        limit,
+    })
+}
+
+pub fn delete_wip_limit(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    let deleted = conn
+        .execute("DELETE FROM wip_limits WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete WIP limit: {}", e))?;
+    if deleted == 0 {
+        return Err(format!("WIP limit not found: {}", id));
+    }
+    Ok(())
+}
+
+// Archived tasks are off the board and don't count
+fn usage(conn: &rusqlite::Connection, limit: WipLimit, excluding: Option<&str>) -> Result<WipLimitUsage, String> {
+    let scope = match limit.scope_kind.as_str() {
+        "project" => "project_id = ?2",
+        "tag" => "id IN (SELECT task_id FROM task_tags WHERE tag_id = ?2)",
+        _ => "?2 IS NULL",
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, title FROM tasks
+             WHERE archived = 0 AND {} = ?1 AND {} AND id != COALESCE(?3, '')
+             ORDER BY updated_at DESC, id",
+            TASK_STATUS_SQL, scope
+        ))
+        .map_err(|e| format!("Query error: {}", e))?;
+    let tasks = stmt
+        .query_map(params![limit.status, limit.scope_id, excluding], |row| {
+            Ok(WipTask { id: row.get(0)?, title: row.get(1)? })
+        })
+        .map_err(|e| format!("Query execution error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Row parsing error: {}", e))?;
+    Ok(WipLimitUsage {
+        limit,
+        count: tasks.len() as i64,
+        tasks,
+    })
+}
+
+/// Limits on `status` that apply to a board showing the project and/or tag (global ones
+/// always apply), with their current counts
+pub fn board_limits(
+    conn: &rusqlite::Connection,
+    status: &str,
+    project_id: Option<&str>,
+    tag_id: Option<&str>,
+) -> Result<Vec<WipLimitUsage>, String> {
+    list_wip_limits(conn)?
+        .into_iter()
+        .filter(|limit| limit.status == status)
+        .filter(|limit| match limit.scope_kind.as_str() {
+            "project" => limit.scope_id.as_deref() == project_id,
+            "tag" => limit.scope_id.as_deref() == tag_id,
+            _ => true,
+        })
+        .map(|limit| usage(conn, limit, None))
+        .collect()
+}
+
+/// Refuse to move `task_id` into `status` if any limit on that status covering the task (by
+/// its project, its tags or globally) is already full. The error is a serialized
+/// WipLimitExceeded listing the tasks filling each limit.
+pub fn check_wip_limits(conn: &rusqlite::Connection, task_id: &str, status: &str) -> Result<(), String> {
+    let (project_id, current): (Option<String>, String) = conn
+        .query_row(
+            &format!("SELECT project_id, {} FROM tasks WHERE id = ?1", TASK_STATUS_SQL),
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| format!("Task not found: {}", task_id))?;
+    if current == status {
+        return Ok(());
+    }
+
+    let tag_ids: Vec<String> = conn
+        .prepare("SELECT tag_id FROM task_tags WHERE task_id = ?1")
+        .and_then(|mut stmt| stmt.query_map(params![task_id], |row| row.get(0))?.collect())
+        .map_err(|e| format!("Query error: {}", e))?;
+
+    let mut exceeded = Vec::new();
+    for limit in list_wip_limits(conn)? {
+        let applies = limit.status == status
+            && match limit.scope_kind.as_str() {
+                "project" => limit.scope_id.is_some() && limit.scope_id == project_id,
+                "tag" => limit.scope_id.as_ref().is_some_and(|tag_id| tag_ids.contains(tag_id)),
+                _ => true,
+            };
+        if !applies {
+            continue;
+        }
+        let usage = usage(conn, limit, Some(task_id))?;
+        if usage.count >= usage.limit.limit {
+            exceeded.push(usage);
+        }
+    }
+    if exceeded.is_empty() {
+        return Ok(());
+    }
+
+    let error = WipLimitExceeded {
+        kind: WIP_LIMIT_KIND.to_string(),
+        message: format!("The {} column is at its WIP limit", status),
+        status: status.to_string(),
+        exceeded,
+    };
+    Err(serde_json::to_string(&error).unwrap_or(error.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                project_id TEXT,
+                completed_at INTEGER,
+                archived INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE task_tags (id TEXT PRIMARY KEY, task_id TEXT NOT NULL, tag_id TEXT NOT NULL);",
+        )
+        .unwrap();
+        conn.execute_batch(include_str!("../../migrations/0040_add_wip_limits.sql")).unwrap();
+        conn
+    }
+
+    fn exceeded(error: &str) -> serde_json::Value {
+        let value: serde_json::Value = serde_json::from_str(error).unwrap();
+        assert_eq!(value["kind"], WIP_LIMIT_KIND);
+        value
+    }
+
+    #[test]
+    fn test_global_limit_blocks_moves_into_a_full_column() {
+        let conn = setup_conn();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, project_id, status) VALUES
+                ('a', 'Alpha', 'p1', 'in_progress'),
+                ('b', 'Beta', 'p2', 'in_progress'),
+                ('c', 'Gamma', 'p1', NULL),
+                ('old', 'Archived', 'p1', 'in_progress');
+            UPDATE tasks SET archived = 1 WHERE id = 'old';",
+        )
+        .unwrap();
+
+        assert!(set_wip_limit(&conn, "global", None, "in_progress", 0, 0).is_err());
+        assert!(set_wip_limit(&conn, "global", None, "doing", 2, 0).is_err());
+        let limit = set_wip_limit(&conn, "global", None, "in_progress", 3, 0).unwrap();
+        assert!(check_wip_limits(&conn, "c", "in_progress").is_ok());
+
+        // Setting it again replaces the limit rather than adding a second one
+        assert_eq!(set_wip_limit(&conn, "global", None, "in_progress", 2, 1).unwrap().id, limit.id);
+        assert_eq!(list_wip_limits(&conn).unwrap().len(), 1);
+
+        let error = exceeded(&check_wip_limits(&conn, "c", "in_progress").unwrap_err());
+        assert_eq!(error["exceeded"][0]["count"], 2);
+        let offending: Vec<&str> = error["exceeded"][0]["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(offending.len(), 2);
+        assert!(offending.contains(&"a") && offending.contains(&"b"));
+
+        // Tasks already in the column can stay, and other columns are unaffected
+        assert!(check_wip_limits(&conn, "a", "in_progress").is_ok());
+        assert!(check_wip_limits(&conn, "c", "done").is_ok());
+
+        let usage = board_limits(&conn, "in_progress", Some("p1"), None).unwrap();
+        assert_eq!((usage.len(), usage[0].count), (1, 2));
+
+        delete_wip_limit(&conn, &limit.id).unwrap();
+        assert!(check_wip_limits(&conn, "c", "in_progress").is_ok());
+        assert!(delete_wip_limit(&conn, &limit.id).is_err());
+    }
+
+    #[test]
+    fn test_tag_limit_only_covers_tagged_tasks() {
+        let conn = setup_conn();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status) VALUES
+                ('bug1', 'Crash on save', 'in_progress'),
+                ('bug2', 'Typo in menu', NULL),
+                ('chore', 'Update deps', NULL),
+                ('closed', 'Old bug', NULL);
+            UPDATE tasks SET completed_at = 100 WHERE id = 'closed';
+            INSERT INTO task_tags (id, task_id, tag_id) VALUES
+                ('tt1', 'bug1', 'bugs'), ('tt2', 'bug2', 'bugs'), ('tt3', 'closed', 'bugs');",
+        )
+        .unwrap();
+
+        assert!(set_wip_limit(&conn, "tag", None, "in_progress", 1, 0).is_err());
+        set_wip_limit(&conn, "tag", Some("bugs"), "in_progress", 1, 0).unwrap();
+
+        let error = exceeded(&check_wip_limits(&conn, "bug2", "in_progress").unwrap_err());
+        assert_eq!(error["exceeded"][0]["scope_id"], "bugs");
+        assert_eq!(error["exceeded"][0]["tasks"][0]["title"], "Crash on save");
+        assert!(check_wip_limits(&conn, "chore", "in_progress").is_ok());
+
+        // A completed task with no stored status counts as done
+        set_wip_limit(&conn, "tag", Some("bugs"), "done", 1, 0).unwrap();
+        assert!(check_wip_limits(&conn, "bug2", "done").is_err());
+
+        assert!(board_limits(&conn, "in_progress", None, None).unwrap().is_empty());
+        assert_eq!(board_limits(&conn, "in_progress", None, Some("bugs")).unwrap()[0].count, 1);
+    }
+}
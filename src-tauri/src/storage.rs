@@ -0,0 +1,443 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether the last probe failed, so the event fires once per outage rather than on every check
+static STORAGE_UNWRITABLE: AtomicBool = AtomicBool::new(false);
+
+/// Why a write to disk failed, with `action` describing what we were doing (e.g. "save the attachment").
+/// Displayed messages are meant to be shown to the user as-is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StorageError {
+    DiskFull { action: String },
+    PermissionDenied { action: String },
+    ReadOnly { action: String },
+    Other { action: String, message: String },
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::DiskFull { action } => write!(
+                f,
+                "Disk full: couldn't {}. Free up some disk space and try again.",
+                action
+            ),
+            StorageError::PermissionDenied { action } => write!(
+                f,
+                "Permission denied: couldn't {}. Check that your user account can write to the app data folder.",
+                action
+            ),
+            StorageError::ReadOnly { action } => write!(
+                f,
+                "Read-only storage: couldn't {}. The app data folder is on a read-only drive.",
+                action
+            ),
+            StorageError::Other { action, message } => write!(f, "Failed to {}: {}", action, message),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<StorageError> for String {
+    fn from(err: StorageError) -> String {
+        err.to_string()
+    }
+}
+
+pub fn from_io(action: &str, err: &io::Error) -> StorageError {
+    let action = action.to_string();
+    match err.kind() {
+        io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => StorageError::DiskFull { action },
+        io::ErrorKind::PermissionDenied => StorageError::PermissionDenied { action },
+        io::ErrorKind::ReadOnlyFilesystem => StorageError::ReadOnly { action },
+        _ => StorageError::Other { action, message: err.to_string() },
+    }
+}
+
+pub fn from_sqlite(action: &str, err: &rusqlite::Error) -> StorageError {
+    let action = action.to_string();
+    match err.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::DiskFull) => StorageError::DiskFull { action },
+        Some(rusqlite::ErrorCode::ReadOnly) => StorageError::ReadOnly { action },
+        Some(rusqlite::ErrorCode::PermissionDenied) | Some(rusqlite::ErrorCode::CannotOpen) => {
+            StorageError::PermissionDenied { action }
+        }
+        _ => StorageError::Other { action, message: err.to_string() },
+    }
+}
+
+/// Classify an error from init_db/migrations, which may wrap either of the above
+pub fn from_anyhow(action: &str, err: &anyhow::Error) -> StorageError {
+    if let Some(io_err) = err.downcast_ref::<io::Error>() {
+        return from_io(action, io_err);
+    }
+    if let Some(sql_err) = err.downcast_ref::<rusqlite::Error>() {
+        return from_sqlite(action, sql_err);
+    }
+    StorageError::Other { action: action.to_string(), message: err.to_string() }
+}
+
+/// Check that `dir` accepts new files by creating, syncing and deleting a small temp file
+pub fn probe_writable(dir: &Path) -> Result<(), StorageError> {
+    let action = "write to the app data folder";
+    let probe = dir.join(format!(".write-probe-{}", uuid::Uuid::new_v4()));
+
+    let result = fs::File::create(&probe).and_then(|mut file| {
+        file.write_all(b"probe")?;
+        file.sync_all()
+    });
+    let _ = fs::remove_file(&probe);
+    result.map_err(|e| from_io(action, &e))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStatus {
+    pub path: String,
+    pub writable: bool,
+    pub error: Option<StorageError>,
+    pub message: Option<String>,
+}
+
+pub fn storage_status(dir: &Path) -> StorageStatus {
+    let error = probe_writable(dir).err();
+    StorageStatus {
+        path: dir.to_string_lossy().to_string(),
+        writable: error.is_none(),
+        message: error.as_ref().map(|e| e.to_string()),
+        error,
+    }
+}
+
+/// Probe the app data folder and, when it stops accepting writes, emit `storage-unwritable`
+/// (with the StorageStatus) and show a notification. Returns whether the folder is writable.
+pub fn check_app_data_writable(app_handle: &tauri::AppHandle) -> bool {
+    use tauri::Manager;
+
+    let dir = match app_handle.path_resolver().app_data_dir() {
+        Some(dir) => dir,
+        None => return false,
+    };
+    let status = storage_status(&dir);
+    let was_unwritable = STORAGE_UNWRITABLE.swap(!status.writable, Ordering::SeqCst);
+
+    if !status.writable && !was_unwritable {
+        let message = status.message.clone().unwrap_or_default();
+        eprintln!("App data folder is not writable: {}", message);
+        let _ = crate::notifications::show_notification("Can't save changes", &message);
+        let _ = app_handle.emit_all("storage-unwritable", status.clone());
+    }
+    status.writable
+}
+
+// Stop walking after this many entries so a huge attachments tree can't stall the command
+const MAX_WALK_ENTRIES: usize = 50_000;
+const TOP_ATTACHMENT_TASKS: usize = 10;
+// Suggest pruning once there are more backups than this
+const KEEP_BACKUPS: u64 = 5;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirUsage {
+    pub path: String,
+    pub bytes: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseUsage {
+    pub db_bytes: u64,
+    pub wal_bytes: u64,
+    pub shm_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAttachmentUsage {
+    pub task_id: String,
+    /// None when the task no longer exists
+    pub title: Option<String>,
+    pub bytes: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttachmentUsage {
+    pub path: String,
+    pub bytes: u64,
+    pub file_count: u64,
+    /// The tasks using the most space, largest first
+    pub top_tasks: Vec<TaskAttachmentUsage>,
+    /// Files in folders of tasks that have been deleted
+    pub orphaned_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupSuggestion {
+    pub id: String,
+    pub description: String,
+    pub reclaimable_bytes: u64,
+    /// Command that performs the cleanup, when there is one
+    pub command: Option<String>,
+    /// Folder to open so the user can clean up by hand
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageBreakdown {
+    pub total_bytes: u64,
+    pub database: DatabaseUsage,
+    pub attachments: AttachmentUsage,
+    pub thumbnails: DirUsage,
+    pub backups: DirUsage,
+    /// JSON exports in the app data folder plus the exports folder
+    pub exports: DirUsage,
+    pub logs: Option<DirUsage>,
+    /// The walk hit its entry limit, so sizes are lower bounds
+    pub truncated: bool,
+    pub suggestions: Vec<CleanupSuggestion>,
+}
+
+struct Walker {
+    entries: usize,
+    truncated: bool,
+}
+
+impl Walker {
+    fn file_size(path: &Path) -> u64 {
+        fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// Total size of the files under `dir`, without following symlinks
+    fn dir_usage(&mut self, dir: &Path) -> DirUsage {
+        let mut usage = DirUsage { path: dir.to_string_lossy().to_string(), ..Default::default() };
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let entries = match fs::read_dir(&current) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                if self.entries >= MAX_WALK_ENTRIES {
+                    self.truncated = true;
+                    return usage;
+                }
+                self.entries += 1;
+                let metadata = match entry.path().symlink_metadata() {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                } else if metadata.is_file() {
+                    usage.bytes += metadata.len();
+                    usage.file_count += 1;
+                }
+            }
+        }
+        usage
+    }
+}
+
+/// Walk the app data folder (and the log folder) and break down what's using space.
+/// `lookup_titles` maps task ids to titles for the attachment folders found; ids it leaves out
+/// are treated as deleted tasks. It's called after the walk, so callers can take the DB lock there.
+pub fn storage_breakdown<F>(app_data_dir: &Path, log_dir: Option<&Path>, lookup_titles: F) -> StorageBreakdown
+where
+    F: FnOnce(&[String]) -> HashMap<String, String>,
+{
+    let mut walker = Walker { entries: 0, truncated: false };
+
+    let database = DatabaseUsage {
+        db_bytes: Walker::file_size(&app_data_dir.join("todo.db")),
+        wal_bytes: Walker::file_size(&app_data_dir.join("todo.db-wal")),
+        shm_bytes: Walker::file_size(&app_data_dir.join("todo.db-shm")),
+    };
+
+    // Attachments live in attachments/<task_id>/...
+    let attachments_dir = app_data_dir.join("attachments");
+    let mut attachments = AttachmentUsage { path: attachments_dir.to_string_lossy().to_string(), ..Default::default() };
+    let mut per_task = Vec::new();
+    if let Ok(entries) = fs::read_dir(&attachments_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let usage = if path.is_dir() {
+                walker.dir_usage(&path)
+            } else {
+                DirUsage { path: String::new(), bytes: Walker::file_size(&path), file_count: 1 }
+            };
+            attachments.bytes += usage.bytes;
+            attachments.file_count += usage.file_count;
+            if path.is_dir() {
+                per_task.push(TaskAttachmentUsage {
+                    task_id: entry.file_name().to_string_lossy().to_string(),
+                    title: None,
+                    bytes: usage.bytes,
+                    file_count: usage.file_count,
+                });
+            }
+        }
+    }
+    let task_ids: Vec<String> = per_task.iter().map(|usage| usage.task_id.clone()).collect();
+    let titles = lookup_titles(&task_ids);
+    for usage in &mut per_task {
+        usage.title = titles.get(&usage.task_id).cloned();
+        if usage.title.is_none() {
+            attachments.orphaned_bytes += usage.bytes;
+        }
+    }
+    per_task.sort_by_key(|usage| std::cmp::Reverse(usage.bytes));
+    per_task.truncate(TOP_ATTACHMENT_TASKS);
+    attachments.top_tasks = per_task;
+
+    let thumbnails = walker.dir_usage(&app_data_dir.join("thumbnails"));
+    let backups = walker.dir_usage(&app_data_dir.join("backups"));
+
+    // export_data writes todo_export_*.json next to todo.db; HTML summaries go to exports/
+    let mut exports = walker.dir_usage(&app_data_dir.join("exports"));
+    if let Ok(entries) = fs::read_dir(app_data_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with("todo_export_") && name.ends_with(".json") {
+                exports.bytes += Walker::file_size(&entry.path());
+                exports.file_count += 1;
+            }
+        }
+    }
+
+    let logs = log_dir.filter(|dir| dir.exists()).map(|dir| walker.dir_usage(dir));
+
+    let total_bytes = database.db_bytes
+        + database.wal_bytes
+        + database.shm_bytes
+        + attachments.bytes
+        + thumbnails.bytes
+        + backups.bytes
+        + exports.bytes
+        + logs.as_ref().map_or(0, |logs| logs.bytes);
+
+    let mut suggestions = Vec::new();
+    if backups.file_count > KEEP_BACKUPS {
+        // Rough estimate: backups are about the same size
+        let reclaimable = backups.bytes / backups.file_count * (backups.file_count - KEEP_BACKUPS);
+        suggestions.push(CleanupSuggestion {
+            id: "prune_backups".to_string(),
+            description: format!("Delete all but the {} most recent backups", KEEP_BACKUPS),
+            reclaimable_bytes: reclaimable,
+            command: None,
+            path: Some(backups.path.clone()),
+        });
+    }
+    if thumbnails.bytes > 0 {
+        suggestions.push(CleanupSuggestion {
+            id: "clear_thumbnails".to_string(),
+            description: "Clear cached thumbnails; they are recreated when needed".to_string(),
+            reclaimable_bytes: thumbnails.bytes,
+            command: None,
+            path: Some(thumbnails.path.clone()),
+        });
+    }
+    if attachments.orphaned_bytes > 0 {
+        suggestions.push(CleanupSuggestion {
+            id: "orphan_cleanup".to_string(),
+            description: "Remove attachment files left behind by deleted tasks".to_string(),
+            reclaimable_bytes: attachments.orphaned_bytes,
+            command: None,
+            path: Some(attachments.path.clone()),
+        });
+    }
+    if exports.file_count > 0 {
+        suggestions.push(CleanupSuggestion {
+            id: "delete_exports".to_string(),
+            description: "Delete exports you've already saved elsewhere".to_string(),
+            reclaimable_bytes: exports.bytes,
+            command: None,
+            path: Some(app_data_dir.to_string_lossy().to_string()),
+        });
+    }
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.reclaimable_bytes));
+
+    StorageBreakdown {
+        total_bytes,
+        database,
+        attachments,
+        thumbnails,
+        backups,
+        exports,
+        logs,
+        truncated: walker.truncated,
+        suggestions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_write_errors() {
+        let full = io::Error::new(io::ErrorKind::StorageFull, "no space left on device");
+        assert_eq!(from_io("save the attachment", &full), StorageError::DiskFull { action: "save the attachment".to_string() });
+        assert!(from_io("save the attachment", &full).to_string().starts_with("Disk full"));
+
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        assert!(matches!(from_io("create a backup", &denied), StorageError::PermissionDenied { .. }));
+
+        let other = io::Error::new(io::ErrorKind::NotFound, "missing");
+        assert_eq!(from_io("create a backup", &other).to_string(), "Failed to create a backup: missing");
+    }
+
+    #[test]
+    fn test_probe_writable() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(probe_writable(temp_dir.path()).is_ok());
+        // The probe file is cleaned up
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+
+        let status = storage_status(&temp_dir.path().join("missing"));
+        assert!(!status.writable);
+        assert!(status.message.is_some());
+    }
+
+    #[test]
+    fn test_storage_breakdown() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("todo.db"), [0u8; 100]).unwrap();
+        fs::write(dir.join("todo.db-wal"), [0u8; 10]).unwrap();
+        fs::create_dir_all(dir.join("attachments/t1/nested")).unwrap();
+        fs::write(dir.join("attachments/t1/a.png"), [0u8; 300]).unwrap();
+        fs::write(dir.join("attachments/t1/nested/b.png"), [0u8; 200]).unwrap();
+        fs::create_dir_all(dir.join("attachments/gone")).unwrap();
+        fs::write(dir.join("attachments/gone/c.pdf"), [0u8; 50]).unwrap();
+        fs::create_dir_all(dir.join("backups")).unwrap();
+        for i in 0..7 {
+            fs::write(dir.join(format!("backups/todo_backup_{}.db", i)), [0u8; 100]).unwrap();
+        }
+        fs::write(dir.join("todo_export_20240101_000000.json"), [0u8; 20]).unwrap();
+
+        let breakdown = storage_breakdown(dir, None, |ids| {
+            assert_eq!(ids.len(), 2);
+            HashMap::from([("t1".to_string(), "Design review".to_string())])
+        });
+
+        assert_eq!(breakdown.database.db_bytes, 100);
+        assert_eq!(breakdown.database.wal_bytes, 10);
+        assert_eq!((breakdown.attachments.bytes, breakdown.attachments.file_count), (550, 3));
+        assert_eq!(breakdown.attachments.top_tasks[0].task_id, "t1");
+        assert_eq!(breakdown.attachments.top_tasks[0].title.as_deref(), Some("Design review"));
+        assert_eq!(breakdown.attachments.orphaned_bytes, 50);
+        assert_eq!(breakdown.backups.bytes, 700);
+        assert_eq!(breakdown.exports.bytes, 20);
+        assert_eq!(breakdown.total_bytes, 100 + 10 + 550 + 700 + 20);
+        assert!(!breakdown.truncated);
+
+        let ids: Vec<&str> = breakdown.suggestions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["prune_backups", "orphan_cleanup", "delete_exports"]);
+        assert_eq!(breakdown.suggestions[0].reclaimable_bytes, 200);
+    }
+}
@@ -0,0 +1,654 @@
+use crate::commands::{import_export_value, task_from_row, ImportSummary, Project, Tag, Task, TASK_COLUMNS};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder shared between devices (e.g. a Dropbox or Syncthing folder); unset disables sync
+pub const SYNC_FOLDER_SETTING: &str = "sync_folder";
+const DEVICE_ID_SETTING: &str = "sync_device_id";
+const LAST_EXPORT_SETTING: &str = "sync_last_export_at";
+
+const CHANGE_FILE_PREFIX: &str = "todo-sync-";
+const CHANGE_FILE_FORMAT: &str = "todo-sync-v1";
+// Our own change files are removed after this long; a device offline for longer needs a full export/import
+const CHANGE_FILE_MAX_AGE_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// "task", "project" or "tag"
+    pub entity: String,
+    pub entity_id: String,
+    pub deleted_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TaskTagLink {
+    task_id: String,
+    tag_id: String,
+}
+
+/// One device's changes since its previous change file. Tasks, projects, tags and task_tags
+/// use the export layout so they can be merged with the regular import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChangeFile {
+    format: String,
+    device_id: String,
+    generated_at: i64,
+    since: i64,
+    tasks: Vec<Task>,
+    projects: Vec<Project>,
+    tags: Vec<Tag>,
+    task_tags: Vec<TaskTagLink>,
+    tombstones: Vec<Tombstone>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncResult {
+    pub device_id: String,
+    /// Records written to this device's change file
+    pub exported: usize,
+    pub files_ingested: usize,
+    pub applied: ImportSummary,
+    pub deleted: usize,
+    pub conflicts: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub device_id: String,
+    pub reason: String,
+    /// None when deleted on this device
+    pub local: Option<serde_json::Value>,
+    /// None when deleted on the other device
+    pub remote: Option<serde_json::Value>,
+    pub detected_at: i64,
+}
+
+fn get_setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).ok()
+}
+
+fn set_setting(conn: &rusqlite::Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    ).map_err(|e| format!("Failed to update setting: {}", e))?;
+    Ok(())
+}
+
+/// This installation's id in change file names, created on first use
+pub fn device_id(conn: &rusqlite::Connection) -> Result<String, String> {
+    if let Some(id) = get_setting(conn, DEVICE_ID_SETTING) {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    set_setting(conn, DEVICE_ID_SETTING, &id)?;
+    Ok(id)
+}
+
+// "todo-sync-<device>-<generated_at>.json" -> (device, generated_at)
+fn parse_change_file_name(name: &str) -> Option<(String, i64)> {
+    let stem = name.strip_prefix(CHANGE_FILE_PREFIX)?.strip_suffix(".json")?;
+    let (device, generated_at) = stem.rsplit_once('-')?;
+    Some((device.to_string(), generated_at.parse().ok()?))
+}
+
+fn query_all<T, P: rusqlite::Params>(
+    conn: &rusqlite::Connection,
+    sql: &str,
+    params: P,
+    map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+) -> Result<Vec<T>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params, map).map_err(|e| format!("Query execution error: {}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("Row parsing error: {}", e))
+}
+
+fn project_from_row(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        completion_note_required: None,
+    })
+}
+
+fn tag_from_row(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        created_at: row.get(3)?,
+        usage_count: row.get(4)?,
+        group_id: row.get(5)?,
+    })
+}
+
+/// Write everything changed since `since` to a new change file. Returns the number of records
+/// written; no file is created when nothing changed.
+fn write_change_file(
+    conn: &rusqlite::Connection,
+    folder: &Path,
+    device_id: &str,
+    since: i64,
+    now: i64,
+) -> Result<usize, String> {
+    // >= so edits made in the same second as the previous export aren't lost; duplicates are harmless
+    let tasks = query_all(
+        conn,
+        &format!("SELECT {} FROM tasks WHERE updated_at >= ?1 ORDER BY updated_at", TASK_COLUMNS),
+        params![since],
+        task_from_row,
+    )?;
+    let projects = query_all(
+        conn,
+        "SELECT id, name, color, created_at, updated_at FROM projects WHERE updated_at >= ?1",
+        params![since],
+        project_from_row,
+    )?;
+    let task_ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let task_tags: Vec<TaskTagLink> = query_all(conn, "SELECT task_id, tag_id FROM task_tags", [], |row| {
+        Ok(TaskTagLink { task_id: row.get(0)?, tag_id: row.get(1)? })
+    })?
+    .into_iter()
+    .filter(|link| task_ids.contains(link.task_id.as_str()))
+    .collect();
+    // New tags plus any tag the exported tasks carry, since the import matches links through the tag list
+    let linked: HashSet<&str> = task_tags.iter().map(|link| link.tag_id.as_str()).collect();
+    let tags: Vec<Tag> = query_all(
+        conn,
+        "SELECT id, name, color, created_at, usage_count, group_id FROM tags",
+        [],
+        tag_from_row,
+    )?
+    .into_iter()
+    .filter(|tag| tag.created_at >= since || linked.contains(tag.id.as_str()))
+    .collect();
+    let tombstones = query_all(
+        conn,
+        "SELECT entity, entity_id, deleted_at FROM sync_tombstones WHERE deleted_at >= ?1",
+        params![since],
+        |row| Ok(Tombstone { entity: row.get(0)?, entity_id: row.get(1)?, deleted_at: row.get(2)? }),
+    )?;
+
+    let records = tasks.len() + projects.len() + tags.len() + tombstones.len();
+    if records == 0 {
+        return Ok(0);
+    }
+
+    let change_file = ChangeFile {
+        format: CHANGE_FILE_FORMAT.to_string(),
+        device_id: device_id.to_string(),
+        generated_at: now,
+        since,
+        tasks,
+        projects,
+        tags,
+        task_tags,
+        tombstones,
+    };
+    let json = serde_json::to_string(&change_file).map_err(|e| format!("Failed to serialize changes: {}", e))?;
+
+    // Write then rename, so other devices never pick up a half-written file
+    let name = format!("{}{}-{}.json", CHANGE_FILE_PREFIX, device_id, now);
+    let tmp_path = folder.join(format!(".{}.tmp", name));
+    if let Err(e) = fs::write(&tmp_path, json).and_then(|_| fs::rename(&tmp_path, folder.join(&name))) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(crate::storage::from_io("write the sync file", &e).to_string());
+    }
+
+    Ok(records)
+}
+
+// Other devices' change files not yet ingested, oldest first
+fn pending_change_files(conn: &rusqlite::Connection, folder: &Path, device_id: &str) -> Result<Vec<(String, i64, PathBuf)>, String> {
+    let last_seen: HashMap<String, i64> = query_all(conn, "SELECT device_id, last_file_at FROM sync_devices", [], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?
+    .into_iter()
+    .collect();
+
+    let entries = fs::read_dir(folder)
+        .map_err(|e| crate::storage::from_io(&format!("read the sync folder {}", folder.display()), &e).to_string())?;
+    let mut files: Vec<(String, i64, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let (device, generated_at) = parse_change_file_name(&entry.file_name().to_string_lossy())?;
+            let newer = generated_at > last_seen.get(&device).copied().unwrap_or(i64::MIN);
+            (device != device_id && newer).then(|| (device, generated_at, entry.path()))
+        })
+        .collect();
+    files.sort_by_key(|(_, generated_at, _)| *generated_at);
+    Ok(files)
+}
+
+// Latest remote state of one record across the ingested files
+enum RemoteChange<T> {
+    Row(T),
+    Deleted,
+}
+
+struct Remote<T> {
+    device_id: String,
+    change: RemoteChange<T>,
+    /// updated_at of the row, or deleted_at of the tombstone
+    at: i64,
+}
+
+fn keep_latest<T>(map: &mut HashMap<String, Remote<T>>, id: String, device_id: &str, change: RemoteChange<T>, at: i64) {
+    if map.get(&id).is_none_or(|existing| at >= existing.at) {
+        map.insert(id, Remote { device_id: device_id.to_string(), change, at });
+    }
+}
+
+enum LocalState {
+    Present { updated_at: i64, same_content: bool },
+    Deleted(i64),
+    Missing,
+}
+
+#[derive(Debug, PartialEq)]
+enum Decision {
+    Apply,
+    Delete,
+    Skip,
+    Conflict(&'static str),
+}
+
+/// Latest updated_at wins; a deletion and an edit that came after it can't be reconciled automatically
+fn decide<T>(local: LocalState, remote: &Remote<T>) -> Decision {
+    let remote_deleted = matches!(remote.change, RemoteChange::Deleted);
+    match local {
+        LocalState::Present { updated_at, same_content } => {
+            if remote_deleted {
+                if updated_at > remote.at {
+                    Decision::Conflict("Edited here after it was deleted on the other device")
+                } else {
+                    Decision::Delete
+                }
+            } else if remote.at > updated_at {
+                Decision::Apply
+            } else if remote.at == updated_at && !same_content {
+                Decision::Conflict("Edited on both devices at the same time")
+            } else {
+                Decision::Skip
+            }
+        }
+        LocalState::Deleted(deleted_at) => {
+            if !remote_deleted && remote.at > deleted_at {
+                Decision::Conflict("Edited on the other device after it was deleted here")
+            } else {
+                Decision::Skip
+            }
+        }
+        LocalState::Missing if !remote_deleted => Decision::Apply,
+        LocalState::Missing => Decision::Skip,
+    }
+}
+
+fn same_task(a: &Task, b: &Task) -> bool {
+    a.title == b.title
+        && a.description == b.description
+        && a.completed == b.completed
+        && a.due_date == b.due_date
+        && a.priority == b.priority
+        && a.project_id == b.project_id
+        && a.recurrence_type == b.recurrence_type
+        && a.recurrence_interval == b.recurrence_interval
+}
+
+fn tombstone_at(conn: &rusqlite::Connection, entity: &str, entity_id: &str) -> Option<i64> {
+    conn.query_row(
+        "SELECT deleted_at FROM sync_tombstones WHERE entity = ?1 AND entity_id = ?2",
+        params![entity, entity_id],
+        |row| row.get(0),
+    ).ok()
+}
+
+fn local_state(conn: &rusqlite::Connection, entity: &str, entity_id: &str, present: Option<(i64, bool)>) -> LocalState {
+    match present {
+        Some((updated_at, same_content)) => LocalState::Present { updated_at, same_content },
+        None => tombstone_at(conn, entity, entity_id).map_or(LocalState::Missing, LocalState::Deleted),
+    }
+}
+
+struct NewConflict {
+    entity: &'static str,
+    entity_id: String,
+    device_id: String,
+    reason: &'static str,
+    local: Option<serde_json::Value>,
+    remote: Option<serde_json::Value>,
+}
+
+fn remote_json<T: Serialize>(change: &RemoteChange<T>) -> Option<serde_json::Value> {
+    match change {
+        RemoteChange::Row(row) => serde_json::to_value(row).ok(),
+        RemoteChange::Deleted => None,
+    }
+}
+
+// Remote changes merged across the pending files, latest per record
+#[derive(Default)]
+struct PendingChanges {
+    files: usize,
+    tasks: HashMap<String, Remote<Task>>,
+    projects: HashMap<String, Remote<Project>>,
+    deleted_tags: HashSet<String>,
+    tags: Vec<Tag>,
+    task_tags: Vec<TaskTagLink>,
+    /// Newest file read per device
+    newest: HashMap<String, i64>,
+}
+
+fn read_pending_changes(conn: &rusqlite::Connection, folder: &Path, device_id: &str) -> Result<PendingChanges, String> {
+    let mut pending = PendingChanges::default();
+    let mut unreadable: HashSet<String> = HashSet::new();
+
+    for (device, generated_at, path) in pending_change_files(conn, folder, device_id)? {
+        if unreadable.contains(&device) {
+            continue;
+        }
+        let change = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<ChangeFile>(&json).ok())
+            .filter(|change| change.format == CHANGE_FILE_FORMAT);
+        let Some(change) = change else {
+            // Possibly still being copied by the sync tool; retry this device's files next time
+            eprintln!("Skipping unreadable sync file {}", path.display());
+            unreadable.insert(device);
+            continue;
+        };
+
+        for task in change.tasks {
+            let at = task.updated_at;
+            keep_latest(&mut pending.tasks, task.id.clone(), &device, RemoteChange::Row(task), at);
+        }
+        for project in change.projects {
+            let at = project.updated_at;
+            keep_latest(&mut pending.projects, project.id.clone(), &device, RemoteChange::Row(project), at);
+        }
+        for tombstone in change.tombstones {
+            let at = tombstone.deleted_at;
+            match tombstone.entity.as_str() {
+                "task" => keep_latest(&mut pending.tasks, tombstone.entity_id, &device, RemoteChange::Deleted, at),
+                "project" => keep_latest(&mut pending.projects, tombstone.entity_id, &device, RemoteChange::Deleted, at),
+                "tag" => {
+                    pending.deleted_tags.insert(tombstone.entity_id);
+                }
+                _ => {}
+            }
+        }
+        pending.tags.extend(change.tags);
+        pending.task_tags.extend(change.task_tags);
+        pending.files += 1;
+        pending.newest.insert(device, generated_at);
+    }
+
+    Ok(pending)
+}
+
+fn record_conflict(tx: &rusqlite::Transaction, conflict: &NewConflict, now: i64) -> Result<(), String> {
+    // Only the latest open conflict per record is kept
+    tx.execute(
+        "DELETE FROM sync_conflicts WHERE entity = ?1 AND entity_id = ?2 AND resolved_at IS NULL",
+        params![conflict.entity, conflict.entity_id],
+    ).map_err(|e| format!("Failed to replace sync conflict: {}", e))?;
+    tx.execute(
+        "INSERT INTO sync_conflicts (id, entity, entity_id, device_id, reason, local_json, remote_json, detected_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            conflict.entity,
+            conflict.entity_id,
+            conflict.device_id,
+            conflict.reason,
+            conflict.local.as_ref().map(|v| v.to_string()),
+            conflict.remote.as_ref().map(|v| v.to_string()),
+            now,
+        ],
+    ).map_err(|e| format!("Failed to record sync conflict: {}", e))?;
+    Ok(())
+}
+
+fn delete_record(tx: &rusqlite::Transaction, entity: &str, entity_id: &str, deleted_at: i64) -> Result<(), String> {
+    let statements: &[&str] = match entity {
+        "task" => &["DELETE FROM task_tags WHERE task_id = ?1", "DELETE FROM tasks WHERE id = ?1"],
+        "project" => &["DELETE FROM projects WHERE id = ?1"],
+        "tag" => &["DELETE FROM task_tags WHERE tag_id = ?1", "DELETE FROM tags WHERE id = ?1"],
+        _ => return Ok(()),
+    };
+    for sql in statements {
+        tx.execute(sql, params![entity_id]).map_err(|e| format!("Failed to delete synced {}: {}", entity, e))?;
+    }
+    // Keep the original deletion time rather than the trigger's, so it isn't mistaken for a new delete
+    tx.execute(
+        "INSERT OR REPLACE INTO sync_tombstones (entity, entity_id, deleted_at) VALUES (?1, ?2, ?3)",
+        params![entity, entity_id, deleted_at],
+    ).map_err(|e| format!("Failed to record tombstone: {}", e))?;
+    Ok(())
+}
+
+/// Merge other devices' pending change files into the database
+fn ingest_change_files(
+    conn: &rusqlite::Connection,
+    folder: &Path,
+    device_id: &str,
+    now: i64,
+) -> Result<(usize, ImportSummary, usize, usize), String> {
+    let pending = read_pending_changes(conn, folder, device_id)?;
+
+    let mut apply_tasks = Vec::new();
+    let mut apply_projects = Vec::new();
+    // (entity, id, remote updated_at) so imported rows keep the other device's timestamp
+    let mut timestamps: Vec<(&str, String, i64)> = Vec::new();
+    let mut deletions: Vec<(&str, String, i64)> = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for (id, remote) in pending.tasks {
+        let local_task = conn.query_row(
+            &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+            params![id],
+            task_from_row,
+        ).ok();
+        let present = local_task.as_ref().map(|task| {
+            (task.updated_at, matches!(&remote.change, RemoteChange::Row(row) if same_task(task, row)))
+        });
+        match decide(local_state(conn, "task", &id, present), &remote) {
+            Decision::Apply => {
+                if let RemoteChange::Row(task) = remote.change {
+                    timestamps.push(("tasks", id, remote.at));
+                    apply_tasks.push(task);
+                }
+            }
+            Decision::Delete => deletions.push(("task", id, remote.at)),
+            Decision::Skip => {}
+            Decision::Conflict(reason) => conflicts.push(NewConflict {
+                entity: "task",
+                local: local_task.and_then(|task| serde_json::to_value(task).ok()),
+                remote: remote_json(&remote.change),
+                entity_id: id,
+                device_id: remote.device_id,
+                reason,
+            }),
+        }
+    }
+
+    for (id, remote) in pending.projects {
+        let local_project = conn.query_row(
+            "SELECT id, name, color, created_at, updated_at FROM projects WHERE id = ?1",
+            params![id],
+            project_from_row,
+        ).ok();
+        let present = local_project.as_ref().map(|project| {
+            let same = matches!(&remote.change, RemoteChange::Row(row) if row.name == project.name && row.color == project.color);
+            (project.updated_at, same)
+        });
+        match decide(local_state(conn, "project", &id, present), &remote) {
+            Decision::Apply => {
+                if let RemoteChange::Row(project) = remote.change {
+                    timestamps.push(("projects", id, remote.at));
+                    apply_projects.push(project);
+                }
+            }
+            Decision::Delete => deletions.push(("project", id, remote.at)),
+            Decision::Skip => {}
+            Decision::Conflict(reason) => conflicts.push(NewConflict {
+                entity: "project",
+                local: local_project.and_then(|project| serde_json::to_value(project).ok()),
+                remote: remote_json(&remote.change),
+                entity_id: id,
+                device_id: remote.device_id,
+                reason,
+            }),
+        }
+    }
+
+    // Tags have no updated_at; a deletion anywhere removes them
+    for id in pending.deleted_tags {
+        deletions.push(("tag", id, now));
+    }
+
+    let applied_ids: HashSet<&str> = apply_tasks.iter().map(|task| task.id.as_str()).collect();
+    let task_tags: Vec<&TaskTagLink> = pending.task_tags.iter().filter(|link| applied_ids.contains(link.task_id.as_str())).collect();
+    let summary = import_export_value(conn, &serde_json::json!({
+        "projects": apply_projects,
+        "tasks": apply_tasks,
+        "tags": pending.tags,
+        "task_tags": task_tags,
+    }))?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for (table, id, updated_at) in &timestamps {
+        tx.execute(&format!("UPDATE {} SET updated_at = ?1 WHERE id = ?2", table), params![updated_at, id])
+            .map_err(|e| format!("Failed to update synced record: {}", e))?;
+    }
+    for (entity, id, deleted_at) in &deletions {
+        delete_record(&tx, entity, id, *deleted_at)?;
+    }
+    if !deletions.is_empty() {
+        tx.execute("UPDATE tags SET usage_count = (SELECT COUNT(*) FROM task_tags WHERE tag_id = tags.id)", [])
+            .map_err(|e| format!("Failed to update tag usage counts: {}", e))?;
+    }
+    for conflict in &conflicts {
+        record_conflict(&tx, conflict, now)?;
+    }
+    for (device, generated_at) in &pending.newest {
+        tx.execute(
+            "INSERT INTO sync_devices (device_id, last_file_at, last_seen_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(device_id) DO UPDATE SET last_file_at = ?2, last_seen_at = ?3",
+            params![device, generated_at, now],
+        ).map_err(|e| format!("Failed to record sync progress: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok((pending.files, summary, deletions.len(), conflicts.len()))
+}
+
+fn remove_old_change_files(folder: &Path, device_id: &str, now: i64) {
+    let Ok(entries) = fs::read_dir(folder) else { return };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if let Some((device, generated_at)) = parse_change_file_name(&entry.file_name().to_string_lossy()) {
+            if device == device_id && now - generated_at > CHANGE_FILE_MAX_AGE_SECS {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// Write this device's changes to the sync folder and merge other devices' changes.
+/// Returns None when no sync folder is configured.
+pub fn run_sync(conn: &rusqlite::Connection, now: i64) -> Result<Option<SyncResult>, String> {
+    let Some(folder) = get_setting(conn, SYNC_FOLDER_SETTING).filter(|folder| !folder.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let folder = PathBuf::from(folder);
+    if !folder.is_dir() {
+        return Err(format!("Sync folder not found: {}", folder.display()));
+    }
+    let device_id = device_id(conn)?;
+
+    // Export before ingesting so the records merged below aren't echoed back
+    let since = get_setting(conn, LAST_EXPORT_SETTING).and_then(|v| v.parse().ok()).unwrap_or(0);
+    let exported = write_change_file(conn, &folder, &device_id, since, now)?;
+    set_setting(conn, LAST_EXPORT_SETTING, &now.to_string())?;
+
+    let (files_ingested, applied, deleted, conflicts) = ingest_change_files(conn, &folder, &device_id, now)?;
+    remove_old_change_files(&folder, &device_id, now);
+
+    Ok(Some(SyncResult { device_id, exported, files_ingested, applied, deleted, conflicts }))
+}
+
+pub fn get_sync_conflicts(conn: &rusqlite::Connection) -> Result<Vec<SyncConflict>, String> {
+    query_all(
+        conn,
+        "SELECT id, entity, entity_id, device_id, reason, local_json, remote_json, detected_at
+         FROM sync_conflicts WHERE resolved_at IS NULL ORDER BY detected_at",
+        [],
+        |row| {
+            let parse = |json: Option<String>| json.and_then(|json| serde_json::from_str(&json).ok());
+            Ok(SyncConflict {
+                id: row.get(0)?,
+                entity: row.get(1)?,
+                entity_id: row.get(2)?,
+                device_id: row.get(3)?,
+                reason: row.get(4)?,
+                local: parse(row.get(5)?),
+                remote: parse(row.get(6)?),
+                detected_at: row.get(7)?,
+            })
+        },
+    )
+}
+
+/// Settle a conflict by keeping this device's version ("local") or the other device's ("remote").
+/// The kept version is stamped with `now` so it wins on every device at the next sync.
+pub fn resolve_sync_conflict(conn: &rusqlite::Connection, id: &str, keep: &str, now: i64) -> Result<(), String> {
+    if keep != "local" && keep != "remote" {
+        return Err(format!("keep must be local or remote, got: {}", keep));
+    }
+    let conflict = get_sync_conflicts(conn)?
+        .into_iter()
+        .find(|conflict| conflict.id == id)
+        .ok_or_else(|| format!("Sync conflict not found: {}", id))?;
+    let table = match conflict.entity.as_str() {
+        "task" => "tasks",
+        "project" => "projects",
+        other => return Err(format!("Unknown sync entity: {}", other)),
+    };
+
+    let kept = if keep == "local" { &conflict.local } else { &conflict.remote };
+    match kept {
+        Some(record) => {
+            if keep == "remote" {
+                import_export_value(conn, &serde_json::json!({ table: [record] }))?;
+                conn.execute("DELETE FROM sync_tombstones WHERE entity = ?1 AND entity_id = ?2", params![conflict.entity, conflict.entity_id])
+                    .map_err(|e| format!("Failed to clear tombstone: {}", e))?;
+            }
+            conn.execute(&format!("UPDATE {} SET updated_at = ?1 WHERE id = ?2", table), params![now, conflict.entity_id])
+                .map_err(|e| format!("Failed to update record: {}", e))?;
+        }
+        None => {
+            let tx = conn.unchecked_transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+            delete_record(&tx, &conflict.entity, &conflict.entity_id, now)?;
+            tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        }
+    }
+
+    conn.execute(
+        "UPDATE sync_conflicts SET resolved_at = ?1, resolution = ?2 WHERE id = ?3",
+        params![now, keep, id],
+    ).map_err(|e| format!("Failed to resolve sync conflict: {}", e))?;
+    Ok(())
+}
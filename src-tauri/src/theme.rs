@@ -0,0 +1,155 @@
+use rusqlite::params;
+use serde::Serialize;
+use tauri::{Manager, Theme};
+
+/// Settings key for the theme preference: "light", "dark" or "system"
+pub const THEME_SETTING_KEY: &str = "theme";
+const DEFAULT_PREFERENCE: &str = "system";
+// In the order the tray toggle cycles through them
+const PREFERENCES: [&str; 3] = ["light", "dark", "system"];
+/// Emitted to all windows whenever the theme to show may have changed
+pub const THEME_CHANGED_EVENT: &str = "theme-changed";
+
+/// Payload of the `theme-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeChanged {
+    pub preference: String,
+    /// What the windows should show: "light" or "dark"
+    pub theme: String,
+}
+
+impl ThemeChanged {
+    pub fn new(preference: &str, os_theme: Option<Theme>) -> Self {
+        Self {
+            preference: preference.to_string(),
+            theme: resolve(preference, os_theme).to_string(),
+        }
+    }
+}
+
+pub fn validate_preference(value: &str) -> Result<(), String> {
+    if PREFERENCES.contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("Unknown theme: {} (expected light, dark or system)", value))
+    }
+}
+
+/// The stored preference; "system" when unset or unreadable
+pub fn get_preference(conn: &rusqlite::Connection) -> String {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![THEME_SETTING_KEY],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .filter(|value| validate_preference(value).is_ok())
+    .unwrap_or_else(|| DEFAULT_PREFERENCE.to_string())
+}
+
+pub fn set_preference(conn: &rusqlite::Connection, preference: &str) -> Result<(), String> {
+    validate_preference(preference)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![THEME_SETTING_KEY, preference],
+    )
+    .map_err(|e| format!("Failed to update setting: {}", e))?;
+    Ok(())
+}
+
+/// The preference after `current`: light, dark, system, then light again
+pub fn next_preference(current: &str) -> &'static str {
+    let index = PREFERENCES.iter().position(|preference| *preference == current).unwrap_or(PREFERENCES.len() - 1);
+    PREFERENCES[(index + 1) % PREFERENCES.len()]
+}
+
+/// "light" or "dark"; "system" follows `os_theme` and falls back to light when it's unknown
+pub fn resolve(preference: &str, os_theme: Option<Theme>) -> &'static str {
+    match preference {
+        "light" => "light",
+        "dark" => "dark",
+        _ => match os_theme {
+            Some(Theme::Dark) => "dark",
+            _ => "light",
+        },
+    }
+}
+
+/// The OS theme as the main window sees it, or straight from the registry on Windows when
+/// there's no window to ask
+pub fn os_theme(app_handle: &tauri::AppHandle) -> Option<Theme> {
+    app_handle
+        .get_window("main")
+        .and_then(|window| window.theme().ok())
+        .or_else(registry_theme)
+}
+
+// HKCU\...\Personalize\AppsUseLightTheme is 0 when apps should be dark
+#[cfg(windows)]
+fn registry_theme() -> Option<Theme> {
+    use std::os::windows::process::CommandExt;
+    // CREATE_NO_WINDOW, so no console flashes up
+    let output = std::process::Command::new("reg")
+        .args(["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize", "/v", "AppsUseLightTheme"])
+        .creation_flags(0x0800_0000)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.split_whitespace().last()? {
+        "0x0" => Some(Theme::Dark),
+        "0x1" => Some(Theme::Light),
+        _ => None,
+    }
+}
+
+#[cfg(not(windows))]
+fn registry_theme() -> Option<Theme> {
+    None
+}
+
+pub fn effective_theme(app_handle: &tauri::AppHandle, conn: &rusqlite::Connection) -> &'static str {
+    resolve(&get_preference(conn), os_theme(app_handle))
+}
+
+/// Tell every window what to show for `preference`
+pub fn emit_theme_changed(app_handle: &tauri::AppHandle, preference: &str, os_theme: Option<Theme>) {
+    app_handle.emit_all(THEME_CHANGED_EVENT, ThemeChanged::new(preference, os_theme)).ok();
+}
+
+/// Tray toggle: store the next preference and announce it. Returns the new preference.
+pub fn cycle_theme(app_handle: &tauri::AppHandle, conn: &rusqlite::Connection) -> Result<&'static str, String> {
+    let preference = next_preference(&get_preference(conn));
+    set_preference(conn, preference)?;
+    emit_theme_changed(app_handle, preference, os_theme(app_handle));
+    Ok(preference)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_preference_cycle_and_resolution() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);").unwrap();
+        assert_eq!(get_preference(&conn), "system");
+
+        let mut seen = Vec::new();
+        let mut preference = get_preference(&conn);
+        for _ in 0..4 {
+            preference = next_preference(&preference).to_string();
+            set_preference(&conn, &preference).unwrap();
+            seen.push(get_preference(&conn));
+        }
+        assert_eq!(seen, vec!["light", "dark", "system", "light"]);
+        assert!(set_preference(&conn, "sepia").is_err());
+
+        // A value written some other way that isn't a preference counts as unset
+        conn.execute("UPDATE settings SET value = 'Dark' WHERE key = 'theme'", []).unwrap();
+        assert_eq!(get_preference(&conn), "system");
+
+        assert_eq!(resolve("dark", Some(Theme::Light)), "dark");
+        assert_eq!(resolve("system", Some(Theme::Dark)), "dark");
+        assert_eq!(resolve("system", None), "light");
+    }
+}
@@ -0,0 +1,276 @@
+use crate::db::DbConnection;
+use chrono::{Duration, Local, NaiveTime, TimeZone, Timelike};
+use rusqlite::params;
+use tauri::{CustomMenuItem, Manager, SystemTrayMenu, SystemTrayMenuItem, SystemTraySubmenu};
+
+// Keep the tray menu short; the rest is a click away in the app
+const MAX_TRAY_TASKS: i64 = 10;
+const MAX_TITLE_CHARS: usize = 40;
+const TASK_ITEM_PREFIX: &str = "task:";
+// Snoozed tasks without a due time land at 9:00 tomorrow
+const DEFAULT_SNOOZE_HOUR: u32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayTaskAction {
+    Complete,
+    SnoozeToTomorrow,
+    RaisePriority,
+}
+
+impl TrayTaskAction {
+    const ALL: [TrayTaskAction; 3] = [
+        TrayTaskAction::Complete,
+        TrayTaskAction::SnoozeToTomorrow,
+        TrayTaskAction::RaisePriority,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            TrayTaskAction::Complete => "complete",
+            TrayTaskAction::SnoozeToTomorrow => "snooze",
+            TrayTaskAction::RaisePriority => "raise",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TrayTaskAction::Complete => "Complete",
+            TrayTaskAction::SnoozeToTomorrow => "Snooze to tomorrow",
+            TrayTaskAction::RaisePriority => "Raise priority",
+        }
+    }
+}
+
+/// Menu item id for an action on a task, e.g. `task:<uuid>:complete`
+pub fn task_item_id(task_id: &str, action: TrayTaskAction) -> String {
+    format!("{}{}:{}", TASK_ITEM_PREFIX, task_id, action.as_str())
+}
+
+pub fn parse_task_item_id(id: &str) -> Option<(String, TrayTaskAction)> {
+    let (task_id, action) = id.strip_prefix(TASK_ITEM_PREFIX)?.rsplit_once(':')?;
+    let action = TrayTaskAction::ALL.into_iter().find(|a| a.as_str() == action)?;
+    if task_id.is_empty() {
+        return None;
+    }
+    Some((task_id.to_string(), action))
+}
+
+pub(crate) fn truncate_title(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let truncated: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+// Open tasks due today or overdue: (id, title)
+fn todays_tasks(conn: &rusqlite::Connection) -> Result<Vec<(String, String)>, String> {
+    let tomorrow = Local::now().date_naive() + Duration::days(1);
+    let end_of_today = Local
+        .from_local_datetime(&tomorrow.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or(i64::MAX);
+
+    let mut stmt = conn.prepare(
+        "SELECT id, title FROM tasks
+         WHERE completed_at IS NULL AND archived = 0 AND due_at IS NOT NULL AND due_at < ?1
+         ORDER BY due_at ASC, order_index ASC
+         LIMIT ?2"
+    ).map_err(|e| format!("Query error: {}", e))?;
+    let rows = stmt.query_map(params![end_of_today, MAX_TRAY_TASKS], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Query execution error: {}", e))?;
+
+    let mut tasks = Vec::new();
+    for row in rows {
+        tasks.push(row.map_err(|e| format!("Row parsing error: {}", e))?);
+    }
+    Ok(tasks)
+}
+
+/// The tray menu: the fixed entries, then one submenu of actions per task due today
+pub fn build_tray_menu(tasks: &[(String, String)]) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("open".to_string(), "Open App"))
+        .add_item(CustomMenuItem::new("quick_add".to_string(), "Quick Add"))
+        .add_item(CustomMenuItem::new("toggle_theme".to_string(), "Toggle Theme"));
+
+    if !tasks.is_empty() {
+        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
+        for (task_id, title) in tasks {
+            let mut actions = SystemTrayMenu::new();
+            for action in TrayTaskAction::ALL {
+                actions = actions.add_item(CustomMenuItem::new(task_item_id(task_id, action), action.label()));
+            }
+            menu = menu.add_submenu(SystemTraySubmenu::new(truncate_title(title), actions));
+        }
+        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
+    }
+
+    menu.add_item(CustomMenuItem::new("quit".to_string(), "Quit"))
+}
+
+/// Rebuild the tray menu from today's tasks; demo mode shows the same placeholders as the app
+pub fn refresh_tray_menu(app_handle: &tauri::AppHandle, conn: &rusqlite::Connection) {
+    match todays_tasks(conn) {
+        Ok(mut tasks) => {
+            if crate::demo_mode::is_enabled_for(app_handle) {
+                for (task_id, title) in &mut tasks {
+                    *title = crate::demo_mode::placeholder(task_id, 2, 5);
+                }
+            }
+            if let Err(e) = app_handle.tray_handle().set_menu(build_tray_menu(&tasks)) {
+                eprintln!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to load tray tasks: {}", e),
+    }
+}
+
+/// Tomorrow (local time) at the due date's time of day, or at 9:00 for tasks without one
+pub fn snooze_to_tomorrow(due_at: Option<i64>, now: i64) -> i64 {
+    let now = Local.timestamp_opt(now, 0).single().unwrap_or_else(Local::now);
+    let time = due_at
+        .and_then(|due| Local.timestamp_opt(due, 0).single())
+        .map(|due| due.time())
+        .unwrap_or_else(|| NaiveTime::from_hms_opt(DEFAULT_SNOOZE_HOUR, 0, 0).unwrap());
+    let tomorrow = (now.date_naive() + Duration::days(1)).and_time(time.with_nanosecond(0).unwrap_or(time));
+
+    Local
+        .from_local_datetime(&tomorrow)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| now.timestamp() + 24 * 60 * 60)
+}
+
+// Apply an action and return the confirmation text; the task title is included in errors by the caller
+fn apply_task_action(db: &DbConnection, task_id: &str, action: TrayTaskAction) -> Result<String, String> {
+    match action {
+        TrayTaskAction::Complete => {
+            crate::commands::ensure_completion_acknowledged(&db.conn, task_id, None)
+                .map_err(|e| format!("{}. Open the app to complete it.", e))?;
+            // The tray can't ask for a note
+            crate::commands::resolve_completion_note(&db.conn, task_id, None, false).map_err(|e| {
+                if e == crate::commands::COMPLETION_NOTE_REQUIRED_ERROR {
+                    "Needs a completion note. Open the app to complete it.".to_string()
+                } else {
+                    e
+                }
+            })?;
+            let result = crate::commands::toggle_complete_internal(&db.conn, task_id)?;
+            Ok(if result.xp_granted > 0 {
+                format!("Completed (+{} XP)", result.xp_granted)
+            } else {
+                "Completed".to_string()
+            })
+        }
+        TrayTaskAction::SnoozeToTomorrow => {
+            let due_at: Option<i64> = db.conn.query_row(
+                "SELECT due_at FROM tasks WHERE id = ?1",
+                params![task_id],
+                |row| row.get(0),
+            ).map_err(|e| format!("Task not found: {}", e))?;
+            let new_due = snooze_to_tomorrow(due_at, Local::now().timestamp());
+
+            // The shared update path moves the reminder along with the due date
+            let input = crate::commands::UpdateTaskInput { due_date: Some(new_due), ..Default::default() };
+            crate::commands::update_task_from(&db.conn, task_id, input, "snooze", "tray")
+                .map_err(|e| format!("Failed to snooze task: {}", e))?;
+
+            let label = Local.timestamp_opt(new_due, 0).single()
+                .map(|dt| dt.format("%a %H:%M").to_string())
+                .unwrap_or_default();
+            Ok(format!("Snoozed to {}", label))
+        }
+        TrayTaskAction::RaisePriority => {
+            let priority: String = db.conn.query_row(
+                "SELECT priority FROM tasks WHERE id = ?1",
+                params![task_id],
+                |row| row.get(0),
+            ).map_err(|e| format!("Task not found: {}", e))?;
+            let raised = match priority.as_str() {
+                "low" => "medium",
+                "medium" => "high",
+                _ => return Err("Already high priority".to_string()),
+            };
+
+            let input = crate::commands::UpdateTaskInput { priority: Some(raised.to_string()), ..Default::default() };
+            crate::commands::update_task_from(&db.conn, task_id, input, "tray", "tray")
+                .map_err(|e| format!("Failed to update priority: {}", e))?;
+
+            Ok(format!("Priority raised to {}", raised))
+        }
+    }
+}
+
+/// Handle a click on a per-task tray item. Returns false if `id` isn't one.
+/// There's no window to report to, so the outcome is shown as a notification.
+pub fn handle_task_item(app_handle: &tauri::AppHandle, id: &str) -> bool {
+    let (task_id, action) = match parse_task_item_id(id) {
+        Some(parsed) => parsed,
+        None => return false,
+    };
+    // Every action changes the task, which demo mode doesn't allow
+    if crate::demo_mode::is_enabled_for(app_handle) {
+        let _ = crate::notifications::show_notification("Demo mode", "Task actions are not available in demo mode");
+        return true;
+    }
+
+    let db_state = app_handle.state::<std::sync::Arc<std::sync::Mutex<DbConnection>>>();
+    let db = match db_state.lock() {
+        Ok(db) => db,
+        Err(e) => {
+            let _ = crate::notifications::show_notification("Task action failed", &format!("Database lock error: {}", e));
+            return true;
+        }
+    };
+
+    let title: String = db.conn.query_row(
+        "SELECT title FROM tasks WHERE id = ?1",
+        params![task_id],
+        |row| row.get(0),
+    ).unwrap_or_else(|_| "Task".to_string());
+
+    match apply_task_action(&db, &task_id, action) {
+        Ok(message) => {
+            let _ = crate::notifications::show_notification(&title, &message);
+            // Let the main window pick up the change
+            let _ = app_handle.emit_all("task-updated", task_id.clone());
+        }
+        Err(e) => {
+            let _ = crate::notifications::show_notification(&format!("Couldn't update \"{}\"", title), &e);
+        }
+    }
+
+    refresh_tray_menu(app_handle, &db.conn);
+    crate::commands::refresh_tray_tooltip(app_handle, &db.conn);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_item_ids_round_trip() {
+        let task_id = "3f2b8c1e-0000-4000-8000-000000000001";
+        for action in TrayTaskAction::ALL {
+            assert_eq!(parse_task_item_id(&task_item_id(task_id, action)), Some((task_id.to_string(), action)));
+        }
+        assert_eq!(parse_task_item_id("quit"), None);
+        assert_eq!(parse_task_item_id("task::complete"), None);
+        assert_eq!(parse_task_item_id("task:abc:delete"), None);
+    }
+
+    #[test]
+    fn test_snooze_to_tomorrow_keeps_time_of_day() {
+        let now = Local.with_ymd_and_hms(2024, 3, 10, 18, 30, 0).earliest().unwrap().timestamp();
+        let due = Local.with_ymd_and_hms(2024, 3, 8, 14, 15, 0).earliest().unwrap().timestamp();
+
+        let snoozed = Local.timestamp_opt(snooze_to_tomorrow(Some(due), now), 0).unwrap();
+        assert_eq!(snoozed.format("%Y-%m-%d %H:%M").to_string(), "2024-03-11 14:15");
+
+        let snoozed = Local.timestamp_opt(snooze_to_tomorrow(None, now), 0).unwrap();
+        assert_eq!(snoozed.format("%Y-%m-%d %H:%M").to_string(), "2024-03-11 09:00");
+    }
+}
@@ -0,0 +1,119 @@
+use crate::db::DbConnection;
+use rusqlite::params;
+use tauri::{Manager, PhysicalPosition, PhysicalSize, WindowBuilder, WindowUrl};
+
+pub const QUICK_ADD_WINDOW_LABEL: &str = "quick-add";
+
+// Below this size a restored window is considered bogus (e.g. saved while minimized)
+const MIN_RESTORE_WIDTH: u32 = 400;
+const MIN_RESTORE_HEIGHT: u32 = 300;
+
+fn get_setting(db: &DbConnection, key: &str) -> Option<String> {
+    db.conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).ok()
+}
+
+fn set_setting(db: &DbConnection, key: &str, value: String) -> Result<(), String> {
+    db.conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    ).map_err(|e| format!("Failed to save window state: {}", e))?;
+    Ok(())
+}
+
+/// Persist the window's size, position and maximized flag into settings.
+/// Size and position are left untouched while maximized so that un-maximizing
+/// after a restart goes back to the last normal geometry.
+pub fn save_window_state(window: &tauri::Window, db: &DbConnection) -> Result<(), String> {
+    let maximized = window.is_maximized().unwrap_or(false);
+    set_setting(db, "window_maximized", maximized.to_string())?;
+
+    if maximized {
+        return Ok(());
+    }
+
+    let size = window.outer_size().map_err(|e| format!("Failed to read window size: {}", e))?;
+    let position = window.outer_position().map_err(|e| format!("Failed to read window position: {}", e))?;
+
+    if size.width >= MIN_RESTORE_WIDTH && size.height >= MIN_RESTORE_HEIGHT {
+        set_setting(db, "window_width", size.width.to_string())?;
+        set_setting(db, "window_height", size.height.to_string())?;
+        set_setting(db, "window_x", position.x.to_string())?;
+        set_setting(db, "window_y", position.y.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Restore the geometry saved by `save_window_state`. Missing or invalid values are ignored.
+pub fn restore_window_state(window: &tauri::Window, db: &DbConnection) -> Result<(), String> {
+    let width = get_setting(db, "window_width").and_then(|v| v.parse::<u32>().ok());
+    let height = get_setting(db, "window_height").and_then(|v| v.parse::<u32>().ok());
+    if let (Some(width), Some(height)) = (width, height) {
+        if width >= MIN_RESTORE_WIDTH && height >= MIN_RESTORE_HEIGHT {
+            window.set_size(PhysicalSize::new(width, height))
+                .map_err(|e| format!("Failed to restore window size: {}", e))?;
+        }
+    }
+
+    let x = get_setting(db, "window_x").and_then(|v| v.parse::<i32>().ok());
+    let y = get_setting(db, "window_y").and_then(|v| v.parse::<i32>().ok());
+    if let (Some(x), Some(y)) = (x, y) {
+        window.set_position(PhysicalPosition::new(x, y))
+            .map_err(|e| format!("Failed to restore window position: {}", e))?;
+    }
+
+    if get_setting(db, "window_maximized").as_deref() == Some("true") {
+        window.maximize().map_err(|e| format!("Failed to maximize window: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Show the small always-on-top quick-add window, creating it on first use.
+/// The window loads the regular frontend with `?window=quick-add`, which renders only the add form.
+pub fn open_quick_add_window(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_window(QUICK_ADD_WINDOW_LABEL) {
+        window.show().map_err(|e| format!("Failed to show quick-add window: {}", e))?;
+        window.set_focus().map_err(|e| format!("Failed to focus quick-add window: {}", e))?;
+        return Ok(());
+    }
+
+    // Native chrome in the chosen theme; None follows the OS
+    let theme = app_handle
+        .try_state::<std::sync::Arc<std::sync::Mutex<DbConnection>>>()
+        .and_then(|db| db.lock().ok().map(|db| crate::theme::get_preference(&db.conn)))
+        .and_then(|preference| match preference.as_str() {
+            "light" => Some(tauri::Theme::Light),
+            "dark" => Some(tauri::Theme::Dark),
+            _ => None,
+        });
+    
+    WindowBuilder::new(
+        app_handle,
+        QUICK_ADD_WINDOW_LABEL,
+        WindowUrl::App("index.html?window=quick-add".into()),
+    )
+    .title("Quick Add")
+    .inner_size(520.0, 180.0)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .center()
+    .focused(true)
+    .theme(theme)
+    .build()
+    .map_err(|e| format!("Failed to create quick-add window: {}", e))?;
+
+    Ok(())
+}
+
+pub fn close_quick_add_window(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_window(QUICK_ADD_WINDOW_LABEL) {
+        window.close().map_err(|e| format!("Failed to close quick-add window: {}", e))?;
+    }
+    Ok(())
+}
@@ -6,8 +6,11 @@ import { useTasks } from './store/useTasks'
 import { useTags } from './store/useTags'
 import { useXp } from './store/useXp'
 import { isTauri } from './utils/tauri'
+import { reloadDatabase, sessionStart, takeLaunchLink, type StorageStatus } from './api/tauriAdapter'
 import { Toaster } from './components/ui/toaster'
+import { toast } from './components/ui/use-toast'
 import { LevelUpDialog } from './components/ui/LevelUpDialog'
+import { TaskDetailsModal } from './components/TaskDetailsModal'
 
 // Lazy load route components for code splitting
 const Dashboard = lazy(() => import('./pages/Dashboard').then(m => ({ default: m.Dashboard })))
@@ -29,10 +32,13 @@ function PageLoader() {
 }
 
 function App() {
-  const { syncTasks } = useTasks()
+  const { syncTasks, tasks } = useTasks()
   const { syncTags } = useTags()
-  const { hasLeveledUp, newLevel, resetLevelUp, syncFromBackend, loadBadges, checkBadges } = useXp()
+  const { hasLeveledUp, newLevel, resetLevelUp, syncFromBackend, hydrateFromSession, loadBadges, checkBadges } = useXp()
   const [levelUpDialogOpen, setLevelUpDialogOpen] = useState(false)
+  // Task opened from a todoapp://task/<id> link; shown once it is in the store
+  const [linkedTaskId, setLinkedTaskId] = useState<string | null>(null)
+  const linkedTask = linkedTaskId ? tasks.find((task) => task.id === linkedTaskId) ?? null : null
 
   // Watch for level-ups
   useEffect(() => {
@@ -46,17 +52,27 @@ function App() {
     resetLevelUp()
   }
 
+  useEffect(() => {
+    const handleOpenTask = (event: Event) => {
+      setLinkedTaskId((event as CustomEvent<{ taskId: string }>).detail.taskId)
+    }
+    window.addEventListener('open-task', handleOpenTask)
+    return () => window.removeEventListener('open-task', handleOpenTask)
+  }, [])
+
   // Initialize gamification on mount
   useEffect(() => {
     const initializeGamification = async () => {
       try {
-        // Always sync XP (has fallback for browser mode)
-        await syncFromBackend()
-        // Load badges
-        await loadBadges()
-        // Check for badges only in Tauri mode
         if (isTauri()) {
+          // One round trip for launch state, including the daily streak check
+          const session = await sessionStart()
+          hydrateFromSession(session.progress, session.badges)
           await checkBadges()
+        } else {
+          // Browser mode fallbacks
+          await syncFromBackend()
+          await loadBadges()
         }
       } catch (error) {
         console.error('Failed to initialize gamification:', error)
@@ -85,13 +101,63 @@ function App() {
           window.dispatchEvent(new CustomEvent('open-add-task'))
         })
 
-        const unlistenTheme = listen('toggle-theme', () => {
-          window.dispatchEvent(new CustomEvent('toggle-theme'))
+        // Tasks added from the separate quick-add window
+        const unlistenTaskCreated = listen('task-created', () => {
+          syncTasks().catch((error) => {
+            console.error('Failed to sync tasks:', error)
+          })
+        })
+
+        // Tasks completed, snoozed or re-prioritised from the tray menu
+        const unlistenTaskUpdated = listen('task-updated', () => {
+          syncTasks().catch((error) => {
+            console.error('Failed to sync tasks:', error)
+          })
+        })
+
+        // Disk full or the data folder lost write permission
+        const unlistenStorage = listen<StorageStatus>('storage-unwritable', (event) => {
+          toast({
+            title: "Can't save changes",
+            description: event.payload.message ?? 'The app data folder is not writable.',
+            variant: 'destructive',
+          })
+        })
+
+        // todo.db was changed by another program (e.g. a sync tool); offer to reload it
+        const unlistenDbModified = listen<{ reason: string }>('db-externally-modified', async (event) => {
+          if (!window.confirm(`${event.payload.reason}. Reload the database now?`)) return
+          try {
+            await reloadDatabase()
+            await syncTasks()
+            await syncTags()
+          } catch (error) {
+            console.error('Failed to reload database:', error)
+          }
         })
 
+        // Opened via a todoapp://task/<id> link while running; a launch link comes back from
+        // takeLaunchLink instead, so each link opens the task once
+        const unlistenNavigate = listen<string>('navigate-to-task', (event) => {
+          window.dispatchEvent(new CustomEvent('open-task', { detail: { taskId: event.payload } }))
+        })
+        takeLaunchLink()
+          .then((taskId) => {
+            if (taskId) {
+              window.dispatchEvent(new CustomEvent('open-task', { detail: { taskId } }))
+            }
+          })
+          .catch((error) => {
+            console.error('Failed to open launch link:', error)
+          })
+
         return () => {
           unlisten.then((fn) => fn())
-          unlistenTheme.then((fn) => fn())
+          unlistenNavigate.then((fn) => fn())
+          unlistenTaskCreated.then((fn) => fn())
+          unlistenTaskUpdated.then((fn) => fn())
+          unlistenStorage.then((fn) => fn())
+          unlistenDbModified.then((fn) => fn())
         }
       }).catch((error) => {
         console.error('Failed to set up event listeners:', error)
@@ -126,6 +192,13 @@ function App() {
           onOpenChange={handleLevelUpDialogClose}
           newLevel={newLevel || 1}
         />
+        <TaskDetailsModal
+          task={linkedTask}
+          open={linkedTask !== null}
+          onOpenChange={(open) => {
+            if (!open) setLinkedTaskId(null)
+          }}
+        />
       </div>
     </BrowserRouter>
   )
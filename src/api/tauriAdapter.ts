@@ -18,6 +18,28 @@ export interface Task {
   reminder_minutes_before?: number;
   notification_repeat?: boolean;
   tags?: Tag[];
+  is_blocked?: boolean;
+  blocker_count?: number;
+  // Set on update_task's response when the edit rescheduled the reminder
+  reminder_state?: 'scheduled' | 'fired_immediately' | 'skipped_past';
+  // Only set on single-task responses (get_task, create_task, update_task)
+  completion_note_required?: boolean;
+  // Only set by getTasks with collapse_recurring: instances in this task's recurrence chain
+  instance_count?: number;
+  // Set while the task is waiting on someone (see setWaiting)
+  waiting_on?: string;
+  waiting_since?: number;
+  // Pinned tasks are listed first
+  pinned?: boolean;
+  // Board column
+  status?: 'todo' | 'in_progress' | 'done';
+  // Recurrence rule, only set on single-task responses; weekdays are ISO (1 = Monday)
+  recurrence_days_of_week?: number[];
+  recurrence_end_date?: number;
+  recurrence_count?: number;
+  // Planned and tracked time, in minutes
+  estimated_minutes?: number;
+  actual_minutes?: number;
 }
 
 export interface Project {
@@ -26,6 +48,8 @@ export interface Project {
   color?: string;
   created_at: number;
   updated_at: number;
+  // Default for tasks created in the project
+  completion_note_required?: boolean;
 }
 
 export interface Subtask {
@@ -54,6 +78,24 @@ export interface Tag {
   color?: string;
   created_at: number;
   usage_count: number;
+  group_id?: string | null;
+}
+
+export interface TagGroup {
+  id: string;
+  name: string;
+  color?: string | null;
+  order_index: number;
+  created_at: number;
+}
+
+export interface TagGroupWithTags extends TagGroup {
+  tags: Tag[];
+}
+
+export interface GroupedTags {
+  groups: TagGroupWithTags[];
+  ungrouped: Tag[];
 }
 
 export interface TaskRelationship {
@@ -82,6 +124,39 @@ export interface TaskFilter {
   due_after?: number;
   search?: string;
   tag_id?: string;
+  with_block_status?: boolean;
+  // Newest matching instance per recurrence chain; expand with getRecurrenceInstances
+  collapse_recurring?: boolean;
+  // Full-text search ranked by relevance; every word must match as a prefix
+  fts_search?: string;
+  // Waiting tasks are hidden unless this is true
+  include_waiting?: boolean;
+  // Page size and start; all matching tasks when limit is absent
+  limit?: number;
+  offset?: number;
+  sort_by?: 'order' | 'due_date' | 'created_at' | 'updated_at' | 'priority' | 'title';
+  sort_dir?: 'asc' | 'desc';
+  // Open tasks past their due date, most overdue first unless sort_by is set
+  overdue_only?: boolean;
+  // true keeps only tasks with a (non-blank) description, attachments or subtasks; false only those without
+  has_description?: boolean;
+  has_attachments?: boolean;
+  has_subtasks?: boolean;
+  // Only the first 300 characters of each description, to keep list payloads small
+  description_preview_only?: boolean;
+}
+
+export interface TaskPage {
+  tasks: Task[];
+  // Matching tasks across all pages
+  total_count: number;
+}
+
+export interface TaskSearchResult extends Task {
+  // HTML: the text around the match, escaped, with matched words wrapped in <mark>
+  snippet: string;
+  // Lower is a better match
+  rank: number;
 }
 
 export interface CreateTaskInput {
@@ -94,6 +169,45 @@ export interface CreateTaskInput {
   recurrence_interval?: number;
   reminder_minutes_before?: number;
   notification_repeat?: boolean;
+  reminder_sound?: string;
+  reminder_urgency?: ReminderUrgency;
+  // Omit to use the project's default
+  completion_note_required?: boolean;
+  // Weekly recurrence on these ISO weekdays (1 = Monday)
+  recurrence_days_of_week?: number[];
+  // The chain stops after this date, or once it has recurrence_count tasks
+  recurrence_end_date?: number;
+  recurrence_count?: number;
+  estimated_minutes?: number;
+  actual_minutes?: number;
+}
+
+// Replaces the reminder fields of the task input
+export interface ReminderSpec {
+  minutes_before: number;
+  repeat?: boolean;
+  sound?: string;
+  urgency?: ReminderUrgency;
+}
+
+// Either a path on disk, or base64 content with a filename
+export interface AttachmentPayload {
+  path?: string;
+  filename?: string;
+  data_base64?: string;
+}
+
+export interface CreateTaskFullInput extends CreateTaskInput {
+  subtasks?: string[];
+  // Missing tags are created
+  tags?: string[];
+  reminder?: ReminderSpec;
+  attachment?: AttachmentPayload;
+}
+
+export interface TaskDetails extends Task {
+  subtasks: Subtask[];
+  attachments: Attachment[];
 }
 
 export interface UpdateTaskInput {
@@ -107,16 +221,48 @@ export interface UpdateTaskInput {
   recurrence_interval?: number;
   reminder_minutes_before?: number;
   notification_repeat?: boolean;
+  // Empty string goes back to the default sound
+  reminder_sound?: string;
+  reminder_urgency?: ReminderUrgency;
+  completion_note_required?: boolean;
+  // updated_at the editor loaded; the save is rejected with an EditConflict if it has changed
+  expected_updated_at?: number;
+  // Moving into a column at its WIP limit is rejected with a WipLimitExceeded unless force is set
+  status?: 'todo' | 'in_progress' | 'done';
+  force?: boolean;
+  // An empty list clears the weekdays; 0 clears the end date or count
+  recurrence_days_of_week?: number[];
+  recurrence_end_date?: number;
+  recurrence_count?: number;
+  // 0 clears the estimate or the tracked time
+  estimated_minutes?: number;
+  actual_minutes?: number;
+}
+
+export type ReminderUrgency = 'low' | 'normal' | 'critical'
+
+export interface ReminderStyle {
+  sound: string | null
+  urgency: ReminderUrgency
+}
+
+export interface NotificationSound {
+  id: string
+  label: string
 }
 
 export interface CreateProjectInput {
   name: string;
   color?: string;
+  completion_note_required?: boolean;
 }
 
 export interface UpdateProjectInput {
   name?: string;
   color?: string;
+  // Only affects tasks created afterwards
+  completion_note_required?: boolean;
+  expected_updated_at?: number;
 }
 
 export interface ImportSummary {
@@ -124,6 +270,20 @@ export interface ImportSummary {
   tasks_updated: number;
   projects_added: number;
   projects_updated: number;
+  badges_added: number;
+  xp_history_added: number;
+  pomodoro_sessions_added: number;
+  user_progress_updated: number;
+  pomodoro_streaks_updated: number;
+  tag_groups_added: number;
+  tags_added: number;
+  checklists_added: number;
+  rules_applied: number;
+  task_tags_added?: number;
+  task_relationships_added?: number;
+  templates_added?: number;
+  templates_updated?: number;
+  translation_overrides_imported?: number;
 }
 
 // Helper to convert Task with Unix timestamp to Date
@@ -144,6 +304,89 @@ export async function getTasks(filter?: TaskFilter): Promise<Task[]> {
   });
 }
 
+export async function getTasksPage(filter?: TaskFilter): Promise<TaskPage> {
+  return safeInvoke<TaskPage>('get_tasks_page', { filter: filter || null }, () =>
+    Promise.resolve({ tasks: [], total_count: 0 })
+  );
+}
+
+// Open tasks past their due date, most overdue first; the filter narrows them further
+export async function getOverdueTasks(filter?: TaskFilter): Promise<Task[]> {
+  return safeInvoke<Task[]>('get_overdue_tasks', { filter: filter || null }, () => Promise.resolve([]));
+}
+
+// Open tasks due today / this ISO week (Monday to Sunday), soonest first. Days run from
+// midnight UTC unless tzOffsetMinutes (east of UTC) is given.
+export async function getTasksDueToday(tzOffsetMinutes?: number): Promise<Task[]> {
+  return safeInvoke<Task[]>('get_tasks_due_today', { tzOffsetMinutes: tzOffsetMinutes ?? null }, () => Promise.resolve([]));
+}
+
+export async function getTasksDueThisWeek(tzOffsetMinutes?: number): Promise<Task[]> {
+  return safeInvoke<Task[]>('get_tasks_due_this_week', { tzOffsetMinutes: tzOffsetMinutes ?? null }, () => Promise.resolve([]));
+}
+
+// Count for the tray badge; archived and waiting tasks are left out
+export async function getOverdueCount(): Promise<number> {
+  return safeInvoke<number>('get_overdue_count', undefined, () => Promise.resolve(0));
+}
+
+// Searches titles, descriptions, subtask titles and attachment filenames, best match first
+export async function searchTasks(query: string, limit?: number): Promise<TaskSearchResult[]> {
+  return safeInvoke<TaskSearchResult[]>('search_tasks', { query, limit: limit ?? null }, () => Promise.resolve([]));
+}
+
+// Every instance of a recurrence chain, newest first; pass any task in the chain
+export async function getRecurrenceInstances(rootId: string, limit?: number, offset?: number): Promise<Task[]> {
+  return safeInvoke<Task[]>('get_recurrence_instances', { rootId, limit: limit ?? null, offset: offset ?? null }, () => Promise.resolve([]));
+}
+
+// Archived tasks are left out of getTasks; getTask still finds them by id
+export async function getArchivedTasks(filter?: TaskFilter): Promise<Task[]> {
+  return safeInvoke<Task[]>('get_archived_tasks', { filter: filter || null }, () => Promise.resolve([]));
+}
+
+export async function archiveTask(id: string): Promise<Task> {
+  return safeInvoke<Task>('archive_task', { id }, () => {
+    throw new Error('Tauri not available - cannot archive task in browser mode');
+  });
+}
+
+export async function unarchiveTask(id: string): Promise<Task> {
+  return safeInvoke<Task>('unarchive_task', { id }, () => {
+    throw new Error('Tauri not available - cannot unarchive task in browser mode');
+  });
+}
+
+export async function pinTask(id: string): Promise<Task> {
+  return safeInvoke<Task>('pin_task', { id }, () => {
+    throw new Error('Tauri not available - cannot pin task in browser mode');
+  });
+}
+
+export async function unpinTask(id: string): Promise<Task> {
+  return safeInvoke<Task>('unpin_task', { id }, () => {
+    throw new Error('Tauri not available - cannot unpin task in browser mode');
+  });
+}
+
+// Pinned tasks, most recently changed first
+export async function getPinnedTasks(): Promise<Task[]> {
+  return safeInvoke<Task[]>('get_pinned_tasks', {}, () => Promise.resolve([]));
+}
+
+// Hide the task until it is no longer waiting; a follow-up reminder fires after followUpDays
+export async function setWaiting(id: string, waitingOn: string, followUpDays?: number): Promise<Task> {
+  return safeInvoke<Task>('set_waiting', { id, waitingOn, followUpDays: followUpDays ?? null }, () => {
+    throw new Error('Tauri not available - cannot set waiting in browser mode');
+  });
+}
+
+export async function clearWaiting(id: string): Promise<Task> {
+  return safeInvoke<Task>('clear_waiting', { id }, () => {
+    throw new Error('Tauri not available - cannot clear waiting in browser mode');
+  });
+}
+
 export async function getTask(id: string): Promise<Task> {
   return safeInvoke<Task>('get_task', { id }, () => {
     throw new Error('Tauri not available - cannot get task in browser mode');
@@ -157,6 +400,14 @@ export async function createTask(input: CreateTaskInput): Promise<Task> {
   return taskFromRust(task) as Task;
 }
 
+// Creates the task with its subtasks, tags, reminder and attachment in one transaction
+export async function createTaskFull(input: CreateTaskFullInput): Promise<TaskDetails> {
+  const details = await safeInvoke<TaskDetails>('create_task_full', { input }, () => {
+    throw new Error('Tauri not available - cannot create task in browser mode');
+  });
+  return taskFromRust(details) as TaskDetails;
+}
+
 export async function updateTask(id: string, input: UpdateTaskInput): Promise<Task> {
   const task = await safeInvoke<Task>('update_task', { id, input }, () => {
     throw new Error('Tauri not available - cannot update task in browser mode');
@@ -170,13 +421,224 @@ export async function deleteTask(id: string): Promise<void> {
   });
 }
 
-export async function toggleComplete(id: string): Promise<Task> {
-  const task = await safeInvoke<Task>('toggle_complete', { id }, () => {
+// Restores the most recent delete this session (task, project, subtask, tag or tag removal).
+// Resolves to a description for a toast, or null if there was nothing to undo.
+export async function undoLastOperation(): Promise<string | null> {
+  return safeInvoke<string | null>('undo_last_operation', undefined, () => Promise.resolve(null));
+}
+
+// Deletes all tasks in one transaction; resolves to how many existed and were deleted
+export async function bulkDeleteTasks(ids: string[]): Promise<number> {
+  return safeInvoke<number>('bulk_delete_tasks', { ids }, () => {
+    throw new Error('Tauri not available - cannot delete tasks in browser mode');
+  });
+}
+
+// Tasks whose title is blank or a placeholder like "Untitled", archived ones included
+export async function findEmptyTasks(): Promise<Task[]> {
+  return safeInvoke<Task[]>('find_empty_tasks', undefined, () => Promise.resolve([]));
+}
+
+// Deletes the given empty tasks (or all of them) as one undoable step; tasks that have a real
+// title by now are kept. Resolves to how many were deleted.
+export async function deleteEmptyTasks(ids?: string[]): Promise<number> {
+  return safeInvoke<number>('delete_empty_tasks', { ids: ids ?? null }, () => {
+    throw new Error('Tauri not available - cannot delete tasks in browser mode');
+  });
+}
+
+// Returns how many tasks were updated; XP for completed tasks follows the new priority
+export async function bulkUpdatePriority(ids: string[], priority: 'low' | 'medium' | 'high'): Promise<number> {
+  return safeInvoke<number>('bulk_update_priority', { ids, priority }, () => {
+    throw new Error('Tauri not available - cannot update tasks in browser mode');
+  });
+}
+
+// Moves due dates by `days` (negative for earlier) for a project's open tasks, the given tasks,
+// or the given tasks within the project; returns how many moved
+export async function shiftTaskDueDates(days: number, projectId?: string, ids?: string[]): Promise<number> {
+  return safeInvoke<number>('shift_task_due_dates', { projectId: projectId ?? null, ids: ids ?? null, days }, () => {
+    throw new Error('Tauri not available - cannot update tasks in browser mode');
+  });
+}
+
+// Omitted flags keep the defaults: subtasks and tags are copied, attachments aren't
+export interface DuplicateTaskOptions {
+  include_subtasks?: boolean;
+  include_tags?: boolean;
+  // Copies the files too, so the duplicate doesn't share them with the original
+  include_attachments?: boolean;
+}
+
+// Copies the task with its subtasks (unchecked) and tags; the title defaults to "<title> (copy)"
+export async function duplicateTask(id: string, newTitle?: string, options?: DuplicateTaskOptions): Promise<Task> {
+  return safeInvoke<Task>('duplicate_task', { id, newTitle: newTitle ?? null, options: options ?? null }, () => {
+    throw new Error('Tauri not available - cannot duplicate task in browser mode');
+  });
+}
+
+export interface ActivityLogEntry {
+  id: string;
+  task_id: string;
+  // e.g. 'title_changed', 'completed', 'tag_added'
+  action: string;
+  old_value?: string;
+  new_value?: string;
+  // What made the change: 'user', 'aging', 'rule', ...
+  source: string;
+  created_at: number;
+}
+
+// Newest first; limit defaults to 100
+export async function getTaskActivityLog(taskId: string, limit?: number): Promise<ActivityLogEntry[]> {
+  return safeInvoke<ActivityLogEntry[]>('get_task_activity_log', { taskId, limit: limit ?? null }, () => Promise.resolve([]));
+}
+
+export async function clearTaskActivityLog(taskId: string): Promise<void> {
+  return safeInvoke<void>('clear_task_activity_log', { taskId }, () => {
+    throw new Error('Tauri not available - cannot clear activity log in browser mode');
+  });
+}
+
+// Moves a task to just before beforeTaskId in its project, or to the end; usually writes only that task
+export async function reorderTasks(taskId: string, beforeTaskId?: string): Promise<Task> {
+  return safeInvoke<Task>('reorder_tasks', { taskId, beforeTaskId: beforeTaskId ?? null }, () => {
+    throw new Error('Tauri not available - cannot reorder tasks in browser mode');
+  });
+}
+
+// Sets the order index of every [taskId, orderIndex] pair in one transaction; fails without
+// changing anything if a task is missing
+export async function reorderTasksBatch(orderMap: [string, number][]): Promise<void> {
+  return safeInvoke<void>('reorder_tasks_batch', { orderMap }, () => {
+    throw new Error('Tauri not available - cannot reorder tasks in browser mode');
+  });
+}
+
+// Rewrites the order of a whole list after drag and drop and resolves to the tasks in their new
+// order. With projectId ('' for the inbox) every task must be in that project; unlisted tasks
+// keep their place, and nothing changes if any id is unknown.
+export async function reorderTaskList(ids: string[], projectId?: string): Promise<Task[]> {
+  return safeInvoke<Task[]>('reorder_task_list', { ids, projectId: projectId ?? null }, () => {
+    throw new Error('Tauri not available - cannot reorder tasks in browser mode');
+  });
+}
+
+// Renumbers order indexes keeping the current order: one project, the inbox (''), or everything
+// when projectId is omitted. Resolves to how many tasks changed.
+export async function normalizeOrderIndexes(projectId?: string): Promise<number> {
+  return safeInvoke<number>('normalize_order_indexes', { projectId: projectId ?? null }, () => Promise.resolve(0));
+}
+
+// Descriptions longer than the max_description_length setting (20000 characters by default)
+// are rejected on create/update; these are older rows that exceeded it
+export interface TruncatedDescription {
+  task_id: string;
+  title: string;
+  original_length: number;
+  moved_length: number;
+  // Markdown attachment holding the rest; null in a dry run
+  attachment_id: string | null;
+}
+
+// Cuts over-long descriptions, moving the rest into a .md attachment; dryRun only reports them
+export async function truncateLongDescriptions(dryRun: boolean): Promise<TruncatedDescription[]> {
+  return safeInvoke<TruncatedDescription[]>('truncate_long_descriptions', { dryRun }, () => Promise.resolve([]));
+}
+
+export interface CompletionWarning {
+  kind: 'open_subtasks' | 'open_blockers' | 'pending_reminders';
+  count: number;
+  samples: string[];
+}
+
+export async function getCompletionWarnings(id: string): Promise<CompletionWarning[]> {
+  return safeInvoke<CompletionWarning[]>('get_completion_warnings', { id }, () => Promise.resolve([]));
+}
+
+// toggle_complete rejects with this when the task requires a note and none is given;
+// skipNote completes it anyway with a "(no note)" marker. acknowledge only covers completion warnings
+export const COMPLETION_NOTE_REQUIRED = 'completion_note_required'
+
+export async function toggleComplete(id: string, acknowledge?: boolean, note?: string, skipNote?: boolean): Promise<Task> {
+  const task = await safeInvoke<Task>('toggle_complete', { id, acknowledge: acknowledge ?? null, note: note ?? null, skipNote: skipNote ?? null }, () => {
     throw new Error('Tauri not available - cannot toggle task in browser mode');
   });
   return taskFromRust(task) as Task;
 }
 
+export interface ToggleCompleteResult {
+  task: Task;
+  completed: boolean;
+  xp_granted: number;
+  xp_revoked: number;
+  level_up: boolean;
+  new_level: number;
+  total_xp: number;
+  streak_extended: boolean;
+  current_streak: number;
+  first_completion_today: boolean;
+  new_badges: Badge[];
+  current_task_cleared: boolean;
+}
+
+export async function toggleCompleteDetailed(id: string, acknowledge?: boolean, note?: string, skipNote?: boolean): Promise<ToggleCompleteResult> {
+  const result = await safeInvoke<ToggleCompleteResult>('toggle_complete_detailed', { id, acknowledge: acknowledge ?? null, note: note ?? null, skipNote: skipNote ?? null }, () => {
+    throw new Error('Tauri not available - cannot toggle task in browser mode');
+  });
+  return { ...result, task: taskFromRust(result.task) as Task };
+}
+
+// Closes an open recurring instance without completing it (no XP, it is archived) and creates
+// the next one; resolves to that, or null when the recurrence has ended
+export async function skipRecurringInstance(id: string): Promise<Task | null> {
+  const task = await safeInvoke<Task | null>('skip_recurring_instance', { id }, () => {
+    throw new Error('Tauri not available - cannot skip task in browser mode');
+  });
+  return task ? (taskFromRust(task) as Task) : null;
+}
+
+export interface UpdateSeriesInput {
+  title?: string;
+  description?: string;
+  priority?: 'low' | 'medium' | 'high';
+  recurrence_type?: string;
+  recurrence_interval?: number;
+  recurrence_days_of_week?: number[];
+  recurrence_end_date?: number;
+  recurrence_count?: number;
+}
+
+// Applies the edits to every open task in the recurrence chain; completed instances are left alone
+export async function updateRecurringSeries(id: string, input: UpdateSeriesInput): Promise<Task[]> {
+  return safeInvoke<Task[]>('update_recurring_series', { id, input }, () => {
+    throw new Error('Tauri not available - cannot update tasks in browser mode');
+  });
+}
+
+export async function setCurrentTask(taskId: string | null): Promise<Task | null> {
+  const task = await safeInvoke<Task | null>('set_current_task', { taskId }, () => {
+    throw new Error('Tauri not available - cannot set current task in browser mode');
+  });
+  return task ? (taskFromRust(task) as Task) : null;
+}
+
+export async function getCurrentTask(): Promise<Task | null> {
+  const task = await safeInvoke<Task | null>('get_current_task', undefined, () => Promise.resolve(null));
+  return task ? (taskFromRust(task) as Task) : null;
+}
+
+export async function quickAdd(text: string, projectId?: string): Promise<Task> {
+  const task = await safeInvoke<Task>('quick_add', { text, projectId: projectId || null }, () => {
+    throw new Error('Tauri not available - cannot create task in browser mode');
+  });
+  return taskFromRust(task) as Task;
+}
+
+export async function closeQuickAddWindow(): Promise<void> {
+  return safeInvoke<void>('close_quick_add_window', undefined, () => Promise.resolve());
+}
+
 // Project commands
 export async function getProjects(): Promise<Project[]> {
   return safeInvoke<Project[]>('get_projects', undefined, () => Promise.resolve([]));
@@ -207,6 +669,12 @@ export async function addSubtask(taskId: string, title: string): Promise<Subtask
   });
 }
 
+export async function addSubtasksBulk(taskId: string, text: string): Promise<Subtask[]> {
+  return safeInvoke<Subtask[]>('add_subtasks_bulk', { taskId, text }, () => {
+    throw new Error('Tauri not available');
+  });
+}
+
 export async function updateSubtask(
   id: string,
   title?: string,
@@ -315,9 +783,21 @@ export async function updateSettings(key: string, value: string): Promise<void>
   });
 }
 
+// "light" or "dark" with the "system" theme setting resolved; also sent as theme-changed whenever it changes
+export async function getEffectiveTheme(): Promise<'light' | 'dark'> {
+  return safeInvoke<'light' | 'dark'>('get_effective_theme', undefined, () =>
+    Promise.resolve(window.matchMedia('(prefers-color-scheme: dark)').matches ? 'dark' : 'light')
+  );
+}
+
+export interface ThemeChangedEvent {
+  preference: 'light' | 'dark' | 'system';
+  theme: 'light' | 'dark';
+}
+
 // Backup commands
-export async function createBackup(): Promise<string> {
-  return safeInvoke<string>('create_backup', undefined, () => {
+export async function createBackup(operationId?: string): Promise<string> {
+  return safeInvoke<string>('create_backup', { operationId: operationId ?? null }, () => {
     throw new Error('Tauri not available - backups not supported in browser mode');
   });
 }
@@ -328,19 +808,191 @@ export async function restoreBackup(backupPath: string): Promise<void> {
   });
 }
 
+export interface DbInfo {
+  path: string | null;
+  size_bytes: number | null;
+  modified_at: number | null;
+  last_write_at: number | null;
+  externally_modified: boolean;
+  warning: string | null;
+}
+
+export async function reloadDatabase(): Promise<void> {
+  return safeInvoke<void>('reload_database', undefined, () => {
+    throw new Error('Tauri not available - reload not supported in browser mode');
+  });
+}
+
+export type ResetScope = 'gamification' | 'pomodoro' | 'translations' | 'statistics_caches' | 'everything';
+
+export interface ResetTokenResponse {
+  scope: ResetScope;
+  token: string;
+  // Unix seconds; the token is single-use
+  expires_at: number;
+}
+
+export interface ResetReport {
+  scope: ResetScope;
+  // Rows removed per table (user_progress rows are zeroed instead)
+  deleted: Record<string, number>;
+  // Safety backup taken before an "everything" reset
+  backup_path?: string | null;
+  // Attachment files and thumbnails an "everything" reset moved next to the backup
+  files_backup_path?: string | null;
+  // App data folders that could not be moved and were kept in place
+  kept_files: string[];
+}
+
+export async function requestResetToken(scope: ResetScope): Promise<ResetTokenResponse> {
+  return safeInvoke<ResetTokenResponse>('request_reset_token', { scope }, () => {
+    throw new Error('Tauri not available - reset not supported in browser mode');
+  });
+}
+
+// Call requestResetToken first and pass its token; "everything" reconnects to a fresh database
+export async function resetAppData(scope: ResetScope, token: string): Promise<ResetReport> {
+  return safeInvoke<ResetReport>('reset_app_data', { scope, token }, () => {
+    throw new Error('Tauri not available - reset not supported in browser mode');
+  });
+}
+
+export async function getDbInfo(): Promise<DbInfo> {
+  return safeInvoke<DbInfo>('get_db_info', undefined, () => {
+    throw new Error('Tauri not available - database info not supported in browser mode');
+  });
+}
+
 // Export/Import commands
-export async function exportData(): Promise<string> {
-  return safeInvoke<string>('export_data', undefined, () => {
+// includeTranslationOverrides adds hand-corrected translations to the export file
+export type ExportFormat = 'json' | 'csv' | 'markdown';
+
+// Resolves to the path of the written file; translation overrides only go into JSON exports
+export async function exportData(includeTranslationOverrides = false, exportFormat: ExportFormat = 'json'): Promise<string> {
+  return safeInvoke<string>('export_data', { includeTranslationOverrides, exportFormat }, () => {
+    throw new Error('Tauri not available - export not supported in browser mode');
+  });
+}
+
+export type CsvExportColumn =
+  | 'id'
+  | 'title'
+  | 'description'
+  | 'priority'
+  | 'due_date'
+  | 'completed'
+  | 'project_name'
+  | 'tags'
+  | 'created_at'
+  | 'updated_at';
+
+// Writes only the given columns, in that order; without a filter every task is exported
+export async function exportToCsv(columns: CsvExportColumn[], filter?: TaskFilter): Promise<string> {
+  return safeInvoke<string>('export_to_csv', { columns, filter: filter ?? null }, () => {
     throw new Error('Tauri not available - export not supported in browser mode');
   });
 }
 
-export async function importData(filePath: string): Promise<ImportSummary> {
-  return safeInvoke<ImportSummary>('import_data', { filePath }, () => {
+export async function importData(filePath: string, operationId?: string): Promise<ImportSummary> {
+  return safeInvoke<ImportSummary>('import_data', { filePath, operationId: operationId ?? null }, () => {
+    throw new Error('Tauri not available - import not supported in browser mode');
+  });
+}
+
+// Column names feeding each task field, as reported by inspectImportFile; null leaves it empty
+export interface ImportMapping {
+  title?: string | null;
+  description?: string | null;
+  due?: string | null;
+  priority?: string | null;
+  // Comma-separated tag names
+  tags?: string | null;
+  // Project name; missing projects are created
+  project?: string | null;
+}
+
+export interface ImportColumn {
+  name: string;
+  samples: string[];
+}
+
+export interface ImportInspection {
+  format: string;
+  columns: ImportColumn[];
+  proposed_mapping: ImportMapping;
+  row_count: number;
+  encoding_issues: string[];
+}
+
+export interface FileImportSummary {
+  imported: number;
+  skipped: number;
+  projects_created: number;
+  warnings: string[];
+}
+
+// format is "csv" or "json"
+export async function inspectImportFile(path: string, format: string): Promise<ImportInspection> {
+  return safeInvoke<ImportInspection>('inspect_import_file', { path, format }, () => {
+    throw new Error('Tauri not available - import not supported in browser mode');
+  });
+}
+
+// Without a mapping the proposed one from inspectImportFile is used
+export async function importTasksFromFile(path: string, format: string, mapping?: ImportMapping): Promise<FileImportSummary> {
+  return safeInvoke<FileImportSummary>('import_tasks_from_file', { path, format, mapping: mapping ?? null }, () => {
+    throw new Error('Tauri not available - import not supported in browser mode');
+  });
+}
+
+// A Todoist export is one project, named after the file; TickTick backups hold every list
+export type ExternalImportSource = 'todoist_csv' | 'ticktick_csv';
+
+export interface FailedImportRow {
+  // Record in the file, header included
+  row: number;
+  reason: string;
+}
+
+export interface ExternalImportSummary {
+  source: ExternalImportSource;
+  imported: number;
+  skipped: number;
+  projects_created: number;
+  tags_created: number;
+  failed_rows: FailedImportRow[];
+  warnings: string[];
+}
+
+export async function importExternal(path: string, source: ExternalImportSource): Promise<ExternalImportSummary> {
+  return safeInvoke<ExternalImportSummary>('import_external', { path, source }, () => {
     throw new Error('Tauri not available - import not supported in browser mode');
   });
 }
 
+// Long-running commands given an operationId emit these events until they finish
+export const OPERATION_PROGRESS_EVENT = 'operation-progress'
+export const OPERATION_FINISHED_EVENT = 'operation-finished'
+// Error of an operation stopped by cancelOperation
+export const OPERATION_CANCELLED = 'operation_cancelled'
+
+export interface OperationProgress {
+  operation_id: string
+  stage: string
+  done: number
+  total: number
+}
+
+export interface OperationFinished<T = unknown> {
+  operation_id: string
+  result: T | null
+  error: string | null
+}
+
+export async function cancelOperation(operationId: string): Promise<boolean> {
+  return safeInvoke<boolean>('cancel_operation', { operationId }, () => Promise.resolve(false))
+}
+
 // Notification command
 export async function showNotification(title: string, body: string): Promise<void> {
   return safeInvoke<void>('show_notification', { title, body }, () => {
@@ -364,6 +1016,13 @@ export interface CompletionStats {
   count: number
 }
 
+// Only days with completions; intensity is 1-5 by quartile of the year's busy days
+export interface HeatmapEntry {
+  date: string
+  count: number
+  intensity: number
+}
+
 export interface PriorityDistribution {
   priority: string
   count: number
@@ -387,37 +1046,111 @@ export interface MostProductiveDay {
   count: number
 }
 
-export async function getCompletionStats(days: number): Promise<CompletionStats[]> {
-  return safeInvoke<CompletionStats[]>('get_completion_stats', { days }, () => Promise.resolve([]))
+export async function getCompletionStats(
+  days: number,
+  includeDeleted?: boolean
+): Promise<CompletionStats[]> {
+  return safeInvoke<CompletionStats[]>('get_completion_stats', { days, includeDeleted }, () =>
+    Promise.resolve([])
+  )
+}
+
+export async function getCompletionHeatmap(
+  year: number,
+  includeDeleted?: boolean
+): Promise<HeatmapEntry[]> {
+  return safeInvoke<HeatmapEntry[]>('get_completion_heatmap', { year, includeDeleted }, () =>
+    Promise.resolve([])
+  )
+}
+
+// Oldest week first; tasks_overdue counts tasks due that week that weren't done in time
+export interface WeeklySummary {
+  week_start: number
+  tasks_created: number
+  tasks_completed: number
+  tasks_overdue: number
+  xp_earned: number
+  pomodoro_sessions: number
+}
+
+export async function getWeeklySummary(
+  weeksBack: number,
+  includeDeleted?: boolean
+): Promise<WeeklySummary[]> {
+  return safeInvoke<WeeklySummary[]>('get_weekly_summary', { weeksBack, includeDeleted }, () =>
+    Promise.resolve([])
+  )
+}
+
+// Today at a glance; text is the same line the tray tooltip shows
+export interface StatusLine {
+  current_streak: number
+  completed_today: number
+  daily_goal: number
+  focus_minutes_today: number
+  level: number
+  text: string
+}
+
+export async function getStatusLine(): Promise<StatusLine | null> {
+  return safeInvoke<StatusLine | null>('get_status_line', undefined, () => Promise.resolve(null))
 }
 
-export async function getPriorityDistribution(): Promise<PriorityDistribution[]> {
-  return safeInvoke<PriorityDistribution[]>('get_priority_distribution', undefined, () =>
+export async function getPriorityDistribution(
+  includeDeleted?: boolean
+): Promise<PriorityDistribution[]> {
+  return safeInvoke<PriorityDistribution[]>('get_priority_distribution', { includeDeleted }, () =>
     Promise.resolve([])
   )
 }
 
-export async function getProjectStats(): Promise<ProjectStats[]> {
-  return safeInvoke<ProjectStats[]>('get_project_stats', undefined, () => Promise.resolve([]))
+export async function getProjectStats(includeDeleted?: boolean): Promise<ProjectStats[]> {
+  return safeInvoke<ProjectStats[]>('get_project_stats', { includeDeleted }, () =>
+    Promise.resolve([])
+  )
 }
 
 export async function getProductivityTrend(
   startDate: number,
-  endDate: number
+  endDate: number,
+  includeDeleted?: boolean
 ): Promise<ProductivityTrend[]> {
-  return safeInvoke<ProductivityTrend[]>('get_productivity_trend', { startDate, endDate }, () =>
-    Promise.resolve([])
+  return safeInvoke<ProductivityTrend[]>(
+    'get_productivity_trend',
+    { startDate, endDate, includeDeleted },
+    () => Promise.resolve([])
   )
 }
 
-export async function getMostProductiveDay(): Promise<MostProductiveDay | null> {
-  return safeInvoke<MostProductiveDay | null>('get_most_productive_day', undefined, () =>
+export async function getMostProductiveDay(
+  includeDeleted?: boolean
+): Promise<MostProductiveDay | null> {
+  return safeInvoke<MostProductiveDay | null>('get_most_productive_day', { includeDeleted }, () =>
     Promise.resolve(null)
   )
 }
 
-export async function getAverageCompletionTime(): Promise<number> {
-  return safeInvoke<number>('get_average_completion_time', undefined, () => Promise.resolve(0))
+export async function getAverageCompletionTime(includeDeleted?: boolean): Promise<number> {
+  return safeInvoke<number>('get_average_completion_time', { includeDeleted }, () =>
+    Promise.resolve(0)
+  )
+}
+
+export interface TimeEstimationRow {
+  task_id: string
+  title: string
+  estimated_minutes: number
+  actual_minutes: number
+  // Positive when the task ran over its estimate
+  deviation_minutes: number
+  deviation_percent: number
+  completed_at?: number
+}
+
+// Estimated vs actual minutes for every task that has both, most recently finished first
+export async function getTimeEstimationAccuracy(): Promise<TimeEstimationRow[]> {
+  return safeInvoke<TimeEstimationRow[]>('get_time_estimation_accuracy', undefined, () => Promise.resolve([]))
 }
 
 // Autostart commands
@@ -440,6 +1173,8 @@ export interface Template {
   description?: string
   priority: string
   project_id?: string
+  // Only shown inside this project; unset for global templates
+  scope_project_id?: string | null
   recurrence_type?: string
   created_at: number
   updated_at: number
@@ -451,6 +1186,7 @@ export interface CreateTemplateInput {
   description?: string
   priority: string
   project_id?: string
+  scope_project_id?: string
   recurrence_type?: string
 }
 
@@ -460,11 +1196,15 @@ export interface UpdateTemplateInput {
   description?: string
   priority?: string
   project_id?: string
+  // Empty string makes the template global again
+  scope_project_id?: string
   recurrence_type?: string
+  expected_updated_at?: number
 }
 
-export async function getTemplates(): Promise<Template[]> {
-  return safeInvoke<Template[]>('get_templates', undefined, () => Promise.resolve([]))
+// With a project id, returns global templates plus those scoped to that project
+export async function getTemplates(projectId?: string): Promise<Template[]> {
+  return safeInvoke<Template[]>('get_templates', { projectId: projectId ?? null }, () => Promise.resolve([]))
 }
 
 export async function getTemplate(id: string): Promise<Template> {
@@ -493,9 +1233,10 @@ export async function deleteTemplate(id: string): Promise<void> {
 
 export async function createTaskFromTemplate(
   templateId: string,
-  dueDate?: number
+  dueDate?: number,
+  projectId?: string
 ): Promise<Task> {
-  return safeInvoke<Task>('create_task_from_template', { templateId, dueDate }, () => {
+  return safeInvoke<Task>('create_task_from_template', { templateId, dueDate, projectId: projectId ?? null }, () => {
     throw new Error('Tauri not available - cannot create task from template in browser mode')
   })
 }
@@ -626,6 +1367,31 @@ export async function saveTranslationOverride(
   )
 }
 
+export interface TranslationOverridesExport {
+  path: string
+  exported: number
+}
+
+export interface TranslationOverridesImport {
+  added: number
+  updated: number
+  unchanged: number
+}
+
+// Writes every hand-corrected translation to path as JSON
+export async function exportTranslationOverrides(path: string): Promise<TranslationOverridesExport> {
+  return safeInvoke<TranslationOverridesExport>('export_translation_overrides', { path }, () => {
+    throw new Error('Translation is only available in Tauri desktop app')
+  })
+}
+
+// Accepts a file from exportTranslationOverrides or an exportData file that includes them
+export async function importTranslationOverrides(path: string): Promise<TranslationOverridesImport> {
+  return safeInvoke<TranslationOverridesImport>('import_translation_overrides', { path }, () => {
+    throw new Error('Translation is only available in Tauri desktop app')
+  })
+}
+
 export async function getTranslation(
   taskId: string,
   field: 'title' | 'description',
@@ -663,16 +1429,62 @@ export async function deleteTag(tagId: string): Promise<void> {
   })
 }
 
-export async function addTagToTask(taskId: string, tagId: string): Promise<void> {
-  return safeInvoke<void>('add_tag_to_task', { taskId, tagId }, () => {
-    throw new Error('Tauri not available - cannot add tag to task in browser mode')
+/**
+ * Update a tag. Pass group_id '' to ungroup it.
+ */
+export async function updateTag(
+  tagId: string,
+  input: { name?: string; color?: string; group_id?: string }
+): Promise<Tag> {
+  return safeInvoke<Tag>('update_tag', { tagId, input }, () => {
+    throw new Error('Tauri not available - cannot update tag in browser mode')
   })
 }
 
-export async function removeTagFromTask(taskId: string, tagId: string): Promise<void> {
-  return safeInvoke<void>('remove_tag_from_task', { taskId, tagId }, () => {
-    throw new Error('Tauri not available - cannot remove tag from task in browser mode')
-  })
+export async function getTagsGrouped(): Promise<GroupedTags> {
+  return safeInvoke<GroupedTags>('get_tags_grouped', undefined, () =>
+    Promise.resolve({ groups: [], ungrouped: [] })
+  )
+}
+
+export async function getTagGroups(): Promise<TagGroup[]> {
+  return safeInvoke<TagGroup[]>('get_tag_groups', undefined, () => Promise.resolve([]))
+}
+
+export async function createTagGroup(input: { name: string; color?: string }): Promise<TagGroup> {
+  return safeInvoke<TagGroup>('create_tag_group', { input }, () => {
+    throw new Error('Tauri not available - cannot create tag group in browser mode')
+  })
+}
+
+export async function updateTagGroup(
+  id: string,
+  input: { name?: string; color?: string; order_index?: number }
+): Promise<TagGroup> {
+  return safeInvoke<TagGroup>('update_tag_group', { id, input }, () => {
+    throw new Error('Tauri not available - cannot update tag group in browser mode')
+  })
+}
+
+/**
+ * Delete a tag group. Its tags are kept and become ungrouped.
+ */
+export async function deleteTagGroup(id: string): Promise<void> {
+  return safeInvoke<void>('delete_tag_group', { id }, () => {
+    throw new Error('Tauri not available - cannot delete tag group in browser mode')
+  })
+}
+
+export async function addTagToTask(taskId: string, tagId: string): Promise<void> {
+  return safeInvoke<void>('add_tag_to_task', { taskId, tagId }, () => {
+    throw new Error('Tauri not available - cannot add tag to task in browser mode')
+  })
+}
+
+export async function removeTagFromTask(taskId: string, tagId: string): Promise<void> {
+  return safeInvoke<void>('remove_tag_from_task', { taskId, tagId }, () => {
+    throw new Error('Tauri not available - cannot remove tag from task in browser mode')
+  })
 }
 
 export async function getSuggestedTags(search: string): Promise<Tag[]> {
@@ -854,3 +1666,1026 @@ export async function getPomodoroStreak(): Promise<PomodoroStreak> {
     last_session_date: undefined,
   }))
 }
+
+// Focus history of one task for the detail pane; break sessions are not counted
+export interface TaskPomodoroSummary {
+  task_id: string
+  pomodoro_count: number
+  completed_count: number
+  total_minutes: number
+  average_minutes: number
+  last_session_at: number | null
+  recent_sessions: PomodoroSession[]
+}
+
+export async function getTaskPomodoroSummary(taskId: string): Promise<TaskPomodoroSummary> {
+  return safeInvoke<TaskPomodoroSummary>('get_task_pomodoro_summary', { taskId }, () => ({
+    task_id: taskId,
+    pomodoro_count: 0,
+    completed_count: 0,
+    total_minutes: 0,
+    average_minutes: 0,
+    last_session_at: null,
+    recent_sessions: [],
+  }))
+}
+
+// Priority aging
+export interface AgedTask {
+  task_id: string
+  title: string
+  old_priority: 'low' | 'medium' | 'high'
+  new_priority: 'low' | 'medium' | 'high'
+  updated_at: number
+}
+
+export interface PriorityAgingPreview {
+  enabled: boolean
+  threshold_days: number
+  exempt_undated: boolean
+  tasks: AgedTask[]
+}
+
+export async function previewPriorityAging(): Promise<PriorityAgingPreview> {
+  return safeInvoke<PriorityAgingPreview>('preview_priority_aging', undefined, () => ({
+    enabled: false,
+    threshold_days: 14,
+    exempt_undated: false,
+    tasks: [],
+  }))
+}
+
+// Dependency conflicts and health check
+export interface DependencyConflict {
+  relationship_id: string
+  blocker_id: string
+  blocker_title: string
+  blocker_due_at: number | null
+  blocked_id: string
+  blocked_title: string
+  blocked_due_at: number
+}
+
+export type DependencyFixStrategy = 'move_blocker_earlier' | 'clear_blocked_due_date'
+
+export interface HealthReport {
+  checked_at: number
+  dependency_conflicts: DependencyConflict[]
+  missing_attachments: Attachment[]
+  storage: StorageStatus | null
+  // Fixed by normalizeOrderIndexes
+  duplicate_order_indexes: DuplicateOrderIndex[]
+}
+
+export interface DuplicateOrderIndex {
+  project_id: string | null
+  order_index: number
+  task_count: number
+}
+
+export type StorageErrorKind = 'disk_full' | 'permission_denied' | 'read_only' | 'other'
+
+export interface StorageStatus {
+  path: string
+  writable: boolean
+  error: { kind: StorageErrorKind; action: string; message?: string } | null
+  message: string | null
+}
+
+export async function getDependencyConflicts(): Promise<DependencyConflict[]> {
+  return safeInvoke<DependencyConflict[]>('get_dependency_conflicts', undefined, () =>
+    Promise.resolve([])
+  )
+}
+
+export async function fixDependencyConflict(
+  relationshipId: string,
+  strategy: DependencyFixStrategy
+): Promise<Task> {
+  const task = await safeInvoke<Task>('fix_dependency_conflict', { relationshipId, strategy }, () => {
+    throw new Error('Tauri not available - cannot update tasks in browser mode')
+  })
+  return taskFromRust(task) as Task
+}
+
+export async function getDbHealth(): Promise<HealthReport> {
+  return safeInvoke<HealthReport>('get_db_health', undefined, () => ({
+    checked_at: Math.floor(Date.now() / 1000),
+    dependency_conflicts: [],
+    missing_attachments: [],
+    storage: null,
+    duplicate_order_indexes: [],
+  }))
+}
+
+export interface DiagnosticBundle {
+  path: string
+  entries: string[]
+  size_bytes: number
+}
+
+// Zip of schema, row counts, migrations, db info, health check and recent logs for bug reports.
+// includeDatabase adds a copy of the database with personal text replaced by hashes.
+export async function generateDiagnosticBundle(path: string, includeDatabase?: boolean): Promise<DiagnosticBundle> {
+  return safeInvoke<DiagnosticBundle>('generate_diagnostic_bundle', { path, includeDatabase: includeDatabase ?? null }, () => {
+    throw new Error('Tauri not available - diagnostic bundles need the desktop app')
+  })
+}
+
+export interface AttachmentBackfillResult {
+  scanned: number
+  updated: number
+  missing: number
+}
+
+export async function backfillAttachmentMetadata(): Promise<AttachmentBackfillResult> {
+  return safeInvoke<AttachmentBackfillResult>('backfill_attachment_metadata', undefined, () => ({
+    scanned: 0,
+    updated: 0,
+    missing: 0,
+  }))
+}
+
+// Vacation mode
+export interface VacationPeriod {
+  id: string
+  start_date: number
+  end_date: number
+  created_at: number
+}
+
+export interface VacationStatus {
+  on_vacation: boolean
+  current: VacationPeriod | null
+  next: VacationPeriod | null
+}
+
+export async function setVacationMode(start: number, end: number): Promise<VacationPeriod> {
+  return safeInvoke<VacationPeriod>('set_vacation_mode', { start, end }, () => {
+    throw new Error('Tauri not available - vacation mode not supported in browser mode')
+  })
+}
+
+export async function getVacationPeriods(): Promise<VacationPeriod[]> {
+  return safeInvoke<VacationPeriod[]>('get_vacation_periods', undefined, () => Promise.resolve([]))
+}
+
+export async function getVacationStatus(): Promise<VacationStatus> {
+  return safeInvoke<VacationStatus>('get_vacation_status', undefined, () => ({
+    on_vacation: false,
+    current: null,
+    next: null,
+  }))
+}
+
+// Project suggestions
+export interface ProjectSuggestion {
+  project_id: string
+  project_name: string
+  score: number
+  confidence: number
+}
+
+export async function suggestProjectForTask(
+  title: string,
+  description?: string
+): Promise<ProjectSuggestion[]> {
+  return safeInvoke<ProjectSuggestion[]>(
+    'suggest_project_for_task',
+    { title, description: description || null },
+    () => Promise.resolve([])
+  )
+}
+
+export interface TagSuggestion {
+  tag_id: string
+  name: string
+  color?: string | null
+  score: number
+  reason: string
+}
+
+// Ranked tag chips for quick-add, from tag names in the text and tags used on similar tasks
+export async function suggestTagsForTask(
+  title: string,
+  description?: string,
+  limit?: number
+): Promise<TagSuggestion[]> {
+  return safeInvoke<TagSuggestion[]>(
+    'suggest_tags_for_task',
+    { title, description: description || null, limit: limit ?? null },
+    () => Promise.resolve([])
+  )
+}
+
+// Task links (todoapp://task/<id>)
+export async function getTaskLink(id: string): Promise<string> {
+  return safeInvoke<string>('get_task_link', { id }, () => Promise.resolve(`todoapp://task/${id}`))
+}
+
+export async function openTaskLink(url: string): Promise<void> {
+  return safeInvoke<void>('open_task_link', { url }, () => Promise.resolve())
+}
+
+/**
+ * Link the app was launched with, if any. Returns the task id to open once.
+ */
+export async function takeLaunchLink(): Promise<string | null> {
+  return safeInvoke<string | null>('take_launch_link', undefined, () => Promise.resolve(null))
+}
+
+// Recent task feeds
+export type RecentTasksKind = 'completed' | 'modified' | 'created'
+
+export interface RecentTask extends Task {
+  timestamp: number
+  seconds_ago: number
+}
+
+export async function getRecentTasks(kind: RecentTasksKind, limit = 10): Promise<RecentTask[]> {
+  return safeInvoke<RecentTask[]>('get_recent_tasks', { kind, limit }, () => Promise.resolve([]))
+}
+
+/**
+ * Copy an attachment into targetPath (a folder) under its original name. Returns the written path.
+ */
+export async function saveAttachmentCopy(id: string, targetPath: string): Promise<string> {
+  return safeInvoke<string>('save_attachment_copy', { id, targetPath }, () => {
+    throw new Error('Tauri not available')
+  })
+}
+
+// Week boundaries (setting `week_starts_on`: 0 = Sunday ... 6 = Saturday, default Monday)
+export interface WeekBounds {
+  week_starts_on: number
+  week_start: number
+  week_end: number
+}
+
+export async function getWeekBounds(timestamp?: number): Promise<WeekBounds> {
+  return safeInvoke<WeekBounds>('get_week_bounds', { timestamp: timestamp ?? null }, () => {
+    const date = timestamp ? new Date(timestamp * 1000) : new Date()
+    date.setHours(0, 0, 0, 0)
+    date.setDate(date.getDate() - ((date.getDay() + 6) % 7))
+    const start = Math.floor(date.getTime() / 1000)
+    date.setDate(date.getDate() + 7)
+    return { week_starts_on: 1, week_start: start, week_end: Math.floor(date.getTime() / 1000) }
+  })
+}
+
+// Day agenda for the "Today" screen
+export interface Agenda {
+  date: string
+  day_start: number
+  day_end: number
+  timeline: Task[]
+  unscheduled: Task[]
+  overdue: Task[]
+  completed: Task[]
+  focus_minutes: number
+  focus_sessions: number
+}
+
+/**
+ * Everything shown for one day (date as YYYY-MM-DD) in a single call, using the browser's time zone.
+ */
+export async function getAgenda(date: string): Promise<Agenda> {
+  const tzOffsetMinutes = -new Date(`${date}T12:00:00`).getTimezoneOffset()
+  return safeInvoke<Agenda>('get_agenda', { date, tzOffsetMinutes }, () => {
+    throw new Error('Tauri not available')
+  })
+}
+
+// Reminder sound and urgency
+export async function getTaskReminderStyle(taskId: string): Promise<ReminderStyle> {
+  return safeInvoke<ReminderStyle>('get_task_reminder_style', { taskId }, () =>
+    Promise.resolve({ sound: null, urgency: 'normal' })
+  )
+}
+
+export async function getAvailableSounds(): Promise<NotificationSound[]> {
+  return safeInvoke<NotificationSound[]>('get_available_sounds', undefined, () => Promise.resolve([]))
+}
+
+// Automatic rules
+export type RuleField = 'title' | 'description' | 'project' | 'priority'
+
+export interface RuleCondition {
+  field: RuleField
+  // 'contains' for title/description, 'equals' for project/priority
+  operator: 'contains' | 'equals'
+  value: string
+}
+
+export interface RuleActions {
+  add_tags?: string[]
+  set_project_id?: string | null
+  set_priority?: string | null
+  set_reminder_minutes?: number | null
+}
+
+export interface Rule {
+  id: string
+  name: string
+  enabled: boolean
+  condition: RuleCondition
+  actions: RuleActions
+  order_index: number
+  created_at: number
+  updated_at: number
+}
+
+export interface RuleInput {
+  name: string
+  enabled?: boolean
+  condition: RuleCondition
+  actions: RuleActions
+}
+
+export interface RuleTestResult {
+  matches: boolean
+  changes: string[]
+}
+
+export interface RuleBackfillFilter {
+  rule_id?: string
+  project_id?: string
+  include_completed?: boolean
+}
+
+export interface RuleBackfillResult {
+  tasks_checked: number
+  tasks_changed: number
+  applications: number
+}
+
+export async function getRules(): Promise<Rule[]> {
+  return safeInvoke<Rule[]>('get_rules', undefined, () => Promise.resolve([]))
+}
+
+export async function createRule(input: RuleInput): Promise<Rule> {
+  return safeInvoke<Rule>('create_rule', { input }, () => {
+    throw new Error('Tauri not available - cannot create rule in browser mode')
+  })
+}
+
+export async function updateRule(id: string, input: RuleInput): Promise<Rule> {
+  return safeInvoke<Rule>('update_rule', { id, input }, () => {
+    throw new Error('Tauri not available - cannot update rule in browser mode')
+  })
+}
+
+export async function deleteRule(id: string): Promise<void> {
+  return safeInvoke<void>('delete_rule', { id }, () => {
+    throw new Error('Tauri not available - cannot delete rule in browser mode')
+  })
+}
+
+export async function testRule(rule: RuleInput, sampleTaskId: string): Promise<RuleTestResult> {
+  return safeInvoke<RuleTestResult>('test_rule', { rule, sampleTaskId }, () =>
+    Promise.resolve({ matches: false, changes: [] })
+  )
+}
+
+export async function runRulesOnExisting(filter?: RuleBackfillFilter): Promise<RuleBackfillResult> {
+  return safeInvoke<RuleBackfillResult>('run_rules_on_existing', { filter: filter ?? null }, () => {
+    throw new Error('Tauri not available - cannot run rules in browser mode')
+  })
+}
+
+// Storage footprint
+export interface DirUsage {
+  path: string
+  bytes: number
+  file_count: number
+}
+
+export interface TaskAttachmentUsage {
+  task_id: string
+  title: string | null
+  bytes: number
+  file_count: number
+}
+
+export interface CleanupSuggestion {
+  id: 'prune_backups' | 'clear_thumbnails' | 'orphan_cleanup' | 'delete_exports'
+  description: string
+  reclaimable_bytes: number
+  command: string | null
+  path: string | null
+}
+
+export interface StorageBreakdown {
+  total_bytes: number
+  database: { db_bytes: number; wal_bytes: number; shm_bytes: number }
+  attachments: DirUsage & { top_tasks: TaskAttachmentUsage[]; orphaned_bytes: number }
+  thumbnails: DirUsage
+  backups: DirUsage
+  exports: DirUsage
+  logs: DirUsage | null
+  // Sizes are lower bounds when the walk hit its limit
+  truncated: boolean
+  suggestions: CleanupSuggestion[]
+}
+
+export async function getStorageBreakdown(): Promise<StorageBreakdown> {
+  return safeInvoke<StorageBreakdown>('get_storage_breakdown', undefined, () => {
+    throw new Error('Tauri not available - storage breakdown needs the desktop app')
+  })
+}
+
+// Reminders that were hours overdue when the app got to them (e.g. after sleep) and were
+// summarised in one "missed while away" notification. The cut-off is the stale_reminder_hours setting.
+export interface MissedReminder {
+  id: string
+  task_id: string
+  title: string
+  scheduled_at: number
+  missed_at: number
+}
+
+export async function getMissedReminders(): Promise<MissedReminder[]> {
+  return safeInvoke<MissedReminder[]>('get_missed_reminders', undefined, () => Promise.resolve([]))
+}
+
+// Dismisses all missed reminders when ids is omitted
+export async function dismissMissedReminders(ids?: string[]): Promise<number> {
+  return safeInvoke<number>('dismiss_missed_reminders', { ids: ids ?? null }, () => Promise.resolve(0))
+}
+
+// Launch state gathered in one call; session_start also runs the daily streak check
+export interface SidebarCounts {
+  open: number
+  inbox: number
+  today: number
+  overdue: number
+  upcoming: number
+  completed_today: number
+}
+
+export interface PendingNotification {
+  id: string
+  task_id: string
+  title: string
+  scheduled_at: number
+  snooze_until: number | null
+}
+
+export interface BackupStatus {
+  frequency: string
+  last_backup_at: number | null
+  overdue: boolean
+}
+
+// Also emitted as the 'while-you-were-away' event when there is something to show
+export interface AwaySummary {
+  missed_reminders: number
+  backup: BackupStatus
+}
+
+export interface SessionState {
+  progress: UserProgress
+  badges: Badge[]
+  settings: Record<string, string>
+  sidebar_counts: SidebarCounts
+  pending_notifications: PendingNotification[]
+  away: AwaySummary
+}
+
+export async function sessionStart(): Promise<SessionState> {
+  return safeInvoke<SessionState>('session_start', undefined, () => {
+    throw new Error('Tauri not available - session start needs the desktop app')
+  })
+}
+
+export async function getSidebarCounts(): Promise<SidebarCounts> {
+  return safeInvoke<SidebarCounts>('get_sidebar_counts', undefined, () =>
+    Promise.resolve({ open: 0, inbox: 0, today: 0, overdue: 0, upcoming: 0, completed_today: 0 })
+  )
+}
+
+export async function getPendingNotifications(): Promise<PendingNotification[]> {
+  return safeInvoke<PendingNotification[]>('get_pending_notifications', undefined, () => Promise.resolve([]))
+}
+
+// Per-project view preferences, stored in the database so they survive export/import
+export interface ProjectViewState {
+  view: 'list' | 'board'
+  collapsed_columns?: ('todo' | 'in_progress' | 'done')[]
+  sort?: { field: 'created' | 'dueDate' | 'priority' | 'title' | 'project'; direction: 'asc' | 'desc' } | null
+}
+
+export async function getProjectViewState(id: string): Promise<ProjectViewState | null> {
+  return safeInvoke<ProjectViewState | null>('get_project_view_state', { id }, () => Promise.resolve(null))
+}
+
+// Pass null to reset the project to the default view
+export async function setProjectViewState(id: string, state: ProjectViewState | null): Promise<void> {
+  return safeInvoke<void>('set_project_view_state', { id, state }, () => Promise.resolve())
+}
+
+// Work-in-progress limits per board column, globally or for one project or tag
+export type BoardStatus = 'todo' | 'in_progress' | 'done'
+
+export interface WipLimit {
+  id: string
+  scope_kind: 'global' | 'project' | 'tag'
+  // Project or tag id; null for global
+  scope_id: string | null
+  status: BoardStatus
+  limit: number
+}
+
+export interface WipLimitUsage extends WipLimit {
+  count: number
+  tasks: { id: string; title: string }[]
+}
+
+export interface BoardColumn {
+  status: BoardStatus
+  tasks: Task[]
+  // Limits on this column that apply to the board; full when count >= limit
+  limits: WipLimitUsage[]
+}
+
+export interface Board {
+  columns: BoardColumn[]
+}
+
+// setTaskStatus and updateTask reject with this (as JSON) when the column is at a WIP limit;
+// `exceeded` lists each full limit with the tasks filling it
+export const WIP_LIMIT_EXCEEDED = 'wip_limit'
+
+export interface WipLimitExceeded {
+  kind: typeof WIP_LIMIT_EXCEEDED
+  message: string
+  status: BoardStatus
+  exceeded: WipLimitUsage[]
+}
+
+export function parseWipLimitExceeded(err: unknown): WipLimitExceeded | null {
+  const text = err instanceof Error ? err.message : String(err)
+  try {
+    const parsed = JSON.parse(text)
+    return parsed && parsed.kind === WIP_LIMIT_EXCEEDED ? (parsed as WipLimitExceeded) : null
+  } catch {
+    return null
+  }
+}
+
+// Moving into 'done' completes the task (rejecting with COMPLETION_NOTE_REQUIRED if it needs a
+// note) and moving out of it reopens the task
+export async function setTaskStatus(id: string, status: BoardStatus, force?: boolean): Promise<Task> {
+  return safeInvoke<Task>('set_task_status', { id, status, force: force ?? null }, () => {
+    throw new Error('Tauri not available - cannot update task in browser mode')
+  })
+}
+
+export async function getBoard(projectId?: string, tagId?: string): Promise<Board> {
+  return safeInvoke<Board>('get_board', { projectId: projectId ?? null, tagId: tagId ?? null }, () =>
+    Promise.resolve({ columns: [] })
+  )
+}
+
+export async function getWipLimits(): Promise<WipLimit[]> {
+  return safeInvoke<WipLimit[]>('get_wip_limits', undefined, () => Promise.resolve([]))
+}
+
+export async function setWipLimit(
+  scopeKind: WipLimit['scope_kind'],
+  scopeId: string | null,
+  status: BoardStatus,
+  limit: number
+): Promise<WipLimit> {
+  return safeInvoke<WipLimit>('set_wip_limit', { scopeKind, scopeId, status, limit }, () => {
+    throw new Error('Tauri not available - cannot save WIP limits in browser mode')
+  })
+}
+
+export async function deleteWipLimit(id: string): Promise<void> {
+  return safeInvoke<void>('delete_wip_limit', { id }, () => {
+    throw new Error('Tauri not available - cannot delete WIP limits in browser mode')
+  })
+}
+
+// Bulk attachment import; progress arrives as 'attachment-import-progress' events
+export interface FolderAttachmentRejected {
+  source_path: string
+  reason: string
+}
+
+export interface FolderAttachmentResult {
+  added: { id: string; source_path: string; filename: string; size: number }[]
+  // Not allowed: file type, size or storage limit
+  skipped: FolderAttachmentRejected[]
+  // Allowed but could not be read or copied
+  failed: FolderAttachmentRejected[]
+}
+
+export interface FolderAttachmentProgress {
+  processed: number
+  total: number
+  current: string
+}
+
+export async function addAttachmentsFromFolder(
+  taskId: string,
+  folderPath: string,
+  recursive = false
+): Promise<FolderAttachmentResult> {
+  return safeInvoke<FolderAttachmentResult>('add_attachments_from_folder', { taskId, folderPath, recursive }, () => {
+    throw new Error('Tauri not available - cannot import attachments in browser mode')
+  })
+}
+
+// Retention for append-only tables, configured with the retention_days_<table> settings (0 = keep forever).
+// Old xp_history rows are folded into monthly_rollup rows unless xp_history_retention_mode is 'delete'.
+export interface RetentionStatus {
+  table: string
+  retention_days: number
+  row_count: number
+  oldest_at: number | null
+  last_pruned_at: number | null
+  last_removed: number
+}
+
+export async function getRetentionStatus(): Promise<RetentionStatus[]> {
+  return safeInvoke<RetentionStatus[]>('get_retention_status', undefined, () => Promise.resolve([]))
+}
+
+// Folder sync between devices. Enabled by setting sync_folder to a folder shared by a sync tool;
+// a background job then runs every 5 minutes and emits 'sync-applied' when changes were merged.
+export interface SyncResult {
+  device_id: string
+  exported: number
+  files_ingested: number
+  applied: ImportSummary
+  deleted: number
+  conflicts: number
+}
+
+export interface SyncConflict {
+  id: string
+  entity: 'task' | 'project'
+  entity_id: string
+  device_id: string
+  reason: string
+  // null when the record was deleted on that side
+  local: Record<string, unknown> | null
+  remote: Record<string, unknown> | null
+  detected_at: number
+}
+
+export async function syncNow(): Promise<SyncResult | null> {
+  return safeInvoke<SyncResult | null>('sync_now', undefined, () => Promise.resolve(null))
+}
+
+export async function getSyncConflicts(): Promise<SyncConflict[]> {
+  return safeInvoke<SyncConflict[]>('get_sync_conflicts', undefined, () => Promise.resolve([]))
+}
+
+export async function resolveSyncConflict(id: string, keep: 'local' | 'remote'): Promise<void> {
+  return safeInvoke<void>('resolve_sync_conflict', { id, keep }, () => {
+    throw new Error('Tauri not available - cannot resolve sync conflicts in browser mode')
+  })
+}
+
+// ICS feed URLs live in the calendar_feed_urls setting, one per line. Tasks whose event was
+// removed upstream are completed, or deleted when calendar_removed_events is 'delete'.
+export interface CalendarFeedSyncResult {
+  url: string
+  created: number
+  updated: number
+  removed: number
+  // Set when the feed couldn't be fetched or read; its tasks are left as they were
+  error: string | null
+}
+
+export interface CalendarSyncReport {
+  feeds: CalendarFeedSyncResult[]
+}
+
+// Also runs every 30 minutes in the background, emitting 'calendar-synced' when tasks change
+export async function syncCalendarFeeds(): Promise<CalendarSyncReport> {
+  return safeInvoke<CalendarSyncReport>('sync_calendar_feeds', undefined, () => Promise.resolve({ feeds: [] }))
+}
+
+// Projected finish date range for a project header. Dates are null (with a reason) when there
+// isn't enough recent history; pessimistic_date is also null when a recent week had no completions.
+export interface ProjectForecast {
+  project_id: string
+  remaining_tasks: number
+  weekly_completions: number[]
+  average_per_week: number | null
+  optimistic_date: number | null
+  expected_date: number | null
+  pessimistic_date: number | null
+  reason: string | null
+}
+
+export async function getProjectForecast(projectId: string): Promise<ProjectForecast> {
+  return safeInvoke<ProjectForecast>('get_project_forecast', { projectId }, () => {
+    throw new Error('Tauri not available - project forecasts need the desktop app')
+  })
+}
+
+// Recently used projects, newest first, offered as one-keystroke targets in the quick-add window
+export async function getRecentProjects(limit?: number): Promise<Project[]> {
+  return safeInvoke<Project[]>('get_recent_projects', { limit: limit ?? null }, () => Promise.resolve([]))
+}
+
+// quick_add rejects with this (as JSON) when an @project token matches no project or several
+export interface ProjectResolutionError {
+  message: string
+  token: string
+  candidates: { id: string; name: string }[]
+}
+
+export function parseProjectResolutionError(err: unknown): ProjectResolutionError | null {
+  const text = err instanceof Error ? err.message : String(err)
+  try {
+    const parsed = JSON.parse(text)
+    return parsed && Array.isArray(parsed.candidates) ? (parsed as ProjectResolutionError) : null
+  } catch {
+    return null
+  }
+}
+
+// updateTask, updateProject and updateTemplate reject with this (as JSON) when expected_updated_at
+// is stale; `current` is the saved record so the editor can reload or merge
+export const EDIT_CONFLICT = 'conflict'
+
+export interface EditConflict<T> {
+  kind: typeof EDIT_CONFLICT
+  message: string
+  current: T
+}
+
+export function parseEditConflict<T>(err: unknown): EditConflict<T> | null {
+  const text = err instanceof Error ? err.message : String(err)
+  try {
+    const parsed = JSON.parse(text)
+    return parsed && parsed.kind === EDIT_CONFLICT ? (parsed as EditConflict<T>) : null
+  } catch {
+    return null
+  }
+}
+
+// Ctrl+K command palette: ranked fuzzy matches across open tasks, projects, tags and templates
+export interface PaletteEntry {
+  kind: 'task' | 'project' | 'tag' | 'template'
+  id: string
+  title: string
+  subtitle: string | null
+  score: number
+}
+
+export async function paletteSearch(query: string, limit?: number): Promise<PaletteEntry[]> {
+  return safeInvoke<PaletteEntry[]>('palette_search', { query, limit: limit ?? null }, () => Promise.resolve([]))
+}
+
+// Demo/screenshot mode: reads return placeholder text and changes are rejected until it is
+// turned off or the app restarts. Listen for `demo-mode-changed` to refetch.
+export async function setDemoMode(enabled: boolean): Promise<boolean> {
+  return safeInvoke<boolean>('set_demo_mode', { enabled }, () => Promise.resolve(false))
+}
+
+export async function getDemoMode(): Promise<boolean> {
+  return safeInvoke<boolean>('get_demo_mode', undefined, () => Promise.resolve(false))
+}
+
+// translate_task_content and get_translation reject with this until the user allows sending
+// task text to the translation services
+export const TRANSLATION_CONSENT_REQUIRED = 'translation_consent_required'
+// Returned while a translation provider keeps failing and its circuit breaker is open
+export const TRANSLATION_UNAVAILABLE = 'translation_temporarily_unavailable'
+
+export async function setTranslationConsent(consent: boolean): Promise<void> {
+  return safeInvoke<void>('set_translation_consent', { consent }, () => Promise.resolve())
+}
+
+export interface ExternalService {
+  id: string
+  name: string
+  endpoint: string
+  enabled: boolean
+  reason: string | null
+  breaker: {
+    state: 'closed' | 'open' | 'half_open'
+    consecutive_failures: number
+    open_until: number | null
+  }
+}
+
+export async function getExternalServicesStatus(): Promise<ExternalService[]> {
+  return safeInvoke<ExternalService[]>('get_external_services_status', undefined, () => Promise.resolve([]))
+}
+
+// 0-100 productivity score over a rolling window. Weights come from the
+// `productivity_score_config` setting; components without data have a null value and no points.
+export interface ScoreComponent {
+  name: 'on_time' | 'throughput' | 'focus' | 'streak'
+  value: number | null
+  weight: number
+  points: number
+}
+
+export interface ProductivityScore {
+  window_days: number
+  score: number
+  components: ScoreComponent[]
+}
+
+export interface ProductivityScorePoint {
+  date: string
+  score: number
+}
+
+export async function getProductivityScore(windowDays?: number): Promise<ProductivityScore> {
+  return safeInvoke<ProductivityScore>('get_productivity_score', { windowDays: windowDays ?? null }, () => ({
+    window_days: windowDays ?? 7,
+    score: 0,
+    components: [],
+  }))
+}
+
+export async function getProductivityScoreHistory(
+  windowDays?: number,
+  days?: number
+): Promise<ProductivityScorePoint[]> {
+  return safeInvoke<ProductivityScorePoint[]>(
+    'get_productivity_score_history',
+    { windowDays: windowDays ?? null, days: days ?? null },
+    () => Promise.resolve([])
+  )
+}
+
+export interface CompletionTimeProfile {
+  // Completions per local hour, index 0 = midnight
+  hours: number[]
+  total_completions: number
+  // Null until there are enough completions to learn from
+  modal_hour: number | null
+  days: number
+}
+
+export interface LearnedReminderHour {
+  // The smart_reminder_time setting
+  enabled: boolean
+  hour: number | null
+  learned_at: number | null
+}
+
+export async function getCompletionTimeProfile(): Promise<CompletionTimeProfile> {
+  return safeInvoke<CompletionTimeProfile>('get_completion_time_profile', {}, () =>
+    Promise.resolve({ hours: new Array(24).fill(0), total_completions: 0, modal_hour: null, days: 90 })
+  )
+}
+
+export async function getLearnedReminderHour(): Promise<LearnedReminderHour> {
+  return safeInvoke<LearnedReminderHour>('get_learned_reminder_hour', {}, () =>
+    Promise.resolve({ enabled: false, hour: null, learned_at: null })
+  )
+}
+
+// Fold a project's completed tasks into one "Archive summary" task (attachments move onto it)
+export interface ArchiveSummaryResult {
+  summary_task: Task
+  summarized_count: number
+  attachments_moved: number
+}
+
+export async function summarizeAndArchiveProject(projectId: string): Promise<ArchiveSummaryResult> {
+  const result = await safeInvoke<ArchiveSummaryResult>('summarize_and_archive_project', { projectId }, () => {
+    throw new Error('Tauri not available - cannot summarize projects in browser mode')
+  })
+  return { ...result, summary_task: taskFromRust(result.summary_task) as Task }
+}
+
+// Restore the tasks behind an archive summary; resolves to how many came back
+export async function undoArchiveSummary(summaryTaskId: string): Promise<number> {
+  return safeInvoke<number>('undo_archive_summary', { summaryTaskId }, () => {
+    throw new Error('Tauri not available - cannot undo archive summaries in browser mode')
+  })
+}
+
+// Reusable checklists, appended to a task as subtasks
+export interface ChecklistTemplate {
+  id: string
+  name: string
+  items: string[]
+  created_at: number
+  updated_at: number
+}
+
+export async function getChecklists(): Promise<ChecklistTemplate[]> {
+  return safeInvoke<ChecklistTemplate[]>('get_checklists', undefined, () => Promise.resolve([]))
+}
+
+export async function createChecklist(name: string, items: string[]): Promise<ChecklistTemplate> {
+  return safeInvoke<ChecklistTemplate>('create_checklist', { input: { name, items } }, () => {
+    throw new Error('Tauri not available - cannot create checklist in browser mode')
+  })
+}
+
+export async function updateChecklist(
+  id: string,
+  input: { name?: string; items?: string[] }
+): Promise<ChecklistTemplate> {
+  return safeInvoke<ChecklistTemplate>('update_checklist', { id, input }, () => {
+    throw new Error('Tauri not available - cannot update checklist in browser mode')
+  })
+}
+
+export async function deleteChecklist(id: string): Promise<void> {
+  return safeInvoke<void>('delete_checklist', { id }, () => {
+    throw new Error('Tauri not available - cannot delete checklist in browser mode')
+  })
+}
+
+// Resolves to the newly added subtasks
+export async function applyChecklistToTask(taskId: string, checklistId: string): Promise<Subtask[]> {
+  return safeInvoke<Subtask[]>('apply_checklist_to_task', { taskId, checklistId }, () => {
+    throw new Error('Tauri not available - cannot apply checklist in browser mode')
+  })
+}
+
+export async function createChecklistFromTask(taskId: string, name: string): Promise<ChecklistTemplate> {
+  return safeInvoke<ChecklistTemplate>('create_checklist_from_task', { taskId, name }, () => {
+    throw new Error('Tauri not available - cannot create checklist in browser mode')
+  })
+}
+
+// Tasks completed on one day (YYYY-MM-DD), for pasting into a journal
+export interface JournalEntry {
+  task_id: string
+  title: string
+  project_name?: string
+  tags: string[]
+  completed_at: number
+  pomodoro_minutes: number
+}
+
+export interface DailyJournal {
+  date: string
+  day_start: number
+  day_end: number
+  entries: JournalEntry[]
+  pomodoro_minutes: number
+}
+
+export async function getDailyJournal(date: string): Promise<DailyJournal | null> {
+  const tzOffsetMinutes = -new Date(`${date}T12:00:00`).getTimezoneOffset()
+  return safeInvoke<DailyJournal | null>(
+    'get_daily_journal',
+    { date, tzOffsetMinutes },
+    () => Promise.resolve(null)
+  )
+}
+
+export async function exportDailyJournalMarkdown(date: string): Promise<string> {
+  const tzOffsetMinutes = -new Date(`${date}T12:00:00`).getTimezoneOffset()
+  return safeInvoke<string>(
+    'export_daily_journal_markdown',
+    { date, tzOffsetMinutes },
+    () => Promise.resolve('')
+  )
+}
+
+export async function getTaskThumbnails(taskIds: string[]): Promise<Record<string, string | null>> {
+  return safeInvoke<Record<string, string | null>>(
+    'get_task_thumbnails',
+    { taskIds },
+    () => Promise.resolve({})
+  )
+}
+
+export type RolloverStrategy = 'next_monday' | 'same_weekday' | 'spread'
+
+export interface RolloverMove {
+  task_id: string
+  title: string
+  old_due_date: number
+  new_due_date: number
+}
+
+export interface RolloverResult {
+  from_week_start: number
+  to_week_start: number
+  dry_run: boolean
+  moves: RolloverMove[]
+}
+
+export async function rolloverWeek(
+  fromWeekStart: number,
+  strategy: RolloverStrategy,
+  dryRun = false
+): Promise<RolloverResult> {
+  return safeInvoke<RolloverResult>(
+    'rollover_week',
+    { fromWeekStart, strategy, dryRun },
+    () => {
+      throw new Error('Tauri not available - cannot roll over tasks in browser mode')
+    }
+  )
+}
@@ -5,7 +5,7 @@ interface TaskStore {
   addTask: (task: Omit<Task, 'id' | 'createdAt' | 'updatedAt'>) => Promise<void>
   updateTask: (id: string, updates: Partial<Omit<Task, 'id' | 'createdAt'>>) => Promise<void>
   deleteTask: (id: string) => Promise<void>
-  toggleComplete: (id: string) => Promise<void>
+  toggleComplete: (id: string, acknowledge?: boolean) => Promise<void>
   getTaskById: (id: string) => Task | undefined
 }
 
@@ -145,7 +145,8 @@ export class ToggleTaskCommand implements Command {
   }
 
   async undo(): Promise<void> {
-    await this.store.toggleComplete(this.taskId)
+    // Undo restores the previous state; never ask for confirmation
+    await this.store.toggleComplete(this.taskId, true)
   }
 
   getDescription(): string {
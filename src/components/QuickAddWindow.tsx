@@ -0,0 +1,142 @@
+import { useEffect, useRef, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import {
+  quickAdd,
+  closeQuickAddWindow,
+  getRecentProjects,
+  getStatusLine,
+  parseProjectResolutionError,
+  type Project,
+  type ProjectResolutionError,
+  type StatusLine,
+} from '../api/tauriAdapter'
+import { useTheme } from '../utils/useTheme'
+
+/**
+ * Minimal add form rendered in the separate always-on-top quick-add window
+ * (opened with Ctrl+Shift+A). Submitting creates the task and closes the window.
+ * Alt+1..9 adds the task straight into one of the recently used projects.
+ * A status bar at the bottom shows the streak and today's progress, like the tray tooltip.
+ */
+export function QuickAddWindow() {
+  const { t } = useTranslation()
+  // Follows the app's theme setting, which the window otherwise wouldn't see
+  useTheme()
+  const [text, setText] = useState('')
+  const [error, setError] = useState<string | null>(null)
+  const [ambiguous, setAmbiguous] = useState<ProjectResolutionError | null>(null)
+  const [recentProjects, setRecentProjects] = useState<Project[]>([])
+  const [submitting, setSubmitting] = useState(false)
+  const [status, setStatus] = useState<StatusLine | null>(null)
+  const inputRef = useRef<HTMLInputElement>(null)
+
+  useEffect(() => {
+    inputRef.current?.focus()
+    getRecentProjects(9)
+      .then(setRecentProjects)
+      .catch((err) => console.error('Failed to load recent projects:', err))
+    getStatusLine()
+      .then(setStatus)
+      .catch((err) => console.error('Failed to load status line:', err))
+  }, [])
+
+  const submit = async (projectId?: string) => {
+    if (!text.trim() || submitting) return
+
+    setSubmitting(true)
+    setError(null)
+    setAmbiguous(null)
+    try {
+      await quickAdd(text, projectId)
+      setText('')
+      await closeQuickAddWindow()
+    } catch (err) {
+      const resolution = parseProjectResolutionError(err)
+      if (resolution) {
+        setAmbiguous(resolution.candidates.length > 0 ? resolution : null)
+        setError(resolution.message)
+      } else {
+        setError(err instanceof Error ? err.message : String(err))
+      }
+    } finally {
+      setSubmitting(false)
+    }
+  }
+
+  const handleSubmit = (e: React.FormEvent) => {
+    e.preventDefault()
+    submit()
+  }
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === 'Escape') {
+      closeQuickAddWindow().catch((err) => console.error('Failed to close quick-add window:', err))
+      return
+    }
+    if (e.altKey && /^[1-9]$/.test(e.key)) {
+      const project = recentProjects[Number(e.key) - 1]
+      if (project) {
+        e.preventDefault()
+        submit(project.id)
+      }
+    }
+  }
+
+  return (
+    <form onSubmit={handleSubmit} className="flex h-screen flex-col justify-center gap-2 bg-background p-4">
+      <input
+        ref={inputRef}
+        value={text}
+        onChange={(e) => setText(e.target.value)}
+        onKeyDown={handleKeyDown}
+        placeholder={t('quickAdd.placeholder')}
+        disabled={submitting}
+        className="focus-ring w-full rounded-lg border border-border bg-background px-3 py-2 text-sm text-foreground"
+        aria-label={t('quickAdd.placeholder')}
+      />
+      <p className="text-xs text-muted-foreground">{error ?? t('quickAdd.hint')}</p>
+      {ambiguous ? (
+        <div className="flex flex-wrap items-center gap-1 text-xs">
+          <span className="text-muted-foreground">{t('quickAdd.pickProject')}</span>
+          {ambiguous.candidates.map((candidate) => (
+            <button
+              key={candidate.id}
+              type="button"
+              onClick={() => submit(candidate.id)}
+              className="focus-ring rounded border border-border px-2 py-0.5 text-foreground"
+            >
+              {candidate.name}
+            </button>
+          ))}
+        </div>
+      ) : (
+        recentProjects.length > 0 && (
+          <div className="flex flex-wrap items-center gap-1 text-xs">
+            <span className="text-muted-foreground">{t('quickAdd.recentProjects')}</span>
+            {recentProjects.map((project, index) => (
+              <button
+                key={project.id}
+                type="button"
+                onClick={() => submit(project.id)}
+                className="focus-ring rounded border border-border px-2 py-0.5 text-foreground"
+              >
+                {index + 1}. {project.name}
+              </button>
+            ))}
+          </div>
+        )
+      )}
+      {status && (
+        <p className="text-xs text-muted-foreground">
+          {t('quickAdd.status', {
+            streak: status.current_streak,
+            done: status.completed_today,
+            goal: status.daily_goal,
+            focus: status.focus_minutes_today,
+            level: status.level,
+          })}
+        </p>
+      )}
+    </form>
+  )
+}
@@ -556,16 +556,27 @@ export function TaskDetailsModal({ task, open, onOpenChange }: TaskDetailsModalP
                                 onClick={async () => {
                                   if (!currentTask) return
                                   setTranslating(true)
+                                  const translate = () =>
+                                    tauriAdapter.translateTaskContent(currentTask.id, targetLang)
                                   try {
-                                    const translated = await tauriAdapter.translateTaskContent(
-                                      currentTask.id,
-                                      targetLang
-                                    )
+                                    let translated
+                                    try {
+                                      translated = await translate()
+                                    } catch (error: any) {
+                                      const message = error?.message ?? String(error)
+                                      if (message !== tauriAdapter.TRANSLATION_CONSENT_REQUIRED) throw error
+                                      if (!window.confirm(t('translation.consentPrompt'))) return
+                                      await tauriAdapter.setTranslationConsent(true)
+                                      translated = await translate()
+                                    }
                                     setTranslation(translated)
                                   } catch (error: any) {
                                     toast({
                                       title: t('translation.error'),
-                                      description: error?.message || t('translation.apiKeyMissing'),
+                                      description:
+                                        error?.message === tauriAdapter.TRANSLATION_UNAVAILABLE
+                                          ? t('translation.unavailable')
+                                          : error?.message || t('translation.apiKeyMissing'),
                                       variant: 'destructive',
                                     })
                                   } finally {
@@ -1,12 +1,16 @@
 import React from 'react'
 import ReactDOM from 'react-dom/client'
 import App from './App.tsx'
+import { QuickAddWindow } from './components/QuickAddWindow'
 import './index.css'
 import './i18n'
 
+// The quick-add window loads the same bundle with ?window=quick-add and only renders the add form
+const isQuickAddWindow = new URLSearchParams(window.location.search).get('window') === 'quick-add'
+
 ReactDOM.createRoot(document.getElementById('root')!).render(
   <React.StrictMode>
-    <App />
+    {isQuickAddWindow ? <QuickAddWindow /> : <App />}
   </React.StrictMode>,
 )
 
@@ -8,6 +8,7 @@ import { useTranslation } from 'react-i18next'
 export function Settings() {
   const { t, i18n } = useTranslation()
   const [notificationsEnabled, setNotificationsEnabled] = useState(false)
+  const [notificationSoundEnabled, setNotificationSoundEnabled] = useState(true)
   const [autostartEnabled, setAutostartEnabled] = useState(false)
   const [statisticsVisible, setStatisticsVisible] = useState(true)
   const [kanbanVisible, setKanbanVisible] = useState(true)
@@ -50,6 +51,7 @@ export function Settings() {
     try {
       const settings = await tauriAdapter.getSettings()
       setNotificationsEnabled(settings.notifications_enabled === 'true')
+      setNotificationSoundEnabled(settings.notification_sound_enabled !== 'false') // Default to true if not set
       setAutostartEnabled(settings.autostart_enabled === 'true')
       setStatisticsVisible(settings.statistics_visible !== 'false') // Default to true if not set
       setKanbanVisible(settings.kanban_visible !== 'false') // Default to true if not set
@@ -86,6 +88,16 @@ export function Settings() {
     }
   }
 
+  const handleNotificationSoundToggle = async (enabled: boolean) => {
+    try {
+      await tauriAdapter.updateSettings('notification_sound_enabled', enabled.toString())
+      setNotificationSoundEnabled(enabled)
+      showMessage('success', 'Notification sound setting saved')
+    } catch (error) {
+      showMessage('error', 'Failed to save notification sound setting')
+    }
+  }
+
   const handleAutostartToggle = async (enabled: boolean) => {
     if (!isTauri()) {
       showMessage('error', 'Auto-start is only available in Tauri desktop app.')
@@ -308,6 +320,26 @@ export function Settings() {
               </label>
             </div>
 
+            {notificationsEnabled && (
+              <div className="flex items-center justify-between">
+                <div>
+                  <p className="text-sm font-medium text-foreground">Reminder sounds</p>
+                  <p className="mt-1 text-xs text-muted-foreground">
+                    Play a sound with reminders. Low urgency reminders are always silent
+                  </p>
+                </div>
+                <label className="relative inline-flex cursor-pointer items-center">
+                  <input
+                    type="checkbox"
+                    checked={notificationSoundEnabled}
+                    onChange={(e) => handleNotificationSoundToggle(e.target.checked)}
+                    className="peer sr-only"
+                  />
+                  <div className="peer h-6 w-11 rounded-full bg-gray-200 after:absolute after:left-[2px] after:top-[2px] after:h-5 after:w-5 after:rounded-full after:border after:border-gray-300 after:bg-white after:transition-all after:content-[''] peer-checked:bg-primary-500 peer-checked:after:translate-x-full peer-checked:after:border-white peer-focus:outline-none peer-focus:ring-4 peer-focus:ring-primary-300 dark:bg-gray-700 dark:peer-focus:ring-primary-800"></div>
+                </label>
+              </div>
+            )}
+
             {notificationsEnabled && (
               <div className="space-y-3 rounded-lg border border-border bg-muted/30 p-4">
                 <p className="text-sm font-medium text-foreground">Default Notification Preferences</p>
@@ -65,6 +65,13 @@ export function Statistics() {
   const [productivityTrend, setProductivityTrend] = useState<ProductivityTrend[]>([])
   const [mostProductiveDay, setMostProductiveDay] = useState<MostProductiveDay | null>(null)
   const [averageCompletionTime, setAverageCompletionTime] = useState<number>(0)
+  const [weekBounds, setWeekBounds] = useState<tauriAdapter.WeekBounds | null>(null)
+
+  useEffect(() => {
+    tauriAdapter.getWeekBounds().then(setWeekBounds).catch((error) => {
+      console.error('Failed to get week bounds:', error)
+    })
+  }, [])
 
   // Summary stats
   const totalTasks = tasks.length
@@ -87,7 +94,8 @@ export function Statistics() {
 
   const now = Math.floor(Date.now() / 1000)
   const todayStart = Math.floor(new Date().setHours(0, 0, 0, 0) / 1000)
-  const weekStart = todayStart - 7 * 24 * 60 * 60
+  // Calendar week, starting on the configured first day of the week
+  const weekStart = weekBounds?.week_start ?? todayStart - 7 * 24 * 60 * 60
   const monthStart = todayStart - 30 * 24 * 60 * 60
 
   const tasksCompletedToday = tasks.filter(
@@ -37,7 +37,7 @@ interface TasksState {
   syncTasks: () => Promise<void>
   addTask: (task: Omit<Task, 'id' | 'createdAt' | 'updatedAt'>) => Promise<void>
   updateTask: (id: string, updates: Partial<Omit<Task, 'id' | 'createdAt'>>) => Promise<void>
-  toggleComplete: (id: string) => Promise<void>
+  toggleComplete: (id: string, acknowledge?: boolean) => Promise<void>
   deleteTask: (id: string) => Promise<void>
   getTaskById: (id: string) => Task | undefined
   addTagToTask: (taskId: string, tagId: string) => Promise<void>
@@ -176,14 +176,27 @@ export const useTasks = create<TasksState>()(
     }
   },
 
-  toggleComplete: async (id) => {
+  toggleComplete: async (id, acknowledge) => {
     try {
       // Get task before toggle to check completion state
       const currentTask = get().tasks.find((task) => task.id === id)
       const wasCompleted = currentTask?.completed ?? false
       const taskPriority = currentTask?.priority ?? 'medium'
 
-      const rustTask = await tauriAdapter.toggleComplete(id)
+      // Confirm before completing a task that still has open subtasks, blockers or reminders
+      if (!wasCompleted && !acknowledge) {
+        const warnings = await tauriAdapter.getCompletionWarnings(id)
+        if (warnings.length > 0) {
+          const details = warnings
+            .map((w) => `${w.count} ${w.kind.replace(/_/g, ' ')}${w.samples.length ? ` (${w.samples.join(', ')})` : ''}`)
+            .join('\n')
+          if (!window.confirm(`This task still has:\n${details}\n\nComplete it anyway?`)) {
+            return
+          }
+        }
+      }
+
+      const rustTask = await tauriAdapter.toggleComplete(id, true)
       const updatedTask = convertTask(rustTask)
       set((state) => ({
         tasks: state.tasks.map((task) => (task.id === id ? updatedTask : task)),
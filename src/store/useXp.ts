@@ -26,6 +26,7 @@ interface XpState {
   resetLevelUp: () => void
   resetRankUp: () => void
   syncFromBackend: () => Promise<void>
+  hydrateFromSession: (progress: tauriAdapter.UserProgress, badges: tauriAdapter.Badge[]) => void
   loadBadges: () => Promise<void>
   checkBadges: () => Promise<void>
   calculateLevel: (totalXp: number) => number
@@ -64,6 +65,21 @@ function calculateCurrentXp(totalXp: number, level: number): number {
   return totalXp - xpForCurrentLevel
 }
 
+function progressState(progress: tauriAdapter.UserProgress) {
+  const level = calculateLevel(progress.total_xp)
+  const rankInfo = getRankInfo(progress.total_xp)
+  return {
+    level,
+    currentXp: calculateCurrentXp(progress.total_xp, level),
+    totalXp: progress.total_xp,
+    xpToNextLevel: calculateXpToNextLevel(level),
+    streak: progress.current_streak,
+    rankTier: rankInfo.tier,
+    rankDivision: rankInfo.division,
+    rankProgress: rankInfo.progress,
+  }
+}
+
 export const useXp = create<XpState>()((set, get) => ({
   level: 1,
   currentXp: 0,
@@ -85,38 +101,28 @@ export const useXp = create<XpState>()((set, get) => ({
   syncFromBackend: async () => {
     try {
       const progress = await tauriAdapter.getUserProgress()
-      const level = calculateLevel(progress.total_xp)
-      const xpToNextLevel = calculateXpToNextLevel(level)
-      const currentXp = calculateCurrentXp(progress.total_xp, level)
-
-      // Calculate rank
-      const rankInfo = getRankInfo(progress.total_xp)
-
-      set({
-        level,
-        currentXp,
-        totalXp: progress.total_xp,
-        xpToNextLevel,
-        streak: progress.current_streak,
-        rankTier: rankInfo.tier,
-        rankDivision: rankInfo.division,
-        rankProgress: rankInfo.progress,
-      })
+      const state = progressState(progress)
+      set(state)
       
       console.log('XP synced from backend:', { 
-        level, 
-        currentXp, 
+        level: state.level, 
+        currentXp: state.currentXp, 
         totalXp: progress.total_xp, 
-        xpToNextLevel, 
+        xpToNextLevel: state.xpToNextLevel, 
         streak: progress.current_streak,
-        rank: getRankDisplayName(rankInfo.tier, rankInfo.division),
-        rankProgress: rankInfo.progress,
+        rank: getRankDisplayName(state.rankTier, state.rankDivision),
+        rankProgress: state.rankProgress,
       })
     } catch (error) {
       console.error('Failed to sync XP from backend:', error)
     }
   },
 
+  // Apply progress and badges already fetched by sessionStart
+  hydrateFromSession: (progress, badges) => {
+    set({ ...progressState(progress), badges })
+  },
+
   loadBadges: async () => {
     try {
       const badges = await tauriAdapter.getBadges()
@@ -200,17 +200,12 @@ export function useKeyboardShortcuts(options: KeyboardShortcutsOptions = {}) {
           }
         })
 
-        const unlistenToggleTheme = await listen('global-shortcut-toggle-theme', () => {
-          window.dispatchEvent(new CustomEvent('toggle-theme'))
-        })
-
         const unlistenDashboard = await listen('global-shortcut-dashboard', () => {
           navigate('/')
         })
 
         return () => {
           unlistenAddTask()
-          unlistenToggleTheme()
           unlistenDashboard()
         }
       } catch (error) {
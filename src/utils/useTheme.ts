@@ -1,10 +1,13 @@
 import { useEffect, useState } from 'react'
+import { getEffectiveTheme, updateSettings, type ThemeChangedEvent } from '../api/tauriAdapter'
+import { isTauri } from './tauri'
 
 type Theme = 'light' | 'dark'
 
 /**
  * Hook to manage theme state (light/dark mode)
- * Persists preference to localStorage
+ * In Tauri the preference lives in the backend's "theme" setting and every window follows its
+ * theme-changed event (tray toggle, OS switches); localStorage only avoids a flash on startup
  */
 export function useTheme() {
   const [theme, setTheme] = useState<Theme>(() => {
@@ -26,10 +29,28 @@ export function useTheme() {
     localStorage.setItem('theme', theme)
   }, [theme])
 
+  useEffect(() => {
+    if (!isTauri()) return
+
+    getEffectiveTheme()
+      .then(setTheme)
+      .catch((error) => console.error('Failed to load theme:', error))
+
+    const unlisten = import('@tauri-apps/api/event').then(({ listen }) =>
+      listen<ThemeChangedEvent>('theme-changed', (event) => setTheme(event.payload.theme))
+    )
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
   const toggleTheme = () => {
-    setTheme((prev) => (prev === 'light' ? 'dark' : 'light'))
+    const next = theme === 'light' ? 'dark' : 'light'
+    setTheme(next)
+    if (isTauri()) {
+      updateSettings('theme', next).catch((error) => console.error('Failed to save theme:', error))
+    }
   }
 
   return { theme, toggleTheme }
 }
-